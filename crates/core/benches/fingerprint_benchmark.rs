@@ -0,0 +1,186 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use similarity_core::{
+    compute_structure_fingerprint, extract_types_from_code, generate_subtree_fingerprints,
+    parse_and_convert_to_tree, FingerprintBuckets, IndexedFunction, SourceLocation, Structure,
+    StructureIdentifier, StructureKind, StructureMember, StructureMetadata,
+};
+
+/// Source with `count` functions of varied shape, standing in for a large file
+fn generate_source(count: usize) -> String {
+    let mut source = String::new();
+    for i in 0..count {
+        source.push_str(&format!(
+            r#"
+export function process{i}(items: Item[], limit: number): Result[] {{
+    const results = [];
+    for (let index = 0; index < items.length && index < limit; index++) {{
+        const item = items[index];
+        if (item.value > {i}) {{
+            results.push({{ id: item.id, value: item.value * {i}, label: "item-" + item.id }});
+        }} else if (item.fallback) {{
+            results.push(item.fallback);
+        }}
+    }}
+    return results.filter((result) => result.value !== undefined);
+}}
+"#
+        ));
+    }
+    source
+}
+
+/// `files` modules of 40 interfaces and type aliases each, with member lists
+/// of varied size and type, standing in for the models of a large repository
+fn generate_type_files(files: usize) -> Vec<(String, String)> {
+    const TYPES: [&str; 6] = ["string", "number", "boolean", "string[]", "{ id: string }", "Date"];
+    (0..files)
+        .map(|file| {
+            let mut source = String::new();
+            for i in 0..40 {
+                let n = file * 40 + i;
+                let members: String = (0..2 + n % 9)
+                    .map(|m| format!("  field{m}: {};\n", TYPES[(n + m) % TYPES.len()]))
+                    .collect();
+                if n % 4 == 0 {
+                    source.push_str(&format!("export type Model{n} = {{\n{members}}};\n\n"));
+                } else {
+                    source.push_str(&format!("export interface Model{n} {{\n{members}}}\n\n"));
+                }
+            }
+            (format!("src/models/file{file}.ts"), source)
+        })
+        .collect()
+}
+
+/// `count` structures spread over kinds, sizes and member types
+fn generate_structures(count: usize) -> Vec<Structure> {
+    const TYPES: [&str; 6] = ["string", "number", "boolean", "string[]", "{ id: string }", "Date"];
+    (0..count)
+        .map(|i| Structure {
+            identifier: StructureIdentifier {
+                name: format!("Model{i}").into(),
+                kind: if i % 4 == 0 {
+                    StructureKind::TypeScriptTypeAlias
+                } else {
+                    StructureKind::TypeScriptInterface
+                },
+                namespace: Some(format!("src/models/file{}.ts", i % 50).into()),
+            },
+            members: (0..2 + i % 9)
+                .map(|m| StructureMember {
+                    name: format!("field{m}"),
+                    value_type: TYPES[(i + m) % TYPES.len()].into(),
+                    modifiers: vec![],
+                    nested: None,
+                })
+                .collect(),
+            metadata: StructureMetadata {
+                location: SourceLocation {
+                    file_path: format!("src/models/file{}.ts", i % 50).into(),
+                    start_line: i * 10,
+                    end_line: i * 10 + 8,
+                },
+                ..Default::default()
+            },
+        })
+        .collect()
+}
+
+fn benchmark_subtree_fingerprints(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Subtree Fingerprints");
+    let tree = parse_and_convert_to_tree("large.ts", &generate_source(200)).unwrap();
+
+    group.bench_function("fingerprint 200 functions", |b| {
+        b.iter(|| generate_subtree_fingerprints(black_box(&tree), 0, 0));
+    });
+
+    group.bench_function("fingerprint and index 200 functions", |b| {
+        b.iter(|| {
+            let (root, subtrees) = generate_subtree_fingerprints(black_box(&tree), 0, 0);
+            let mut indexed = IndexedFunction::new("all".to_string(), "large.ts".to_string(), root);
+            for subtree in subtrees {
+                indexed.add_subtree(subtree);
+            }
+            indexed
+        });
+    });
+
+    group.finish();
+}
+
+fn benchmark_structure_bucketing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Structure Bucketing");
+    let structures = generate_structures(2000);
+
+    group.bench_function("fingerprint 2000 structures", |b| {
+        b.iter(|| {
+            structures
+                .iter()
+                .map(|structure| compute_structure_fingerprint(black_box(structure)))
+                .collect::<Vec<_>>()
+        });
+    });
+
+    group.bench_function("bucket 2000 structures into candidate pairs", |b| {
+        b.iter_batched(
+            || structures.clone(),
+            |structures| {
+                let mut buckets = FingerprintBuckets::new();
+                for structure in structures {
+                    let fingerprint = compute_structure_fingerprint(&structure);
+                    buckets.insert(structure, fingerprint);
+                }
+                buckets.candidate_pairs()
+            },
+            BatchSize::LargeInput,
+        );
+    });
+
+    group.finish();
+}
+
+/// The whole pipeline a large-repository run goes through: source text to
+/// extracted definitions to fingerprints to buckets and candidate pairs
+fn benchmark_extraction_and_bucketing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Extraction and Bucketing");
+    group.sample_size(20);
+    let type_files = generate_type_files(50);
+    let function_files: Vec<(String, String)> =
+        (0..20).map(|file| (format!("src/lib/file{file}.ts"), generate_source(50))).collect();
+
+    group.bench_function("extract and bucket 2000 types from 50 files", |b| {
+        b.iter(|| {
+            let mut buckets = FingerprintBuckets::new();
+            for (path, source) in black_box(&type_files) {
+                for definition in extract_types_from_code(source, path).unwrap() {
+                    let structure = Structure::from(definition);
+                    let fingerprint = compute_structure_fingerprint(&structure);
+                    buckets.insert(structure, fingerprint);
+                }
+            }
+            buckets.candidate_pairs()
+        });
+    });
+
+    group.bench_function("parse and fingerprint 1000 functions from 20 files", |b| {
+        b.iter(|| {
+            black_box(&function_files)
+                .iter()
+                .map(|(path, source)| {
+                    let tree = parse_and_convert_to_tree(path, source).unwrap();
+                    generate_subtree_fingerprints(&tree, 0, 0).1.len()
+                })
+                .sum::<usize>()
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_subtree_fingerprints,
+    benchmark_structure_bucketing,
+    benchmark_extraction_and_bucketing
+);
+criterion_main!(benches);
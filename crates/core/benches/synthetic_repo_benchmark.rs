@@ -0,0 +1,67 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use similarity_core::{
+    find_similar_functions_across_files, find_similar_functions_across_files_fast,
+    generate_synthetic_repo, FastSimilarityOptions, SyntheticRepoOptions, TSEDOptions,
+};
+use std::time::Duration;
+
+const SIZES: [usize; 2] = [100, 400];
+const CLONE_RATIOS: [f64; 2] = [0.1, 0.3];
+const THRESHOLD: f64 = 0.87;
+
+/// End-to-end analysis (extraction and comparison) of synthetic repos, for each
+/// algorithm configuration; the numbers feed `REFERENCE_TIMINGS`
+fn benchmark_synthetic_repos(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Synthetic Repos");
+    group.sample_size(10).measurement_time(Duration::from_secs(10));
+
+    let tsed = TSEDOptions::default();
+    let tsed_no_penalty = TSEDOptions { size_penalty: false, ..TSEDOptions::default() };
+    let fingerprint = FastSimilarityOptions {
+        similarity_threshold: THRESHOLD,
+        ..FastSimilarityOptions::default()
+    };
+
+    for functions in SIZES {
+        for clone_ratio in CLONE_RATIOS {
+            let repo = generate_synthetic_repo(&SyntheticRepoOptions {
+                functions,
+                clone_ratio,
+                ..SyntheticRepoOptions::default()
+            });
+            let label = format!("{functions} functions, {:.0}% clones", clone_ratio * 100.0);
+            group.throughput(Throughput::Elements(functions as u64));
+
+            group.bench_with_input(BenchmarkId::new("tsed", &label), &repo.files, |b, files| {
+                b.iter(|| find_similar_functions_across_files(black_box(files), THRESHOLD, &tsed))
+            });
+            group.bench_with_input(
+                BenchmarkId::new("tsed-no-penalty", &label),
+                &repo.files,
+                |b, files| {
+                    b.iter(|| {
+                        find_similar_functions_across_files(
+                            black_box(files),
+                            THRESHOLD,
+                            &tsed_no_penalty,
+                        )
+                    })
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new("fingerprint", &label),
+                &repo.files,
+                |b, files| {
+                    b.iter(|| {
+                        find_similar_functions_across_files_fast(black_box(files), &fingerprint)
+                    })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_synthetic_repos);
+criterion_main!(benches);
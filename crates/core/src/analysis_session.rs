@@ -0,0 +1,242 @@
+//! Resumable analysis sessions, checkpointed in a session directory.
+//!
+//! A session records the work of a function analysis as it completes: the
+//! functions extracted from each file, the duplicates found within each file
+//! and the duplicates found in each batch of cross-file candidate pairs. The
+//! checkpoint is rewritten every [`CHECKPOINT_INTERVAL`], so a run that is
+//! interrupted (Ctrl-C, a CI timeout) loses at most that much work, and a
+//! resumed run skips everything already recorded.
+//!
+//! Results are keyed by the unit of work that produced them, so recording a
+//! unit twice replaces its results instead of duplicating them, and the
+//! checkpoint is replaced atomically. A checkpoint only applies to the run it
+//! was recorded for: its run key covers the analyzed files, their sizes and
+//! modification times, and the comparison options.
+
+use crate::function_extractor::{FunctionDefinition, SimilarityResult};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use xxhash_rust::xxh3::Xxh3;
+
+/// Default session directory, relative to the working directory
+pub const DEFAULT_SESSION_DIR: &str = ".similarity/session";
+
+/// Name of the checkpoint file inside the session directory
+pub const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+/// Least time between two checkpoints written while the analysis runs
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Cross-file candidate pairs recorded together as one unit of work
+pub const CROSS_FILE_BATCH_SIZE: usize = 4096;
+
+/// A cross-file duplicate: first file, result, second file
+pub type CrossFileFinding = (String, SimilarityResult, String);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Checkpoint {
+    run_key: u64,
+    /// Functions extracted from each parsed file
+    parsed: BTreeMap<String, Vec<FunctionDefinition>>,
+    /// Duplicates within each compared file
+    within_file: BTreeMap<String, Vec<SimilarityResult>>,
+    /// Duplicates of each compared batch of cross-file pairs, by batch index
+    cross_file: BTreeMap<usize, Vec<CrossFileFinding>>,
+}
+
+/// How a session was opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStart {
+    /// No checkpoint was resumed
+    Fresh,
+    /// The recorded checkpoint was resumed
+    Resumed,
+    /// A checkpoint was found but recorded for other files or options
+    Stale,
+}
+
+/// Work recorded in a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionProgress {
+    pub files_parsed: usize,
+    pub files_compared: usize,
+    pub batches_compared: usize,
+}
+
+/// Checkpointed progress of one analysis run
+#[derive(Debug)]
+pub struct AnalysisSession {
+    dir: PathBuf,
+    checkpoint: Mutex<Checkpoint>,
+    last_saved: Mutex<Instant>,
+}
+
+impl AnalysisSession {
+    /// Open a session in `dir` for the run identified by `run_key`. With
+    /// `resume`, a checkpoint recorded for the same run key is picked up;
+    /// otherwise the session starts empty and replaces any checkpoint on the
+    /// first save.
+    pub fn open(
+        dir: impl Into<PathBuf>,
+        run_key: u64,
+        resume: bool,
+    ) -> Result<(Self, SessionStart)> {
+        let dir = dir.into();
+        let path = dir.join(CHECKPOINT_FILE);
+        let (checkpoint, start) = match fs::read_to_string(&path) {
+            Ok(content) if resume => {
+                let checkpoint = serde_json::from_str::<Checkpoint>(&content)
+                    .with_context(|| format!("Failed to parse {}", path.display()))?;
+                if checkpoint.run_key == run_key {
+                    (checkpoint, SessionStart::Resumed)
+                } else {
+                    (Checkpoint { run_key, ..Checkpoint::default() }, SessionStart::Stale)
+                }
+            }
+            Ok(_) => (Checkpoint { run_key, ..Checkpoint::default() }, SessionStart::Fresh),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                (Checkpoint { run_key, ..Checkpoint::default() }, SessionStart::Fresh)
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let session = Self {
+            dir,
+            checkpoint: Mutex::new(checkpoint),
+            last_saved: Mutex::new(Instant::now()),
+        };
+        Ok((session, start))
+    }
+
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    #[must_use]
+    pub fn progress(&self) -> SessionProgress {
+        let checkpoint = self.checkpoint.lock().unwrap();
+        SessionProgress {
+            files_parsed: checkpoint.parsed.len(),
+            files_compared: checkpoint.within_file.len(),
+            batches_compared: checkpoint.cross_file.len(),
+        }
+    }
+
+    /// Functions recorded for a parsed file
+    #[must_use]
+    pub fn parsed_functions(&self, file: &str) -> Option<Vec<FunctionDefinition>> {
+        self.checkpoint.lock().unwrap().parsed.get(file).cloned()
+    }
+
+    pub fn record_parsed(&self, file: &str, functions: Vec<FunctionDefinition>) {
+        self.checkpoint.lock().unwrap().parsed.insert(file.to_string(), functions);
+    }
+
+    /// Duplicates recorded for a file compared with itself
+    #[must_use]
+    pub fn within_file_results(&self, file: &str) -> Option<Vec<SimilarityResult>> {
+        self.checkpoint.lock().unwrap().within_file.get(file).cloned()
+    }
+
+    pub fn record_within_file(&self, file: &str, results: Vec<SimilarityResult>) {
+        self.checkpoint.lock().unwrap().within_file.insert(file.to_string(), results);
+    }
+
+    /// Duplicates recorded for a batch of cross-file pairs
+    #[must_use]
+    pub fn cross_file_batch(&self, batch: usize) -> Option<Vec<CrossFileFinding>> {
+        self.checkpoint.lock().unwrap().cross_file.get(&batch).cloned()
+    }
+
+    pub fn record_cross_file_batch(&self, batch: usize, results: Vec<CrossFileFinding>) {
+        self.checkpoint.lock().unwrap().cross_file.insert(batch, results);
+    }
+
+    /// Save the checkpoint if [`CHECKPOINT_INTERVAL`] passed since the last save
+    pub fn checkpoint(&self) -> Result<()> {
+        {
+            let mut last_saved = self.last_saved.lock().unwrap();
+            if last_saved.elapsed() < CHECKPOINT_INTERVAL {
+                return Ok(());
+            }
+            *last_saved = Instant::now();
+        }
+        self.save()
+    }
+
+    /// Write the checkpoint, replacing the previous one atomically
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.dir.join(CHECKPOINT_FILE);
+        let partial = path.with_extension("json.tmp");
+        let content = serde_json::to_string(&*self.checkpoint.lock().unwrap())?;
+        fs::write(&partial, content)
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        fs::rename(&partial, &path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Key of a run over `files` with the given comparison `settings`, changing
+/// whenever a file is added, removed or modified
+#[must_use]
+pub fn run_key(files: &[PathBuf], settings: &str) -> u64 {
+    let mut hasher = Xxh3::new();
+    hasher.update(settings.as_bytes());
+    for file in files {
+        hasher.update(file.to_string_lossy().as_bytes());
+        let metadata = fs::metadata(file).ok();
+        let len = metadata.as_ref().map_or(0, fs::Metadata::len);
+        let modified = metadata
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |modified| modified.as_nanos());
+        hasher.update(&len.to_le_bytes());
+        hasher.update(&modified.to_le_bytes());
+    }
+    hasher.digest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function_extractor::extract_functions;
+
+    #[test]
+    fn test_resume_only_matching_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let functions =
+            extract_functions("a.ts", "function add(a: number, b: number) { return a + b; }")
+                .unwrap();
+
+        let (session, start) = AnalysisSession::open(dir.path(), 7, true).unwrap();
+        assert_eq!(start, SessionStart::Fresh);
+        session.record_parsed("a.ts", functions.clone());
+        session.record_within_file("a.ts", vec![]);
+        // Recording a unit again replaces it
+        session.record_within_file("a.ts", vec![]);
+        session.save().unwrap();
+
+        let (resumed, start) = AnalysisSession::open(dir.path(), 7, true).unwrap();
+        assert_eq!(start, SessionStart::Resumed);
+        assert_eq!(
+            resumed.progress(),
+            SessionProgress { files_parsed: 1, files_compared: 1, batches_compared: 0 }
+        );
+        let restored = resumed.parsed_functions("a.ts").unwrap();
+        assert_eq!(restored[0].name, "add");
+        assert_eq!(restored[0].body_span, functions[0].body_span);
+        assert_eq!(restored[0].parameter_types, functions[0].parameter_types);
+
+        let (_, start) = AnalysisSession::open(dir.path(), 8, true).unwrap();
+        assert_eq!(start, SessionStart::Stale);
+        let (fresh, start) = AnalysisSession::open(dir.path(), 7, false).unwrap();
+        assert_eq!(start, SessionStart::Fresh);
+        assert!(fresh.parsed_functions("a.ts").is_none());
+    }
+}
@@ -0,0 +1,560 @@
+//! Plugin interface for custom analyzers.
+//!
+//! An [`Analyzer`] turns source files into comparable [`AnalysisUnit`]s, scores
+//! pairs of units and renders the resulting [`AnalyzerFinding`]s. Third-party
+//! crates implement the trait and register it in an [`AnalyzerRegistry`] to
+//! add detection for new languages or artifacts (SQL queries, config blocks,
+//! ...) without touching the built-in CLIs.
+
+use crate::cli_output::format_function_output;
+use crate::comparison_budget::ComparisonBudget;
+use crate::language_parser::LanguageParser;
+use crate::structure_comparator::{
+    ComparisonOptions, SourceLocation, Structure, StructureComparator, StructureIdentifier,
+    StructureKind, StructureMember, StructureMetadata,
+};
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, TSEDOptions};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Version of the analyzer interface. Bumped on breaking changes to
+/// [`Analyzer`], [`AnalysisUnit`] or [`AnalyzerFinding`].
+pub const ANALYZER_API_VERSION: u32 = 1;
+
+pub type AnalyzerError = Box<dyn Error + Send + Sync>;
+
+/// A comparable piece of code extracted by an analyzer (function, query, rule, ...)
+#[derive(Debug, Clone)]
+pub struct AnalysisUnit {
+    pub name: String,
+    pub file: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub tree: Rc<TreeNode>,
+    /// Member-level view for analyzers that compare structures instead of trees
+    pub structure: Option<Structure>,
+}
+
+impl AnalysisUnit {
+    #[must_use]
+    pub fn location(&self) -> UnitLocation {
+        UnitLocation {
+            name: self.name.clone(),
+            file: self.file.clone(),
+            start_line: self.start_line,
+            end_line: self.end_line,
+        }
+    }
+}
+
+/// Where an analysis unit lives, without its tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnitLocation {
+    pub name: String,
+    pub file: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// A pair of units whose similarity reached the threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalyzerFinding {
+    pub analyzer: String,
+    pub similarity: f64,
+    pub first: UnitLocation,
+    pub second: UnitLocation,
+}
+
+/// Extension point for detecting duplicates in new languages or artifacts
+pub trait Analyzer {
+    /// Unique name used to select the analyzer (e.g. `--analyzer sql`)
+    fn name(&self) -> &str;
+
+    /// File extensions (without the dot) this analyzer handles
+    fn extensions(&self) -> &[&str];
+
+    /// Whether this analyzer handles `path`. Defaults to matching [`Analyzer::extensions`];
+    /// analyzers for files named by convention (e.g. `Dockerfile`) match the file name.
+    fn matches_file(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.extensions().contains(&ext))
+    }
+
+    /// Extract comparable units from one source file
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError>;
+
+    /// Similarity of two units in `[0.0, 1.0]`. Defaults to TSED on their trees.
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        calculate_tsed(&first.tree, &second.tree, &TSEDOptions::default())
+    }
+
+    /// Write findings in human-readable form
+    fn report(&self, findings: &[AnalyzerFinding], out: &mut dyn Write) -> io::Result<()> {
+        if findings.is_empty() {
+            return writeln!(out, "No duplicates found by analyzer '{}'.", self.name());
+        }
+
+        writeln!(out, "Duplicates found by analyzer '{}':", self.name())?;
+        writeln!(out, "{}", "-".repeat(60))?;
+        for finding in findings {
+            let first = &finding.first;
+            let second = &finding.second;
+            writeln!(
+                out,
+                "\nSimilarity: {:.2}%\n  {}\n  {}",
+                finding.similarity * 100.0,
+                format_function_output(&first.file, &first.name, first.start_line, first.end_line),
+                format_function_output(
+                    &second.file,
+                    &second.name,
+                    second.start_line,
+                    second.end_line
+                ),
+            )?;
+        }
+        writeln!(out, "\nTotal duplicate pairs found: {}", findings.len())
+    }
+}
+
+/// Extract units from every file and report all pairs at or above `threshold`,
+/// most similar first. Pairs lying inside a pair that is at least as similar
+/// (e.g. the nested blocks of two duplicated documents) are dropped.
+pub fn run_analyzer(
+    analyzer: &mut dyn Analyzer,
+    files: &[(String, String)],
+    threshold: f64,
+) -> Result<Vec<AnalyzerFinding>, AnalyzerError> {
+    run_analyzer_with_budget(analyzer, files, threshold, &ComparisonBudget::unlimited())
+}
+
+/// [`run_analyzer`], skipping the remaining pairs once the budget runs out
+pub fn run_analyzer_with_budget(
+    analyzer: &mut dyn Analyzer,
+    files: &[(String, String)],
+    threshold: f64,
+    budget: &ComparisonBudget,
+) -> Result<Vec<AnalyzerFinding>, AnalyzerError> {
+    let mut units = Vec::new();
+    for (file, source) in files {
+        units.extend(analyzer.extract(file, source)?);
+    }
+
+    let mut findings = Vec::new();
+    for i in 0..units.len() {
+        for j in (i + 1)..units.len() {
+            if !budget.try_compare() {
+                continue;
+            }
+            let similarity = analyzer.compare(&units[i], &units[j]);
+            if similarity >= threshold {
+                findings.push(AnalyzerFinding {
+                    analyzer: analyzer.name().to_string(),
+                    similarity,
+                    first: units[i].location(),
+                    second: units[j].location(),
+                });
+            }
+        }
+    }
+
+    let redundant: Vec<bool> = findings
+        .iter()
+        .map(|finding| {
+            findings.iter().any(|outer| {
+                outer.similarity >= finding.similarity && pair_contains(outer, finding)
+            })
+        })
+        .collect();
+    let mut findings: Vec<AnalyzerFinding> = findings
+        .into_iter()
+        .zip(redundant)
+        .filter(|(_, redundant)| !redundant)
+        .map(|(finding, _)| finding)
+        .collect();
+
+    findings.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    Ok(findings)
+}
+
+fn location_contains(outer: &UnitLocation, inner: &UnitLocation) -> bool {
+    outer.file == inner.file
+        && outer.start_line <= inner.start_line
+        && inner.end_line <= outer.end_line
+}
+
+/// Whether both sides of `inner` lie within the two sides of `outer`
+fn pair_contains(outer: &AnalyzerFinding, inner: &AnalyzerFinding) -> bool {
+    let same_pair = outer.first == inner.first && outer.second == inner.second;
+    let contains = |a: &UnitLocation, b: &UnitLocation| {
+        location_contains(a, &inner.first) && location_contains(b, &inner.second)
+    };
+    !same_pair && (contains(&outer.first, &outer.second) || contains(&outer.second, &outer.first))
+}
+
+/// Analyzers available by name
+#[derive(Default)]
+pub struct AnalyzerRegistry {
+    analyzers: Vec<Box<dyn Analyzer>>,
+}
+
+impl AnalyzerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an analyzer, replacing any previous one with the same name
+    pub fn register(&mut self, analyzer: Box<dyn Analyzer>) {
+        self.analyzers.retain(|existing| existing.name() != analyzer.name());
+        self.analyzers.push(analyzer);
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut (dyn Analyzer + 'static)> {
+        self.analyzers.iter_mut().find(|analyzer| analyzer.name() == name).map(|a| a.as_mut())
+    }
+
+    /// Registered analyzer names in registration order
+    pub fn names(&self) -> Vec<&str> {
+        self.analyzers.iter().map(|analyzer| analyzer.name()).collect()
+    }
+}
+
+/// Exposes any [`LanguageParser`] as an analyzer comparing function bodies
+pub struct LanguageParserAnalyzer {
+    name: String,
+    extensions: Vec<&'static str>,
+    parser: Box<dyn LanguageParser>,
+    options: TSEDOptions,
+}
+
+impl LanguageParserAnalyzer {
+    pub fn new(
+        name: &str,
+        extensions: Vec<&'static str>,
+        parser: Box<dyn LanguageParser>,
+        options: TSEDOptions,
+    ) -> Self {
+        Self { name: name.to_string(), extensions, parser, options }
+    }
+}
+
+impl Analyzer for LanguageParserAnalyzer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let lines: Vec<&str> = source.lines().collect();
+        let functions = self.parser.extract_functions(source, file)?;
+
+        let mut units = Vec::new();
+        for function in functions {
+            if function.end_line - function.start_line + 1 < self.options.min_lines {
+                continue;
+            }
+
+            let start = function.body_start_line.saturating_sub(1) as usize;
+            let end = (function.body_end_line as usize).min(lines.len());
+            if start >= end {
+                continue;
+            }
+
+            // Bodies that do not parse on their own are skipped rather than failing the run
+            let body = lines[start..end].join("\n");
+            if let Ok(tree) = self.parser.parse(&body, &format!("{}:{}", file, function.name)) {
+                units.push(AnalysisUnit {
+                    name: function.name.to_string(),
+                    file: file.to_string(),
+                    start_line: function.start_line,
+                    end_line: function.end_line,
+                    tree,
+                    structure: None,
+                });
+            }
+        }
+
+        Ok(units)
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        calculate_tsed(&first.tree, &second.tree, &self.options)
+    }
+}
+
+/// Exposes the type definitions of a [`LanguageParser`] as an analyzer comparing
+/// their fields, e.g. DTO classes and records that repeat the same properties
+pub struct LanguageTypeAnalyzer {
+    name: String,
+    extensions: Vec<&'static str>,
+    parser: Box<dyn LanguageParser>,
+    comparator: RefCell<StructureComparator>,
+    min_fields: usize,
+}
+
+impl LanguageTypeAnalyzer {
+    pub fn new(name: &str, extensions: Vec<&'static str>, parser: Box<dyn LanguageParser>) -> Self {
+        // Copies of a DTO are usually renamed per layer (`OrderDto`, `OrderViewModel`),
+        // so the fields weigh more than the type name
+        let options =
+            ComparisonOptions { name_weight: 0.2, structure_weight: 0.8, ..Default::default() };
+        Self::with_options(name, extensions, parser, options, 3)
+    }
+
+    /// Types with fewer than `min_fields` fields are not compared
+    pub fn with_options(
+        name: &str,
+        extensions: Vec<&'static str>,
+        parser: Box<dyn LanguageParser>,
+        options: ComparisonOptions,
+        min_fields: usize,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            extensions,
+            parser,
+            comparator: RefCell::new(StructureComparator::new(options)),
+            min_fields,
+        }
+    }
+}
+
+impl Analyzer for LanguageTypeAnalyzer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let types = self.parser.extract_types(source, file)?;
+
+        Ok(types
+            .into_iter()
+            .filter(|type_def| type_def.fields.len() >= self.min_fields)
+            .map(|type_def| {
+                let mut tree = TreeNode::new(type_def.kind.clone(), String::new(), 0);
+                for (id, field) in type_def.fields.iter().enumerate() {
+                    tree.add_child(Rc::new(TreeNode::new(field.clone(), String::new(), id + 1)));
+                }
+                let structure = Structure {
+                    identifier: StructureIdentifier {
+                        name: type_def.name.as_str().into(),
+                        kind: StructureKind::Generic(type_def.kind),
+                        namespace: None,
+                    },
+                    members: type_def
+                        .fields
+                        .into_iter()
+                        .map(|name| StructureMember {
+                            name,
+                            value_type: String::new().into(),
+                            modifiers: vec![],
+                            nested: None,
+                        })
+                        .collect(),
+                    metadata: StructureMetadata {
+                        location: SourceLocation {
+                            file_path: file.into(),
+                            start_line: type_def.start_line as usize,
+                            end_line: type_def.end_line as usize,
+                        },
+                        ..Default::default()
+                    },
+                };
+                AnalysisUnit {
+                    name: type_def.name,
+                    file: file.to_string(),
+                    start_line: type_def.start_line,
+                    end_line: type_def.end_line,
+                    tree: Rc::new(tree),
+                    structure: Some(structure),
+                }
+            })
+            .collect())
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        // A class and a record with the same fields are still duplicated DTOs,
+        // so the kind of declaration is not required to match
+        match (&first.structure, &second.structure) {
+            (Some(s1), Some(s2)) => self.comparator.borrow_mut().compare(s1, s2).overall_similarity,
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic_tree_sitter_parser::GenericTreeSitterParser;
+
+    /// Toy analyzer treating every `;`-terminated statement as a unit of tokens
+    struct StatementAnalyzer;
+
+    impl Analyzer for StatementAnalyzer {
+        fn name(&self) -> &str {
+            "sql"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["sql"]
+        }
+
+        fn extract(
+            &mut self,
+            file: &str,
+            source: &str,
+        ) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+            let mut units = Vec::new();
+            for (index, line) in source.lines().enumerate() {
+                let statement = line.trim().trim_end_matches(';');
+                if statement.is_empty() {
+                    continue;
+                }
+
+                let mut root = TreeNode::new("statement".to_string(), String::new(), 0);
+                for (id, token) in statement.split_whitespace().enumerate() {
+                    let label = token.to_uppercase();
+                    root.add_child(Rc::new(TreeNode::new(label, String::new(), id + 1)));
+                }
+
+                let line_number = index as u32 + 1;
+                units.push(AnalysisUnit {
+                    name: format!("statement{}", line_number),
+                    file: file.to_string(),
+                    start_line: line_number,
+                    end_line: line_number,
+                    tree: Rc::new(root),
+                    structure: None,
+                });
+            }
+            Ok(units)
+        }
+
+        fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+            let labels = |unit: &AnalysisUnit| {
+                unit.tree.children.iter().map(|child| child.label.clone()).collect::<Vec<_>>()
+            };
+            if labels(first) == labels(second) {
+                1.0
+            } else {
+                0.0
+            }
+        }
+    }
+
+    #[test]
+    fn runs_custom_analyzer_from_registry() {
+        let mut registry = AnalyzerRegistry::new();
+        registry.register(Box::new(StatementAnalyzer));
+        assert_eq!(registry.names(), vec!["sql"]);
+        assert!(registry.get_mut("missing").is_none());
+
+        let files = vec![
+            ("a.sql".to_string(), "select * from users;\nselect id from orders;\n".to_string()),
+            ("b.sql".to_string(), "SELECT * FROM users;\n".to_string()),
+        ];
+        let analyzer = registry.get_mut("sql").unwrap();
+        let findings = run_analyzer(analyzer, &files, 0.9).unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].analyzer, "sql");
+        assert_eq!(findings[0].first.file, "a.sql");
+        assert_eq!(findings[0].second.file, "b.sql");
+
+        let mut out = Vec::new();
+        analyzer.report(&findings, &mut out).unwrap();
+        let report = String::from_utf8(out).unwrap();
+        assert!(report.contains("Similarity: 100.00%"));
+        assert!(report.contains("a.sql:1-1 statement1"));
+        assert!(report.contains("Total duplicate pairs found: 1"));
+    }
+
+    #[test]
+    fn stops_comparing_when_the_budget_runs_out() {
+        let files = vec![
+            ("a.sql".to_string(), "select 1;\nselect 2;\n".to_string()),
+            ("b.sql".to_string(), "select 1;\n".to_string()),
+        ];
+        let budget = ComparisonBudget::unlimited().with_max_comparisons(1);
+        let findings =
+            run_analyzer_with_budget(&mut StatementAnalyzer, &files, 0.0, &budget).unwrap();
+
+        assert_eq!(findings.len(), 1);
+        let coverage = budget.coverage();
+        assert_eq!((coverage.compared, coverage.candidates), (1, 3));
+        assert!(!coverage.is_complete());
+    }
+
+    #[test]
+    fn wraps_language_parsers() {
+        let parser = GenericTreeSitterParser::from_language_name("go").unwrap();
+        let options = TSEDOptions { min_lines: 1, size_penalty: false, ..Default::default() };
+        let mut analyzer = LanguageParserAnalyzer::new("go", vec!["go"], Box::new(parser), options);
+
+        let source = r#"package main
+
+func sumA(items []int) int {
+	total := 0
+	for _, item := range items {
+		total += item
+	}
+	return total
+}
+
+func sumB(values []int) int {
+	result := 0
+	for _, value := range values {
+		result += value
+	}
+	return result
+}
+"#;
+        let files = vec![("main.go".to_string(), source.to_string())];
+        let findings = run_analyzer(&mut analyzer, &files, 0.9).unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].first.name, "sumA");
+        assert_eq!(findings[0].second.name, "sumB");
+    }
+
+    #[test]
+    fn compares_type_fields_across_declaration_kinds() {
+        let parser = GenericTreeSitterParser::from_language_name("csharp").unwrap();
+        let mut analyzer = LanguageTypeAnalyzer::new("csharp-types", vec!["cs"], Box::new(parser));
+
+        let source = r#"
+public record UserDto(int Id, string Name, string Email);
+
+public class UserModel
+{
+    public int Id { get; set; }
+    public string Name { get; set; }
+    public string Email { get; set; }
+}
+
+public class Settings
+{
+    public bool DarkMode { get; set; }
+}
+"#;
+        let files = vec![("Users.cs".to_string(), source.to_string())];
+        let findings = run_analyzer(&mut analyzer, &files, 0.8).unwrap();
+
+        // `Settings` has too few fields to be compared
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].first.name, "UserDto");
+        assert_eq!(findings[0].second.name, "UserModel");
+    }
+}
@@ -51,18 +51,18 @@ pub fn compute_edit_distance_with_cutoff(
     compute_edit_distance_cutoff(tree1, tree2, options, &mut memo, max_distance)
 }
 
+/// Cost of turning `node1` into `node2`: renaming the weight they share when
+/// they differ, plus deleting or inserting the weight one has over the other
 fn node_rename_cost(node1: &TreeNode, node2: &TreeNode, options: &APTEDOptions) -> f64 {
-    if options.compare_values {
-        if node1.label == node2.label && node1.value == node2.value {
-            0.0
-        } else {
-            options.rename_cost
-        }
-    } else if node1.label == node2.label {
-        0.0
+    let same =
+        node1.label == node2.label && (!options.compare_values || node1.value == node2.value);
+    let rename = if same { 0.0 } else { options.rename_cost * node1.weight.min(node2.weight) };
+    let resize = if node1.weight > node2.weight {
+        options.delete_cost * (node1.weight - node2.weight)
     } else {
-        options.rename_cost
-    }
+        options.insert_cost * (node2.weight - node1.weight)
+    };
+    rename + resize
 }
 
 fn compute_edit_distance_recursive(
@@ -85,8 +85,8 @@ fn compute_edit_distance_recursive(
     }
 
     // Calculate costs for all three operations
-    let delete_all_cost = options.delete_cost * node1.get_subtree_size() as f64;
-    let insert_all_cost = options.insert_cost * node2.get_subtree_size() as f64;
+    let delete_all_cost = options.delete_cost * node1.get_subtree_weight();
+    let insert_all_cost = options.insert_cost * node2.get_subtree_weight();
 
     // Calculate rename + optimal children alignment
     let mut rename_plus_cost = node_rename_cost(node1, node2, options);
@@ -139,10 +139,10 @@ fn compute_edit_distance_cutoff(
         return cost;
     }
 
-    let size1 = node1.get_subtree_size() as f64;
-    let size2 = node2.get_subtree_size() as f64;
+    let size1 = node1.get_subtree_weight();
+    let size2 = node2.get_subtree_weight();
 
-    // Lower bound: at minimum, we need to insert/delete the size difference
+    // Lower bound: at minimum, we need to insert/delete the weight difference
     let min_op_cost = options.delete_cost.min(options.insert_cost);
     let lower_bound = (size1 - size2).abs() * min_op_cost;
     if lower_bound > max_distance {
@@ -212,10 +212,10 @@ fn compute_children_alignment(
 
     // Initialize base cases
     for i in 1..=m {
-        dp[i][0] = dp[i - 1][0] + options.delete_cost * children1[i - 1].get_subtree_size() as f64;
+        dp[i][0] = dp[i - 1][0] + options.delete_cost * children1[i - 1].get_subtree_weight();
     }
     for j in 1..=n {
-        dp[0][j] = dp[0][j - 1] + options.insert_cost * children2[j - 1].get_subtree_size() as f64;
+        dp[0][j] = dp[0][j - 1] + options.insert_cost * children2[j - 1].get_subtree_weight();
     }
 
     // Fill DP table
@@ -225,8 +225,8 @@ fn compute_children_alignment(
             let child2 = &children2[j - 1];
             let edit_cost = cost_matrix.get(&(child1.id, child2.id)).unwrap_or(&0.0);
 
-            dp[i][j] = (dp[i - 1][j] + options.delete_cost * child1.get_subtree_size() as f64)
-                .min(dp[i][j - 1] + options.insert_cost * child2.get_subtree_size() as f64)
+            dp[i][j] = (dp[i - 1][j] + options.delete_cost * child1.get_subtree_weight())
+                .min(dp[i][j - 1] + options.insert_cost * child2.get_subtree_weight())
                 .min(dp[i - 1][j - 1] + edit_cost);
         }
     }
@@ -247,8 +247,8 @@ fn compute_children_alignment(
             let child2 = &children2[j - 1];
             let edit_cost = cost_matrix.get(&(child1.id, child2.id)).unwrap_or(&0.0);
 
-            let delete_cost = dp[i - 1][j] + options.delete_cost * child1.get_subtree_size() as f64;
-            let insert_cost = dp[i][j - 1] + options.insert_cost * child2.get_subtree_size() as f64;
+            let delete_cost = dp[i - 1][j] + options.delete_cost * child1.get_subtree_weight();
+            let insert_cost = dp[i][j - 1] + options.insert_cost * child2.get_subtree_weight();
             let match_cost = dp[i - 1][j - 1] + edit_cost;
 
             if match_cost <= delete_cost && match_cost <= insert_cost {
@@ -282,10 +282,10 @@ fn compute_children_alignment_with_cutoff(
     let mut dp = vec![vec![0.0; n + 1]; m + 1];
 
     for i in 1..=m {
-        dp[i][0] = dp[i - 1][0] + options.delete_cost * children1[i - 1].get_subtree_size() as f64;
+        dp[i][0] = dp[i - 1][0] + options.delete_cost * children1[i - 1].get_subtree_weight();
     }
     for j in 1..=n {
-        dp[0][j] = dp[0][j - 1] + options.insert_cost * children2[j - 1].get_subtree_size() as f64;
+        dp[0][j] = dp[0][j - 1] + options.insert_cost * children2[j - 1].get_subtree_weight();
     }
 
     for i in 1..=m {
@@ -298,14 +298,14 @@ fn compute_children_alignment_with_cutoff(
 
             // If edit_cost is DISTANCE_EXCEEDED, use delete+insert as fallback
             let edit_cost = if edit_cost >= DISTANCE_EXCEEDED {
-                options.delete_cost * child1.get_subtree_size() as f64
-                    + options.insert_cost * child2.get_subtree_size() as f64
+                options.delete_cost * child1.get_subtree_weight()
+                    + options.insert_cost * child2.get_subtree_weight()
             } else {
                 edit_cost
             };
 
-            dp[i][j] = (dp[i - 1][j] + options.delete_cost * child1.get_subtree_size() as f64)
-                .min(dp[i][j - 1] + options.insert_cost * child2.get_subtree_size() as f64)
+            dp[i][j] = (dp[i - 1][j] + options.delete_cost * child1.get_subtree_weight())
+                .min(dp[i][j - 1] + options.insert_cost * child2.get_subtree_weight())
                 .min(dp[i - 1][j - 1] + edit_cost);
 
             row_min = row_min.min(dp[i][j]);
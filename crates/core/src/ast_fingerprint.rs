@@ -28,10 +28,7 @@ impl AstFingerprint {
         let ret = Parser::new(&allocator, source, source_type).parse();
 
         if !ret.errors.is_empty() {
-            // Create a more readable error message
-            let error_messages: Vec<String> =
-                ret.errors.iter().map(|e| e.message.to_string()).collect();
-            return Err(format!("Parse errors: {}", error_messages.join(", ")));
+            return Err(crate::parser::parse_errors_message(&ret.errors, source));
         }
 
         let mut fingerprint = Self::new();
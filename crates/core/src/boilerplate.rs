@@ -0,0 +1,215 @@
+//! Known boilerplate functions, persisted in `.similarity/boilerplate.json`.
+//!
+//! Functions learned from a directory of templates (module/controller/service
+//! skeletons and the like) are stored with their source. Later runs parse them
+//! back and suppress duplicates whose functions match one of them within the
+//! similarity threshold, so expected duplication stops dominating the report.
+
+use crate::function_extractor::{extract_functions, FunctionDefinition};
+use crate::parser::parse_and_convert_to_tree;
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, TSEDOptions};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Default location of the boilerplate file, relative to the working directory
+pub const DEFAULT_BOILERPLATE_PATH: &str = ".similarity/boilerplate.json";
+
+/// A learned boilerplate function
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoilerplateEntry {
+    pub file: String,
+    pub name: String,
+    pub source: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BoilerplateFile {
+    functions: Vec<BoilerplateEntry>,
+}
+
+/// Boilerplate functions together with their parsed trees
+#[derive(Debug)]
+pub struct BoilerplateSet {
+    path: PathBuf,
+    entries: Vec<BoilerplateEntry>,
+    trees: Vec<Option<Rc<TreeNode>>>,
+}
+
+impl BoilerplateSet {
+    /// Load the boilerplate file; a missing file is an empty set
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => {
+                serde_json::from_str::<BoilerplateFile>(&content)
+                    .with_context(|| format!("Failed to parse {}", path.display()))?
+                    .functions
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        Ok(Self::from_entries(path, entries))
+    }
+
+    /// Learn every function of the given TypeScript/JavaScript files, replacing the stored set
+    pub fn learn(path: impl Into<PathBuf>, files: &[PathBuf]) -> Result<Self> {
+        let mut entries = Vec::new();
+        for file in files {
+            let content = fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let filename = file.to_string_lossy();
+            let functions = extract_functions(&filename, &content)
+                .map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e))?;
+            entries.extend(functions.iter().map(|function| BoilerplateEntry {
+                file: filename.to_string(),
+                name: function.name.to_string(),
+                source: function_source(function, &content).to_string(),
+            }));
+        }
+        Ok(Self::from_entries(path.into(), entries))
+    }
+
+    fn from_entries(path: PathBuf, entries: Vec<BoilerplateEntry>) -> Self {
+        let trees = entries.iter().map(|entry| function_tree(&entry.source)).collect();
+        Self { path, entries, trees }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = BoilerplateFile { functions: self.entries.clone() };
+        fs::write(&self.path, serde_json::to_string_pretty(&file)? + "\n")
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The boilerplate function that `function` is an instance of, if any.
+    ///
+    /// Template functions are often short, so instead of the size penalty the
+    /// trees must have comparable sizes before their TSED score is considered.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn find_match(
+        &self,
+        function: &FunctionDefinition,
+        source_text: &str,
+        threshold: f64,
+        options: &TSEDOptions,
+    ) -> Option<&BoilerplateEntry> {
+        let tree = function_tree(function_source(function, source_text))?;
+        let options = TSEDOptions { size_penalty: false, ..options.clone() };
+        let size = tree.get_subtree_size() as f64;
+        self.entries
+            .iter()
+            .zip(&self.trees)
+            .filter_map(|(entry, boilerplate)| {
+                let boilerplate = boilerplate.as_ref()?;
+                let other_size = boilerplate.get_subtree_size() as f64;
+                if size.min(other_size) / size.max(other_size) < threshold {
+                    return None;
+                }
+                let similarity = calculate_tsed(&tree, boilerplate, &options);
+                (similarity >= threshold).then_some((entry, similarity))
+            })
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(entry, _)| entry)
+    }
+}
+
+fn function_source<'a>(function: &FunctionDefinition, source_text: &'a str) -> &'a str {
+    let start = function.body_span.start as usize;
+    let end = (function.body_span.end as usize).min(source_text.len());
+    source_text.get(start..end).unwrap_or_default()
+}
+
+/// Parse a function's source; methods only parse inside a class body
+fn function_tree(source: &str) -> Option<Rc<TreeNode>> {
+    parse_and_convert_to_tree("boilerplate.ts", source)
+        .or_else(|_| {
+            parse_and_convert_to_tree("boilerplate.ts", &format!("class C {{ {source} }}"))
+        })
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const TEMPLATE: &str = r#"
+export class UserController {
+    constructor(private readonly service: UserService) {}
+
+    async findOne(id: string) {
+        const entity = await this.service.findOne(id);
+        if (!entity) {
+            throw new NotFoundException(`User ${id} not found`);
+        }
+        return entity;
+    }
+}
+"#;
+
+    #[test]
+    fn learned_functions_round_trip_and_match_instances() {
+        let dir = TempDir::new().unwrap();
+        let template = dir.path().join("controller.ts");
+        fs::write(&template, TEMPLATE).unwrap();
+        let path = dir.path().join(".similarity/boilerplate.json");
+
+        let set = BoilerplateSet::learn(&path, &[template]).unwrap();
+        assert_eq!(set.len(), 2);
+        set.save().unwrap();
+
+        let set = BoilerplateSet::load(&path).unwrap();
+        assert_eq!(set.len(), 2);
+
+        let instance = TEMPLATE
+            .replace("UserController", "OrderController")
+            .replace("UserService", "OrderService")
+            .replace("User ", "Order ");
+        let functions = extract_functions("orders.ts", &instance).unwrap();
+        let find_one = functions.iter().find(|f| f.name == "findOne").unwrap();
+        let options = TSEDOptions::default();
+        let matched = set.find_match(find_one, &instance, 0.9, &options).unwrap();
+        assert_eq!(matched.name, "findOne");
+
+        let unrelated = r#"
+function total(xs: number[]) {
+    let s = 0;
+    for (const x of xs) {
+        s += x;
+    }
+    return s;
+}
+"#;
+        let functions = extract_functions("total.ts", unrelated).unwrap();
+        assert!(set.find_match(&functions[0], unrelated, 0.9, &options).is_none());
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_set() {
+        let dir = TempDir::new().unwrap();
+        let set = BoilerplateSet::load(dir.path().join("boilerplate.json")).unwrap();
+        assert!(set.is_empty());
+    }
+}
@@ -1,6 +1,20 @@
-use crate::class_extractor::{ClassDefinition, ClassMethod, ClassProperty};
+use crate::class_extractor::{ClassDefinition, ClassMethod, ClassProperty, Decorator};
+use crate::comparison_budget::ComparisonBudget;
+use crate::parser::parse_function_body;
+use crate::source_text::SourceSlice;
+use crate::tsed::{calculate_tsed, TSEDOptions};
 use std::collections::HashMap;
 
+/// How decorators take part in class comparison
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClassComparisonOptions {
+    /// Compare classes as if they had no decorators
+    pub ignore_decorators: bool,
+    /// Only compare classes carrying the same class decorators, so that e.g.
+    /// components are compared with components and services with services
+    pub same_decorators_only: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct NormalizedClass {
     pub name: String,
@@ -9,6 +23,7 @@ pub struct NormalizedClass {
     pub constructor_signature: String,
     pub extends: Option<String>,
     pub implements: Vec<String>,
+    pub decorators: Vec<Decorator>,
 }
 
 #[derive(Debug, Clone)]
@@ -17,6 +32,15 @@ pub struct ClassComparisonResult {
     pub structural_similarity: f64,
     pub naming_similarity: f64,
     pub differences: ClassDifferences,
+    /// Body similarity of each method both classes define, most similar first.
+    /// Only filled in for the pairs returned by `find_similar_classes`.
+    pub method_similarities: Vec<MethodSimilarity>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MethodSimilarity {
+    pub name: String,
+    pub similarity: f64,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +51,16 @@ pub struct ClassDifferences {
     pub extra_methods: Vec<String>,
     pub property_type_mismatches: Vec<PropertyMismatch>,
     pub method_signature_mismatches: Vec<MethodMismatch>,
+    pub missing_decorators: Vec<String>,
+    pub extra_decorators: Vec<String>,
+    pub decorator_mismatches: Vec<DecoratorMismatch>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecoratorMismatch {
+    pub name: String,
+    pub decorator1: String,
+    pub decorator2: String,
 }
 
 #[derive(Debug, Clone)]
@@ -51,9 +85,26 @@ pub struct SimilarClassPair {
 }
 
 pub fn normalize_class(class: &ClassDefinition) -> NormalizedClass {
+    normalize_class_with_options(class, &ClassComparisonOptions::default())
+}
+
+pub fn normalize_class_with_options(
+    class: &ClassDefinition,
+    options: &ClassComparisonOptions,
+) -> NormalizedClass {
+    let decorators = |decorators: &[Decorator]| {
+        if options.ignore_decorators {
+            Vec::new()
+        } else {
+            normalize_decorators(decorators)
+        }
+    };
+
     let mut properties = HashMap::new();
     for prop in &class.properties {
-        properties.insert(prop.name.clone(), prop.clone());
+        let normalized_prop =
+            ClassProperty { decorators: decorators(&prop.decorators), ..prop.clone() };
+        properties.insert(prop.name.clone(), normalized_prop);
     }
 
     let mut methods = HashMap::new();
@@ -62,12 +113,15 @@ pub fn normalize_class(class: &ClassDefinition) -> NormalizedClass {
         let normalized_method = ClassMethod {
             name: method.name.clone(),
             parameters: normalize_parameters(&method.parameters),
-            return_type: normalize_type(&method.return_type),
+            return_type: normalize_type(&method.return_type).into(),
             is_static: method.is_static,
             is_private: method.is_private,
+            is_protected: method.is_protected,
             is_async: method.is_async,
             is_generator: method.is_generator,
             kind: method.kind.clone(),
+            decorators: decorators(&method.decorators),
+            body: method.body.clone(),
         };
         methods.insert(method.name.clone(), normalized_method);
     }
@@ -79,15 +133,46 @@ pub fn normalize_class(class: &ClassDefinition) -> NormalizedClass {
     };
 
     NormalizedClass {
-        name: class.name.clone(),
+        name: class.name.to_string(),
         properties,
         methods,
         constructor_signature,
         extends: class.extends.clone(),
         implements: class.implements.clone(),
+        decorators: decorators(&class.decorators),
     }
 }
 
+fn normalize_decorators(decorators: &[Decorator]) -> Vec<Decorator> {
+    decorators
+        .iter()
+        .map(|decorator| Decorator {
+            name: decorator.name.replace(' ', ""),
+            arguments: decorator.arguments.as_ref().map(|arguments| {
+                SourceSlice::owned(&arguments.split_whitespace().collect::<String>())
+            }),
+        })
+        .collect()
+}
+
+/// `signature` prefixed with the member's decorators, e.g. `@Input() string`
+fn with_decorators(decorators: &[Decorator], signature: &str) -> String {
+    decorators
+        .iter()
+        .map(ToString::to_string)
+        .chain(std::iter::once(signature.to_string()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Sorted names of the class decorators, identifying a class's decorator cohort
+fn decorator_names(class: &ClassDefinition) -> Vec<&str> {
+    let mut names: Vec<&str> =
+        class.decorators.iter().map(|decorator| decorator.name.as_str()).collect();
+    names.sort_unstable();
+    names
+}
+
 fn normalize_parameters(params: &[String]) -> Vec<String> {
     params.iter().map(|p| normalize_type(p)).collect()
 }
@@ -101,8 +186,16 @@ pub fn compare_classes(
     class1: &ClassDefinition,
     class2: &ClassDefinition,
 ) -> ClassComparisonResult {
-    let norm1 = normalize_class(class1);
-    let norm2 = normalize_class(class2);
+    compare_classes_with_options(class1, class2, &ClassComparisonOptions::default())
+}
+
+pub fn compare_classes_with_options(
+    class1: &ClassDefinition,
+    class2: &ClassDefinition,
+    options: &ClassComparisonOptions,
+) -> ClassComparisonResult {
+    let norm1 = normalize_class_with_options(class1, options);
+    let norm2 = normalize_class_with_options(class2, options);
 
     // Calculate naming similarity
     let naming_similarity = calculate_name_similarity(&class1.name, &class2.name);
@@ -113,7 +206,13 @@ pub fn compare_classes(
     // Combined similarity (weighted average)
     let similarity = 0.3 * naming_similarity + 0.7 * structural_similarity;
 
-    ClassComparisonResult { similarity, structural_similarity, naming_similarity, differences }
+    ClassComparisonResult {
+        similarity,
+        structural_similarity,
+        naming_similarity,
+        differences,
+        method_similarities: Vec::new(),
+    }
 }
 
 fn calculate_name_similarity(name1: &str, name2: &str) -> f64 {
@@ -147,13 +246,16 @@ fn calculate_structural_similarity(
     for (name, prop1) in &class1.properties {
         property_total += 1;
         if let Some(prop2) = class2.properties.get(name) {
-            if prop1.type_annotation == prop2.type_annotation {
+            let type1 = with_decorators(&prop1.decorators, &prop1.type_annotation);
+            let type2 = with_decorators(&prop2.decorators, &prop2.type_annotation);
+
+            if type1 == type2 {
                 property_matches += 1;
             } else {
                 property_type_mismatches.push(PropertyMismatch {
                     name: name.clone(),
-                    type1: prop1.type_annotation.clone(),
-                    type2: prop2.type_annotation.clone(),
+                    type1,
+                    type2,
                 });
             }
         } else {
@@ -179,8 +281,14 @@ fn calculate_structural_similarity(
     for (name, method1) in &class1.methods {
         method_total += 1;
         if let Some(method2) = class2.methods.get(name) {
-            let sig1 = format!("({}) => {}", method1.parameters.join(", "), method1.return_type);
-            let sig2 = format!("({}) => {}", method2.parameters.join(", "), method2.return_type);
+            let sig1 = with_decorators(
+                &method1.decorators,
+                &format!("({}) => {}", method1.parameters.join(", "), method1.return_type),
+            );
+            let sig2 = with_decorators(
+                &method2.decorators,
+                &format!("({}) => {}", method2.parameters.join(", "), method2.return_type),
+            );
 
             if sig1 == sig2 {
                 method_matches += 1;
@@ -203,8 +311,36 @@ fn calculate_structural_similarity(
         }
     }
 
+    // Check class decorators. Identical decorators are framework boilerplate
+    // shared by every class of a kind, so they do not add to the similarity;
+    // decorators that differ or are missing count as unmatched elements.
+    let mut missing_decorators = Vec::new();
+    let mut extra_decorators = Vec::new();
+    let mut decorator_mismatches = Vec::new();
+
+    for decorator1 in &class1.decorators {
+        match class2.decorators.iter().find(|decorator2| decorator2.name == decorator1.name) {
+            Some(decorator2) if decorator2 == decorator1 => {}
+            Some(decorator2) => decorator_mismatches.push(DecoratorMismatch {
+                name: decorator1.name.clone(),
+                decorator1: decorator1.to_string(),
+                decorator2: decorator2.to_string(),
+            }),
+            None => missing_decorators.push(decorator1.to_string()),
+        }
+    }
+
+    for decorator2 in &class2.decorators {
+        if !class1.decorators.iter().any(|decorator1| decorator1.name == decorator2.name) {
+            extra_decorators.push(decorator2.to_string());
+        }
+    }
+
+    let decorator_total =
+        missing_decorators.len() + extra_decorators.len() + decorator_mismatches.len();
+
     // Calculate overall structural similarity
-    let total_elements = property_total + method_total;
+    let total_elements = property_total + method_total + decorator_total;
     let matched_elements = property_matches + method_matches;
 
     let structural_similarity =
@@ -217,11 +353,43 @@ fn calculate_structural_similarity(
         extra_methods,
         property_type_mismatches,
         method_signature_mismatches,
+        missing_decorators,
+        extra_decorators,
+        decorator_mismatches,
     };
 
     (structural_similarity, differences)
 }
 
+/// Compare the bodies of the methods `class1` and `class2` share by name
+pub fn compare_method_bodies(
+    class1: &ClassDefinition,
+    class2: &ClassDefinition,
+    options: &TSEDOptions,
+) -> Vec<MethodSimilarity> {
+    let mut similarities: Vec<MethodSimilarity> = class1
+        .methods
+        .iter()
+        .filter_map(|method1| {
+            let method2 = class2.methods.iter().find(|method2| method2.name == method1.name)?;
+            if method1.body.is_empty() || method2.body.is_empty() {
+                return None;
+            }
+            let parse = |body: &str| parse_function_body("method.ts", body, false).ok();
+            let (tree1, tree2) = (parse(&method1.body)?, parse(&method2.body)?);
+            Some(MethodSimilarity {
+                name: method1.name.clone(),
+                similarity: calculate_tsed(&tree1, &tree2, options),
+            })
+        })
+        .collect();
+
+    similarities.sort_by(|a, b| {
+        b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    similarities
+}
+
 fn levenshtein_distance(s1: &str, s2: &str) -> usize {
     let len1 = s1.len();
     let len2 = s2.len();
@@ -251,13 +419,44 @@ fn levenshtein_distance(s1: &str, s2: &str) -> usize {
 }
 
 pub fn find_similar_classes(classes: &[ClassDefinition], threshold: f64) -> Vec<SimilarClassPair> {
+    find_similar_classes_with_options(classes, threshold, &ClassComparisonOptions::default())
+}
+
+pub fn find_similar_classes_with_options(
+    classes: &[ClassDefinition],
+    threshold: f64,
+    options: &ClassComparisonOptions,
+) -> Vec<SimilarClassPair> {
+    find_similar_classes_with_budget(classes, threshold, options, &ComparisonBudget::unlimited())
+}
+
+/// Find similar class pairs, stopping when the budget runs out
+pub fn find_similar_classes_with_budget(
+    classes: &[ClassDefinition],
+    threshold: f64,
+    options: &ClassComparisonOptions,
+    budget: &ComparisonBudget,
+) -> Vec<SimilarClassPair> {
     let mut similar_pairs = Vec::new();
+    // Method bodies are short, so the short-code penalty would hide identical ones
+    let tsed_options = TSEDOptions { size_penalty: false, ..Default::default() };
 
     for i in 0..classes.len() {
         for j in i + 1..classes.len() {
-            let result = compare_classes(&classes[i], &classes[j]);
+            if options.same_decorators_only
+                && decorator_names(&classes[i]) != decorator_names(&classes[j])
+            {
+                continue;
+            }
+            if !budget.try_compare() {
+                continue;
+            }
+
+            let mut result = compare_classes_with_options(&classes[i], &classes[j], options);
 
             if result.similarity >= threshold {
+                result.method_similarities =
+                    compare_method_bodies(&classes[i], &classes[j], &tsed_options);
                 similar_pairs.push(SimilarClassPair {
                     class1: classes[i].clone(),
                     class2: classes[j].clone(),
@@ -289,3 +488,106 @@ pub fn find_similar_classes_across_files(
 
     find_similar_classes(&all_classes, threshold)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_extractor::extract_classes_from_code;
+
+    const SOURCE: &str = r#"
+@Component({ selector: 'app-user', template: '<p>user</p>' })
+class UserCard {
+    @Input() label: string;
+    load(): void {}
+}
+
+@Component({ selector: 'app-team', template: '<p>team</p>' })
+class TeamCard {
+    @Output() label: string;
+    load(): void {}
+}
+
+@Injectable()
+class UserStore {
+    label: string;
+    load(): void {}
+}
+"#;
+
+    #[test]
+    fn test_differing_decorators_lower_similarity() {
+        let classes = extract_classes_from_code(SOURCE, "test.ts").unwrap();
+
+        let result = compare_classes(&classes[0], &classes[1]);
+        assert_eq!(result.differences.decorator_mismatches.len(), 1);
+        assert_eq!(result.differences.property_type_mismatches[0].type1, "@Input() string");
+        assert!((result.structural_similarity - 1.0 / 3.0).abs() < 1e-9);
+
+        let options = ClassComparisonOptions { ignore_decorators: true, ..Default::default() };
+        let result = compare_classes_with_options(&classes[0], &classes[1], &options);
+        assert!(result.differences.decorator_mismatches.is_empty());
+        assert_eq!(result.structural_similarity, 1.0);
+    }
+
+    #[test]
+    fn test_same_decorators_only() {
+        let classes = extract_classes_from_code(SOURCE, "test.ts").unwrap();
+        let options =
+            ClassComparisonOptions { ignore_decorators: true, same_decorators_only: true };
+
+        let pairs = find_similar_classes_with_options(&classes, 0.0, &options);
+        let names: Vec<(&str, &str)> = pairs
+            .iter()
+            .map(|pair| (pair.class1.name.as_str(), pair.class2.name.as_str()))
+            .collect();
+        assert_eq!(names, [("UserCard", "TeamCard")]);
+        assert_eq!(find_similar_classes(&classes, 0.0).len(), 3);
+    }
+
+    #[test]
+    fn test_method_body_similarities() {
+        let source = r#"
+class OrderService {
+    total(items: number[]): number {
+        let sum = 0;
+        for (const item of items) {
+            sum += item;
+        }
+        return sum;
+    }
+
+    describe(): string {
+        return "orders";
+    }
+}
+
+class InvoiceService {
+    total(lines: number[]): number {
+        let amount = 0;
+        for (const line of lines) {
+            amount += line;
+        }
+        return amount;
+    }
+
+    describe(): string {
+        if (this.lines.length === 0) {
+            throw new Error("empty");
+        }
+        return this.lines.map((line) => line.toString()).join(", ");
+    }
+}
+"#;
+
+        let classes = extract_classes_from_code(source, "test.ts").unwrap();
+        let pairs = find_similar_classes(&classes, 0.0);
+        let similarities = &pairs[0].result.method_similarities;
+
+        let names: Vec<&str> = similarities.iter().map(|method| method.name.as_str()).collect();
+        assert_eq!(names, ["total", "describe"]);
+        assert!(similarities[0].similarity > 0.9);
+        assert!(similarities[1].similarity < 0.8);
+
+        assert!(compare_classes(&classes[0], &classes[1]).method_similarities.is_empty());
+    }
+}
@@ -1,13 +1,16 @@
+use crate::interner::Symbol;
+use crate::source_text::{SharedSource, SourceSlice};
 use oxc_allocator::Allocator;
-use oxc_ast::ast::{ClassElement, MethodDefinitionKind, Statement};
-use oxc_parser::Parser;
-use oxc_span::SourceType;
+use oxc_ast::ast::{ClassElement, MethodDefinitionKind, PropertyKey, Statement, TSAccessibility};
+use oxc_span::{GetSpan, SourceType, Span};
+use std::fmt;
 
 use crate::ignore_directive::has_similarity_ignore_directive;
+use crate::tolerant_parse::parse_tolerant;
 
 #[derive(Debug, Clone)]
 pub struct ClassDefinition {
-    pub name: String,
+    pub name: Symbol,
     pub properties: Vec<ClassProperty>,
     pub methods: Vec<ClassMethod>,
     pub constructor_params: Vec<String>,
@@ -15,31 +18,61 @@ pub struct ClassDefinition {
     pub implements: Vec<String>,
     pub start_line: usize,
     pub end_line: usize,
-    pub file_path: String,
+    pub file_path: Symbol,
     pub is_abstract: bool,
+    /// Class decorators such as `@Component({...})`, in source order
+    pub decorators: Vec<Decorator>,
     pub has_ignore_directive: bool,
+    /// Extracted from a file with syntax errors elsewhere
+    pub partial: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct ClassProperty {
     pub name: String,
-    pub type_annotation: String,
+    pub type_annotation: Symbol,
     pub is_static: bool,
+    /// `private` or `#private`
     pub is_private: bool,
+    pub is_protected: bool,
     pub is_readonly: bool,
     pub is_optional: bool,
+    pub decorators: Vec<Decorator>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ClassMethod {
     pub name: String,
     pub parameters: Vec<String>,
-    pub return_type: String,
+    pub return_type: Symbol,
     pub is_static: bool,
+    /// `private` or `#private`
     pub is_private: bool,
+    pub is_protected: bool,
     pub is_async: bool,
     pub is_generator: bool,
     pub kind: MethodKind,
+    pub decorators: Vec<Decorator>,
+    /// Source text of the body block, empty for methods without a body
+    pub body: SourceSlice,
+}
+
+/// A decorator applied to a class or one of its members
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decorator {
+    /// Decorator expression without its call, e.g. `Component` or `ng.Input`
+    pub name: String,
+    /// Source text of the call arguments, `None` when the decorator is not called
+    pub arguments: Option<SourceSlice>,
+}
+
+impl fmt::Display for Decorator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.arguments {
+            Some(arguments) => write!(f, "@{}({})", self.name, arguments),
+            None => write!(f, "@{}", self.name),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -51,15 +84,15 @@ pub enum MethodKind {
 }
 
 struct ClassExtractor {
-    source_text: String,
-    file_path: String,
+    source_text: SharedSource,
+    file_path: Symbol,
     line_offsets: Vec<usize>,
 }
 
 impl ClassExtractor {
-    fn new(source_text: String, file_path: String) -> Self {
+    fn new(source_text: SharedSource, file_path: String) -> Self {
         let line_offsets = Self::calculate_line_offsets(&source_text);
-        Self { source_text, file_path, line_offsets }
+        Self { source_text, file_path: file_path.into(), line_offsets }
     }
 
     fn calculate_line_offsets(source: &str) -> Vec<usize> {
@@ -257,6 +290,54 @@ impl ClassExtractor {
         param_strings.join(", ")
     }
 
+    /// Member name, with a leading `#` for ECMAScript private names
+    fn member_name(key: &PropertyKey) -> Option<String> {
+        match key {
+            PropertyKey::StaticIdentifier(ident) => Some(ident.name.as_str().to_string()),
+            PropertyKey::StringLiteral(str_lit) => Some(str_lit.value.as_str().to_string()),
+            PropertyKey::PrivateIdentifier(ident) => Some(format!("#{}", ident.name)),
+            _ => None,
+        }
+    }
+
+    /// Whether a member is `private` in TypeScript or has an ECMAScript `#private` name
+    fn is_private(key: &PropertyKey, accessibility: Option<TSAccessibility>) -> bool {
+        accessibility == Some(TSAccessibility::Private)
+            || matches!(key, PropertyKey::PrivateIdentifier(_))
+    }
+
+    fn source_slice(&self, span: Span) -> &str {
+        self.source_text.get(span.start as usize..span.end as usize).unwrap_or_default()
+    }
+
+    fn shared_slice(&self, span: Span) -> SourceSlice {
+        SourceSlice::new(&self.source_text, span)
+    }
+
+    fn extract_decorators(&self, decorators: &[oxc_ast::ast::Decorator]) -> Vec<Decorator> {
+        decorators
+            .iter()
+            .map(|decorator| match &decorator.expression {
+                oxc_ast::ast::Expression::CallExpression(call) => {
+                    let arguments = match (call.arguments.first(), call.arguments.last()) {
+                        (Some(first), Some(last)) => {
+                            self.shared_slice(Span::new(first.span().start, last.span().end))
+                        }
+                        _ => SourceSlice::default(),
+                    };
+                    Decorator {
+                        name: self.source_slice(call.callee.span()).to_string(),
+                        arguments: Some(arguments),
+                    }
+                }
+                expression => Decorator {
+                    name: self.source_slice(expression.span()).to_string(),
+                    arguments: None,
+                },
+            })
+            .collect()
+    }
+
     fn extract_class(&self, class: &oxc_ast::ast::Class) -> ClassDefinition {
         let name = class
             .id
@@ -293,14 +374,8 @@ impl ClassExtractor {
         for element in &class.body.body {
             match element {
                 ClassElement::PropertyDefinition(prop) => {
-                    let name = match &prop.key {
-                        oxc_ast::ast::PropertyKey::StaticIdentifier(ident) => {
-                            ident.name.as_str().to_string()
-                        }
-                        oxc_ast::ast::PropertyKey::StringLiteral(str_lit) => {
-                            str_lit.value.as_str().to_string()
-                        }
-                        _ => continue,
+                    let Some(name) = Self::member_name(&prop.key) else {
+                        continue;
                     };
 
                     let type_annotation = prop
@@ -311,47 +386,58 @@ impl ClassExtractor {
 
                     properties.push(ClassProperty {
                         name,
-                        type_annotation,
+                        type_annotation: type_annotation.into(),
                         is_static: prop.r#static,
-                        is_private: false, // PropertyDefinitionType doesn't have TSPrivateProperty
+                        is_private: Self::is_private(&prop.key, prop.accessibility),
+                        is_protected: prop.accessibility == Some(TSAccessibility::Protected),
                         is_readonly: prop.readonly,
                         is_optional: prop.optional,
+                        decorators: self.extract_decorators(&prop.decorators),
                     });
                 }
                 ClassElement::MethodDefinition(method) => {
-                    let name = match &method.key {
-                        oxc_ast::ast::PropertyKey::StaticIdentifier(ident) => {
-                            ident.name.as_str().to_string()
-                        }
-                        oxc_ast::ast::PropertyKey::StringLiteral(str_lit) => {
-                            str_lit.value.as_str().to_string()
-                        }
-                        _ => continue,
+                    let Some(name) = Self::member_name(&method.key) else {
+                        continue;
                     };
 
                     let kind = match method.kind {
                         MethodDefinitionKind::Constructor => {
                             // Extract constructor parameters
-                            constructor_params = method
-                                .value
-                                .params
-                                .items
-                                .iter()
-                                .map(|param| {
-                                    let param_name = match &param.pattern {
-                                        oxc_ast::ast::BindingPattern::BindingIdentifier(ident) => {
-                                            ident.name.as_str()
-                                        }
-                                        _ => "param",
-                                    };
-                                    let type_str = param
-                                        .type_annotation
-                                        .as_ref()
-                                        .map(|ta| self.extract_type_string(ta))
-                                        .unwrap_or_else(|| "any".to_string());
-                                    format!("{}: {}", param_name, type_str)
-                                })
-                                .collect();
+                            constructor_params.clear();
+                            for param in &method.value.params.items {
+                                let param_name = match &param.pattern {
+                                    oxc_ast::ast::BindingPattern::BindingIdentifier(ident) => {
+                                        ident.name.as_str()
+                                    }
+                                    _ => "param",
+                                };
+                                let type_str = param
+                                    .type_annotation
+                                    .as_ref()
+                                    .map(|ta| self.extract_type_string(ta))
+                                    .unwrap_or_else(|| "any".to_string());
+
+                                // Parameter properties (`private readonly repo: Repo`) also declare members
+                                if param.accessibility.is_some()
+                                    || param.readonly
+                                    || param.r#override
+                                {
+                                    properties.push(ClassProperty {
+                                        name: param_name.to_string(),
+                                        type_annotation: Symbol::from(&type_str),
+                                        is_static: false,
+                                        is_private: param.accessibility
+                                            == Some(TSAccessibility::Private),
+                                        is_protected: param.accessibility
+                                            == Some(TSAccessibility::Protected),
+                                        is_readonly: param.readonly,
+                                        is_optional: param.optional,
+                                        decorators: self.extract_decorators(&param.decorators),
+                                    });
+                                }
+
+                                constructor_params.push(format!("{}: {}", param_name, type_str));
+                            }
                             MethodKind::Constructor
                         }
                         MethodDefinitionKind::Method => MethodKind::Method,
@@ -371,12 +457,20 @@ impl ClassExtractor {
                         methods.push(ClassMethod {
                             name,
                             parameters: vec![parameters],
-                            return_type,
+                            return_type: return_type.into(),
                             is_static: method.r#static,
-                            is_private: false, // Would need to check for private keyword
+                            is_private: Self::is_private(&method.key, method.accessibility),
+                            is_protected: method.accessibility == Some(TSAccessibility::Protected),
                             is_async: method.value.r#async,
                             is_generator: method.value.generator,
                             kind,
+                            decorators: self.extract_decorators(&method.decorators),
+                            body: method
+                                .value
+                                .body
+                                .as_ref()
+                                .map(|body| self.shared_slice(body.span))
+                                .unwrap_or_default(),
                         });
                     }
                 }
@@ -385,7 +479,7 @@ impl ClassExtractor {
         }
 
         ClassDefinition {
-            name,
+            name: name.into(),
             properties,
             methods,
             constructor_params,
@@ -393,27 +487,25 @@ impl ClassExtractor {
             implements,
             start_line,
             end_line,
-            file_path: self.file_path.clone(),
+            file_path: self.file_path,
             is_abstract: class.r#abstract,
+            decorators: self.extract_decorators(&class.decorators),
             has_ignore_directive: has_similarity_ignore_directive(&self.source_text, start_line),
+            partial: false,
         }
     }
 
     pub fn extract_classes(&self) -> Result<Vec<ClassDefinition>, String> {
         let allocator = Allocator::default();
-        let source_type = SourceType::from_path(&self.file_path).unwrap_or(SourceType::tsx());
-        let ret = Parser::new(&allocator, &self.source_text, source_type).parse();
-
-        if !ret.errors.is_empty() {
-            let error_messages: Vec<String> =
-                ret.errors.iter().map(|e| format!("{:?}", e)).collect();
-            return Err(format!("Parse errors: {}", error_messages.join(", ")));
-        }
+        let source_type = SourceType::from_path(self.file_path).unwrap_or(SourceType::tsx());
+        let parsed = parse_tolerant(&allocator, &self.source_text, source_type)?;
 
         let mut classes = Vec::new();
 
         // Walk through all statements and find classes
-        for statement in &ret.program.body {
+        for statement in
+            parsed.program.body.iter().filter(|statement| !parsed.is_damaged(statement.span()))
+        {
             match statement {
                 Statement::ExportDefaultDeclaration(export) => {
                     if let oxc_ast::ast::ExportDefaultDeclarationKind::ClassDeclaration(class) =
@@ -436,6 +528,11 @@ impl ClassExtractor {
             }
         }
 
+        if parsed.errors.is_some() {
+            for class in &mut classes {
+                class.partial = true;
+            }
+        }
         Ok(classes)
     }
 }
@@ -444,7 +541,7 @@ pub fn extract_classes_from_code(
     code: &str,
     file_path: &str,
 ) -> Result<Vec<ClassDefinition>, String> {
-    let extractor = ClassExtractor::new(code.to_string(), file_path.to_string());
+    let extractor = ClassExtractor::new(SharedSource::from(code), file_path.to_string());
     extractor.extract_classes()
 }
 
@@ -486,4 +583,85 @@ class IgnoredService {
         let ignored = classes.iter().find(|class| class.name == "IgnoredService").unwrap();
         assert!(ignored.has_ignore_directive);
     }
+
+    #[test]
+    fn test_extract_decorators() {
+        let source = r#"
+@Component({
+    selector: 'app-user',
+    template: '<p>user</p>',
+})
+export class UserComponent {
+    @Input() name: string;
+    @ng.Output
+    changed: string;
+
+    @HostListener('click', ['$event'])
+    onClick(event: Event): void {}
+}
+"#;
+
+        let classes = extract_classes_from_code(source, "test.ts").unwrap();
+        let class = &classes[0];
+        assert_eq!(class.decorators.len(), 1);
+        assert_eq!(class.decorators[0].name, "Component");
+        assert_eq!(
+            class.decorators[0].to_string(),
+            "@Component({\n    selector: 'app-user',\n    template: '<p>user</p>',\n})"
+        );
+
+        assert_eq!(class.properties[0].decorators[0].to_string(), "@Input()");
+        assert_eq!(class.properties[1].decorators[0].to_string(), "@ng.Output");
+        assert_eq!(
+            class.methods[0].decorators[0].to_string(),
+            "@HostListener('click', ['$event'])"
+        );
+    }
+
+    #[test]
+    fn test_extract_member_accessibility() {
+        let source = r#"
+class UserService {
+    private cache: string;
+    protected retries: number;
+    #token: string;
+
+    constructor(private readonly repo: Repo, protected logger: Logger, name: string) {}
+
+    protected load(): void {}
+    #refresh(): void {}
+    run(): void {}
+}
+"#;
+
+        let classes = extract_classes_from_code(source, "test.ts").unwrap();
+        let class = &classes[0];
+
+        let properties: Vec<(&str, bool, bool, bool)> = class
+            .properties
+            .iter()
+            .map(|prop| (prop.name.as_str(), prop.is_private, prop.is_protected, prop.is_readonly))
+            .collect();
+        assert_eq!(
+            properties,
+            [
+                ("cache", true, false, false),
+                ("retries", false, true, false),
+                ("#token", true, false, false),
+                ("repo", true, false, true),
+                ("logger", false, true, false),
+            ]
+        );
+        assert_eq!(class.constructor_params.len(), 3);
+
+        let methods: Vec<(&str, bool, bool)> = class
+            .methods
+            .iter()
+            .map(|method| (method.name.as_str(), method.is_private, method.is_protected))
+            .collect();
+        assert_eq!(
+            methods,
+            [("load", false, true), ("#refresh", true, false), ("run", false, false)]
+        );
+    }
 }
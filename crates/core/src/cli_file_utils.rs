@@ -1,9 +1,36 @@
-use ignore::WalkBuilder;
+use ignore::{Walk, WalkBuilder};
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
+/// Analysis excludes in gitignore syntax, discovered per directory like `.gitignore`
+pub const IGNORE_FILE_NAME: &str = ".similarityignore";
+
+/// Walk a directory, skipping what `.gitignore` and `.similarityignore` files exclude.
+///
+/// Ignore files apply to their directory and everything below it, and the
+/// ones in parent directories of `path` apply too, so each root of a
+/// multi-root analysis honors the excludes of the repository it lives in.
+pub fn walk_dir(path: impl AsRef<Path>) -> Walk {
+    WalkBuilder::new(path).follow_links(false).add_custom_ignore_filename(IGNORE_FILE_NAME).build()
+}
+
 /// Collect files from paths with given extensions
 pub fn collect_files(paths: &[String], extensions: &[&str]) -> anyhow::Result<Vec<PathBuf>> {
+    collect_matching_files(paths, |path| {
+        path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| extensions.contains(&ext))
+    })
+}
+
+/// Collect every file from paths, whatever its extension
+pub fn collect_all_files(paths: &[String]) -> anyhow::Result<Vec<PathBuf>> {
+    collect_matching_files(paths, |_| true)
+}
+
+/// Collect files from paths for which `matches` holds
+pub fn collect_matching_files(
+    paths: &[String],
+    matches: impl Fn(&Path) -> bool,
+) -> anyhow::Result<Vec<PathBuf>> {
     let mut files = Vec::new();
     let mut visited = HashSet::new();
 
@@ -13,20 +40,16 @@ pub fn collect_files(paths: &[String], extensions: &[&str]) -> anyhow::Result<Ve
 
         if path.is_file() {
             // If it's a file, check extension and add it
-            if let Some(ext) = path.extension() {
-                if let Some(ext_str) = ext.to_str() {
-                    if extensions.contains(&ext_str) {
-                        if let Ok(canonical) = path.canonicalize() {
-                            if visited.insert(canonical.clone()) {
-                                files.push(path.to_path_buf());
-                            }
-                        }
+            if matches(path) {
+                if let Ok(canonical) = path.canonicalize() {
+                    if visited.insert(canonical.clone()) {
+                        files.push(path.to_path_buf());
                     }
                 }
             }
         } else if path.is_dir() {
-            // If it's a directory, walk it respecting .gitignore
-            let walker = WalkBuilder::new(path).follow_links(false).build();
+            // If it's a directory, walk it respecting .gitignore and .similarityignore
+            let walker = walk_dir(path);
 
             for entry in walker {
                 let entry = entry?;
@@ -38,14 +61,10 @@ pub fn collect_files(paths: &[String], extensions: &[&str]) -> anyhow::Result<Ve
                 }
 
                 // Check extension
-                if let Some(ext) = entry_path.extension() {
-                    if let Some(ext_str) = ext.to_str() {
-                        if extensions.contains(&ext_str) {
-                            if let Ok(canonical) = entry_path.canonicalize() {
-                                if visited.insert(canonical.clone()) {
-                                    files.push(entry_path.to_path_buf());
-                                }
-                            }
+                if matches(entry_path) {
+                    if let Ok(canonical) = entry_path.canonicalize() {
+                        if visited.insert(canonical.clone()) {
+                            files.push(entry_path.to_path_buf());
                         }
                     }
                 }
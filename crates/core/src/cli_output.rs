@@ -1,3 +1,7 @@
+use crate::output_schema::JsonFinding;
+use crate::quickfix::Fix;
+use crate::result_formatter::{Finding, ResultFormatter, VscodeFormatter};
+use crate::severity::Severity;
 use std::fs;
 
 /// Format function output in VSCode-compatible format
@@ -60,3 +64,72 @@ impl<T> DuplicateResult<T> {
         self.similarity * avg_size
     }
 }
+
+/// Format a finding as a single line for the default VSCode problem matcher
+///
+/// Both locations are emitted as `file:line:col` so each one can be linked.
+pub fn format_vscode_warning(
+    file_path: &str,
+    line: usize,
+    message: &str,
+    other_file: &str,
+    other_line: usize,
+) -> String {
+    format_vscode_finding(file_path, line, Severity::Warning, message, other_file, other_line)
+}
+
+/// Like [`format_vscode_warning`], with the finding's severity
+pub fn format_vscode_finding(
+    file_path: &str,
+    line: usize,
+    severity: Severity,
+    message: &str,
+    other_file: &str,
+    other_line: usize,
+) -> String {
+    let finding = Finding {
+        file: file_path,
+        line,
+        severity,
+        message,
+        related_file: other_file,
+        related_line: other_line,
+        fixes: &[],
+    };
+    VscodeFormatter.format_finding(&finding)
+}
+
+/// Format a finding as one line of JSON (see `finding_json_schema`), with the
+/// fixes that resolve it
+pub fn format_json_finding(
+    file_path: &str,
+    line: usize,
+    severity: Severity,
+    message: &str,
+    other_file: &str,
+    other_line: usize,
+    fixes: &[Fix],
+) -> String {
+    json_finding(file_path, line, severity, message, other_file, other_line, fixes).to_string()
+}
+
+/// The finding of [`format_json_finding`], for callers filling in fields of their own
+pub fn json_finding(
+    file_path: &str,
+    line: usize,
+    severity: Severity,
+    message: &str,
+    other_file: &str,
+    other_line: usize,
+    fixes: &[Fix],
+) -> JsonFinding {
+    JsonFinding::new(&Finding {
+        file: file_path,
+        line,
+        severity,
+        message,
+        related_file: other_file,
+        related_line: other_line,
+        fixes,
+    })
+}
@@ -0,0 +1,117 @@
+//! CODEOWNERS parsing and matching.
+//!
+//! Patterns follow the gitignore syntax GitHub and GitLab use for CODEOWNERS,
+//! and the last matching rule wins. Section headers (`[Section]`) and rules
+//! without owners are accepted; the latter explicitly leave a path unowned.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Locations searched for the CODEOWNERS file, relative to the repository root
+pub const CODEOWNERS_LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+#[derive(Debug)]
+struct OwnerRule {
+    matcher: Gitignore,
+    owners: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct CodeOwners {
+    root: PathBuf,
+    rules: Vec<OwnerRule>,
+}
+
+impl CodeOwners {
+    /// Find a CODEOWNERS file in `start` or the closest ancestor that has one
+    #[must_use]
+    pub fn discover(start: &Path) -> Option<Self> {
+        let start = start.canonicalize().ok()?;
+        start.ancestors().find_map(|dir| {
+            CODEOWNERS_LOCATIONS.iter().find_map(|location| {
+                let content = fs::read_to_string(dir.join(location)).ok()?;
+                Some(Self::parse(dir, &content))
+            })
+        })
+    }
+
+    /// Parse CODEOWNERS content whose patterns are relative to `root`
+    #[must_use]
+    pub fn parse(root: &Path, content: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('['))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pattern = parts.next()?;
+                let owners =
+                    parts.take_while(|part| !part.starts_with('#')).map(str::to_string).collect();
+                let mut builder = GitignoreBuilder::new(root);
+                builder.add_line(None, pattern).ok()?;
+                Some(OwnerRule { matcher: builder.build().ok()?, owners })
+            })
+            .collect();
+        Self { root: root.to_path_buf(), rules }
+    }
+
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Owners of a file; empty when no rule matches or the matching rule has no owners
+    #[must_use]
+    pub fn owners_of(&self, path: &Path) -> &[String] {
+        let absolute = path.canonicalize().unwrap_or_else(|_| self.root.join(path));
+        let Ok(relative) = absolute.strip_prefix(&self.root) else {
+            return &[];
+        };
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matcher.matched_path_or_any_parents(relative, false).is_ignore())
+            .map_or(&[], |rule| rule.owners.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODEOWNERS: &str = r#"
+# Default owners
+*                   @acme/core
+*.css               @acme/design
+/packages/web/      @acme/web @alice
+packages/api/**     @acme/api   # inline comment
+[Docs]
+docs/
+"#;
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let owners = CodeOwners::parse(Path::new("/repo"), CODEOWNERS);
+        let of = |path: &str| owners.owners_of(Path::new(path)).to_vec();
+
+        assert_eq!(of("src/index.ts"), ["@acme/core"]);
+        assert_eq!(of("src/theme.css"), ["@acme/design"]);
+        assert_eq!(of("packages/web/src/app.ts"), ["@acme/web", "@alice"]);
+        assert_eq!(of("packages/api/src/server.ts"), ["@acme/api"]);
+        assert!(of("docs/guide.md").is_empty());
+        assert!(of("/elsewhere/file.ts").is_empty());
+    }
+
+    #[test]
+    fn discovers_the_github_location() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".github")).unwrap();
+        fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        fs::write(dir.path().join(".github/CODEOWNERS"), "/src/ @acme/src\n").unwrap();
+        fs::write(dir.path().join("src/nested/a.ts"), "").unwrap();
+
+        let owners = CodeOwners::discover(&dir.path().join("src/nested")).unwrap();
+        assert_eq!(owners.owners_of(&dir.path().join("src/nested/a.ts")), ["@acme/src"]);
+    }
+}
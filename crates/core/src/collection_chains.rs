@@ -0,0 +1,311 @@
+//! Equivalent forms of collection processing in TypeScript code.
+//!
+//! With `--normalize-collections`, loops that build a collection are compared
+//! as the array method they spell out, and the array methods are compared in
+//! a form free of callee details. The rules, with `xs` any expression:
+//!
+//! - `let acc = init; for (const x of xs) { acc = acc + e; }`, or with
+//!   `acc += e` or any other compound assignment, is compared as
+//!   `let acc = xs.reduce((acc, x) => acc + e, init)`
+//! - `const out = []; for (const x of xs) { out.push(e); }` is compared as
+//!   `const out = xs.map((x) => e)`
+//! - `const out = []; for (const x of xs) { if (c) out.push(x); }` is
+//!   compared as `const out = xs.filter((x) => c)`
+//!
+//! `xs.forEach((x) => { ... })` in place of the `for...of` loop follows the
+//! same rules, and a loop followed by `return acc;` is compared as returning
+//! the method call directly. Loop bodies must be the single statement shown;
+//! index-based `for` loops, `for await` and callbacks taking an index are
+//! left as written.
+
+use crate::parser::{callback, parameter_name, Callback};
+use oxc_ast::ast::{
+    AssignmentOperator, AssignmentTarget, BinaryOperator, BindingPattern, CallExpression,
+    Expression, ForStatementLeft, Statement,
+};
+
+/// The array method a collection call or loop amounts to
+pub(crate) enum CollectionStep<'a, 's> {
+    Map {
+        item: &'s str,
+        value: &'s Expression<'a>,
+    },
+    Filter {
+        item: &'s str,
+        test: &'s Expression<'a>,
+    },
+    Reduce {
+        accumulator: &'s str,
+        item: &'s str,
+        body: ReduceBody<'a, 's>,
+        initial: &'s Expression<'a>,
+    },
+}
+
+impl CollectionStep<'_, '_> {
+    pub(crate) fn method(&self) -> &'static str {
+        match self {
+            CollectionStep::Map { .. } => "map",
+            CollectionStep::Filter { .. } => "filter",
+            CollectionStep::Reduce { .. } => "reduce",
+        }
+    }
+}
+
+/// What a reduction callback returns
+pub(crate) enum ReduceBody<'a, 's> {
+    /// The callback's own expression
+    Expression(&'s Expression<'a>),
+    /// `accumulator <operator> value`, from an assignment in a loop
+    Combine(BinaryOperator, &'s Expression<'a>),
+}
+
+/// `source.<method>(callback)`
+pub(crate) struct CollectionCall<'a, 's> {
+    pub source: &'s Expression<'a>,
+    pub step: CollectionStep<'a, 's>,
+}
+
+/// Statements building `result` with a loop, amounting to a collection call
+pub(crate) struct CollectionLoop<'a, 's> {
+    pub result: &'s str,
+    pub call: CollectionCall<'a, 's>,
+    /// Whether the loop is followed by `return result;`, folded in as well
+    pub returned: bool,
+}
+
+impl CollectionLoop<'_, '_> {
+    /// Number of statements the loop replaces
+    pub(crate) fn statement_count(&self) -> usize {
+        if self.returned {
+            3
+        } else {
+            2
+        }
+    }
+}
+
+/// `xs.map(cb)`, `xs.filter(cb)` or `xs.reduce(cb, init)` with a callback of
+/// the plain form the rules produce
+pub(crate) fn collection_call<'a, 's>(
+    call: &'s CallExpression<'a>,
+) -> Option<CollectionCall<'a, 's>> {
+    let Expression::StaticMemberExpression(member) = &call.callee else {
+        return None;
+    };
+    let callback = callback(call.arguments.first()?.as_expression()?)?;
+    let value = callback_value(&callback)?;
+    let step = match (member.property.name.as_str(), callback.params, call.arguments.len()) {
+        ("map", [item], 1) => CollectionStep::Map { item: parameter_name(item)?, value },
+        ("filter", [item], 1) => {
+            CollectionStep::Filter { item: parameter_name(item)?, test: value }
+        }
+        ("reduce", [accumulator, item], 2) => CollectionStep::Reduce {
+            accumulator: parameter_name(accumulator)?,
+            item: parameter_name(item)?,
+            body: ReduceBody::Expression(value),
+            initial: call.arguments[1].as_expression()?,
+        },
+        _ => return None,
+    };
+    Some(CollectionCall { source: &member.object, step })
+}
+
+/// The loop at the start of `statements` with the declaration of its result
+pub(crate) fn collection_loop<'a, 's>(
+    statements: &'s [Statement<'a>],
+) -> Option<CollectionLoop<'a, 's>> {
+    let [Statement::VariableDeclaration(declaration), looped, rest @ ..] = statements else {
+        return None;
+    };
+    let [declarator] = declaration.declarations.as_slice() else {
+        return None;
+    };
+    let BindingPattern::BindingIdentifier(result) = &declarator.id else {
+        return None;
+    };
+    let (result, initial) = (result.name.as_str(), declarator.init.as_ref()?);
+    let (source, item, body) = iteration(looped)?;
+    let [statement] = body else {
+        return None;
+    };
+
+    let step = if let Some(value) = pushed_value(statement, result) {
+        is_empty_array(initial).then_some(CollectionStep::Map { item, value })?
+    } else if let Statement::IfStatement(if_stmt) = statement {
+        let [pushed] = statements_of(&if_stmt.consequent) else {
+            return None;
+        };
+        let value = pushed_value(pushed, result)?;
+        let pushes_item = matches!(value, Expression::Identifier(ident) if ident.name == item);
+        (if_stmt.alternate.is_none() && pushes_item && is_empty_array(initial))
+            .then_some(CollectionStep::Filter { item, test: &if_stmt.test })?
+    } else {
+        let (operator, value) = accumulation(statement, result)?;
+        CollectionStep::Reduce {
+            accumulator: result,
+            item,
+            body: ReduceBody::Combine(operator, value),
+            initial,
+        }
+    };
+
+    let returned = matches!(
+        rest.first(),
+        Some(Statement::ReturnStatement(ret))
+            if matches!(&ret.argument, Some(Expression::Identifier(ident)) if ident.name == result)
+    );
+    Some(CollectionLoop { result, call: CollectionCall { source, step }, returned })
+}
+
+/// The collection, item name and body of `for (const x of xs)` or `xs.forEach((x) => ...)`
+fn iteration<'a, 's>(
+    statement: &'s Statement<'a>,
+) -> Option<(&'s Expression<'a>, &'s str, &'s [Statement<'a>])> {
+    match statement {
+        Statement::ForOfStatement(for_of) if !for_of.r#await => {
+            let ForStatementLeft::VariableDeclaration(declaration) = &for_of.left else {
+                return None;
+            };
+            let [declarator] = declaration.declarations.as_slice() else {
+                return None;
+            };
+            let BindingPattern::BindingIdentifier(item) = &declarator.id else {
+                return None;
+            };
+            Some((&for_of.right, item.name.as_str(), statements_of(&for_of.body)))
+        }
+        Statement::ExpressionStatement(expr_stmt) => {
+            let Expression::CallExpression(call) = &expr_stmt.expression else {
+                return None;
+            };
+            let Expression::StaticMemberExpression(member) = &call.callee else {
+                return None;
+            };
+            if member.property.name != "forEach" || call.arguments.len() != 1 {
+                return None;
+            }
+            let callback = callback(call.arguments[0].as_expression()?)?;
+            let [item] = callback.params else {
+                return None;
+            };
+            Some((&member.object, parameter_name(item)?, &callback.body.statements))
+        }
+        _ => None,
+    }
+}
+
+/// The statements of a block, or the statement itself
+fn statements_of<'a, 's>(statement: &'s Statement<'a>) -> &'s [Statement<'a>] {
+    match statement {
+        Statement::BlockStatement(block) => &block.body,
+        other => std::slice::from_ref(other),
+    }
+}
+
+/// The value a callback returns, from an expression body or a lone `return`
+fn callback_value<'a, 's>(callback: &Callback<'a, 's>) -> Option<&'s Expression<'a>> {
+    match callback.body.statements.as_slice() {
+        [Statement::ExpressionStatement(expr_stmt)] if callback.expression => {
+            Some(&expr_stmt.expression)
+        }
+        [Statement::ReturnStatement(ret)] => ret.argument.as_ref(),
+        _ => None,
+    }
+}
+
+/// `e` of `result.push(e);`
+fn pushed_value<'a, 's>(statement: &'s Statement<'a>, result: &str) -> Option<&'s Expression<'a>> {
+    let Statement::ExpressionStatement(expr_stmt) = statement else {
+        return None;
+    };
+    let Expression::CallExpression(call) = &expr_stmt.expression else {
+        return None;
+    };
+    let Expression::StaticMemberExpression(member) = &call.callee else {
+        return None;
+    };
+    let is_result = matches!(&member.object, Expression::Identifier(ident) if ident.name == result);
+    match call.arguments.as_slice() {
+        [argument] if is_result && member.property.name == "push" => argument.as_expression(),
+        _ => None,
+    }
+}
+
+/// Operator and value of `result = result <op> e;` or `result <op>= e;`
+fn accumulation<'a, 's>(
+    statement: &'s Statement<'a>,
+    result: &str,
+) -> Option<(BinaryOperator, &'s Expression<'a>)> {
+    let Statement::ExpressionStatement(expr_stmt) = statement else {
+        return None;
+    };
+    let Expression::AssignmentExpression(assign) = &expr_stmt.expression else {
+        return None;
+    };
+    match &assign.left {
+        AssignmentTarget::AssignmentTargetIdentifier(ident) if ident.name == result => {}
+        _ => return None,
+    }
+    if assign.operator != AssignmentOperator::Assign {
+        return Some((assign.operator.to_binary_operator()?, &assign.right));
+    }
+    let Expression::BinaryExpression(binary) = &assign.right else {
+        return None;
+    };
+    match &binary.left {
+        Expression::Identifier(ident) if ident.name == result => {
+            Some((binary.operator, &binary.right))
+        }
+        _ => None,
+    }
+}
+
+fn is_empty_array(expr: &Expression) -> bool {
+    matches!(expr, Expression::ArrayExpression(array) if array.elements.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_and_convert_to_tree_with, TreeConversion};
+    use crate::tree::TreeNode;
+
+    fn shape(code: &str) -> String {
+        fn render(node: &TreeNode) -> String {
+            let children: Vec<String> = node.children.iter().map(|child| render(child)).collect();
+            format!("{}:{}({})", node.value, node.label, children.join(","))
+        }
+        let conversion =
+            TreeConversion { normalize_collections: true, ..TreeConversion::default() };
+        let wrapped = format!("function f() {{\n{code}\n}}");
+        render(&parse_and_convert_to_tree_with("test.ts", &wrapped, conversion).unwrap())
+    }
+
+    #[test]
+    fn test_loops_fold_into_the_method_they_spell_out() {
+        assert_eq!(
+            shape("let sum = 0;\nfor (const x of xs) { sum = sum + x; }\nreturn sum;"),
+            shape("return xs.reduce((sum, x) => sum + x, 0);")
+        );
+        assert_eq!(
+            shape("const out = [];\nxs.forEach((x) => out.push(f(x)));"),
+            shape("const out = xs.map((x) => f(x));")
+        );
+        assert_eq!(
+            shape("const out = [];\nfor (const x of xs) if (ok(x)) out.push(x);"),
+            shape("const out = xs.filter((x) => ok(x));")
+        );
+    }
+
+    #[test]
+    fn test_loops_outside_the_rules_are_kept() {
+        // Two statements in the body, a transformed value in a filter, an index parameter
+        for code in [
+            "const out = [];\nfor (const x of xs) { log(x); out.push(x); }",
+            "const out = [];\nfor (const x of xs) { if (ok(x)) out.push(f(x)); }",
+            "const out = [];\nxs.forEach((x, i) => out.push(i));",
+        ] {
+            assert!(!shape(code).contains("CollectionCall"), "{code}");
+        }
+    }
+}
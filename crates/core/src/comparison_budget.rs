@@ -0,0 +1,357 @@
+//! Time and comparison budget for the pairwise comparison loops.
+//!
+//! Comparison loops ask the budget before every detailed comparison and stop
+//! once the deadline has passed or the comparison cap is reached, so a
+//! bounded run still yields the results found so far. Candidate pairs are
+//! counted even after that, which lets the caller report how much of the
+//! search space was covered.
+//!
+//! Under a comparison cap, candidate lists known up front are sampled instead
+//! of cut off: pairs sharing a locality bucket are all kept, and the other
+//! pairs are thinned out evenly to fit the remaining comparisons.
+//!
+//! A budget restricted to a [`Shard`] only runs the comparisons the shard
+//! owns. Work is assigned by a stable hash of its key, so every job of a
+//! sharded CI run splits the same candidates the same way and together the
+//! jobs compare each pair exactly once.
+
+use crate::function_extractor::FunctionDefinition;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use xxhash_rust::xxh3::{xxh3_64, xxh3_64_with_seed};
+
+#[derive(Debug, Default)]
+pub struct ComparisonBudget {
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    max_comparisons: Option<usize>,
+    candidates: AtomicUsize,
+    compared: AtomicUsize,
+    /// Candidate pairs dropped by sampling
+    sampled_out: AtomicUsize,
+    shard: Option<Shard>,
+}
+
+/// One of `count` deterministic partitions of the comparisons, written
+/// `index/count` with `index` counted from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Shard {
+    /// The shard also running the analyses that are not partitioned
+    #[must_use]
+    pub fn is_first(&self) -> bool {
+        self.index == 1
+    }
+
+    /// Whether the work identified by `key` (see [`shard_key`]) belongs to this shard
+    #[must_use]
+    pub fn owns(&self, key: u64) -> bool {
+        key % self.count as u64 == (self.index - 1) as u64
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid shard {text:?} (use INDEX/COUNT, e.g. 2/8)");
+        let (index, count) = text.trim().split_once('/').ok_or_else(invalid)?;
+        let index: usize = index.trim().parse().map_err(|_| invalid())?;
+        let count: usize = count.trim().parse().map_err(|_| invalid())?;
+        if count == 0 || index == 0 || index > count {
+            return Err(format!("shard index must be between 1 and {count}, got {text:?}"));
+        }
+        Ok(Self { index, count })
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// Stable key of a unit of work, such as a file path, for [`Shard::owns`]
+#[must_use]
+pub fn shard_key(text: &str) -> u64 {
+    xxh3_64(text.as_bytes())
+}
+
+/// Key of the pair of the units keyed `first` and `second`, in either order
+#[must_use]
+pub fn pair_shard_key(first: u64, second: u64) -> u64 {
+    let (low, high) = if first <= second { (first, second) } else { (second, first) };
+    xxh3_64_with_seed(&low.to_le_bytes(), high)
+}
+
+/// How many candidate pairs were compared before the budget ran out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coverage {
+    pub compared: usize,
+    pub candidates: usize,
+}
+
+impl Coverage {
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.compared >= self.candidates
+    }
+
+    #[must_use]
+    pub fn percent(&self) -> f64 {
+        if self.candidates == 0 {
+            100.0
+        } else {
+            self.compared as f64 / self.candidates as f64 * 100.0
+        }
+    }
+}
+
+impl ComparisonBudget {
+    /// A budget that never runs out
+    #[must_use]
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// A budget ending `timeout` from now
+    #[must_use]
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self { timeout: Some(timeout), deadline: Some(Instant::now() + timeout), ..Self::default() }
+    }
+
+    /// The same budget, also allowing at most `max` detailed comparisons
+    #[must_use]
+    pub fn with_max_comparisons(self, max: usize) -> Self {
+        Self { max_comparisons: Some(max), ..self }
+    }
+
+    /// The same budget, only running the comparisons `shard` owns
+    #[must_use]
+    pub fn with_shard(self, shard: Shard) -> Self {
+        Self { shard: Some(shard), ..self }
+    }
+
+    #[must_use]
+    pub fn shard(&self) -> Option<Shard> {
+        self.shard
+    }
+
+    /// Whether the work keyed `key` is run under this budget; always true
+    /// without a shard
+    #[must_use]
+    pub fn in_shard(&self, key: u64) -> bool {
+        self.shard.is_none_or(|shard| shard.owns(key))
+    }
+
+    #[must_use]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    #[must_use]
+    pub fn max_comparisons(&self) -> Option<usize> {
+        self.max_comparisons
+    }
+
+    #[must_use]
+    pub fn is_exhausted(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            || self.max_comparisons.is_some_and(|max| self.compared.load(Ordering::Relaxed) >= max)
+    }
+
+    /// Fit candidate `pairs` of items in `buckets` into the comparisons left:
+    /// pairs within a bucket are kept first, and the rest are sampled evenly.
+    /// Dropped pairs stay registered as candidates.
+    pub fn sample_pairs(&self, pairs: &mut Vec<(usize, usize)>, buckets: &[u64]) {
+        self.add_candidates(pairs.len());
+        let Some(max) = self.max_comparisons else {
+            return;
+        };
+        let remaining = max.saturating_sub(self.compared.load(Ordering::Relaxed));
+        if pairs.len() <= remaining {
+            return;
+        }
+
+        let (mut kept, rest): (Vec<_>, Vec<_>) =
+            pairs.iter().partition(|&&(i, j)| buckets[i] == buckets[j]);
+        kept.truncate(remaining);
+        let slots = remaining - kept.len();
+        if slots > 0 {
+            // Evenly spaced picks, so every part of the candidate list is represented
+            kept.extend((0..slots).map(|pick| rest[pick * rest.len() / slots]));
+        }
+        self.sampled_out.fetch_add(pairs.len() - kept.len(), Ordering::Relaxed);
+        *pairs = kept;
+    }
+
+    /// Register candidate pairs that will be offered through `start_comparison`
+    pub fn add_candidates(&self, count: usize) {
+        self.candidates.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Whether a registered candidate pair may still be compared
+    pub fn start_comparison(&self) -> bool {
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return false;
+        }
+        let max = self.max_comparisons.unwrap_or(usize::MAX);
+        self.compared
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |compared| {
+                (compared < max).then_some(compared + 1)
+            })
+            .is_ok()
+    }
+
+    /// Count registered candidate pairs as compared without offering them, for
+    /// pairs whose results an earlier run recorded
+    pub fn mark_compared(&self, count: usize) {
+        self.compared.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Register one candidate pair and check whether it may be compared
+    pub fn try_compare(&self) -> bool {
+        self.add_candidates(1);
+        self.start_comparison()
+    }
+
+    #[must_use]
+    pub fn coverage(&self) -> Coverage {
+        Coverage {
+            compared: self.compared.load(Ordering::Relaxed),
+            candidates: self.candidates.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Number of candidate pairs dropped by [`Self::sample_pairs`]
+    #[must_use]
+    pub fn sampled_out(&self) -> usize {
+        self.sampled_out.load(Ordering::Relaxed)
+    }
+}
+
+/// Parse durations like `300s`, `5m`, `1h`, `500ms` or a bare number of seconds
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let value: f64 = number.parse().map_err(|_| format!("invalid duration: {text:?}"))?;
+    let seconds = match unit.trim() {
+        "" | "s" | "sec" | "secs" => value,
+        "ms" => value / 1000.0,
+        "m" | "min" | "mins" => value * 60.0,
+        "h" => value * 3600.0,
+        other => return Err(format!("unknown duration unit {other:?} (use ms, s, m or h)")),
+    };
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// Coarse locality bucket of a function: its size band and arity.
+///
+/// Functions far apart in size cannot pass the similarity threshold, so likely
+/// duplicates share a bucket.
+#[must_use]
+pub fn locality_bucket(function: &FunctionDefinition) -> u64 {
+    let size = u64::from(function.node_count.unwrap_or_else(|| function.line_count()).max(1));
+    let size_band = u64::from(size.ilog2());
+    (size_band << 8) | function.parameters.len().min(255) as u64
+}
+
+/// Order pairs so that those within the densest shared bucket are compared first.
+///
+/// Pairs spanning two buckets keep their relative order after all same-bucket pairs.
+pub fn prioritize_by_bucket_density(pairs: &mut [(usize, usize)], buckets: &[u64]) {
+    let mut density: HashMap<u64, usize> = HashMap::new();
+    for bucket in buckets {
+        *density.entry(*bucket).or_default() += 1;
+    }
+    pairs.sort_by_key(|&(i, j)| {
+        if buckets[i] == buckets[j] {
+            std::cmp::Reverse(density[&buckets[i]])
+        } else {
+            std::cmp::Reverse(0)
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("300s"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("42"), Ok(Duration::from_secs(42)));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("3d").is_err());
+    }
+
+    #[test]
+    fn exhausted_budget_counts_but_refuses_comparisons() {
+        let budget = ComparisonBudget::with_timeout(Duration::ZERO);
+        assert!(!budget.try_compare());
+        budget.add_candidates(3);
+        let coverage = budget.coverage();
+        assert_eq!(coverage, Coverage { compared: 0, candidates: 4 });
+        assert!(!coverage.is_complete());
+
+        let unlimited = ComparisonBudget::unlimited();
+        assert!(unlimited.try_compare());
+        assert!(unlimited.coverage().is_complete());
+        assert_eq!(unlimited.coverage().percent(), 100.0);
+    }
+
+    #[test]
+    fn capped_budget_keeps_same_bucket_pairs_and_samples_the_rest() {
+        let budget = ComparisonBudget::unlimited().with_max_comparisons(4);
+        assert!(budget.try_compare());
+
+        let buckets = [1, 1, 1, 2, 3, 4];
+        let mut pairs = vec![(0, 1), (0, 2), (0, 3), (0, 4), (1, 3), (1, 5), (3, 4), (4, 5)];
+        budget.sample_pairs(&mut pairs, &buckets);
+        assert_eq!(pairs, vec![(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(budget.sampled_out(), 5);
+
+        assert!(pairs.iter().all(|_| budget.start_comparison()));
+        assert!(!budget.try_compare());
+        assert_eq!(budget.coverage(), Coverage { compared: 4, candidates: 10 });
+    }
+
+    #[test]
+    fn shards_partition_work() {
+        assert_eq!("2/8".parse(), Ok(Shard { index: 2, count: 8 }));
+        assert_eq!(Shard { index: 2, count: 8 }.to_string(), "2/8");
+        assert!("0/8".parse::<Shard>().is_err());
+        assert!("9/8".parse::<Shard>().is_err());
+        assert!("2".parse::<Shard>().is_err());
+
+        let shards: Vec<ComparisonBudget> = (1..=3)
+            .map(|index| ComparisonBudget::unlimited().with_shard(Shard { index, count: 3 }))
+            .collect();
+        let keys: Vec<u64> = (0..100).map(|n| shard_key(&format!("src/file{n}.ts"))).collect();
+        for &key in &keys {
+            assert_eq!(shards.iter().filter(|budget| budget.in_shard(key)).count(), 1);
+        }
+        assert!(shards.iter().all(|budget| keys.iter().any(|&key| budget.in_shard(key))));
+        assert_eq!(pair_shard_key(keys[0], keys[1]), pair_shard_key(keys[1], keys[0]));
+        assert!(ComparisonBudget::unlimited().in_shard(keys[0]));
+    }
+
+    #[test]
+    fn dense_buckets_come_first() {
+        let buckets = [1, 2, 2, 2, 1];
+        let mut pairs = vec![(0, 1), (0, 4), (1, 2), (3, 4), (2, 3)];
+        prioritize_by_bucket_density(&mut pairs, &buckets);
+        assert_eq!(pairs, vec![(1, 2), (2, 3), (0, 4), (0, 1), (3, 4)]);
+    }
+}
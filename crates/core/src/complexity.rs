@@ -0,0 +1,193 @@
+//! Cyclomatic complexity of TypeScript/JavaScript function bodies.
+//!
+//! The count starts at 1 and adds one for every branch point: `if`, loops,
+//! `case` labels, `catch`, `?:` and the short-circuit operators `&&`, `||`
+//! and `??`. Nested function bodies are skipped since they are extracted
+//! and measured as functions of their own.
+
+use oxc_ast::ast::*;
+
+/// Cyclomatic complexity of a function body
+#[must_use]
+pub fn cyclomatic_complexity(body: &FunctionBody) -> u32 {
+    1 + body.statements.iter().map(statement_branches).sum::<u32>()
+}
+
+fn statement_branches(stmt: &Statement) -> u32 {
+    match stmt {
+        Statement::BlockStatement(block) => block.body.iter().map(statement_branches).sum(),
+        Statement::IfStatement(stmt) => {
+            1 + expression_branches(&stmt.test)
+                + statement_branches(&stmt.consequent)
+                + stmt.alternate.as_ref().map_or(0, statement_branches)
+        }
+        Statement::ForStatement(stmt) => {
+            let init = match &stmt.init {
+                Some(ForStatementInit::VariableDeclaration(decl)) => declaration_branches(decl),
+                Some(init) => init.as_expression().map_or(0, expression_branches),
+                None => 0,
+            };
+            1 + init
+                + stmt.test.as_ref().map_or(0, expression_branches)
+                + stmt.update.as_ref().map_or(0, expression_branches)
+                + statement_branches(&stmt.body)
+        }
+        Statement::ForInStatement(stmt) => {
+            1 + expression_branches(&stmt.right) + statement_branches(&stmt.body)
+        }
+        Statement::ForOfStatement(stmt) => {
+            1 + expression_branches(&stmt.right) + statement_branches(&stmt.body)
+        }
+        Statement::WhileStatement(stmt) => {
+            1 + expression_branches(&stmt.test) + statement_branches(&stmt.body)
+        }
+        Statement::DoWhileStatement(stmt) => {
+            1 + expression_branches(&stmt.test) + statement_branches(&stmt.body)
+        }
+        Statement::SwitchStatement(stmt) => {
+            expression_branches(&stmt.discriminant)
+                + stmt
+                    .cases
+                    .iter()
+                    .map(|case| {
+                        u32::from(case.test.is_some())
+                            + case.test.as_ref().map_or(0, expression_branches)
+                            + case.consequent.iter().map(statement_branches).sum::<u32>()
+                    })
+                    .sum::<u32>()
+        }
+        Statement::TryStatement(stmt) => {
+            let handler = stmt.handler.as_ref().map_or(0, |handler| {
+                1 + handler.body.body.iter().map(statement_branches).sum::<u32>()
+            });
+            stmt.block.body.iter().map(statement_branches).sum::<u32>()
+                + handler
+                + stmt
+                    .finalizer
+                    .as_ref()
+                    .map_or(0, |block| block.body.iter().map(statement_branches).sum())
+        }
+        Statement::LabeledStatement(stmt) => statement_branches(&stmt.body),
+        Statement::ReturnStatement(stmt) => stmt.argument.as_ref().map_or(0, expression_branches),
+        Statement::ThrowStatement(stmt) => expression_branches(&stmt.argument),
+        Statement::ExpressionStatement(stmt) => expression_branches(&stmt.expression),
+        Statement::VariableDeclaration(decl) => declaration_branches(decl),
+        _ => 0,
+    }
+}
+
+fn declaration_branches(decl: &VariableDeclaration) -> u32 {
+    decl.declarations.iter().filter_map(|d| d.init.as_ref()).map(expression_branches).sum()
+}
+
+fn expression_branches(expr: &Expression) -> u32 {
+    match expr {
+        Expression::ConditionalExpression(expr) => {
+            1 + expression_branches(&expr.test)
+                + expression_branches(&expr.consequent)
+                + expression_branches(&expr.alternate)
+        }
+        Expression::LogicalExpression(expr) => {
+            1 + expression_branches(&expr.left) + expression_branches(&expr.right)
+        }
+        Expression::AssignmentExpression(expr) => {
+            u32::from(expr.operator.is_logical()) + expression_branches(&expr.right)
+        }
+        Expression::BinaryExpression(expr) => {
+            expression_branches(&expr.left) + expression_branches(&expr.right)
+        }
+        Expression::UnaryExpression(expr) => expression_branches(&expr.argument),
+        Expression::AwaitExpression(expr) => expression_branches(&expr.argument),
+        Expression::ParenthesizedExpression(expr) => expression_branches(&expr.expression),
+        Expression::SequenceExpression(expr) => {
+            expr.expressions.iter().map(expression_branches).sum()
+        }
+        Expression::CallExpression(expr) => {
+            expression_branches(&expr.callee) + arguments_branches(&expr.arguments)
+        }
+        Expression::NewExpression(expr) => {
+            expression_branches(&expr.callee) + arguments_branches(&expr.arguments)
+        }
+        Expression::StaticMemberExpression(expr) => expression_branches(&expr.object),
+        Expression::ComputedMemberExpression(expr) => {
+            expression_branches(&expr.object) + expression_branches(&expr.expression)
+        }
+        Expression::ArrayExpression(expr) => expr
+            .elements
+            .iter()
+            .filter_map(ArrayExpressionElement::as_expression)
+            .map(expression_branches)
+            .sum(),
+        Expression::ObjectExpression(expr) => expr
+            .properties
+            .iter()
+            .map(|property| match property {
+                ObjectPropertyKind::ObjectProperty(property) => {
+                    expression_branches(&property.value)
+                }
+                ObjectPropertyKind::SpreadProperty(spread) => expression_branches(&spread.argument),
+            })
+            .sum(),
+        Expression::TemplateLiteral(expr) => expr.expressions.iter().map(expression_branches).sum(),
+        _ => 0,
+    }
+}
+
+fn arguments_branches(arguments: &[Argument]) -> u32 {
+    arguments.iter().filter_map(Argument::as_expression).map(expression_branches).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::function_extractor::extract_functions;
+
+    fn complexity_of(code: &str) -> u32 {
+        extract_functions("test.ts", code).unwrap()[0].complexity
+    }
+
+    #[test]
+    fn test_straight_line_function_has_complexity_one() {
+        assert_eq!(complexity_of("function get(o) { return o.value; }"), 1);
+    }
+
+    #[test]
+    fn test_branches_are_counted() {
+        let code = r#"
+            function classify(items) {
+                let total = 0;
+                for (const item of items) {
+                    if (item.active && item.count > 0) {
+                        total += item.count;
+                    } else if (item.fallback ?? false) {
+                        total += 1;
+                    }
+                }
+                switch (total) {
+                    case 0: return "none";
+                    case 1: return "one";
+                    default: return total > 10 ? "many" : "some";
+                }
+            }
+        "#;
+        // 1 + for + if + && + else-if + ?? + 2 cases + ?:
+        assert_eq!(complexity_of(code), 9);
+    }
+
+    #[test]
+    fn test_nested_functions_are_measured_separately() {
+        let code = r#"
+            function outer(x) {
+                function inner(y) {
+                    if (y) { return 1; }
+                    return 0;
+                }
+                return inner(x);
+            }
+        "#;
+        let functions = crate::function_extractor::extract_functions("test.ts", code).unwrap();
+        let outer = functions.iter().find(|f| f.name == "outer").unwrap();
+        let inner = functions.iter().find(|f| f.name == "inner").unwrap();
+        assert_eq!(outer.complexity, 1);
+        assert_eq!(inner.complexity, 2);
+    }
+}
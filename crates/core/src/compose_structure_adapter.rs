@@ -0,0 +1,382 @@
+//! Duplicate detection for docker-compose files.
+//!
+//! Every entry under `services` becomes a structure whose members are its
+//! keys, with nested mappings flattened into dotted paths. Equivalent forms
+//! are normalized before comparing: `environment`, `labels` and `build.args`
+//! become one member per variable whether written as a list (`KEY=value`) or
+//! a mapping, the short and long forms of `depends_on` compare equal, image
+//! tags are dropped and other values are compared by category like config
+//! blocks.
+
+use crate::analyzer::{AnalysisUnit, Analyzer, AnalyzerError, AnalyzerFinding};
+use crate::cli_output::format_function_output;
+use crate::config_parser::{parse_yaml, ConfigNode, ConfigValue};
+use crate::config_structure_adapter::categorize_config_value;
+use crate::structure_comparator::{
+    ComparisonOptions, MemberComparisonStrategy, SourceLocation, Structure, StructureComparator,
+    StructureIdentifier, StructureKind, StructureMember, StructureMetadata,
+};
+use crate::tree::TreeNode;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Keys holding `KEY=value` lists or mappings, compared by their keys
+const VARIABLE_KEYS: &[&str] = &["environment", "labels", "build.args", "deploy.labels"];
+
+/// Keys whose entries are compared as one unordered list, in either form
+const LIST_KEYS: &[&str] =
+    &["depends_on", "ports", "volumes", "networks", "env_file", "secrets", "configs"];
+
+/// A compose service with its keys flattened
+#[derive(Debug, Clone)]
+pub struct ComposeService {
+    pub name: String,
+    pub file_path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Key paths and their normalized values
+    pub members: Vec<(String, String)>,
+}
+
+impl From<ComposeService> for Structure {
+    fn from(service: ComposeService) -> Self {
+        let members = service
+            .members
+            .into_iter()
+            .map(|(name, value_type)| StructureMember {
+                name,
+                value_type: value_type.into(),
+                modifiers: vec![],
+                nested: None,
+            })
+            .collect();
+
+        Structure {
+            identifier: StructureIdentifier {
+                name: service.name.into(),
+                kind: StructureKind::Generic("service".to_string()),
+                namespace: Some(service.file_path.as_str().into()),
+            },
+            members,
+            metadata: StructureMetadata {
+                location: SourceLocation {
+                    file_path: service.file_path.into(),
+                    start_line: service.start_line,
+                    end_line: service.end_line,
+                },
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Parse a compose file into its services
+pub fn extract_compose_services(
+    source: &str,
+    file_path: &str,
+) -> Result<Vec<ComposeService>, String> {
+    let mut services = Vec::new();
+    for document in parse_yaml(source)? {
+        let ConfigValue::Mapping(entries) = &document.value else { continue };
+        let Some((_, node)) = entries.iter().find(|(key, _)| key == "services") else { continue };
+        let ConfigValue::Mapping(definitions) = &node.value else { continue };
+
+        for (name, definition) in definitions {
+            let mut members = Vec::new();
+            flatten_service(definition, "", &mut members);
+            services.push(ComposeService {
+                name: name.clone(),
+                file_path: file_path.to_string(),
+                start_line: definition.start_line,
+                end_line: definition.end_line,
+                members,
+            });
+        }
+    }
+    Ok(services)
+}
+
+fn flatten_service(node: &ConfigNode, prefix: &str, members: &mut Vec<(String, String)>) {
+    let ConfigValue::Mapping(entries) = &node.value else { return };
+    for (key, value) in entries {
+        // `x-` extension fields and merge keys are how services share settings
+        if key.starts_with("x-") || key == "<<" {
+            continue;
+        }
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+
+        if VARIABLE_KEYS.contains(&path.as_str()) {
+            let mut variables = variable_names(value);
+            variables.sort_unstable();
+            members.extend(
+                variables
+                    .into_iter()
+                    .map(|name| (format!("{}.{}", path, name), "variable".to_string())),
+            );
+        } else if LIST_KEYS.contains(&path.as_str()) {
+            members.push((path, "list".to_string()));
+        } else if path == "image" {
+            let image = match &value.value {
+                ConfigValue::String(image) => image_without_tag(image),
+                _ => "",
+            };
+            members.push((path, format!("image {}", image)));
+        } else if matches!(value.value, ConfigValue::Mapping(_)) {
+            flatten_service(value, &path, members);
+        } else {
+            members.push((path, categorize_config_value(&value.value)));
+        }
+    }
+}
+
+/// Names of a `KEY=value` list or a mapping
+fn variable_names(node: &ConfigNode) -> Vec<String> {
+    match &node.value {
+        ConfigValue::Mapping(entries) => entries.iter().map(|(key, _)| key.clone()).collect(),
+        ConfigValue::Sequence(items) => items
+            .iter()
+            .filter_map(|item| match &item.value {
+                ConfigValue::String(text) => {
+                    Some(text.split('=').next().unwrap_or(text).trim().to_string())
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn image_without_tag(image: &str) -> &str {
+    let image = image.split('@').next().unwrap_or(image);
+    match image.rfind(':') {
+        Some(colon) if image.rfind('/').is_none_or(|slash| colon > slash) => &image[..colon],
+        _ => image,
+    }
+}
+
+fn service_tree(service: &ComposeService) -> TreeNode {
+    let mut tree = TreeNode::new("service".to_string(), String::new(), 0);
+    for (id, (name, value_type)) in service.members.iter().enumerate() {
+        tree.add_child(Rc::new(TreeNode::new(name.clone(), value_type.clone(), id + 1)));
+    }
+    tree
+}
+
+/// Analyzer for docker-compose files, comparing services
+pub struct ComposeAnalyzer {
+    min_members: usize,
+    comparator: RefCell<StructureComparator>,
+}
+
+impl ComposeAnalyzer {
+    pub fn new() -> Self {
+        let options = ComparisonOptions {
+            name_weight: 0.1,      // Copied services get new names
+            structure_weight: 0.9, // Keys and their normalized values
+            member_comparison: MemberComparisonStrategy::Exact,
+            ignore_order: true,
+            ..Default::default()
+        };
+        Self::with_options(options, 3)
+    }
+
+    /// Services with fewer than `min_members` keys after normalization are not compared
+    pub fn with_options(options: ComparisonOptions, min_members: usize) -> Self {
+        Self { min_members, comparator: RefCell::new(StructureComparator::new(options)) }
+    }
+}
+
+impl Default for ComposeAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Analyzer for ComposeAnalyzer {
+    fn name(&self) -> &str {
+        "compose"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["yml", "yaml"]
+    }
+
+    /// `docker-compose.yml`, `compose.yaml` and overrides such as `docker-compose.prod.yml`
+    fn matches_file(&self, path: &Path) -> bool {
+        let is_yaml = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| self.extensions().contains(&extension));
+        let is_compose = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("docker-compose") || name.starts_with("compose"));
+        is_yaml && is_compose
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let services = extract_compose_services(source, file)
+            .map_err(|e| format!("Failed to parse {}: {}", file, e))?;
+        Ok(services
+            .into_iter()
+            .filter(|service| service.members.len() >= self.min_members)
+            .map(|service| AnalysisUnit {
+                name: service.name.clone(),
+                file: file.to_string(),
+                start_line: service.start_line as u32,
+                end_line: service.end_line as u32,
+                tree: Rc::new(service_tree(&service)),
+                structure: Some(Structure::from(service)),
+            })
+            .collect())
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        match (&first.structure, &second.structure) {
+            (Some(s1), Some(s2)) => self.comparator.borrow_mut().compare(s1, s2).overall_similarity,
+            _ => 0.0,
+        }
+    }
+
+    fn report(&self, findings: &[AnalyzerFinding], out: &mut dyn Write) -> io::Result<()> {
+        if findings.is_empty() {
+            return writeln!(out, "No duplicated compose services found.");
+        }
+
+        writeln!(out, "Duplicated compose services:")?;
+        writeln!(out, "{}", "-".repeat(60))?;
+        for finding in findings {
+            let (first, second) = (&finding.first, &finding.second);
+            writeln!(out, "\nSimilarity: {:.2}%", finding.similarity * 100.0)?;
+            writeln!(
+                out,
+                "  {}",
+                format_function_output(&first.file, &first.name, first.start_line, first.end_line)
+            )?;
+            writeln!(
+                out,
+                "  {}",
+                format_function_output(
+                    &second.file,
+                    &second.name,
+                    second.start_line,
+                    second.end_line
+                )
+            )?;
+            writeln!(out, "  Suggestion: {}", consolidation_hint(finding))?;
+        }
+        writeln!(out, "\nTotal duplicated service pairs: {}", findings.len())
+    }
+}
+
+/// How to share the settings of two services
+fn consolidation_hint(finding: &AnalyzerFinding) -> String {
+    let (first, second) = (&finding.first, &finding.second);
+    if first.file == second.file {
+        format!(
+            "move the shared settings of '{}' and '{}' into an extension field (`x-{}: &{}`) and merge it into both with `<<: *{}`",
+            first.name, second.name, first.name, first.name, first.name
+        )
+    } else {
+        format!(
+            "reuse the service in {} with `extends: {{ file: {}, service: {} }}` and keep only the differences",
+            second.file, first.file, first.name
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::run_analyzer;
+
+    const COMPOSE: &str = r#"services:
+  api:
+    image: registry.example.com/shop/api:1.4.2
+    build:
+      context: ./api
+      args:
+        - NODE_VERSION=20
+    environment:
+      - DATABASE_URL=postgres://db/shop
+      - LOG_LEVEL=info
+    depends_on:
+      - db
+    ports:
+      - "3000:3000"
+    restart: unless-stopped
+  worker:
+    image: registry.example.com/shop/api:1.5.0
+    build:
+      context: ./api
+      args:
+        NODE_VERSION: "20"
+    environment:
+      LOG_LEVEL: debug
+      DATABASE_URL: postgres://db/shop
+    depends_on:
+      db:
+        condition: service_healthy
+    ports:
+      - "3001:3000"
+    restart: unless-stopped
+  db:
+    image: postgres:16
+    volumes:
+      - data:/var/lib/postgresql/data
+    healthcheck:
+      test: ["CMD", "pg_isready"]
+      interval: 10s
+"#;
+
+    #[test]
+    fn test_extracts_services_with_normalized_members() {
+        let services = extract_compose_services(COMPOSE, "docker-compose.yml").unwrap();
+        let names: Vec<_> =
+            services.iter().map(|s| (s.name.as_str(), s.start_line, s.end_line)).collect();
+        assert_eq!(names, vec![("api", 3, 15), ("worker", 17, 30), ("db", 32, 37)]);
+        assert_eq!(services[0].members, services[1].members);
+
+        let members: Vec<_> =
+            services[0].members.iter().map(|(n, t)| (n.as_str(), t.as_str())).collect();
+        assert_eq!(
+            members,
+            vec![
+                ("image", "image registry.example.com/shop/api"),
+                ("build.context", "path"),
+                ("build.args.NODE_VERSION", "variable"),
+                ("environment.DATABASE_URL", "variable"),
+                ("environment.LOG_LEVEL", "variable"),
+                ("depends_on", "list"),
+                ("ports", "list"),
+                ("restart", "string"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suggests_extension_field_within_a_file() {
+        let mut analyzer = ComposeAnalyzer::new();
+        let files = vec![("docker-compose.yml".to_string(), COMPOSE.to_string())];
+        let findings = run_analyzer(&mut analyzer, &files, 0.9).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            (findings[0].first.name.as_str(), findings[0].second.name.as_str()),
+            ("api", "worker")
+        );
+
+        let mut report = Vec::new();
+        analyzer.report(&findings, &mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(
+            report.contains("(`x-api: &api`) and merge it into both with `<<: *api`"),
+            "{report}"
+        );
+
+        assert!(analyzer.matches_file(Path::new("deploy/docker-compose.prod.yml")));
+        assert!(analyzer.matches_file(Path::new("compose.yaml")));
+        assert!(!analyzer.matches_file(Path::new(".github/workflows/ci.yml")));
+    }
+}
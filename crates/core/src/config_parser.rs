@@ -0,0 +1,834 @@
+//! Minimal YAML and JSON readers that keep line numbers.
+//!
+//! Only what is needed for duplication analysis of configuration files is
+//! supported: block and flow collections, plain/quoted/block scalars,
+//! multiple documents, tags (ignored) and anchors, aliases and `<<` merge
+//! keys, which are resolved while parsing.
+
+use std::collections::HashMap;
+
+/// A parsed value with the 1-based lines it spans
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigNode {
+    pub value: ConfigValue,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    Null,
+    Bool(bool),
+    /// Numbers keep their source text
+    Number(String),
+    String(String),
+    Sequence(Vec<ConfigNode>),
+    /// Entries in source order
+    Mapping(Vec<(String, ConfigNode)>),
+}
+
+impl ConfigNode {
+    fn new(value: ConfigValue, start_line: usize, end_line: usize) -> Self {
+        Self { value, start_line, end_line }
+    }
+
+    /// Look up a key of a mapping node
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&ConfigNode> {
+        match &self.value {
+            ConfigValue::Mapping(entries) => {
+                entries.iter().find(|(name, _)| name == key).map(|(_, node)| node)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse a JSON document
+pub fn parse_json(source: &str) -> Result<ConfigNode, String> {
+    let mut parser = JsonParser { chars: source.chars().collect(), pos: 0, line: 1 };
+    let node = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        return Err(format!("Unexpected trailing content at line {}", parser.line));
+    }
+    Ok(node)
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl JsonParser {
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.get(self.pos) {
+            if !c.is_whitespace() {
+                break;
+            }
+            if c == '\n' {
+                self.line += 1;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' at line {}", expected, self.line))
+        }
+    }
+
+    fn value(&mut self) -> Result<ConfigNode, String> {
+        self.skip_whitespace();
+        let start_line = self.line;
+        let value = match self.chars.get(self.pos) {
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.pos) == Some(&'}') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(':')?;
+                        entries.push((key, self.value()?));
+                        self.skip_whitespace();
+                        match self.chars.get(self.pos) {
+                            Some(',') => self.pos += 1,
+                            Some('}') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err(format!("Expected ',' or '}}' at line {}", self.line)),
+                        }
+                    }
+                }
+                ConfigValue::Mapping(entries)
+            }
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.pos) == Some(&']') {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        items.push(self.value()?);
+                        self.skip_whitespace();
+                        match self.chars.get(self.pos) {
+                            Some(',') => self.pos += 1,
+                            Some(']') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return Err(format!("Expected ',' or ']' at line {}", self.line)),
+                        }
+                    }
+                }
+                ConfigValue::Sequence(items)
+            }
+            Some('"') => ConfigValue::String(self.string()?),
+            Some(_) => {
+                let start = self.pos;
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|c| c.is_alphanumeric() || matches!(c, '-' | '+' | '.'))
+                {
+                    self.pos += 1;
+                }
+                let word: String = self.chars[start..self.pos].iter().collect();
+                match word.as_str() {
+                    "null" => ConfigValue::Null,
+                    "true" => ConfigValue::Bool(true),
+                    "false" => ConfigValue::Bool(false),
+                    _ if word.parse::<f64>().is_ok() => ConfigValue::Number(word),
+                    _ => return Err(format!("Unexpected token at line {}", self.line)),
+                }
+            }
+            None => return Err("Unexpected end of input".to_string()),
+        };
+        Ok(ConfigNode::new(value, start_line, self.line))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.chars.get(self.pos) != Some(&'"') {
+            return Err(format!("Expected string at line {}", self.line));
+        }
+        self.pos += 1;
+        let mut result = String::new();
+        while let Some(&c) = self.chars.get(self.pos) {
+            self.pos += 1;
+            match c {
+                '"' => return Ok(result),
+                '\\' => {
+                    let escaped = self.chars.get(self.pos).copied().unwrap_or('\\');
+                    self.pos += 1;
+                    match escaped {
+                        'n' => result.push('\n'),
+                        't' => result.push('\t'),
+                        'u' => {
+                            let hex: String = self.chars.iter().skip(self.pos).take(4).collect();
+                            self.pos += 4;
+                            result.extend(
+                                u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32),
+                            );
+                        }
+                        other => result.push(other),
+                    }
+                }
+                _ => result.push(c),
+            }
+        }
+        Err("Unterminated string".to_string())
+    }
+}
+
+/// Parse every document of a YAML stream
+pub fn parse_yaml(source: &str) -> Result<Vec<ConfigNode>, String> {
+    let mut documents = Vec::new();
+    let mut current = Vec::new();
+
+    for (index, raw) in source.lines().enumerate() {
+        let is_separator = raw.starts_with("---") || raw.starts_with("...");
+        if is_separator {
+            if !current.is_empty() {
+                documents.push(std::mem::take(&mut current));
+            }
+            // `--- value` puts the document content on the marker line
+            let rest = raw.trim_start_matches(['-', '.']).trim();
+            if !rest.is_empty() && !rest.starts_with('#') && !rest.starts_with('!') {
+                current.push(YamlLine::new(index + 1, &format!("    {}", rest)));
+            }
+            continue;
+        }
+        current.push(YamlLine::new(index + 1, raw));
+    }
+    if !current.is_empty() {
+        documents.push(current);
+    }
+
+    let mut anchors = HashMap::new();
+    let mut nodes = Vec::new();
+    for lines in documents {
+        let mut parser = YamlParser { lines, pos: 0, anchors: &mut anchors };
+        parser.skip_blank();
+        if parser.pos < parser.lines.len() {
+            let indent = parser.lines[parser.pos].indent;
+            nodes.push(parser.node(indent)?);
+        }
+    }
+    Ok(nodes)
+}
+
+#[derive(Debug, Clone)]
+struct YamlLine {
+    number: usize,
+    indent: usize,
+    /// Content without indentation and trailing comment
+    text: String,
+    raw: String,
+}
+
+impl YamlLine {
+    fn new(number: usize, raw: &str) -> Self {
+        let indent = raw.len() - raw.trim_start_matches(' ').len();
+        let text = strip_comment(raw.trim()).trim_end().to_string();
+        Self { number, indent, text, raw: raw.to_string() }
+    }
+
+    fn is_blank(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn is_sequence_item(&self) -> bool {
+        self.text == "-" || self.text.starts_with("- ")
+    }
+}
+
+/// Remove a `#` comment that is not inside quotes
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return &text[..index],
+            None => {}
+        }
+        previous = c;
+    }
+    text
+}
+
+/// Byte offset of the `:` separating a mapping key from its value, if any
+fn mapping_colon(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut depth = 0;
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    for (i, &(index, c)) in chars.iter().enumerate() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' if i == 0 => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                ':' if depth == 0 => {
+                    let next = chars.get(i + 1).map(|&(_, n)| n);
+                    if next.is_none_or(char::is_whitespace) {
+                        return Some(index);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+    None
+}
+
+fn unquote(text: &str) -> String {
+    let text = text.trim();
+    if text.len() >= 2
+        && ((text.starts_with('"') && text.ends_with('"'))
+            || (text.starts_with('\'') && text.ends_with('\'')))
+    {
+        text[1..text.len() - 1].replace("''", "'").replace("\\\"", "\"")
+    } else {
+        text.to_string()
+    }
+}
+
+fn scalar(text: &str) -> ConfigValue {
+    let text = text.trim();
+    if text.starts_with('"') || text.starts_with('\'') {
+        return ConfigValue::String(unquote(text));
+    }
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => ConfigValue::Null,
+        "true" | "True" | "TRUE" => ConfigValue::Bool(true),
+        "false" | "False" | "FALSE" => ConfigValue::Bool(false),
+        _ if text.parse::<f64>().is_ok() && !text.ends_with('.') => {
+            ConfigValue::Number(text.to_string())
+        }
+        _ => ConfigValue::String(text.to_string()),
+    }
+}
+
+struct YamlParser<'a> {
+    lines: Vec<YamlLine>,
+    pos: usize,
+    /// Shared across documents of a stream
+    anchors: &'a mut HashMap<String, ConfigNode>,
+}
+
+impl YamlParser<'_> {
+    fn skip_blank(&mut self) {
+        while self.lines.get(self.pos).is_some_and(YamlLine::is_blank) {
+            self.pos += 1;
+        }
+    }
+
+    fn current(&self) -> Option<&YamlLine> {
+        self.lines.get(self.pos)
+    }
+
+    fn last_line(&self) -> usize {
+        self.lines[..self.pos].iter().rev().find(|l| !l.is_blank()).map_or(0, |l| l.number)
+    }
+
+    /// Parse the block node starting at the current line
+    fn node(&mut self, indent: usize) -> Result<ConfigNode, String> {
+        self.skip_blank();
+        let Some(line) = self.current().cloned() else {
+            return Ok(ConfigNode::new(ConfigValue::Null, 0, 0));
+        };
+
+        if line.is_sequence_item() {
+            self.sequence(line.indent)
+        } else if mapping_colon(&line.text).is_some() {
+            self.mapping(line.indent)
+        } else {
+            self.pos += 1;
+            self.inline_value(&line.text, line.number, indent)
+        }
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<ConfigNode, String> {
+        let start_line = self.current().map_or(0, |l| l.number);
+        let mut entries: Vec<(String, ConfigNode)> = Vec::new();
+        let mut merged: Vec<(String, ConfigNode)> = Vec::new();
+
+        while let Some(line) = self.current().cloned() {
+            if line.indent != indent || line.is_sequence_item() {
+                break;
+            }
+            let Some(colon) = mapping_colon(&line.text) else {
+                break;
+            };
+            self.pos += 1;
+
+            let key = unquote(&line.text[..colon]);
+            let rest = line.text[colon + 1..].trim();
+            let value = self.value_after_indicator(rest, line.number, indent, true)?;
+
+            if key == "<<" {
+                let sources = match value.value {
+                    ConfigValue::Sequence(items) => items,
+                    _ => vec![value],
+                };
+                for source in sources {
+                    if let ConfigValue::Mapping(source_entries) = source.value {
+                        merged.extend(source_entries);
+                    }
+                }
+            } else {
+                entries.retain(|(existing, _)| existing != &key);
+                entries.push((key, value));
+            }
+            self.skip_blank();
+        }
+
+        // Explicit keys override merged ones
+        for (key, value) in merged {
+            if !entries.iter().any(|(existing, _)| existing == &key) {
+                entries.push((key, value));
+            }
+        }
+
+        Ok(ConfigNode::new(ConfigValue::Mapping(entries), start_line, self.last_line()))
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<ConfigNode, String> {
+        let start_line = self.current().map_or(0, |l| l.number);
+        let mut items = Vec::new();
+
+        while let Some(line) = self.current().cloned() {
+            if line.indent != indent || !line.is_sequence_item() {
+                break;
+            }
+
+            let rest = line.text[1..].trim_start();
+            if rest.is_empty() || rest.starts_with('&') || rest.starts_with('!') {
+                self.pos += 1;
+                items.push(self.value_after_indicator(rest, line.number, indent, false)?);
+            } else {
+                // Re-read the item content as a line indented to its own column
+                let column = line.indent + (line.text.len() - rest.len());
+                self.lines[self.pos] = YamlLine {
+                    number: line.number,
+                    indent: column,
+                    text: rest.to_string(),
+                    raw: format!("{}{}", " ".repeat(column), rest),
+                };
+                if mapping_colon(rest).is_some() && !rest.starts_with(['[', '{']) {
+                    items.push(self.mapping(column)?);
+                } else {
+                    self.pos += 1;
+                    items.push(self.inline_value(rest, line.number, column)?);
+                }
+            }
+            self.skip_blank();
+        }
+
+        Ok(ConfigNode::new(ConfigValue::Sequence(items), start_line, self.last_line()))
+    }
+
+    /// Parse what follows `key:` or `-`: nested block, block scalar, alias or inline value
+    fn value_after_indicator(
+        &mut self,
+        rest: &str,
+        line_number: usize,
+        indent: usize,
+        in_mapping: bool,
+    ) -> Result<ConfigNode, String> {
+        let mut rest = rest.trim();
+        let mut anchor = None;
+
+        loop {
+            if let Some(stripped) = rest.strip_prefix('&') {
+                let end = stripped.find(char::is_whitespace).unwrap_or(stripped.len());
+                anchor = Some(stripped[..end].to_string());
+                rest = stripped[end..].trim_start();
+            } else if rest.starts_with('!') {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                rest = rest[end..].trim_start();
+            } else {
+                break;
+            }
+        }
+
+        let node = if rest.is_empty() {
+            self.skip_blank();
+            match self.current() {
+                Some(next) if next.indent > indent => {
+                    let child_indent = next.indent;
+                    self.node(child_indent)?
+                }
+                // `key:` followed by a sequence at the same indentation
+                Some(next) if in_mapping && next.indent == indent && next.is_sequence_item() => {
+                    self.sequence(indent)?
+                }
+                _ => ConfigNode::new(ConfigValue::Null, line_number, line_number),
+            }
+        } else if rest.starts_with('|') || rest.starts_with('>') {
+            self.block_scalar(rest, line_number, indent)
+        } else {
+            self.inline_value(rest, line_number, indent)?
+        };
+
+        if let Some(anchor) = anchor {
+            self.anchors.insert(anchor, node.clone());
+        }
+        Ok(node)
+    }
+
+    fn block_scalar(&mut self, header: &str, line_number: usize, indent: usize) -> ConfigNode {
+        let folded = header.starts_with('>');
+        let mut content: Vec<String> = Vec::new();
+        let mut end_line = line_number;
+        let mut block_indent = None;
+
+        while let Some(line) = self.current() {
+            if line.raw.trim().is_empty() {
+                content.push(String::new());
+                self.pos += 1;
+                continue;
+            }
+            if line.indent <= indent {
+                break;
+            }
+            let column = *block_indent.get_or_insert(line.indent);
+            content.push(line.raw.get(column.min(line.indent)..).unwrap_or("").to_string());
+            end_line = line.number;
+            self.pos += 1;
+        }
+        while content.last().is_some_and(String::is_empty) {
+            content.pop();
+        }
+
+        let text = content.join(if folded { " " } else { "\n" });
+        ConfigNode::new(ConfigValue::String(text), line_number, end_line)
+    }
+
+    /// Scalar, alias or flow collection, which may continue on following lines
+    fn inline_value(
+        &mut self,
+        text: &str,
+        line_number: usize,
+        indent: usize,
+    ) -> Result<ConfigNode, String> {
+        let mut text = text.to_string();
+        let mut end_line = line_number;
+
+        if text.starts_with('[') || text.starts_with('{') {
+            while !flow_is_closed(&text) {
+                let Some(next) = self.current() else {
+                    break;
+                };
+                text.push(' ');
+                text.push_str(&next.text);
+                end_line = next.number;
+                self.pos += 1;
+            }
+            let mut flow =
+                FlowParser { chars: text.chars().collect(), pos: 0, anchors: &*self.anchors };
+            let value = flow.value()?.value;
+            return Ok(with_lines(value, line_number, end_line));
+        }
+
+        if let Some(alias) = text.strip_prefix('*') {
+            let node = self.anchors.get(alias.trim()).ok_or_else(|| {
+                format!("Unknown alias '{}' at line {}", alias.trim(), line_number)
+            })?;
+            return Ok(ConfigNode::new(node.value.clone(), line_number, line_number));
+        }
+
+        // Multi-line plain scalars continue on more indented lines
+        while let Some(next) = self.current() {
+            if next.is_blank() || next.indent <= indent || mapping_colon(&next.text).is_some() {
+                break;
+            }
+            text.push(' ');
+            text.push_str(&next.text);
+            end_line = next.number;
+            self.pos += 1;
+        }
+
+        Ok(ConfigNode::new(scalar(&text), line_number, end_line))
+    }
+}
+
+fn flow_is_closed(text: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote = None;
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                _ => {}
+            },
+        }
+    }
+    depth <= 0
+}
+
+/// Assign the lines of a flow collection to it and all of its children
+fn with_lines(value: ConfigValue, start_line: usize, end_line: usize) -> ConfigNode {
+    let value = match value {
+        ConfigValue::Sequence(items) => ConfigValue::Sequence(
+            items.into_iter().map(|item| with_lines(item.value, start_line, end_line)).collect(),
+        ),
+        ConfigValue::Mapping(entries) => ConfigValue::Mapping(
+            entries
+                .into_iter()
+                .map(|(key, node)| (key, with_lines(node.value, start_line, end_line)))
+                .collect(),
+        ),
+        scalar => scalar,
+    };
+    ConfigNode::new(value, start_line, end_line)
+}
+
+/// Parser for flow collections such as `[a, b]` and `{ key: value }`
+struct FlowParser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    anchors: &'a HashMap<String, ConfigNode>,
+}
+
+impl FlowParser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<ConfigNode, String> {
+        self.skip_whitespace();
+        let value = match self.chars.get(self.pos) {
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.chars.get(self.pos) {
+                        Some(']') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(',') => self.pos += 1,
+                        Some(_) => items.push(self.value()?),
+                        None => return Err("Unterminated flow sequence".to_string()),
+                    }
+                }
+                ConfigValue::Sequence(items)
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.chars.get(self.pos) {
+                        Some('}') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(',') => self.pos += 1,
+                        Some(_) => {
+                            let key = unquote(&self.token(true));
+                            self.skip_whitespace();
+                            let value = if self.chars.get(self.pos) == Some(&':') {
+                                self.pos += 1;
+                                self.value()?
+                            } else {
+                                ConfigNode::new(ConfigValue::Null, 0, 0)
+                            };
+                            entries.push((key, value));
+                        }
+                        None => return Err("Unterminated flow mapping".to_string()),
+                    }
+                }
+                ConfigValue::Mapping(entries)
+            }
+            Some('*') => {
+                self.pos += 1;
+                let alias = self.token(false);
+                self.anchors
+                    .get(alias.trim())
+                    .map(|node| node.value.clone())
+                    .ok_or_else(|| format!("Unknown alias '{}'", alias.trim()))?
+            }
+            _ => scalar(&self.token(false)),
+        };
+        Ok(ConfigNode::new(value, 0, 0))
+    }
+
+    /// Read a scalar up to the next flow indicator
+    fn token(&mut self, is_key: bool) -> String {
+        self.skip_whitespace();
+        let mut token = String::new();
+        let quote = self.chars.get(self.pos).copied().filter(|c| *c == '"' || *c == '\'');
+        if let Some(q) = quote {
+            token.push(q);
+            self.pos += 1;
+            while let Some(&c) = self.chars.get(self.pos) {
+                token.push(c);
+                self.pos += 1;
+                if c == q {
+                    break;
+                }
+            }
+            return token;
+        }
+
+        while let Some(&c) = self.chars.get(self.pos) {
+            let ends_key = is_key
+                && c == ':'
+                && self.chars.get(self.pos + 1).is_none_or(|n| n.is_whitespace());
+            if matches!(c, ',' | ']' | '}') || ends_key {
+                break;
+            }
+            token.push(c);
+            self.pos += 1;
+        }
+        token.trim().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping_keys(node: &ConfigNode) -> Vec<&str> {
+        match &node.value {
+            ConfigValue::Mapping(entries) => entries.iter().map(|(key, _)| key.as_str()).collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn parses_json_with_lines() {
+        let source = r#"{
+  "name": "app",
+  "scripts": {
+    "build": "tsc",
+    "test": "vitest"
+  },
+  "private": true,
+  "workspaces": ["a", "b"],
+  "version": 1.5
+}"#;
+        let root = parse_json(source).unwrap();
+        assert_eq!((root.start_line, root.end_line), (1, 10));
+        assert_eq!(
+            mapping_keys(&root),
+            vec!["name", "scripts", "private", "workspaces", "version"]
+        );
+
+        let scripts = root.get("scripts").unwrap();
+        assert_eq!((scripts.start_line, scripts.end_line), (3, 6));
+        assert_eq!(root.get("private").unwrap().value, ConfigValue::Bool(true));
+        assert_eq!(root.get("version").unwrap().value, ConfigValue::Number("1.5".to_string()));
+        assert!(parse_json("{\"a\": }").is_err());
+    }
+
+    #[test]
+    fn parses_yaml_blocks_and_documents() {
+        let source = r#"# deployment
+apiVersion: apps/v1
+kind: Deployment
+spec:
+  replicas: 3
+  containers:
+    - name: web   # main container
+      image: "nginx:1.25"
+      ports: [80, 443]
+    - name: sidecar
+      args:
+      - --verbose
+  script: |
+    echo one
+    echo two
+---
+kind: Service
+metadata: { name: web, labels: { app: web } }
+"#;
+        let documents = parse_yaml(source).unwrap();
+        assert_eq!(documents.len(), 2);
+
+        let deployment = &documents[0];
+        assert_eq!(mapping_keys(deployment), vec!["apiVersion", "kind", "spec"]);
+        let spec = deployment.get("spec").unwrap();
+        assert_eq!((spec.start_line, spec.end_line), (5, 15));
+        assert_eq!(spec.get("replicas").unwrap().value, ConfigValue::Number("3".to_string()));
+        assert_eq!(
+            spec.get("script").unwrap().value,
+            ConfigValue::String("echo one\necho two".to_string())
+        );
+
+        let ConfigValue::Sequence(containers) = &spec.get("containers").unwrap().value else {
+            panic!("containers should be a sequence");
+        };
+        assert_eq!(containers.len(), 2);
+        assert_eq!(mapping_keys(&containers[0]), vec!["name", "image", "ports"]);
+        assert_eq!((containers[0].start_line, containers[0].end_line), (7, 9));
+        assert_eq!(
+            containers[0].get("image").unwrap().value,
+            ConfigValue::String("nginx:1.25".to_string())
+        );
+        let ConfigValue::Sequence(args) = &containers[1].get("args").unwrap().value else {
+            panic!("args should be a sequence");
+        };
+        assert_eq!(args[0].value, ConfigValue::String("--verbose".to_string()));
+
+        let service = &documents[1];
+        let metadata = service.get("metadata").unwrap();
+        assert_eq!(mapping_keys(metadata), vec!["name", "labels"]);
+        assert_eq!(metadata.start_line, 18);
+    }
+
+    #[test]
+    fn resolves_anchors_aliases_and_merge_keys() {
+        let source = r#"
+defaults: &defaults
+  runs-on: ubuntu-latest
+  timeout-minutes: 10
+  env:
+    CI: true
+build:
+  <<: *defaults
+  timeout-minutes: 30
+  steps: &steps
+    - run: make
+test:
+  steps: *steps
+"#;
+        let root = parse_yaml(source).unwrap().remove(0);
+        let build = root.get("build").unwrap();
+        assert_eq!(mapping_keys(build), vec!["timeout-minutes", "steps", "runs-on", "env"]);
+        assert_eq!(
+            build.get("timeout-minutes").unwrap().value,
+            ConfigValue::Number("30".to_string())
+        );
+        assert_eq!(build.get("env").unwrap().get("CI").unwrap().value, ConfigValue::Bool(true));
+
+        let test_steps = root.get("test").unwrap().get("steps").unwrap();
+        assert_eq!(test_steps.value, build.get("steps").unwrap().value);
+        assert!(parse_yaml("a: *missing").is_err());
+    }
+}
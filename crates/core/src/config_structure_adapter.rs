@@ -0,0 +1,387 @@
+use crate::analyzer::{AnalysisUnit, Analyzer, AnalyzerError};
+use crate::config_parser::{parse_json, parse_yaml, ConfigNode, ConfigValue};
+use crate::structure_comparator::{
+    ComparisonOptions, SourceLocation, Structure, StructureComparator, StructureIdentifier,
+    StructureKind, StructureMember, StructureMetadata,
+};
+use crate::tree::TreeNode;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// 設定ファイルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    fn name(self) -> &'static str {
+        match self {
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Json => "json",
+        }
+    }
+
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            ConfigFormat::Yaml => &["yaml", "yml"],
+            ConfigFormat::Json => &["json"],
+        }
+    }
+}
+
+/// 設定ファイル内のマッピング（ドキュメント全体またはネストしたブロック）
+#[derive(Debug, Clone)]
+pub struct ConfigBlock {
+    /// `jobs.build` や `spec.containers[0]` のようなキーパス
+    pub path: String,
+    pub file_path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// キーと値（エイリアス・マージキーは解決済み）
+    pub entries: Vec<(String, ConfigNode)>,
+}
+
+/// 設定ブロックを一般構造に変換
+impl From<ConfigBlock> for Structure {
+    fn from(block: ConfigBlock) -> Self {
+        // キーをメンバーとして追加（値はカテゴリで比較する）
+        let members = block
+            .entries
+            .iter()
+            .map(|(key, node)| StructureMember {
+                name: key.clone(),
+                value_type: categorize_config_value(&node.value).into(),
+                modifiers: vec![],
+                nested: None,
+            })
+            .collect();
+
+        Structure {
+            identifier: StructureIdentifier {
+                name: block.path.into(),
+                kind: StructureKind::Generic("config-block".to_string()),
+                namespace: Some(block.file_path.as_str().into()),
+            },
+            members,
+            metadata: StructureMetadata {
+                location: SourceLocation {
+                    file_path: block.file_path.into(),
+                    start_line: block.start_line,
+                    end_line: block.end_line,
+                },
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// 設定値をカテゴライズ（型として扱う）
+pub(crate) fn categorize_config_value(value: &ConfigValue) -> String {
+    let text = match value {
+        ConfigValue::Null => return "null".to_string(),
+        ConfigValue::Bool(_) => return "bool".to_string(),
+        ConfigValue::Number(_) => return "number".to_string(),
+        ConfigValue::Sequence(_) => return "sequence".to_string(),
+        ConfigValue::Mapping(_) => return "mapping".to_string(),
+        ConfigValue::String(text) => text.trim(),
+    };
+
+    // Template and environment references
+    if text.contains("${") || text.contains("{{") || text.starts_with('$') {
+        return "reference".to_string();
+    }
+
+    // URLs
+    if text.contains("://") {
+        return "url".to_string();
+    }
+
+    // Durations such as 30s, 5m, 1h
+    let (digits, unit) = text.split_at(text.trim_end_matches(char::is_alphabetic).len());
+    if !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && matches!(unit, "ms" | "s" | "m" | "h" | "d")
+    {
+        return "duration".to_string();
+    }
+
+    // Versions such as v1.2.3 or 1.2
+    let version = text.strip_prefix('v').unwrap_or(text);
+    if version.contains('.')
+        && version
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+    {
+        return "version".to_string();
+    }
+
+    // Container images and action references: name:tag, owner/repo@ref
+    if !text.contains(char::is_whitespace)
+        && (text.contains('@')
+            || text.split_once(':').is_some_and(|(name, tag)| !name.is_empty() && !tag.is_empty()))
+    {
+        return "image".to_string();
+    }
+
+    // Paths
+    if !text.contains(char::is_whitespace) && (text.contains('/') || text.starts_with('.')) {
+        return "path".to_string();
+    }
+
+    // Shell commands and other free text
+    if text.contains(char::is_whitespace) {
+        return "text".to_string();
+    }
+
+    "string".to_string()
+}
+
+/// Collect every mapping with at least `min_members` keys
+#[must_use]
+pub fn extract_config_blocks(
+    document: &ConfigNode,
+    document_name: &str,
+    file_path: &str,
+    min_members: usize,
+) -> Vec<ConfigBlock> {
+    let mut blocks = Vec::new();
+    collect_blocks(document, document_name, file_path, min_members, &mut blocks);
+    blocks
+}
+
+fn collect_blocks(
+    node: &ConfigNode,
+    path: &str,
+    file_path: &str,
+    min_members: usize,
+    blocks: &mut Vec<ConfigBlock>,
+) {
+    match &node.value {
+        ConfigValue::Mapping(entries) => {
+            if entries.len() >= min_members {
+                blocks.push(ConfigBlock {
+                    path: path.to_string(),
+                    file_path: file_path.to_string(),
+                    start_line: node.start_line,
+                    end_line: node.end_line,
+                    entries: entries.clone(),
+                });
+            }
+            for (key, child) in entries {
+                collect_blocks(child, &format!("{}.{}", path, key), file_path, min_members, blocks);
+            }
+        }
+        ConfigValue::Sequence(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect_blocks(
+                    item,
+                    &format!("{}[{}]", path, index),
+                    file_path,
+                    min_members,
+                    blocks,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Tree view of a config value: keys as nodes, scalars by category
+fn config_tree(node: &ConfigNode, label: &str, next_id: &mut usize) -> TreeNode {
+    let mut tree = TreeNode::new(label.to_string(), categorize_config_value(&node.value), *next_id);
+    *next_id += 1;
+    match &node.value {
+        ConfigValue::Mapping(entries) => {
+            for (key, child) in entries {
+                tree.add_child(Rc::new(config_tree(child, key, next_id)));
+            }
+        }
+        ConfigValue::Sequence(items) => {
+            for item in items {
+                tree.add_child(Rc::new(config_tree(item, "item", next_id)));
+            }
+        }
+        _ => {}
+    }
+    tree
+}
+
+/// YAML/JSON用の解析器：ブロック単位で構造比較する
+pub struct ConfigAnalyzer {
+    format: ConfigFormat,
+    min_members: usize,
+    comparator: RefCell<StructureComparator>,
+}
+
+impl ConfigAnalyzer {
+    pub fn new(format: ConfigFormat) -> Self {
+        let options = ComparisonOptions {
+            name_weight: 0.2,      // キーパスはファイル間で異なりやすい
+            structure_weight: 0.8, // キーと値の種類を重視
+            ignore_order: true,    // キーの順序は無視
+            ..Default::default()
+        };
+        Self::with_options(format, options, 3)
+    }
+
+    pub fn yaml() -> Self {
+        Self::new(ConfigFormat::Yaml)
+    }
+
+    pub fn json() -> Self {
+        Self::new(ConfigFormat::Json)
+    }
+
+    /// `min_members` 未満のキーしか持たないブロックは比較しない
+    pub fn with_options(
+        format: ConfigFormat,
+        options: ComparisonOptions,
+        min_members: usize,
+    ) -> Self {
+        Self { format, min_members, comparator: RefCell::new(StructureComparator::new(options)) }
+    }
+}
+
+impl Analyzer for ConfigAnalyzer {
+    fn name(&self) -> &str {
+        self.format.name()
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.format.extensions()
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let documents = match self.format {
+            ConfigFormat::Yaml => parse_yaml(source),
+            ConfigFormat::Json => parse_json(source).map(|document| vec![document]),
+        }
+        .map_err(|e| format!("Failed to parse {}: {}", file, e))?;
+
+        let mut units = Vec::new();
+        for (index, document) in documents.iter().enumerate() {
+            let name =
+                if index == 0 { "document".to_string() } else { format!("document{}", index + 1) };
+            for block in extract_config_blocks(document, &name, file, self.min_members) {
+                let node = ConfigNode {
+                    value: ConfigValue::Mapping(block.entries.clone()),
+                    start_line: block.start_line,
+                    end_line: block.end_line,
+                };
+                units.push(AnalysisUnit {
+                    name: block.path.clone(),
+                    file: file.to_string(),
+                    start_line: block.start_line as u32,
+                    end_line: block.end_line as u32,
+                    tree: Rc::new(config_tree(&node, "mapping", &mut 0)),
+                    structure: Some(Structure::from(block)),
+                });
+            }
+        }
+        Ok(units)
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        match (&first.structure, &second.structure) {
+            (Some(s1), Some(s2)) => self.comparator.borrow_mut().compare(s1, s2).overall_similarity,
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::run_analyzer;
+
+    #[test]
+    fn test_value_categorization() {
+        let string = |s: &str| ConfigValue::String(s.to_string());
+        assert_eq!(categorize_config_value(&ConfigValue::Bool(true)), "bool");
+        assert_eq!(categorize_config_value(&ConfigValue::Number("3".to_string())), "number");
+        assert_eq!(categorize_config_value(&string("${{ secrets.TOKEN }}")), "reference");
+        assert_eq!(categorize_config_value(&string("https://example.com")), "url");
+        assert_eq!(categorize_config_value(&string("30s")), "duration");
+        assert_eq!(categorize_config_value(&string("v1.2.3")), "version");
+        assert_eq!(categorize_config_value(&string("nginx:1.25")), "image");
+        assert_eq!(categorize_config_value(&string("actions/checkout@v4")), "image");
+        assert_eq!(categorize_config_value(&string("./src")), "path");
+        assert_eq!(categorize_config_value(&string("npm run build")), "text");
+        assert_eq!(categorize_config_value(&string("ubuntu-latest")), "string");
+    }
+
+    #[test]
+    fn test_extracts_blocks_with_paths() {
+        let source = r#"
+on: push
+jobs:
+  build:
+    runs-on: ubuntu-latest
+    timeout-minutes: 10
+    steps:
+      - uses: actions/checkout@v4
+      - run: npm ci
+"#;
+        let document = parse_yaml(source).unwrap().remove(0);
+        let blocks = extract_config_blocks(&document, "document", "ci.yml", 3);
+        let paths: Vec<_> = blocks.iter().map(|b| (b.path.as_str(), b.start_line)).collect();
+        assert_eq!(paths, vec![("document.jobs.build", 5)]);
+
+        let structure = Structure::from(blocks[0].clone());
+        let steps = structure.members.iter().find(|m| m.name == "steps").unwrap();
+        assert_eq!(steps.value_type, "sequence");
+    }
+
+    #[test]
+    fn test_reports_copy_pasted_jobs_with_aliases_resolved() {
+        let first = r#"
+name: CI
+on: push
+defaults: &defaults
+  runs-on: ubuntu-latest
+  timeout-minutes: 10
+jobs:
+  build:
+    <<: *defaults
+    env:
+      NODE_ENV: production
+    steps:
+      - run: npm ci
+  lint:
+    image: node:20
+"#;
+        let second = r#"{
+  "jobs": {
+    "test": {
+      "runs-on": "windows-latest",
+      "timeout-minutes": 15,
+      "env": { "NODE_ENV": "test" },
+      "steps": [{ "run": "npm test" }]
+    }
+  }
+}"#;
+
+        let mut yaml = ConfigAnalyzer::yaml();
+        let mut units = yaml.extract("ci.yml", first).unwrap();
+        let build = units.pop().unwrap();
+        assert_eq!(build.name, "document.jobs.build");
+        assert_eq!(build.structure.as_ref().unwrap().members.len(), 4);
+
+        let mut json = ConfigAnalyzer::json();
+        let test = json.extract("ci.json", second).unwrap().remove(0);
+        assert_eq!(test.name, "document.jobs.test");
+        assert!(json.compare(&build, &test) > 0.8);
+
+        let files = vec![
+            ("a.yml".to_string(), first.to_string()),
+            ("b.yml".to_string(), first.to_string()),
+        ];
+        let findings = run_analyzer(&mut yaml, &files, 0.9).unwrap();
+        // The duplicated jobs are covered by the duplicated documents
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].first.name, "document");
+        assert_eq!(findings[0].second.file, "b.yml");
+    }
+}
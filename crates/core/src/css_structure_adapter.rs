@@ -1,6 +1,7 @@
 use crate::structure_comparator::{
-    ComparisonOptions, SourceLocation, Structure, StructureComparator, StructureComparisonResult,
-    StructureIdentifier, StructureKind, StructureMember, StructureMetadata,
+    BucketStats, ComparisonOptions, FingerprintBuckets, SourceLocation, Structure,
+    StructureComparator, StructureComparisonResult, StructureIdentifier, StructureKind,
+    StructureMember, StructureMetadata,
 };
 use std::collections::HashMap;
 
@@ -31,7 +32,7 @@ impl From<CssStructDef> for Structure {
         for (property, value) in css_rule.declarations {
             members.push(StructureMember {
                 name: property.clone(),
-                value_type: categorize_css_value(&value),
+                value_type: categorize_css_value(&value).into(),
                 modifiers: vec![],
                 nested: None,
             });
@@ -41,7 +42,7 @@ impl From<CssStructDef> for Structure {
         if let Some(media) = &css_rule.media_query {
             members.push(StructureMember {
                 name: "@media".to_string(),
-                value_type: media.clone(),
+                value_type: media.into(),
                 modifiers: vec!["media-query".to_string()],
                 nested: None,
             });
@@ -51,7 +52,7 @@ impl From<CssStructDef> for Structure {
         if !css_rule.parent_selectors.is_empty() {
             members.push(StructureMember {
                 name: "@parent".to_string(),
-                value_type: css_rule.parent_selectors.join(" "),
+                value_type: css_rule.parent_selectors.join(" ").into(),
                 modifiers: vec!["parent-selector".to_string()],
                 nested: None,
             });
@@ -59,14 +60,14 @@ impl From<CssStructDef> for Structure {
 
         Structure {
             identifier: StructureIdentifier {
-                name: css_rule.selector.clone(),
+                name: css_rule.selector.into(),
                 kind,
-                namespace: Some(css_rule.file_path.clone()),
+                namespace: Some(css_rule.file_path.as_str().into()),
             },
             members,
             metadata: StructureMetadata {
                 location: SourceLocation {
-                    file_path: css_rule.file_path,
+                    file_path: css_rule.file_path.into(),
                     start_line: css_rule.start_line,
                     end_line: css_rule.end_line,
                 },
@@ -405,7 +406,7 @@ fn normalize_css_value(value: &str) -> String {
 /// 複数のCSSルールを効率的に比較
 pub struct CssBatchComparator {
     comparator: CssStructureComparator,
-    fingerprint_cache: HashMap<String, Vec<Structure>>,
+    buckets: FingerprintBuckets,
 }
 
 impl Default for CssBatchComparator {
@@ -416,56 +417,34 @@ impl Default for CssBatchComparator {
 
 impl CssBatchComparator {
     pub fn new() -> Self {
-        Self { comparator: CssStructureComparator::new(), fingerprint_cache: HashMap::new() }
+        Self { comparator: CssStructureComparator::new(), buckets: FingerprintBuckets::new() }
     }
 
-    /// CSSルールをフィンガープリントでグループ化
+    /// CSSルールをフィンガープリントでグループ化（同じ位置のルールは一度だけ登録）
     pub fn group_by_fingerprint(&mut self, rules: Vec<CssStructDef>) {
         for rule in rules {
             let structure = Structure::from(rule);
             let fingerprint = self.comparator.comparator.generate_fingerprint(&structure);
-            self.fingerprint_cache.entry(fingerprint).or_default().push(structure);
+            self.buckets.insert(structure, fingerprint);
         }
     }
 
-    /// 類似CSSルールを検出
-    pub fn find_similar_rules(&mut self, threshold: f64) -> Vec<(Structure, Structure, f64)> {
-        use crate::structure_comparator::should_compare_fingerprints;
+    /// バケットの統計情報
+    pub fn bucket_stats(&self) -> BucketStats {
+        self.buckets.stats()
+    }
 
+    /// 類似CSSルールを検出（各ペアは一度だけ比較・報告される）
+    pub fn find_similar_rules(&mut self, threshold: f64) -> Vec<(Structure, Structure, f64)> {
         let mut results = Vec::new();
-        let fingerprints: Vec<String> = self.fingerprint_cache.keys().cloned().collect();
 
-        for i in 0..fingerprints.len() {
-            for j in i..fingerprints.len() {
-                let fp1 = &fingerprints[i];
-                let fp2 = &fingerprints[j];
+        for (i, j) in self.buckets.candidate_pairs() {
+            let s1 = &self.buckets.structures()[i];
+            let s2 = &self.buckets.structures()[j];
+            let result = self.comparator.comparator.compare(s1, s2);
 
-                if !should_compare_fingerprints(fp1, fp2) {
-                    continue;
-                }
-
-                let structures1 = &self.fingerprint_cache[fp1];
-                let structures2 = &self.fingerprint_cache[fp2];
-
-                for s1 in structures1 {
-                    let start_idx = if i == j {
-                        structures2
-                            .iter()
-                            .position(|s| std::ptr::eq(s, s1))
-                            .map(|pos| pos + 1)
-                            .unwrap_or(0)
-                    } else {
-                        0
-                    };
-
-                    for s2 in &structures2[start_idx..] {
-                        let result = self.comparator.comparator.compare(s1, s2);
-
-                        if result.overall_similarity >= threshold {
-                            results.push((s1.clone(), s2.clone(), result.overall_similarity));
-                        }
-                    }
-                }
+            if result.overall_similarity >= threshold {
+                results.push((s1.clone(), s2.clone(), result.overall_similarity));
             }
         }
 
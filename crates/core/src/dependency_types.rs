@@ -0,0 +1,266 @@
+//! Local types re-declaring the types of dependencies.
+//!
+//! Teams copy a dependency's type, or the few properties of it they use,
+//! instead of importing it, and the copy drifts when the dependency changes.
+//! Only packages on an allowlist are read: the declaration files of each are
+//! found in the nearest `node_modules`, or those of its `@types` package when
+//! it ships none, and at most [`MAX_DECLARATION_FILES`] of them are parsed.
+//! A local type is reported when it is similar to a dependency type as a
+//! whole, suggesting an import, or when all of its properties, at least
+//! [`MIN_SUBSET_PROPERTIES`] of them, appear with compatible types in a
+//! larger dependency type, suggesting `Pick` of that type.
+
+use crate::comparison_budget::ComparisonBudget;
+use crate::type_comparator::{compare_types, TypeComparisonOptions};
+use crate::type_extractor::TypeDefinition;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Most declaration files read from one package
+pub const MAX_DECLARATION_FILES: usize = 500;
+
+/// Fewest properties of a local type reported as a subset of a dependency type
+pub const MIN_SUBSET_PROPERTIES: usize = 3;
+
+/// An installed package whose declaration files are compared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyPackage {
+    /// Name the types are imported from, e.g. `axios`
+    pub name: String,
+    /// Directory holding the declaration files
+    pub dir: PathBuf,
+}
+
+/// The package `name` in the nearest `node_modules` at or above `start`, or
+/// its `@types` package when the package itself has no declaration files
+pub fn find_dependency_package(start: &Path, name: &str) -> Option<DependencyPackage> {
+    let types_name = match name.strip_prefix('@') {
+        Some(scoped) => format!("@types/{}", scoped.replacen('/', "__", 1)),
+        None => format!("@types/{}", name),
+    };
+    for dir in start.ancestors() {
+        let node_modules = dir.join("node_modules");
+        if !node_modules.is_dir() {
+            continue;
+        }
+        for candidate in [name, types_name.as_str()] {
+            let package = node_modules.join(candidate);
+            if package.is_dir() && !declaration_files(&package).is_empty() {
+                return Some(DependencyPackage { name: name.to_string(), dir: package });
+            }
+        }
+    }
+    None
+}
+
+/// The `.d.ts`, `.d.mts` and `.d.cts` files of a package directory, leaving out
+/// nested `node_modules`, in path order and at most [`MAX_DECLARATION_FILES`]
+pub fn declaration_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        let mut entries: Vec<_> = entries.filter_map(Result::ok).map(|e| e.path()).collect();
+        entries.sort();
+        for path in entries.into_iter().rev() {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+            if path.is_dir() {
+                if name != "node_modules" {
+                    pending.push(path);
+                }
+            } else if [".d.ts", ".d.mts", ".d.cts"].iter().any(|ext| name.ends_with(ext)) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files.truncate(MAX_DECLARATION_FILES);
+    files
+}
+
+/// A local type found in a dependency
+#[derive(Debug, Clone)]
+pub struct DependencyTypeMatch {
+    pub local: TypeDefinition,
+    /// Package the dependency type is imported from
+    pub package: String,
+    pub dependency: TypeDefinition,
+    pub similarity: f64,
+    /// Properties of the dependency type the local type re-declares, when
+    /// it covers only part of it
+    pub picked: Option<Vec<String>>,
+}
+
+impl DependencyTypeMatch {
+    /// The type to use instead of the local one, e.g. `AxiosRequestConfig` or
+    /// `Pick<AxiosRequestConfig, "url" | "method">`
+    pub fn replacement(&self) -> String {
+        match &self.picked {
+            Some(properties) => {
+                let keys: Vec<String> = properties.iter().map(|key| format!("{key:?}")).collect();
+                format!("Pick<{}, {}>", self.dependency.name, keys.join(" | "))
+            }
+            None => self.dependency.name.to_string(),
+        }
+    }
+
+    /// The import bringing the dependency type into scope
+    pub fn import(&self) -> String {
+        format!("import type {{ {} }} from {:?};", self.dependency.name, self.package)
+    }
+}
+
+/// The dependency type each local type duplicates or is a subset of, if any:
+/// whole matches first, then the subset with the highest similarity
+pub fn find_dependency_type_matches(
+    local: &[TypeDefinition],
+    dependencies: &[(String, TypeDefinition)],
+    threshold: f64,
+    options: &TypeComparisonOptions,
+    budget: &ComparisonBudget,
+) -> Vec<DependencyTypeMatch> {
+    let mut matches = Vec::new();
+    for local_type in local.iter().filter(|t| !t.properties.is_empty() && !t.has_ignore_directive) {
+        let mut best: Option<DependencyTypeMatch> = None;
+        for (package, dependency) in dependencies {
+            if dependency.properties.is_empty() || !budget.try_compare() {
+                continue;
+            }
+            let result = compare_types(local_type, dependency, options);
+            let picked = if result.similarity >= threshold {
+                None
+            } else {
+                let covered = result.differences.missing_properties.is_empty()
+                    && dependency.properties.len() > local_type.properties.len()
+                    && local_type.properties.len() >= MIN_SUBSET_PROPERTIES
+                    && result
+                        .matched_properties
+                        .iter()
+                        .all(|property| property.similarity >= options.property_match_threshold);
+                if !covered {
+                    continue;
+                }
+                // Declaration order of the local type
+                Some(local_type.properties.iter().map(|property| property.name.clone()).collect())
+            };
+            let better = best.as_ref().is_none_or(|best| {
+                (picked.is_none(), result.similarity) > (best.picked.is_none(), best.similarity)
+            });
+            if better {
+                best = Some(DependencyTypeMatch {
+                    local: local_type.clone(),
+                    package: package.clone(),
+                    dependency: dependency.clone(),
+                    similarity: result.similarity,
+                    picked,
+                });
+            }
+        }
+        matches.extend(best);
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_extractor::extract_types_from_code;
+
+    const AXIOS: &str = r#"
+export interface AxiosRequestConfig<D = any> {
+  url?: string;
+  method?: string;
+  baseURL?: string;
+  headers?: Record<string, string>;
+  params?: any;
+  data?: D;
+  timeout?: number;
+  withCredentials?: boolean;
+}
+
+export interface AxiosProxyConfig {
+  host: string;
+  port: number;
+  protocol?: string;
+}
+"#;
+
+    #[test]
+    fn test_whole_and_partial_copies_of_dependency_types() {
+        let dependencies: Vec<(String, TypeDefinition)> =
+            extract_types_from_code(AXIOS, "node_modules/axios/index.d.ts")
+                .unwrap()
+                .into_iter()
+                .map(|t| ("axios".to_string(), t))
+                .collect();
+        let local = extract_types_from_code(
+            r#"
+interface ProxySettings {
+  host: string;
+  port: number;
+  protocol?: string;
+}
+
+type RequestOptions = {
+  url?: string;
+  method?: string;
+  timeout?: number;
+};
+
+interface Pair {
+  url?: string;
+  retries: number;
+  backoff: number;
+}
+"#,
+            "src/http.ts",
+        )
+        .unwrap();
+
+        let matches = find_dependency_type_matches(
+            &local,
+            &dependencies,
+            0.87,
+            &TypeComparisonOptions::default(),
+            &ComparisonBudget::unlimited(),
+        );
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].local.name.as_str(), "ProxySettings");
+        assert_eq!(matches[0].replacement(), "AxiosProxyConfig");
+        assert_eq!(matches[0].import(), r#"import type { AxiosProxyConfig } from "axios";"#);
+        assert_eq!(matches[1].local.name.as_str(), "RequestOptions");
+        assert_eq!(
+            matches[1].replacement(),
+            r#"Pick<AxiosRequestConfig, "url" | "method" | "timeout">"#
+        );
+    }
+
+    #[test]
+    fn test_packages_fall_back_to_types_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let node_modules = dir.path().join("node_modules");
+        fs::create_dir_all(node_modules.join("axios/lib")).unwrap();
+        fs::write(node_modules.join("axios/index.d.ts"), AXIOS).unwrap();
+        fs::write(node_modules.join("axios/lib/core.d.mts"), "").unwrap();
+        fs::create_dir_all(node_modules.join("axios/node_modules/inner")).unwrap();
+        fs::write(node_modules.join("axios/node_modules/inner/index.d.ts"), "").unwrap();
+        fs::create_dir_all(node_modules.join("lodash")).unwrap();
+        fs::write(node_modules.join("lodash/index.js"), "").unwrap();
+        fs::create_dir_all(node_modules.join("@types/lodash")).unwrap();
+        fs::write(node_modules.join("@types/lodash/index.d.ts"), "").unwrap();
+        let start = dir.path().join("packages/app/src");
+        fs::create_dir_all(&start).unwrap();
+
+        let axios = find_dependency_package(&start, "axios").unwrap();
+        assert_eq!(axios.dir, node_modules.join("axios"));
+        assert_eq!(
+            declaration_files(&axios.dir),
+            [node_modules.join("axios/index.d.ts"), node_modules.join("axios/lib/core.d.mts")]
+        );
+        let lodash = find_dependency_package(&start, "lodash").unwrap();
+        assert_eq!(lodash.dir, node_modules.join("@types/lodash"));
+        assert!(find_dependency_package(&start, "react").is_none());
+    }
+}
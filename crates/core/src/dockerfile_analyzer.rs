@@ -0,0 +1,498 @@
+//! Duplicate detection for Dockerfiles.
+//!
+//! Every build stage (`FROM` up to the next `FROM`) is turned into a tree of
+//! its instructions and compared with TSED. Normalization makes stages that
+//! build the same image compare equal:
+//!
+//! - continuation lines and heredocs are joined, comments are dropped and
+//!   instruction keywords are case-folded
+//! - layers do not matter: consecutive `RUN` instructions become one node whose
+//!   children are the individual commands (split at `&&`, `||` and `;`), and
+//!   consecutive `ENV`, `ARG`, `LABEL` and `EXPOSE` entries are merged and sorted
+//! - base image tags and digests are dropped, exec-form arguments are
+//!   rendered as shell words and package lists of install commands are sorted
+
+use crate::analyzer::{AnalysisUnit, Analyzer, AnalyzerError, AnalyzerFinding};
+use crate::cli_output::format_function_output;
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, TSEDOptions};
+use crate::APTEDOptions;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Stages with fewer instructions (e.g. `FROM scratch` + `COPY`) are not reported
+pub const DEFAULT_DOCKERFILE_MIN_INSTRUCTIONS: usize = 3;
+
+/// Instructions whose consecutive entries form one unordered set
+const MERGED_INSTRUCTIONS: &[&str] = &["ENV", "ARG", "LABEL", "EXPOSE"];
+
+/// Package manager subcommands whose arguments are an unordered package list
+const INSTALL_COMMANDS: &[(&str, &str)] = &[
+    ("apt-get", "install"),
+    ("apt", "install"),
+    ("apk", "add"),
+    ("yum", "install"),
+    ("dnf", "install"),
+    ("pip", "install"),
+    ("pip3", "install"),
+    ("npm", "install"),
+    ("gem", "install"),
+];
+
+/// An instruction with its continuation lines joined
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    /// Upper-case keyword such as `RUN`
+    pub keyword: String,
+    pub arguments: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl Instruction {
+    /// Normalized text of the instruction, as used for comparison
+    #[must_use]
+    pub fn normalized(&self) -> String {
+        let arguments = match self.keyword.as_str() {
+            "FROM" => base_image(&self.arguments).to_string(),
+            "RUN" => split_commands(&self.arguments).join(" && "),
+            _ => shell_words(&self.arguments),
+        };
+        format!("{} {}", self.keyword, arguments)
+    }
+}
+
+/// Split a Dockerfile into instructions
+#[must_use]
+pub fn parse_instructions(source: &str) -> Vec<Instruction> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut instructions = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index].trim();
+        index += 1;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let start_line = index;
+
+        // Join continuation lines; comments between them are skipped
+        let mut text = String::new();
+        let mut current = line;
+        loop {
+            match current.strip_suffix('\\') {
+                Some(head) if index < lines.len() => {
+                    text.push_str(head.trim_end());
+                    text.push(' ');
+                    while index < lines.len() && lines[index].trim_start().starts_with('#') {
+                        index += 1;
+                    }
+                    current = lines.get(index).map_or("", |line| line.trim());
+                    index += 1;
+                }
+                _ => {
+                    text.push_str(current.strip_suffix('\\').unwrap_or(current));
+                    break;
+                }
+            }
+        }
+
+        // Heredoc bodies (`RUN <<EOF ... EOF`) are part of the instruction
+        if let Some(delimiter) = heredoc_delimiter(&text) {
+            while index < lines.len() {
+                let body = lines[index].trim();
+                index += 1;
+                if body == delimiter {
+                    break;
+                }
+                text.push('\n');
+                text.push_str(body);
+            }
+        }
+
+        let (keyword, arguments) = text.split_once(char::is_whitespace).unwrap_or((&text, ""));
+        instructions.push(Instruction {
+            keyword: keyword.to_ascii_uppercase(),
+            arguments: arguments.trim().to_string(),
+            start_line,
+            end_line: index.min(lines.len()),
+        });
+    }
+    instructions
+}
+
+fn heredoc_delimiter(text: &str) -> Option<String> {
+    let (_, rest) = text.split_once("<<")?;
+    let rest = rest.strip_prefix('-').unwrap_or(rest);
+    let word: String = rest
+        .trim_start_matches(['"', '\''])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    (!word.is_empty()).then_some(word)
+}
+
+/// Image of a `FROM` instruction without tag, digest, platform and stage name
+fn base_image(arguments: &str) -> &str {
+    let image = arguments.split_whitespace().find(|word| !word.starts_with("--")).unwrap_or("");
+    let image = image.split('@').next().unwrap_or(image);
+    // A colon after the last slash starts the tag; one before it is a registry port
+    match image.rfind(':') {
+        Some(colon) if image.rfind('/').is_none_or(|slash| colon > slash) => &image[..colon],
+        _ => image,
+    }
+}
+
+/// Stage name of a `FROM` instruction (`FROM node AS build`)
+fn stage_name(arguments: &str) -> Option<&str> {
+    let words: Vec<&str> = arguments.split_whitespace().collect();
+    words
+        .iter()
+        .position(|word| word.eq_ignore_ascii_case("as"))
+        .and_then(|position| words.get(position + 1))
+        .copied()
+}
+
+/// Arguments as whitespace-separated words, with exec form (`["a", "b"]`) unwrapped
+fn shell_words(arguments: &str) -> String {
+    if let Ok(words) = serde_json::from_str::<Vec<String>>(arguments) {
+        return words.join(" ");
+    }
+    arguments.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Commands of a `RUN` instruction, each normalized
+fn split_commands(arguments: &str) -> Vec<String> {
+    let script = shell_words_preserving_lines(arguments);
+    script
+        .split(['\n', ';'])
+        .flat_map(|part| part.split("&&"))
+        .flat_map(|part| part.split("||"))
+        .map(normalize_command)
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+fn shell_words_preserving_lines(arguments: &str) -> String {
+    if let Ok(words) = serde_json::from_str::<Vec<String>>(arguments) {
+        return words.join(" ");
+    }
+    arguments.to_string()
+}
+
+/// Collapse whitespace and sort the package list of install commands
+fn normalize_command(command: &str) -> String {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let install = words.windows(2).position(|pair| INSTALL_COMMANDS.contains(&(pair[0], pair[1])));
+    match install {
+        Some(position) => {
+            let (head, packages) = words.split_at(position + 2);
+            let (mut flags, mut packages): (Vec<&str>, Vec<&str>) =
+                packages.iter().partition(|word| word.starts_with('-'));
+            flags.sort_unstable();
+            packages.sort_unstable();
+            [head, &flags, &packages].concat().join(" ")
+        }
+        None => words.join(" "),
+    }
+}
+
+/// Tree of a stage with layer boundaries removed
+fn stage_tree(instructions: &[Instruction]) -> TreeNode {
+    let mut next_id = 0;
+    let mut node = |label: &str, value: String| {
+        next_id += 1;
+        TreeNode::new(label.to_string(), value, next_id - 1)
+    };
+
+    let mut root = node("stage", base_image(&instructions[0].arguments).to_string());
+    let mut index = 1;
+    while index < instructions.len() {
+        let keyword = instructions[index].keyword.as_str();
+        let run_end = instructions[index..]
+            .iter()
+            .position(|instruction| instruction.keyword != keyword)
+            .map_or(instructions.len(), |offset| index + offset);
+        let group = &instructions[index..run_end];
+
+        if keyword == "RUN" {
+            let mut run = node("RUN", String::new());
+            for command in
+                group.iter().flat_map(|instruction| split_commands(&instruction.arguments))
+            {
+                run.add_child(Rc::new(node("command", command)));
+            }
+            root.add_child(Rc::new(run));
+            index = run_end;
+        } else if MERGED_INSTRUCTIONS.contains(&keyword) {
+            let mut entries: Vec<String> = group
+                .iter()
+                .flat_map(|instruction| {
+                    shell_words(&instruction.arguments)
+                        .split(' ')
+                        .map(String::from)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|entry| !entry.is_empty())
+                .collect();
+            entries.sort_unstable();
+            let mut merged = node(keyword, String::new());
+            for entry in entries {
+                merged.add_child(Rc::new(node("entry", entry)));
+            }
+            root.add_child(Rc::new(merged));
+            index = run_end;
+        } else {
+            root.add_child(Rc::new(node(keyword, shell_words(&instructions[index].arguments))));
+            index += 1;
+        }
+    }
+    root
+}
+
+/// Analyzer for Dockerfiles, comparing build stages
+pub struct DockerfileAnalyzer {
+    options: TSEDOptions,
+    min_instructions: usize,
+    /// Normalized instructions of each extracted stage, by file and start line
+    stages: HashMap<(String, u32), Vec<String>>,
+}
+
+impl DockerfileAnalyzer {
+    pub fn new() -> Self {
+        Self::with_options(
+            TSEDOptions {
+                apted_options: APTEDOptions {
+                    compare_values: true,
+                    ..TSEDOptions::default().apted_options
+                },
+                min_lines: 1,
+                size_penalty: false,
+                ..TSEDOptions::default()
+            },
+            DEFAULT_DOCKERFILE_MIN_INSTRUCTIONS,
+        )
+    }
+
+    /// Use custom TSED options; stages with fewer than `min_instructions` are not compared
+    pub fn with_options(options: TSEDOptions, min_instructions: usize) -> Self {
+        Self { options, min_instructions, stages: HashMap::new() }
+    }
+
+    /// Suggestion for consolidating a pair of stages, based on the
+    /// instructions they start with
+    fn consolidation_hint(&self, finding: &AnalyzerFinding) -> String {
+        let stage = |unit: &crate::analyzer::UnitLocation| {
+            self.stages.get(&(unit.file.clone(), unit.start_line)).cloned().unwrap_or_default()
+        };
+        let (first, second) = (stage(&finding.first), stage(&finding.second));
+        let shared = first.iter().zip(&second).take_while(|(a, b)| a == b).count();
+
+        if shared < 2 {
+            return "build both from one Dockerfile, passing what differs as build arguments (ARG)"
+                .to_string();
+        }
+        let range = format!("'{}' through '{}'", first[0], first[shared - 1]);
+        if finding.first.file == finding.second.file {
+            format!(
+                "move the {} shared leading instructions ({}) into a common stage and start both stages FROM it",
+                shared, range
+            )
+        } else {
+            format!(
+                "build the {} shared leading instructions ({}) once as a base image and use it in FROM of both Dockerfiles",
+                shared, range
+            )
+        }
+    }
+}
+
+impl Default for DockerfileAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Analyzer for DockerfileAnalyzer {
+    fn name(&self) -> &str {
+        "dockerfile"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dockerfile"]
+    }
+
+    /// `Dockerfile`, `Dockerfile.prod` and `api.dockerfile`
+    fn matches_file(&self, path: &Path) -> bool {
+        path.file_name().and_then(|name| name.to_str()).is_some_and(|name| {
+            let name = name.to_ascii_lowercase();
+            name == "dockerfile" || name.starts_with("dockerfile.") || name.ends_with(".dockerfile")
+        })
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let instructions = parse_instructions(source);
+        let starts: Vec<usize> = instructions
+            .iter()
+            .enumerate()
+            .filter(|(_, instruction)| instruction.keyword == "FROM")
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut units = Vec::new();
+        for (number, &start) in starts.iter().enumerate() {
+            let end = starts.get(number + 1).copied().unwrap_or(instructions.len());
+            let stage = &instructions[start..end];
+            if stage.len() < self.min_instructions {
+                continue;
+            }
+            let name = stage_name(&stage[0].arguments)
+                .map_or_else(|| format!("stage {}", number + 1), |name| format!("stage {}", name));
+            let start_line = stage[0].start_line as u32;
+            self.stages.insert(
+                (file.to_string(), start_line),
+                stage.iter().map(Instruction::normalized).collect(),
+            );
+            units.push(AnalysisUnit {
+                name,
+                file: file.to_string(),
+                start_line,
+                end_line: stage[stage.len() - 1].end_line as u32,
+                tree: Rc::new(stage_tree(stage)),
+                structure: None,
+            });
+        }
+        Ok(units)
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        calculate_tsed(&first.tree, &second.tree, &self.options)
+    }
+
+    fn report(&self, findings: &[AnalyzerFinding], out: &mut dyn Write) -> io::Result<()> {
+        if findings.is_empty() {
+            return writeln!(out, "No duplicated Dockerfile stages found.");
+        }
+
+        writeln!(out, "Duplicated Dockerfile stages:")?;
+        writeln!(out, "{}", "-".repeat(60))?;
+        for finding in findings {
+            let (first, second) = (&finding.first, &finding.second);
+            writeln!(out, "\nSimilarity: {:.2}%", finding.similarity * 100.0)?;
+            writeln!(
+                out,
+                "  {}",
+                format_function_output(&first.file, &first.name, first.start_line, first.end_line)
+            )?;
+            writeln!(
+                out,
+                "  {}",
+                format_function_output(
+                    &second.file,
+                    &second.name,
+                    second.start_line,
+                    second.end_line
+                )
+            )?;
+            writeln!(out, "  Suggestion: {}", self.consolidation_hint(finding))?;
+        }
+        writeln!(out, "\nTotal duplicated stage pairs: {}", findings.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::run_analyzer;
+
+    const API: &str = r#"# syntax=docker/dockerfile:1
+FROM node:18-alpine AS build
+WORKDIR /app
+COPY package.json package-lock.json ./
+RUN apk add --no-cache python3 make g++ \
+    && npm ci
+ENV NODE_ENV=production PORT=3000
+COPY . .
+RUN npm run build
+
+FROM nginx:1.25
+COPY --from=build /app/dist /usr/share/nginx/html
+EXPOSE 80
+CMD ["nginx", "-g", "daemon off;"]
+"#;
+
+    #[test]
+    fn test_parses_instructions_with_continuations_and_heredocs() {
+        let source = "from ubuntu:22.04\nRUN apt-get update \\\n    # refresh\n    && apt-get install -y curl\nRUN <<EOF\nset -e\necho done\nEOF\nCMD [\"bash\"]\n";
+        let instructions = parse_instructions(source);
+        let summary: Vec<_> =
+            instructions.iter().map(|i| (i.keyword.as_str(), i.start_line, i.end_line)).collect();
+        assert_eq!(summary, vec![("FROM", 1, 1), ("RUN", 2, 4), ("RUN", 5, 8), ("CMD", 9, 9)]);
+        assert_eq!(instructions[1].arguments, "apt-get update && apt-get install -y curl");
+        assert_eq!(
+            split_commands(&instructions[2].arguments),
+            vec!["<<EOF", "set -e", "echo done"]
+        );
+        assert_eq!(instructions[3].normalized(), "CMD bash");
+
+        assert_eq!(base_image("--platform=linux/amd64 node:18-alpine AS build"), "node");
+        assert_eq!(base_image("registry:5000/team/app@sha256:abc"), "registry:5000/team/app");
+        assert_eq!(stage_name("node:18 as build"), Some("build"));
+        assert_eq!(
+            normalize_command("apt-get install  -y  wget curl"),
+            "apt-get install -y curl wget"
+        );
+    }
+
+    #[test]
+    fn test_layer_splits_and_tags_do_not_matter() {
+        // Same build as the first stage of API, with RUN and ENV split into
+        // separate layers, packages reordered and another base image tag
+        let split = r#"FROM node:20-alpine AS builder
+workdir /app
+COPY package.json package-lock.json ./
+RUN apk add --no-cache g++ make python3
+RUN npm ci
+ENV PORT=3000
+ENV NODE_ENV=production
+COPY . .
+RUN npm run build
+"#;
+        let mut analyzer = DockerfileAnalyzer::new();
+        let first = analyzer.extract("api/Dockerfile", API).unwrap();
+        let second = analyzer.extract("web/Dockerfile", split).unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].name, "stage build");
+        assert_eq!((first[1].start_line, first[1].end_line), (11, 14));
+        assert!((analyzer.compare(&first[0], &second[0]) - 1.0).abs() < 1e-9);
+        assert!(analyzer.compare(&first[0], &first[1]) < 0.7);
+
+        let files = vec![
+            ("api/Dockerfile".to_string(), API.to_string()),
+            ("web/Dockerfile".to_string(), split.to_string()),
+        ];
+        let findings = run_analyzer(&mut analyzer, &files, 0.9).unwrap();
+        assert_eq!(findings.len(), 1);
+        let mut report = Vec::new();
+        analyzer.report(&findings, &mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(
+            report.contains(
+                "Suggestion: build the 3 shared leading instructions ('FROM node' through 'COPY package.json package-lock.json ./') once as a base image"
+            ),
+            "{report}"
+        );
+    }
+
+    #[test]
+    fn test_matches_dockerfile_names() {
+        let analyzer = DockerfileAnalyzer::new();
+        assert!(analyzer.matches_file(Path::new("services/api/Dockerfile")));
+        assert!(analyzer.matches_file(Path::new("Dockerfile.prod")));
+        assert!(analyzer.matches_file(Path::new("worker.dockerfile")));
+        assert!(!analyzer.matches_file(Path::new("docker-compose.yml")));
+    }
+}
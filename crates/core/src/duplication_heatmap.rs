@@ -0,0 +1,181 @@
+use crate::output_schema::HeatmapReport;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Per-file duplication density, suitable for rendering a treemap
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialEq)]
+pub struct FileHeatmapEntry {
+    /// Path relative to the working directory
+    pub file: String,
+    pub total_lines: usize,
+    /// Lines covered by at least one finding
+    pub duplicated_lines: usize,
+    /// duplicated_lines / total_lines (0.0 when the file is empty)
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub density: f64,
+    /// Number of findings touching this file, keyed by analyzer name
+    pub findings: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Default)]
+struct FileRecord {
+    total_lines: Option<usize>,
+    ranges: Vec<(usize, usize)>,
+    findings: BTreeMap<String, usize>,
+}
+
+/// Collects duplicated line ranges per file across analyzers
+#[derive(Debug, Default)]
+pub struct DuplicationHeatmap {
+    files: BTreeMap<String, FileRecord>,
+}
+
+impl DuplicationHeatmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an analyzed file so it shows up even without findings
+    pub fn add_file(&mut self, file: &str) {
+        self.files.entry(file.to_string()).or_default();
+    }
+
+    /// Set the total line count of a file instead of reading it from disk
+    pub fn set_total_lines(&mut self, file: &str, total_lines: usize) {
+        self.files.entry(file.to_string()).or_default().total_lines = Some(total_lines);
+    }
+
+    /// Record one finding that spans two (possibly identical) files
+    pub fn record_pair(
+        &mut self,
+        analyzer: &str,
+        (file1, start1, end1): (&str, usize, usize),
+        (file2, start2, end2): (&str, usize, usize),
+    ) {
+        self.record_range(file1, start1, end1);
+        self.record_range(file2, start2, end2);
+
+        self.increment_findings(file1, analyzer);
+        if file1 != file2 {
+            self.increment_findings(file2, analyzer);
+        }
+    }
+
+    fn record_range(&mut self, file: &str, start_line: usize, end_line: usize) {
+        let (start, end) = (start_line.min(end_line), start_line.max(end_line));
+        self.files.entry(file.to_string()).or_default().ranges.push((start, end));
+    }
+
+    fn increment_findings(&mut self, file: &str, analyzer: &str) {
+        let record = self.files.entry(file.to_string()).or_default();
+        *record.findings.entry(analyzer.to_string()).or_insert(0) += 1;
+    }
+
+    /// Build per-file entries, merging overlapping ranges so lines are counted once
+    pub fn entries(&self) -> Vec<FileHeatmapEntry> {
+        self.files
+            .iter()
+            .map(|(file, record)| {
+                let total_lines = record.total_lines.unwrap_or_else(|| {
+                    fs::read_to_string(file).map(|content| content.lines().count()).unwrap_or(0)
+                });
+                let duplicated_lines = count_covered_lines(&record.ranges).min(total_lines);
+                let density = if total_lines > 0 {
+                    duplicated_lines as f64 / total_lines as f64
+                } else {
+                    0.0
+                };
+
+                FileHeatmapEntry {
+                    file: file.clone(),
+                    total_lines,
+                    duplicated_lines,
+                    density,
+                    findings: record.findings.clone(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&HeatmapReport::new(self.entries()))
+    }
+
+    /// Write the heatmap as JSON to the given path
+    pub fn write_json(&self, path: &str) -> anyhow::Result<()> {
+        fs::write(path, self.to_json()?)
+            .map_err(|error| anyhow::anyhow!("Failed to write {}: {}", path, error))
+    }
+}
+
+/// Count lines covered by a set of inclusive ranges
+fn count_covered_lines(ranges: &[(usize, usize)]) -> usize {
+    let mut sorted = ranges.to_vec();
+    sorted.sort_unstable();
+
+    let mut covered = 0;
+    let mut current: Option<(usize, usize)> = None;
+    for (start, end) in sorted {
+        match current {
+            Some((cur_start, cur_end)) if start <= cur_end + 1 => {
+                current = Some((cur_start, cur_end.max(end)));
+            }
+            Some((cur_start, cur_end)) => {
+                covered += cur_end - cur_start + 1;
+                current = Some((start, end));
+            }
+            None => current = Some((start, end)),
+        }
+    }
+    if let Some((start, end)) = current {
+        covered += end - start + 1;
+    }
+
+    covered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_overlapping_ranges() {
+        assert_eq!(count_covered_lines(&[(1, 5), (3, 8), (10, 10)]), 9);
+        assert_eq!(count_covered_lines(&[(4, 6), (1, 3)]), 6);
+        assert_eq!(count_covered_lines(&[]), 0);
+    }
+
+    #[test]
+    fn computes_density_and_counts_by_analyzer() {
+        let mut heatmap = DuplicationHeatmap::new();
+        heatmap.set_total_lines("a.ts", 20);
+        heatmap.set_total_lines("b.ts", 10);
+        heatmap.set_total_lines("c.ts", 5);
+
+        heatmap.record_pair("functions", ("a.ts", 1, 5), ("b.ts", 1, 5));
+        heatmap.record_pair("functions", ("a.ts", 3, 10), ("a.ts", 11, 15));
+        heatmap.record_pair("types", ("b.ts", 7, 8), ("a.ts", 16, 17));
+        heatmap.add_file("c.ts");
+
+        let entries = heatmap.entries();
+        assert_eq!(entries.len(), 3);
+
+        let a = &entries[0];
+        assert_eq!(a.file, "a.ts");
+        assert_eq!(a.duplicated_lines, 17);
+        assert!((a.density - 0.85).abs() < 1e-9);
+        assert_eq!(a.findings.get("functions"), Some(&2));
+        assert_eq!(a.findings.get("types"), Some(&1));
+
+        let b = &entries[1];
+        assert_eq!(b.duplicated_lines, 7);
+        assert_eq!(b.findings.get("functions"), Some(&1));
+
+        let c = &entries[2];
+        assert_eq!(c.duplicated_lines, 0);
+        assert_eq!(c.density, 0.0);
+        assert!(c.findings.is_empty());
+    }
+}
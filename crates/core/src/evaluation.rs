@@ -0,0 +1,290 @@
+//! Calibration of similarity scores against labeled clone pairs.
+//!
+//! A dataset is a `pairs.toml` manifest in the style of BigCloneBench: each
+//! entry names two files, each holding one code fragment, and the clone type
+//! of the pair (`type1` to `type4`, or `none` for unrelated code). Every pair
+//! is scored like the function analyzer scores two functions; scores at or
+//! above the threshold count as detected.
+//! Recall is reported per clone type, and precision per clone type counts the
+//! detected clones of that type against all detected `none` pairs.
+
+use crate::function_extractor::{compare_functions, extract_functions};
+use crate::tsed::{calculate_tsed_from_code, TSEDOptions};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Manifest file name looked up when a dataset is given as a directory
+pub const DATASET_MANIFEST: &str = "pairs.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloneType {
+    /// Identical apart from whitespace, layout and comments
+    Type1,
+    /// Identical structure with renamed identifiers, literals and types
+    Type2,
+    /// Copied with statements added, removed or modified
+    Type3,
+    /// Same behavior, different implementation
+    Type4,
+    /// Unrelated code
+    None,
+}
+
+impl CloneType {
+    pub const CLONES: [CloneType; 4] =
+        [CloneType::Type1, CloneType::Type2, CloneType::Type3, CloneType::Type4];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CloneType::Type1 => "type1",
+            CloneType::Type2 => "type2",
+            CloneType::Type3 => "type3",
+            CloneType::Type4 => "type4",
+            CloneType::None => "none",
+        }
+    }
+}
+
+impl fmt::Display for CloneType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Two files and the clone type of their code
+#[derive(Debug, Clone, Deserialize)]
+pub struct LabeledPair {
+    pub first: PathBuf,
+    pub second: PathBuf,
+    pub clone: CloneType,
+}
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    pairs: Vec<LabeledPair>,
+}
+
+/// Labeled pairs with paths resolved against the manifest directory
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub pairs: Vec<LabeledPair>,
+}
+
+impl Dataset {
+    /// Load a dataset from a manifest, or a directory holding `pairs.toml`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let manifest_path =
+            if path.is_dir() { path.join(DATASET_MANIFEST) } else { path.to_path_buf() };
+        let content = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("failed to read {}", manifest_path.display()))?;
+        let manifest: Manifest = toml::from_str(&content)
+            .with_context(|| format!("failed to parse {}", manifest_path.display()))?;
+
+        let root = manifest_path.parent().unwrap_or(Path::new("."));
+        let pairs = manifest
+            .pairs
+            .into_iter()
+            .map(|pair| LabeledPair {
+                first: root.join(pair.first),
+                second: root.join(pair.second),
+                clone: pair.clone,
+            })
+            .collect();
+        Ok(Self { pairs })
+    }
+}
+
+/// The score of a labeled pair
+#[derive(Debug, Clone)]
+pub struct PairResult {
+    pub pair: LabeledPair,
+    pub similarity: f64,
+}
+
+/// Detection counts for one clone type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeStats {
+    pub pairs: usize,
+    pub detected: usize,
+}
+
+impl TypeStats {
+    /// Share of the pairs detected, `None` without pairs
+    #[must_use]
+    pub fn recall(&self) -> Option<f64> {
+        (self.pairs > 0).then(|| self.detected as f64 / self.pairs as f64)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EvaluationReport {
+    pub threshold: f64,
+    pub results: Vec<PairResult>,
+}
+
+impl EvaluationReport {
+    #[must_use]
+    pub fn stats(&self, clone: CloneType) -> TypeStats {
+        let mut stats = TypeStats::default();
+        for result in self.results.iter().filter(|result| result.pair.clone == clone) {
+            stats.pairs += 1;
+            if result.similarity >= self.threshold {
+                stats.detected += 1;
+            }
+        }
+        stats
+    }
+
+    /// Clone pairs of any type, detected or not
+    #[must_use]
+    pub fn clone_stats(&self) -> TypeStats {
+        CloneType::CLONES.iter().fold(TypeStats::default(), |total, &clone| {
+            let stats = self.stats(clone);
+            TypeStats {
+                pairs: total.pairs + stats.pairs,
+                detected: total.detected + stats.detected,
+            }
+        })
+    }
+
+    /// Share of detected pairs that are clones, counting the clones of `clone`
+    /// only, or of every type when `None`; `None` when nothing was detected
+    #[must_use]
+    pub fn precision(&self, clone: Option<CloneType>) -> Option<f64> {
+        let detected = clone.map_or_else(|| self.clone_stats(), |clone| self.stats(clone)).detected;
+        let false_positives = self.stats(CloneType::None).detected;
+        (detected + false_positives > 0)
+            .then(|| detected as f64 / (detected + false_positives) as f64)
+    }
+
+    /// Pairs on the wrong side of the threshold: missed clones and detected `none` pairs
+    pub fn misclassified(&self) -> impl Iterator<Item = &PairResult> {
+        self.results.iter().filter(|result| {
+            (result.similarity >= self.threshold) == (result.pair.clone == CloneType::None)
+        })
+    }
+}
+
+fn percent(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| format!("{:.1}%", value * 100.0))
+}
+
+impl fmt::Display for EvaluationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Threshold: {:.2}", self.threshold)?;
+        writeln!(
+            f,
+            "{:<8} {:>6} {:>9} {:>8} {:>10}",
+            "Type", "Pairs", "Detected", "Recall", "Precision"
+        )?;
+        for clone in CloneType::CLONES {
+            let stats = self.stats(clone);
+            writeln!(
+                f,
+                "{:<8} {:>6} {:>9} {:>8} {:>10}",
+                clone,
+                stats.pairs,
+                stats.detected,
+                percent(stats.recall()),
+                percent(self.precision(Some(clone)))
+            )?;
+        }
+        let stats = self.clone_stats();
+        writeln!(
+            f,
+            "{:<8} {:>6} {:>9} {:>8} {:>10}",
+            "all",
+            stats.pairs,
+            stats.detected,
+            percent(stats.recall()),
+            percent(self.precision(None))
+        )?;
+        let none = self.stats(CloneType::None);
+        write!(f, "False positives: {} of {} unrelated pairs", none.detected, none.pairs)
+    }
+}
+
+/// Score every pair of `dataset`
+///
+/// # Errors
+///
+/// Returns an error when a file of a pair cannot be read or parsed
+pub fn evaluate(
+    dataset: &Dataset,
+    threshold: f64,
+    options: &TSEDOptions,
+) -> Result<EvaluationReport> {
+    let mut results = Vec::with_capacity(dataset.pairs.len());
+    for pair in &dataset.pairs {
+        let similarity = score_pair(&pair.first, &pair.second, options)?;
+        results.push(PairResult { pair: pair.clone(), similarity });
+    }
+    Ok(EvaluationReport { threshold, results })
+}
+
+/// Similarity of two fragments as the function analyzer sees it: the first
+/// function of each file is compared, whole files when a file has none
+fn score_pair(first: &Path, second: &Path, options: &TSEDOptions) -> Result<f64> {
+    let read = |path: &Path| {
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+    };
+    let (source1, source2) = (read(first)?, read(second)?);
+    let (name1, name2) = (first.to_string_lossy(), second.to_string_lossy());
+    let error = |e: String| anyhow::anyhow!("{} / {}: {}", name1, name2, e);
+
+    let functions1 = extract_functions(&name1, &source1).map_err(error)?;
+    let functions2 = extract_functions(&name2, &source2).map_err(error)?;
+    match (functions1.first(), functions2.first()) {
+        (Some(func1), Some(func2)) => {
+            compare_functions(func1, func2, &source1, &source2, options).map_err(error)
+        }
+        _ => calculate_tsed_from_code(&source1, &source2, &name1, &name2, options).map_err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(clone: CloneType, similarity: f64) -> PairResult {
+        PairResult {
+            pair: LabeledPair { first: "a.ts".into(), second: "b.ts".into(), clone },
+            similarity,
+        }
+    }
+
+    #[test]
+    fn test_precision_and_recall() {
+        let report = EvaluationReport {
+            threshold: 0.8,
+            results: vec![
+                result(CloneType::Type1, 1.0),
+                result(CloneType::Type2, 0.9),
+                result(CloneType::Type3, 0.6),
+                result(CloneType::None, 0.85),
+                result(CloneType::None, 0.3),
+            ],
+        };
+
+        assert_eq!(report.stats(CloneType::Type3), TypeStats { pairs: 1, detected: 0 });
+        assert_eq!(report.clone_stats().recall(), Some(2.0 / 3.0));
+        assert_eq!(report.precision(None), Some(2.0 / 3.0));
+        assert_eq!(report.precision(Some(CloneType::Type1)), Some(0.5));
+        assert_eq!(report.stats(CloneType::Type4).recall(), None);
+
+        let misclassified: Vec<CloneType> =
+            report.misclassified().map(|result| result.pair.clone).collect();
+        assert_eq!(misclassified, [CloneType::Type3, CloneType::None]);
+
+        let table = report.to_string();
+        assert!(table.contains("type3         1         0     0.0%"));
+        assert!(table.ends_with("False positives: 1 of 2 unrelated pairs"));
+    }
+}
@@ -1,6 +1,9 @@
 use crate::ast_fingerprint::AstFingerprint;
-use crate::compare_functions;
-use crate::function_extractor::{extract_functions, FunctionDefinition, SimilarityResult};
+use crate::compare_functions_with_algorithm;
+use crate::comparison_budget::ComparisonBudget;
+use crate::function_extractor::{
+    extract_functions, log_pruned, FunctionDefinition, SimilarityResult,
+};
 use crate::tsed::TSEDOptions;
 
 /// Fast similarity options
@@ -39,6 +42,30 @@ pub fn find_similar_functions_fast(
     filename: &str,
     source_text: &str,
     options: &FastSimilarityOptions,
+) -> Result<Vec<SimilarityResult>, String> {
+    find_similar_functions_fast_with_budget(
+        filename,
+        source_text,
+        options,
+        &ComparisonBudget::unlimited(),
+    )
+}
+
+/// Log a function left out of all comparisons and the `reason` it was left out
+fn log_skipped_function(filename: &str, func: &FunctionDefinition, reason: &str) {
+    tracing::debug!(
+        function = %format!("{}:{}:{}", filename, func.name, func.start_line),
+        reason,
+        "skipped function"
+    );
+}
+
+/// Find similar functions using fingerprint pre-filtering, stopping when the budget runs out
+pub fn find_similar_functions_fast_with_budget(
+    filename: &str,
+    source_text: &str,
+    options: &FastSimilarityOptions,
+    budget: &ComparisonBudget,
 ) -> Result<Vec<SimilarityResult>, String> {
     // Extract functions
     let mut functions = extract_functions(filename, source_text)?;
@@ -52,11 +79,13 @@ pub fn find_similar_functions_fast(
             // If min_tokens is specified, use token count instead of line count
             let tokens = func.node_count.unwrap_or(0);
             if tokens < min_tokens {
+                log_skipped_function(filename, &func, "min_tokens");
                 continue;
             }
         } else {
             // Otherwise use line count
             if func.line_count() < options.tsed_options.min_lines {
+                log_skipped_function(filename, &func, "min_lines");
                 continue;
             }
         }
@@ -89,6 +118,7 @@ pub fn find_similar_functions_fast(
                 .might_be_similar(&func2.fingerprint, options.fingerprint_threshold)
             {
                 comparisons_skipped += 1;
+                log_pruned(filename, &func1.function, filename, &func2.function, "fingerprint");
                 continue;
             }
 
@@ -96,12 +126,17 @@ pub fn find_similar_functions_fast(
             let fp_similarity = func1.fingerprint.similarity(&func2.fingerprint);
             if fp_similarity < options.fingerprint_threshold {
                 comparisons_skipped += 1;
+                log_pruned(filename, &func1.function, filename, &func2.function, "fingerprint");
+                continue;
+            }
+
+            if !budget.try_compare() {
                 continue;
             }
 
             // Full comparison
             comparisons_made += 1;
-            let similarity = compare_functions(
+            let (similarity, algorithm) = compare_functions_with_algorithm(
                 &func1.function,
                 &func2.function,
                 source_text,
@@ -110,11 +145,14 @@ pub fn find_similar_functions_fast(
             )?;
 
             if similarity >= options.similarity_threshold {
-                similar_pairs.push(SimilarityResult::new(
-                    func1.function.clone(),
-                    func2.function.clone(),
-                    similarity,
-                ));
+                similar_pairs.push(
+                    SimilarityResult::new(
+                        func1.function.clone(),
+                        func2.function.clone(),
+                        similarity,
+                    )
+                    .with_algorithm(algorithm),
+                );
             }
         }
     }
@@ -143,6 +181,19 @@ pub fn find_similar_functions_fast(
 pub fn find_similar_functions_across_files_fast(
     files: &[(String, String)],
     options: &FastSimilarityOptions,
+) -> Result<Vec<(String, SimilarityResult, String)>, String> {
+    find_similar_functions_across_files_fast_with_budget(
+        files,
+        options,
+        &ComparisonBudget::unlimited(),
+    )
+}
+
+/// Cross-file variant of [`find_similar_functions_fast_with_budget`]
+pub fn find_similar_functions_across_files_fast_with_budget(
+    files: &[(String, String)],
+    options: &FastSimilarityOptions,
+    budget: &ComparisonBudget,
 ) -> Result<Vec<(String, SimilarityResult, String)>, String> {
     let mut all_functions = Vec::new();
 
@@ -155,11 +206,13 @@ pub fn find_similar_functions_across_files_fast(
                 // If min_tokens is specified, use token count instead of line count
                 let tokens = func.node_count.unwrap_or(0);
                 if tokens < min_tokens {
+                    log_skipped_function(filename, &func, "min_tokens");
                     continue;
                 }
             } else {
                 // Otherwise use line count
                 if func.line_count() < options.tsed_options.min_lines {
+                    log_skipped_function(filename, &func, "min_lines");
                     continue;
                 }
             }
@@ -201,6 +254,7 @@ pub fn find_similar_functions_across_files_fast(
                 .might_be_similar(&func2.fingerprint, options.fingerprint_threshold)
             {
                 comparisons_skipped += 1;
+                log_pruned(file1, &func1.function, file2, &func2.function, "fingerprint");
                 continue;
             }
 
@@ -208,12 +262,17 @@ pub fn find_similar_functions_across_files_fast(
             let fp_similarity = func1.fingerprint.similarity(&func2.fingerprint);
             if fp_similarity < options.fingerprint_threshold {
                 comparisons_skipped += 1;
+                log_pruned(file1, &func1.function, file2, &func2.function, "fingerprint");
+                continue;
+            }
+
+            if !budget.try_compare() {
                 continue;
             }
 
             // Full comparison
             comparisons_made += 1;
-            let similarity = compare_functions(
+            let (similarity, algorithm) = compare_functions_with_algorithm(
                 &func1.function,
                 &func2.function,
                 source1,
@@ -228,7 +287,8 @@ pub fn find_similar_functions_across_files_fast(
                         func1.function.clone(),
                         func2.function.clone(),
                         similarity,
-                    ),
+                    )
+                    .with_algorithm(algorithm),
                     file2.clone(),
                 ));
             }
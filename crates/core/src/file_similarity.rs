@@ -0,0 +1,363 @@
+//! Whole-file similarity for spotting near-duplicate files.
+//!
+//! Files are compared without parsing, so any text file takes part: copied
+//! configs, vendored code and forked modules show up before the AST analyzers
+//! run. Each file is reduced to a 64-bit simhash of its normalized token
+//! shingles; near-copies have fingerprints a few bits apart, and the
+//! similarity of two files is the share of bits their fingerprints agree on.
+//!
+//! File similarities roll up into directory clones: two directories whose
+//! files pair up one-to-one score the summed similarity of the pairs over
+//! their average file count, so unmatched files on either side lower it.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Tokens per shingle
+const SHINGLE_SIZE: usize = 3;
+
+/// Files with fewer tokens are too small for a meaningful fingerprint
+pub const DEFAULT_MIN_FILE_TOKENS: usize = 20;
+
+/// Simhash of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileFingerprint {
+    pub path: PathBuf,
+    pub simhash: u64,
+    pub tokens: usize,
+}
+
+impl FileFingerprint {
+    /// Fingerprint of `content`, `None` when it has fewer than `min_tokens` tokens
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>, content: &str, min_tokens: usize) -> Option<Self> {
+        let tokens = tokenize(content);
+        if tokens.len() < min_tokens.max(1) {
+            return None;
+        }
+        Some(Self { path: path.into(), simhash: simhash(&tokens), tokens: tokens.len() })
+    }
+
+    /// Share of fingerprint bits the two files agree on (0.0-1.0)
+    #[must_use]
+    pub fn similarity(&self, other: &FileFingerprint) -> f64 {
+        1.0 - f64::from((self.simhash ^ other.simhash).count_ones()) / 64.0
+    }
+}
+
+/// Two files whose fingerprints reach the threshold
+#[derive(Debug, Clone)]
+pub struct FileDuplicate<'a> {
+    pub first: &'a FileFingerprint,
+    pub second: &'a FileFingerprint,
+    pub similarity: f64,
+}
+
+/// Pairs of files at least `threshold` similar, most similar first
+#[must_use]
+pub fn find_similar_files(
+    fingerprints: &[FileFingerprint],
+    threshold: f64,
+) -> Vec<FileDuplicate<'_>> {
+    let mut duplicates = Vec::new();
+    for (i, first) in fingerprints.iter().enumerate() {
+        for second in &fingerprints[i + 1..] {
+            let similarity = first.similarity(second);
+            if similarity >= threshold {
+                duplicates.push(FileDuplicate { first, second, similarity });
+            }
+        }
+    }
+    duplicates.sort_by(|a, b| {
+        b.similarity.total_cmp(&a.similarity).then_with(|| a.first.path.cmp(&b.first.path))
+    });
+    duplicates
+}
+
+/// Two directories holding near-copies of each other's files
+#[derive(Debug, Clone)]
+pub struct DirectoryDuplicate<'a> {
+    pub first: &'a Path,
+    pub second: &'a Path,
+    pub similarity: f64,
+    /// Matched files, most similar first
+    pub files: Vec<FileDuplicate<'a>>,
+    pub only_in_first: Vec<&'a FileFingerprint>,
+    pub only_in_second: Vec<&'a FileFingerprint>,
+}
+
+impl DirectoryDuplicate<'_> {
+    /// Whether both directories lie within the directories of `other`
+    fn is_within(&self, other: &DirectoryDuplicate<'_>) -> bool {
+        (self.first.starts_with(other.first) && self.second.starts_with(other.second))
+            || (self.first.starts_with(other.second) && self.second.starts_with(other.first))
+    }
+}
+
+/// Pairs of directories at least `threshold` similar, most similar first.
+///
+/// Candidates are the ancestor directories of similar files, walked up in
+/// lockstep until the files share a directory. Each side needs two files or
+/// more, and a clone inside a reported clone (`a/x` and `b/x` when `a` and
+/// `b` are reported) is left out.
+#[must_use]
+pub fn find_similar_directories(
+    fingerprints: &[FileFingerprint],
+    threshold: f64,
+) -> Vec<DirectoryDuplicate<'_>> {
+    let mut members: HashMap<&Path, Vec<&FileFingerprint>> = HashMap::new();
+    for fingerprint in fingerprints {
+        for directory in fingerprint.path.ancestors().skip(1) {
+            members.entry(directory).or_default().push(fingerprint);
+        }
+    }
+
+    let mut candidates = BTreeSet::new();
+    for duplicate in find_similar_files(fingerprints, threshold) {
+        let mut first = duplicate.first.path.parent();
+        let mut second = duplicate.second.path.parent();
+        while let (Some(a), Some(b)) = (first, second) {
+            if a.starts_with(b) || b.starts_with(a) {
+                break;
+            }
+            candidates.insert(if a < b { (a, b) } else { (b, a) });
+            (first, second) = (a.parent(), b.parent());
+        }
+    }
+
+    let mut duplicates: Vec<DirectoryDuplicate> = candidates
+        .into_iter()
+        .filter(|(a, b)| members[a].len() >= 2 && members[b].len() >= 2)
+        .map(|(a, b)| match_directories(a, b, &members[a], &members[b], threshold))
+        .filter(|duplicate| duplicate.similarity >= threshold)
+        .collect();
+
+    // Outermost clones first, so the clones nested in them can be dropped
+    duplicates.sort_by(|a, b| {
+        let depth = |duplicate: &DirectoryDuplicate| duplicate.first.components().count();
+        depth(a)
+            .cmp(&depth(b))
+            .then(b.similarity.total_cmp(&a.similarity))
+            .then_with(|| (a.first, a.second).cmp(&(b.first, b.second)))
+    });
+    let mut reported: Vec<DirectoryDuplicate> = Vec::new();
+    for duplicate in duplicates {
+        if !reported.iter().any(|outer| duplicate.is_within(outer)) {
+            reported.push(duplicate);
+        }
+    }
+
+    reported
+        .sort_by(|a, b| b.similarity.total_cmp(&a.similarity).then_with(|| a.first.cmp(b.first)));
+    reported
+}
+
+/// Pair up the files of two directories, most similar pairs first
+fn match_directories<'a>(
+    first: &'a Path,
+    second: &'a Path,
+    first_files: &[&'a FileFingerprint],
+    second_files: &[&'a FileFingerprint],
+    threshold: f64,
+) -> DirectoryDuplicate<'a> {
+    let mut pairs = Vec::new();
+    for &file1 in first_files {
+        for &file2 in second_files {
+            let similarity = file1.similarity(file2);
+            if similarity >= threshold {
+                pairs.push(FileDuplicate { first: file1, second: file2, similarity });
+            }
+        }
+    }
+    pairs.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then_with(|| (&a.first.path, &a.second.path).cmp(&(&b.first.path, &b.second.path)))
+    });
+
+    let mut files: Vec<FileDuplicate> = Vec::new();
+    for pair in pairs {
+        let taken = files.iter().any(|matched| {
+            std::ptr::eq(matched.first, pair.first) || std::ptr::eq(matched.second, pair.second)
+        });
+        if !taken {
+            files.push(pair);
+        }
+    }
+
+    let unmatched = |directory: &[&'a FileFingerprint],
+                     side: fn(&FileDuplicate<'a>) -> &'a FileFingerprint| {
+        directory
+            .iter()
+            .copied()
+            .filter(|file| !files.iter().any(|matched| std::ptr::eq(side(matched), *file)))
+            .collect::<Vec<_>>()
+    };
+    let only_in_first = unmatched(first_files, |matched| matched.first);
+    let only_in_second = unmatched(second_files, |matched| matched.second);
+
+    let matched: f64 = files.iter().map(|matched| matched.similarity).sum();
+    let similarity = 2.0 * matched / (first_files.len() + second_files.len()) as f64;
+    DirectoryDuplicate { first, second, similarity, files, only_in_first, only_in_second }
+}
+
+/// Words and numbers, lowercased, and single punctuation characters;
+/// whitespace and layout are dropped
+fn tokenize(content: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in content.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            word.extend(c.to_lowercase());
+            continue;
+        }
+        if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+        if !c.is_whitespace() {
+            tokens.push(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+pub(crate) fn simhash(tokens: &[String]) -> u64 {
+    let mut counts: HashMap<u64, i64> = HashMap::new();
+    for shingle in tokens.windows(SHINGLE_SIZE.min(tokens.len())) {
+        *counts.entry(hash_shingle(shingle)).or_default() += 1;
+    }
+
+    let mut weights = [0i64; 64];
+    for (hash, count) in counts {
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += count;
+            } else {
+                *weight -= count;
+            }
+        }
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, &weight)| weight > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+fn hash_shingle(shingle: &[String]) -> u64 {
+    // FNV-1a keeps fingerprints stable across builds, unlike `DefaultHasher`
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in shingle.iter().flat_map(|token| token.bytes().chain([0])) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    // Mix the bits (splitmix64 finalizer), as simhash weighs every bit alike
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+  "compilerOptions": {
+    "target": "ES2022",
+    "module": "ESNext",
+    "moduleResolution": "bundler",
+    "strict": true,
+    "esModuleInterop": true,
+    "skipLibCheck": true,
+    "outDir": "dist",
+    "declaration": true
+  },
+  "include": ["src"]
+}
+"#;
+
+    const CODE: &str = r#"export function parseArgs(argv: string[]): Options {
+  const options: Options = { verbose: false, files: [] };
+  for (const arg of argv) {
+    if (arg === "--verbose") {
+      options.verbose = true;
+    } else {
+      options.files.push(arg);
+    }
+  }
+  return options;
+}
+"#;
+
+    fn fingerprint(path: &str, content: &str) -> FileFingerprint {
+        FileFingerprint::new(path, content, DEFAULT_MIN_FILE_TOKENS).unwrap()
+    }
+
+    #[test]
+    fn test_near_copies_rank_above_unrelated_files() {
+        let reformatted = CONFIG.replace("  ", "    ").replace("\"dist\"", "\"build\"");
+        let fingerprints = [
+            fingerprint("a/tsconfig.json", CONFIG),
+            fingerprint("b/tsconfig.json", &reformatted),
+            fingerprint("src/args.ts", CODE),
+        ];
+
+        let near_copy = fingerprints[0].similarity(&fingerprints[1]);
+        assert!(near_copy >= 0.9, "near copy scored {near_copy}");
+        assert!(fingerprints[0].similarity(&fingerprints[2]) < 0.8);
+
+        let duplicates = find_similar_files(&fingerprints, 0.9);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].second.path, PathBuf::from("b/tsconfig.json"));
+    }
+
+    #[test]
+    fn test_directory_clones_with_file_mapping() {
+        let api = |entity: &str| {
+            format!(
+                "export async function fetch{entity}(id: string) {{\n  const response = await fetch(`/api/{entity}/${{id}}`);\n  if (!response.ok) {{\n    throw new Error(response.statusText);\n  }}\n  return response.json();\n}}\n"
+            )
+        };
+        let view = |entity: &str| {
+            format!(
+                "export function render{entity}(item: Item): string {{\n  const title = escape(item.title);\n  const rows = item.fields.map((field) => `<td>${{field}}</td>`).join(\"\");\n  return `<h1>${{title}}</h1><table>${{rows}}</table>`;\n}}\n"
+            )
+        };
+        let mut fingerprints = vec![
+            fingerprint("features/user/api.ts", &api("User")),
+            fingerprint("features/user/parts/view.ts", &view("User")),
+            fingerprint("features/team/api.ts", &api("Team")),
+            fingerprint("features/team/parts/view.ts", &view("Team")),
+            fingerprint("config/tsconfig.json", CONFIG),
+        ];
+
+        // `parts` is reported as part of its parent directories
+        let duplicates = find_similar_directories(&fingerprints, 0.85);
+        assert_eq!(duplicates.len(), 1, "{duplicates:#?}");
+        let clone = &duplicates[0];
+        assert_eq!(
+            (clone.first, clone.second),
+            (Path::new("features/team"), Path::new("features/user"))
+        );
+        assert_eq!(clone.files.len(), 2);
+        assert_eq!(clone.files[0].first.path, PathBuf::from("features/team/parts/view.ts"));
+
+        // An extra file is listed on its side and lowers the score
+        fingerprints.push(fingerprint("features/team/cli.ts", CODE));
+        assert!(find_similar_directories(&fingerprints, 0.85).is_empty());
+        let duplicates = find_similar_directories(&fingerprints, 0.7);
+        assert_eq!(duplicates.len(), 1, "{duplicates:#?}");
+        assert_eq!(duplicates[0].only_in_first[0].path, PathBuf::from("features/team/cli.ts"));
+        assert!(duplicates[0].only_in_second.is_empty());
+    }
+
+    #[test]
+    fn test_layout_and_case_are_ignored() {
+        let compact = CODE.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+        assert_eq!(fingerprint("a.ts", CODE).simhash, fingerprint("b.ts", &compact).simhash);
+        assert!(FileFingerprint::new("tiny.ts", "export {};", DEFAULT_MIN_FILE_TOKENS).is_none());
+    }
+}
@@ -1,40 +1,99 @@
 use oxc_ast::ast::*;
-use oxc_span::Span;
+use oxc_span::{GetSpan, Span};
 
+use crate::comparison_budget::ComparisonBudget;
+use crate::complexity::cyclomatic_complexity;
 use crate::ignore_directive::has_similarity_ignore_directive;
-use crate::parser::parse_and_convert_to_tree;
-use crate::tsed::{calculate_tsed, TSEDOptions};
+use crate::import_graph::exported_names;
+use crate::interner::Symbol;
+use crate::parser::{
+    parse_and_convert_to_tree, parse_and_convert_to_tree_with,
+    parse_and_convert_to_tree_with_comments, TreeConversion,
+};
+use crate::semantic_types::SemanticTypeResolver;
+use crate::statement_order::canonicalize_statement_order;
+use crate::test_blocks::test_call_kind;
+use crate::tolerant_parse::parse_tolerant;
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, ComparisonAlgorithm, TSEDOptions};
+use crate::type_compatibility::signature_compatibility;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::rc::Rc;
 
 type CrossFileSimilarityResult = Vec<(String, SimilarityResult, String)>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimilarityResult {
     pub func1: FunctionDefinition,
     pub func2: FunctionDefinition,
     pub similarity: f64,
     pub impact: u32, // Total lines that could be removed
+    /// How well the parameter and return types of the two functions agree
+    pub type_compatibility: f64,
+    /// Whether `similarity` is a tree edit distance or the fallback for large trees
+    #[serde(default)]
+    pub algorithm: ComparisonAlgorithm,
 }
 
 impl SimilarityResult {
     pub fn new(func1: FunctionDefinition, func2: FunctionDefinition, similarity: f64) -> Self {
         // Impact is the smaller function's line count (since we'd remove the duplicate)
         let impact = func1.line_count().min(func2.line_count());
-        SimilarityResult { func1, func2, similarity, impact }
+        let type_compatibility =
+            signature_compatibility(&func1, &func2, &SemanticTypeResolver::new());
+        SimilarityResult {
+            func1,
+            func2,
+            similarity,
+            impact,
+            type_compatibility,
+            algorithm: ComparisonAlgorithm::Tsed,
+        }
+    }
+
+    /// Record the algorithm that computed `similarity`
+    #[must_use]
+    pub fn with_algorithm(mut self, algorithm: ComparisonAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// How easily the pair merges into one function: the mean of structural
+    /// similarity and type compatibility
+    #[must_use]
+    pub fn merge_score(&self) -> f64 {
+        (self.similarity + self.type_compatibility) / 2.0
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionDefinition {
-    pub name: String,
+    pub name: Symbol,
     pub function_type: FunctionType,
     pub parameters: Vec<String>,
+    /// Type annotation of each parameter as written, `None` when unannotated
+    pub parameter_types: Vec<Option<Symbol>>,
+    pub return_type: Option<Symbol>,
+    #[serde(with = "span_serde")]
     pub body_span: Span,
     pub start_line: u32,
     pub end_line: u32,
-    pub class_name: Option<String>,
-    pub parent_function: Option<String>,
+    pub class_name: Option<Symbol>,
+    pub parent_function: Option<Symbol>,
+    /// Enclosing namespaces, classes and functions with the name, e.g.
+    /// `UserService.updateProfile > validatePayload`; `None` at the top level
+    #[serde(default)]
+    pub qualified_name: Option<Symbol>,
+    /// Whether the function can be imported from its module, directly or as a
+    /// method of an exported class or object
+    #[serde(default)]
+    pub exported: bool,
     pub node_count: Option<u32>,
+    pub complexity: u32,
     pub has_ignore_directive: bool,
+    /// Extracted from a file with syntax errors elsewhere
+    pub partial: bool,
 }
 
 impl FunctionDefinition {
@@ -42,6 +101,12 @@ impl FunctionDefinition {
         self.end_line - self.start_line + 1
     }
 
+    /// Name with its enclosing scopes, for reports
+    #[must_use]
+    pub fn display_name(&self) -> &str {
+        self.qualified_name.as_ref().unwrap_or(&self.name).as_str()
+    }
+
     /// Check if this function is a parent or child of another function
     pub fn is_parent_child_relationship(&self, other: &FunctionDefinition) -> bool {
         // Check if 'other' is inside 'self' (self is parent of other)
@@ -60,7 +125,22 @@ impl FunctionDefinition {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Spans serialize as `[start, end]`, so checkpoints do not depend on the parser's serde support
+mod span_serde {
+    use oxc_span::Span;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(span: &Span, serializer: S) -> Result<S::Ok, S::Error> {
+        [span.start, span.end].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Span, D::Error> {
+        let [start, end] = <[u32; 2]>::deserialize(deserializer)?;
+        Ok(Span::new(start, end))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FunctionType {
     Function,
     Method,
@@ -68,25 +148,20 @@ pub enum FunctionType {
     Constructor,
 }
 
-/// Extract all functions from TypeScript/JavaScript code
+/// Extract all functions from TypeScript/JavaScript code.
+///
+/// Functions outside localized syntax errors are still extracted and marked
+/// `partial`; only files with nothing recoverable fail with their parse errors.
 pub fn extract_functions(
     filename: &str,
     source_text: &str,
 ) -> Result<Vec<FunctionDefinition>, String> {
     use oxc_allocator::Allocator;
-    use oxc_parser::Parser;
     use oxc_span::SourceType;
 
     let allocator = Allocator::default();
     let source_type = SourceType::from_path(filename).unwrap_or(SourceType::tsx());
-    let ret = Parser::new(&allocator, source_text, source_type).parse();
-
-    if !ret.errors.is_empty() {
-        // Create a more readable error message
-        let error_messages: Vec<String> =
-            ret.errors.iter().map(|e| e.message.to_string()).collect();
-        return Err(format!("Parse errors: {}", error_messages.join(", ")));
-    }
+    let parsed = parse_tolerant(&allocator, source_text, source_type)?;
 
     let mut functions = Vec::new();
     let mut context = ExtractionContext {
@@ -94,17 +169,49 @@ pub fn extract_functions(
         source_text,
         class_name: None,
         parent_function: None,
+        scope: String::new(),
+        exported: false,
+        exports_visible: true,
+        exported_names: exported_names(&parsed.program),
     };
 
-    extract_from_program(&ret.program, &mut context);
+    extract_from_program(&parsed.program, &mut context);
+    if parsed.errors.is_some() {
+        functions.retain(|function| !parsed.is_damaged(function.body_span));
+        for function in &mut functions {
+            function.partial = true;
+        }
+    }
     Ok(functions)
 }
 
 struct ExtractionContext<'a> {
     functions: &'a mut Vec<FunctionDefinition>,
     source_text: &'a str,
-    class_name: Option<String>,
-    parent_function: Option<String>,
+    class_name: Option<Symbol>,
+    parent_function: Option<Symbol>,
+    /// Path of the enclosing scopes with a trailing separator: `.` after
+    /// namespaces and classes, ` > ` after functions; empty at the top level
+    scope: String,
+    /// Whether the declaration being extracted is exported
+    exported: bool,
+    /// Whether `export` at the current level makes a declaration importable,
+    /// false inside namespaces that are not exported themselves
+    exports_visible: bool,
+    /// Top-level declarations exported separately, e.g. by `export { name }`
+    exported_names: HashMap<String, String>,
+}
+
+impl ExtractionContext<'_> {
+    fn qualify(&self, name: &str) -> Option<Symbol> {
+        (!self.scope.is_empty()).then(|| Symbol::from(format!("{}{}", self.scope, name)))
+    }
+
+    /// Whether the declaration `name` is exported, by an `export` keyword or
+    /// a separate export at the top level
+    fn exports(&self, name: &str) -> bool {
+        self.exported || (self.scope.is_empty() && self.exported_names.contains_key(name))
+    }
 }
 
 fn extract_from_program(program: &Program, ctx: &mut ExtractionContext) {
@@ -117,19 +224,25 @@ fn extract_from_statement(stmt: &Statement, ctx: &mut ExtractionContext) {
     match stmt {
         Statement::FunctionDeclaration(func) => {
             if let Some(name) = &func.id {
-                let func_name = name.name.to_string();
+                let func_name = Symbol::intern(&name.name);
                 let params = extract_parameters(&func.params);
                 let start_line = get_line_number(func.span.start, ctx.source_text);
                 ctx.functions.push(FunctionDefinition {
-                    name: func_name.clone(),
+                    name: func_name,
                     function_type: FunctionType::Function,
                     parameters: params,
+                    parameter_types: extract_parameter_types(&func.params, ctx.source_text),
+                    return_type: type_annotation_text(func.return_type.as_deref(), ctx.source_text),
                     body_span: func.span,
                     start_line,
                     end_line: get_line_number(func.span.end, ctx.source_text),
                     class_name: None,
-                    parent_function: ctx.parent_function.clone(),
+                    parent_function: ctx.parent_function,
+                    qualified_name: ctx.qualify(func_name.as_str()),
+                    exported: ctx.exports(func_name.as_str()),
                     node_count: count_function_nodes(func.span, ctx.source_text),
+                    complexity: func.body.as_deref().map_or(1, cyclomatic_complexity),
+                    partial: false,
                     has_ignore_directive: has_similarity_ignore_directive(
                         ctx.source_text,
                         start_line as usize,
@@ -138,127 +251,72 @@ fn extract_from_statement(stmt: &Statement, ctx: &mut ExtractionContext) {
 
                 // Extract nested functions within the function body
                 if let Some(body) = &func.body {
-                    let saved_parent = ctx.parent_function.clone();
-                    ctx.parent_function = Some(func_name);
-                    extract_from_function_body(body, ctx);
-                    ctx.parent_function = saved_parent;
+                    let path = ctx.qualify(func_name.as_str()).unwrap_or(func_name);
+                    extract_nested(body, func_name, path.as_str(), ctx);
                 }
             }
         }
-        Statement::ClassDeclaration(class) => {
-            let class_name = class.id.as_ref().map(|id| id.name.to_string());
-            let saved_class_name = ctx.class_name.clone();
-            ctx.class_name = class_name.clone();
-
-            for element in &class.body.body {
-                if let ClassElement::MethodDefinition(method) = element {
-                    let method_name = match &method.key {
-                        PropertyKey::StaticIdentifier(ident) => ident.name.to_string(),
-                        PropertyKey::PrivateIdentifier(ident) => format!("#{}", ident.name),
-                        _ => "anonymous".to_string(),
-                    };
-
-                    let params = extract_parameters(&method.value.params);
-                    let function_type = if method.kind == MethodDefinitionKind::Constructor {
-                        FunctionType::Constructor
-                    } else {
-                        FunctionType::Method
-                    };
-
-                    let method_full_name = if let Some(ref class) = class_name {
-                        format!("{class}.{method_name}")
-                    } else {
-                        method_name.clone()
-                    };
-                    let start_line = get_line_number(method.span.start, ctx.source_text);
-
-                    ctx.functions.push(FunctionDefinition {
-                        name: method_name.clone(),
-                        function_type,
-                        parameters: params,
-                        body_span: method.span,
-                        start_line,
-                        end_line: get_line_number(method.span.end, ctx.source_text),
-                        class_name: class_name.clone(),
-                        parent_function: ctx.parent_function.clone(),
-                        node_count: count_function_nodes(method.span, ctx.source_text),
-                        has_ignore_directive: has_similarity_ignore_directive(
-                            ctx.source_text,
-                            start_line as usize,
-                        ),
-                    });
-
-                    // Extract nested functions within method body
-                    if let Some(body) = &method.value.body {
-                        let saved_parent = ctx.parent_function.clone();
-                        ctx.parent_function = Some(method_full_name);
-                        extract_from_function_body(body, ctx);
-                        ctx.parent_function = saved_parent;
-                    }
-                }
-            }
-
-            ctx.class_name = saved_class_name;
-        }
+        Statement::ClassDeclaration(class) => extract_from_class(class, ctx),
         Statement::VariableDeclaration(var_decl) => {
-            for decl in &var_decl.declarations {
-                if let Some(Expression::ArrowFunctionExpression(arrow)) = &decl.init {
-                    if let BindingPattern::BindingIdentifier(ident) = &decl.id {
-                        let params = extract_parameters(&arrow.params);
-                        let arrow_name = ident.name.to_string();
-                        let start_line = get_line_number(arrow.span.start, ctx.source_text);
-                        ctx.functions.push(FunctionDefinition {
-                            name: arrow_name.clone(),
-                            function_type: FunctionType::Arrow,
-                            parameters: params,
-                            body_span: arrow.span,
-                            start_line,
-                            end_line: get_line_number(arrow.span.end, ctx.source_text),
-                            class_name: None,
-                            parent_function: ctx.parent_function.clone(),
-                            node_count: count_function_nodes(arrow.span, ctx.source_text),
-                            has_ignore_directive: has_similarity_ignore_directive(
-                                ctx.source_text,
-                                start_line as usize,
-                            ),
-                        });
-
-                        // Extract nested functions within arrow function body
-                        if !arrow.expression {
-                            let saved_parent = ctx.parent_function.clone();
-                            ctx.parent_function = Some(arrow_name);
-                            extract_from_function_body(&arrow.body, ctx);
-                            ctx.parent_function = saved_parent;
-                        }
-                    }
-                }
-            }
+            extract_from_variable_declaration(var_decl, ctx)
         }
         Statement::ExportNamedDeclaration(export) => {
             if let Some(decl) = &export.declaration {
+                let saved_exported = std::mem::replace(&mut ctx.exported, ctx.exports_visible);
                 extract_from_declaration(decl, ctx);
+                ctx.exported = saved_exported;
             }
         }
+        Statement::TSModuleDeclaration(module) => extract_from_namespace(module, ctx),
+        Statement::ExpressionStatement(stmt) => match stmt.expression.get_inner_expression() {
+            Expression::CallExpression(call) => extract_callbacks(call, None, ctx),
+            Expression::AwaitExpression(await_expr) => {
+                if let Expression::CallExpression(call) = await_expr.argument.get_inner_expression()
+                {
+                    extract_callbacks(call, None, ctx);
+                }
+            }
+            // `module.exports.handler = () => { ... }`, `Foo.prototype.bar = function () { ... }`
+            Expression::AssignmentExpression(assign) => {
+                if let Some(path) = assignment_target_path(&assign.left) {
+                    extract_from_expression(&assign.right, &path, ctx);
+                }
+            }
+            _ => {}
+        },
         Statement::ExportDefaultDeclaration(export) => {
-            if let ExportDefaultDeclarationKind::FunctionDeclaration(func) = &export.declaration {
+            let saved_exported = std::mem::replace(&mut ctx.exported, ctx.exports_visible);
+            if let ExportDefaultDeclarationKind::ClassDeclaration(class) = &export.declaration {
+                extract_from_class(class, ctx);
+            } else if let Some(expr) = export.declaration.as_expression() {
+                extract_from_expression(expr, "default", ctx);
+            } else if let ExportDefaultDeclarationKind::FunctionDeclaration(func) =
+                &export.declaration
+            {
                 let name = func
                     .id
                     .as_ref()
                     .map(|id| id.name.to_string())
                     .unwrap_or_else(|| "default".to_string());
                 let params = extract_parameters(&func.params);
-                let func_name = name.clone();
+                let func_name = Symbol::from(name);
                 let start_line = get_line_number(func.span.start, ctx.source_text);
                 ctx.functions.push(FunctionDefinition {
-                    name: func_name.clone(),
+                    name: func_name,
                     function_type: FunctionType::Function,
                     parameters: params,
+                    parameter_types: extract_parameter_types(&func.params, ctx.source_text),
+                    return_type: type_annotation_text(func.return_type.as_deref(), ctx.source_text),
                     body_span: func.span,
                     start_line,
                     end_line: get_line_number(func.span.end, ctx.source_text),
                     class_name: None,
-                    parent_function: ctx.parent_function.clone(),
+                    parent_function: ctx.parent_function,
+                    qualified_name: ctx.qualify(func_name.as_str()),
+                    exported: ctx.exports(func_name.as_str()),
                     node_count: count_function_nodes(func.span, ctx.source_text),
+                    complexity: func.body.as_deref().map_or(1, cyclomatic_complexity),
+                    partial: false,
                     has_ignore_directive: has_similarity_ignore_directive(
                         ctx.source_text,
                         start_line as usize,
@@ -267,34 +325,67 @@ fn extract_from_statement(stmt: &Statement, ctx: &mut ExtractionContext) {
 
                 // Extract nested functions within the function body
                 if let Some(body) = &func.body {
-                    let saved_parent = ctx.parent_function.clone();
-                    ctx.parent_function = Some(func_name);
-                    extract_from_function_body(body, ctx);
-                    ctx.parent_function = saved_parent;
+                    let path = ctx.qualify(func_name.as_str()).unwrap_or(func_name);
+                    extract_nested(body, func_name, path.as_str(), ctx);
                 }
             }
+            ctx.exported = saved_exported;
         }
         _ => {}
     }
 }
 
+/// Extract the functions of `namespace Name { ... }`, qualified by the namespace
+fn extract_from_namespace(module: &TSModuleDeclaration, ctx: &mut ExtractionContext) {
+    let name = match &module.id {
+        TSModuleDeclarationName::Identifier(ident) => ident.name.to_string(),
+        TSModuleDeclarationName::StringLiteral(literal) => literal.value.to_string(),
+    };
+    let saved_scope = ctx.scope.clone();
+    let saved_visible = ctx.exports_visible;
+    ctx.exports_visible = ctx.exports(&name);
+    ctx.scope = format!("{}{}.", ctx.scope, name);
+    match &module.body {
+        Some(TSModuleDeclarationBody::TSModuleBlock(block)) => {
+            let saved_exported = std::mem::replace(&mut ctx.exported, false);
+            for stmt in &block.body {
+                extract_from_statement(stmt, ctx);
+            }
+            ctx.exported = saved_exported;
+        }
+        // `namespace A.B { ... }`
+        Some(TSModuleDeclarationBody::TSModuleDeclaration(inner)) => {
+            extract_from_namespace(inner, ctx);
+        }
+        None => {}
+    }
+    ctx.scope = saved_scope;
+    ctx.exports_visible = saved_visible;
+}
+
 fn extract_from_declaration(decl: &Declaration, ctx: &mut ExtractionContext) {
     match decl {
         Declaration::FunctionDeclaration(func) => {
             if let Some(name) = &func.id {
-                let func_name = name.name.to_string();
+                let func_name = Symbol::intern(&name.name);
                 let params = extract_parameters(&func.params);
                 let start_line = get_line_number(func.span.start, ctx.source_text);
                 ctx.functions.push(FunctionDefinition {
-                    name: func_name.clone(),
+                    name: func_name,
                     function_type: FunctionType::Function,
                     parameters: params,
+                    parameter_types: extract_parameter_types(&func.params, ctx.source_text),
+                    return_type: type_annotation_text(func.return_type.as_deref(), ctx.source_text),
                     body_span: func.span,
                     start_line,
                     end_line: get_line_number(func.span.end, ctx.source_text),
                     class_name: None,
-                    parent_function: ctx.parent_function.clone(),
+                    parent_function: ctx.parent_function,
+                    qualified_name: ctx.qualify(func_name.as_str()),
+                    exported: ctx.exports(func_name.as_str()),
                     node_count: count_function_nodes(func.span, ctx.source_text),
+                    complexity: func.body.as_deref().map_or(1, cyclomatic_complexity),
+                    partial: false,
                     has_ignore_directive: has_similarity_ignore_directive(
                         ctx.source_text,
                         start_line as usize,
@@ -303,106 +394,305 @@ fn extract_from_declaration(decl: &Declaration, ctx: &mut ExtractionContext) {
 
                 // Extract nested functions within the function body
                 if let Some(body) = &func.body {
-                    let saved_parent = ctx.parent_function.clone();
-                    ctx.parent_function = Some(func_name);
-                    extract_from_function_body(body, ctx);
-                    ctx.parent_function = saved_parent;
+                    let path = ctx.qualify(func_name.as_str()).unwrap_or(func_name);
+                    extract_nested(body, func_name, path.as_str(), ctx);
                 }
             }
         }
-        Declaration::ClassDeclaration(class) => {
-            let class_name = class.id.as_ref().map(|id| id.name.to_string());
-            let saved_class_name = ctx.class_name.clone();
-            ctx.class_name = class_name.clone();
-
-            for element in &class.body.body {
-                if let ClassElement::MethodDefinition(method) = element {
-                    let method_name = match &method.key {
-                        PropertyKey::StaticIdentifier(ident) => ident.name.to_string(),
-                        PropertyKey::PrivateIdentifier(ident) => format!("#{}", ident.name),
-                        _ => "anonymous".to_string(),
-                    };
+        Declaration::ClassDeclaration(class) => extract_from_class(class, ctx),
+        Declaration::VariableDeclaration(var) => extract_from_variable_declaration(var, ctx),
+        Declaration::TSModuleDeclaration(module) => extract_from_namespace(module, ctx),
+        _ => {}
+    }
+}
 
-                    let params = extract_parameters(&method.value.params);
-                    let function_type = if method.kind == MethodDefinitionKind::Constructor {
-                        FunctionType::Constructor
-                    } else {
-                        FunctionType::Method
-                    };
+fn extract_from_class(class: &Class, ctx: &mut ExtractionContext) {
+    let class_name = class.id.as_ref().map(|id| Symbol::intern(&id.name));
+    let saved_class_name = ctx.class_name;
+    ctx.class_name = class_name;
+    let saved_exported = ctx.exported;
+    let saved_scope = ctx.scope.clone();
+    if let Some(class) = class_name {
+        ctx.exported = ctx.exports(class.as_str());
+        ctx.scope = format!("{}{}.", ctx.scope, class);
+    }
 
-                    let method_full_name = if let Some(ref class) = class_name {
-                        format!("{class}.{method_name}")
-                    } else {
-                        method_name.clone()
-                    };
-                    let start_line = get_line_number(method.span.start, ctx.source_text);
-
-                    ctx.functions.push(FunctionDefinition {
-                        name: method_name.clone(),
-                        function_type,
-                        parameters: params,
-                        body_span: method.span,
-                        start_line,
-                        end_line: get_line_number(method.span.end, ctx.source_text),
-                        class_name: class_name.clone(),
-                        parent_function: ctx.parent_function.clone(),
-                        node_count: count_function_nodes(method.span, ctx.source_text),
-                        has_ignore_directive: has_similarity_ignore_directive(
-                            ctx.source_text,
-                            start_line as usize,
-                        ),
-                    });
-
-                    // Extract nested functions within method body
-                    if let Some(body) = &method.value.body {
-                        let saved_parent = ctx.parent_function.clone();
-                        ctx.parent_function = Some(method_full_name);
-                        extract_from_function_body(body, ctx);
-                        ctx.parent_function = saved_parent;
-                    }
+    for element in &class.body.body {
+        match element {
+            ClassElement::MethodDefinition(method) => {
+                let method_name = match &method.key {
+                    PropertyKey::StaticIdentifier(ident) => ident.name.to_string(),
+                    PropertyKey::PrivateIdentifier(ident) => format!("#{}", ident.name),
+                    _ => "anonymous".to_string(),
+                };
+
+                let params = extract_parameters(&method.value.params);
+                let function_type = if method.kind == MethodDefinitionKind::Constructor {
+                    FunctionType::Constructor
+                } else {
+                    FunctionType::Method
+                };
+
+                let method_full_name = if let Some(ref class) = class_name {
+                    format!("{class}.{method_name}")
+                } else {
+                    method_name.clone()
+                };
+                let start_line = get_line_number(method.span.start, ctx.source_text);
+
+                ctx.functions.push(FunctionDefinition {
+                    name: Symbol::from(&method_name),
+                    function_type,
+                    parameters: params,
+                    parameter_types: extract_parameter_types(&method.value.params, ctx.source_text),
+                    return_type: type_annotation_text(
+                        method.value.return_type.as_deref(),
+                        ctx.source_text,
+                    ),
+                    body_span: method.span,
+                    start_line,
+                    end_line: get_line_number(method.span.end, ctx.source_text),
+                    class_name,
+                    parent_function: ctx.parent_function,
+                    qualified_name: ctx.qualify(&method_name),
+                    exported: ctx.exported
+                        && !matches!(method.key, PropertyKey::PrivateIdentifier(_))
+                        && method.accessibility != Some(TSAccessibility::Private),
+                    node_count: count_function_nodes(method.span, ctx.source_text),
+                    complexity: method.value.body.as_deref().map_or(1, cyclomatic_complexity),
+                    partial: false,
+                    has_ignore_directive: has_similarity_ignore_directive(
+                        ctx.source_text,
+                        start_line as usize,
+                    ),
+                });
+
+                // Extract nested functions within method body
+                if let Some(body) = &method.value.body {
+                    let path =
+                        ctx.qualify(&method_name).unwrap_or_else(|| method_name.as_str().into());
+                    extract_nested(body, method_full_name.into(), path.as_str(), ctx);
                 }
             }
+            // Arrow functions assigned to fields: `handleClick = () => { ... }`
+            ClassElement::PropertyDefinition(prop) => {
+                let (Some(value), Some(field_name)) = (&prop.value, property_key_name(&prop.key))
+                else {
+                    continue;
+                };
+                let full_name = match &class_name {
+                    Some(class) => format!("{class}.{field_name}"),
+                    None => field_name.clone(),
+                };
+                if let Some(function) = function_value(value, field_name) {
+                    push_function(function, class_name, full_name, ctx);
+                }
+            }
+            _ => {}
+        }
+    }
 
-            ctx.class_name = saved_class_name;
+    ctx.class_name = saved_class_name;
+    ctx.exported = saved_exported;
+    ctx.scope = saved_scope;
+}
+
+fn extract_from_variable_declaration(var_decl: &VariableDeclaration, ctx: &mut ExtractionContext) {
+    for decl in &var_decl.declarations {
+        if let (Some(init), BindingPattern::BindingIdentifier(ident)) = (&decl.init, &decl.id) {
+            let saved_exported = ctx.exported;
+            ctx.exported = ctx.exports(&ident.name);
+            extract_from_expression(init, &ident.name, ctx);
+            ctx.exported = saved_exported;
         }
-        Declaration::VariableDeclaration(var) => {
-            for decl in &var.declarations {
-                if let Some(Expression::ArrowFunctionExpression(arrow)) = &decl.init {
-                    if let BindingPattern::BindingIdentifier(ident) = &decl.id {
-                        let params = extract_parameters(&arrow.params);
-                        let arrow_name = ident.name.to_string();
-                        let start_line = get_line_number(arrow.span.start, ctx.source_text);
-                        ctx.functions.push(FunctionDefinition {
-                            name: arrow_name.clone(),
-                            function_type: FunctionType::Arrow,
-                            parameters: params,
-                            body_span: arrow.span,
-                            start_line,
-                            end_line: get_line_number(arrow.span.end, ctx.source_text),
-                            class_name: None,
-                            parent_function: ctx.parent_function.clone(),
-                            node_count: count_function_nodes(arrow.span, ctx.source_text),
-                            has_ignore_directive: has_similarity_ignore_directive(
-                                ctx.source_text,
-                                start_line as usize,
-                            ),
-                        });
-
-                        // Extract nested functions within arrow function body
-                        if !arrow.expression {
-                            let saved_parent = ctx.parent_function.clone();
-                            ctx.parent_function = Some(arrow_name);
-                            extract_from_function_body(&arrow.body, ctx);
-                            ctx.parent_function = saved_parent;
-                        }
+    }
+}
+
+/// A function found in an expression, not yet pushed to the context
+struct FunctionValue<'b, 'a> {
+    name: String,
+    function_type: FunctionType,
+    params: &'b FormalParameters<'a>,
+    return_type: Option<&'b TSTypeAnnotation<'a>>,
+    span: Span,
+    complexity: u32,
+    /// Body to search for nested functions
+    body: Option<&'b FunctionBody<'a>>,
+}
+
+/// The arrow function or function expression `expr` evaluates to, named `name`
+fn function_value<'b, 'a>(expr: &'b Expression<'a>, name: String) -> Option<FunctionValue<'b, 'a>> {
+    match expr.get_inner_expression() {
+        Expression::ArrowFunctionExpression(arrow) => Some(FunctionValue {
+            name,
+            function_type: FunctionType::Arrow,
+            params: &arrow.params,
+            return_type: arrow.return_type.as_deref(),
+            span: arrow.span,
+            complexity: cyclomatic_complexity(&arrow.body),
+            body: (!arrow.expression).then_some(&*arrow.body),
+        }),
+        Expression::FunctionExpression(func) => Some(FunctionValue {
+            name,
+            function_type: FunctionType::Function,
+            params: &func.params,
+            return_type: func.return_type.as_deref(),
+            span: func.span,
+            complexity: func.body.as_deref().map_or(1, cyclomatic_complexity),
+            body: func.body.as_deref(),
+        }),
+        _ => None,
+    }
+}
+
+/// Record `function` and the functions nested in its body, which get
+/// `parent_name` as their parent
+fn push_function(
+    function: FunctionValue,
+    class_name: Option<Symbol>,
+    parent_name: String,
+    ctx: &mut ExtractionContext,
+) {
+    let start_line = get_line_number(function.span.start, ctx.source_text);
+    let qualified_name = ctx.qualify(&function.name);
+    let path = qualified_name.unwrap_or_else(|| function.name.as_str().into());
+    ctx.functions.push(FunctionDefinition {
+        name: function.name.into(),
+        function_type: function.function_type,
+        parameters: extract_parameters(function.params),
+        parameter_types: extract_parameter_types(function.params, ctx.source_text),
+        return_type: type_annotation_text(function.return_type, ctx.source_text),
+        body_span: function.span,
+        start_line,
+        end_line: get_line_number(function.span.end, ctx.source_text),
+        class_name,
+        parent_function: ctx.parent_function,
+        qualified_name,
+        exported: ctx.exported,
+        node_count: count_function_nodes(function.span, ctx.source_text),
+        complexity: function.complexity,
+        partial: false,
+        has_ignore_directive: has_similarity_ignore_directive(ctx.source_text, start_line as usize),
+    });
+
+    if let Some(body) = function.body {
+        extract_nested(body, parent_name.into(), path.as_str(), ctx);
+    }
+}
+
+/// Extract the functions `expr` defines, named after `name` (a variable or
+/// property path): the function itself, the methods of an object literal as
+/// `name.method`, and the callbacks of a higher-order call as `name`
+fn extract_from_expression(expr: &Expression, name: &str, ctx: &mut ExtractionContext) {
+    if let Some(function) = function_value(expr, name.to_string()) {
+        push_function(function, None, name.to_string(), ctx);
+        return;
+    }
+
+    match expr.get_inner_expression() {
+        Expression::ObjectExpression(object) => {
+            for property in &object.properties {
+                let ObjectPropertyKind::ObjectProperty(property) = property else {
+                    continue;
+                };
+                let Some(key) = property_key_name(&property.key) else {
+                    continue;
+                };
+                let path = format!("{name}.{key}");
+                if property.method || property.kind != PropertyKind::Init {
+                    if let Some(mut function) = function_value(&property.value, path.clone()) {
+                        function.function_type = FunctionType::Method;
+                        push_function(function, None, path, ctx);
                     }
+                } else {
+                    extract_from_expression(&property.value, &path, ctx);
                 }
             }
         }
+        Expression::CallExpression(call) => extract_callbacks(call, Some(name), ctx),
+        Expression::AwaitExpression(await_expr) => {
+            extract_from_expression(&await_expr.argument, name, ctx)
+        }
         _ => {}
     }
 }
 
+/// Extract the block-bodied function arguments of `call`, e.g. the handler of
+/// `router.get('/users', async (req, res) => { ... })`. Without a `name`
+/// they are named after the call: `router.get('/users')`, or
+/// `items.forEach callback` when the call has no leading string argument.
+/// Callbacks of test framework calls (`describe`, `it`, ...) are left out.
+fn extract_callbacks(call: &CallExpression, name: Option<&str>, ctx: &mut ExtractionContext) {
+    // Test code is compared separately by `test_blocks`
+    if test_call_kind(call).is_some() {
+        return;
+    }
+
+    let name = name.map_or_else(|| callback_name(call), str::to_string);
+
+    // Callbacks earlier in a chain: `app.route('/x').get(h1).post(h2)`
+    if let Some(member) = call.callee.get_inner_expression().as_member_expression() {
+        if let Expression::CallExpression(inner) = member.object().get_inner_expression() {
+            extract_callbacks(inner, None, ctx);
+        }
+    }
+
+    for argument in &call.arguments {
+        let Some(argument) = argument.as_expression() else {
+            continue;
+        };
+        match argument.get_inner_expression() {
+            Expression::ArrowFunctionExpression(arrow) if arrow.expression => {}
+            Expression::CallExpression(inner) => extract_callbacks(inner, Some(&name), ctx),
+            _ => {
+                if let Some(function) = function_value(argument, name.clone()) {
+                    push_function(function, None, name.clone(), ctx);
+                }
+            }
+        }
+    }
+}
+
+fn callback_name(call: &CallExpression) -> String {
+    let callee = expression_path(&call.callee);
+    match call.arguments.first().and_then(Argument::as_expression) {
+        Some(Expression::StringLiteral(literal)) => format!("{callee}('{}')", literal.value),
+        _ => format!("{callee} callback"),
+    }
+}
+
+/// Dotted path of an identifier or member chain, e.g. `this.router.get`
+fn expression_path(expr: &Expression) -> String {
+    match expr.get_inner_expression() {
+        Expression::Identifier(ident) => ident.name.to_string(),
+        Expression::ThisExpression(_) => "this".to_string(),
+        Expression::StaticMemberExpression(member) => {
+            format!("{}.{}", expression_path(&member.object), member.property.name)
+        }
+        Expression::CallExpression(call) => format!("{}()", expression_path(&call.callee)),
+        _ => "anonymous".to_string(),
+    }
+}
+
+fn assignment_target_path(target: &AssignmentTarget) -> Option<String> {
+    match target {
+        AssignmentTarget::AssignmentTargetIdentifier(ident) => Some(ident.name.to_string()),
+        AssignmentTarget::StaticMemberExpression(member) => {
+            Some(format!("{}.{}", expression_path(&member.object), member.property.name))
+        }
+        _ => None,
+    }
+}
+
+fn property_key_name(key: &PropertyKey) -> Option<String> {
+    match key {
+        PropertyKey::StaticIdentifier(ident) => Some(ident.name.to_string()),
+        PropertyKey::PrivateIdentifier(ident) => Some(format!("#{}", ident.name)),
+        PropertyKey::StringLiteral(literal) => Some(literal.value.to_string()),
+        _ => None,
+    }
+}
+
 fn extract_parameters(params: &oxc_ast::ast::FormalParameters) -> Vec<String> {
     params
         .items
@@ -414,12 +704,44 @@ fn extract_parameters(params: &oxc_ast::ast::FormalParameters) -> Vec<String> {
         .collect()
 }
 
+fn extract_parameter_types(
+    params: &oxc_ast::ast::FormalParameters,
+    source_text: &str,
+) -> Vec<Option<Symbol>> {
+    params
+        .items
+        .iter()
+        .map(|param| type_annotation_text(param.type_annotation.as_deref(), source_text))
+        .collect()
+}
+
+/// Source text of the type in an annotation, without the colon
+fn type_annotation_text(
+    annotation: Option<&TSTypeAnnotation>,
+    source_text: &str,
+) -> Option<Symbol> {
+    let span = annotation?.type_annotation.span();
+    source_text.get(span.start as usize..span.end as usize).map(Symbol::intern)
+}
+
 fn extract_from_function_body(body: &FunctionBody, ctx: &mut ExtractionContext) {
     for stmt in &body.statements {
         extract_from_statement(stmt, ctx);
     }
 }
 
+/// Extract the functions nested in `body` of the function `parent`, whose
+/// full path is `path`; they are local to it and never exported
+fn extract_nested(body: &FunctionBody, parent: Symbol, path: &str, ctx: &mut ExtractionContext) {
+    let saved_parent = ctx.parent_function.replace(parent);
+    let saved_scope = std::mem::replace(&mut ctx.scope, format!("{} > ", path));
+    let saved_exported = std::mem::replace(&mut ctx.exported, false);
+    extract_from_function_body(body, ctx);
+    ctx.parent_function = saved_parent;
+    ctx.scope = saved_scope;
+    ctx.exported = saved_exported;
+}
+
 fn get_line_number(offset: u32, source_text: &str) -> u32 {
     let mut line = 1;
     let mut current_offset = 0;
@@ -445,27 +767,81 @@ pub fn compare_functions(
     source2: &str,
     options: &TSEDOptions,
 ) -> Result<f64, String> {
+    compare_functions_with_algorithm(func1, func2, source1, source2, options)
+        .map(|(similarity, _)| similarity)
+}
+
+/// Compare similarity between two functions, with the algorithm the size of
+/// their trees selected
+pub fn compare_functions_with_algorithm(
+    func1: &FunctionDefinition,
+    func2: &FunctionDefinition,
+    source1: &str,
+    source2: &str,
+    options: &TSEDOptions,
+) -> Result<(f64, ComparisonAlgorithm), String> {
     // Extract function body text
     let body1 = extract_body_text(func1, source1);
     let body2 = extract_body_text(func2, source2);
 
     // Parse and compare
-    let tree1 = parse_and_convert_to_tree("func1.ts", &body1)?;
-    let tree2 = parse_and_convert_to_tree("func2.ts", &body2)?;
+    let conversion = TreeConversion {
+        include_comments: options.include_comments,
+        normalize_async: options.normalize_async,
+        normalize_collections: options.normalize_collections,
+        strip_types: options.strip_types,
+    };
+    let mut tree1 = parse_and_convert_to_tree_with("func1.ts", &body1, conversion)?;
+    let mut tree2 = parse_and_convert_to_tree_with("func2.ts", &body2, conversion)?;
+    if options.canonicalize_order {
+        tree1 = canonicalize_statement_order(&tree1);
+        tree2 = canonicalize_statement_order(&tree2);
+    }
 
-    let mut similarity = calculate_tsed(&tree1, &tree2, options);
+    let algorithm =
+        ComparisonAlgorithm::for_sizes(tree1.get_subtree_size(), tree2.get_subtree_size(), options);
+    let similarity = calculate_tsed(&tree1, &tree2, options);
+    Ok((
+        apply_short_function_penalty(similarity, func1.line_count(), func2.line_count(), options),
+        algorithm,
+    ))
+}
 
-    // Apply size penalty for short functions if enabled
+/// Apply the size penalty for short functions if enabled
+pub(crate) fn apply_short_function_penalty(
+    similarity: f64,
+    lines1: u32,
+    lines2: u32,
+    options: &TSEDOptions,
+) -> f64 {
     if options.size_penalty {
-        let avg_lines = (func1.line_count() + func2.line_count()) as f64 / 2.0;
+        let avg_lines = (lines1 + lines2) as f64 / 2.0;
         if avg_lines < 10.0 {
             // Apply penalty: shorter functions get more penalty
             let penalty = avg_lines / 10.0;
-            similarity *= penalty;
+            return similarity * penalty;
         }
     }
+    similarity
+}
 
-    Ok(similarity)
+/// Tree of a function's source including its comment nodes; methods only
+/// parse inside a class body, so they are retried wrapped in one
+pub fn parse_function_tree(
+    function: &FunctionDefinition,
+    source_text: &str,
+) -> Option<Rc<TreeNode>> {
+    let start = function.body_span.start as usize;
+    let end = (function.body_span.end as usize).min(source_text.len());
+    let source = source_text.get(start..end)?;
+    parse_and_convert_to_tree_with_comments("function.ts", source)
+        .or_else(|_| {
+            parse_and_convert_to_tree_with_comments(
+                "function.ts",
+                &format!("class C {{ {source} }}"),
+            )
+        })
+        .ok()
 }
 
 fn extract_body_text(func: &FunctionDefinition, source: &str) -> String {
@@ -540,9 +916,91 @@ pub fn find_similar_functions_in_file(
     threshold: f64,
     options: &TSEDOptions,
 ) -> Result<Vec<SimilarityResult>, String> {
-    let mut functions = extract_functions(filename, source_text)?;
-    functions.retain(|function| !function.has_ignore_directive);
+    find_similar_functions_in_file_with_budget(
+        filename,
+        source_text,
+        threshold,
+        options,
+        &ComparisonBudget::unlimited(),
+    )
+}
+
+/// Find similar functions within the same file, stopping when the budget runs out
+pub fn find_similar_functions_in_file_with_budget(
+    filename: &str,
+    source_text: &str,
+    threshold: f64,
+    options: &TSEDOptions,
+    budget: &ComparisonBudget,
+) -> Result<Vec<SimilarityResult>, String> {
     let mut similar_pairs = Vec::new();
+    visit_similar_functions_in_file(
+        filename,
+        source_text,
+        threshold,
+        options,
+        budget,
+        |func1, func2, similarity, algorithm| {
+            similar_pairs.push(
+                SimilarityResult::new(func1.clone(), func2.clone(), similarity)
+                    .with_algorithm(algorithm),
+            );
+        },
+    )?;
+
+    // Sort by impact (descending), then by similarity (descending)
+    similar_pairs.sort_by(|a, b| {
+        b.impact
+            .cmp(&a.impact)
+            .then(b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal))
+    });
+
+    Ok(similar_pairs)
+}
+
+/// A file with the functions extracted from it, for callers comparing the
+/// same files within and across files without parsing them again
+pub struct ExtractedFile<'a> {
+    pub filename: &'a str,
+    pub source: &'a str,
+    /// Functions without an ignore directive
+    pub functions: Vec<FunctionDefinition>,
+}
+
+impl<'a> ExtractedFile<'a> {
+    pub fn extract(filename: &'a str, source: &'a str) -> Result<Self, String> {
+        let mut functions = extract_functions(filename, source)?;
+        functions.retain(|function| !function.has_ignore_directive);
+        Ok(Self { filename, source, functions })
+    }
+}
+
+/// Call `visit` with every pair of functions in the same file scoring at
+/// least `threshold`, as it is found, so callers such as a [`TopPairs`]
+/// collector decide what to keep
+///
+/// [`TopPairs`]: crate::top_pairs::TopPairs
+pub fn visit_similar_functions_in_file(
+    filename: &str,
+    source_text: &str,
+    threshold: f64,
+    options: &TSEDOptions,
+    budget: &ComparisonBudget,
+    visit: impl FnMut(&FunctionDefinition, &FunctionDefinition, f64, ComparisonAlgorithm),
+) -> Result<(), String> {
+    let file = ExtractedFile::extract(filename, source_text)?;
+    visit_similar_functions_in_extracted(&file, threshold, options, budget, visit)
+}
+
+/// [`visit_similar_functions_in_file`] for a file already extracted
+pub fn visit_similar_functions_in_extracted(
+    file: &ExtractedFile,
+    threshold: f64,
+    options: &TSEDOptions,
+    budget: &ComparisonBudget,
+    mut visit: impl FnMut(&FunctionDefinition, &FunctionDefinition, f64, ComparisonAlgorithm),
+) -> Result<(), String> {
+    let (filename, source_text, functions) = (file.filename, file.source, &file.functions);
 
     // Compare all pairs
     for i in 0..functions.len() {
@@ -553,6 +1011,7 @@ pub fn find_similar_functions_in_file(
                 let tokens_i = functions[i].node_count.unwrap_or(0);
                 let tokens_j = functions[j].node_count.unwrap_or(0);
                 if tokens_i < min_tokens || tokens_j < min_tokens {
+                    log_pruned(filename, &functions[i], filename, &functions[j], "min_tokens");
                     continue;
                 }
             } else {
@@ -560,36 +1019,52 @@ pub fn find_similar_functions_in_file(
                 if functions[i].line_count() < options.min_lines
                     || functions[j].line_count() < options.min_lines
                 {
+                    log_pruned(filename, &functions[i], filename, &functions[j], "min_lines");
                     continue;
                 }
             }
 
             // Skip if functions have parent-child relationship
             if functions[i].is_parent_child_relationship(&functions[j]) {
+                log_pruned(filename, &functions[i], filename, &functions[j], "nested");
+                continue;
+            }
+
+            if !budget.try_compare() {
                 continue;
             }
 
-            let similarity =
-                compare_functions(&functions[i], &functions[j], source_text, source_text, options)?;
+            let (similarity, algorithm) = compare_functions_with_algorithm(
+                &functions[i],
+                &functions[j],
+                source_text,
+                source_text,
+                options,
+            )?;
 
             if similarity >= threshold {
-                similar_pairs.push(SimilarityResult::new(
-                    functions[i].clone(),
-                    functions[j].clone(),
-                    similarity,
-                ));
+                visit(&functions[i], &functions[j], similarity, algorithm);
             }
         }
     }
 
-    // Sort by impact (descending), then by similarity (descending)
-    similar_pairs.sort_by(|a, b| {
-        b.impact
-            .cmp(&a.impact)
-            .then(b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal))
-    });
+    Ok(())
+}
 
-    Ok(similar_pairs)
+/// Log a pair skipped before comparison and the `reason` it was skipped
+pub(crate) fn log_pruned(
+    file1: &str,
+    func1: &FunctionDefinition,
+    file2: &str,
+    func2: &FunctionDefinition,
+    reason: &str,
+) {
+    tracing::debug!(
+        first = %format!("{}:{}:{}", file1, func1.name, func1.start_line),
+        second = %format!("{}:{}:{}", file2, func2.name, func2.start_line),
+        reason,
+        "pruned pair"
+    );
 }
 
 /// Find similar functions across multiple files
@@ -598,16 +1073,39 @@ pub fn find_similar_functions_across_files(
     threshold: f64,
     options: &TSEDOptions,
 ) -> Result<CrossFileSimilarityResult, String> {
-    let mut all_functions = Vec::new();
+    find_similar_functions_across_files_with_budget(
+        files,
+        threshold,
+        options,
+        &ComparisonBudget::unlimited(),
+    )
+}
 
-    // Extract functions from all files
-    for (filename, source) in files {
-        let mut functions = extract_functions(filename, source)?;
-        functions.retain(|function| !function.has_ignore_directive);
-        for func in functions {
-            all_functions.push((filename.clone(), source.clone(), func));
-        }
-    }
+/// Find similar functions across multiple files, stopping when the budget runs out
+pub fn find_similar_functions_across_files_with_budget(
+    files: &[(String, String)], // (filename, source_text)
+    threshold: f64,
+    options: &TSEDOptions,
+    budget: &ComparisonBudget,
+) -> Result<CrossFileSimilarityResult, String> {
+    let files = files
+        .iter()
+        .map(|(filename, source)| ExtractedFile::extract(filename, source))
+        .collect::<Result<Vec<_>, _>>()?;
+    find_similar_functions_across_extracted(&files, threshold, options, budget)
+}
+
+/// [`find_similar_functions_across_files_with_budget`] for files already extracted
+pub fn find_similar_functions_across_extracted(
+    files: &[ExtractedFile],
+    threshold: f64,
+    options: &TSEDOptions,
+    budget: &ComparisonBudget,
+) -> Result<CrossFileSimilarityResult, String> {
+    let all_functions: Vec<(&str, &str, &FunctionDefinition)> = files
+        .iter()
+        .flat_map(|file| file.functions.iter().map(move |func| (file.filename, file.source, func)))
+        .collect();
 
     let mut similar_pairs = Vec::new();
 
@@ -628,28 +1126,37 @@ pub fn find_similar_functions_across_files(
                 let tokens1 = func1.node_count.unwrap_or(0);
                 let tokens2 = func2.node_count.unwrap_or(0);
                 if tokens1 < min_tokens || tokens2 < min_tokens {
+                    log_pruned(first_file, func1, second_file, func2, "min_tokens");
                     continue;
                 }
             } else {
                 // Otherwise use line count
                 if func1.line_count() < options.min_lines || func2.line_count() < options.min_lines
                 {
+                    log_pruned(first_file, func1, second_file, func2, "min_lines");
                     continue;
                 }
             }
 
             // Skip if functions have parent-child relationship (across files)
             if func1.is_parent_child_relationship(func2) {
+                log_pruned(first_file, func1, second_file, func2, "nested");
+                continue;
+            }
+
+            if !budget.try_compare() {
                 continue;
             }
 
-            let similarity = compare_functions(func1, func2, source1, source2, options)?;
+            let (similarity, algorithm) =
+                compare_functions_with_algorithm(func1, func2, source1, source2, options)?;
 
             if similarity >= threshold {
                 similar_pairs.push((
-                    first_file.clone(),
-                    SimilarityResult::new(func1.clone(), func2.clone(), similarity),
-                    second_file.clone(),
+                    first_file.to_string(),
+                    SimilarityResult::new((*func1).clone(), (*func2).clone(), similarity)
+                        .with_algorithm(algorithm),
+                    second_file.to_string(),
                 ));
             }
         }
@@ -718,7 +1225,7 @@ mod tests {
 
         let constructor = functions.iter().find(|f| f.name == "constructor").unwrap();
         assert_eq!(constructor.function_type, FunctionType::Constructor);
-        assert_eq!(constructor.class_name, Some("Calculator".to_string()));
+        assert_eq!(constructor.class_name, Some("Calculator".into()));
 
         // Check that node_count is populated for all functions
         for func in &functions {
@@ -904,4 +1411,142 @@ export function ignoredExport() {
         let ignored_export = functions.iter().find(|f| f.name == "ignoredExport").unwrap();
         assert!(ignored_export.has_ignore_directive);
     }
+
+    #[test]
+    fn test_extract_functions_around_syntax_error() {
+        let code = r#"
+function before(a: number) {
+    return a + 1;
+}
+
+function broken(a: number {
+    return a;
+}
+
+class Service {
+    handle() {
+        return 2;
+    }
+}
+"#;
+
+        let functions = extract_functions("test.ts", code).unwrap();
+        let names: Vec<&str> = functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["before", "handle"]);
+        assert!(functions.iter().all(|f| f.partial));
+        assert_eq!(functions[1].start_line, 11);
+
+        let clean = extract_functions("test.ts", "function ok() {\n    return 1;\n}\n").unwrap();
+        assert!(!clean[0].partial);
+    }
+
+    #[test]
+    fn test_extract_arrow_object_and_callback_functions() {
+        let code = r#"
+const parse = function (input: string) {
+    return input.trim();
+};
+
+const api = {
+    list() {
+        return [];
+    },
+    users: {
+        get: async (id: string) => {
+            return id;
+        },
+    },
+} as const;
+
+router.get('/users', async (req, res) => {
+    res.send(ids.map((id) => id));
+});
+
+app.route('/items').get((req) => {
+    return req;
+}).post(withAuth(function (req) {
+    return req;
+}));
+
+class Widget {
+    handleClick = () => {
+        return 1;
+    };
+}
+
+module.exports.handler = (event) => {
+    return event;
+};
+
+export default () => {
+    return 0;
+};
+"#;
+
+        let functions = extract_functions("test.ts", code).unwrap();
+        let names: Vec<(&str, Option<&str>)> = functions
+            .iter()
+            .map(|function| (function.name.as_str(), function.class_name.as_deref()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("parse", None),
+                ("api.list", None),
+                ("api.users.get", None),
+                ("router.get('/users')", None),
+                ("app.route().get callback", None),
+                ("app.route().get().post callback", None),
+                ("handleClick", Some("Widget")),
+                ("module.exports.handler", None),
+                ("default", None),
+            ]
+        );
+
+        assert_eq!(functions[0].function_type, FunctionType::Function);
+        assert_eq!(functions[1].function_type, FunctionType::Method);
+        assert_eq!(functions[2].function_type, FunctionType::Arrow);
+        assert_eq!(functions[2].parameters, ["id"]);
+    }
+
+    #[test]
+    fn test_extract_functions_with_scope_chain_and_export_status() {
+        let code = r#"
+export namespace Admin {
+    export class UserService {
+        updateProfile(input: string) {
+            function validatePayload(payload: string) {
+                return payload.length > 0;
+            }
+            return validatePayload(input);
+        }
+
+        private audit() {}
+    }
+
+    function internal() {}
+}
+
+function helper() {}
+const format = (value: string) => value.trim();
+export { format };
+"#;
+
+        let functions = extract_functions("test.ts", code).unwrap();
+        let names: Vec<(&str, bool)> =
+            functions.iter().map(|function| (function.display_name(), function.exported)).collect();
+        assert_eq!(
+            names,
+            [
+                ("Admin.UserService.updateProfile", true),
+                ("Admin.UserService.updateProfile > validatePayload", false),
+                ("Admin.UserService.audit", false),
+                ("Admin.internal", false),
+                ("helper", false),
+                ("format", true),
+            ]
+        );
+        assert_eq!(functions[1].name.as_str(), "validatePayload");
+        assert_eq!(functions[4].qualified_name, None);
+    }
 }
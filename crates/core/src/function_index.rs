@@ -0,0 +1,622 @@
+//! Function fingerprint index shared between CI jobs.
+//!
+//! One job exports the trees of every function of a repository to a compact
+//! binary file; another job compares its own functions against that file, so
+//! it never needs the indexed repository's source. Layout, with integers as
+//! LEB128 varints unless noted:
+//!
+//! - magic `SIMIDX`, then [`INDEX_VERSION`] as a little endian u32
+//! - string table: count, then length-prefixed UTF-8 strings
+//! - function count, then per function: file and name (string indices),
+//!   start line, end line and its tree
+//! - tree nodes in preorder: label and value (string indices), child count
+//!
+//! Trees keep their comment nodes; the comparison options decide whether
+//! they count. Readers reject any other version, so every layout change
+//! bumps [`INDEX_VERSION`].
+//!
+//! Entries own their text as shared `Arc<str>`s rather than interned
+//! symbols: the functions of one file, or of one string table entry, share
+//! a single allocation, and it is freed with the last entry holding it.
+
+use crate::comparison_budget::ComparisonBudget;
+use crate::function_extractor::{
+    apply_short_function_penalty, extract_functions, parse_function_tree, FunctionDefinition,
+};
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, TSEDOptions};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Version of the binary layout, bumped on every change
+pub const INDEX_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 6] = b"SIMIDX";
+
+/// A function of the indexed repository
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    pub file: Arc<str>,
+    pub name: Arc<str>,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub tree: Rc<TreeNode>,
+}
+
+impl IndexEntry {
+    #[must_use]
+    pub fn line_count(&self) -> u32 {
+        self.end_line - self.start_line + 1
+    }
+}
+
+/// A function of a source compared against the index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFunction {
+    pub name: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+impl SourceFunction {
+    fn new(function: &FunctionDefinition) -> Self {
+        Self {
+            name: function.name.to_string(),
+            start_line: function.start_line,
+            end_line: function.end_line,
+        }
+    }
+}
+
+/// A local function similar to an indexed one
+#[derive(Debug, Clone)]
+pub struct IndexMatch<'a> {
+    pub function: SourceFunction,
+    pub indexed: &'a IndexEntry,
+    pub similarity: f64,
+}
+
+/// The functions resembling one function of a file, for an editor code lens
+/// such as "3 similar implementations"
+#[derive(Debug, Clone)]
+pub struct FunctionLens {
+    pub function: SourceFunction,
+    /// Functions scoring at least the threshold
+    pub similar: usize,
+    /// The most similar of them and its score
+    pub best: Option<(IndexEntry, f64)>,
+}
+
+/// Functions loaded from or about to be written to an index file
+#[derive(Debug, Default)]
+pub struct FunctionIndex {
+    functions: Vec<IndexEntry>,
+}
+
+impl FunctionIndex {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn functions(&self) -> &[IndexEntry] {
+        &self.functions
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Index the functions of a TypeScript/JavaScript file, returning how many were added.
+    /// Functions with an ignore directive are left out.
+    pub fn add_source(&mut self, file: &str, source_text: &str) -> Result<usize, String> {
+        let file_name: Arc<str> = Arc::from(file);
+        let entries: Vec<IndexEntry> = extract_functions(file, source_text)?
+            .iter()
+            .filter(|function| !function.has_ignore_directive)
+            .filter_map(|function| {
+                Some(IndexEntry {
+                    file: Arc::clone(&file_name),
+                    name: Arc::from(function.name.as_str()),
+                    start_line: function.start_line,
+                    end_line: function.end_line,
+                    tree: parse_function_tree(function, source_text)?,
+                })
+            })
+            .collect();
+        let added = entries.len();
+        self.functions.extend(entries);
+        Ok(added)
+    }
+
+    /// Drop the functions indexed from `file`, returning how many were removed
+    pub fn remove_file(&mut self, file: &str) -> usize {
+        let before = self.functions.len();
+        self.functions.retain(|entry| &*entry.file != file);
+        before - self.functions.len()
+    }
+
+    /// Functions of a TypeScript/JavaScript file scoring at least `threshold`
+    /// against an indexed function, each with its best indexed match
+    pub fn find_matches(
+        &self,
+        file: &str,
+        source_text: &str,
+        threshold: f64,
+        options: &TSEDOptions,
+        budget: &ComparisonBudget,
+    ) -> Result<Vec<IndexMatch<'_>>, String> {
+        let mut matches = Vec::new();
+        for function in extract_functions(file, source_text)? {
+            if function.has_ignore_directive || is_too_short(&function, options) {
+                continue;
+            }
+            let Some(tree) = parse_function_tree(&function, source_text) else {
+                continue;
+            };
+
+            let similar =
+                similar_entries(&function, &tree, &self.functions, threshold, options, budget);
+            if let Some((indexed, similarity)) = best_entry(similar) {
+                let function = SourceFunction::new(&function);
+                matches.push(IndexMatch { function, indexed, similarity });
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Every function of a TypeScript/JavaScript file with the number of
+    /// functions scoring at least `threshold` against it and the best of them.
+    /// The functions indexed from `file` itself are replaced by the other
+    /// functions of `source_text`, so the lenses follow unsaved edits.
+    pub fn lens(
+        &self,
+        file: &str,
+        source_text: &str,
+        threshold: f64,
+        options: &TSEDOptions,
+        budget: &ComparisonBudget,
+    ) -> Result<Vec<FunctionLens>, String> {
+        let functions: Vec<(FunctionDefinition, Rc<TreeNode>)> =
+            extract_functions(file, source_text)?
+                .into_iter()
+                .filter(|function| !function.has_ignore_directive)
+                .filter_map(|function| {
+                    let tree = parse_function_tree(&function, source_text)?;
+                    Some((function, tree))
+                })
+                .collect();
+        let file_name: Arc<str> = Arc::from(file);
+        let own: Vec<IndexEntry> = functions
+            .iter()
+            .map(|(function, tree)| IndexEntry {
+                file: Arc::clone(&file_name),
+                name: Arc::from(function.name.as_str()),
+                start_line: function.start_line,
+                end_line: function.end_line,
+                tree: Rc::clone(tree),
+            })
+            .collect();
+        let path = file.trim_start_matches("./");
+        let elsewhere: Vec<&IndexEntry> = self
+            .functions
+            .iter()
+            .filter(|entry| entry.file.trim_start_matches("./") != path)
+            .collect();
+
+        let mut lenses = Vec::new();
+        for (index, (function, tree)) in functions.iter().enumerate() {
+            if is_too_short(function, options) {
+                continue;
+            }
+            let candidates = elsewhere.iter().copied().chain(
+                own.iter().enumerate().filter(|(other, _)| *other != index).map(|(_, entry)| entry),
+            );
+            let similar = similar_entries(function, tree, candidates, threshold, options, budget);
+            lenses.push(FunctionLens {
+                function: SourceFunction::new(function),
+                similar: similar.len(),
+                best: best_entry(similar).map(|(entry, similarity)| (entry.clone(), similarity)),
+            });
+        }
+        Ok(lenses)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file = fs::File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        self.write_to(&mut writer)
+            .and_then(|()| writer.flush())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        Self::read_from(BufReader::new(file))
+            .with_context(|| format!("Failed to read function index {}", path.display()))
+    }
+
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut strings = StringTable::default();
+        let mut body = Vec::new();
+        push_varint(&mut body, self.functions.len() as u64);
+        for function in &self.functions {
+            push_varint(&mut body, strings.intern(&function.file));
+            push_varint(&mut body, strings.intern(&function.name));
+            push_varint(&mut body, u64::from(function.start_line));
+            push_varint(&mut body, u64::from(function.end_line));
+            push_tree(&mut body, &function.tree, &mut strings);
+        }
+
+        let mut header = MAGIC.to_vec();
+        header.extend(INDEX_VERSION.to_le_bytes());
+        push_varint(&mut header, strings.strings.len() as u64);
+        for string in &strings.strings {
+            push_varint(&mut header, string.len() as u64);
+            header.extend(string.as_bytes());
+        }
+        writer.write_all(&header)?;
+        writer.write_all(&body)
+    }
+
+    pub fn read_from(mut reader: impl Read) -> io::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a similarity function index"));
+        }
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != INDEX_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported index version {} (expected {})",
+                version, INDEX_VERSION
+            )));
+        }
+
+        let string_count = read_varint(&mut reader)?;
+        let mut strings = Vec::with_capacity(capacity_hint(string_count));
+        for _ in 0..string_count {
+            let len = usize::try_from(read_varint(&mut reader)?)
+                .map_err(|_| invalid_data("string too long"))?;
+            let mut bytes = Vec::with_capacity(capacity_hint(len as u64));
+            (&mut reader).take(len as u64).read_to_end(&mut bytes)?;
+            if bytes.len() != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            strings.push(Arc::from(String::from_utf8(bytes).map_err(invalid_data)?));
+        }
+
+        let mut decoder = Decoder { reader, strings, next_id: 0 };
+        let function_count = read_varint(&mut decoder.reader)?;
+        let mut functions = Vec::with_capacity(capacity_hint(function_count));
+        for _ in 0..function_count {
+            let file = decoder.string()?;
+            let name = decoder.string()?;
+            let start_line = decoder.line()?;
+            let end_line = decoder.line()?;
+            if end_line < start_line {
+                return Err(invalid_data("function ends before it starts"));
+            }
+            decoder.next_id = 0;
+            let tree = Rc::new(decoder.tree()?);
+            functions.push(IndexEntry { file, name, start_line, end_line, tree });
+        }
+        Ok(Self { functions })
+    }
+}
+
+/// Entries scoring at least `threshold` against a function, leaving out
+/// entries too short to compare
+fn similar_entries<'a>(
+    function: &FunctionDefinition,
+    tree: &Rc<TreeNode>,
+    entries: impl IntoIterator<Item = &'a IndexEntry>,
+    threshold: f64,
+    options: &TSEDOptions,
+    budget: &ComparisonBudget,
+) -> Vec<(&'a IndexEntry, f64)> {
+    let mut similar = Vec::new();
+    for indexed in entries {
+        if let Some(min_tokens) = options.min_tokens {
+            if (indexed.tree.get_subtree_size() as u32) < min_tokens {
+                continue;
+            }
+        } else if indexed.line_count() < options.min_lines {
+            continue;
+        }
+        if !budget.try_compare() {
+            continue;
+        }
+
+        let similarity = apply_short_function_penalty(
+            calculate_tsed(tree, &indexed.tree, options),
+            function.line_count(),
+            indexed.line_count(),
+            options,
+        );
+        if similarity >= threshold {
+            similar.push((indexed, similarity));
+        }
+    }
+    similar
+}
+
+/// The most similar entry, the first one among equals
+fn best_entry(similar: Vec<(&IndexEntry, f64)>) -> Option<(&IndexEntry, f64)> {
+    similar.into_iter().fold(None, |best, (entry, similarity)| match best {
+        Some((_, score)) if similarity <= score => best,
+        _ => Some((entry, similarity)),
+    })
+}
+
+fn is_too_short(function: &FunctionDefinition, options: &TSEDOptions) -> bool {
+    match options.min_tokens {
+        Some(min_tokens) => function.node_count.unwrap_or(0) < min_tokens,
+        None => function.line_count() < options.min_lines,
+    }
+}
+
+#[derive(Default)]
+struct StringTable {
+    strings: Vec<String>,
+    indices: HashMap<String, u64>,
+}
+
+impl StringTable {
+    fn intern(&mut self, string: &str) -> u64 {
+        if let Some(&index) = self.indices.get(string) {
+            return index;
+        }
+        let index = self.strings.len() as u64;
+        self.strings.push(string.to_string());
+        self.indices.insert(string.to_string(), index);
+        index
+    }
+}
+
+fn push_tree(out: &mut Vec<u8>, node: &TreeNode, strings: &mut StringTable) {
+    push_varint(out, strings.intern(&node.label));
+    push_varint(out, strings.intern(&node.value));
+    push_varint(out, node.children.len() as u64);
+    for child in &node.children {
+        push_tree(out, child, strings);
+    }
+}
+
+struct Decoder<R> {
+    reader: R,
+    strings: Vec<Arc<str>>,
+    /// Node ids are assigned in preorder, as the parser does
+    next_id: usize,
+}
+
+impl<R: Read> Decoder<R> {
+    fn string(&mut self) -> io::Result<Arc<str>> {
+        let index = read_varint(&mut self.reader)?;
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.strings.get(index))
+            .cloned()
+            .ok_or_else(|| invalid_data(format!("string index {} out of range", index)))
+    }
+
+    fn line(&mut self) -> io::Result<u32> {
+        u32::try_from(read_varint(&mut self.reader)?).map_err(invalid_data)
+    }
+
+    fn tree(&mut self) -> io::Result<TreeNode> {
+        let label = self.string()?.to_string();
+        let value = self.string()?.to_string();
+        let mut node = TreeNode::new(label, value, self.next_id);
+        self.next_id += 1;
+        let child_count = read_varint(&mut self.reader)?;
+        node.children.reserve(capacity_hint(child_count));
+        for _ in 0..child_count {
+            let child = self.tree()?;
+            node.add_child(Rc::new(child));
+        }
+        Ok(node)
+    }
+}
+
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(mut reader: impl Read) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("varint too long"))
+}
+
+/// Preallocation for a count read from the file, bounded so a corrupt count
+/// fails on the missing data instead of on allocation
+fn capacity_hint(count: u64) -> usize {
+    count.min(1024) as usize
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY: &str = r"
+        export function sumPrices(items: Item[]): number {
+            let total = 0;
+            for (const item of items) {
+                // skip free items
+                if (item.price > 0) {
+                    total += item.price * item.quantity;
+                }
+            }
+            return total;
+        }
+    ";
+
+    const CONSUMER: &str = r"
+        function computeTotal(items: Item[]): number {
+            let total = 0;
+            for (const item of items) {
+                if (item.price > 0) {
+                    total += item.price * item.quantity;
+                }
+            }
+            return total;
+        }
+
+        function greet(name: string): string {
+            const message = `Hello, ${name}`;
+            console.log(message);
+            return message;
+        }
+    ";
+
+    fn labels(node: &TreeNode) -> Vec<(String, String, usize)> {
+        let mut out = vec![(node.label.clone(), node.value.clone(), node.children.len())];
+        for child in &node.children {
+            out.extend(labels(child));
+        }
+        out
+    }
+
+    #[test]
+    fn test_round_trip_and_matching() {
+        let mut index = FunctionIndex::new();
+        assert_eq!(index.add_source("lib/prices.ts", LIBRARY), Ok(1));
+
+        let mut bytes = Vec::new();
+        index.write_to(&mut bytes).unwrap();
+        assert_eq!(&bytes[..6], MAGIC);
+        let restored = FunctionIndex::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(restored.len(), 1);
+        let (original, copy) = (&index.functions()[0], &restored.functions()[0]);
+        assert_eq!((&*copy.file, &*copy.name), ("lib/prices.ts", "sumPrices"));
+        assert_eq!((copy.start_line, copy.end_line), (original.start_line, original.end_line));
+        assert_eq!(labels(&copy.tree), labels(&original.tree));
+
+        let matches = restored
+            .find_matches(
+                "app/cart.ts",
+                CONSUMER,
+                0.9,
+                &TSEDOptions { size_penalty: false, ..TSEDOptions::default() },
+                &ComparisonBudget::unlimited(),
+            )
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].function.name, "computeTotal");
+        assert_eq!(&*matches[0].indexed.name, "sumPrices");
+    }
+
+    #[test]
+    fn test_entries_share_their_text_and_results_own_theirs() {
+        let mut index = FunctionIndex::new();
+        let two = format!("{LIBRARY}\n{}", LIBRARY.replace("sumPrices", "sumCosts"));
+        assert_eq!(index.add_source("lib/prices.ts", &two), Ok(2));
+        let (first, second) = (&index.functions()[0], &index.functions()[1]);
+        assert!(Arc::ptr_eq(&first.file, &second.file));
+
+        let mut bytes = Vec::new();
+        index.write_to(&mut bytes).unwrap();
+        let restored = FunctionIndex::read_from(bytes.as_slice()).unwrap();
+        assert!(Arc::ptr_eq(&restored.functions()[0].file, &restored.functions()[1].file));
+
+        let options = TSEDOptions { size_penalty: false, ..TSEDOptions::default() };
+        let budget = ComparisonBudget::unlimited();
+        let lenses = restored.lens("app/cart.ts", CONSUMER, 0.9, &options, &budget).unwrap();
+        drop(restored);
+        assert_eq!(lenses[0].function.name, "computeTotal");
+        let (best, _) = lenses[0].best.as_ref().unwrap();
+        assert_eq!(&*best.file, "lib/prices.ts");
+    }
+
+    #[test]
+    fn test_lens_counts_similar_functions_elsewhere() {
+        let mut index = FunctionIndex::new();
+        index.add_source("lib/prices.ts", LIBRARY).unwrap();
+        // An earlier version of the file, replaced by the text given to the lens
+        index.add_source("app/cart.ts", LIBRARY).unwrap();
+        let copy = CONSUMER.replace("computeTotal", "computeSubtotal");
+        let text = format!("{}\n{}", CONSUMER, &copy[..copy.find("function greet").unwrap()]);
+
+        let lenses = index
+            .lens(
+                "./app/cart.ts",
+                &text,
+                0.9,
+                &TSEDOptions { size_penalty: false, ..TSEDOptions::default() },
+                &ComparisonBudget::unlimited(),
+            )
+            .unwrap();
+        let summary: Vec<(&str, usize)> =
+            lenses.iter().map(|lens| (&*lens.function.name, lens.similar)).collect();
+        assert_eq!(summary, [("computeTotal", 2), ("greet", 0), ("computeSubtotal", 2)]);
+        let (best, similarity) = lenses[0].best.as_ref().unwrap();
+        assert_eq!((&*best.file, &*best.name), ("lib/prices.ts", "sumPrices"));
+        assert!(*similarity >= 0.9);
+        assert!(lenses[1].best.is_none());
+    }
+
+    #[test]
+    fn test_remove_file() {
+        let mut index = FunctionIndex::new();
+        index.add_source("lib/prices.ts", LIBRARY).unwrap();
+        index.add_source("app/cart.ts", CONSUMER).unwrap();
+
+        assert_eq!(index.remove_file("app/cart.ts"), 2);
+        assert_eq!(index.remove_file("app/cart.ts"), 0);
+        assert_eq!(index.len(), 1);
+        assert_eq!(&*index.functions()[0].file, "lib/prices.ts");
+    }
+
+    #[test]
+    fn test_rejects_foreign_and_newer_files() {
+        let error = FunctionIndex::read_from(&b"PK\x03\x04 zip"[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((INDEX_VERSION + 1).to_le_bytes());
+        bytes.extend([0, 0]);
+        let error = FunctionIndex::read_from(bytes.as_slice()).unwrap_err();
+        assert!(error.to_string().contains("unsupported index version"));
+
+        let mut index = FunctionIndex::new();
+        index.add_source("lib/prices.ts", LIBRARY).unwrap();
+        let mut bytes = Vec::new();
+        index.write_to(&mut bytes).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        assert!(FunctionIndex::read_from(bytes.as_slice()).is_err());
+    }
+}
@@ -414,6 +414,35 @@ impl GenericParserConfig {
         }
     }
 
+    pub fn bash() -> Self {
+        Self {
+            language: "bash".to_string(),
+            function_nodes: vec!["function_definition".to_string()],
+            type_nodes: vec![],
+            field_mappings: FieldMappings {
+                name_field: "name".to_string(),
+                params_field: "parameters".to_string(),
+                body_field: "body".to_string(),
+                decorator_field: None,
+                class_field: None,
+            },
+            value_nodes: vec![
+                "word".to_string(),
+                "variable_name".to_string(),
+                "string_content".to_string(),
+                "raw_string".to_string(),
+                "ansi_c_string".to_string(),
+                "number".to_string(),
+            ],
+            test_patterns: Some(TestPatterns {
+                attribute_patterns: vec![],
+                name_prefixes: vec!["test_".to_string()],
+                name_suffixes: vec![],
+            }),
+            custom_mappings: None,
+        }
+    }
+
     pub fn php() -> Self {
         Self {
             language: "php".to_string(),
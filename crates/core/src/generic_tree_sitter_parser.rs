@@ -1,6 +1,7 @@
 #![allow(clippy::io_other_error)]
 
 use crate::generic_parser_config::GenericParserConfig;
+use crate::interner::Symbol;
 use crate::language_parser::{GenericFunctionDef, GenericTypeDef, Language, LanguageParser};
 use crate::tree::TreeNode;
 use std::error::Error;
@@ -40,6 +41,7 @@ impl GenericTreeSitterParser {
                 (tree_sitter_c_sharp::LANGUAGE.into(), GenericParserConfig::csharp())
             }
             "ruby" | "rb" => (tree_sitter_ruby::LANGUAGE.into(), GenericParserConfig::ruby()),
+            "bash" | "sh" => (tree_sitter_bash::LANGUAGE.into(), GenericParserConfig::bash()),
             _ => {
                 return Err(Box::new(std::io::Error::new(
                     std::io::ErrorKind::InvalidInput,
@@ -222,14 +224,14 @@ impl GenericTreeSitterParser {
         let is_generator = self.is_generator_function(node, source);
 
         Some(GenericFunctionDef {
-            name: name_string,
+            name: name_string.into(),
             start_line: node.start_position().row as u32 + 1,
             end_line: node.end_position().row as u32 + 1,
             body_start_line: body_node.map(|n| n.start_position().row as u32 + 1).unwrap_or(0),
             body_end_line: body_node.map(|n| n.end_position().row as u32 + 1).unwrap_or(0),
             parameters: params,
             is_method: class_name.is_some(),
-            class_name: class_name.map(String::from),
+            class_name: class_name.map(Symbol::from),
             is_async,
             is_generator,
             decorators,
@@ -359,9 +361,113 @@ impl GenericTreeSitterParser {
             kind: actual_type_node.kind().to_string(),
             start_line: node.start_position().row as u32 + 1,
             end_line: node.end_position().row as u32 + 1,
-            fields: Vec::new(), // TODO: Extract fields based on language
+            fields: self.extract_type_fields(node, source),
         })
     }
+
+    /// Data members of a type; only C# and Ruby have field extraction so far
+    fn extract_type_fields(&self, node: Node, source: &str) -> Vec<String> {
+        let text = |n: Node| n.utf8_text(source.as_bytes()).unwrap_or("").to_string();
+        let mut fields = Vec::new();
+
+        match self.config.language.as_str() {
+            "csharp" => {
+                // Positional record parameters: `record Person(string Name, int Age)`
+                for params in
+                    node.children(&mut node.walk()).filter(|n| n.kind() == "parameter_list")
+                {
+                    for param in params.named_children(&mut params.walk()) {
+                        fields.extend(param.child_by_field_name("name").map(text));
+                    }
+                }
+
+                let Some(body) = node.child_by_field_name("body") else { return fields };
+                for member in body.named_children(&mut body.walk()) {
+                    match member.kind() {
+                        "property_declaration" => {
+                            fields.extend(member.child_by_field_name("name").map(text))
+                        }
+                        "field_declaration" => {
+                            // Constants are not part of the data shape
+                            let is_const = member
+                                .children(&mut member.walk())
+                                .any(|m| m.kind() == "modifier" && text(m) == "const");
+                            if is_const {
+                                continue;
+                            }
+                            for declaration in member
+                                .children(&mut member.walk())
+                                .filter(|n| n.kind() == "variable_declaration")
+                            {
+                                for declarator in declaration
+                                    .children(&mut declaration.walk())
+                                    .filter(|n| n.kind() == "variable_declarator")
+                                {
+                                    fields.extend(declarator.child_by_field_name("name").map(text));
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "ruby" => {
+                let Some(body) = node.child_by_field_name("body") else { return fields };
+                for statement in body.named_children(&mut body.walk()) {
+                    match statement.kind() {
+                        // attr_accessor :name, :email
+                        "call" => {
+                            let method = statement.child_by_field_name("method").map(text);
+                            if !matches!(
+                                method.as_deref(),
+                                Some("attr_accessor" | "attr_reader" | "attr_writer")
+                            ) {
+                                continue;
+                            }
+                            let Some(args) = statement.child_by_field_name("arguments") else {
+                                continue;
+                            };
+                            for arg in args.named_children(&mut args.walk()) {
+                                if arg.kind() == "simple_symbol" {
+                                    fields.push(text(arg).trim_start_matches(':').to_string());
+                                }
+                            }
+                        }
+                        // @name = name inside initialize
+                        "method"
+                            if statement.child_by_field_name("name").map(text).as_deref()
+                                == Some("initialize") =>
+                        {
+                            if let Some(method_body) = statement.child_by_field_name("body") {
+                                collect_instance_variables(method_body, source, &mut fields);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        fields.retain(|field| seen.insert(field.clone()));
+        fields
+    }
+}
+
+/// Instance variables assigned in a Ruby method body, without the `@`
+fn collect_instance_variables(node: Node, source: &str, fields: &mut Vec<String>) {
+    if node.kind() == "assignment" {
+        if let Some(left) = node.child_by_field_name("left") {
+            if left.kind() == "instance_variable" {
+                let name = left.utf8_text(source.as_bytes()).unwrap_or("");
+                fields.push(name.trim_start_matches('@').to_string());
+            }
+        }
+    }
+    for child in node.children(&mut node.walk()) {
+        collect_instance_variables(child, source, fields);
+    }
 }
 
 impl LanguageParser for GenericTreeSitterParser {
@@ -423,6 +529,7 @@ impl LanguageParser for GenericTreeSitterParser {
             "cpp" => Language::Cpp,
             "csharp" => Language::CSharp,
             "ruby" => Language::Ruby,
+            "bash" => Language::Bash,
             "php" => Language::Php,
             _ => Language::Unknown,
         }
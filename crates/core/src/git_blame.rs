@@ -0,0 +1,235 @@
+use git2::{BlameOptions, Repository};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const UNCOMMITTED_AUTHOR: &str = "Not Committed Yet";
+
+/// The most recent change touching a line range, i.e. when that copy was introduced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameInfo {
+    /// Full commit id, empty when the lines are not committed yet
+    pub commit: String,
+    pub author: String,
+    /// Commit time in seconds since the Unix epoch
+    pub time: i64,
+}
+
+impl BlameInfo {
+    #[must_use]
+    pub fn is_committed(&self) -> bool {
+        !self.commit.is_empty()
+    }
+
+    #[must_use]
+    pub fn short_commit(&self) -> &str {
+        if self.is_committed() {
+            &self.commit[..self.commit.len().min(8)]
+        } else {
+            "uncommitted"
+        }
+    }
+
+    /// Commit date as YYYY-MM-DD (UTC)
+    #[must_use]
+    pub fn date(&self) -> String {
+        let (year, month, day) = civil_from_days(self.time.div_euclid(86_400));
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// Which side of a pair holds the older copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OlderCopy {
+    First,
+    Second,
+    /// Both ranges were last changed by the same commit
+    Same,
+}
+
+/// Decide which copy came first based on the commits that introduced them
+#[must_use]
+pub fn older_copy(first: &BlameInfo, second: &BlameInfo) -> OlderCopy {
+    if first.commit == second.commit && first.is_committed() {
+        return OlderCopy::Same;
+    }
+    if first.time <= second.time {
+        OlderCopy::First
+    } else {
+        OlderCopy::Second
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BlameHunk {
+    start_line: usize,
+    end_line: usize,
+    info: BlameInfo,
+}
+
+/// Runs `git blame` on line ranges, caching the result per file
+pub struct GitBlamer {
+    repo: Repository,
+    workdir: PathBuf,
+    cache: HashMap<PathBuf, Option<Vec<BlameHunk>>>,
+}
+
+impl GitBlamer {
+    /// Open the repository containing `path`, or `None` outside of a git work tree
+    #[must_use]
+    pub fn discover(path: &Path) -> Option<Self> {
+        let repo = Repository::discover(path).ok()?;
+        let workdir = repo.workdir()?.canonicalize().ok()?;
+        Some(Self { repo, workdir, cache: HashMap::new() })
+    }
+
+    /// Blame the inclusive 1-based line range of a file.
+    /// Returns `None` for files outside the repository or not tracked by git.
+    pub fn blame_range(
+        &mut self,
+        file: &Path,
+        start_line: usize,
+        end_line: usize,
+    ) -> Option<BlameInfo> {
+        let file = file.canonicalize().ok()?;
+        if !self.cache.contains_key(&file) {
+            let hunks = self.blame_file(&file);
+            self.cache.insert(file.clone(), hunks);
+        }
+        let hunks = self.cache.get(&file)?.as_ref()?;
+
+        let (start, end) = (start_line.min(end_line), start_line.max(end_line));
+        hunks
+            .iter()
+            .filter(|hunk| hunk.start_line <= end && hunk.end_line >= start)
+            .max_by_key(|hunk| hunk.info.time)
+            .map(|hunk| hunk.info.clone())
+    }
+
+    fn blame_file(&self, file: &Path) -> Option<Vec<BlameHunk>> {
+        let relative = file.strip_prefix(&self.workdir).ok()?;
+        let committed = self.repo.blame_file(relative, Some(&mut BlameOptions::new())).ok()?;
+
+        // Blame the working tree contents so uncommitted edits are attributed correctly
+        let content = fs::read(file).ok()?;
+        let blame = committed.blame_buffer(&content).ok()?;
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+        let hunks = blame
+            .iter()
+            .filter(|hunk| hunk.lines_in_hunk() > 0)
+            .map(|hunk| {
+                let commit_id = hunk.final_commit_id();
+                let info = if commit_id.is_zero() {
+                    BlameInfo {
+                        commit: String::new(),
+                        author: UNCOMMITTED_AUTHOR.to_string(),
+                        time: now,
+                    }
+                } else {
+                    let signature = hunk.final_signature();
+                    BlameInfo {
+                        commit: commit_id.to_string(),
+                        author: signature.name().unwrap_or("unknown").to_string(),
+                        time: signature.when().seconds(),
+                    }
+                };
+                BlameHunk {
+                    start_line: hunk.final_start_line(),
+                    end_line: hunk.final_start_line() + hunk.lines_in_hunk() - 1,
+                    info,
+                }
+            })
+            .collect();
+
+        Some(hunks)
+    }
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+
+    fn commit_file(repo: &Repository, name: &str, content: &str, author: &str, time: i64) {
+        let workdir = repo.workdir().unwrap();
+        fs::write(workdir.join(name), content).unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+        let signature =
+            Signature::new(author, "dev@example.com", &git2::Time::new(time, 0)).unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, "commit", &tree, &parents).unwrap();
+    }
+
+    #[test]
+    fn formats_dates() {
+        let info =
+            BlameInfo { commit: "0123456789abcdef".to_string(), author: "a".to_string(), time: 0 };
+        assert_eq!(info.date(), "1970-01-01");
+        assert_eq!(info.short_commit(), "01234567");
+
+        let info = BlameInfo { time: 1_709_251_200, ..info };
+        assert_eq!(info.date(), "2024-03-01");
+    }
+
+    #[test]
+    fn finds_newer_copy_and_its_author() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+
+        let original = "function a() {\n  return 1;\n}\n";
+        commit_file(&repo, "a.ts", original, "Alice", 1_600_000_000);
+        commit_file(&repo, "b.ts", original, "Bob", 1_700_000_000);
+        fs::write(dir.path().join("c.ts"), original).unwrap();
+
+        let mut blamer = GitBlamer::discover(dir.path()).unwrap();
+        let first = blamer.blame_range(&dir.path().join("a.ts"), 1, 3).unwrap();
+        let second = blamer.blame_range(&dir.path().join("b.ts"), 1, 3).unwrap();
+
+        assert_eq!(first.author, "Alice");
+        assert_eq!(second.author, "Bob");
+        assert_eq!(older_copy(&first, &second), OlderCopy::First);
+        assert_eq!(older_copy(&second, &first), OlderCopy::Second);
+        assert_eq!(older_copy(&first, &first), OlderCopy::Same);
+
+        // Untracked files cannot be blamed
+        assert!(blamer.blame_range(&dir.path().join("c.ts"), 1, 3).is_none());
+    }
+
+    #[test]
+    fn attributes_uncommitted_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit_file(&repo, "a.ts", "line1\nline2\n", "Alice", 1_600_000_000);
+        fs::write(dir.path().join("a.ts"), "line1\nline2\nline3\n").unwrap();
+
+        let mut blamer = GitBlamer::discover(dir.path()).unwrap();
+        let info = blamer.blame_range(&dir.path().join("a.ts"), 1, 3).unwrap();
+        assert!(!info.is_committed());
+        assert_eq!(info.short_commit(), "uncommitted");
+
+        let info = blamer.blame_range(&dir.path().join("a.ts"), 1, 2).unwrap();
+        assert_eq!(info.author, "Alice");
+    }
+}
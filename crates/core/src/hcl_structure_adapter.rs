@@ -0,0 +1,472 @@
+//! Duplicate detection for Terraform and other HCL files.
+//!
+//! Top-level blocks (`resource`, `data`, `module`, `variable`, `output`, ...)
+//! become structures whose members are their attributes, with nested blocks
+//! flattened into dotted paths (`versioning.enabled`) and `dynamic` blocks
+//! unwrapped. Values are compared by category, like config blocks, after
+//! provider-specific normalization: tag maps are reduced to their sorted keys
+//! and attributes set to the provider default are dropped, since leaving them
+//! out is equivalent. Resources and data sources are only compared with blocks
+//! of the same type.
+
+use crate::analyzer::{AnalysisUnit, Analyzer, AnalyzerError};
+use crate::structure_comparator::{
+    ComparisonOptions, MemberComparisonStrategy, SourceLocation, Structure, StructureComparator,
+    StructureIdentifier, StructureKind, StructureMember, StructureMetadata,
+};
+use crate::tree::TreeNode;
+use hcl_edit::expr::{Expression, ObjectKey, TraversalOperator};
+use hcl_edit::structure::{Block, Body};
+use hcl_edit::Span;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Attributes holding tag maps, compared by their keys regardless of order
+const TAG_ATTRIBUTES: &[&str] = &["tags", "tags_all", "labels", "default_tags"];
+
+/// Attribute values equivalent to omitting the attribute: block kind, attribute, literal
+const DEFAULT_VALUES: &[(&str, &str, &str)] = &[
+    ("resource aws_s3_bucket", "force_destroy", "false"),
+    ("resource aws_s3_bucket", "object_lock_enabled", "false"),
+    ("resource aws_vpc", "enable_dns_support", "true"),
+    ("resource aws_vpc", "enable_dns_hostnames", "false"),
+    ("resource aws_vpc", "instance_tenancy", "default"),
+    ("resource aws_instance", "monitoring", "false"),
+    ("resource aws_instance", "ebs_optimized", "false"),
+    ("resource aws_security_group", "revoke_rules_on_delete", "false"),
+    ("resource aws_db_instance", "multi_az", "false"),
+    ("resource aws_db_instance", "publicly_accessible", "false"),
+    ("resource aws_lambda_function", "memory_size", "128"),
+    ("resource aws_lambda_function", "timeout", "3"),
+    ("resource google_storage_bucket", "force_destroy", "false"),
+    ("resource google_storage_bucket", "storage_class", "STANDARD"),
+    ("resource azurerm_storage_account", "account_kind", "StorageV2"),
+    ("resource azurerm_storage_account", "https_traffic_only_enabled", "true"),
+    ("variable", "nullable", "true"),
+    ("variable", "sensitive", "false"),
+    ("output", "sensitive", "false"),
+];
+
+/// Meta-arguments whose default holds for every resource and module
+const DEFAULT_META_ARGUMENTS: &[(&str, &str)] = &[("count", "1")];
+
+/// A top-level HCL block with its attributes flattened
+#[derive(Debug, Clone)]
+pub struct HclBlock {
+    /// Address such as `aws_s3_bucket.logs`, `module.vpc` or `var.region`
+    pub address: String,
+    /// Block type, with the resource type for `resource` and `data` blocks
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Attribute paths and their normalized value categories
+    pub attributes: Vec<(String, String)>,
+}
+
+impl From<HclBlock> for Structure {
+    fn from(block: HclBlock) -> Self {
+        let members = block
+            .attributes
+            .into_iter()
+            .map(|(name, value_type)| StructureMember {
+                name,
+                value_type: value_type.into(),
+                modifiers: vec![],
+                nested: None,
+            })
+            .collect();
+
+        Structure {
+            identifier: StructureIdentifier {
+                name: block.address.into(),
+                kind: StructureKind::Generic(block.kind),
+                namespace: Some(block.file_path.as_str().into()),
+            },
+            members,
+            metadata: StructureMetadata {
+                location: SourceLocation {
+                    file_path: block.file_path.into(),
+                    start_line: block.start_line,
+                    end_line: block.end_line,
+                },
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Parse an HCL file into its top-level blocks
+pub fn extract_hcl_blocks(source: &str, file_path: &str) -> Result<Vec<HclBlock>, String> {
+    let body: Body = source.parse().map_err(|e: hcl_edit::parser::Error| e.to_string())?;
+    Ok(body
+        .blocks()
+        .map(|block| {
+            let labels: Vec<&str> = block.labels.iter().map(|label| label.as_str()).collect();
+            let ident = block.ident.as_str();
+            let (address, kind) = match (ident, labels.as_slice()) {
+                ("resource", [kind, name, ..]) => {
+                    (format!("{}.{}", kind, name), format!("resource {}", kind))
+                }
+                ("data", [kind, name, ..]) => {
+                    (format!("data.{}.{}", kind, name), format!("data {}", kind))
+                }
+                ("variable", [name, ..]) => (format!("var.{}", name), ident.to_string()),
+                (_, []) => (ident.to_string(), ident.to_string()),
+                (_, labels) => (format!("{}.{}", ident, labels.join(".")), ident.to_string()),
+            };
+
+            let mut flattened = Vec::new();
+            flatten_body(&block.body, "", &mut flattened);
+            let attributes = flattened
+                .into_iter()
+                .filter(|((name, _), literal)| !is_default(&kind, name, literal.as_deref()))
+                .map(|(attribute, _)| attribute)
+                .collect();
+            let (start_line, end_line) = block_lines(source, block);
+
+            HclBlock {
+                address,
+                kind,
+                file_path: file_path.to_string(),
+                start_line,
+                end_line,
+                attributes,
+            }
+        })
+        .collect())
+}
+
+/// Attribute paths of `body` with their value category and literal value,
+/// descending into nested blocks
+fn flatten_body(
+    body: &Body,
+    prefix: &str,
+    attributes: &mut Vec<((String, String), Option<String>)>,
+) {
+    for attribute in body.attributes() {
+        let key = attribute.key.as_str();
+        let value_type = if TAG_ATTRIBUTES.contains(&key) {
+            tag_keys(&attribute.value)
+        } else {
+            categorize_hcl_value(&attribute.value)
+        };
+        attributes.push(((format!("{}{}", prefix, key), value_type), literal(&attribute.value)));
+    }
+    for block in body.blocks() {
+        match (block.ident.as_str(), block.labels.first()) {
+            // `dynamic "ingress" { content { ... } }` stands for repeated `ingress` blocks
+            ("dynamic", Some(label)) => {
+                let prefix = format!("{}{}.", prefix, label.as_str());
+                for content in block.body.get_blocks("content") {
+                    flatten_body(&content.body, &prefix, attributes);
+                }
+            }
+            (ident, _) => flatten_body(&block.body, &format!("{}{}.", prefix, ident), attributes),
+        }
+    }
+}
+
+/// Literal text of scalar values, for matching provider defaults
+fn literal(value: &Expression) -> Option<String> {
+    match value {
+        Expression::Bool(value) => Some(value.value().to_string()),
+        Expression::Number(value) => Some(value.value().to_string()),
+        Expression::String(value) => Some(value.value().clone()),
+        _ => None,
+    }
+}
+
+fn is_default(kind: &str, name: &str, literal: Option<&str>) -> bool {
+    let Some(literal) = literal else {
+        return false;
+    };
+    let meta = (kind.starts_with("resource") || kind.starts_with("data") || kind == "module")
+        && DEFAULT_META_ARGUMENTS.contains(&(name, literal));
+    meta || DEFAULT_VALUES.contains(&(kind, name, literal))
+}
+
+/// Sorted keys of a tag map, so tag order and tag values do not matter
+fn tag_keys(value: &Expression) -> String {
+    let Expression::Object(object) = value else {
+        return categorize_hcl_value(value);
+    };
+    let mut keys: Vec<String> = object
+        .iter()
+        .map(|(key, _)| match key {
+            ObjectKey::Ident(ident) => ident.as_str().to_string(),
+            ObjectKey::Expression(Expression::String(text)) => text.value().clone(),
+            ObjectKey::Expression(expression) => expression.to_string().trim().to_string(),
+        })
+        .collect();
+    keys.sort_unstable();
+    format!("tags({})", keys.join(","))
+}
+
+/// Category of an attribute value, used as its type
+fn categorize_hcl_value(value: &Expression) -> String {
+    match value {
+        Expression::Null(_) => "null".to_string(),
+        Expression::Bool(_) => "bool".to_string(),
+        Expression::Number(_) => "number".to_string(),
+        Expression::String(_) => "string".to_string(),
+        Expression::Array(_) | Expression::ForExpr(_) => "list".to_string(),
+        Expression::Object(_) => "object".to_string(),
+        Expression::StringTemplate(_) | Expression::HeredocTemplate(_) => "template".to_string(),
+        Expression::Parenthesis(inner) => categorize_hcl_value(inner.inner()),
+        Expression::Variable(_) | Expression::Traversal(_) => reference_kind(value),
+        Expression::FuncCall(call) => format!("call {}", call.name.name.as_str()),
+        Expression::Conditional(_) | Expression::UnaryOp(_) | Expression::BinaryOp(_) => {
+            "expression".to_string()
+        }
+    }
+}
+
+/// Kind of a reference: `var`, `local`, `module` or the referenced resource
+/// type with its attribute (`aws_vpc.id`), without the instance name
+fn reference_kind(value: &Expression) -> String {
+    let mut segments = Vec::new();
+    let mut current = value;
+    if let Expression::Traversal(traversal) = value {
+        segments.extend(traversal.operators.iter().filter_map(|operator| match operator.value() {
+            TraversalOperator::GetAttr(ident) => Some(ident.as_str()),
+            _ => None,
+        }));
+        current = &traversal.expr;
+    }
+    let Expression::Variable(root) = current else {
+        return "reference".to_string();
+    };
+    match (root.as_str(), segments.as_slice()) {
+        (root @ ("var" | "local" | "module" | "each" | "count" | "path"), _) => {
+            format!("ref {}", root)
+        }
+        ("data", [kind, _, attribute, ..]) => format!("ref data.{}.{}", kind, attribute),
+        (kind, [_, attribute, ..]) => format!("ref {}.{}", kind, attribute),
+        (kind, _) => format!("ref {}", kind),
+    }
+}
+
+fn block_lines(source: &str, block: &Block) -> (usize, usize) {
+    let span = block.span().unwrap_or(0..0);
+    let line_of = |offset: usize| source[..offset.min(source.len())].matches('\n').count() + 1;
+    (line_of(span.start), line_of(span.end.saturating_sub(1).max(span.start)))
+}
+
+/// Tree view of a block: one node per attribute path, labeled with its category
+fn hcl_tree(block: &HclBlock) -> TreeNode {
+    let mut tree = TreeNode::new("block".to_string(), block.kind.clone(), 0);
+    for (id, (name, value_type)) in block.attributes.iter().enumerate() {
+        tree.add_child(Rc::new(TreeNode::new(name.clone(), value_type.clone(), id + 1)));
+    }
+    tree
+}
+
+/// Analyzer for Terraform and HCL files, comparing top-level blocks
+pub struct HclAnalyzer {
+    min_members: usize,
+    comparator: RefCell<StructureComparator>,
+}
+
+impl HclAnalyzer {
+    pub fn new() -> Self {
+        let options = ComparisonOptions {
+            name_weight: 0.1,      // Copied resources are usually renamed
+            structure_weight: 0.9, // Attributes and their value categories
+            member_comparison: MemberComparisonStrategy::Exact,
+            ignore_order: true,
+            ..Default::default()
+        };
+        Self::with_options(options, 3)
+    }
+
+    /// Blocks with fewer than `min_members` attributes after normalization are not compared
+    pub fn with_options(options: ComparisonOptions, min_members: usize) -> Self {
+        Self { min_members, comparator: RefCell::new(StructureComparator::new(options)) }
+    }
+}
+
+impl Default for HclAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Analyzer for HclAnalyzer {
+    fn name(&self) -> &str {
+        "hcl"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tf", "hcl"]
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let blocks = extract_hcl_blocks(source, file)
+            .map_err(|e| format!("Failed to parse {}: {}", file, e))?;
+        Ok(blocks
+            .into_iter()
+            .filter(|block| block.attributes.len() >= self.min_members)
+            .map(|block| AnalysisUnit {
+                name: block.address.clone(),
+                file: file.to_string(),
+                start_line: block.start_line as u32,
+                end_line: block.end_line as u32,
+                tree: Rc::new(hcl_tree(&block)),
+                structure: Some(Structure::from(block)),
+            })
+            .collect())
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        match (&first.structure, &second.structure) {
+            // A bucket and an instance never duplicate each other
+            (Some(s1), Some(s2)) if s1.identifier.kind == s2.identifier.kind => {
+                self.comparator.borrow_mut().compare(s1, s2).overall_similarity
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::run_analyzer;
+
+    const LOGS: &str = r#"
+variable "region" {
+  type    = string
+  default = "us-east-1"
+}
+
+resource "aws_s3_bucket" "logs" {
+  bucket        = "${var.prefix}-logs"
+  force_destroy = false
+
+  tags = {
+    Name        = "logs"
+    Environment = var.environment
+  }
+
+  versioning {
+    enabled = true
+  }
+
+  lifecycle_rule {
+    id      = "expire"
+    enabled = true
+    expiration {
+      days = 90
+    }
+  }
+}
+
+resource "aws_instance" "web" {
+  ami           = data.aws_ami.ubuntu.id
+  instance_type = "t3.micro"
+  subnet_id     = aws_subnet.public.id
+  count         = 1
+}
+"#;
+
+    #[test]
+    fn test_extracts_blocks_with_normalized_attributes() {
+        let blocks = extract_hcl_blocks(LOGS, "main.tf").unwrap();
+        let addresses: Vec<_> =
+            blocks.iter().map(|b| (b.address.as_str(), b.kind.as_str(), b.start_line)).collect();
+        assert_eq!(
+            addresses,
+            vec![
+                ("var.region", "variable", 2),
+                ("aws_s3_bucket.logs", "resource aws_s3_bucket", 7),
+                ("aws_instance.web", "resource aws_instance", 29),
+            ]
+        );
+        assert_eq!(blocks[1].end_line, 27);
+
+        let attributes: Vec<_> =
+            blocks[1].attributes.iter().map(|(n, t)| (n.as_str(), t.as_str())).collect();
+        // `force_destroy = false` is the provider default and is dropped
+        assert_eq!(
+            attributes,
+            vec![
+                ("bucket", "template"),
+                ("tags", "tags(Environment,Name)"),
+                ("versioning.enabled", "bool"),
+                ("lifecycle_rule.id", "string"),
+                ("lifecycle_rule.enabled", "bool"),
+                ("lifecycle_rule.expiration.days", "number"),
+            ]
+        );
+
+        let instance: Vec<_> =
+            blocks[2].attributes.iter().map(|(n, t)| (n.as_str(), t.as_str())).collect();
+        assert_eq!(
+            instance,
+            vec![
+                ("ami", "ref data.aws_ami.id"),
+                ("instance_type", "string"),
+                ("subnet_id", "ref aws_subnet.id"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_copied_resources_of_the_same_type() {
+        // Renamed, tags reordered, a default spelled out and versioning
+        // written as a dynamic block
+        let copy = r#"
+resource "aws_s3_bucket" "audit" {
+  bucket = "${var.prefix}-audit"
+
+  tags = {
+    Environment = var.environment
+    Name        = "audit"
+  }
+
+  dynamic "versioning" {
+    for_each = [1]
+    content {
+      enabled = true
+    }
+  }
+
+  lifecycle_rule {
+    id      = "expire"
+    enabled = true
+    expiration {
+      days = 30
+    }
+  }
+}
+
+resource "aws_instance" "web" {
+  instance_type = "t3.micro"
+  monitoring    = true
+  key_name      = var.key_name
+}
+"#;
+        let mut analyzer = HclAnalyzer::new();
+        let files = vec![
+            ("a/main.tf".to_string(), LOGS.to_string()),
+            ("b/main.tf".to_string(), copy.to_string()),
+        ];
+        let findings = run_analyzer(&mut analyzer, &files, 0.8).unwrap();
+        let pairs: Vec<_> =
+            findings.iter().map(|f| (f.first.name.as_str(), f.second.name.as_str())).collect();
+        assert_eq!(pairs, vec![("aws_s3_bucket.logs", "aws_s3_bucket.audit")]);
+
+        let units = analyzer.extract("b/main.tf", copy).unwrap();
+        let bucket = analyzer.extract("a/main.tf", LOGS).unwrap().remove(0);
+        // Different resource types are never compared
+        assert_eq!(analyzer.compare(&bucket, &units[1]), 0.0);
+    }
+
+    #[test]
+    fn test_reports_parse_errors() {
+        let mut analyzer = HclAnalyzer::new();
+        let error = analyzer.extract("broken.tf", "resource \"aws_s3_bucket\" {").unwrap_err();
+        assert!(error.to_string().starts_with("Failed to parse broken.tf"));
+    }
+}
@@ -0,0 +1,193 @@
+//! Duplicate detection for HTML markup.
+//!
+//! Every element holding at least a few elements is a unit, so a duplicated
+//! card or form is found whether or not the surrounding page matches. Elements
+//! are compared by tag and attribute names; attribute values and text are
+//! abstracted, so markup that only differs in classes, labels or bound values
+//! is reported as duplicate. Comments are dropped.
+
+use crate::analyzer::{AnalysisUnit, Analyzer, AnalyzerError};
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, TSEDOptions};
+use crate::APTEDOptions;
+use std::rc::Rc;
+use tree_sitter::{Node, Parser};
+
+/// Elements with fewer elements, themselves included, are not reported
+pub const DEFAULT_HTML_MIN_ELEMENTS: usize = 3;
+
+const ELEMENT_KINDS: [&str; 3] = ["element", "script_element", "style_element"];
+
+pub struct HtmlAnalyzer {
+    parser: Parser,
+    options: TSEDOptions,
+    min_elements: usize,
+}
+
+impl HtmlAnalyzer {
+    pub fn new() -> Result<Self, AnalyzerError> {
+        Self::with_options(
+            TSEDOptions {
+                apted_options: APTEDOptions {
+                    compare_values: true,
+                    ..TSEDOptions::default().apted_options
+                },
+                min_lines: 1,
+                size_penalty: false,
+                ..TSEDOptions::default()
+            },
+            DEFAULT_HTML_MIN_ELEMENTS,
+        )
+    }
+
+    /// Use custom TSED options; `min_elements` limits the size of reported elements
+    pub fn with_options(options: TSEDOptions, min_elements: usize) -> Result<Self, AnalyzerError> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_html::LANGUAGE.into())
+            .map_err(|e| format!("Failed to set language: {:?}", e))?;
+        Ok(Self { parser, options, min_elements: min_elements.max(1) })
+    }
+
+    /// The tree of an element and the number of elements in it
+    fn convert_element(node: Node, source: &str, id_counter: &mut usize) -> (TreeNode, usize) {
+        let text =
+            |node: Node| node.utf8_text(source.as_bytes()).unwrap_or("").to_ascii_lowercase();
+        let mut tree = TreeNode::new("element".to_string(), String::new(), *id_counter);
+        *id_counter += 1;
+        let mut elements = 1;
+
+        for child in node.children(&mut node.walk()) {
+            match child.kind() {
+                "start_tag" | "self_closing_tag" => {
+                    for part in child.children(&mut child.walk()) {
+                        match part.kind() {
+                            "tag_name" => tree.value = text(part),
+                            "attribute" => {
+                                let name = part
+                                    .children(&mut part.walk())
+                                    .find(|n| n.kind() == "attribute_name")
+                                    .map_or_else(String::new, text);
+                                tree.add_child(Rc::new(TreeNode::new(
+                                    "attribute".to_string(),
+                                    name,
+                                    *id_counter,
+                                )));
+                                *id_counter += 1;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                kind if ELEMENT_KINDS.contains(&kind) => {
+                    let (element, count) = Self::convert_element(child, source, id_counter);
+                    tree.add_child(Rc::new(element));
+                    elements += count;
+                }
+                "text" | "raw_text" | "entity" => {
+                    tree.add_child(Rc::new(TreeNode::new(
+                        "text".to_string(),
+                        String::new(),
+                        *id_counter,
+                    )));
+                    *id_counter += 1;
+                }
+                _ => {}
+            }
+        }
+        (tree, elements)
+    }
+
+    fn collect_elements(
+        &self,
+        node: Node,
+        file: &str,
+        source: &str,
+        units: &mut Vec<AnalysisUnit>,
+    ) {
+        if ELEMENT_KINDS.contains(&node.kind()) {
+            let (tree, elements) = Self::convert_element(node, source, &mut 0);
+            let lines = node.end_position().row - node.start_position().row + 1;
+            if elements >= self.min_elements && lines as u32 >= self.options.min_lines {
+                units.push(AnalysisUnit {
+                    name: format!("<{}>", tree.value),
+                    file: file.to_string(),
+                    start_line: node.start_position().row as u32 + 1,
+                    end_line: node.end_position().row as u32 + 1,
+                    tree: Rc::new(tree),
+                    structure: None,
+                });
+            }
+        }
+
+        for child in node.children(&mut node.walk()) {
+            self.collect_elements(child, file, source, units);
+        }
+    }
+}
+
+impl Analyzer for HtmlAnalyzer {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["html", "htm"]
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let tree = self
+            .parser
+            .parse(source, None)
+            .ok_or_else(|| format!("Failed to parse HTML in {}", file))?;
+        let mut units = Vec::new();
+        self.collect_elements(tree.root_node(), file, source, &mut units);
+        Ok(units)
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        calculate_tsed(&first.tree, &second.tree, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::run_analyzer;
+
+    const CARD: &str = r#"<div class="card">
+  <h2 class="title">Orders</h2>
+  <!-- totals -->
+  <p>Total: <b>42</b></p>
+  <button type="button" disabled>Refresh</button>
+</div>
+"#;
+
+    #[test]
+    fn test_markup_differing_in_values_is_duplicate() {
+        let mut analyzer = HtmlAnalyzer::new().unwrap();
+        let other = CARD.replace("card", "panel").replace("Orders", "Invoices").replace("42", "7");
+        let files = vec![
+            ("orders.html".to_string(), CARD.to_string()),
+            ("invoices.html".to_string(), format!("<main>\n{}</main>\n", other)),
+        ];
+        let findings = run_analyzer(&mut analyzer, &files, 0.95).unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].similarity, 1.0);
+        assert_eq!(findings[0].first.name, "<div>");
+        assert_eq!((findings[0].second.start_line, findings[0].second.end_line), (2, 7));
+    }
+
+    #[test]
+    fn test_small_and_differently_structured_elements() {
+        let mut analyzer = HtmlAnalyzer::new().unwrap();
+        let units = analyzer.extract("a.html", "<p>Total: <b>42</b></p>").unwrap();
+        assert!(units.is_empty());
+
+        let form = "<form>\n  <label>Name</label>\n  <input name=\"name\">\n  <select><option>A</option></select>\n</form>\n";
+        let card = analyzer.extract("a.html", CARD).unwrap();
+        let form = analyzer.extract("b.html", form).unwrap();
+        assert!(analyzer.compare(&card[0], &form[0]) < 0.7);
+    }
+}
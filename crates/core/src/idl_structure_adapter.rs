@@ -0,0 +1,899 @@
+//! Duplicate detection for Protocol Buffers and Thrift IDL files.
+//!
+//! Protobuf messages and Thrift structs, unions and exceptions become message
+//! structures whose members are their fields, and enums become enum
+//! structures whose members are their values. Scalar types are mapped to a
+//! shared vocabulary (`sint32` and `i32` are both `int32`, `repeated T` and
+//! `list<T>` are both `T[]`), so a message copied from a `.proto` file into a
+//! `.thrift` file is still found.
+//!
+//! Field numbers decide whether a copy is still wire-compatible. Two messages
+//! with the same fields under different numbers, or where one reuses a number
+//! the other reserves, are evolved copies rather than duplicates: they score
+//! lower and are reported as such.
+
+use crate::analyzer::{AnalysisUnit, Analyzer, AnalyzerError, AnalyzerFinding, UnitLocation};
+use crate::cli_output::format_function_output;
+use crate::structure_comparator::{
+    ComparisonOptions, SourceLocation, Structure, StructureComparator, StructureIdentifier,
+    StructureKind, StructureMember, StructureMetadata,
+};
+use crate::tree::TreeNode;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// Kind of message-like definitions (proto messages, Thrift structs)
+pub const IDL_MESSAGE: &str = "message";
+/// Kind of enum definitions
+pub const IDL_ENUM: &str = "enum";
+
+/// Share of the similarity lost when every field common to both definitions
+/// has a conflicting number
+const NUMBERING_CONFLICT_PENALTY: f64 = 0.3;
+
+/// A field of a message or a value of an enum
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdlField {
+    pub name: String,
+    /// Normalized type; `value` for enum values
+    pub value_type: String,
+    /// Field number or enum value, if declared
+    pub number: Option<i64>,
+    pub required: bool,
+}
+
+/// A message, struct or enum from a `.proto` or `.thrift` file
+#[derive(Debug, Clone)]
+pub struct IdlDefinition {
+    /// Name, qualified by the enclosing messages (`Order.Item`)
+    pub name: String,
+    /// [`IDL_MESSAGE`] or [`IDL_ENUM`]
+    pub kind: &'static str,
+    pub file_path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub fields: Vec<IdlField>,
+    /// Reserved field number ranges, inclusive
+    pub reserved: Vec<(i64, i64)>,
+}
+
+impl IdlDefinition {
+    fn is_reserved(&self, number: i64) -> bool {
+        self.reserved.iter().any(|&(low, high)| (low..=high).contains(&number))
+    }
+}
+
+impl From<&IdlDefinition> for Structure {
+    fn from(definition: &IdlDefinition) -> Self {
+        let members = definition
+            .fields
+            .iter()
+            .map(|field| StructureMember {
+                name: field.name.clone(),
+                value_type: field.value_type.as_str().into(),
+                modifiers: if field.required { vec!["required".to_string()] } else { vec![] },
+                nested: None,
+            })
+            .collect();
+
+        Structure {
+            identifier: StructureIdentifier {
+                name: definition.name.as_str().into(),
+                kind: StructureKind::Generic(definition.kind.to_string()),
+                namespace: Some(definition.file_path.as_str().into()),
+            },
+            members,
+            metadata: StructureMetadata {
+                location: SourceLocation {
+                    file_path: definition.file_path.as_str().into(),
+                    start_line: definition.start_line,
+                    end_line: definition.end_line,
+                },
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// A field present in two definitions under conflicting numbers
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberingConflict {
+    /// The field has a different number in each definition
+    Renumbered { field: String, first: i64, second: i64 },
+    /// The field's number is reserved in the other definition
+    Reserved { field: String, number: i64 },
+}
+
+/// Numbering conflicts between the fields two definitions share by name,
+/// and the number of shared fields
+#[must_use]
+pub fn numbering_conflicts(
+    first: &IdlDefinition,
+    second: &IdlDefinition,
+) -> (Vec<NumberingConflict>, usize) {
+    let mut conflicts = Vec::new();
+    let mut shared = 0;
+    for field in &first.fields {
+        let Some(other) = second.fields.iter().find(|other| other.name == field.name) else {
+            continue;
+        };
+        shared += 1;
+        match (field.number, other.number) {
+            (Some(a), Some(b)) if a != b => conflicts.push(NumberingConflict::Renumbered {
+                field: field.name.clone(),
+                first: a,
+                second: b,
+            }),
+            _ => {}
+        }
+    }
+    // A field of one definition reusing a number the other retired
+    for (definition, other) in [(first, second), (second, first)] {
+        for field in &definition.fields {
+            if let Some(number) = field.number.filter(|&number| other.is_reserved(number)) {
+                conflicts.push(NumberingConflict::Reserved { field: field.name.clone(), number });
+            }
+        }
+    }
+    (conflicts, shared)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum IdlToken {
+    Name(String),
+    Str,
+    Punct(char),
+}
+
+/// Tokenize proto and Thrift sources, skipping `//`, `#` and `/* */` comments
+fn tokenize_idl(source: &str) -> Vec<(IdlToken, usize)> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '#' || (c == '/' && chars.get(i + 1) == Some(&'/')) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push((IdlToken::Str, line));
+        } else if c.is_alphanumeric() || c == '_' || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.')) {
+                i += 1;
+            }
+            tokens.push((IdlToken::Name(chars[start..i].iter().collect()), line));
+        } else {
+            tokens.push((IdlToken::Punct(c), line));
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+/// Map proto and Thrift scalar types to a shared vocabulary
+fn normalize_idl_type(name: &str) -> String {
+    match name {
+        "int32" | "sint32" | "sfixed32" | "i32" => "int32".to_string(),
+        "int64" | "sint64" | "sfixed64" | "i64" => "int64".to_string(),
+        "uint32" | "fixed32" => "uint32".to_string(),
+        "uint64" | "fixed64" => "uint64".to_string(),
+        "i16" => "int16".to_string(),
+        "i8" | "byte" => "int8".to_string(),
+        "bytes" | "binary" => "bytes".to_string(),
+        "google.protobuf.Timestamp" => "timestamp".to_string(),
+        "google.protobuf.Duration" => "duration".to_string(),
+        // Message references compare by name regardless of package
+        other => other.rsplit('.').next().unwrap_or(other).to_string(),
+    }
+}
+
+/// Cursor over IDL tokens
+struct Parser<'a> {
+    tokens: &'a [(IdlToken, usize)],
+    i: usize,
+}
+
+impl Parser<'_> {
+    fn name(&self) -> Option<&str> {
+        match self.tokens.get(self.i) {
+            Some((IdlToken::Name(name), _)) => Some(name.as_str()),
+            _ => None,
+        }
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        matches!(self.tokens.get(self.i), Some((IdlToken::Punct(p), _)) if *p == c)
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let found = self.is_punct(c);
+        if found {
+            self.i += 1;
+        }
+        found
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.i).or(self.tokens.last()).map_or(1, |(_, line)| *line)
+    }
+
+    fn at_end(&self) -> bool {
+        self.i >= self.tokens.len()
+    }
+
+    /// Skip a balanced group starting at the current `open` token
+    fn skip_group(&mut self, open: char, close: char) {
+        let mut depth = 0;
+        while !self.at_end() {
+            if self.is_punct(open) {
+                depth += 1;
+            } else if self.is_punct(close) {
+                depth -= 1;
+                if depth == 0 {
+                    self.i += 1;
+                    return;
+                }
+            }
+            self.i += 1;
+        }
+    }
+
+    /// Skip to just past the next `;` or past a `{ ... }` group, whichever comes first
+    fn skip_statement(&mut self) {
+        while !self.at_end() {
+            if self.is_punct('{') {
+                self.skip_group('{', '}');
+                return;
+            }
+            if self.eat_punct(';') {
+                return;
+            }
+            if self.is_punct('}') {
+                return;
+            }
+            self.i += 1;
+        }
+    }
+
+    /// A possibly negative integer literal
+    fn number(&mut self) -> Option<i64> {
+        let negative = self.eat_punct('-');
+        let text = self.name()?;
+        let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+            None => text.parse().ok()?,
+        };
+        self.i += 1;
+        Some(if negative { -value } else { value })
+    }
+
+    /// A type such as `map<string, Item>` or `list<i32>`
+    fn value_type(&mut self) -> String {
+        let Some(name) = self.name().map(str::to_string) else {
+            return "any".to_string();
+        };
+        self.i += 1;
+        if !self.eat_punct('<') {
+            return normalize_idl_type(&name);
+        }
+        let mut arguments = vec![self.value_type()];
+        while self.eat_punct(',') {
+            arguments.push(self.value_type());
+        }
+        self.eat_punct('>');
+        match (name.as_str(), arguments.as_slice()) {
+            ("list", [inner]) => format!("{}[]", inner),
+            (container, _) => format!("{}<{}>", container, arguments.join(",")),
+        }
+    }
+}
+
+/// Parse a `.proto` file into its messages and enums, nested ones included
+#[must_use]
+pub fn extract_proto_definitions(source: &str, file_path: &str) -> Vec<IdlDefinition> {
+    let tokens = tokenize_idl(source);
+    let mut parser = Parser { tokens: &tokens, i: 0 };
+    let mut definitions = Vec::new();
+
+    while !parser.at_end() {
+        match parser.name() {
+            Some(keyword @ ("message" | "enum")) => {
+                let kind = if keyword == "message" { IDL_MESSAGE } else { IDL_ENUM };
+                parse_proto_definition(&mut parser, kind, "", file_path, &mut definitions);
+            }
+            Some(_) => parser.skip_statement(),
+            None => parser.i += 1,
+        }
+    }
+    definitions
+}
+
+/// Parse a message or enum at its keyword, appending it and any nested definitions
+fn parse_proto_definition(
+    parser: &mut Parser,
+    kind: &'static str,
+    scope: &str,
+    file_path: &str,
+    definitions: &mut Vec<IdlDefinition>,
+) {
+    let start_line = parser.line();
+    parser.i += 1;
+    let Some(name) = parser.name().map(str::to_string) else { return };
+    parser.i += 1;
+    if !parser.eat_punct('{') {
+        return;
+    }
+    let name = if scope.is_empty() { name } else { format!("{}.{}", scope, name) };
+
+    // Reserve the slot so the outer definition precedes its nested ones
+    let index = definitions.len();
+    definitions.push(IdlDefinition {
+        name: name.clone(),
+        kind,
+        file_path: file_path.to_string(),
+        start_line,
+        end_line: start_line,
+        fields: vec![],
+        reserved: vec![],
+    });
+    let mut fields = Vec::new();
+    let mut reserved = Vec::new();
+
+    while !parser.at_end() && !parser.is_punct('}') {
+        match parser.name() {
+            Some(keyword @ ("message" | "enum")) => {
+                let nested = if keyword == "message" { IDL_MESSAGE } else { IDL_ENUM };
+                parse_proto_definition(parser, nested, &name, file_path, definitions);
+            }
+            Some("reserved") => {
+                parser.i += 1;
+                while !parser.at_end() && !parser.eat_punct(';') {
+                    match parser.number() {
+                        Some(low) => {
+                            let high = if parser.name() == Some("to") {
+                                parser.i += 1;
+                                parser.number().unwrap_or(i64::MAX)
+                            } else {
+                                low
+                            };
+                            reserved.push((low, high));
+                        }
+                        None => parser.i += 1,
+                    }
+                }
+            }
+            Some("oneof") => {
+                parser.i += 2;
+                if parser.eat_punct('{') {
+                    while !parser.at_end() && !parser.eat_punct('}') {
+                        parse_proto_field(parser, kind, &mut fields);
+                    }
+                }
+            }
+            Some("option" | "extensions" | "extend") => parser.skip_statement(),
+            Some(_) => parse_proto_field(parser, kind, &mut fields),
+            None => parser.skip_statement(),
+        }
+    }
+
+    definitions[index].end_line = parser.line();
+    definitions[index].fields = fields;
+    definitions[index].reserved = reserved;
+    parser.eat_punct('}');
+}
+
+/// Parse `[repeated] Type name = N [options];` or an enum value `NAME = N;`
+fn parse_proto_field(parser: &mut Parser, kind: &'static str, fields: &mut Vec<IdlField>) {
+    let mut repeated = false;
+    let mut required = false;
+    if kind == IDL_MESSAGE {
+        while let Some(label @ ("repeated" | "optional" | "required")) = parser.name() {
+            repeated |= label == "repeated";
+            required |= label == "required";
+            parser.i += 1;
+        }
+    }
+    let value_type = if kind == IDL_MESSAGE {
+        let value_type = parser.value_type();
+        if repeated {
+            format!("{}[]", value_type)
+        } else {
+            value_type
+        }
+    } else {
+        "value".to_string()
+    };
+
+    let Some(name) = parser.name().map(str::to_string) else {
+        parser.skip_statement();
+        return;
+    };
+    parser.i += 1;
+    let number = if parser.eat_punct('=') { parser.number() } else { None };
+    parser.skip_statement();
+    fields.push(IdlField { name, value_type, number, required });
+}
+
+/// Parse a `.thrift` file into its structs, unions, exceptions and enums
+#[must_use]
+pub fn extract_thrift_definitions(source: &str, file_path: &str) -> Vec<IdlDefinition> {
+    let tokens = tokenize_idl(source);
+    let mut parser = Parser { tokens: &tokens, i: 0 };
+    let mut definitions = Vec::new();
+
+    while !parser.at_end() {
+        let kind = match parser.name() {
+            Some("struct" | "union" | "exception") => IDL_MESSAGE,
+            Some("enum") => IDL_ENUM,
+            // Thrift statements need no terminator, so move on token by token
+            _ => {
+                parser.i += 1;
+                continue;
+            }
+        };
+        let start_line = parser.line();
+        parser.i += 1;
+        let Some(name) = parser.name().map(str::to_string) else { continue };
+        parser.i += 1;
+        if !parser.eat_punct('{') {
+            continue;
+        }
+
+        let mut fields = Vec::new();
+        let mut next_value = 0;
+        while !parser.at_end() && !parser.is_punct('}') {
+            let field = if kind == IDL_ENUM {
+                parse_thrift_enum_value(&mut parser, &mut next_value)
+            } else {
+                parse_thrift_field(&mut parser)
+            };
+            match field {
+                Some(field) => fields.push(field),
+                None => parser.i += 1,
+            }
+            // Annotations and separators between fields
+            if parser.is_punct('(') {
+                parser.skip_group('(', ')');
+            }
+            while parser.eat_punct(',') || parser.eat_punct(';') {}
+        }
+
+        definitions.push(IdlDefinition {
+            name,
+            kind,
+            file_path: file_path.to_string(),
+            start_line,
+            end_line: parser.line(),
+            fields,
+            reserved: vec![],
+        });
+        parser.eat_punct('}');
+    }
+    definitions
+}
+
+/// Parse `N: [required|optional] Type name [= default]`
+fn parse_thrift_field(parser: &mut Parser) -> Option<IdlField> {
+    let number = parser.number();
+    if number.is_some() && !parser.eat_punct(':') {
+        return None;
+    }
+    let mut required = false;
+    while let Some(label @ ("required" | "optional")) = parser.name() {
+        required |= label == "required";
+        parser.i += 1;
+    }
+    let value_type = parser.value_type();
+    let name = parser.name()?.to_string();
+    parser.i += 1;
+    if parser.eat_punct('=') {
+        // Default values may be literals, constants or collections
+        if parser.is_punct('[') {
+            parser.skip_group('[', ']');
+        } else if parser.is_punct('{') {
+            parser.skip_group('{', '}');
+        } else {
+            parser.eat_punct('-');
+            parser.i += 1;
+        }
+    }
+    Some(IdlField { name, value_type, number, required })
+}
+
+/// Parse `NAME [= N]`, numbering implicit values after the previous one
+fn parse_thrift_enum_value(parser: &mut Parser, next_value: &mut i64) -> Option<IdlField> {
+    let name = parser.name()?.to_string();
+    parser.i += 1;
+    let number = if parser.eat_punct('=') { parser.number() } else { None };
+    let number = number.unwrap_or(*next_value);
+    *next_value = number + 1;
+    Some(IdlField { name, value_type: "value".to_string(), number: Some(number), required: false })
+}
+
+fn is_thrift_file(file: &str) -> bool {
+    file.ends_with(".thrift")
+}
+
+/// Analyzer for protobuf and Thrift IDL files, comparing messages and enums
+pub struct IdlAnalyzer {
+    min_members: usize,
+    comparator: RefCell<StructureComparator>,
+    /// Extracted definitions, by file and start line
+    definitions: HashMap<(String, u32), IdlDefinition>,
+}
+
+impl Default for IdlAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdlAnalyzer {
+    pub fn new() -> Self {
+        let options = ComparisonOptions {
+            name_weight: 0.2, // Copies are often renamed (UserV2, UserMessage)
+            structure_weight: 0.8,
+            ignore_order: true,
+            ..Default::default()
+        };
+        Self::with_options(options, 3)
+    }
+
+    /// Definitions with fewer than `min_members` fields or values are not compared
+    pub fn with_options(options: ComparisonOptions, min_members: usize) -> Self {
+        Self {
+            min_members,
+            comparator: RefCell::new(StructureComparator::new(options)),
+            definitions: HashMap::new(),
+        }
+    }
+
+    fn definition(&self, unit: &UnitLocation) -> Option<&IdlDefinition> {
+        self.definitions.get(&(unit.file.clone(), unit.start_line))
+    }
+}
+
+impl Analyzer for IdlAnalyzer {
+    fn name(&self) -> &str {
+        "idl"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["proto", "thrift"]
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let definitions = if is_thrift_file(file) {
+            extract_thrift_definitions(source, file)
+        } else {
+            extract_proto_definitions(source, file)
+        };
+
+        let mut units = Vec::new();
+        for definition in definitions {
+            if definition.fields.len() < self.min_members {
+                continue;
+            }
+            let mut tree = TreeNode::new(definition.kind.to_string(), definition.name.clone(), 0);
+            for (id, field) in definition.fields.iter().enumerate() {
+                tree.add_child(Rc::new(TreeNode::new(
+                    field.name.clone(),
+                    field.value_type.clone(),
+                    id + 1,
+                )));
+            }
+            units.push(AnalysisUnit {
+                name: definition.name.clone(),
+                file: file.to_string(),
+                start_line: definition.start_line as u32,
+                end_line: definition.end_line as u32,
+                tree: Rc::new(tree),
+                structure: Some(Structure::from(&definition)),
+            });
+            self.definitions.insert((file.to_string(), definition.start_line as u32), definition);
+        }
+        Ok(units)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        let (Some(s1), Some(s2)) = (&first.structure, &second.structure) else { return 0.0 };
+        if s1.identifier.kind != s2.identifier.kind {
+            return 0.0;
+        }
+        let similarity = self.comparator.borrow_mut().compare(s1, s2).overall_similarity;
+
+        let key = |unit: &AnalysisUnit| (unit.file.clone(), unit.start_line);
+        match (self.definitions.get(&key(first)), self.definitions.get(&key(second))) {
+            (Some(d1), Some(d2)) => {
+                let (conflicts, shared) = numbering_conflicts(d1, d2);
+                if shared == 0 {
+                    return similarity;
+                }
+                let conflict_ratio = (conflicts.len() as f64 / shared as f64).min(1.0);
+                similarity * (1.0 - NUMBERING_CONFLICT_PENALTY * conflict_ratio)
+            }
+            _ => similarity,
+        }
+    }
+
+    fn report(&self, findings: &[AnalyzerFinding], out: &mut dyn Write) -> io::Result<()> {
+        if findings.is_empty() {
+            return writeln!(out, "No duplicated IDL definitions found.");
+        }
+
+        writeln!(out, "Duplicated IDL definitions:")?;
+        writeln!(out, "{}", "-".repeat(60))?;
+        for finding in findings {
+            let (first, second) = (&finding.first, &finding.second);
+            writeln!(out, "\nSimilarity: {:.2}%", finding.similarity * 100.0)?;
+            writeln!(
+                out,
+                "  {}",
+                format_function_output(&first.file, &first.name, first.start_line, first.end_line)
+            )?;
+            writeln!(
+                out,
+                "  {}",
+                format_function_output(
+                    &second.file,
+                    &second.name,
+                    second.start_line,
+                    second.end_line
+                )
+            )?;
+
+            let conflicts = match (self.definition(first), self.definition(second)) {
+                (Some(d1), Some(d2)) => numbering_conflicts(d1, d2).0,
+                _ => vec![],
+            };
+            if conflicts.is_empty() {
+                writeln!(out, "  Numbering: identical (wire-compatible duplicate)")?;
+            } else {
+                let details: Vec<String> = conflicts
+                    .iter()
+                    .map(|conflict| match conflict {
+                        NumberingConflict::Renumbered { field, first, second } => {
+                            format!("{} ({} vs {})", field, first, second)
+                        }
+                        NumberingConflict::Reserved { field, number } => {
+                            format!("{} ({} is reserved in the other)", field, number)
+                        }
+                    })
+                    .collect();
+                writeln!(out, "  Numbering: evolved copy, differs for {}", details.join(", "))?;
+            }
+            writeln!(out, "  Suggestion: {}", consolidation_hint(finding, conflicts.is_empty()))?;
+        }
+        writeln!(out, "\nTotal duplicated definition pairs: {}", findings.len())
+    }
+}
+
+/// How to consolidate a pair, depending on whether it is wire-compatible
+fn consolidation_hint(finding: &AnalyzerFinding, wire_compatible: bool) -> String {
+    let (first, second) = (&finding.first, &finding.second);
+    let name = if first.name.len() <= second.name.len() { &first.name } else { &second.name };
+
+    if !wire_compatible {
+        return format!(
+            "keep one definition of '{}' and migrate readers of the other; retired field numbers must stay reserved",
+            name
+        );
+    }
+    match (is_thrift_file(&first.file), is_thrift_file(&second.file)) {
+        (false, false) => {
+            format!("move '{}' into a shared .proto file and import it from both", name)
+        }
+        (true, true) => {
+            format!("move '{}' into a shared .thrift file and include it from both", name)
+        }
+        _ => format!("generate both definitions of '{}' from a single IDL source", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::run_analyzer;
+
+    const ORDERS_PROTO: &str = r#"
+syntax = "proto3";
+package shop.orders;
+
+import "google/protobuf/timestamp.proto";
+
+// An order placed by a customer
+message Order {
+  string id = 1;
+  repeated Item items = 2;
+  map<string, string> metadata = 3 [deprecated = true];
+  google.protobuf.Timestamp created_at = 4;
+  oneof payment {
+    string card_token = 5;
+    string invoice_id = 6;
+  }
+
+  message Item {
+    string sku = 1;
+    sint32 quantity = 2;
+    reserved 3, 5 to 7;
+    reserved "price";
+  }
+}
+
+enum Status {
+  STATUS_UNSPECIFIED = 0;
+  STATUS_OPEN = 1;
+  STATUS_CLOSED = 2;
+}
+"#;
+
+    const ORDERS_THRIFT: &str = r#"
+namespace java shop.orders
+
+/* An order placed by a customer */
+struct Order {
+  1: required string id,
+  2: list<Item> items;
+  3: map<string, string> metadata = {} (deprecated = "true"),
+  4: i64 created_at
+}
+
+enum Status {
+  STATUS_UNSPECIFIED,
+  STATUS_OPEN = 1,
+  STATUS_CLOSED
+}
+"#;
+
+    fn fields(definition: &IdlDefinition) -> Vec<(&str, &str, Option<i64>)> {
+        definition
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.value_type.as_str(), f.number))
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_proto_and_thrift_definitions() {
+        let proto = extract_proto_definitions(ORDERS_PROTO, "orders.proto");
+        let names: Vec<_> = proto.iter().map(|d| (d.name.as_str(), d.kind, d.start_line)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Order", IDL_MESSAGE, 8),
+                ("Order.Item", IDL_MESSAGE, 18),
+                ("Status", IDL_ENUM, 26)
+            ]
+        );
+        assert_eq!(proto[0].end_line, 24);
+        assert_eq!(
+            fields(&proto[0]),
+            vec![
+                ("id", "string", Some(1)),
+                ("items", "Item[]", Some(2)),
+                ("metadata", "map<string,string>", Some(3)),
+                ("created_at", "timestamp", Some(4)),
+                ("card_token", "string", Some(5)),
+                ("invoice_id", "string", Some(6)),
+            ]
+        );
+        assert_eq!(
+            fields(&proto[1]),
+            vec![("sku", "string", Some(1)), ("quantity", "int32", Some(2))]
+        );
+        assert_eq!(proto[1].reserved, vec![(3, 3), (5, 7)]);
+
+        let thrift = extract_thrift_definitions(ORDERS_THRIFT, "orders.thrift");
+        assert_eq!(thrift.len(), 2);
+        assert_eq!((thrift[0].start_line, thrift[0].end_line), (5, 10));
+        assert_eq!(
+            fields(&thrift[0]),
+            vec![
+                ("id", "string", Some(1)),
+                ("items", "Item[]", Some(2)),
+                ("metadata", "map<string,string>", Some(3)),
+                ("created_at", "int64", Some(4)),
+            ]
+        );
+        assert!(thrift[0].fields[0].required);
+        assert_eq!(
+            thrift[1].fields.iter().map(|f| f.number).collect::<Vec<_>>(),
+            vec![Some(0), Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn test_renumbered_copies_are_reported_as_evolved() {
+        let copy = r#"
+syntax = "proto3";
+
+enum State {
+  STATUS_UNSPECIFIED = 0;
+  STATUS_OPEN = 1;
+  STATUS_CLOSED = 2;
+}
+
+message LegacyOrder {
+  string id = 1;
+  repeated Item items = 3;
+  map<string, string> metadata = 2;
+  google.protobuf.Timestamp created_at = 4;
+  string card_token = 5;
+  string invoice_id = 6;
+}
+"#;
+        let files = vec![
+            ("orders.proto".to_string(), ORDERS_PROTO.to_string()),
+            ("legacy.proto".to_string(), copy.to_string()),
+        ];
+        let mut analyzer = IdlAnalyzer::new();
+        let findings = run_analyzer(&mut analyzer, &files, 0.7).unwrap();
+        let pairs: Vec<_> = findings
+            .iter()
+            .map(|f| (f.first.name.as_str(), f.second.name.as_str(), f.similarity < 0.9))
+            .collect();
+        assert_eq!(pairs.len(), 2, "{pairs:?}");
+        assert!(pairs.contains(&("Status", "State", false)));
+        assert!(pairs.contains(&("Order", "LegacyOrder", true)));
+
+        let mut report = Vec::new();
+        analyzer.report(&findings, &mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.contains("Numbering: identical (wire-compatible duplicate)"), "{report}");
+        assert!(
+            report
+                .contains("Numbering: evolved copy, differs for items (2 vs 3), metadata (3 vs 2)"),
+            "{report}"
+        );
+        assert!(report.contains("move 'State' into a shared .proto file"), "{report}");
+
+        // Reusing a retired number conflicts even when the numbers match
+        let proto = extract_proto_definitions(ORDERS_PROTO, "orders.proto");
+        let mut reuse = proto[1].clone();
+        reuse.reserved.clear();
+        reuse.fields[1].number = Some(6);
+        let (conflicts, shared) = numbering_conflicts(&proto[1], &reuse);
+        assert_eq!(shared, 2);
+        assert_eq!(
+            conflicts,
+            vec![
+                NumberingConflict::Renumbered {
+                    field: "quantity".to_string(),
+                    first: 2,
+                    second: 6
+                },
+                NumberingConflict::Reserved { field: "quantity".to_string(), number: 6 },
+            ]
+        );
+    }
+}
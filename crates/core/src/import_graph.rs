@@ -0,0 +1,374 @@
+//! Lightweight import graph between TypeScript/JavaScript files.
+//!
+//! Only static `import ... from`, `export ... from` and `export * from`
+//! declarations with relative specifiers are followed; package imports and
+//! dynamic `import()`/`require()` calls are ignored. Knowing whether two files
+//! already depend on each other tells how costly merging their duplicates is.
+//!
+//! The graph also records which declarations each file exports, so a local
+//! copy of an exported declaration can be reported as "should import instead
+//! of duplicate" together with the import to use, written with the tsconfig
+//! path aliases governing the copy's file.
+
+use crate::tsconfig_paths::PathAliases;
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    BindingPattern, Declaration, ExportDefaultDeclarationKind, ModuleExportName, Program, Statement,
+};
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const RESOLVE_EXTENSIONS: [&str; 8] = ["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"];
+
+/// How the modules of two duplicated functions relate to each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleRelation {
+    SameFile,
+    /// The first file imports the second (possibly both ways)
+    FirstImportsSecond,
+    SecondImportsFirst,
+    /// No import between them, but both live in the same directory
+    SameDirectory,
+    Unrelated,
+}
+
+/// A local copy of an exported declaration that could import the original instead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportInstead {
+    /// Whether the first of the two compared declarations is the exported original
+    pub original_is_first: bool,
+    /// Name the original is exported under, `default` for a default export
+    pub exported_name: String,
+    /// Module specifier of the original, relative to the file of the copy
+    pub specifier: String,
+}
+
+impl ImportInstead {
+    /// Import statement replacing the copy, keeping its local name
+    #[must_use]
+    pub fn statement(&self, local_name: &str, type_only: bool) -> String {
+        let keyword = if type_only { "import type" } else { "import" };
+        if self.exported_name == "default" {
+            format!("{} {} from \"{}\";", keyword, local_name, self.specifier)
+        } else if self.exported_name == local_name {
+            format!("{} {{ {} }} from \"{}\";", keyword, local_name, self.specifier)
+        } else {
+            format!(
+                "{} {{ {} as {} }} from \"{}\";",
+                keyword, self.exported_name, local_name, self.specifier
+            )
+        }
+    }
+}
+
+/// Direct imports between the analyzed files and the names each file exports
+#[derive(Debug, Default)]
+pub struct ImportGraph {
+    imports: HashMap<PathBuf, HashSet<PathBuf>>,
+    /// Exported name of each exported local declaration, per file
+    exports: HashMap<PathBuf, HashMap<String, String>>,
+    aliases: PathAliases,
+}
+
+impl ImportGraph {
+    /// Parse every file and resolve its relative imports against the other files
+    #[must_use]
+    pub fn build(files: &[PathBuf]) -> Self {
+        let known: HashSet<PathBuf> = files.iter().filter_map(|f| f.canonicalize().ok()).collect();
+        let mut imports = HashMap::new();
+        let mut exports = HashMap::new();
+
+        for file in files {
+            let (Ok(canonical), Ok(source)) = (file.canonicalize(), fs::read_to_string(file))
+            else {
+                continue;
+            };
+            let filename = file.to_string_lossy();
+            let (specifiers, exported) = with_program(&filename, &source, |program| {
+                (import_specifiers(program), exported_names(program))
+            });
+            let targets: HashSet<PathBuf> = specifiers
+                .iter()
+                .filter_map(|specifier| resolve_specifier(&canonical, specifier, &known))
+                .collect();
+            imports.insert(canonical.clone(), targets);
+            exports.insert(canonical, exported);
+        }
+
+        Self { imports, exports, aliases: PathAliases::discover(files) }
+    }
+
+    /// tsconfig path aliases governing the analyzed files
+    #[must_use]
+    pub fn aliases(&self) -> &PathAliases {
+        &self.aliases
+    }
+
+    /// Name under which `file` exports its local declaration `name`
+    #[must_use]
+    pub fn exported_name(&self, file: &Path, name: &str) -> Option<&str> {
+        let file = file.canonicalize().ok()?;
+        self.exports.get(&file)?.get(name).map(String::as_str)
+    }
+
+    /// Whether one of two duplicated declarations is exported and the other
+    /// is a private copy in another file that could import it instead.
+    ///
+    /// Pairs where both sides are exported are left alone, as each is part of
+    /// its module's interface, as are copies in a file the original already
+    /// imports, where importing back would create a cycle.
+    #[must_use]
+    pub fn import_instead(
+        &self,
+        (file1, name1): (&Path, &str),
+        (file2, name2): (&Path, &str),
+    ) -> Option<ImportInstead> {
+        if self.relation(file1, file2) == ModuleRelation::SameFile {
+            return None;
+        }
+        let (original_is_first, exported_name) =
+            match (self.exported_name(file1, name1), self.exported_name(file2, name2)) {
+                (Some(exported), None) => (true, exported),
+                (None, Some(exported)) => (false, exported),
+                _ => return None,
+            };
+        let (original, copy) = if original_is_first { (file1, file2) } else { (file2, file1) };
+        if self.imports(original, copy) {
+            return None;
+        }
+        Some(ImportInstead {
+            original_is_first,
+            exported_name: exported_name.to_string(),
+            specifier: self.aliases.specifier(copy, original),
+        })
+    }
+
+    /// Whether `from` directly imports `to`
+    #[must_use]
+    pub fn imports(&self, from: &Path, to: &Path) -> bool {
+        let (Ok(from), Ok(to)) = (from.canonicalize(), to.canonicalize()) else {
+            return false;
+        };
+        self.imports.get(&from).is_some_and(|targets| targets.contains(&to))
+    }
+
+    #[must_use]
+    pub fn relation(&self, file1: &Path, file2: &Path) -> ModuleRelation {
+        let (canonical1, canonical2) = (file1.canonicalize().ok(), file2.canonicalize().ok());
+        if canonical1.is_some() && canonical1 == canonical2 {
+            ModuleRelation::SameFile
+        } else if self.imports(file1, file2) {
+            ModuleRelation::FirstImportsSecond
+        } else if self.imports(file2, file1) {
+            ModuleRelation::SecondImportsFirst
+        } else if canonical1.as_deref().and_then(Path::parent)
+            == canonical2.as_deref().and_then(Path::parent)
+        {
+            ModuleRelation::SameDirectory
+        } else {
+            ModuleRelation::Unrelated
+        }
+    }
+}
+
+/// Module specifiers of the static imports and re-exports in a file
+#[must_use]
+pub fn extract_import_specifiers(filename: &str, source_text: &str) -> Vec<String> {
+    with_program(filename, source_text, import_specifiers)
+}
+
+fn with_program<T>(filename: &str, source_text: &str, f: impl FnOnce(&Program) -> T) -> T {
+    let allocator = Allocator::default();
+    let source_type = SourceType::from_path(filename).unwrap_or(SourceType::tsx());
+    let ret = Parser::new(&allocator, source_text, source_type).parse();
+    f(&ret.program)
+}
+
+fn import_specifiers(program: &Program) -> Vec<String> {
+    program
+        .body
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Statement::ImportDeclaration(decl) => Some(decl.source.value.to_string()),
+            Statement::ExportAllDeclaration(decl) => Some(decl.source.value.to_string()),
+            Statement::ExportNamedDeclaration(decl) => {
+                decl.source.as_ref().map(|source| source.value.to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Exported name of each local declaration a module exports; re-exports from
+/// other modules are not local declarations and are left out
+pub(crate) fn exported_names(program: &Program) -> HashMap<String, String> {
+    let mut exported = HashMap::new();
+    for stmt in &program.body {
+        match stmt {
+            Statement::ExportNamedDeclaration(export) if export.source.is_none() => {
+                if let Some(declaration) = &export.declaration {
+                    for name in declared_names(declaration) {
+                        exported.insert(name.clone(), name);
+                    }
+                }
+                for specifier in &export.specifiers {
+                    if let ModuleExportName::IdentifierReference(local) = &specifier.local {
+                        exported.insert(local.name.to_string(), specifier.exported.to_string());
+                    }
+                }
+            }
+            Statement::ExportDefaultDeclaration(export) => {
+                let local = match &export.declaration {
+                    ExportDefaultDeclarationKind::FunctionDeclaration(func) => {
+                        func.id.as_ref().map(|id| id.name.to_string())
+                    }
+                    ExportDefaultDeclarationKind::ClassDeclaration(class) => {
+                        class.id.as_ref().map(|id| id.name.to_string())
+                    }
+                    ExportDefaultDeclarationKind::Identifier(ident) => Some(ident.name.to_string()),
+                    _ => None,
+                };
+                if let Some(local) = local {
+                    exported.insert(local, "default".to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    exported
+}
+
+fn declared_names(declaration: &Declaration) -> Vec<String> {
+    match declaration {
+        Declaration::FunctionDeclaration(func) => {
+            func.id.iter().map(|id| id.name.to_string()).collect()
+        }
+        Declaration::ClassDeclaration(class) => {
+            class.id.iter().map(|id| id.name.to_string()).collect()
+        }
+        Declaration::VariableDeclaration(var_decl) => var_decl
+            .declarations
+            .iter()
+            .filter_map(|declarator| match &declarator.id {
+                BindingPattern::BindingIdentifier(ident) => Some(ident.name.to_string()),
+                _ => None,
+            })
+            .collect(),
+        Declaration::TSTypeAliasDeclaration(alias) => vec![alias.id.name.to_string()],
+        Declaration::TSInterfaceDeclaration(interface) => vec![interface.id.name.to_string()],
+        Declaration::TSEnumDeclaration(enum_decl) => vec![enum_decl.id.name.to_string()],
+        _ => Vec::new(),
+    }
+}
+
+/// Resolve a relative specifier the way bundlers do: exact file, added extension, or index file
+fn resolve_specifier(from: &Path, specifier: &str, known: &HashSet<PathBuf>) -> Option<PathBuf> {
+    if !specifier.starts_with('.') {
+        return None;
+    }
+    let base = from.parent()?.join(specifier);
+    // ESM-style TypeScript imports name the emitted `.js` file
+    let stem = match base.extension().and_then(|ext| ext.to_str()) {
+        Some("js" | "jsx" | "mjs" | "cjs") => Some(base.with_extension("")),
+        _ => None,
+    };
+
+    let mut candidates = vec![base.clone()];
+    for dir in [Some(&base), stem.as_ref()].into_iter().flatten() {
+        for ext in RESOLVE_EXTENSIONS {
+            candidates.push(PathBuf::from(format!("{}.{}", dir.display(), ext)));
+        }
+    }
+    for ext in RESOLVE_EXTENSIONS {
+        candidates.push(base.join(format!("index.{}", ext)));
+    }
+
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.is_file())
+        .filter_map(|candidate| candidate.canonicalize().ok())
+        .find(|candidate| known.contains(candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn resolves_relative_imports_between_files() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/utils")).unwrap();
+        fs::create_dir_all(root.join("lib")).unwrap();
+        fs::write(root.join("src/utils/index.ts"), "export const x = 1;").unwrap();
+        fs::write(
+            root.join("src/app.ts"),
+            "import { x } from './utils';\nimport { y } from './helpers.js';\nimport React from 'react';",
+        )
+        .unwrap();
+        fs::write(root.join("src/helpers.ts"), "export * from './utils/index';").unwrap();
+        fs::write(root.join("lib/other.ts"), "export const z = 3;").unwrap();
+
+        let files: Vec<PathBuf> =
+            ["src/app.ts", "src/helpers.ts", "src/utils/index.ts", "lib/other.ts"]
+                .iter()
+                .map(|f| root.join(f))
+                .collect();
+        let graph = ImportGraph::build(&files);
+
+        assert!(graph.imports(&files[0], &files[2]));
+        assert!(graph.imports(&files[0], &files[1]));
+        assert!(graph.imports(&files[1], &files[2]));
+        assert!(!graph.imports(&files[2], &files[0]));
+
+        assert_eq!(graph.relation(&files[0], &files[0]), ModuleRelation::SameFile);
+        assert_eq!(graph.relation(&files[0], &files[1]), ModuleRelation::FirstImportsSecond);
+        assert_eq!(graph.relation(&files[2], &files[1]), ModuleRelation::SecondImportsFirst);
+        assert_eq!(graph.relation(&files[0], &files[3]), ModuleRelation::Unrelated);
+    }
+
+    #[test]
+    fn suggests_importing_exported_originals() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/utils")).unwrap();
+        fs::write(
+            root.join("src/utils/math.ts"),
+            "export function sum(xs: number[]) { return 0; }\n\
+             function helper() {}\n\
+             export { helper as assist };\n\
+             export default function mean() {}",
+        )
+        .unwrap();
+        fs::write(root.join("src/app.ts"), "function total(xs: number[]) { return 0; }").unwrap();
+        fs::write(root.join("src/lib.ts"), "export function sum() {}").unwrap();
+
+        let files: Vec<PathBuf> = ["src/utils/math.ts", "src/app.ts", "src/lib.ts"]
+            .iter()
+            .map(|f| root.join(f))
+            .collect();
+        let graph = ImportGraph::build(&files);
+
+        assert_eq!(graph.exported_name(&files[0], "helper"), Some("assist"));
+        assert_eq!(graph.exported_name(&files[0], "mean"), Some("default"));
+        assert_eq!(graph.exported_name(&files[1], "total"), None);
+
+        let found = graph.import_instead((&files[1], "total"), (&files[0], "sum")).unwrap();
+        assert!(!found.original_is_first);
+        assert_eq!(found.specifier, crate::refactor_patch::module_specifier(&files[1], &files[0]));
+        assert!(found.specifier.ends_with("/utils/math"));
+        assert_eq!(
+            found.statement("total", false),
+            format!("import {{ sum as total }} from \"{}\";", found.specifier)
+        );
+        let found = graph.import_instead((&files[0], "mean"), (&files[1], "total")).unwrap();
+        assert!(found.statement("total", true).starts_with("import type total from"));
+
+        // Both exported: each is part of its module's interface
+        assert_eq!(graph.import_instead((&files[0], "sum"), (&files[2], "sum")), None);
+    }
+}
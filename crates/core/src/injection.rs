@@ -0,0 +1,582 @@
+//! Languages embedded in tagged template literals.
+//!
+//! `html`...`` and `sql`...`` templates hold markup and queries that get
+//! copied between components and repositories just like functions do. An
+//! [`InjectionAnalyzer`] finds the templates whose tag belongs to an embedded
+//! language, hands their bodies to that language's [`Analyzer`] and maps the
+//! units it extracts back to the lines of the JavaScript or TypeScript file.
+//! Templates are the `TaggedTemplateExpression`s of the oxc AST, so backticks
+//! in strings, comments and regular expressions are never mistaken for them.
+//! Files are parsed tolerantly: a syntax error only hides the templates of
+//! the top-level chunk it is in. `${...}` interpolations become placeholders
+//! of the same length, which keeps positions and most markup and queries
+//! intact.
+//!
+//! Embedded SQL is handed to [`SqlAnalyzer`], the clause-level analyzer used
+//! for `.sql` files, rather than a tree-sitter grammar: no SQL grammar is
+//! among the workspace's tree-sitter dependencies, and sharing the analyzer
+//! means a query scores the same inside a template as in a `.sql` file.
+
+use crate::analyzer::{AnalysisUnit, Analyzer, AnalyzerError};
+use crate::html_analyzer::HtmlAnalyzer;
+use crate::sql_analyzer::SqlAnalyzer;
+use crate::tolerant_parse::parse_tolerant;
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    Argument, ArrayExpressionElement, ChainElement, Class, ClassElement, Declaration,
+    ExportDefaultDeclarationKind, Expression, ForStatementInit, Function, JSXAttributeItem,
+    JSXAttributeValue, JSXChild, JSXElement, MemberExpression, ObjectPropertyKind, Statement,
+    TSModuleDeclaration, TSModuleDeclarationBody, TaggedTemplateExpression, VariableDeclaration,
+};
+use oxc_span::SourceType;
+
+/// Extensions of the files scanned for tagged templates
+pub const INJECTION_HOST_EXTENSIONS: [&str; 8] =
+    ["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+
+/// A tagged template literal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaggedTemplate {
+    pub tag: String,
+    /// Text between the backticks, interpolations included
+    pub body: String,
+    /// Line of the opening backtick, where the body starts
+    pub line: u32,
+}
+
+impl TaggedTemplate {
+    /// The body with each `${...}` replaced by `fill`, keeping line breaks so
+    /// positions stay valid
+    pub fn body_without_interpolations(&self, fill: char) -> String {
+        let mut result = String::with_capacity(self.body.len());
+        let mut depth = 0usize;
+        let mut chars = self.body.chars().peekable();
+        while let Some(c) = chars.next() {
+            if depth == 0 && c == '$' && chars.peek() == Some(&'{') {
+                chars.next();
+                depth = 1;
+                result.extend([fill, fill]);
+                continue;
+            }
+            if depth > 0 {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                result.push(if c == '\n' { '\n' } else { fill });
+                continue;
+            }
+            result.push(c);
+        }
+        result
+    }
+}
+
+/// Templates of `file` tagged with one of `tags`, e.g. `gql` in
+/// `gql`query { ... }`` and `db.gql`...`` but not in `notgql`...``
+///
+/// Files that cannot be parsed at all have no templates.
+pub fn find_tagged_templates(file: &str, source: &str, tags: &[&str]) -> Vec<TaggedTemplate> {
+    let allocator = Allocator::default();
+    let source_type = SourceType::from_path(file).unwrap_or(SourceType::tsx());
+    let Ok(parsed) = parse_tolerant(&allocator, source, source_type) else {
+        return Vec::new();
+    };
+    let mut finder = TemplateFinder { source, tags, templates: Vec::new() };
+    finder.visit_statements(&parsed.program.body);
+    finder.templates
+}
+
+/// Collects the `TaggedTemplateExpression`s of a program whose tag is known
+struct TemplateFinder<'s> {
+    source: &'s str,
+    tags: &'s [&'s str],
+    templates: Vec<TaggedTemplate>,
+}
+
+impl TemplateFinder<'_> {
+    fn visit_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.visit_statement(statement);
+        }
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::BlockStatement(block) => self.visit_statements(&block.body),
+            Statement::ExpressionStatement(statement) => {
+                self.visit_expression(&statement.expression);
+            }
+            Statement::IfStatement(statement) => {
+                self.visit_expression(&statement.test);
+                self.visit_statement(&statement.consequent);
+                if let Some(alternate) = &statement.alternate {
+                    self.visit_statement(alternate);
+                }
+            }
+            Statement::ForStatement(statement) => {
+                match &statement.init {
+                    Some(ForStatementInit::VariableDeclaration(declaration)) => {
+                        self.visit_variable_declaration(declaration);
+                    }
+                    Some(init) => self.visit_optional(init.as_expression()),
+                    None => {}
+                }
+                self.visit_optional(statement.test.as_ref());
+                self.visit_optional(statement.update.as_ref());
+                self.visit_statement(&statement.body);
+            }
+            Statement::ForInStatement(statement) => {
+                self.visit_expression(&statement.right);
+                self.visit_statement(&statement.body);
+            }
+            Statement::ForOfStatement(statement) => {
+                self.visit_expression(&statement.right);
+                self.visit_statement(&statement.body);
+            }
+            Statement::WhileStatement(statement) => {
+                self.visit_expression(&statement.test);
+                self.visit_statement(&statement.body);
+            }
+            Statement::DoWhileStatement(statement) => {
+                self.visit_statement(&statement.body);
+                self.visit_expression(&statement.test);
+            }
+            Statement::LabeledStatement(statement) => self.visit_statement(&statement.body),
+            Statement::ReturnStatement(statement) => {
+                self.visit_optional(statement.argument.as_ref());
+            }
+            Statement::ThrowStatement(statement) => self.visit_expression(&statement.argument),
+            Statement::TryStatement(statement) => {
+                self.visit_statements(&statement.block.body);
+                if let Some(handler) = &statement.handler {
+                    self.visit_statements(&handler.body.body);
+                }
+                if let Some(finalizer) = &statement.finalizer {
+                    self.visit_statements(&finalizer.body);
+                }
+            }
+            Statement::SwitchStatement(statement) => {
+                self.visit_expression(&statement.discriminant);
+                for case in &statement.cases {
+                    self.visit_optional(case.test.as_ref());
+                    self.visit_statements(&case.consequent);
+                }
+            }
+            Statement::WithStatement(statement) => {
+                self.visit_expression(&statement.object);
+                self.visit_statement(&statement.body);
+            }
+            Statement::ExportNamedDeclaration(export) => {
+                if let Some(declaration) = &export.declaration {
+                    self.visit_declaration(declaration);
+                }
+            }
+            Statement::ExportDefaultDeclaration(export) => match &export.declaration {
+                ExportDefaultDeclarationKind::FunctionDeclaration(function) => {
+                    self.visit_function(function);
+                }
+                ExportDefaultDeclarationKind::ClassDeclaration(class) => self.visit_class(class),
+                declaration => self.visit_optional(declaration.as_expression()),
+            },
+            Statement::TSExportAssignment(export) => self.visit_expression(&export.expression),
+            _ => {
+                if let Some(declaration) = statement.as_declaration() {
+                    self.visit_declaration(declaration);
+                }
+            }
+        }
+    }
+
+    fn visit_declaration(&mut self, declaration: &Declaration) {
+        match declaration {
+            Declaration::VariableDeclaration(declaration) => {
+                self.visit_variable_declaration(declaration);
+            }
+            Declaration::FunctionDeclaration(function) => self.visit_function(function),
+            Declaration::ClassDeclaration(class) => self.visit_class(class),
+            Declaration::TSModuleDeclaration(module) => self.visit_module(module),
+            Declaration::TSGlobalDeclaration(global) => self.visit_statements(&global.body.body),
+            _ => {}
+        }
+    }
+
+    fn visit_module(&mut self, module: &TSModuleDeclaration) {
+        match &module.body {
+            Some(TSModuleDeclarationBody::TSModuleDeclaration(inner)) => self.visit_module(inner),
+            Some(TSModuleDeclarationBody::TSModuleBlock(block)) => {
+                self.visit_statements(&block.body);
+            }
+            None => {}
+        }
+    }
+
+    fn visit_variable_declaration(&mut self, declaration: &VariableDeclaration) {
+        for declarator in &declaration.declarations {
+            self.visit_optional(declarator.init.as_ref());
+        }
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        if let Some(body) = &function.body {
+            self.visit_statements(&body.statements);
+        }
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        self.visit_optional(class.super_class.as_ref());
+        for element in &class.body.body {
+            match element {
+                ClassElement::MethodDefinition(method) => self.visit_function(&method.value),
+                ClassElement::PropertyDefinition(property) => {
+                    self.visit_optional(property.value.as_ref());
+                }
+                ClassElement::AccessorProperty(property) => {
+                    self.visit_optional(property.value.as_ref());
+                }
+                ClassElement::StaticBlock(block) => self.visit_statements(&block.body),
+                ClassElement::TSIndexSignature(_) => {}
+            }
+        }
+    }
+
+    fn visit_arguments(&mut self, arguments: &[Argument]) {
+        for argument in arguments {
+            match argument {
+                Argument::SpreadElement(spread) => self.visit_expression(&spread.argument),
+                argument => self.visit_optional(argument.as_expression()),
+            }
+        }
+    }
+
+    fn visit_optional(&mut self, expression: Option<&Expression>) {
+        if let Some(expression) = expression {
+            self.visit_expression(expression);
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::TaggedTemplateExpression(tagged) => {
+                self.record(tagged);
+                self.visit_expression(&tagged.tag);
+                for expression in &tagged.quasi.expressions {
+                    self.visit_expression(expression);
+                }
+            }
+            Expression::TemplateLiteral(template) => {
+                for expression in &template.expressions {
+                    self.visit_expression(expression);
+                }
+            }
+            Expression::ArrayExpression(array) => {
+                for element in &array.elements {
+                    match element {
+                        ArrayExpressionElement::SpreadElement(spread) => {
+                            self.visit_expression(&spread.argument);
+                        }
+                        element => self.visit_optional(element.as_expression()),
+                    }
+                }
+            }
+            Expression::ObjectExpression(object) => {
+                for property in &object.properties {
+                    match property {
+                        ObjectPropertyKind::ObjectProperty(property) => {
+                            self.visit_optional(property.key.as_expression());
+                            self.visit_expression(&property.value);
+                        }
+                        ObjectPropertyKind::SpreadProperty(spread) => {
+                            self.visit_expression(&spread.argument);
+                        }
+                    }
+                }
+            }
+            Expression::ArrowFunctionExpression(arrow) => {
+                self.visit_statements(&arrow.body.statements);
+            }
+            Expression::FunctionExpression(function) => self.visit_function(function),
+            Expression::ClassExpression(class) => self.visit_class(class),
+            Expression::CallExpression(call) => {
+                self.visit_expression(&call.callee);
+                self.visit_arguments(&call.arguments);
+            }
+            Expression::NewExpression(call) => {
+                self.visit_expression(&call.callee);
+                self.visit_arguments(&call.arguments);
+            }
+            Expression::V8IntrinsicExpression(call) => self.visit_arguments(&call.arguments),
+            Expression::ImportExpression(import) => self.visit_expression(&import.source),
+            Expression::ChainExpression(chain) => match &chain.expression {
+                ChainElement::CallExpression(call) => {
+                    self.visit_expression(&call.callee);
+                    self.visit_arguments(&call.arguments);
+                }
+                ChainElement::TSNonNullExpression(non_null) => {
+                    self.visit_expression(&non_null.expression);
+                }
+                element => {
+                    if let Some(member) = element.as_member_expression() {
+                        self.visit_member(member);
+                    }
+                }
+            },
+            Expression::AssignmentExpression(assignment) => {
+                self.visit_expression(&assignment.right);
+            }
+            Expression::AwaitExpression(await_expression) => {
+                self.visit_expression(&await_expression.argument);
+            }
+            Expression::YieldExpression(yield_expression) => {
+                self.visit_optional(yield_expression.argument.as_ref());
+            }
+            Expression::UnaryExpression(unary) => self.visit_expression(&unary.argument),
+            Expression::BinaryExpression(binary) => {
+                self.visit_expression(&binary.left);
+                self.visit_expression(&binary.right);
+            }
+            Expression::LogicalExpression(logical) => {
+                self.visit_expression(&logical.left);
+                self.visit_expression(&logical.right);
+            }
+            Expression::PrivateInExpression(private_in) => {
+                self.visit_expression(&private_in.right);
+            }
+            Expression::ConditionalExpression(conditional) => {
+                self.visit_expression(&conditional.test);
+                self.visit_expression(&conditional.consequent);
+                self.visit_expression(&conditional.alternate);
+            }
+            Expression::SequenceExpression(sequence) => {
+                for expression in &sequence.expressions {
+                    self.visit_expression(expression);
+                }
+            }
+            Expression::ParenthesizedExpression(inner) => self.visit_expression(&inner.expression),
+            Expression::TSAsExpression(inner) => self.visit_expression(&inner.expression),
+            Expression::TSSatisfiesExpression(inner) => self.visit_expression(&inner.expression),
+            Expression::TSTypeAssertion(inner) => self.visit_expression(&inner.expression),
+            Expression::TSNonNullExpression(inner) => self.visit_expression(&inner.expression),
+            Expression::TSInstantiationExpression(inner) => {
+                self.visit_expression(&inner.expression);
+            }
+            Expression::JSXElement(element) => self.visit_jsx_element(element),
+            Expression::JSXFragment(fragment) => self.visit_jsx_children(&fragment.children),
+            expression => {
+                if let Some(member) = expression.as_member_expression() {
+                    self.visit_member(member);
+                }
+            }
+        }
+    }
+
+    fn visit_member(&mut self, member: &MemberExpression) {
+        self.visit_expression(member.object());
+        if let MemberExpression::ComputedMemberExpression(computed) = member {
+            self.visit_expression(&computed.expression);
+        }
+    }
+
+    fn visit_jsx_element(&mut self, element: &JSXElement) {
+        for attribute in &element.opening_element.attributes {
+            match attribute {
+                JSXAttributeItem::Attribute(attribute) => match &attribute.value {
+                    Some(JSXAttributeValue::ExpressionContainer(container)) => {
+                        self.visit_optional(container.expression.as_expression());
+                    }
+                    Some(JSXAttributeValue::Element(element)) => self.visit_jsx_element(element),
+                    Some(JSXAttributeValue::Fragment(fragment)) => {
+                        self.visit_jsx_children(&fragment.children);
+                    }
+                    Some(JSXAttributeValue::StringLiteral(_)) | None => {}
+                },
+                JSXAttributeItem::SpreadAttribute(spread) => {
+                    self.visit_expression(&spread.argument);
+                }
+            }
+        }
+        self.visit_jsx_children(&element.children);
+    }
+
+    fn visit_jsx_children(&mut self, children: &[JSXChild]) {
+        for child in children {
+            match child {
+                JSXChild::Element(element) => self.visit_jsx_element(element),
+                JSXChild::Fragment(fragment) => self.visit_jsx_children(&fragment.children),
+                JSXChild::ExpressionContainer(container) => {
+                    self.visit_optional(container.expression.as_expression());
+                }
+                JSXChild::Spread(spread) => self.visit_expression(&spread.expression),
+                JSXChild::Text(_) => {}
+            }
+        }
+    }
+
+    /// Keep `tagged` when its tag, or the last property of a member tag, is known
+    fn record(&mut self, tagged: &TaggedTemplateExpression) {
+        let name = match &tagged.tag {
+            Expression::Identifier(identifier) => identifier.name.as_str(),
+            Expression::StaticMemberExpression(member) => member.property.name.as_str(),
+            _ => return,
+        };
+        let Some(tag) = self.tags.iter().find(|tag| **tag == name) else {
+            return;
+        };
+        // The quasi span includes the backticks
+        let span = tagged.quasi.span;
+        let Some(body) = self.source.get(span.start as usize + 1..span.end as usize - 1) else {
+            return;
+        };
+        self.templates.push(TaggedTemplate {
+            tag: tag.to_string(),
+            body: body.to_string(),
+            line: self.source[..span.start as usize].matches('\n').count() as u32 + 1,
+        });
+    }
+}
+
+/// Runs an embedded language's analyzer on the templates carrying its tags
+pub struct InjectionAnalyzer {
+    name: String,
+    tags: Vec<&'static str>,
+    analyzer: Box<dyn Analyzer>,
+}
+
+impl InjectionAnalyzer {
+    /// Named after the embedded analyzer, e.g. `html` for `html`...`` templates
+    pub fn new(tags: Vec<&'static str>, analyzer: Box<dyn Analyzer>) -> Self {
+        Self { name: analyzer.name().to_string(), tags, analyzer }
+    }
+
+    pub fn tags(&self) -> &[&'static str] {
+        &self.tags
+    }
+}
+
+impl Analyzer for InjectionAnalyzer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &INJECTION_HOST_EXTENSIONS
+    }
+
+    /// Units of every template with a known tag. Templates the embedded
+    /// analyzer rejects are skipped; the error is returned only when no
+    /// template of the file could be analyzed.
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let mut units = Vec::new();
+        let mut errors = Vec::new();
+        let templates = find_tagged_templates(file, source, &self.tags);
+        for template in &templates {
+            let body = template.body_without_interpolations('_');
+            match self.analyzer.extract(file, &body) {
+                Ok(found) => units.extend(found.into_iter().map(|mut unit| {
+                    unit.start_line += template.line - 1;
+                    unit.end_line += template.line - 1;
+                    unit
+                })),
+                Err(e) => errors.push(format!("line {}: {}", template.line, e)),
+            }
+        }
+        if errors.len() == templates.len() && !errors.is_empty() {
+            return Err(errors.join("; ").into());
+        }
+        Ok(units)
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        self.analyzer.compare(first, second)
+    }
+}
+
+/// The embedded languages analyzed by default: HTML in `html` and `svg`
+/// templates (lit-html, htm, uhtml) and SQL in `sql` and `SQL` templates
+/// (postgres.js, slonik, sql-template-strings)
+pub fn builtin_injections() -> Result<Vec<InjectionAnalyzer>, AnalyzerError> {
+    Ok(vec![
+        InjectionAnalyzer::new(vec!["html", "svg"], Box::new(HtmlAnalyzer::new()?)),
+        InjectionAnalyzer::new(vec!["sql", "SQL"], Box::new(SqlAnalyzer::new())),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::run_analyzer;
+
+    #[test]
+    fn test_finds_tagged_templates() {
+        let source = "const a = html`<p>${user.name}</p>`;\nconst b = nothtml`<p></p>`;\nconst c = sql `\n  SELECT ${`nested`}`;\n";
+        let templates = find_tagged_templates("a.ts", source, &["html", "sql"]);
+        assert_eq!(templates.len(), 2);
+        assert_eq!((templates[0].tag.as_str(), templates[0].line), ("html", 1));
+        assert_eq!(templates[0].body_without_interpolations('_'), "<p>____________</p>");
+        assert_eq!((templates[1].tag.as_str(), templates[1].line), ("sql", 3));
+        assert_eq!(templates[1].body_without_interpolations(' '), "\n  SELECT            ");
+    }
+
+    #[test]
+    fn test_finds_templates_anywhere_in_the_ast() {
+        let source = r#"// an html`<p>` in a comment
+const note = "sql`SELECT 1`";
+class Repo {
+  find = (id) => db.sql`SELECT * FROM users WHERE id = ${id}`;
+}
+export const View = () => <div title={html`<b>${"`"}</b>`}>{items.map((item) => html`<li>${item}</li>`)}</div>;
+function broken( {
+"#;
+        let templates = find_tagged_templates("view.tsx", source, &["html", "sql"]);
+        let found: Vec<(&str, u32)> =
+            templates.iter().map(|template| (template.tag.as_str(), template.line)).collect();
+        assert_eq!(found, vec![("sql", 4), ("html", 6), ("html", 6)]);
+        assert_eq!(templates[1].body, "<b>${\"`\"}</b>");
+    }
+
+    #[test]
+    fn test_embedded_duplicates_map_to_host_lines() {
+        let orders = r#"import { sql } from "./db";
+
+export function recentOrders(userId) {
+  return sql`
+    SELECT id, total, created_at
+    FROM orders
+    WHERE user_id = ${userId} AND status = 'paid'
+    ORDER BY created_at DESC
+  `;
+}
+"#;
+        let invoices = r#"export const paidInvoices = (customer) => sql`SELECT id, total, created_at FROM orders WHERE user_id = ${customer} AND status = 'open' ORDER BY created_at DESC`;
+"#;
+        let files = vec![
+            ("src/orders.ts".to_string(), orders.to_string()),
+            ("src/invoices.ts".to_string(), invoices.to_string()),
+        ];
+        let mut injections = builtin_injections().unwrap();
+        let sql = injections.iter_mut().find(|injection| injection.name() == "sql").unwrap();
+        let findings = run_analyzer(sql, &files, 0.9).unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].first.start_line, findings[0].first.end_line), (5, 8));
+        assert_eq!(findings[0].second.start_line, 1);
+    }
+
+    #[test]
+    fn test_html_templates() {
+        let card = |name: &str| {
+            format!(
+                "const {name} = (item) => html`\n  <div class=${{item.kind}}>\n    <h2>${{item.title}}</h2>\n    <p>${{item.body}}</p>\n  </div>`;\n"
+            )
+        };
+        let mut injections = builtin_injections().unwrap();
+        let html = injections.iter_mut().find(|injection| injection.name() == "html").unwrap();
+        let units = html.extract("a.ts", &card("card")).unwrap();
+        assert_eq!(units.len(), 1);
+        assert_eq!((units[0].start_line, units[0].end_line), (2, 5));
+
+        let files = vec![("a.ts".to_string(), card("card")), ("b.ts".to_string(), card("teaser"))];
+        assert_eq!(run_analyzer(html, &files, 0.95).unwrap().len(), 1);
+    }
+}
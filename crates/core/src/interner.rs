@@ -0,0 +1,242 @@
+//! Interned identifiers, type names and file paths.
+//!
+//! The same names recur across definitions: every function of a file carries
+//! its path, every method its class name. Extractors intern them once in a
+//! process-wide arena and hand out [`Symbol`]s, which are `Copy`, compare and
+//! hash as integers, and dereference to the text without a lookup.
+//!
+//! The integer behind a symbol is exposed through [`Symbol::id`] for callers
+//! that keep their own tables, and [`Symbol::resolve`] turns it back into a
+//! symbol. Interned text lives until the process exits, so structures kept
+//! by long-running processes, such as the function index a daemon serves,
+//! hold owned text instead of symbols.
+
+use lasso::{Key, Spur, ThreadedRodeo};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::ffi::OsStr;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::OnceLock;
+
+fn arena() -> &'static ThreadedRodeo {
+    static ARENA: OnceLock<ThreadedRodeo> = OnceLock::new();
+    ARENA.get_or_init(ThreadedRodeo::new)
+}
+
+/// Handle to an interned string
+#[derive(Clone, Copy)]
+pub struct Symbol {
+    key: Spur,
+    text: &'static str,
+}
+
+impl Symbol {
+    /// Intern `text`, returning the existing symbol when it was seen before
+    #[must_use]
+    pub fn intern(text: &str) -> Self {
+        let arena = arena();
+        let key = arena.get_or_intern(text);
+        Self { key, text: arena.resolve(&key) }
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        self.text
+    }
+
+    /// Integer id of the symbol, stable for the lifetime of the process
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        // Keys index an arena capped at u32::MAX entries
+        self.key.into_usize() as u32
+    }
+
+    /// Symbol of an id returned by [`Symbol::id`], `None` for ids never handed out
+    #[must_use]
+    pub fn resolve(id: u32) -> Option<Self> {
+        let arena = arena();
+        let key = Spur::try_from_usize(id as usize)?;
+        Some(Self { key, text: arena.try_resolve(&key)? })
+    }
+}
+
+impl Default for Symbol {
+    fn default() -> Self {
+        Self::intern("")
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.text
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.text
+    }
+}
+
+/// File paths are interned too
+impl AsRef<Path> for Symbol {
+    fn as_ref(&self) -> &Path {
+        Path::new(self.text)
+    }
+}
+
+impl AsRef<OsStr> for Symbol {
+    fn as_ref(&self) -> &OsStr {
+        OsStr::new(self.text)
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+/// Symbols sort by their text, so sorted output does not depend on interning order
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.key == other.key {
+            Ordering::Equal
+        } else {
+            self.text.cmp(other.text)
+        }
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.text == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.text == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.text == other
+    }
+}
+
+impl PartialEq<Symbol> for str {
+    fn eq(&self, other: &Symbol) -> bool {
+        self == other.text
+    }
+}
+
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool {
+        *self == other.text
+    }
+}
+
+impl PartialEq<Symbol> for String {
+    fn eq(&self, other: &Symbol) -> bool {
+        self == other.text
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.text, f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.text)
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::intern(&Cow::<str>::deserialize(deserializer)?))
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(text: &str) -> Self {
+        Self::intern(text)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(text: &String) -> Self {
+        Self::intern(text)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(text: String) -> Self {
+        Self::intern(&text)
+    }
+}
+
+impl From<Cow<'_, str>> for Symbol {
+    fn from(text: Cow<'_, str>) -> Self {
+        Self::intern(&text)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interning_shares_text_and_ids() {
+        let first = Symbol::intern("calculateTotal");
+        let second = Symbol::from(String::from("calculateTotal"));
+        let other = Symbol::intern("computeSum");
+
+        assert_eq!(first, second);
+        assert_eq!(first.id(), second.id());
+        assert!(std::ptr::eq(first.as_str(), second.as_str()));
+        assert_ne!(first, other);
+        assert_eq!(first, "calculateTotal");
+        assert_eq!(format!("{first}"), "calculateTotal");
+        assert!(other > first);
+
+        assert_eq!(Symbol::resolve(other.id()), Some(other));
+        assert_eq!(Symbol::resolve(u32::MAX - 1), None);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::interner::Symbol;
 use crate::tree::TreeNode;
 use std::error::Error;
 use std::rc::Rc;
@@ -16,6 +17,7 @@ pub enum Language {
     Cpp,
     CSharp,
     Ruby,
+    Bash,
     MoonBit,
     Unknown,
 }
@@ -34,6 +36,7 @@ impl Language {
             "cpp" | "cc" | "cxx" | "hpp" | "hxx" | "c++" => Some(Language::Cpp),
             "cs" => Some(Language::CSharp),
             "rb" => Some(Language::Ruby),
+            "sh" | "bash" => Some(Language::Bash),
             "mbt" => Some(Language::MoonBit),
             _ => None,
         }
@@ -47,14 +50,14 @@ impl Language {
 /// Generic function definition that works across languages
 #[derive(Debug, Clone)]
 pub struct GenericFunctionDef {
-    pub name: String,
+    pub name: Symbol,
     pub start_line: u32,
     pub end_line: u32,
     pub body_start_line: u32,
     pub body_end_line: u32,
     pub parameters: Vec<String>,
     pub is_method: bool,
-    pub class_name: Option<String>,
+    pub class_name: Option<Symbol>,
     pub is_async: bool,
     pub is_generator: bool,
     pub decorators: Vec<String>,
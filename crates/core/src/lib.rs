@@ -1,28 +1,86 @@
 #![allow(clippy::uninlined_format_args)]
 
+pub mod analysis_session;
+pub mod analyzer;
 pub mod apted;
 pub mod ast_exchange;
 pub mod ast_fingerprint;
+pub mod boilerplate;
 pub mod class_comparator;
 pub mod class_extractor;
+pub mod codeowners;
+mod collection_chains;
+pub mod comparison_budget;
+pub mod complexity;
+pub mod compose_structure_adapter;
 pub mod config_loader;
+pub mod config_parser;
+pub mod config_structure_adapter;
 pub mod css_structure_adapter;
+pub mod dependency_types;
+pub mod dockerfile_analyzer;
+pub mod duplication_heatmap;
 pub mod enhanced_similarity;
+pub mod evaluation;
 pub mod fast_similarity;
+pub mod file_similarity;
 pub mod function_extractor;
+pub mod function_index;
 pub mod generic_overlap_detector;
 pub mod generic_parser_config;
 pub mod generic_tree_sitter_parser;
+pub mod git_blame;
+pub mod hcl_structure_adapter;
+pub mod html_analyzer;
+pub mod idl_structure_adapter;
 mod ignore_directive;
+pub mod import_graph;
+pub mod injection;
+pub mod interner;
 pub mod language_parser;
+pub mod name_similarity;
+pub mod node_weights;
+pub mod notebook;
+pub mod output_schema;
 pub mod overlap_detector;
+pub mod package_boundary;
+pub mod pair_diff;
 pub mod parser;
+pub mod pr_comment;
+pub mod preset;
+pub mod quickfix;
+pub mod react_hooks;
+pub mod refactor_patch;
+pub mod remote_repo;
+pub mod report_diff;
+pub mod report_merge;
+pub mod result_formatter;
+pub mod run_report;
 pub mod rust_structure_adapter;
+pub mod schema_structure_adapter;
+pub mod self_test;
+pub mod semantic_types;
+pub mod severity;
+pub mod shell_analyzer;
+pub mod similarity_matrix;
+pub mod size_penalty;
+pub mod source_text;
+pub mod sql_analyzer;
+pub mod statement_order;
+pub mod structural_search;
 pub mod structure_comparator;
 pub mod subtree_fingerprint;
+pub mod suppression;
+pub mod synthetic_repo;
+pub mod test_blocks;
+mod tolerant_parse;
+pub mod top_pairs;
 pub mod tree;
+pub mod triage;
+pub mod tsconfig_paths;
 pub mod tsed;
 pub mod type_comparator;
+pub mod type_compatibility;
 pub mod type_extractor;
 pub mod type_fingerprint;
 pub mod type_normalizer;
@@ -34,27 +92,67 @@ pub mod cli_file_utils;
 pub mod cli_output;
 pub mod cli_parallel;
 
+pub use analysis_session::{
+    run_key, AnalysisSession, SessionProgress, SessionStart, DEFAULT_SESSION_DIR,
+};
+pub use analyzer::{
+    run_analyzer, run_analyzer_with_budget, AnalysisUnit, Analyzer, AnalyzerError, AnalyzerFinding,
+    AnalyzerRegistry, LanguageParserAnalyzer, LanguageTypeAnalyzer, UnitLocation,
+    ANALYZER_API_VERSION,
+};
 pub use apted::{compute_edit_distance, APTEDOptions};
+pub use boilerplate::{BoilerplateEntry, BoilerplateSet, DEFAULT_BOILERPLATE_PATH};
+pub use comparison_budget::{
+    locality_bucket, pair_shard_key, parse_duration, prioritize_by_bucket_density, shard_key,
+    ComparisonBudget, Coverage, Shard,
+};
+pub use complexity::cyclomatic_complexity;
+pub use dependency_types::{
+    declaration_files, find_dependency_package, find_dependency_type_matches, DependencyPackage,
+    DependencyTypeMatch,
+};
 pub use enhanced_similarity::{
     calculate_enhanced_similarity, calculate_semantic_similarity, EnhancedSimilarityOptions,
 };
+pub use file_similarity::{
+    find_similar_directories, find_similar_files, DirectoryDuplicate, FileDuplicate,
+    FileFingerprint, DEFAULT_MIN_FILE_TOKENS,
+};
 pub use function_extractor::{
-    compare_functions, extract_functions, find_similar_functions_across_files,
-    find_similar_functions_in_file, FunctionDefinition, FunctionType, SimilarityResult,
+    compare_functions, compare_functions_with_algorithm, extract_functions,
+    find_similar_functions_across_extracted, find_similar_functions_across_files,
+    find_similar_functions_across_files_with_budget, find_similar_functions_in_file,
+    find_similar_functions_in_file_with_budget, parse_function_tree,
+    visit_similar_functions_in_extracted, visit_similar_functions_in_file, ExtractedFile,
+    FunctionDefinition, FunctionType, SimilarityResult,
+};
+pub use function_index::{
+    FunctionIndex, FunctionLens, IndexEntry, IndexMatch, SourceFunction, INDEX_VERSION,
 };
-pub use parser::{ast_to_tree_node, parse_and_convert_to_tree};
+pub use interner::Symbol;
+pub use name_similarity::{name_similarity, NameSimilarityMetric};
+pub use node_weights::{NodeWeightConfig, NodeWeights};
+pub use parser::{
+    ast_to_tree_node, parse_and_convert_to_tree, parse_and_convert_to_tree_with, TreeConversion,
+};
+pub use similarity_matrix::{MatrixExporter, DEFAULT_MATRIX_FLOOR};
+pub use source_text::{SharedSource, SourceSlice};
 pub use tree::TreeNode;
 pub use tsed::{
-    calculate_tsed, calculate_tsed_from_code, calculate_tsed_with_threshold, TSEDOptions,
+    calculate_tsed, calculate_tsed_from_code, calculate_tsed_with_threshold, ComparisonAlgorithm,
+    TSEDOptions, DEFAULT_MAX_TREE_NODES,
 };
 
 // Type-related exports
 pub use type_comparator::{
-    compare_type_literal_with_type, compare_types, find_duplicate_types,
-    find_similar_type_literals, find_similar_type_literals_pairs, find_similar_types,
-    group_similar_types, MatchedProperty, SimilarTypePair, TypeComparisonOptions,
+    compare_type_literal_with_type, compare_types, diff_type_properties, find_duplicate_types,
+    find_similar_type_literals, find_similar_type_literals_pairs,
+    find_similar_type_literals_pairs_with_budget, find_similar_type_literals_with_budget,
+    find_similar_types, find_similar_types_with_budget, group_similar_types, MatchedProperty,
+    PropertyDiffEntry, PropertyDiffKind, SimilarTypePair, TypeComparisonOptions,
     TypeComparisonResult, TypeDifferences, TypeLiteralComparisonPair, TypeMismatch,
 };
+pub use type_compatibility::signature_compatibility;
 pub use type_extractor::{
     extract_type_literals_from_code, extract_type_literals_from_files, extract_types_from_code,
     extract_types_from_files, PropertyDefinition, TypeDefinition, TypeKind, TypeLiteralContext,
@@ -65,8 +163,9 @@ pub use type_normalizer::{
     normalize_type, NormalizationOptions, NormalizedType, PropertyMatch,
 };
 pub use unified_type_comparator::{
-    find_similar_unified_types, find_similar_unified_types_structured, UnifiedType,
-    UnifiedTypeComparisonPair,
+    find_similar_unified_types, find_similar_unified_types_structured,
+    find_similar_unified_types_structured_with_budget, find_similar_unified_types_with_budget,
+    UnifiedType, UnifiedTypeComparisonPair,
 };
 
 // Structure comparator exports
@@ -76,17 +175,19 @@ pub use rust_structure_adapter::{
     RustVariantType,
 };
 pub use structure_comparator::{
-    compute_structure_fingerprint, should_compare_fingerprints, ComparisonOptions,
-    MemberComparisonStrategy, MemberMatch, SourceLocation, Structure, StructureComparator,
-    StructureComparisonResult, StructureDifferences, StructureIdentifier, StructureKind,
-    StructureMember, StructureMetadata,
+    compute_structure_fingerprint, should_compare_fingerprints, BucketStats, ComparisonOptions,
+    FingerprintBuckets, MemberComparisonStrategy, MemberMatch, SizeCategory, SourceLocation,
+    Structure, StructureComparator, StructureComparisonResult, StructureDifferences,
+    StructureFingerprint, StructureIdentifier, StructureKind, StructureMember, StructureMetadata,
+    TypeCategory,
 };
 pub use typescript_structure_adapter::{BatchComparator, TypeScriptStructureComparator};
 
 // Fast similarity exports
 pub use ast_fingerprint::AstFingerprint;
 pub use fast_similarity::{
-    find_similar_functions_across_files_fast, find_similar_functions_fast, FastSimilarityOptions,
+    find_similar_functions_across_files_fast, find_similar_functions_across_files_fast_with_budget,
+    find_similar_functions_fast, find_similar_functions_fast_with_budget, FastSimilarityOptions,
 };
 
 // Subtree fingerprint exports
@@ -110,15 +211,99 @@ pub use generic_overlap_detector::{
 
 // Class-related exports
 pub use class_comparator::{
-    compare_classes, find_similar_classes, find_similar_classes_across_files, normalize_class,
-    ClassComparisonResult, ClassDifferences, MethodMismatch, NormalizedClass, PropertyMismatch,
-    SimilarClassPair,
+    compare_classes, compare_classes_with_options, compare_method_bodies, find_similar_classes,
+    find_similar_classes_across_files, find_similar_classes_with_budget,
+    find_similar_classes_with_options, normalize_class, normalize_class_with_options,
+    ClassComparisonOptions, ClassComparisonResult, ClassDifferences, DecoratorMismatch,
+    MethodMismatch, MethodSimilarity, NormalizedClass, PropertyMismatch, SimilarClassPair,
 };
 pub use class_extractor::{
     extract_classes_from_code, extract_classes_from_files, ClassDefinition, ClassMethod,
-    ClassProperty, MethodKind,
+    ClassProperty, Decorator, MethodKind,
 };
+pub use codeowners::{CodeOwners, CODEOWNERS_LOCATIONS};
+pub use compose_structure_adapter::{extract_compose_services, ComposeAnalyzer, ComposeService};
 pub use config_loader::ConfigLoader;
+pub use config_parser::{parse_json, parse_yaml, ConfigNode, ConfigValue};
+pub use config_structure_adapter::{
+    extract_config_blocks, ConfigAnalyzer, ConfigBlock, ConfigFormat,
+};
+pub use dockerfile_analyzer::{parse_instructions, DockerfileAnalyzer, Instruction};
+pub use duplication_heatmap::{DuplicationHeatmap, FileHeatmapEntry};
+pub use evaluation::{
+    evaluate, CloneType, Dataset, EvaluationReport, LabeledPair, PairResult, TypeStats,
+    DATASET_MANIFEST,
+};
+pub use git_blame::{older_copy, BlameInfo, GitBlamer, OlderCopy};
+pub use hcl_structure_adapter::{extract_hcl_blocks, HclAnalyzer, HclBlock};
+pub use html_analyzer::HtmlAnalyzer;
+pub use idl_structure_adapter::{
+    extract_proto_definitions, extract_thrift_definitions, IdlAnalyzer, IdlDefinition, IdlField,
+};
+pub use import_graph::{extract_import_specifiers, ImportGraph, ImportInstead, ModuleRelation};
+pub use injection::{
+    builtin_injections, find_tagged_templates, InjectionAnalyzer, TaggedTemplate,
+    INJECTION_HOST_EXTENSIONS,
+};
+pub use notebook::{
+    find_duplicate_cells, parse_notebook, report_duplicate_cells, CellDuplicate, Notebook,
+    NotebookCell, ParsedCell,
+};
+pub use output_schema::{
+    finding_json_schema, heatmap_json_schema, FindingImport, FindingLocation, FindingSymbol,
+    HeatmapReport, JsonFinding, SCHEMA_VERSION,
+};
+pub use package_boundary::{PackageInfo, PackageResolver};
+pub use pair_diff::{render_pair_diff, DiffLayout, DiffSide, MIN_SIDE_BY_SIDE_WIDTH};
+pub use pr_comment::{render_pr_comment, BlobLinks, PR_COMMENT_MARKER};
+pub use preset::{
+    Preset, PresetAnalyzer, PresetConfig, PresetDefinition, ProjectSettings, BUILD_OUTPUT_EXCLUDE,
+    PRESETS,
+};
+pub use quickfix::{apply_fixes, identical_function_fix, Edit, Fix, FunctionSite};
+pub use react_hooks::{
+    compare_hooks, extract_hooks, find_similar_hooks, report_similar_hooks, HookDefinition,
+    HookDuplicate, HookSimilarity,
+};
+pub use refactor_patch::{
+    extract_shared_function_patch, import_specifier, module_specifier, shared_module_path,
+    PatchSource, PATCH_SIMILARITY_THRESHOLD,
+};
+pub use remote_repo::{default_cache_dir, fetch_repository, FetchedRepository, RemoteSpec};
+pub use report_diff::{
+    diff_reports, load_findings, parse_findings, ReportDiff, ReportFinding, ScoreChange,
+};
+pub use report_merge::merge_reports;
+pub use result_formatter::{
+    Finding, FormatterRegistry, JsonFormatter, ResultFormatter, TextFormatter, VscodeFormatter,
+};
+pub use run_report::{RunReport, SkipReason};
+pub use schema_structure_adapter::{
+    extract_graphql_types, extract_openapi_schemas, SchemaAnalyzer,
+};
+pub use self_test::{check_writable, ParserBackend, ParserSample, PARSER_SAMPLES};
+pub use semantic_types::{SemanticTypeConfig, SemanticTypeResolver};
+pub use severity::{Severity, SeverityConfig, SeverityPolicy, SeverityRule};
+pub use shell_analyzer::ShellAnalyzer;
+pub use size_penalty::{PenaltyCurve, SizePenalty, SizePenaltyConfig};
+pub use sql_analyzer::SqlAnalyzer;
+pub use statement_order::canonicalize_statement_order;
+pub use structural_search::{search_source, StructuralMatch, StructuralPattern, HOLE};
+pub use suppression::{AllowRule, SuppressionConfig, Suppressions};
+pub use synthetic_repo::{
+    format_reference_timings, generate_synthetic_repo, ReferenceTiming, SyntheticRepo,
+    SyntheticRepoOptions, REFERENCE_TIMINGS,
+};
+pub use test_blocks::{
+    extract_test_units, find_similar_tests, report_similar_tests, TestDuplicate, TestUnit,
+    TestUnitKind,
+};
+pub use top_pairs::TopPairs;
+pub use triage::{
+    content_finding_id, content_id_distance, finding_id, TriageEntry, TriageState, TriageStore,
+    DEFAULT_TRIAGE_PATH, MAX_DRIFT_BITS,
+};
+pub use tsconfig_paths::PathAliases;
 
 #[cfg(test)]
 mod structure_comparator_tests;
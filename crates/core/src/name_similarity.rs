@@ -0,0 +1,251 @@
+/// Metric used to compare identifier names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameSimilarityMetric {
+    /// Normalized Levenshtein distance
+    #[default]
+    Levenshtein,
+    /// Jaro-Winkler similarity, favouring shared prefixes
+    JaroWinkler,
+}
+
+/// Split an identifier into lowercase tokens on camelCase, snake_case,
+/// kebab-case, punctuation and letter/digit boundaries.
+///
+/// `getHTTPResponse2xx` becomes `["get", "http", "response", "2", "xx"]`.
+#[must_use]
+pub fn split_identifier(name: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+
+    for word in name.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()) {
+        let chars: Vec<char> = word.chars().collect();
+        let mut start = 0;
+
+        for i in 1..chars.len() {
+            let (prev, current) = (chars[i - 1], chars[i]);
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+
+            let boundary = (prev.is_lowercase() && current.is_uppercase())
+                || (prev.is_uppercase() && current.is_uppercase() && next_is_lower)
+                || (prev.is_alphabetic() && current.is_numeric())
+                || (prev.is_numeric() && current.is_alphabetic());
+
+            if boundary {
+                tokens.push(chars[start..i].iter().collect::<String>().to_lowercase());
+                start = i;
+            }
+        }
+        tokens.push(chars[start..].iter().collect::<String>().to_lowercase());
+    }
+
+    tokens
+}
+
+/// Similarity of two identifiers in `[0.0, 1.0]`.
+///
+/// Names are compared token by token: the result is one minus a token-level
+/// edit distance where substituting a token costs one minus its character
+/// similarity under `metric`. `getUserById` and `getUserByName` therefore
+/// differ only in their last token.
+#[must_use]
+pub fn name_similarity(name1: &str, name2: &str, metric: NameSimilarityMetric) -> f64 {
+    if name1 == name2 {
+        return 1.0;
+    }
+
+    let tokens1 = split_identifier(name1);
+    let tokens2 = split_identifier(name2);
+    let max_len = tokens1.len().max(tokens2.len());
+    if max_len == 0 {
+        // Only punctuation on both sides
+        return if name1.is_empty() || name2.is_empty() { 0.0 } else { 1.0 };
+    }
+
+    let distance =
+        weighted_token_distance(&tokens1, &tokens2, |a, b| 1.0 - string_similarity(a, b, metric));
+    (1.0 - distance / max_len as f64).clamp(0.0, 1.0)
+}
+
+/// Character-level similarity of two strings in `[0.0, 1.0]`
+#[must_use]
+pub fn string_similarity(s1: &str, s2: &str, metric: NameSimilarityMetric) -> f64 {
+    match metric {
+        NameSimilarityMetric::Levenshtein => levenshtein_similarity(s1, s2),
+        NameSimilarityMetric::JaroWinkler => jaro_winkler(s1, s2),
+    }
+}
+
+/// Edit distance over token sequences with a custom substitution cost
+fn weighted_token_distance(
+    tokens1: &[String],
+    tokens2: &[String],
+    substitution_cost: impl Fn(&str, &str) -> f64,
+) -> f64 {
+    let mut previous: Vec<f64> = (0..=tokens2.len()).map(|j| j as f64).collect();
+
+    for (i, token1) in tokens1.iter().enumerate() {
+        let mut current = vec![(i + 1) as f64; tokens2.len() + 1];
+        for (j, token2) in tokens2.iter().enumerate() {
+            let substitution = previous[j] + substitution_cost(token1, token2);
+            current[j + 1] = substitution.min(previous[j + 1] + 1.0).min(current[j] + 1.0);
+        }
+        previous = current;
+    }
+
+    previous[tokens2.len()]
+}
+
+/// Levenshtein distance between two strings, counted in characters
+#[must_use]
+pub fn levenshtein_distance(s1: &str, s2: &str) -> usize {
+    let chars2: Vec<char> = s2.chars().collect();
+    let mut previous: Vec<usize> = (0..=chars2.len()).collect();
+
+    for (i, c1) in s1.chars().enumerate() {
+        let mut current = vec![i + 1; chars2.len() + 1];
+        for (j, &c2) in chars2.iter().enumerate() {
+            let cost = usize::from(c1 != c2);
+            current[j + 1] = (previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[chars2.len()]
+}
+
+fn levenshtein_similarity(s1: &str, s2: &str) -> f64 {
+    let max_len = s1.chars().count().max(s2.chars().count());
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein_distance(s1, s2) as f64 / max_len as f64
+}
+
+/// Jaro-Winkler similarity with the standard 0.1 prefix scale (up to 4 chars)
+#[must_use]
+pub fn jaro_winkler(s1: &str, s2: &str) -> f64 {
+    let jaro = jaro(s1, s2);
+    let prefix = s1.chars().zip(s2.chars()).take_while(|(a, b)| a == b).take(4).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+fn jaro(s1: &str, s2: &str) -> f64 {
+    let chars1: Vec<char> = s1.chars().collect();
+    let chars2: Vec<char> = s2.chars().collect();
+    if chars1.is_empty() && chars2.is_empty() {
+        return 1.0;
+    }
+    if chars1.is_empty() || chars2.is_empty() {
+        return 0.0;
+    }
+
+    let window = (chars1.len().max(chars2.len()) / 2).saturating_sub(1);
+    let mut matched1 = vec![false; chars1.len()];
+    let mut matched2 = vec![false; chars2.len()];
+    let mut matches = 0usize;
+
+    for (i, c1) in chars1.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(chars2.len());
+        for j in start..end {
+            if !matched2[j] && chars2[j] == *c1 {
+                matched1[i] = true;
+                matched2[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let matched_chars2: Vec<char> =
+        chars2.iter().zip(&matched2).filter(|(_, m)| **m).map(|(c, _)| *c).collect();
+    let transpositions = chars1
+        .iter()
+        .zip(&matched1)
+        .filter(|(_, m)| **m)
+        .zip(&matched_chars2)
+        .filter(|((c1, _), c2)| *c1 != *c2)
+        .count();
+
+    let m = matches as f64;
+    (m / chars1.len() as f64 + m / chars2.len() as f64 + (m - transpositions as f64 / 2.0) / m)
+        / 3.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    const METRICS: [NameSimilarityMetric; 2] =
+        [NameSimilarityMetric::Levenshtein, NameSimilarityMetric::JaroWinkler];
+
+    #[test]
+    fn test_split_identifier() {
+        assert_eq!(split_identifier("getUserById"), vec!["get", "user", "by", "id"]);
+        assert_eq!(split_identifier("get_user_by_id"), vec!["get", "user", "by", "id"]);
+        assert_eq!(split_identifier("HTTPServer"), vec!["http", "server"]);
+        assert_eq!(
+            split_identifier("getHTTPResponse2xx"),
+            vec!["get", "http", "response", "2", "xx"]
+        );
+        assert_eq!(split_identifier(".btn-primary"), vec!["btn", "primary"]);
+        assert!(split_identifier("__").is_empty());
+    }
+
+    #[test]
+    fn test_levenshtein_and_jaro_winkler() {
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("", "abc"), 3);
+        assert!((jaro_winkler("MARTHA", "MARHTA") - 0.9611).abs() < 1e-3);
+        assert!((jaro_winkler("DIXON", "DICKSONX") - 0.8133).abs() < 1e-3);
+        assert_eq!(jaro_winkler("abc", "xyz"), 0.0);
+    }
+
+    #[test]
+    fn test_token_aware_name_similarity() {
+        for metric in METRICS {
+            // Naming convention alone does not make names different
+            assert_eq!(name_similarity("getUserById", "get_user_by_id", metric), 1.0);
+
+            // Only the last of four tokens differs
+            let by_name = name_similarity("getUserById", "getUserByName", metric);
+            assert!(by_name >= 0.75, "{metric:?}: {by_name}");
+
+            // Renaming the verb and the noun scores lower than a differing suffix
+            let other = name_similarity("getUserById", "removeAccountById", metric);
+            assert!(other < by_name, "{metric:?}: {other} >= {by_name}");
+
+            assert!(name_similarity("User", "Product", metric) < 0.5);
+        }
+    }
+
+    quickcheck! {
+        fn prop_similarity_is_bounded(a: String, b: String) -> bool {
+            METRICS.iter().all(|&metric| {
+                let similarity = name_similarity(&a, &b, metric);
+                (0.0..=1.0).contains(&similarity)
+            })
+        }
+
+        fn prop_similarity_is_symmetric(a: String, b: String) -> bool {
+            METRICS.iter().all(|&metric| {
+                (name_similarity(&a, &b, metric) - name_similarity(&b, &a, metric)).abs() < 1e-9
+            })
+        }
+
+        fn prop_identical_names_are_fully_similar(a: String) -> bool {
+            METRICS.iter().all(|&metric| name_similarity(&a, &a, metric) == 1.0)
+        }
+
+        fn prop_levenshtein_is_a_metric(a: String, b: String, c: String) -> bool {
+            let ab = levenshtein_distance(&a, &b);
+            ab == levenshtein_distance(&b, &a)
+                && (ab == 0) == (a == b)
+                && ab <= levenshtein_distance(&a, &c) + levenshtein_distance(&c, &b)
+        }
+    }
+}
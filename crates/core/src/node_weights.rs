@@ -0,0 +1,157 @@
+//! Weights of node kinds in tree comparison.
+//!
+//! By default every node costs the same to insert, delete or rename, so a
+//! changed condition weighs as much as a changed operand. A weight table
+//! makes some kinds count more: with control flow weighted up, two functions
+//! with the same branching but different arithmetic score higher than two
+//! with the same arithmetic but different branching. Keys are node kinds as
+//! the parsers name them (`IfStatement` in TypeScript, `if_statement` in the
+//! tree-sitter languages) and two groups:
+//!
+//! - `@control-flow`: branches, loops, jumps and exception handling
+//! - `@leaf`: nodes without children, such as identifiers and literals
+//!
+//! A kind's own entry takes precedence over its group, and kinds without an
+//! entry weigh 1.
+
+use crate::config_loader::ConfigLoader;
+use crate::tree::TreeNode;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Group key for the control flow kinds of every language
+pub const CONTROL_FLOW_GROUP: &str = "@control-flow";
+/// Group key for nodes without children
+pub const LEAF_GROUP: &str = "@leaf";
+
+const CONTROL_FLOW_KINDS: &[&str] = &[
+    // TypeScript
+    "IfStatement",
+    "ForStatement",
+    "ForInStatement",
+    "ForOfStatement",
+    "WhileStatement",
+    "DoWhileStatement",
+    "SwitchStatement",
+    "TryStatement",
+    "ReturnStatement",
+    "BreakStatement",
+    "ContinueStatement",
+    "ThrowStatement",
+    "ConditionalExpression",
+    // tree-sitter grammars
+    "if_statement",
+    "if_expression",
+    "else_clause",
+    "elif_clause",
+    "for_statement",
+    "for_expression",
+    "for_in_statement",
+    "while_statement",
+    "while_expression",
+    "loop_expression",
+    "do_statement",
+    "switch_statement",
+    "match_expression",
+    "match_statement",
+    "case_statement",
+    "try_statement",
+    "try_expression",
+    "catch_clause",
+    "except_clause",
+    "return_statement",
+    "return_expression",
+    "break_statement",
+    "break_expression",
+    "continue_statement",
+    "continue_expression",
+    "throw_statement",
+    "raise_statement",
+    "conditional_expression",
+    "ternary_expression",
+];
+
+/// Weight table of node kinds, from the `[node_weights]` table of `similarity.toml`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct NodeWeights(BTreeMap<String, f64>);
+
+impl NodeWeights {
+    #[must_use]
+    pub fn new(weights: BTreeMap<String, f64>) -> Self {
+        Self(weights)
+    }
+
+    /// Whether every node weighs 1
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(|&weight| weight == 1.0)
+    }
+
+    /// Weight of a node: its kind's entry (the label, or the value for parsers
+    /// that label nodes by name), then its group's, then 1
+    #[must_use]
+    pub fn weight_of(&self, node: &TreeNode) -> f64 {
+        let entry = |key: &str| self.0.get(key).copied();
+        entry(&node.label)
+            .or_else(|| entry(&node.value))
+            .or_else(|| {
+                let control_flow = CONTROL_FLOW_KINDS
+                    .iter()
+                    .any(|kind| *kind == node.label || *kind == node.value);
+                control_flow.then(|| entry(CONTROL_FLOW_GROUP)).flatten()
+            })
+            .or_else(|| node.children.is_empty().then(|| entry(LEAF_GROUP)).flatten())
+            .unwrap_or(1.0)
+            .max(0.0)
+    }
+
+    /// The tree with the weight of every node set
+    #[must_use]
+    pub fn apply(&self, tree: &Rc<TreeNode>) -> Rc<TreeNode> {
+        Rc::new(TreeNode {
+            label: tree.label.clone(),
+            value: tree.value.clone(),
+            children: tree.children.iter().map(|child| self.apply(child)).collect(),
+            id: tree.id,
+            subtree_size: tree.subtree_size,
+            weight: self.weight_of(tree),
+        })
+    }
+}
+
+/// `[node_weights]` table of `similarity.toml`, e.g. `"@control-flow" = 3.0`
+#[derive(Debug, Default, Deserialize)]
+pub struct NodeWeightConfig {
+    #[serde(default)]
+    pub node_weights: NodeWeights,
+}
+
+impl ConfigLoader for NodeWeightConfig {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_entries_take_precedence_over_groups() {
+        let config: NodeWeightConfig = toml::from_str(
+            "[node_weights]\n\"@control-flow\" = 3.0\n\"@leaf\" = 0.5\nReturnStatement = 2.0\n",
+        )
+        .unwrap();
+        let weights = config.node_weights;
+        let node = |label: &str, value: &str| TreeNode::new(label.into(), value.into(), 0);
+        let mut branch = node("IfStatement", "IfStatement");
+        branch.add_child(Rc::new(node("x", "Identifier")));
+
+        assert_eq!(weights.weight_of(&branch), 3.0);
+        assert_eq!(weights.weight_of(&node("ReturnStatement", "ReturnStatement")), 2.0);
+        assert_eq!(weights.weight_of(&node("x", "Identifier")), 0.5);
+        assert_eq!(weights.weight_of(&node("CallExpression", "CallExpression")), 0.5);
+        assert_eq!(weights.weight_of(&branch.children[0]), 0.5);
+        assert_eq!(weights.apply(&Rc::new(branch)).get_subtree_weight(), 3.5);
+        assert!(!weights.is_empty());
+        assert!(NodeWeights::default().is_empty());
+    }
+}
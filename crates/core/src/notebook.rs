@@ -0,0 +1,246 @@
+//! Duplicate detection for Jupyter notebooks (`.ipynb`).
+//!
+//! Code cells are extracted from the notebook JSON and compared as whole
+//! units, within and across notebooks. Findings refer to the position of the
+//! cell in the notebook (1-based, counting markdown cells too) as shown by
+//! Jupyter, since execution counts change on every run. Parsing the cell code
+//! is left to the caller so that each CLI can use its own language parser.
+
+use crate::cli_output::format_function_output;
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, TSEDOptions};
+use serde_json::Value;
+use std::rc::Rc;
+
+/// A code cell of a notebook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotebookCell {
+    /// 1-based position among all cells of the notebook
+    pub number: usize,
+    pub source: String,
+}
+
+impl NotebookCell {
+    #[must_use]
+    pub fn line_count(&self) -> u32 {
+        self.source.lines().count() as u32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notebook {
+    /// Kernel language in lowercase (`python`, `javascript`, `typescript`, ...)
+    pub language: Option<String>,
+    pub cells: Vec<NotebookCell>,
+}
+
+impl Notebook {
+    /// Whether the notebook runs one of `languages`
+    #[must_use]
+    pub fn is_language(&self, languages: &[&str]) -> bool {
+        self.language.as_deref().is_some_and(|language| languages.contains(&language))
+    }
+}
+
+/// Parse notebook JSON (nbformat 4) and return its non-empty code cells
+///
+/// # Errors
+///
+/// Returns an error if the content is not a notebook
+pub fn parse_notebook(content: &str) -> Result<Notebook, String> {
+    let json: Value = serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {e}"))?;
+    let cells = json.get("cells").and_then(Value::as_array).ok_or("Missing 'cells' array")?;
+
+    let metadata = json.get("metadata");
+    let language = metadata
+        .and_then(|m| {
+            m.pointer("/kernelspec/language").or_else(|| m.pointer("/language_info/name"))
+        })
+        .and_then(Value::as_str)
+        .map(str::to_lowercase);
+
+    let cells = cells
+        .iter()
+        .enumerate()
+        .filter(|(_, cell)| cell.get("cell_type").and_then(Value::as_str) == Some("code"))
+        .filter_map(|(index, cell)| {
+            let source = match cell.get("source")? {
+                Value::String(source) => source.clone(),
+                Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+                _ => return None,
+            };
+            let source = strip_magics(&source)?;
+            (!source.trim().is_empty()).then_some(NotebookCell { number: index + 1, source })
+        })
+        .collect();
+
+    Ok(Notebook { language, cells })
+}
+
+/// Blank out IPython line magics and shell escapes (`%time`, `!pip install`) so the
+/// rest of the cell parses; cells run by a cell magic (`%%bash`) are not code
+/// of the kernel language and are dropped
+fn strip_magics(source: &str) -> Option<String> {
+    if source.trim_start().starts_with("%%") {
+        return None;
+    }
+    let lines: Vec<&str> = source
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('%') || trimmed.starts_with('!') {
+                ""
+            } else {
+                line
+            }
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// A code cell converted to a tree for comparison
+#[derive(Debug, Clone)]
+pub struct ParsedCell {
+    pub notebook: String,
+    pub cell: NotebookCell,
+    pub tree: Rc<TreeNode>,
+}
+
+/// A pair of cells whose similarity reached the threshold
+#[derive(Debug, Clone)]
+pub struct CellDuplicate<'a> {
+    pub first: &'a ParsedCell,
+    pub second: &'a ParsedCell,
+    pub similarity: f64,
+}
+
+/// Compare all pairs of cells, within and across notebooks, most similar first.
+/// Cells below `options.min_tokens` AST nodes, or `options.min_lines` lines
+/// when no token minimum is set, are skipped.
+#[must_use]
+pub fn find_duplicate_cells<'a>(
+    cells: &'a [ParsedCell],
+    threshold: f64,
+    options: &TSEDOptions,
+) -> Vec<CellDuplicate<'a>> {
+    let candidates: Vec<&ParsedCell> = cells
+        .iter()
+        .filter(|cell| match options.min_tokens {
+            Some(min_tokens) => cell.tree.get_subtree_size() as u32 >= min_tokens,
+            None => cell.cell.line_count() >= options.min_lines,
+        })
+        .collect();
+
+    let mut duplicates = Vec::new();
+    for (i, first) in candidates.iter().enumerate() {
+        for second in &candidates[i + 1..] {
+            let similarity = calculate_tsed(&first.tree, &second.tree, options);
+            if similarity >= threshold {
+                duplicates.push(CellDuplicate { first, second, similarity });
+            }
+        }
+    }
+
+    duplicates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    duplicates
+}
+
+fn cell_label(cell: &ParsedCell) -> String {
+    format_function_output(
+        &cell.notebook,
+        &format!("cell {}", cell.cell.number),
+        1,
+        cell.cell.line_count(),
+    )
+}
+
+/// Print duplicated cells; returns the number of pairs
+pub fn report_duplicate_cells(duplicates: &[CellDuplicate], print: bool) -> usize {
+    if duplicates.is_empty() {
+        println!("\nNo duplicate notebook cells found!");
+        return 0;
+    }
+
+    println!("\nDuplicate notebook cells:");
+    println!("{}", "-".repeat(60));
+    for duplicate in duplicates {
+        println!("\nSimilarity: {:.2}%", duplicate.similarity * 100.0);
+        println!("  {}", cell_label(duplicate.first));
+        println!("  {}", cell_label(duplicate.second));
+
+        if print {
+            for cell in [duplicate.first, duplicate.second] {
+                println!(
+                    "\n\x1b[36m--- {} cell {} ---\x1b[0m\n{}",
+                    cell.notebook, cell.cell.number, cell.cell.source
+                );
+            }
+        }
+    }
+    println!("\nTotal duplicate cell pairs found: {}", duplicates.len());
+
+    duplicates.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_and_convert_to_tree;
+
+    const NOTEBOOK: &str = r##"{
+  "cells": [
+    { "cell_type": "markdown", "metadata": {}, "source": ["# Sales"] },
+    {
+      "cell_type": "code",
+      "execution_count": 4,
+      "metadata": {},
+      "outputs": [],
+      "source": ["%time\n", "const total = rows.reduce((sum, row) => sum + row.amount, 0);\n", "console.log(total);"]
+    },
+    { "cell_type": "code", "metadata": {}, "outputs": [], "source": "%%bash\nls -la" },
+    { "cell_type": "code", "metadata": {}, "outputs": [], "source": [] }
+  ],
+  "metadata": { "kernelspec": { "language": "typescript", "name": "deno" } },
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"##;
+
+    #[test]
+    fn extracts_code_cells_with_their_position() {
+        let notebook = parse_notebook(NOTEBOOK).unwrap();
+
+        assert!(notebook.is_language(&["javascript", "typescript"]));
+        assert_eq!(notebook.cells.len(), 1);
+        assert_eq!(notebook.cells[0].number, 2);
+        // The magic line is blanked, keeping line numbers intact
+        assert!(notebook.cells[0].source.starts_with("\nconst total"));
+        assert_eq!(notebook.cells[0].line_count(), 3);
+
+        assert!(parse_notebook("{\"metadata\": {}}").is_err());
+    }
+
+    #[test]
+    fn finds_cells_copied_between_notebooks() {
+        let cell = |notebook: &str, number: usize, source: &str| ParsedCell {
+            notebook: notebook.to_string(),
+            cell: NotebookCell { number, source: source.to_string() },
+            tree: parse_and_convert_to_tree("cell.mts", source).unwrap(),
+        };
+        let cells = vec![
+            cell("a.ipynb", 2, "const total = rows.reduce((sum, row) => sum + row.amount, 0);\nconsole.log(total);"),
+            cell("b.ipynb", 5, "const sum = items.reduce((acc, item) => acc + item.amount, 0);\nconsole.log(sum);"),
+            cell(
+                "b.ipynb",
+                6,
+                "for (const region of regions) {\n  if (region.active) {\n    await render(region.id);\n  }\n}",
+            ),
+        ];
+
+        let options = TSEDOptions { min_lines: 1, size_penalty: false, ..Default::default() };
+        let duplicates = find_duplicate_cells(&cells, 0.9, &options);
+
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].first.cell.number, 2);
+        assert_eq!(duplicates[0].second.cell.number, 5);
+    }
+}
@@ -0,0 +1,356 @@
+//! Versioned schema of the machine-readable outputs.
+//!
+//! The duplication heatmap written by `--export` and each finding printed by
+//! `--output json`, one JSON object per line, carry a `schema_version`. Their
+//! JSON Schemas, [`heatmap_json_schema`] and [`finding_json_schema`], are
+//! generated from the types serializing them. Compatibility rules, for both:
+//!
+//! - Adding a field, or a new analyzer key under `findings`, keeps the version.
+//!   Consumers must ignore fields they do not know.
+//! - Removing or renaming a field, or changing its type or meaning, bumps
+//!   [`SCHEMA_VERSION`].
+
+use crate::duplication_heatmap::FileHeatmapEntry;
+use crate::quickfix::Fix;
+use crate::result_formatter::Finding;
+use crate::severity::Severity;
+use crate::tsed::ComparisonAlgorithm;
+use crate::type_comparator::PropertyDiffEntry;
+use schemars::generate::SchemaSettings;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// Version of the JSON documents, bumped on incompatible changes only
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Document written by `--export`
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialEq)]
+#[schemars(title = "similarity duplication heatmap")]
+pub struct HeatmapReport {
+    /// Bumped on incompatible changes; unknown fields must be ignored
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    pub files: Vec<FileHeatmapEntry>,
+}
+
+impl HeatmapReport {
+    #[must_use]
+    pub fn new(files: Vec<FileHeatmapEntry>) -> Self {
+        Self { schema_version: SCHEMA_VERSION, files }
+    }
+}
+
+/// One finding printed by `--output json`. Analyzers fill in the optional
+/// fields they know.
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialEq)]
+#[schemars(title = "similarity finding")]
+pub struct JsonFinding {
+    /// Bumped on incompatible changes; unknown fields must be ignored
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    /// Function findings: content ID of the pair, stable across renames and
+    /// moves; matches triage IDs and keys report-diff
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "String")]
+    pub id: Option<String>,
+    pub file: String,
+    /// 1-based
+    #[schemars(range(min = 1))]
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+    pub related: FindingLocation,
+    /// Function findings: the similarity the message rounds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "f64", range(min = 0.0, max = 1.0))]
+    pub similarity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "FindingSymbol")]
+    pub symbol: Option<FindingSymbol>,
+    /// Function findings: tsed (tree edit distance) or fallback (node label
+    /// counts, for functions above --max-tree-nodes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "ComparisonAlgorithm")]
+    pub algorithm: Option<ComparisonAlgorithm>,
+    /// Function findings where one side is a private copy of the other,
+    /// exported one: the import that should replace the copy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "FindingImport")]
+    pub import_instead: Option<FindingImport>,
+    /// Machine-applicable fixes; present only for findings that support them.
+    /// The edits of a fix are applied together, against the analyzed
+    /// contents; lines are 1-based and inclusive.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<Fix>,
+    /// Type findings with --diff: the properties of both types aligned, in
+    /// the declaration order of the first type with extra properties last
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Vec<PropertyDiffEntry>")]
+    pub property_diff: Option<Vec<PropertyDiffEntry>>,
+}
+
+impl JsonFinding {
+    /// A finding with only the fields every analyzer fills in
+    #[must_use]
+    pub fn new(finding: &Finding) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            id: None,
+            file: finding.file.to_string(),
+            line: finding.line,
+            severity: finding.severity,
+            message: finding.message.to_string(),
+            related: FindingLocation {
+                file: finding.related_file.to_string(),
+                line: finding.related_line,
+                symbol: None,
+            },
+            similarity: None,
+            symbol: None,
+            algorithm: None,
+            import_instead: None,
+            fixes: finding.fixes.to_vec(),
+            property_diff: None,
+        }
+    }
+
+    /// The fields every analyzer fills in, for formatters without room for the rest
+    #[must_use]
+    pub fn as_finding(&self) -> Finding<'_> {
+        Finding {
+            file: &self.file,
+            line: self.line,
+            severity: self.severity,
+            message: &self.message,
+            related_file: &self.related.file,
+            related_line: self.related.line,
+            fixes: &self.fixes,
+        }
+    }
+}
+
+/// One line of JSON
+impl fmt::Display for JsonFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+/// The other location of a finding
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialEq)]
+pub struct FindingLocation {
+    pub file: String,
+    /// 1-based
+    #[schemars(range(min = 1))]
+    pub line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "FindingSymbol")]
+    pub symbol: Option<FindingSymbol>,
+}
+
+/// Function findings: the function with its enclosing namespaces, classes and
+/// functions, e.g. `UserService.updateProfile > validatePayload`
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialEq)]
+pub struct FindingSymbol {
+    pub path: String,
+    /// Importable from its module, directly or through an exported class or object
+    pub exported: bool,
+}
+
+/// Import replacing a private copy of an exported function
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialEq)]
+pub struct FindingImport {
+    /// File holding the copy
+    pub file: String,
+    /// Specifier of the exporting module, relative to file
+    pub module: String,
+    pub statement: String,
+}
+
+/// JSON Schema (draft 2020-12) of `T` as serialized, with no references
+fn json_schema<T: JsonSchema>() -> Value {
+    let mut settings = SchemaSettings::draft2020_12().for_serialize();
+    settings.inline_subschemas = true;
+    settings.into_generator().into_root_schema_for::<T>().to_value()
+}
+
+/// JSON Schema (draft 2020-12) of [`HeatmapReport`]
+#[must_use]
+pub fn heatmap_json_schema() -> Value {
+    json_schema::<HeatmapReport>()
+}
+
+/// JSON Schema (draft 2020-12) of one finding printed by `--output json`
+#[must_use]
+pub fn finding_json_schema() -> Value {
+    json_schema::<JsonFinding>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_output::{format_json_finding, json_finding};
+    use crate::duplication_heatmap::DuplicationHeatmap;
+    use crate::quickfix::Edit;
+    use crate::type_comparator::PropertyDiffKind;
+
+    /// Check `value` against the parts of JSON Schema the generated schemas use
+    fn check(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+        if let Some(variants) = schema["oneOf"].as_array() {
+            let matching = variants.iter().filter(|v| check(value, v, path).is_ok()).count();
+            return match matching {
+                1 => Ok(()),
+                _ => Err(format!("{path}: {value} matches {matching} variants")),
+            };
+        }
+        if schema.get("const").is_some_and(|expected| expected != value) {
+            return Err(format!("{path}: {value} is not {}", schema["const"]));
+        }
+        if schema["enum"].as_array().is_some_and(|allowed| !allowed.contains(value)) {
+            return Err(format!("{path}: {value} is not one of {}", schema["enum"]));
+        }
+        let types: Vec<&str> = match &schema["type"] {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let type_matches = types.is_empty()
+            || types.iter().any(|name| match *name {
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                "string" => value.is_string(),
+                "boolean" => value.is_boolean(),
+                "integer" => value.is_u64() || value.is_i64(),
+                "number" => value.is_number(),
+                "null" => value.is_null(),
+                _ => false,
+            });
+        if !type_matches {
+            return Err(format!("{path}: {value} is not {}", schema["type"]));
+        }
+        if let (Some(number), Some(minimum)) = (value.as_f64(), schema["minimum"].as_f64()) {
+            if number < minimum {
+                return Err(format!("{path}: {number} is below {minimum}"));
+            }
+        }
+        if let (Some(number), Some(maximum)) = (value.as_f64(), schema["maximum"].as_f64()) {
+            if number > maximum {
+                return Err(format!("{path}: {number} is above {maximum}"));
+            }
+        }
+
+        if let Some(object) = value.as_object() {
+            for required in schema["required"].as_array().into_iter().flatten() {
+                let key = required.as_str().unwrap();
+                if !object.contains_key(key) {
+                    return Err(format!("{path}: missing {key}"));
+                }
+            }
+            for (key, field) in object {
+                let field_schema = schema["properties"]
+                    .get(key)
+                    .or_else(|| schema.get("additionalProperties"))
+                    .ok_or_else(|| format!("{path}: {key} is not in the schema"))?;
+                check(field, field_schema, &format!("{path}.{key}"))?;
+            }
+        }
+        for item in value.as_array().into_iter().flatten() {
+            check(item, &schema["items"], &format!("{path}[]"))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_heatmap_matches_schema() {
+        let mut heatmap = DuplicationHeatmap::new();
+        heatmap.set_total_lines("a.ts", 10);
+        heatmap.set_total_lines("b.ts", 10);
+        heatmap.record_pair("functions", ("a.ts", 1, 5), ("b.ts", 2, 6));
+
+        let document: Value = serde_json::from_str(&heatmap.to_json().unwrap()).unwrap();
+        assert_eq!(document["schema_version"], SCHEMA_VERSION);
+        check(&document, &heatmap_json_schema(), "$").unwrap();
+
+        let mut outdated = document.clone();
+        outdated["schema_version"] = (SCHEMA_VERSION + 1).into();
+        assert!(check(&outdated, &heatmap_json_schema(), "$").is_err());
+    }
+
+    #[test]
+    fn test_findings_carry_the_schema_version() {
+        let schema = finding_json_schema();
+        assert_eq!(schema["title"], "similarity finding");
+        let required: Vec<&str> =
+            schema["required"].as_array().unwrap().iter().filter_map(Value::as_str).collect();
+        assert!(required.contains(&"schema_version"), "{required:?}");
+
+        let finding: Value = serde_json::from_str(&format_json_finding(
+            "a.ts",
+            1,
+            Severity::Info,
+            "similar",
+            "b.ts",
+            2,
+            &[],
+        ))
+        .unwrap();
+        assert_eq!(finding["schema_version"], SCHEMA_VERSION);
+        assert!(finding.get("fixes").is_none());
+        assert!(finding.get("similarity").is_none());
+        check(&finding, &schema, "$").unwrap();
+
+        let mut unversioned = finding.clone();
+        unversioned.as_object_mut().unwrap().remove("schema_version");
+        assert!(check(&unversioned, &schema, "$").is_err());
+    }
+
+    #[test]
+    fn test_finding_matches_schema() {
+        let fix = Fix {
+            title: "Remove the duplicate".to_string(),
+            edits: vec![
+                Edit::InsertImport {
+                    file: "b.ts".to_string(),
+                    line: 1,
+                    text: "import".to_string(),
+                },
+                Edit::DeleteRange { file: "b.ts".to_string(), start_line: 2, end_line: 6 },
+            ],
+        };
+        let mut finding = json_finding("a.ts", 1, Severity::Error, "duplicate", "b.ts", 2, &[fix]);
+        finding.id = Some("00000000000000ff".to_string());
+        finding.similarity = Some(1.0);
+        finding.algorithm = Some(ComparisonAlgorithm::Tsed);
+        finding.symbol = Some(FindingSymbol { path: "add".to_string(), exported: false });
+        finding.related.symbol = Some(FindingSymbol { path: "sum".to_string(), exported: true });
+        finding.import_instead = Some(FindingImport {
+            file: "a.ts".to_string(),
+            module: "./b".to_string(),
+            statement: "import { sum as add } from \"./b\";".to_string(),
+        });
+
+        finding.property_diff = Some(vec![
+            PropertyDiffEntry {
+                kind: PropertyDiffKind::TypeMismatch,
+                left: Some("age: string".to_string()),
+                right: Some("age: number".to_string()),
+            },
+            PropertyDiffEntry {
+                kind: PropertyDiffKind::Missing,
+                left: Some("extra: boolean".to_string()),
+                right: None,
+            },
+        ]);
+
+        let value: Value = serde_json::from_str(&finding.to_string()).unwrap();
+        check(&value, &finding_json_schema(), "$").unwrap();
+        assert_eq!(value["property_diff"][0]["kind"], "type_mismatch");
+        assert_eq!(value["property_diff"][1]["right"], Value::Null);
+        assert_eq!(value["fixes"][0]["edits"][1]["kind"], "delete_range");
+        assert_eq!(value["algorithm"], "tsed");
+        assert_eq!(value["related"]["symbol"]["path"], "sum");
+    }
+}
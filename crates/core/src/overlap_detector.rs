@@ -141,7 +141,7 @@ fn index_function(
     let (root_fp, subtrees) = generate_subtree_fingerprints(&tree, 0, func.start_line);
 
     // Create indexed function
-    let mut indexed = IndexedFunction::new(func.name.clone(), file_name.to_string(), root_fp);
+    let mut indexed = IndexedFunction::new(func.name.to_string(), file_name.to_string(), root_fp);
 
     // Add all subtrees to the index
     for subtree in subtrees {
@@ -0,0 +1,110 @@
+//! Package boundaries in monorepos.
+//!
+//! A file belongs to the package whose manifest (`package.json` or
+//! `Cargo.toml`) is found in its closest ancestor directory. Knowing the
+//! owning packages tells whether a duplicate is local to one package or a
+//! candidate for a shared library.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MANIFESTS: [&str; 2] = ["package.json", "Cargo.toml"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PackageInfo {
+    /// Name from the manifest, or the directory name when the manifest has none
+    pub name: String,
+    pub root: PathBuf,
+}
+
+/// Finds owning packages, caching the result for every directory visited
+#[derive(Debug, Default)]
+pub struct PackageResolver {
+    cache: HashMap<PathBuf, Option<PackageInfo>>,
+}
+
+impl PackageResolver {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The package owning `file`, if any ancestor directory has a manifest
+    pub fn package_of(&mut self, file: &Path) -> Option<PackageInfo> {
+        let file = file.canonicalize().ok()?;
+        let mut visited = Vec::new();
+        let mut found = None;
+
+        for dir in file.ancestors().skip(1) {
+            if let Some(cached) = self.cache.get(dir) {
+                found = cached.clone();
+                break;
+            }
+            visited.push(dir.to_path_buf());
+            if let Some(package) = read_manifest(dir) {
+                found = Some(package);
+                break;
+            }
+        }
+
+        for dir in visited {
+            self.cache.insert(dir, found.clone());
+        }
+        found
+    }
+}
+
+fn read_manifest(dir: &Path) -> Option<PackageInfo> {
+    let manifest = MANIFESTS.iter().map(|name| dir.join(name)).find(|path| path.is_file())?;
+    let content = fs::read_to_string(&manifest).unwrap_or_default();
+    let name = if manifest.ends_with("package.json") {
+        serde_json::from_str::<serde_json::Value>(&content)
+            .ok()
+            .and_then(|json| json.get("name")?.as_str().map(str::to_string))
+    } else {
+        toml::from_str::<toml::Value>(&content)
+            .ok()
+            .and_then(|value| value.get("package")?.get("name")?.as_str().map(str::to_string))
+    };
+    let name = name.unwrap_or_else(|| {
+        dir.file_name()
+            .map_or_else(|| dir.display().to_string(), |n| n.to_string_lossy().to_string())
+    });
+    Some(PackageInfo { name, root: dir.to_path_buf() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn nearest_manifest_owns_the_file() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("packages/web/src")).unwrap();
+        fs::create_dir_all(root.join("crates/engine/src")).unwrap();
+        fs::create_dir_all(root.join("packages/unnamed")).unwrap();
+        fs::write(root.join("package.json"), r#"{"name": "monorepo", "private": true}"#).unwrap();
+        fs::write(root.join("packages/web/package.json"), r#"{"name": "@acme/web"}"#).unwrap();
+        fs::write(root.join("packages/unnamed/package.json"), "{}").unwrap();
+        fs::write(root.join("crates/engine/Cargo.toml"), "[package]\nname = \"engine\"\n").unwrap();
+        for file in
+            ["packages/web/src/a.ts", "crates/engine/src/lib.rs", "packages/unnamed/b.ts", "c.ts"]
+        {
+            fs::write(root.join(file), "").unwrap();
+        }
+
+        let mut resolver = PackageResolver::new();
+        let name = |resolver: &mut PackageResolver, file: &str| {
+            resolver.package_of(&root.join(file)).map(|package| package.name)
+        };
+        assert_eq!(name(&mut resolver, "packages/web/src/a.ts").as_deref(), Some("@acme/web"));
+        assert_eq!(name(&mut resolver, "crates/engine/src/lib.rs").as_deref(), Some("engine"));
+        assert_eq!(name(&mut resolver, "packages/unnamed/b.ts").as_deref(), Some("unnamed"));
+        assert_eq!(name(&mut resolver, "c.ts").as_deref(), Some("monorepo"));
+        // Served from the cache the second time
+        assert_eq!(name(&mut resolver, "packages/web/src/a.ts").as_deref(), Some("@acme/web"));
+    }
+}
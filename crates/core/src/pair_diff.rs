@@ -0,0 +1,396 @@
+//! Aligned diff of two similar code fragments for `--print`.
+//!
+//! Lines are matched on their trimmed text, so a method and a copy of it at
+//! another indentation still line up. Changed lines that pair up get their
+//! differing tokens highlighted; the result is laid out side by side when the
+//! terminal is wide enough, or as a unified diff.
+
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+use std::ops::Range;
+
+/// Narrowest terminal that fits a readable side-by-side view
+pub const MIN_SIDE_BY_SIDE_WIDTH: usize = 100;
+
+const TAB: &str = "    ";
+const RESET: &str = "\x1b[0m";
+const HEADER: &str = "\x1b[36m";
+const REMOVED: &str = "\x1b[31m";
+const REMOVED_TOKEN: &str = "\x1b[1;97;41m";
+const ADDED: &str = "\x1b[32m";
+const ADDED_TOKEN: &str = "\x1b[1;97;42m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLayout {
+    /// Two columns filling `width` terminal columns
+    SideBySide {
+        width: usize,
+    },
+    Unified,
+}
+
+/// One of the two fragments: a label, its code and its first line number
+#[derive(Debug, Clone, Copy)]
+pub struct DiffSide<'a> {
+    pub label: &'a str,
+    pub code: &'a str,
+    pub start_line: u32,
+}
+
+/// A line of one side and the byte ranges of its changed tokens
+struct Line {
+    number: usize,
+    text: String,
+    highlights: Vec<Range<usize>>,
+}
+
+/// Aligned lines; a row is changed unless both lines match
+struct Row {
+    left: Option<Line>,
+    right: Option<Line>,
+    changed: bool,
+}
+
+/// Render the diff of two fragments with ANSI colors
+#[must_use]
+pub fn render_pair_diff(left: DiffSide<'_>, right: DiffSide<'_>, layout: DiffLayout) -> String {
+    let rows = align(left, right);
+    match layout {
+        DiffLayout::SideBySide { width } => render_side_by_side(left, right, &rows, width),
+        DiffLayout::Unified => render_unified(left, right, &rows),
+    }
+}
+
+fn align(left: DiffSide<'_>, right: DiffSide<'_>) -> Vec<Row> {
+    let left_lines: Vec<String> = left.code.lines().map(|line| line.replace('\t', TAB)).collect();
+    let right_lines: Vec<String> = right.code.lines().map(|line| line.replace('\t', TAB)).collect();
+    let left_keys: Vec<&str> = left_lines.iter().map(|line| line.trim()).collect();
+    let right_keys: Vec<&str> = right_lines.iter().map(|line| line.trim()).collect();
+
+    let line = |lines: &[String], start: u32, index: usize| Line {
+        number: start as usize + index,
+        text: lines[index].clone(),
+        highlights: Vec::new(),
+    };
+    let left_line = |index| line(&left_lines, left.start_line, index);
+    let right_line = |index| line(&right_lines, right.start_line, index);
+
+    let mut rows = Vec::new();
+    for op in capture_diff_slices(Algorithm::Patience, &left_keys, &right_keys) {
+        let (tag, old, new) = op.as_tag_tuple();
+        match tag {
+            DiffTag::Equal => {
+                for (i, j) in old.zip(new) {
+                    rows.push(Row {
+                        left: Some(left_line(i)),
+                        right: Some(right_line(j)),
+                        changed: false,
+                    });
+                }
+            }
+            DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                let old_texts: Vec<&str> = old.clone().map(|i| left_keys[i]).collect();
+                let new_texts: Vec<&str> = new.clone().map(|j| right_keys[j]).collect();
+                for (i, j) in pair_changed_lines(&old_texts, &new_texts) {
+                    let mut first = i.map(|i| left_line(old.start + i));
+                    let mut second = j.map(|j| right_line(new.start + j));
+                    match (&mut first, &mut second) {
+                        (Some(a), Some(b)) => {
+                            (a.highlights, b.highlights) = changed_tokens(&a.text, &b.text);
+                        }
+                        (Some(a), None) => a.highlights = vec![content_range(&a.text)],
+                        (None, Some(b)) => b.highlights = vec![content_range(&b.text)],
+                        (None, None) => {}
+                    }
+                    rows.push(Row { left: first, right: second, changed: true });
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// Lowest token similarity for two changed lines to share a row
+const MIN_LINE_SIMILARITY: f64 = 0.5;
+
+/// Rows for a run of changed lines: in order, lines pair with the most
+/// similar line of the other side they can, the others stand alone
+fn pair_changed_lines(old: &[&str], new: &[&str]) -> Vec<(Option<usize>, Option<usize>)> {
+    // Highest total similarity of order-preserving pairings of the prefixes
+    let mut best = vec![vec![0.0f64; new.len() + 1]; old.len() + 1];
+    for i in 1..=old.len() {
+        for j in 1..=new.len() {
+            let ratio = token_similarity(old[i - 1], new[j - 1]);
+            let paired =
+                if ratio >= MIN_LINE_SIMILARITY { best[i - 1][j - 1] + ratio } else { f64::MIN };
+            best[i][j] = best[i - 1][j].max(best[i][j - 1]).max(paired);
+        }
+    }
+
+    let mut rows = Vec::new();
+    let (mut i, mut j) = (old.len(), new.len());
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 {
+            let ratio = token_similarity(old[i - 1], new[j - 1]);
+            if ratio >= MIN_LINE_SIMILARITY && best[i][j] == best[i - 1][j - 1] + ratio {
+                rows.push((Some(i - 1), Some(j - 1)));
+                (i, j) = (i - 1, j - 1);
+                continue;
+            }
+        }
+        if j > 0 && (i == 0 || best[i][j] == best[i][j - 1]) {
+            rows.push((None, Some(j - 1)));
+            j -= 1;
+        } else {
+            rows.push((Some(i - 1), None));
+            i -= 1;
+        }
+    }
+    rows.reverse();
+    rows
+}
+
+/// Share of tokens two lines have in common (0.0-1.0)
+fn token_similarity(left: &str, right: &str) -> f64 {
+    let left: Vec<&str> = tokens(left).into_iter().map(|range| &left[range]).collect();
+    let right: Vec<&str> = tokens(right).into_iter().map(|range| &right[range]).collect();
+    if left.is_empty() && right.is_empty() {
+        return 1.0;
+    }
+    let common: usize = capture_diff_slices(Algorithm::Myers, &left, &right)
+        .iter()
+        .filter(|op| op.tag() == DiffTag::Equal)
+        .map(|op| op.old_range().len())
+        .sum();
+    2.0 * common as f64 / (left.len() + right.len()) as f64
+}
+
+/// Byte ranges of the tokens that differ between two lines, indentation aside
+fn changed_tokens(left: &str, right: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let left_tokens = tokens(left);
+    let right_tokens = tokens(right);
+    let left_texts: Vec<&str> = left_tokens.iter().map(|range| &left[range.clone()]).collect();
+    let right_texts: Vec<&str> = right_tokens.iter().map(|range| &right[range.clone()]).collect();
+
+    let (mut left_changed, mut right_changed) = (Vec::new(), Vec::new());
+    for op in capture_diff_slices(Algorithm::Myers, &left_texts, &right_texts) {
+        let (tag, old, new) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+        if !old.is_empty() {
+            left_changed.push(left_tokens[old.start].start..left_tokens[old.end - 1].end);
+        }
+        if !new.is_empty() {
+            right_changed.push(right_tokens[new.start].start..right_tokens[new.end - 1].end);
+        }
+    }
+    (left_changed, right_changed)
+}
+
+/// Identifiers and numbers, runs of whitespace, and single other characters,
+/// after the indentation
+fn tokens(line: &str) -> Vec<Range<usize>> {
+    let indentation = line.len() - line.trim_start().len();
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' || c == '$' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut previous = None;
+    for (index, c) in line.char_indices().skip_while(|&(index, _)| index < indentation) {
+        let current = class(c);
+        match tokens.last_mut() {
+            Some(token) if previous == Some(current) && current != 2 => {
+                token.end = index + c.len_utf8();
+            }
+            _ => tokens.push(index..index + c.len_utf8()),
+        }
+        previous = Some(current);
+    }
+    tokens
+}
+
+/// The line without its indentation and trailing whitespace
+fn content_range(text: &str) -> Range<usize> {
+    let start = text.len() - text.trim_start().len();
+    start..text.trim_end().len().max(start)
+}
+
+/// `text` cut to `max_chars` visible characters, painted in the line color of
+/// `color` with its `highlights` in the token color; returns the painted text
+/// and its visible width
+fn paint(
+    text: &str,
+    highlights: &[Range<usize>],
+    color: Option<(&str, &str)>,
+    max_chars: usize,
+) -> (String, usize) {
+    let total = text.chars().count();
+    let (limit, ellipsis) =
+        if total > max_chars { (max_chars.saturating_sub(1), true) } else { (total, false) };
+
+    let mut painted = String::new();
+    let mut highlighted = false;
+    if let Some((line_color, _)) = color {
+        painted.push_str(line_color);
+    }
+    for (index, c) in text.char_indices().take(limit) {
+        if let Some((line_color, token_color)) = color {
+            let inside = highlights.iter().any(|range| range.contains(&index));
+            if inside != highlighted {
+                painted.push_str(RESET);
+                painted.push_str(if inside { token_color } else { line_color });
+                highlighted = inside;
+            }
+        }
+        painted.push(c);
+    }
+    if ellipsis {
+        painted.push('…');
+    }
+    if color.is_some() {
+        painted.push_str(RESET);
+    }
+    (painted, limit + usize::from(ellipsis))
+}
+
+fn render_side_by_side(
+    left: DiffSide<'_>,
+    right: DiffSide<'_>,
+    rows: &[Row],
+    width: usize,
+) -> String {
+    // Each column: 4-digit line number, marker, space and text; 3 columns divide them
+    let column = width.saturating_sub(3) / 2;
+    let text_width = column.saturating_sub(6).max(10);
+
+    let cell = |line: &Option<Line>, changed: bool, marker: char, color: (&str, &str)| {
+        let Some(line) = line else {
+            return " ".repeat(text_width + 6);
+        };
+        let (painted, visible) =
+            paint(&line.text, &line.highlights, changed.then_some(color), text_width);
+        let marker = if changed { marker } else { ' ' };
+        format!("{:>4}{} {}{}", line.number, marker, painted, " ".repeat(text_width - visible))
+    };
+
+    let mut output = String::new();
+    let header = |label: &str| {
+        let (painted, visible) = paint(label, &[], None, text_width + 6);
+        format!("{}{}{}{}", HEADER, painted, RESET, " ".repeat(text_width + 6 - visible))
+    };
+    output.push_str(&format!("{} │ {}\n", header(left.label), header(right.label)));
+    for row in rows {
+        output.push_str(&format!(
+            "{} │ {}\n",
+            cell(&row.left, row.changed, '-', (REMOVED, REMOVED_TOKEN)),
+            cell(&row.right, row.changed, '+', (ADDED, ADDED_TOKEN)),
+        ));
+    }
+    output
+}
+
+fn render_unified(left: DiffSide<'_>, right: DiffSide<'_>, rows: &[Row]) -> String {
+    let line = |marker: char, line: &Line, color: Option<(&str, &str)>| {
+        let (painted, _) = paint(&line.text, &line.highlights, color, usize::MAX);
+        format!("{}{:>4} │ {}\n", marker, line.number, painted)
+    };
+
+    let mut output =
+        format!("{HEADER}--- {}{RESET}\n{HEADER}+++ {}{RESET}\n", left.label, right.label);
+    let mut index = 0;
+    while index < rows.len() {
+        if !rows[index].changed {
+            if let Some(context) = &rows[index].left {
+                output.push_str(&line(' ', context, None));
+            }
+            index += 1;
+            continue;
+        }
+        // A run of changed rows: removed lines first, then added lines
+        let end =
+            rows[index..].iter().position(|row| !row.changed).map_or(rows.len(), |n| index + n);
+        for removed in rows[index..end].iter().filter_map(|row| row.left.as_ref()) {
+            output.push_str(&line('-', removed, Some((REMOVED, REMOVED_TOKEN))));
+        }
+        for added in rows[index..end].iter().filter_map(|row| row.right.as_ref()) {
+            output.push_str(&line('+', added, Some((ADDED, ADDED_TOKEN))));
+        }
+        index = end;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: &str = "function sumPrices(items) {\n  let total = 0;\n  for (const item of items) {\n    total += item.price;\n  }\n  return total;\n}";
+    const RIGHT: &str = "  sumWeights(items) {\n    let total = 0;\n    for (const item of items) {\n      total += item.weight;\n    }\n    log(total);\n    return total;\n  }";
+
+    fn strip_ansi(text: &str) -> String {
+        let mut plain = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|&c| c == 'm');
+            } else {
+                plain.push(c);
+            }
+        }
+        plain
+    }
+
+    fn side(label: &'static str, code: &'static str, start_line: u32) -> DiffSide<'static> {
+        DiffSide { label, code, start_line }
+    }
+
+    #[test]
+    fn test_alignment_ignores_indentation() {
+        let rows = align(side("a.ts", LEFT, 1), side("b.ts", RIGHT, 10));
+        let changed: Vec<(Option<usize>, Option<usize>)> = rows
+            .iter()
+            .filter(|row| row.changed)
+            .map(|row| (row.left.as_ref().map(|l| l.number), row.right.as_ref().map(|l| l.number)))
+            .collect();
+        assert_eq!(changed, [(Some(1), Some(10)), (Some(4), Some(13)), (None, Some(15))]);
+
+        // Changed lines pair with their most similar counterpart
+        let wrapped = ["if (item.weight > 0) {", "total += item.weight;", "}"];
+        assert_eq!(
+            pair_changed_lines(&["total += item.price;"], &wrapped),
+            [(None, Some(0)), (Some(0), Some(1)), (None, Some(2))]
+        );
+
+        let (left, right) =
+            changed_tokens("    total += item.price;", "      total += item.weight;");
+        assert_eq!((left.len(), &left[0]), (1, &(18..23)));
+        assert_eq!((right.len(), &right[0]), (1, &(20..26)));
+    }
+
+    #[test]
+    fn test_layouts() {
+        let (left, right) = (side("a.ts:sumPrices", LEFT, 1), side("b.ts:sumWeights", RIGHT, 10));
+
+        let columns =
+            strip_ansi(&render_pair_diff(left, right, DiffLayout::SideBySide { width: 100 }));
+        let lines: Vec<&str> = columns.lines().collect();
+        assert!(lines.iter().all(|line| line.chars().count() == 99), "{columns}");
+        assert!(lines[0].starts_with("a.ts:sumPrices") && lines[0].contains("│ b.ts:sumWeights"));
+        assert!(lines[4].contains("   4-     total += item.price;"), "{columns}");
+        assert!(
+            lines[6].trim_start().starts_with('│') && lines[6].contains("  15+     log(total);")
+        );
+
+        let unified = strip_ansi(&render_pair_diff(left, right, DiffLayout::Unified));
+        assert!(unified.starts_with("--- a.ts:sumPrices\n+++ b.ts:sumWeights\n-   1 │ function"));
+        assert!(unified
+            .contains("-   4 │     total += item.price;\n+  13 │       total += item.weight;\n"));
+        assert!(unified.contains("+  15 │     log(total);\n    6 │   return total;\n"));
+    }
+}
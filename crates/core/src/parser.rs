@@ -4,12 +4,70 @@ use oxc_ast::ast::{
     Expression, FormalParameter, Function, FunctionBody, Program, PropertyKey, Statement,
     VariableDeclaration, VariableDeclarator,
 };
+use oxc_diagnostics::OxcDiagnostic;
 use oxc_parser::Parser;
 use oxc_span::SourceType;
 use std::rc::Rc;
 
+use crate::collection_chains::{
+    collection_call, collection_loop, CollectionCall, CollectionLoop, CollectionStep, ReduceBody,
+};
 use crate::tree::TreeNode;
 
+/// A `Parse errors: ...` message locating each diagnostic by line and column
+pub(crate) fn parse_errors_message(errors: &[OxcDiagnostic], source_text: &str) -> String {
+    let messages: Vec<String> = errors
+        .iter()
+        .map(|error| {
+            let offset =
+                error.labels.as_ref().and_then(|labels| labels.first()).map(|l| l.offset());
+            match offset {
+                Some(offset) => {
+                    let before = &source_text[..offset.min(source_text.len())];
+                    let line = before.matches('\n').count() + 1;
+                    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+                    format!("{} at {}:{}", error.message, line, column)
+                }
+                None => error.message.to_string(),
+            }
+        })
+        .collect();
+    format!("Parse errors: {}", messages.join(", "))
+}
+
+/// How a TypeScript AST is turned into a tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeConversion {
+    /// Keep a `Comment` node per source comment, appended to the root
+    pub include_comments: bool,
+    /// Flatten `.then()` chains and error-first callbacks into the statements
+    /// of their `await` equivalent, and drop `await` itself
+    pub normalize_async: bool,
+    /// Compare loops building a collection and array method calls in one
+    /// form, following the rules of the `collection_chains` module
+    pub normalize_collections: bool,
+    /// Compare TypeScript as the JavaScript it compiles to: drop `as`,
+    /// `satisfies`, `!` and type assertions around expressions, and type-only
+    /// declarations
+    pub strip_types: bool,
+}
+
+/// Ids and settings shared while converting one AST
+pub struct TreeBuilder {
+    next_id: usize,
+    normalize_async: bool,
+    normalize_collections: bool,
+    strip_types: bool,
+}
+
+impl TreeBuilder {
+    fn node(&mut self, label: String, value: String) -> TreeNode {
+        let node = TreeNode::new(label, value, self.next_id);
+        self.next_id += 1;
+        node
+    }
+}
+
 /// Parse TypeScript code and convert to `TreeNode` structure
 ///
 /// # Errors
@@ -18,84 +76,422 @@ use crate::tree::TreeNode;
 pub fn parse_and_convert_to_tree(
     filename: &str,
     source_text: &str,
+) -> Result<Rc<TreeNode>, String> {
+    parse_and_convert_to_tree_with(filename, source_text, TreeConversion::default())
+}
+
+/// Like `parse_and_convert_to_tree`, but appends a `Comment` node per source comment
+/// to the root so comment text takes part in the comparison.
+pub fn parse_and_convert_to_tree_with_comments(
+    filename: &str,
+    source_text: &str,
+) -> Result<Rc<TreeNode>, String> {
+    let conversion = TreeConversion { include_comments: true, ..TreeConversion::default() };
+    parse_and_convert_to_tree_with(filename, source_text, conversion)
+}
+
+/// Parse TypeScript code and convert it to a tree as `conversion` asks
+///
+/// # Errors
+///
+/// Returns an error if parsing fails due to syntax errors
+pub fn parse_and_convert_to_tree_with(
+    filename: &str,
+    source_text: &str,
+    conversion: TreeConversion,
 ) -> Result<Rc<TreeNode>, String> {
     let allocator = Allocator::default();
     let source_type = SourceType::from_path(filename).unwrap_or(SourceType::tsx());
     let ret = Parser::new(&allocator, source_text, source_type).parse();
 
     if !ret.errors.is_empty() {
-        // Create a more readable error message
-        let error_messages: Vec<String> =
-            ret.errors.iter().map(|e| e.message.to_string()).collect();
-        return Err(format!("Parse errors: {}", error_messages.join(", ")));
+        return Err(crate::parser::parse_errors_message(&ret.errors, source_text));
+    }
+
+    let mut ctx = TreeBuilder {
+        next_id: 0,
+        normalize_async: conversion.normalize_async,
+        normalize_collections: conversion.normalize_collections,
+        strip_types: conversion.strip_types,
+    };
+    let tree = program_to_tree_node(&ret.program, &mut ctx);
+    if !conversion.include_comments {
+        return Ok(tree);
+    }
+
+    let mut root = (*tree).clone();
+    for comment in &ret.program.comments {
+        let text = &source_text[comment.span.start as usize..comment.span.end as usize];
+        root.add_child(Rc::new(ctx.node("Comment".to_string(), text.to_string())));
     }
 
-    let mut id_counter = 0;
-    Ok(ast_to_tree_node(&ret.program, &mut id_counter))
+    Ok(Rc::new(root))
+}
+
+/// Parse a function body given on its own (`{ ... }`, or the expression of an
+/// expression-bodied arrow). Bodies are wrapped in the same function so that
+/// `return`, `await` and `yield` parse and all bodies share the same wrapper
+/// nodes.
+pub(crate) fn parse_function_body(
+    filename: &str,
+    body: &str,
+    expression: bool,
+) -> Result<Rc<TreeNode>, String> {
+    let source = if expression {
+        format!("async function* body() {{ return ({body}); }}")
+    } else {
+        format!("async function* body() {body}")
+    };
+    parse_and_convert_to_tree(filename, &source)
 }
 
 pub fn ast_to_tree_node(program: &Program, id_counter: &mut usize) -> Rc<TreeNode> {
-    let mut root = TreeNode::new("Program".to_string(), "Program".to_string(), *id_counter);
-    *id_counter += 1;
+    let mut ctx = TreeBuilder {
+        next_id: *id_counter,
+        normalize_async: false,
+        normalize_collections: false,
+        strip_types: false,
+    };
+    let tree = program_to_tree_node(program, &mut ctx);
+    *id_counter = ctx.next_id;
+    tree
+}
+
+fn program_to_tree_node(program: &Program, ctx: &mut TreeBuilder) -> Rc<TreeNode> {
+    let mut root = ctx.node("Program".to_string(), "Program".to_string());
+
+    root.children.extend(statements_to_tree_nodes(&program.body, ctx));
 
-    for stmt in &program.body {
-        if let Some(child) = statement_to_tree_node(stmt, id_counter) {
-            root.add_child(child);
+    Rc::new(root)
+}
+
+/// Nodes of a statement list, with `normalize_collections` folding loops that
+/// build a collection into the equivalent method call and `strip_types`
+/// leaving out type-only declarations
+fn statements_to_tree_nodes(statements: &[Statement], ctx: &mut TreeBuilder) -> Vec<Rc<TreeNode>> {
+    let mut nodes = Vec::new();
+    let mut rest = statements;
+    while let [stmt, tail @ ..] = rest {
+        if ctx.normalize_collections {
+            if let Some(folded) = collection_loop(rest) {
+                nodes.push(collection_loop_to_tree_node(&folded, ctx));
+                rest = &rest[folded.statement_count()..];
+                continue;
+            }
         }
+        if !(ctx.strip_types && is_type_only(stmt)) {
+            nodes.extend(statement_to_tree_nodes(stmt, ctx));
+        }
+        rest = tail;
+    }
+    nodes
+}
+
+/// Declarations with no JavaScript output
+fn is_type_only(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::TSTypeAliasDeclaration(_) | Statement::TSInterfaceDeclaration(_) => true,
+        Statement::ExportNamedDeclaration(export) => {
+            export.export_kind.is_type()
+                || matches!(
+                    export.declaration,
+                    Some(
+                        Declaration::TSTypeAliasDeclaration(_)
+                            | Declaration::TSInterfaceDeclaration(_)
+                    )
+                )
+        }
+        Statement::ImportDeclaration(import) => import.import_kind.is_type(),
+        _ => false,
     }
+}
 
-    Rc::new(root)
+fn collection_loop_to_tree_node(folded: &CollectionLoop, ctx: &mut TreeBuilder) -> Rc<TreeNode> {
+    let call = collection_call_to_tree_node(&folded.call, ctx);
+    let result =
+        if folded.returned { ChainResult::Return } else { ChainResult::Declare(folded.result) };
+    finish_chain(call, result, ctx)
+}
+
+/// A `CollectionCall` node labeled with the method: the source, the callback
+/// parameters, the callback value and, for `reduce`, the initial value
+fn collection_call_to_tree_node(call: &CollectionCall, ctx: &mut TreeBuilder) -> Rc<TreeNode> {
+    let mut node = ctx.node(call.step.method().to_string(), "CollectionCall".to_string());
+    node.children.extend(expression_to_tree_node(call.source, ctx));
+    let parameter = |name: &str, ctx: &mut TreeBuilder| {
+        Rc::new(ctx.node(name.to_string(), "Parameter".to_string()))
+    };
+    match &call.step {
+        CollectionStep::Map { item, value: expr } | CollectionStep::Filter { item, test: expr } => {
+            node.add_child(parameter(item, ctx));
+            node.children.extend(expression_to_tree_node(expr, ctx));
+        }
+        CollectionStep::Reduce { accumulator, item, body, initial } => {
+            node.add_child(parameter(accumulator, ctx));
+            node.add_child(parameter(item, ctx));
+            match body {
+                ReduceBody::Expression(expr) => {
+                    node.children.extend(expression_to_tree_node(expr, ctx));
+                }
+                ReduceBody::Combine(operator, value) => {
+                    let mut combined =
+                        ctx.node(format!("{:?}", operator), "BinaryExpression".to_string());
+                    combined.add_child(Rc::new(
+                        ctx.node(accumulator.to_string(), "Identifier".to_string()),
+                    ));
+                    combined.children.extend(expression_to_tree_node(value, ctx));
+                    node.add_child(Rc::new(combined));
+                }
+            }
+            node.children.extend(expression_to_tree_node(initial, ctx));
+        }
+    }
+    Rc::new(node)
+}
+
+/// Nodes of a statement: one, or with `normalize_async` the statements of
+/// the `await` equivalent of a `.then()` chain or error-first callback
+fn statement_to_tree_nodes(stmt: &Statement, ctx: &mut TreeBuilder) -> Vec<Rc<TreeNode>> {
+    if ctx.normalize_async {
+        if let Some(nodes) = async_statement_to_tree_nodes(stmt, ctx) {
+            return nodes;
+        }
+    }
+    statement_to_tree_node(stmt, ctx).into_iter().collect()
+}
+
+/// What becomes of the value a flattened `.then()` chain resolves to
+#[derive(Clone, Copy)]
+enum ChainResult<'s> {
+    Return,
+    Discard,
+    Declare(&'s str),
+}
+
+/// The parameters and body of a callback function
+pub(crate) struct Callback<'a, 's> {
+    pub params: &'s [FormalParameter<'a>],
+    pub body: &'s FunctionBody<'a>,
+    pub expression: bool,
+}
+
+pub(crate) fn callback<'a, 's>(expr: &'s Expression<'a>) -> Option<Callback<'a, 's>> {
+    match expr {
+        Expression::ArrowFunctionExpression(arrow) => Some(Callback {
+            params: &arrow.params.items,
+            body: &arrow.body,
+            expression: arrow.expression,
+        }),
+        Expression::FunctionExpression(func) => Some(Callback {
+            params: &func.params.items,
+            body: func.body.as_ref()?,
+            expression: false,
+        }),
+        _ => None,
+    }
 }
 
-fn statement_to_tree_node(stmt: &Statement, id_counter: &mut usize) -> Option<Rc<TreeNode>> {
+pub(crate) fn parameter_name<'s>(param: &'s FormalParameter) -> Option<&'s str> {
+    match &param.pattern {
+        BindingPattern::BindingIdentifier(ident) => Some(ident.name.as_str()),
+        _ => None,
+    }
+}
+
+/// `promise.then(callback)` split into the promise and the callback
+fn then_call<'a, 's>(expr: &'s Expression<'a>) -> Option<(&'s Expression<'a>, Callback<'a, 's>)> {
+    let Expression::CallExpression(call) = expr.without_parentheses() else {
+        return None;
+    };
+    let Expression::StaticMemberExpression(member) = &call.callee else {
+        return None;
+    };
+    if member.property.name != "then" {
+        return None;
+    }
+    let callback = callback(call.arguments.first()?.as_expression()?)?;
+    Some((&member.object, callback))
+}
+
+fn await_argument<'a, 's>(expr: &'s Expression<'a>) -> &'s Expression<'a> {
+    match expr.without_parentheses() {
+        Expression::AwaitExpression(await_expr) => &await_expr.argument,
+        other => other,
+    }
+}
+
+fn async_statement_to_tree_nodes(
+    stmt: &Statement,
+    ctx: &mut TreeBuilder,
+) -> Option<Vec<Rc<TreeNode>>> {
     match stmt {
-        Statement::FunctionDeclaration(func) => {
-            function_declaration_to_tree_node(func, id_counter, "Function")
+        Statement::ReturnStatement(ret_stmt) => then_chain_to_tree_nodes(
+            await_argument(ret_stmt.argument.as_ref()?),
+            ChainResult::Return,
+            ctx,
+        ),
+        Statement::ExpressionStatement(expr_stmt) => {
+            let expr = await_argument(&expr_stmt.expression);
+            then_chain_to_tree_nodes(expr, ChainResult::Discard, ctx)
+                .or_else(|| error_first_callback_to_tree_nodes(expr, ctx))
+        }
+        Statement::VariableDeclaration(var_decl) => {
+            let [declarator] = var_decl.declarations.as_slice() else {
+                return None;
+            };
+            let name = match &declarator.id {
+                BindingPattern::BindingIdentifier(ident) => ident.name.as_str(),
+                _ => return None,
+            };
+            then_chain_to_tree_nodes(
+                await_argument(declarator.init.as_ref()?),
+                ChainResult::Declare(name),
+                ctx,
+            )
+        }
+        _ => None,
+    }
+}
+
+/// `p.then((v) => body)` as `const v = await p;` followed by the body, with
+/// the value of an expression body going to `result`
+fn then_chain_to_tree_nodes(
+    expr: &Expression,
+    result: ChainResult,
+    ctx: &mut TreeBuilder,
+) -> Option<Vec<Rc<TreeNode>>> {
+    let (promise, callback) = then_call(expr)?;
+    let resolved = callback.params.first().and_then(parameter_name);
+
+    let declared = resolved.map_or(ChainResult::Discard, ChainResult::Declare);
+    let mut nodes = then_chain_to_tree_nodes(promise, declared, ctx).unwrap_or_else(|| {
+        let value = expression_to_tree_node(await_argument(promise), ctx);
+        value.map(|value| finish_chain(value, declared, ctx)).into_iter().collect()
+    });
+
+    if callback.expression {
+        if let Some(Statement::ExpressionStatement(expr_stmt)) = callback.body.statements.first() {
+            let value = await_argument(&expr_stmt.expression);
+            match then_chain_to_tree_nodes(value, result, ctx) {
+                Some(rest) => nodes.extend(rest),
+                None => {
+                    if let Some(value) = expression_to_tree_node(value, ctx) {
+                        nodes.push(finish_chain(value, result, ctx));
+                    }
+                }
+            }
+        }
+    } else {
+        nodes.extend(statements_to_tree_nodes(&callback.body.statements, ctx));
+    }
+    Some(nodes)
+}
+
+/// The statement handing `value` to `result`
+fn finish_chain(value: Rc<TreeNode>, result: ChainResult, ctx: &mut TreeBuilder) -> Rc<TreeNode> {
+    match result {
+        ChainResult::Discard => value,
+        ChainResult::Return => {
+            let mut node = ctx.node("ReturnStatement".to_string(), "ReturnStatement".to_string());
+            node.add_child(value);
+            Rc::new(node)
+        }
+        ChainResult::Declare(name) => {
+            let mut node =
+                ctx.node("VariableDeclaration".to_string(), "VariableDeclaration".to_string());
+            let mut declarator = ctx.node(name.to_string(), "VariableDeclarator".to_string());
+            declarator.add_child(value);
+            node.add_child(Rc::new(declarator));
+            Rc::new(node)
+        }
+    }
+}
+
+/// `read(path, (err, data) => { body })` as `const data = await read(path);`
+/// followed by the body
+fn error_first_callback_to_tree_nodes(
+    expr: &Expression,
+    ctx: &mut TreeBuilder,
+) -> Option<Vec<Rc<TreeNode>>> {
+    let Expression::CallExpression(call) = expr else {
+        return None;
+    };
+    let callback = callback(call.arguments.last()?.as_expression()?)?;
+    let [error, rest @ ..] = callback.params else {
+        return None;
+    };
+    if !matches!(parameter_name(error), Some("err" | "error")) {
+        return None;
+    }
+
+    let mut call_node = ctx.node("CallExpression".to_string(), "CallExpression".to_string());
+    if let Some(callee_node) = expression_to_tree_node(&call.callee, ctx) {
+        call_node.add_child(callee_node);
+    }
+    for arg in &call.arguments[..call.arguments.len() - 1] {
+        if let Some(arg_node) =
+            arg.as_expression().and_then(|expr| expression_to_tree_node(expr, ctx))
+        {
+            call_node.add_child(arg_node);
         }
-        Statement::ClassDeclaration(class) => {
-            class_declaration_to_tree_node(class, id_counter, "Class")
+    }
+
+    let declared =
+        rest.last().and_then(parameter_name).map_or(ChainResult::Discard, ChainResult::Declare);
+    let mut nodes = vec![finish_chain(Rc::new(call_node), declared, ctx)];
+    if callback.expression {
+        if let Some(stmt) = callback.body.statements.first() {
+            nodes.extend(statement_to_tree_nodes(stmt, ctx));
+        }
+    } else {
+        nodes.extend(statements_to_tree_nodes(&callback.body.statements, ctx));
+    }
+    Some(nodes)
+}
+
+fn statement_to_tree_node(stmt: &Statement, ctx: &mut TreeBuilder) -> Option<Rc<TreeNode>> {
+    match stmt {
+        Statement::FunctionDeclaration(func) => {
+            function_declaration_to_tree_node(func, ctx, "Function")
         }
+        Statement::ClassDeclaration(class) => class_declaration_to_tree_node(class, ctx, "Class"),
         Statement::VariableDeclaration(var_decl) => {
-            variable_declaration_to_tree_node(var_decl, id_counter)
+            variable_declaration_to_tree_node(var_decl, ctx)
         }
         Statement::ExpressionStatement(expr_stmt) => {
-            expression_to_tree_node(&expr_stmt.expression, id_counter)
+            expression_to_tree_node(&expr_stmt.expression, ctx)
         }
-        Statement::BlockStatement(block) => block_statement_to_tree_node(block, id_counter),
+        Statement::BlockStatement(block) => block_statement_to_tree_node(block, ctx),
         Statement::ExportNamedDeclaration(export) => {
             if let Some(decl) = &export.declaration {
-                declaration_to_tree_node(decl, id_counter)
+                declaration_to_tree_node(decl, ctx)
             } else {
-                let node = TreeNode::new(
+                let node = ctx.node(
                     "ExportNamedDeclaration".to_string(),
                     "ExportNamedDeclaration".to_string(),
-                    *id_counter,
                 );
-                *id_counter += 1;
                 Some(Rc::new(node))
             }
         }
         Statement::ExportDefaultDeclaration(export) => {
-            export_default_declaration_to_tree_node(&export.declaration, id_counter)
+            export_default_declaration_to_tree_node(&export.declaration, ctx)
         }
         Statement::IfStatement(if_stmt) => {
-            let mut node =
-                TreeNode::new("IfStatement".to_string(), "IfStatement".to_string(), *id_counter);
-            *id_counter += 1;
+            let mut node = ctx.node("IfStatement".to_string(), "IfStatement".to_string());
 
             // Add test expression
-            if let Some(test_node) = expression_to_tree_node(&if_stmt.test, id_counter) {
+            if let Some(test_node) = expression_to_tree_node(&if_stmt.test, ctx) {
                 node.add_child(test_node);
             }
 
             // Add consequent
-            if let Some(cons_node) = statement_to_tree_node(&if_stmt.consequent, id_counter) {
+            if let Some(cons_node) = statement_to_tree_node(&if_stmt.consequent, ctx) {
                 node.add_child(cons_node);
             }
 
             // Add alternate if exists
             if let Some(alt) = &if_stmt.alternate {
-                if let Some(alt_node) = statement_to_tree_node(alt, id_counter) {
+                if let Some(alt_node) = statement_to_tree_node(alt, ctx) {
                     node.add_child(alt_node);
                 }
             }
@@ -103,15 +499,10 @@ fn statement_to_tree_node(stmt: &Statement, id_counter: &mut usize) -> Option<Rc
             Some(Rc::new(node))
         }
         Statement::ReturnStatement(ret_stmt) => {
-            let mut node = TreeNode::new(
-                "ReturnStatement".to_string(),
-                "ReturnStatement".to_string(),
-                *id_counter,
-            );
-            *id_counter += 1;
+            let mut node = ctx.node("ReturnStatement".to_string(), "ReturnStatement".to_string());
 
             if let Some(arg) = &ret_stmt.argument {
-                if let Some(arg_node) = expression_to_tree_node(arg, id_counter) {
+                if let Some(arg_node) = expression_to_tree_node(arg, ctx) {
                     node.add_child(arg_node);
                 }
             }
@@ -120,28 +511,23 @@ fn statement_to_tree_node(stmt: &Statement, id_counter: &mut usize) -> Option<Rc
         }
         _ => {
             // For other statement types, create a generic node
-            let node = TreeNode::new("Statement".to_string(), "Statement".to_string(), *id_counter);
-            *id_counter += 1;
+            let node = ctx.node("Statement".to_string(), "Statement".to_string());
             Some(Rc::new(node))
         }
     }
 }
 
-fn declaration_to_tree_node(decl: &Declaration, id_counter: &mut usize) -> Option<Rc<TreeNode>> {
+fn declaration_to_tree_node(decl: &Declaration, ctx: &mut TreeBuilder) -> Option<Rc<TreeNode>> {
     match decl {
         Declaration::FunctionDeclaration(func) => {
-            function_declaration_to_tree_node(func, id_counter, "Function")
-        }
-        Declaration::ClassDeclaration(class) => {
-            class_declaration_to_tree_node(class, id_counter, "Class")
+            function_declaration_to_tree_node(func, ctx, "Function")
         }
+        Declaration::ClassDeclaration(class) => class_declaration_to_tree_node(class, ctx, "Class"),
         Declaration::VariableDeclaration(var_decl) => {
-            variable_declaration_to_tree_node(var_decl, id_counter)
+            variable_declaration_to_tree_node(var_decl, ctx)
         }
         _ => {
-            let node =
-                TreeNode::new("Declaration".to_string(), "Declaration".to_string(), *id_counter);
-            *id_counter += 1;
+            let node = ctx.node("Declaration".to_string(), "Declaration".to_string());
             Some(Rc::new(node))
         }
     }
@@ -149,22 +535,20 @@ fn declaration_to_tree_node(decl: &Declaration, id_counter: &mut usize) -> Optio
 
 fn export_default_declaration_to_tree_node(
     decl: &ExportDefaultDeclarationKind,
-    id_counter: &mut usize,
+    ctx: &mut TreeBuilder,
 ) -> Option<Rc<TreeNode>> {
     match decl {
         ExportDefaultDeclarationKind::FunctionDeclaration(func) => {
-            function_declaration_to_tree_node(func, id_counter, "DefaultFunction")
+            function_declaration_to_tree_node(func, ctx, "DefaultFunction")
         }
         ExportDefaultDeclarationKind::ClassDeclaration(class) => {
-            class_declaration_to_tree_node(class, id_counter, "DefaultClass")
+            class_declaration_to_tree_node(class, ctx, "DefaultClass")
         }
         _ => {
-            let node = TreeNode::new(
+            let node = ctx.node(
                 "ExportDefaultDeclaration".to_string(),
                 "ExportDefaultDeclaration".to_string(),
-                *id_counter,
             );
-            *id_counter += 1;
             Some(Rc::new(node))
         }
     }
@@ -172,21 +556,20 @@ fn export_default_declaration_to_tree_node(
 
 fn function_declaration_to_tree_node(
     func: &Function,
-    id_counter: &mut usize,
+    ctx: &mut TreeBuilder,
     default_label: &str,
 ) -> Option<Rc<TreeNode>> {
     let label = func.id.as_ref().map_or(default_label, |id| id.name.as_str()).to_string();
-    let mut node = TreeNode::new(label, "FunctionDeclaration".to_string(), *id_counter);
-    *id_counter += 1;
+    let mut node = ctx.node(label, "FunctionDeclaration".to_string());
 
     for param in &func.params.items {
-        if let Some(param_node) = formal_parameter_to_tree_node(param, id_counter) {
+        if let Some(param_node) = formal_parameter_to_tree_node(param, ctx) {
             node.add_child(param_node);
         }
     }
 
     if let Some(body) = &func.body {
-        if let Some(body_node) = function_body_to_tree_node(body, id_counter) {
+        if let Some(body_node) = function_body_to_tree_node(body, ctx) {
             node.add_child(body_node);
         }
     }
@@ -196,15 +579,14 @@ fn function_declaration_to_tree_node(
 
 fn class_declaration_to_tree_node(
     class: &Class,
-    id_counter: &mut usize,
+    ctx: &mut TreeBuilder,
     default_label: &str,
 ) -> Option<Rc<TreeNode>> {
     let label = class.id.as_ref().map_or(default_label, |id| id.name.as_str()).to_string();
-    let mut node = TreeNode::new(label, "ClassDeclaration".to_string(), *id_counter);
-    *id_counter += 1;
+    let mut node = ctx.node(label, "ClassDeclaration".to_string());
 
     for element in &class.body.body {
-        if let Some(elem_node) = class_element_to_tree_node(element, id_counter) {
+        if let Some(elem_node) = class_element_to_tree_node(element, ctx) {
             node.add_child(elem_node);
         }
     }
@@ -214,17 +596,12 @@ fn class_declaration_to_tree_node(
 
 fn variable_declaration_to_tree_node(
     var_decl: &VariableDeclaration,
-    id_counter: &mut usize,
+    ctx: &mut TreeBuilder,
 ) -> Option<Rc<TreeNode>> {
-    let mut node = TreeNode::new(
-        "VariableDeclaration".to_string(),
-        "VariableDeclaration".to_string(),
-        *id_counter,
-    );
-    *id_counter += 1;
+    let mut node = ctx.node("VariableDeclaration".to_string(), "VariableDeclaration".to_string());
 
     for decl in &var_decl.declarations {
-        if let Some(decl_node) = variable_declarator_to_tree_node(decl, id_counter) {
+        if let Some(decl_node) = variable_declarator_to_tree_node(decl, ctx) {
             node.add_child(decl_node);
         }
     }
@@ -232,68 +609,74 @@ fn variable_declaration_to_tree_node(
     Some(Rc::new(node))
 }
 
-fn expression_to_tree_node(expr: &Expression, id_counter: &mut usize) -> Option<Rc<TreeNode>> {
+fn expression_to_tree_node(expr: &Expression, ctx: &mut TreeBuilder) -> Option<Rc<TreeNode>> {
     match expr {
+        Expression::AwaitExpression(await_expr) if ctx.normalize_async => {
+            expression_to_tree_node(&await_expr.argument, ctx)
+        }
+        Expression::TSAsExpression(as_expr) if ctx.strip_types => {
+            expression_to_tree_node(&as_expr.expression, ctx)
+        }
+        Expression::TSSatisfiesExpression(satisfies) if ctx.strip_types => {
+            expression_to_tree_node(&satisfies.expression, ctx)
+        }
+        Expression::TSNonNullExpression(non_null) if ctx.strip_types => {
+            expression_to_tree_node(&non_null.expression, ctx)
+        }
+        Expression::TSTypeAssertion(assertion) if ctx.strip_types => {
+            expression_to_tree_node(&assertion.expression, ctx)
+        }
+        Expression::TSInstantiationExpression(instantiation) if ctx.strip_types => {
+            expression_to_tree_node(&instantiation.expression, ctx)
+        }
         Expression::Identifier(ident) => {
-            let node = TreeNode::new(
-                ident.name.as_str().to_string(),
-                "Identifier".to_string(),
-                *id_counter,
-            );
-            *id_counter += 1;
+            let node = ctx.node(ident.name.as_str().to_string(), "Identifier".to_string());
             Some(Rc::new(node))
         }
         Expression::StringLiteral(str_lit) => {
             let label = format!("\"{}\"", str_lit.value.as_str());
-            let node = TreeNode::new(label, "StringLiteral".to_string(), *id_counter);
-            *id_counter += 1;
+            let node = ctx.node(label, "StringLiteral".to_string());
             Some(Rc::new(node))
         }
         Expression::NumericLiteral(num_lit) => {
             let label = num_lit.value.to_string();
-            let node = TreeNode::new(label, "NumericLiteral".to_string(), *id_counter);
-            *id_counter += 1;
+            let node = ctx.node(label, "NumericLiteral".to_string());
             Some(Rc::new(node))
         }
         Expression::BooleanLiteral(bool_lit) => {
             let label = bool_lit.value.to_string();
-            let node = TreeNode::new(label, "BooleanLiteral".to_string(), *id_counter);
-            *id_counter += 1;
+            let node = ctx.node(label, "BooleanLiteral".to_string());
             Some(Rc::new(node))
         }
         Expression::BinaryExpression(bin_expr) => {
-            let mut node = TreeNode::new(
-                format!("{:?}", bin_expr.operator),
-                "BinaryExpression".to_string(),
-                *id_counter,
-            );
-            *id_counter += 1;
+            let mut node =
+                ctx.node(format!("{:?}", bin_expr.operator), "BinaryExpression".to_string());
 
-            if let Some(left_node) = expression_to_tree_node(&bin_expr.left, id_counter) {
+            if let Some(left_node) = expression_to_tree_node(&bin_expr.left, ctx) {
                 node.add_child(left_node);
             }
 
-            if let Some(right_node) = expression_to_tree_node(&bin_expr.right, id_counter) {
+            if let Some(right_node) = expression_to_tree_node(&bin_expr.right, ctx) {
                 node.add_child(right_node);
             }
 
             Some(Rc::new(node))
         }
         Expression::CallExpression(call_expr) => {
-            let mut node = TreeNode::new(
-                "CallExpression".to_string(),
-                "CallExpression".to_string(),
-                *id_counter,
-            );
-            *id_counter += 1;
+            if ctx.normalize_collections {
+                if let Some(call) = collection_call(call_expr) {
+                    return Some(collection_call_to_tree_node(&call, ctx));
+                }
+            }
+            let mut node = ctx.node("CallExpression".to_string(), "CallExpression".to_string());
 
-            if let Some(callee_node) = expression_to_tree_node(&call_expr.callee, id_counter) {
+            if let Some(callee_node) = expression_to_tree_node(&call_expr.callee, ctx) {
                 node.add_child(callee_node);
             }
 
             for arg in &call_expr.arguments {
                 if let Some(expr) = arg.as_expression() {
-                    if let Some(arg_node) = expression_to_tree_node(expr, id_counter) {
+                    if let Some(arg_node) = expression_to_tree_node(expr, ctx) {
                         node.add_child(arg_node);
                     }
                 }
@@ -302,16 +685,12 @@ fn expression_to_tree_node(expr: &Expression, id_counter: &mut usize) -> Option<
             Some(Rc::new(node))
         }
         Expression::ArrowFunctionExpression(arrow) => {
-            let mut node = TreeNode::new(
-                "ArrowFunction".to_string(),
-                "ArrowFunctionExpression".to_string(),
-                *id_counter,
-            );
-            *id_counter += 1;
+            let mut node =
+                ctx.node("ArrowFunction".to_string(), "ArrowFunctionExpression".to_string());
 
             // Add parameters
             for param in &arrow.params.items {
-                if let Some(param_node) = formal_parameter_to_tree_node(param, id_counter) {
+                if let Some(param_node) = formal_parameter_to_tree_node(param, ctx) {
                     node.add_child(param_node);
                 }
             }
@@ -322,15 +701,13 @@ fn expression_to_tree_node(expr: &Expression, id_counter: &mut usize) -> Option<
                 if let Some(Statement::ExpressionStatement(expr_stmt)) =
                     arrow.body.statements.first()
                 {
-                    if let Some(expr_node) =
-                        expression_to_tree_node(&expr_stmt.expression, id_counter)
-                    {
+                    if let Some(expr_node) = expression_to_tree_node(&expr_stmt.expression, ctx) {
                         node.add_child(expr_node);
                     }
                 }
             } else {
                 // Block body (e.g., => { return x + 1; })
-                if let Some(body_node) = function_body_to_tree_node(&arrow.body, id_counter) {
+                if let Some(body_node) = function_body_to_tree_node(&arrow.body, ctx) {
                     node.add_child(body_node);
                 }
             }
@@ -339,9 +716,7 @@ fn expression_to_tree_node(expr: &Expression, id_counter: &mut usize) -> Option<
         }
         _ => {
             // For other expression types, create a generic node
-            let node =
-                TreeNode::new("Expression".to_string(), "Expression".to_string(), *id_counter);
-            *id_counter += 1;
+            let node = ctx.node("Expression".to_string(), "Expression".to_string());
             Some(Rc::new(node))
         }
     }
@@ -349,61 +724,45 @@ fn expression_to_tree_node(expr: &Expression, id_counter: &mut usize) -> Option<
 
 fn formal_parameter_to_tree_node(
     param: &FormalParameter,
-    id_counter: &mut usize,
+    ctx: &mut TreeBuilder,
 ) -> Option<Rc<TreeNode>> {
     let label = match &param.pattern {
         BindingPattern::BindingIdentifier(ident) => ident.name.as_str().to_string(),
         _ => "Parameter".to_string(),
     };
-    let node = TreeNode::new(label, "Parameter".to_string(), *id_counter);
-    *id_counter += 1;
+    let node = ctx.node(label, "Parameter".to_string());
     Some(Rc::new(node))
 }
 
-fn function_body_to_tree_node(body: &FunctionBody, id_counter: &mut usize) -> Option<Rc<TreeNode>> {
-    let mut node =
-        TreeNode::new("BlockStatement".to_string(), "BlockStatement".to_string(), *id_counter);
-    *id_counter += 1;
-
-    for stmt in &body.statements {
-        if let Some(stmt_node) = statement_to_tree_node(stmt, id_counter) {
-            node.add_child(stmt_node);
-        }
-    }
+fn function_body_to_tree_node(body: &FunctionBody, ctx: &mut TreeBuilder) -> Option<Rc<TreeNode>> {
+    let mut node = ctx.node("BlockStatement".to_string(), "BlockStatement".to_string());
+    node.children.extend(statements_to_tree_nodes(&body.statements, ctx));
 
     Some(Rc::new(node))
 }
 
 fn block_statement_to_tree_node(
     block: &BlockStatement,
-    id_counter: &mut usize,
+    ctx: &mut TreeBuilder,
 ) -> Option<Rc<TreeNode>> {
-    let mut node =
-        TreeNode::new("BlockStatement".to_string(), "BlockStatement".to_string(), *id_counter);
-    *id_counter += 1;
-
-    for stmt in &block.body {
-        if let Some(stmt_node) = statement_to_tree_node(stmt, id_counter) {
-            node.add_child(stmt_node);
-        }
-    }
+    let mut node = ctx.node("BlockStatement".to_string(), "BlockStatement".to_string());
+    node.children.extend(statements_to_tree_nodes(&block.body, ctx));
 
     Some(Rc::new(node))
 }
 
 fn variable_declarator_to_tree_node(
     decl: &VariableDeclarator,
-    id_counter: &mut usize,
+    ctx: &mut TreeBuilder,
 ) -> Option<Rc<TreeNode>> {
     let label = match &decl.id {
         BindingPattern::BindingIdentifier(ident) => ident.name.as_str().to_string(),
         _ => "Variable".to_string(),
     };
-    let mut node = TreeNode::new(label, "VariableDeclarator".to_string(), *id_counter);
-    *id_counter += 1;
+    let mut node = ctx.node(label, "VariableDeclarator".to_string());
 
     if let Some(init) = &decl.init {
-        if let Some(init_node) = expression_to_tree_node(init, id_counter) {
+        if let Some(init_node) = expression_to_tree_node(init, ctx) {
             node.add_child(init_node);
         }
     }
@@ -413,7 +772,7 @@ fn variable_declarator_to_tree_node(
 
 fn class_element_to_tree_node(
     element: &ClassElement,
-    id_counter: &mut usize,
+    ctx: &mut TreeBuilder,
 ) -> Option<Rc<TreeNode>> {
     match element {
         ClassElement::MethodDefinition(method) => {
@@ -422,12 +781,11 @@ fn class_element_to_tree_node(
                 PropertyKey::PrivateIdentifier(ident) => format!("#{}", ident.name.as_str()),
                 _ => "Method".to_string(),
             };
-            let mut node = TreeNode::new(label, "MethodDefinition".to_string(), *id_counter);
-            *id_counter += 1;
+            let mut node = ctx.node(label, "MethodDefinition".to_string());
 
             // Add method body
             if let Some(body) = &method.value.body {
-                if let Some(body_node) = function_body_to_tree_node(body, id_counter) {
+                if let Some(body_node) = function_body_to_tree_node(body, ctx) {
                     node.add_child(body_node);
                 }
             }
@@ -440,8 +798,7 @@ fn class_element_to_tree_node(
                 PropertyKey::PrivateIdentifier(ident) => format!("#{}", ident.name.as_str()),
                 _ => "Property".to_string(),
             };
-            let node = TreeNode::new(label, "PropertyDefinition".to_string(), *id_counter);
-            *id_counter += 1;
+            let node = ctx.node(label, "PropertyDefinition".to_string());
             Some(Rc::new(node))
         }
         _ => None,
@@ -0,0 +1,217 @@
+//! Markdown summary of a run for a pull-request comment.
+//!
+//! The comment lists the duplicates a pull request introduces, each linked
+//! to both locations at the analyzed commit, with totals and the files
+//! holding the most findings. Given the results of the base branch, only
+//! findings missing there count as new (see [`crate::report_diff`]);
+//! otherwise every finding does. The comment starts with
+//! [`PR_COMMENT_MARKER`] so CI can find and update its earlier comment
+//! instead of adding another one.
+
+use crate::report_diff::{diff_reports, ReportFinding};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Hidden first line of every comment
+pub const PR_COMMENT_MARKER: &str = "<!-- similarity-report -->";
+
+/// Most new duplicates listed before the rest are only counted
+const MAX_LISTED: usize = 20;
+/// Files listed among the worst offenders
+const MAX_OFFENDERS: usize = 5;
+
+/// Links to files of a repository at a commit, branch or tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobLinks {
+    /// URL of the directory holding the files, e.g. `https://github.com/o/r/blob/<sha>`
+    base: String,
+}
+
+impl BlobLinks {
+    /// Links into `repo_url` at `git_ref`, in the URL layout of GitLab for
+    /// repositories hosted there and of GitHub, Gitea and Forgejo otherwise
+    pub fn new(repo_url: &str, git_ref: &str) -> Self {
+        let repo_url = repo_url.trim_end_matches('/').trim_end_matches(".git");
+        let blob = if repo_url.contains("gitlab") { "-/blob" } else { "blob" };
+        Self { base: format!("{}/{}/{}", repo_url, blob, git_ref) }
+    }
+
+    /// Links from the variables of GitHub Actions or GitLab CI, if set
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Self::from_vars(var)
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        if let (Some(server), Some(repository)) =
+            (var("GITHUB_SERVER_URL"), var("GITHUB_REPOSITORY"))
+        {
+            let repo_url = format!("{}/{}", server, repository);
+            return Some(Self::new(&repo_url, &var("GITHUB_SHA")?));
+        }
+        Some(Self::new(&var("CI_PROJECT_URL")?, &var("CI_COMMIT_SHA")?))
+    }
+
+    pub fn url(&self, file: &str, line: usize) -> String {
+        format!("{}/{}#L{}", self.base, file.trim_start_matches("./"), line)
+    }
+}
+
+/// Render the comment for the `findings` of a run, compared with the findings
+/// of its base branch when given
+pub fn render_pr_comment(
+    findings: Vec<ReportFinding>,
+    base: Option<Vec<ReportFinding>>,
+    links: Option<&BlobLinks>,
+) -> String {
+    let total = findings.len();
+    let offenders = worst_offenders(&findings);
+    let (mut introduced, resolved) = match base {
+        Some(base) => {
+            let diff = diff_reports(base, findings);
+            (diff.introduced, Some(diff.resolved.len()))
+        }
+        None => (findings, None),
+    };
+    introduced.sort_by(|a, b| b.similarity.unwrap_or(0.0).total_cmp(&a.similarity.unwrap_or(0.0)));
+
+    let mut comment = format!("{}\n### Code similarity\n\n", PR_COMMENT_MARKER);
+    let noun = |count: usize| if count == 1 { "duplicate" } else { "duplicates" };
+    let mut totals = match resolved {
+        Some(resolved) => format!(
+            "**+{} new {}**, -{} resolved",
+            introduced.len(),
+            noun(introduced.len()),
+            resolved
+        ),
+        None => format!("**{} {}**", introduced.len(), noun(introduced.len())),
+    };
+    totals.push_str(&format!(" · {} in total\n", total));
+    comment.push_str(&totals);
+    if introduced.is_empty() {
+        comment.push_str("\nNo new duplicates.\n");
+        return comment;
+    }
+
+    comment.push_str("\n| Similarity | Location | Similar to |\n|---:|---|---|\n");
+    for finding in introduced.iter().take(MAX_LISTED) {
+        let related = finding.value.get("related").unwrap_or(&Value::Null);
+        let related_file = related.get("file").and_then(Value::as_str).unwrap_or(&finding.file);
+        let related_line = related.get("line").and_then(Value::as_u64).unwrap_or(0) as usize;
+        comment.push_str(&format!(
+            "| {} | {} | {} |\n",
+            finding.similarity.map_or_else(|| "-".to_string(), |s| format!("{:.0}%", s * 100.0)),
+            location(&finding.file, finding.line, &finding.value, links),
+            location(related_file, related_line, related, links),
+        ));
+    }
+    if introduced.len() > MAX_LISTED {
+        comment.push_str(&format!("\n…and {} more.\n", introduced.len() - MAX_LISTED));
+    }
+
+    if !offenders.is_empty() {
+        comment.push_str(
+            "\n<details><summary>Worst offenders</summary>\n\n| File | Findings | Highest |\n|---|---:|---:|\n",
+        );
+        for (file, count, highest) in offenders {
+            comment.push_str(&format!("| `{}` | {} | {:.0}% |\n", file, count, highest * 100.0));
+        }
+        comment.push_str("\n</details>\n");
+    }
+    comment
+}
+
+/// `file:line`, linked when links are given, followed by the symbol if any
+fn location(file: &str, line: usize, finding: &Value, links: Option<&BlobLinks>) -> String {
+    let file = file.trim_start_matches("./");
+    let label = format!("{}:{}", file, line);
+    let mut cell = match links {
+        Some(links) => format!("[{}]({})", label, links.url(file, line)),
+        None => format!("`{}`", label),
+    };
+    if let Some(symbol) = finding.pointer("/symbol/path").and_then(Value::as_str) {
+        cell.push_str(&format!(" `{}`", symbol.replace('|', "\\|")));
+    }
+    cell
+}
+
+/// Files with the most findings on either side and their highest similarity,
+/// leaving out files with a single finding
+fn worst_offenders(findings: &[ReportFinding]) -> Vec<(String, usize, f64)> {
+    let mut files: HashMap<String, (usize, f64)> = HashMap::new();
+    for finding in findings {
+        let related = finding.value.pointer("/related/file").and_then(Value::as_str);
+        let mut sides = vec![finding.file.trim_start_matches("./")];
+        sides.extend(related.map(|file| file.trim_start_matches("./")));
+        sides.dedup();
+        for file in sides {
+            let entry = files.entry(file.to_string()).or_insert((0, 0.0));
+            entry.0 += 1;
+            entry.1 = entry.1.max(finding.similarity.unwrap_or(0.0));
+        }
+    }
+    let mut files: Vec<_> = files
+        .into_iter()
+        .filter(|(_, (count, _))| *count > 1)
+        .map(|(file, (count, highest))| (file, count, highest))
+        .collect();
+    files.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.total_cmp(&a.2)).then(a.0.cmp(&b.0)));
+    files.truncate(MAX_OFFENDERS);
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report_diff::parse_findings;
+
+    const RESULTS: &str = r#"{"id":"00000000000000ff","file":"./src/cart.ts","line":12,"severity":"warning","message":"Function 'cartTotal' is 97% similar to 'sumPrices'","similarity":0.97,"symbol":{"path":"cartTotal","exported":true},"related":{"file":"./src/prices.ts","line":4,"symbol":{"path":"sumPrices","exported":true}}}
+{"id":"1111111111111111","file":"./src/prices.ts","line":30,"severity":"warning","message":"Function 'round' is 90% similar to 'toCents'","similarity":0.9,"related":{"file":"./src/money.ts","line":2}}"#;
+
+    #[test]
+    fn test_new_duplicates_are_linked() {
+        let links = BlobLinks::new("https://github.com/acme/shop.git", "abc123");
+        let base = parse_findings(RESULTS.lines().nth(1).unwrap()).unwrap();
+        let comment = render_pr_comment(parse_findings(RESULTS).unwrap(), Some(base), Some(&links));
+
+        assert!(comment.starts_with(PR_COMMENT_MARKER));
+        assert!(comment.contains("**+1 new duplicate**, -0 resolved · 2 in total"));
+        assert!(comment.contains(
+            "| 97% | [src/cart.ts:12](https://github.com/acme/shop/blob/abc123/src/cart.ts#L12) `cartTotal` | [src/prices.ts:4](https://github.com/acme/shop/blob/abc123/src/prices.ts#L4) `sumPrices` |"
+        ));
+        assert!(!comment.contains("src/money.ts:2"));
+        assert!(comment.contains("| `src/prices.ts` | 2 | 97% |"));
+    }
+
+    #[test]
+    fn test_without_base_or_findings() {
+        let comment = render_pr_comment(parse_findings(RESULTS).unwrap(), None, None);
+        assert!(comment.contains("**2 duplicates** · 2 in total"));
+        assert!(comment.contains("| 90% | `src/prices.ts:30` | `src/money.ts:2` |"));
+
+        let comment = render_pr_comment(Vec::new(), Some(Vec::new()), None);
+        assert!(comment.contains("**+0 new duplicates**, -0 resolved · 0 in total"));
+        assert!(comment.ends_with("No new duplicates.\n"));
+    }
+
+    #[test]
+    fn test_links_from_ci_variables() {
+        let github = |name: &str| match name {
+            "GITHUB_SERVER_URL" => Some("https://github.com".to_string()),
+            "GITHUB_REPOSITORY" => Some("acme/shop".to_string()),
+            "GITHUB_SHA" => Some("abc123".to_string()),
+            _ => None,
+        };
+        let links = BlobLinks::from_vars(github).unwrap();
+        assert_eq!(links.url("./a.ts", 3), "https://github.com/acme/shop/blob/abc123/a.ts#L3");
+
+        let gitlab = |name: &str| match name {
+            "CI_PROJECT_URL" => Some("https://gitlab.com/acme/shop".to_string()),
+            "CI_COMMIT_SHA" => Some("def456".to_string()),
+            _ => None,
+        };
+        let links = BlobLinks::from_vars(gitlab).unwrap();
+        assert_eq!(links.url("a.ts", 1), "https://gitlab.com/acme/shop/-/blob/def456/a.ts#L1");
+        assert!(BlobLinks::from_vars(|_| None).is_none());
+    }
+}
@@ -0,0 +1,207 @@
+//! Bundled settings for common project types.
+//!
+//! A preset picks a threshold, a minimum function size, directories to
+//! exclude and the analyzers to run, so a first run does not need a dozen
+//! flags. It is chosen with `--preset` or in `similarity.toml`, whose other
+//! top-level keys override the preset's values:
+//!
+//! ```toml
+//! preset = "react"
+//! threshold = 0.9
+//! exclude = ["**/legacy/**"]
+//! analyzers = ["functions", "hooks"]
+//! ```
+//!
+//! Command line flags override both. `exclude` in the config replaces the
+//! preset's patterns, while `--exclude` flags add to them.
+
+use crate::config_loader::ConfigLoader;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    React,
+    NodeApi,
+    DesignSystem,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::React, Preset::NodeApi, Preset::DesignSystem];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Preset::React => "react",
+            Preset::NodeApi => "node-api",
+            Preset::DesignSystem => "design-system",
+        }
+    }
+
+    #[must_use]
+    pub fn definition(self) -> &'static PresetDefinition {
+        PRESETS
+            .iter()
+            .find(|definition| definition.preset == self)
+            .expect("every preset has a definition")
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Preset::ALL.into_iter().find(|preset| preset.as_str() == s).ok_or_else(|| {
+            format!("unknown preset '{}' (expected react, node-api or design-system)", s)
+        })
+    }
+}
+
+/// Analyzers a preset or config can enable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresetAnalyzer {
+    Functions,
+    Types,
+    Classes,
+    Hooks,
+    Tests,
+    Graphql,
+}
+
+/// Settings bundled by a preset
+#[derive(Debug)]
+pub struct PresetDefinition {
+    pub preset: Preset,
+    pub description: &'static str,
+    pub threshold: f64,
+    pub min_lines: u32,
+    /// Patterns excluded in addition to [`BUILD_OUTPUT_EXCLUDE`]
+    pub exclude: &'static [&'static str],
+    pub analyzers: &'static [PresetAnalyzer],
+}
+
+impl PresetDefinition {
+    /// Every pattern the preset excludes
+    #[must_use]
+    pub fn exclude_patterns(&self) -> Vec<String> {
+        BUILD_OUTPUT_EXCLUDE.iter().chain(self.exclude).map(|pattern| pattern.to_string()).collect()
+    }
+}
+
+/// Build output, coverage reports and generated code, excluded by every preset
+pub const BUILD_OUTPUT_EXCLUDE: &[&str] =
+    &["dist", "build", "coverage", "generated", "**/__generated__/**", "**/*.generated.*"];
+
+pub const PRESETS: &[PresetDefinition] = &[
+    PresetDefinition {
+        preset: Preset::React,
+        description: "React apps: functions, types and custom hooks, without stories",
+        threshold: 0.85,
+        min_lines: 4,
+        exclude: &[".next", "storybook-static", "**/*.stories.*"],
+        analyzers: &[PresetAnalyzer::Functions, PresetAnalyzer::Types, PresetAnalyzer::Hooks],
+    },
+    PresetDefinition {
+        preset: Preset::NodeApi,
+        description: "Node.js services: functions, types and classes, without migrations and mocks",
+        threshold: 0.87,
+        min_lines: 5,
+        exclude: &["migrations", "**/*.mock.*"],
+        analyzers: &[PresetAnalyzer::Functions, PresetAnalyzer::Types, PresetAnalyzer::Classes],
+    },
+    PresetDefinition {
+        preset: Preset::DesignSystem,
+        description: "Component libraries: strict matching of small components and prop types",
+        threshold: 0.9,
+        min_lines: 3,
+        exclude: &["storybook-static", "**/*.stories.*"],
+        analyzers: &[PresetAnalyzer::Functions, PresetAnalyzer::Types],
+    },
+];
+
+/// Preset and overriding top-level keys of `similarity.toml`
+#[derive(Debug, Default, Deserialize)]
+pub struct PresetConfig {
+    pub preset: Option<Preset>,
+    pub threshold: Option<f64>,
+    pub min_lines: Option<u32>,
+    pub exclude: Option<Vec<String>>,
+    pub analyzers: Option<Vec<PresetAnalyzer>>,
+}
+
+impl ConfigLoader for PresetConfig {}
+
+/// Settings of a run from its preset and config; `None` keeps the tool's default
+#[derive(Debug, Default, PartialEq)]
+pub struct ProjectSettings {
+    pub preset: Option<Preset>,
+    pub threshold: Option<f64>,
+    pub min_lines: Option<u32>,
+    pub exclude: Vec<String>,
+    pub analyzers: Option<Vec<PresetAnalyzer>>,
+}
+
+impl ProjectSettings {
+    /// Whether `analyzer` runs, or `None` when neither preset nor config decide
+    #[must_use]
+    pub fn enables(&self, analyzer: PresetAnalyzer) -> Option<bool> {
+        self.analyzers.as_ref().map(|analyzers| analyzers.contains(&analyzer))
+    }
+}
+
+impl PresetConfig {
+    /// Combine the config with `preset`, which takes the place of the config's
+    /// own `preset` when given (from `--preset`)
+    #[must_use]
+    pub fn resolve(&self, preset: Option<Preset>) -> ProjectSettings {
+        let preset = preset.or(self.preset);
+        let definition = preset.map(Preset::definition);
+        ProjectSettings {
+            preset,
+            threshold: self.threshold.or(definition.map(|d| d.threshold)),
+            min_lines: self.min_lines.or(definition.map(|d| d.min_lines)),
+            exclude: self.exclude.clone().unwrap_or_else(|| {
+                definition.map(PresetDefinition::exclude_patterns).unwrap_or_default()
+            }),
+            analyzers: self.analyzers.clone().or(definition.map(|d| d.analyzers.to_vec())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_overrides_preset() {
+        let config: PresetConfig =
+            toml::from_str("preset = \"react\"\nthreshold = 0.95\nexclude = [\"legacy\"]\n")
+                .unwrap();
+        let settings = config.resolve(None);
+        assert_eq!(settings.preset, Some(Preset::React));
+        assert_eq!(settings.threshold, Some(0.95));
+        assert_eq!(settings.min_lines, Some(4));
+        assert_eq!(settings.exclude, ["legacy"]);
+        assert_eq!(settings.enables(PresetAnalyzer::Hooks), Some(true));
+        assert_eq!(settings.enables(PresetAnalyzer::Classes), Some(false));
+
+        // --preset replaces the config's preset, not its overrides
+        let settings = config.resolve(Some(Preset::NodeApi));
+        assert_eq!(settings.threshold, Some(0.95));
+        assert_eq!(settings.min_lines, Some(5));
+        assert_eq!(settings.enables(PresetAnalyzer::Classes), Some(true));
+
+        assert_eq!(PresetConfig::default().resolve(None), ProjectSettings::default());
+        assert_eq!("design-system".parse(), Ok(Preset::DesignSystem));
+        assert!("vue".parse::<Preset>().is_err());
+    }
+}
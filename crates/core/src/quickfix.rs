@@ -0,0 +1,433 @@
+//! Machine-applicable edits ("quick fixes") attached to findings.
+//!
+//! The schema is shared by everything that consumes fixes: the JSON output of
+//! the CLIs, editor integrations turning them into code actions, and tools
+//! applying them in place with [`apply_fixes`]. Edits are line based, with
+//! 1-based inclusive line numbers like the rest of the output, and refer to the
+//! file contents the finding was computed from. The edits of one fix are
+//! applied together; edits of different fixes may conflict, so apply one fix
+//! at a time and re-run the analysis before the next.
+//!
+//! Fixes are only offered where the result is known to behave the same: the
+//! later copy of an exact CSS duplicate, and TypeScript/JavaScript functions
+//! with identical parameters and bodies.
+
+use crate::function_extractor::{FunctionDefinition, FunctionType};
+use crate::refactor_patch::{import_insertion_index, import_specifier};
+use crate::tsconfig_paths::PathAliases;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A single change to a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Edit {
+    /// Remove lines `start_line..=end_line`
+    DeleteRange { file: String, start_line: u32, end_line: u32 },
+    /// Insert an import statement as a new line before `line`
+    InsertImport { file: String, line: u32, text: String },
+    /// Replace lines `start_line..=end_line` with `text`, e.g. a call to the kept copy
+    ReplaceRange { file: String, start_line: u32, end_line: u32, text: String },
+}
+
+impl Edit {
+    #[must_use]
+    pub fn file(&self) -> &str {
+        match self {
+            Edit::DeleteRange { file, .. }
+            | Edit::InsertImport { file, .. }
+            | Edit::ReplaceRange { file, .. } => file,
+        }
+    }
+
+    /// First line the edit touches; insertions touch the line they precede
+    fn start_line(&self) -> u32 {
+        match self {
+            Edit::DeleteRange { start_line, .. } | Edit::ReplaceRange { start_line, .. } => {
+                *start_line
+            }
+            Edit::InsertImport { line, .. } => *line,
+        }
+    }
+}
+
+/// A set of edits resolving one finding
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Fix {
+    /// Short description, shown as the title of the code action
+    pub title: String,
+    pub edits: Vec<Edit>,
+}
+
+/// Apply the edits of `fixes` that target `file` to its `source`.
+///
+/// Fails without changing anything when edits overlap or reach past the end
+/// of the file.
+pub fn apply_fixes(source: &str, file: &str, fixes: &[Fix]) -> Result<String, String> {
+    let mut edits: Vec<&Edit> =
+        fixes.iter().flat_map(|fix| &fix.edits).filter(|edit| edit.file() == file).collect();
+    // Bottom to top, so applied edits do not shift the lines of the remaining ones;
+    // at the same line, replace the range before inserting above it
+    edits.sort_by_key(|edit| {
+        (std::cmp::Reverse(edit.start_line()), matches!(edit, Edit::InsertImport { .. }))
+    });
+
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let line_count = lines.len() as u32;
+    let mut lowest_touched = line_count + 1;
+    for edit in edits {
+        match edit {
+            Edit::DeleteRange { start_line, end_line, .. }
+            | Edit::ReplaceRange { start_line, end_line, .. } => {
+                if *start_line == 0 || start_line > end_line || *end_line > line_count {
+                    return Err(format!(
+                        "{}: lines {}-{} are outside the file",
+                        file, start_line, end_line
+                    ));
+                }
+                if *end_line >= lowest_touched {
+                    return Err(format!("{}: overlapping edits at line {}", file, end_line));
+                }
+                let range = (*start_line as usize - 1)..(*end_line as usize);
+                match edit {
+                    Edit::ReplaceRange { text, .. } => {
+                        lines.splice(range, text.lines().map(str::to_string));
+                    }
+                    _ => {
+                        lines.drain(range);
+                    }
+                }
+                lowest_touched = *start_line;
+            }
+            Edit::InsertImport { line, text, .. } => {
+                if *line == 0 || *line > line_count + 1 {
+                    return Err(format!("{}: line {} is outside the file", file, line));
+                }
+                if *line > lowest_touched {
+                    return Err(format!("{}: overlapping edits at line {}", file, line));
+                }
+                lines.insert(*line as usize - 1, text.clone());
+                lowest_touched = *line;
+            }
+        }
+    }
+
+    let mut result = lines.join("\n");
+    if source.ends_with('\n') || source.is_empty() {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// A function as found in its file, for building fixes
+#[derive(Debug, Clone, Copy)]
+pub struct FunctionSite<'a> {
+    /// Path of the file, as it should appear in the edits
+    pub path: &'a str,
+    pub source: &'a str,
+    pub function: &'a FunctionDefinition,
+}
+
+impl FunctionSite<'_> {
+    fn first_line(&self) -> &str {
+        self.source.lines().nth(self.function.start_line as usize - 1).unwrap_or("")
+    }
+
+    /// Source of the function: the whole declaration, or the arrow function
+    fn text(&self) -> &str {
+        let span = self.function.body_span;
+        self.source.get(span.start as usize..span.end as usize).unwrap_or("")
+    }
+
+    /// The function's text without the declared name, so renamed copies compare equal
+    fn anonymous_text(&self) -> String {
+        let text = self.text();
+        let name = &*self.function.name;
+        let keyword_end = text.find("function").map_or(0, |position| position + "function".len());
+        match text[keyword_end..].find(name) {
+            Some(offset) if self.function.function_type == FunctionType::Function => {
+                let position = keyword_end + offset;
+                format!("{}{}", &text[..position], &text[position + name.len()..])
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    fn is_exported(&self) -> bool {
+        self.first_line().trim_start().starts_with("export ")
+    }
+
+    /// Byte offset of the start of the function's first line
+    fn line_start_offset(&self, line: u32) -> usize {
+        self.source.split_inclusive('\n').take(line as usize - 1).map(str::len).sum()
+    }
+}
+
+/// Fix for a pair of identical TypeScript/JavaScript functions that keeps
+/// `keep` and makes `remove` use it.
+///
+/// Within a file, the body of `remove` is replaced with a call to `keep`.
+/// Across files, `remove` is deleted and `keep` imported in its place
+/// (re-exported if `remove` was exported), exporting `keep` if needed; the
+/// import uses a tsconfig path alias when `aliases` has one for `keep`.
+/// Returns `None` unless both are top-level functions with the same
+/// parameters and bodies that are equal up to whitespace.
+#[must_use]
+pub fn identical_function_fix(
+    keep: &FunctionSite,
+    remove: &FunctionSite,
+    aliases: Option<&PathAliases>,
+) -> Option<Fix> {
+    let movable = |site: &FunctionSite| {
+        let function = site.function;
+        function.class_name.is_none()
+            && function.parent_function.is_none()
+            && matches!(function.function_type, FunctionType::Function | FunctionType::Arrow)
+    };
+    let normalized = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !movable(keep)
+        || !movable(remove)
+        || keep.function.parameters != remove.function.parameters
+        || keep.text().is_empty()
+        || normalized(&keep.anonymous_text()) != normalized(&remove.anonymous_text())
+    {
+        return None;
+    }
+
+    let (keep_name, remove_name) = (&*keep.function.name, &*remove.function.name);
+    if keep.path == remove.path {
+        return replace_with_call_fix(keep_name, remove);
+    }
+    // A default export cannot be imported by name without changing its importers
+    if keep.first_line().trim_start().starts_with("export default ") {
+        return None;
+    }
+
+    let mut edits = Vec::new();
+    if !keep.is_exported() {
+        let line = keep.first_line();
+        let indent_len = line.len() - line.trim_start().len();
+        edits.push(Edit::ReplaceRange {
+            file: keep.path.to_string(),
+            start_line: keep.function.start_line,
+            end_line: keep.function.start_line,
+            text: format!("{}export {}", &line[..indent_len], &line[indent_len..]),
+        });
+    }
+
+    let binding = if keep_name == remove_name {
+        keep_name.to_string()
+    } else {
+        format!("{} as {}", keep_name, remove_name)
+    };
+    let lines: Vec<&str> = remove.source.lines().collect();
+    let start = (remove.function.start_line as usize - 1).min(lines.len());
+    edits.push(Edit::InsertImport {
+        file: remove.path.to_string(),
+        line: import_insertion_index(&lines[..start]) as u32 + 1,
+        text: format!(
+            "import {{ {} }} from \"{}\";",
+            binding,
+            import_specifier(Path::new(remove.path), Path::new(keep.path), aliases)
+        ),
+    });
+    let (start_line, end_line) = (remove.function.start_line, remove.function.end_line);
+    edits.push(if remove.is_exported() {
+        Edit::ReplaceRange {
+            file: remove.path.to_string(),
+            start_line,
+            end_line,
+            text: format!("export {{ {} }};", remove_name),
+        }
+    } else {
+        Edit::DeleteRange { file: remove.path.to_string(), start_line, end_line }
+    });
+
+    Some(Fix { title: format!("Import '{}' from {} instead", keep_name, keep.path), edits })
+}
+
+/// Replace the body of `remove` with a call to `keep_name`, keeping its signature
+fn replace_with_call_fix(keep_name: &str, remove: &FunctionSite) -> Option<Fix> {
+    let parameters = &remove.function.parameters;
+    let is_identifier =
+        |name: &String| name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+    if !parameters.iter().all(is_identifier) {
+        return None;
+    }
+
+    // Only block bodies: an expression body's extent is not known from the text alone
+    let span = remove.function.body_span;
+    let body_start = span.start as usize + trailing_block_start(remove.text())?;
+    let (start_line, end_line) = (remove.function.start_line, remove.function.end_line);
+    let region_start = remove.line_start_offset(start_line);
+    let region_end = remove.line_start_offset(end_line + 1);
+    let text = format!(
+        "{}{{\n  return {}({});\n}}{}",
+        remove.source.get(region_start..body_start)?,
+        keep_name,
+        parameters.join(", "),
+        remove.source.get(span.end as usize..region_end)?.trim_end_matches('\n')
+    );
+
+    Some(Fix {
+        title: format!(
+            "Replace the body of '{}' with a call to '{}'",
+            remove.function.name, keep_name
+        ),
+        edits: vec![Edit::ReplaceRange {
+            file: remove.path.to_string(),
+            start_line,
+            end_line,
+            text,
+        }],
+    })
+}
+
+/// Offset of the `{` opening the block `text` ends with, skipping braces in
+/// strings, template literals and comments
+fn trailing_block_start(text: &str) -> Option<usize> {
+    if !text.trim_end().ends_with('}') {
+        return None;
+    }
+    let bytes = text.as_bytes();
+    let mut open = Vec::new();
+    let mut last_closed = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'"' | b'\'' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'{' => open.push(i),
+            b'}' => last_closed = open.pop(),
+            _ => {}
+        }
+        i += 1;
+    }
+    if open.is_empty() {
+        last_closed
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function_extractor::extract_functions;
+
+    #[test]
+    fn test_apply_fixes_bottom_to_top() {
+        let source = "import a from \"a\";\nconst x = 1;\nfunction f() {\n  return x;\n}\nf();\n";
+        let fix = Fix {
+            title: "t".to_string(),
+            edits: vec![
+                Edit::InsertImport {
+                    file: "a.ts".to_string(),
+                    line: 2,
+                    text: "import { g } from \"./g\";".to_string(),
+                },
+                Edit::DeleteRange { file: "a.ts".to_string(), start_line: 3, end_line: 5 },
+                Edit::ReplaceRange {
+                    file: "a.ts".to_string(),
+                    start_line: 6,
+                    end_line: 6,
+                    text: "g();".to_string(),
+                },
+                Edit::DeleteRange { file: "other.ts".to_string(), start_line: 1, end_line: 1 },
+            ],
+        };
+        assert_eq!(
+            apply_fixes(source, "a.ts", std::slice::from_ref(&fix)).unwrap(),
+            "import a from \"a\";\nimport { g } from \"./g\";\nconst x = 1;\ng();\n"
+        );
+
+        let overlapping = Fix {
+            title: "t".to_string(),
+            edits: vec![
+                Edit::DeleteRange { file: "a.ts".to_string(), start_line: 3, end_line: 5 },
+                Edit::InsertImport { file: "a.ts".to_string(), line: 4, text: String::new() },
+            ],
+        };
+        assert!(apply_fixes(source, "a.ts", &[overlapping]).is_err());
+
+        // The schema is what editors consume
+        let json = serde_json::to_value(&fix.edits[1]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "delete_range", "file": "a.ts", "start_line": 3, "end_line": 5 })
+        );
+    }
+
+    #[test]
+    fn test_identical_function_fixes() {
+        let utils = "import { round } from \"./round\";\n\nfunction sum(xs: number[]) {\n  return xs.reduce((a, b) => a + b, 0);\n}\n";
+        let report = "import { log } from \"./log\";\n\nexport function sum(xs: number[]) {\n  return xs.reduce((a, b) =>\n    a + b, 0);\n}\n\nfunction total(xs: number[]) {\n  return xs.reduce((a, b) => a + b, 0);\n}\n\nfunction other(values: number[]) {\n  return values.length;\n}\n";
+        let utils_functions = extract_functions("src/utils.ts", utils).unwrap();
+        let report_functions = extract_functions("src/report/sum.ts", report).unwrap();
+        let site = |path, source, function| FunctionSite { path, source, function };
+
+        // Across files: export the kept copy, import it and re-export the removed one
+        let keep = site("src/utils.ts", utils, &utils_functions[0]);
+        let remove = site("src/report/sum.ts", report, &report_functions[0]);
+        let fix = identical_function_fix(&keep, &remove, None).unwrap();
+        assert_eq!(
+            apply_fixes(utils, "src/utils.ts", std::slice::from_ref(&fix)).unwrap(),
+            utils.replace("function sum", "export function sum")
+        );
+        assert_eq!(
+            apply_fixes(report, "src/report/sum.ts", std::slice::from_ref(&fix)).unwrap(),
+            report.replace(
+                "\nexport function sum(xs: number[]) {\n  return xs.reduce((a, b) =>\n    a + b, 0);\n}\n",
+                "import { sum } from \"../utils\";\n\nexport { sum };\n"
+            )
+        );
+        let aliases = PathAliases::parse(
+            Path::new("."),
+            r#"{"compilerOptions":{"paths":{"@/*":["src/*"]}}}"#,
+        )
+        .unwrap();
+        let fix = identical_function_fix(&keep, &remove, Some(&aliases)).unwrap();
+        assert!(fix.edits.contains(&Edit::InsertImport {
+            file: "src/report/sum.ts".to_string(),
+            line: 2,
+            text: "import { sum } from \"@/utils\";".to_string(),
+        }));
+
+        // Within a file: replace the body with a call
+        let total = report_functions.iter().find(|f| &*f.name == "total").unwrap();
+        let fix = identical_function_fix(&remove, &site("src/report/sum.ts", report, total), None)
+            .unwrap();
+        assert_eq!(fix.title, "Replace the body of 'total' with a call to 'sum'");
+        assert_eq!(
+            fix.edits,
+            vec![Edit::ReplaceRange {
+                file: "src/report/sum.ts".to_string(),
+                start_line: 8,
+                end_line: 10,
+                text: "function total(xs: number[]) {\n  return sum(xs);\n}".to_string(),
+            }]
+        );
+
+        // Different parameters or bodies are not identical
+        let other = report_functions.iter().find(|f| &*f.name == "other").unwrap();
+        assert!(identical_function_fix(&keep, &site("src/report/sum.ts", report, other), None)
+            .is_none());
+    }
+}
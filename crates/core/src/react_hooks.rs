@@ -0,0 +1,443 @@
+//! Duplicate detection for custom React hooks.
+//!
+//! Custom hooks (functions named `useXxx`) are compared on what makes a hook
+//! a hook rather than on their text alone: the state they hold (`useState`,
+//! `useReducer`, `useRef`), the dependency arrays of their effects and
+//! memoized values, and the bodies of those effect callbacks. Hooks may only
+//! be called at the top level of a hook, so only its top-level statements are
+//! searched for hook calls.
+
+use crate::cli_output::format_function_output;
+use crate::function_extractor::extract_functions;
+use crate::parser::parse_function_body;
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, TSEDOptions};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{Argument, CallExpression, Expression, FunctionBody, Statement};
+use oxc_parser::Parser;
+use oxc_span::{SourceType, Span};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Hooks taking a callback and a dependency array, with the argument positions of both
+const DEPENDENCY_HOOKS: [(&str, usize, usize); 6] = [
+    ("useEffect", 0, 1),
+    ("useLayoutEffect", 0, 1),
+    ("useInsertionEffect", 0, 1),
+    ("useMemo", 0, 1),
+    ("useCallback", 0, 1),
+    ("useImperativeHandle", 1, 2),
+];
+
+/// Hooks holding state across renders
+const STATE_HOOKS: [&str; 3] = ["useState", "useReducer", "useRef"];
+
+/// A custom hook and the hooks it calls
+#[derive(Debug, Clone)]
+pub struct HookDefinition {
+    pub name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub source: String,
+    /// Hooks called at the top level, in order
+    pub calls: Vec<String>,
+    /// State held by the hook, e.g. `useState<number>` or `useReducer`
+    pub state: Vec<String>,
+    /// Dependency array of each effect or memoized value, e.g. `useEffect[2]`,
+    /// or the hook name alone when it has no dependency array
+    pub dependencies: Vec<String>,
+    /// Callback bodies of effects and memoized values, in order
+    pub effects: Vec<Rc<TreeNode>>,
+    pub tree: Rc<TreeNode>,
+}
+
+impl HookDefinition {
+    #[must_use]
+    pub fn line_count(&self) -> u32 {
+        self.end_line - self.start_line + 1
+    }
+}
+
+/// Whether `name` follows the React hook naming convention
+#[must_use]
+pub fn is_hook_name(name: &str) -> bool {
+    name.strip_prefix("use").and_then(|rest| rest.chars().next()).is_some_and(char::is_uppercase)
+}
+
+/// Extract the top-level custom hooks of a TypeScript/JavaScript file
+///
+/// # Errors
+///
+/// Returns the parse errors when the file cannot be parsed
+pub fn extract_hooks(file_path: &str, source_text: &str) -> Result<Vec<HookDefinition>, String> {
+    let functions = extract_functions(file_path, source_text)?;
+    Ok(functions
+        .iter()
+        .filter(|function| {
+            is_hook_name(&function.name)
+                && function.class_name.is_none()
+                && function.parent_function.is_none()
+                && !function.has_ignore_directive
+        })
+        .filter_map(|function| {
+            let span = function.body_span;
+            let source = source_text.get(span.start as usize..span.end as usize)?;
+            let mut hook = analyze_hook(file_path, source)?;
+            hook.name = function.name.to_string();
+            hook.file_path = file_path.to_string();
+            hook.start_line = function.start_line;
+            hook.end_line = function.end_line;
+            Some(hook)
+        })
+        .collect())
+}
+
+/// Parse a hook's source on its own and collect the hooks it calls
+fn analyze_hook(file_path: &str, source: &str) -> Option<HookDefinition> {
+    // Wrapping in parentheses parses declarations and anonymous functions alike
+    let wrapped = format!("({source})");
+    let allocator = Allocator::default();
+    let source_type = SourceType::from_path(file_path).unwrap_or(SourceType::tsx());
+    let ret = Parser::new(&allocator, &wrapped, source_type).parse();
+    if !ret.errors.is_empty() {
+        return None;
+    }
+    let Some(Statement::ExpressionStatement(statement)) = ret.program.body.first() else {
+        return None;
+    };
+    let (body, expression) = match statement.expression.get_inner_expression() {
+        Expression::ArrowFunctionExpression(arrow) => (&*arrow.body, arrow.expression),
+        Expression::FunctionExpression(func) => (func.body.as_deref()?, false),
+        _ => return None,
+    };
+
+    let mut hook = HookDefinition {
+        name: String::new(),
+        file_path: String::new(),
+        start_line: 0,
+        end_line: 0,
+        source: source.to_string(),
+        calls: Vec::new(),
+        state: Vec::new(),
+        dependencies: Vec::new(),
+        effects: Vec::new(),
+        tree: body_tree(file_path, &wrapped, body, expression)?,
+    };
+
+    for statement in &body.statements {
+        let expressions: Vec<&Expression> = match statement {
+            Statement::VariableDeclaration(declaration) => {
+                declaration.declarations.iter().filter_map(|decl| decl.init.as_ref()).collect()
+            }
+            Statement::ExpressionStatement(statement) => vec![&statement.expression],
+            Statement::ReturnStatement(statement) => statement.argument.iter().collect(),
+            _ => Vec::new(),
+        };
+        for expression in expressions {
+            if let Expression::CallExpression(call) = expression.get_inner_expression() {
+                record_hook_call(&mut hook, call, file_path, &wrapped);
+            }
+        }
+    }
+    Some(hook)
+}
+
+fn record_hook_call(hook: &mut HookDefinition, call: &CallExpression, file_path: &str, text: &str) {
+    let name = match call.callee.get_inner_expression() {
+        Expression::Identifier(ident) => ident.name.as_str(),
+        // `React.useState(...)`
+        Expression::StaticMemberExpression(member) => member.property.name.as_str(),
+        _ => return,
+    };
+    if !is_hook_name(name) {
+        return;
+    }
+    hook.calls.push(name.to_string());
+
+    if STATE_HOOKS.contains(&name) {
+        hook.state.push(state_shape(name, call, text));
+    }
+
+    let Some(&(_, callback_index, deps_index)) =
+        DEPENDENCY_HOOKS.iter().find(|(hook_name, _, _)| *hook_name == name)
+    else {
+        return;
+    };
+    match call.arguments.get(deps_index).and_then(Argument::as_expression) {
+        Some(Expression::ArrayExpression(deps)) => {
+            hook.dependencies.push(format!("{}[{}]", name, deps.elements.len()));
+        }
+        _ => hook.dependencies.push(name.to_string()),
+    }
+    let callback = call.arguments.get(callback_index).and_then(Argument::as_expression);
+    let tree = match callback.map(Expression::get_inner_expression) {
+        Some(Expression::ArrowFunctionExpression(arrow)) => {
+            body_tree(file_path, text, &arrow.body, arrow.expression)
+        }
+        Some(Expression::FunctionExpression(func)) => {
+            func.body.as_deref().and_then(|body| body_tree(file_path, text, body, false))
+        }
+        _ => None,
+    };
+    hook.effects.extend(tree);
+}
+
+/// `useState<number>` from the type argument or the initial value, `useReducer`
+fn state_shape(name: &str, call: &CallExpression, text: &str) -> String {
+    if name == "useReducer" {
+        return name.to_string();
+    }
+    let shape =
+        match (&call.type_arguments, call.arguments.first().and_then(Argument::as_expression)) {
+            (Some(type_arguments), _) => slice(text, type_arguments.span)
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .split_whitespace()
+                .collect::<String>(),
+            (None, Some(initial)) => match initial.get_inner_expression() {
+                Expression::NumericLiteral(_) => "number".to_string(),
+                Expression::StringLiteral(_) | Expression::TemplateLiteral(_) => {
+                    "string".to_string()
+                }
+                Expression::BooleanLiteral(_) => "boolean".to_string(),
+                Expression::NullLiteral(_) => "null".to_string(),
+                Expression::ArrayExpression(_) => "array".to_string(),
+                Expression::ObjectExpression(_) => "object".to_string(),
+                Expression::ArrowFunctionExpression(_) | Expression::FunctionExpression(_) => {
+                    "lazy".to_string()
+                }
+                _ => "unknown".to_string(),
+            },
+            (None, None) => "undefined".to_string(),
+        };
+    format!("{name}<{shape}>")
+}
+
+fn body_tree(
+    file_path: &str,
+    text: &str,
+    body: &FunctionBody,
+    expression: bool,
+) -> Option<Rc<TreeNode>> {
+    parse_function_body(file_path, slice(text, body.span), expression).ok()
+}
+
+fn slice(text: &str, span: Span) -> &str {
+    text.get(span.start as usize..span.end as usize).unwrap_or_default()
+}
+
+/// Similarity of two hooks and its components
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookSimilarity {
+    pub similarity: f64,
+    /// TSED similarity of the whole hook bodies
+    pub body: f64,
+    pub state: f64,
+    pub dependencies: f64,
+    pub effects: f64,
+}
+
+/// Compare two hooks: 40% body structure and 20% each for state shape,
+/// dependency arrays and effect bodies
+#[must_use]
+pub fn compare_hooks(
+    hook1: &HookDefinition,
+    hook2: &HookDefinition,
+    options: &TSEDOptions,
+) -> HookSimilarity {
+    let body = calculate_tsed(&hook1.tree, &hook2.tree, options);
+    let state = multiset_overlap(&hook1.state, &hook2.state);
+    let dependencies = multiset_overlap(&hook1.dependencies, &hook2.dependencies);
+
+    // Effects are paired in order; unpaired effects count as completely different.
+    // Effect callbacks are short, so the short-code penalty would hide identical ones.
+    let effect_options = TSEDOptions { size_penalty: false, ..options.clone() };
+    let effect_count = hook1.effects.len().max(hook2.effects.len());
+    let effects = if effect_count == 0 {
+        1.0
+    } else {
+        let paired: f64 = hook1
+            .effects
+            .iter()
+            .zip(&hook2.effects)
+            .map(|(effect1, effect2)| calculate_tsed(effect1, effect2, &effect_options))
+            .sum();
+        paired / effect_count as f64
+    };
+
+    HookSimilarity {
+        similarity: 0.4 * body + 0.2 * state + 0.2 * dependencies + 0.2 * effects,
+        body,
+        state,
+        dependencies,
+        effects,
+    }
+}
+
+/// Share of items two lists have in common, counting repeats; 1.0 when both are empty
+fn multiset_overlap(items1: &[String], items2: &[String]) -> f64 {
+    if items1.is_empty() && items2.is_empty() {
+        return 1.0;
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for item in items1 {
+        *counts.entry(item).or_default() += 1;
+    }
+    let mut common = 0;
+    for item in items2 {
+        if let Some(count) = counts.get_mut(item.as_str()).filter(|count| **count > 0) {
+            *count -= 1;
+            common += 1;
+        }
+    }
+    2.0 * common as f64 / (items1.len() + items2.len()) as f64
+}
+
+/// A pair of similar hooks
+#[derive(Debug, Clone)]
+pub struct HookDuplicate<'a> {
+    pub first: &'a HookDefinition,
+    pub second: &'a HookDefinition,
+    pub similarity: HookSimilarity,
+}
+
+/// Find pairs of hooks at least `threshold` similar, most similar first
+#[must_use]
+pub fn find_similar_hooks<'a>(
+    hooks: &'a [HookDefinition],
+    threshold: f64,
+    options: &TSEDOptions,
+) -> Vec<HookDuplicate<'a>> {
+    let candidates: Vec<&HookDefinition> = hooks
+        .iter()
+        .filter(|hook| match options.min_tokens {
+            Some(min_tokens) => hook.tree.get_subtree_size() as u32 >= min_tokens,
+            None => hook.line_count() >= options.min_lines,
+        })
+        .collect();
+
+    let mut duplicates = Vec::new();
+    for (i, first) in candidates.iter().enumerate() {
+        for second in &candidates[i + 1..] {
+            let similarity = compare_hooks(first, second, options);
+            if similarity.similarity >= threshold {
+                duplicates.push(HookDuplicate { first, second, similarity });
+            }
+        }
+    }
+
+    duplicates.sort_by(|a, b| b.similarity.similarity.total_cmp(&a.similarity.similarity));
+    duplicates
+}
+
+fn hook_label(hook: &HookDefinition) -> String {
+    format_function_output(&hook.file_path, &hook.name, hook.start_line, hook.end_line)
+}
+
+/// Print similar hooks; returns the number of pairs
+pub fn report_similar_hooks(duplicates: &[HookDuplicate], print: bool) -> usize {
+    if duplicates.is_empty() {
+        println!("\nNo similar hooks found!");
+        return 0;
+    }
+
+    println!("\nSimilar hooks (candidates for a shared hooks package):");
+    println!("{}", "-".repeat(60));
+    for duplicate in duplicates {
+        let similarity = &duplicate.similarity;
+        println!(
+            "\nSimilarity: {:.2}% (body: {:.2}%, state: {:.2}%, dependencies: {:.2}%, effects: {:.2}%)",
+            similarity.similarity * 100.0,
+            similarity.body * 100.0,
+            similarity.state * 100.0,
+            similarity.dependencies * 100.0,
+            similarity.effects * 100.0
+        );
+        println!("  {}", hook_label(duplicate.first));
+        println!("  {}", hook_label(duplicate.second));
+
+        if print {
+            for hook in [duplicate.first, duplicate.second] {
+                println!(
+                    "\n\x1b[36m--- {}:{} ---\x1b[0m\n{}",
+                    hook.file_path, hook.name, hook.source
+                );
+            }
+        }
+    }
+    println!("\nTotal similar hook pairs found: {}", duplicates.len());
+
+    duplicates.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOOKS: &str = r#"
+export function useUser(id: string) {
+    const [user, setUser] = useState<User | null>(null);
+    const [loading, setLoading] = useState(false);
+    useEffect(() => {
+        setLoading(true);
+        fetch(`/users/${id}`).then((res) => res.json()).then(setUser).finally(() => setLoading(false));
+    }, [id]);
+    return { user, loading };
+}
+
+export const useTeam = (id: string) => {
+    const [team, setTeam] = React.useState<Team | null>(null);
+    const [loading, setLoading] = useState(false);
+    useEffect(() => {
+        setLoading(true);
+        fetch(`/teams/${id}`).then((res) => res.json()).then(setTeam).finally(() => setLoading(false));
+    }, [id]);
+    return { team, loading };
+};
+
+export function useToggle(initial = false) {
+    const [on, setOn] = useState(initial);
+    const toggle = useCallback(() => setOn((value) => !value), []);
+    return [on, toggle];
+}
+
+function formatUser(user: User) {
+    return user.name;
+}
+"#;
+
+    #[test]
+    fn test_extract_hooks() {
+        let hooks = extract_hooks("hooks.ts", HOOKS).unwrap();
+        let names: Vec<&str> = hooks.iter().map(|hook| hook.name.as_str()).collect();
+        assert_eq!(names, ["useUser", "useTeam", "useToggle"]);
+
+        assert_eq!(hooks[0].calls, ["useState", "useState", "useEffect"]);
+        assert_eq!(hooks[0].state, ["useState<User|null>", "useState<boolean>"]);
+        assert_eq!(hooks[0].dependencies, ["useEffect[1]"]);
+        assert_eq!(hooks[0].effects.len(), 1);
+
+        assert_eq!(hooks[2].state, ["useState<unknown>"]);
+        assert_eq!(hooks[2].dependencies, ["useCallback[0]"]);
+
+        assert!(is_hook_name("useX") && !is_hook_name("user") && !is_hook_name("use"));
+    }
+
+    #[test]
+    fn test_find_similar_hooks() {
+        let hooks = extract_hooks("hooks.ts", HOOKS).unwrap();
+        let options = TSEDOptions { min_lines: 1, ..Default::default() };
+
+        let duplicates = find_similar_hooks(&hooks, 0.8, &options);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].first.name, "useUser");
+        assert_eq!(duplicates[0].second.name, "useTeam");
+        assert_eq!(duplicates[0].similarity.dependencies, 1.0);
+        assert!(duplicates[0].similarity.state > 0.4);
+
+        let unrelated = compare_hooks(&hooks[0], &hooks[2], &options);
+        assert!(unrelated.similarity < 0.8);
+        assert_eq!(unrelated.dependencies, 0.0);
+    }
+}
@@ -0,0 +1,261 @@
+//! Unified-diff suggestions that extract a duplicated TypeScript/JavaScript
+//! function into a shared module.
+//!
+//! The first copy becomes the shared implementation, both original definitions
+//! are replaced by an import (re-exported if the original was exported), and
+//! lines where the second copy diverges are marked with `TODO(similarity)`
+//! comments in the shared module. The patch is a starting point for a manual
+//! refactoring, not a guaranteed-correct transformation. Imports go through
+//! the project's tsconfig path aliases when given.
+
+use crate::tsconfig_paths::PathAliases;
+use similar::{ChangeTag, TextDiff};
+use std::path::{Component, Path, PathBuf};
+
+/// Similarity above which a pair is considered safe enough to suggest a patch for
+pub const PATCH_SIMILARITY_THRESHOLD: f64 = 0.97;
+
+/// One copy of the duplicated function
+#[derive(Debug, Clone, Copy)]
+pub struct PatchSource<'a> {
+    /// Path of the file, as it should appear in the diff headers
+    pub path: &'a Path,
+    pub source: &'a str,
+    pub name: &'a str,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+impl PatchSource<'_> {
+    fn lines(&self) -> Vec<&str> {
+        let start = self.start_line.saturating_sub(1) as usize;
+        let end = (self.end_line as usize).min(self.source.lines().count());
+        self.source.lines().skip(start).take(end.saturating_sub(start)).collect()
+    }
+
+    fn is_exported(&self) -> bool {
+        self.lines().first().is_some_and(|line| line.trim_start().starts_with("export "))
+    }
+}
+
+/// Location of the shared module: `shared/<name>.<ext>` under the closest common directory
+#[must_use]
+pub fn shared_module_path(path1: &Path, path2: &Path, name: &str) -> PathBuf {
+    let dir1 = path1.parent().unwrap_or(Path::new(""));
+    let dir2 = path2.parent().unwrap_or(Path::new(""));
+    let common: PathBuf = dir1
+        .components()
+        .zip(dir2.components())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect();
+    let extension = path1.extension().and_then(|ext| ext.to_str()).unwrap_or("ts");
+    common.join("shared").join(format!("{}.{}", name, extension))
+}
+
+/// Import specifier of `target` as seen from `from_file` (`./x` or `../x`, without extension)
+#[must_use]
+pub fn module_specifier(from_file: &Path, target: &Path) -> String {
+    let from: Vec<Component> = from_file.parent().unwrap_or(Path::new("")).components().collect();
+    let target_stem = target.with_extension("");
+    let to: Vec<Component> = target_stem.components().collect();
+    let shared = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - shared];
+    if parts.is_empty() {
+        parts.push(".".to_string());
+    }
+    parts.extend(to[shared..].iter().map(|c| c.as_os_str().to_string_lossy().to_string()));
+    parts.join("/")
+}
+
+/// Import specifier of `target` as seen from `from_file`, through a tsconfig
+/// path alias when `aliases` has one for it
+#[must_use]
+pub fn import_specifier(from_file: &Path, target: &Path, aliases: Option<&PathAliases>) -> String {
+    match aliases {
+        Some(aliases) => aliases.specifier(from_file, target),
+        None => module_specifier(from_file, target),
+    }
+}
+
+/// Unified diff moving `first` into `shared` and replacing both copies with imports
+#[must_use]
+pub fn extract_shared_function_patch(
+    first: &PatchSource,
+    second: &PatchSource,
+    shared: &Path,
+    aliases: Option<&PathAliases>,
+) -> String {
+    let mut patch = String::new();
+
+    let module = shared_module_content(first, second);
+    patch.push_str(&unified_diff("", &module, "/dev/null", &format!("b/{}", shared.display())));
+
+    for copy in [first, second] {
+        let replaced = replace_with_import(copy, first.name, shared, aliases);
+        let path = copy.path.display();
+        patch.push_str(&unified_diff(
+            copy.source,
+            &replaced,
+            &format!("a/{}", path),
+            &format!("b/{}", path),
+        ));
+    }
+    patch
+}
+
+fn unified_diff(old: &str, new: &str, old_header: &str, new_header: &str) -> String {
+    TextDiff::from_lines(old, new).unified_diff().header(old_header, new_header).to_string()
+}
+
+/// The first copy, exported, with TODO markers where the second copy differs
+fn shared_module_content(first: &PatchSource, second: &PatchSource) -> String {
+    let first_text = first.lines().join("\n") + "\n";
+    // Compare against the second copy under the shared name, so the rename itself is no divergence
+    let mut second_lines: Vec<String> = second.lines().into_iter().map(str::to_string).collect();
+    if let Some(signature) = second_lines.first_mut() {
+        *signature = signature.replacen(second.name, first.name, 1);
+    }
+    let second_text = second_lines.join("\n") + "\n";
+    let location = format!("{}:{}", second.path.display(), second.start_line);
+
+    let mut content = String::new();
+    let mut first_line = true;
+    for change in TextDiff::from_lines(&first_text, &second_text).iter_all_changes() {
+        let line = change.value().trim_end_matches('\n');
+        match change.tag() {
+            ChangeTag::Equal | ChangeTag::Delete => {
+                if change.tag() == ChangeTag::Delete && !first_line {
+                    let indent: String = line.chars().take_while(|c| c.is_whitespace()).collect();
+                    content.push_str(&format!(
+                        "{}// TODO(similarity): differs from {}\n",
+                        indent, location
+                    ));
+                }
+                if first_line {
+                    content.push_str(&exported_signature(line));
+                    first_line = false;
+                } else {
+                    content.push_str(line);
+                }
+                content.push('\n');
+            }
+            ChangeTag::Insert => {
+                let indent: String = line.chars().take_while(|c| c.is_whitespace()).collect();
+                content.push_str(&format!(
+                    "{}// TODO(similarity): {} has `{}` here\n",
+                    indent,
+                    location,
+                    line.trim()
+                ));
+            }
+        }
+    }
+    content
+}
+
+fn exported_signature(line: &str) -> String {
+    let trimmed = line.trim_start();
+    if let Some(rest) = trimmed.strip_prefix("export default ") {
+        format!("export {}", rest)
+    } else if trimmed.starts_with("export ") {
+        trimmed.to_string()
+    } else {
+        format!("export {}", trimmed)
+    }
+}
+
+/// The file with the copy removed and an import of the shared function added
+fn replace_with_import(
+    copy: &PatchSource,
+    shared_name: &str,
+    shared: &Path,
+    aliases: Option<&PathAliases>,
+) -> String {
+    let binding = if copy.name == shared_name {
+        shared_name.to_string()
+    } else {
+        format!("{} as {}", shared_name, copy.name)
+    };
+    let import = format!(
+        "import {{ {} }} from \"{}\";",
+        binding,
+        import_specifier(copy.path, shared, aliases)
+    );
+
+    let lines: Vec<&str> = copy.source.lines().collect();
+    let end = (copy.end_line as usize).min(lines.len());
+    let start = (copy.start_line.saturating_sub(1) as usize).min(end);
+    let insert_at = import_insertion_index(&lines[..start]);
+
+    let mut result: Vec<String> = Vec::with_capacity(lines.len());
+    for (index, line) in lines.iter().enumerate() {
+        if index == insert_at {
+            result.push(import.clone());
+        }
+        if index == start && copy.is_exported() {
+            result.push(format!("export {{ {} }};", copy.name));
+        }
+        if (start..end).contains(&index) {
+            continue;
+        }
+        result.push((*line).to_string());
+    }
+    if insert_at >= lines.len() {
+        result.push(import);
+    }
+    result.join("\n") + "\n"
+}
+
+/// Index of the line a new import goes before: after the leading import block
+pub(crate) fn import_insertion_index(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .rposition(|line| line.trim_start().starts_with("import "))
+        .map_or(0, |index| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_module_lives_under_the_common_directory() {
+        let shared =
+            shared_module_path(Path::new("src/a/math.ts"), Path::new("src/b/report.ts"), "sum");
+        assert_eq!(shared, Path::new("src/shared/sum.ts"));
+        assert_eq!(module_specifier(Path::new("src/a/math.ts"), &shared), "../shared/sum");
+        assert_eq!(module_specifier(Path::new("src/index.ts"), &shared), "./shared/sum");
+    }
+
+    #[test]
+    fn patch_extracts_the_function_and_marks_divergence() {
+        let source1 = "import { log } from \"./log\";\n\nexport function sum(xs: number[]) {\n  let total = 0;\n  for (const x of xs) total += x;\n  return total;\n}\n";
+        let source2 = "function total(values: number[]) {\n  let total = 0;\n  for (const x of values) total += x;\n  return total;\n}\n\nconsole.log(total([1]));\n";
+        let first = PatchSource {
+            path: Path::new("src/a/math.ts"),
+            source: source1,
+            name: "sum",
+            start_line: 3,
+            end_line: 7,
+        };
+        let second = PatchSource {
+            path: Path::new("src/b/report.ts"),
+            source: source2,
+            name: "total",
+            start_line: 1,
+            end_line: 5,
+        };
+        let shared = shared_module_path(first.path, second.path, first.name);
+        let patch = extract_shared_function_patch(&first, &second, &shared, None);
+
+        assert!(patch.contains("+++ b/src/shared/sum.ts"));
+        assert!(patch.contains("+export function sum(xs: number[]) {"));
+        assert!(patch.contains("// TODO(similarity): differs from src/b/report.ts:1"));
+        assert!(patch.contains("+import { sum } from \"../shared/sum\";"));
+        assert!(patch.contains("+export { sum };"));
+        assert!(patch.contains("+import { sum as total } from \"../shared/sum\";"));
+        assert!(patch.contains("-function total(values: number[]) {"));
+    }
+}
@@ -0,0 +1,249 @@
+//! Fetching remote repositories for analysis (`--git URL#BRANCH`).
+//!
+//! Repositories are shallow-cloned with the `git` command so that any URL the
+//! user's git understands (https, ssh, file) works with their credentials.
+//! Without a cache directory the clone lives in a temporary directory that is
+//! removed when the [`FetchedRepository`] is dropped; with one, clones are kept
+//! and updated on the next run.
+
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A repository URL with an optional branch or tag, written as `URL#BRANCH`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteSpec {
+    pub url: String,
+    pub branch: Option<String>,
+}
+
+impl RemoteSpec {
+    #[must_use]
+    pub fn parse(spec: &str) -> Self {
+        match spec.rsplit_once('#') {
+            Some((url, branch)) if !branch.is_empty() => {
+                Self { url: url.to_string(), branch: Some(branch.to_string()) }
+            }
+            Some((url, _)) => Self { url: url.to_string(), branch: None },
+            None => Self { url: spec.to_string(), branch: None },
+        }
+    }
+
+    /// Directory name for the clone: the repository name plus a hash of the spec,
+    /// so different branches of the same repository do not share a checkout
+    #[must_use]
+    pub fn cache_key(&self) -> String {
+        let name = self
+            .url
+            .trim_end_matches('/')
+            .trim_end_matches(".git")
+            .rsplit(['/', ':', '\\'])
+            .next()
+            .unwrap_or("repo");
+        let name: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+
+        // FNV-1a keeps the key stable across builds, unlike `DefaultHasher`
+        let spec = format!("{}#{}", self.url, self.branch.as_deref().unwrap_or(""));
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in spec.bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        format!("{}-{:08x}", if name.is_empty() { "repo" } else { &name }, hash as u32)
+    }
+}
+
+/// A checked-out repository; temporary checkouts are deleted on drop
+#[derive(Debug)]
+pub struct FetchedRepository {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl FetchedRepository {
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for FetchedRepository {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+/// Default location for cached clones: `$XDG_CACHE_HOME/similarity/repos`,
+/// falling back to `~/.cache/similarity/repos`
+#[must_use]
+pub fn default_cache_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("similarity").join("repos"))
+}
+
+/// Shallow-clone `spec`, reusing and updating an existing clone in `cache_dir` if given
+pub fn fetch_repository(spec: &RemoteSpec, cache_dir: Option<&Path>) -> Result<FetchedRepository> {
+    let Some(cache_dir) = cache_dir else {
+        let path = std::env::temp_dir().join(format!(
+            "similarity-{}-{}",
+            std::process::id(),
+            spec.cache_key()
+        ));
+        // Leftovers from a previous run with the same pid are not ours to keep
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        let fetched = FetchedRepository { path, temporary: true };
+        clone(spec, &fetched.path)?;
+        return Ok(fetched);
+    };
+
+    fs::create_dir_all(cache_dir)
+        .with_context(|| format!("Failed to create cache directory {}", cache_dir.display()))?;
+    let path = cache_dir.join(spec.cache_key());
+
+    if path.join(".git").exists() {
+        if update(spec, &path).is_ok() {
+            return Ok(FetchedRepository { path, temporary: false });
+        }
+        // A broken or diverged cache is cheaper to replace than to repair
+        fs::remove_dir_all(&path)?;
+    } else if path.exists() {
+        fs::remove_dir_all(&path)?;
+    }
+
+    clone(spec, &path)?;
+    Ok(FetchedRepository { path, temporary: false })
+}
+
+fn clone(spec: &RemoteSpec, target: &Path) -> Result<()> {
+    let mut command = Command::new("git");
+    command.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(branch) = &spec.branch {
+        command.args(["--branch", branch]);
+    }
+    command.arg(&spec.url).arg(target);
+
+    let result = run_git(&mut command);
+    if result.is_err() && target.exists() {
+        let _ = fs::remove_dir_all(target);
+    }
+    result.with_context(|| format!("Failed to clone {}", spec.url))
+}
+
+fn update(spec: &RemoteSpec, path: &Path) -> Result<()> {
+    let reference = spec.branch.as_deref().unwrap_or("HEAD");
+    run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(path)
+            .args(["fetch", "--quiet", "--depth", "1", "origin", reference]),
+    )?;
+    run_git(Command::new("git").arg("-C").arg(path).args([
+        "reset",
+        "--quiet",
+        "--hard",
+        "FETCH_HEAD",
+    ]))
+}
+
+fn run_git(command: &mut Command) -> Result<()> {
+    let output = command
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .context("Failed to run git; is it installed and on PATH?")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=dev", "-c", "user.email=dev@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    /// A local repository with `main` and `feature` branches
+    fn origin() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q", "-b", "main"]);
+        fs::write(dir.path().join("a.ts"), "export const a = 1;\n").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-q", "-m", "a"]);
+        git(dir.path(), &["checkout", "-q", "-b", "feature"]);
+        fs::write(dir.path().join("b.ts"), "export const b = 2;\n").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-q", "-m", "b"]);
+        git(dir.path(), &["checkout", "-q", "main"]);
+        dir
+    }
+
+    #[test]
+    fn parses_branch_suffix() {
+        let spec = RemoteSpec::parse("https://github.com/org/repo#dev");
+        assert_eq!(spec.url, "https://github.com/org/repo");
+        assert_eq!(spec.branch.as_deref(), Some("dev"));
+
+        let spec = RemoteSpec::parse("git@github.com:org/repo.git");
+        assert_eq!(spec.branch, None);
+        assert!(spec.cache_key().starts_with("repo-"));
+        assert_ne!(
+            spec.cache_key(),
+            RemoteSpec::parse("git@github.com:org/repo.git#v1").cache_key()
+        );
+    }
+
+    #[test]
+    fn temporary_clone_is_removed_on_drop() {
+        let origin = origin();
+        let spec = RemoteSpec::parse(&format!("file://{}#feature", origin.path().display()));
+
+        let fetched = fetch_repository(&spec, None).unwrap();
+        let path = fetched.path().to_path_buf();
+        assert!(path.join("b.ts").exists());
+
+        drop(fetched);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn cached_clone_is_updated() {
+        let origin = origin();
+        let cache = TempDir::new().unwrap();
+        let spec = RemoteSpec::parse(&format!("file://{}", origin.path().display()));
+
+        let first = fetch_repository(&spec, Some(cache.path())).unwrap();
+        assert!(!first.path().join("b.ts").exists());
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(path.exists(), "cached clones are kept");
+
+        fs::write(origin.path().join("c.ts"), "export const c = 3;\n").unwrap();
+        git(origin.path(), &["add", "."]);
+        git(origin.path(), &["commit", "-q", "-m", "c"]);
+
+        let second = fetch_repository(&spec, Some(cache.path())).unwrap();
+        assert_eq!(second.path(), path);
+        assert!(second.path().join("c.ts").exists());
+    }
+}
@@ -0,0 +1,280 @@
+//! Differences between two JSON result files (`--output json`).
+//!
+//! Findings are matched by their `id`: the content ID of the functions they
+//! cover, which survives renames and moves. A finding whose ID drifted by at
+//! most [`MAX_DRIFT_BITS`] bits after a small edit still matches its earlier
+//! version. Findings of older files without an `id` are keyed by their files
+//! and symbols, or by their message with the numbers left out.
+
+use crate::triage::{content_id_distance, finding_id, MAX_DRIFT_BITS};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Smallest similarity change reported, half a displayed percentage point
+const SCORE_EPSILON: f64 = 0.005;
+
+/// One finding of a result file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportFinding {
+    pub id: String,
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+    /// 0.0-1.0, from `similarity` or the percentage in the message
+    pub similarity: Option<f64>,
+    /// The finding as written
+    pub value: Value,
+}
+
+impl ReportFinding {
+    pub fn from_value(value: Value) -> Result<Self> {
+        let text = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+        let file = text("file").context("finding without 'file'")?;
+        let message = text("message").context("finding without 'message'")?;
+        let line = value.get("line").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let similarity = value
+            .get("similarity")
+            .and_then(Value::as_f64)
+            .or_else(|| message_percentage(&message));
+        let id = text("id").unwrap_or_else(|| fallback_id(&value, &file, &message));
+        Ok(Self { id, file, line, message, similarity, value })
+    }
+}
+
+/// `NN%` of a message such as "Function 'a' is 92% similar to 'b'", as 0.0-1.0
+fn message_percentage(message: &str) -> Option<f64> {
+    let end = message.find('%')?;
+    let start = message[..end]
+        .rfind(|c: char| !c.is_ascii_digit() && c != '.')
+        .map_or(0, |position| position + 1);
+    message[start..end].parse::<f64>().ok().map(|percent| percent / 100.0)
+}
+
+/// Name-based ID of a finding written without one
+fn fallback_id(value: &Value, file: &str, message: &str) -> String {
+    let symbol = |finding: &Value| {
+        finding.pointer("/symbol/path").and_then(Value::as_str).map(str::to_string)
+    };
+    let subject =
+        symbol(value).unwrap_or_else(|| message.chars().filter(|c| !c.is_ascii_digit()).collect());
+    let related = value.get("related").unwrap_or(&Value::Null);
+    let related_file = related.get("file").and_then(Value::as_str).unwrap_or(file);
+    let related_subject = symbol(related).unwrap_or_else(|| subject.clone());
+    finding_id([(file, subject.as_str()), (related_file, related_subject.as_str())])
+}
+
+/// Findings of a result file: one JSON object per line, or a JSON array
+pub fn parse_findings(content: &str) -> Result<Vec<ReportFinding>> {
+    let values: Vec<Value> = if content.trim_start().starts_with('[') {
+        serde_json::from_str(content).context("invalid JSON array")?
+    } else {
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).with_context(|| format!("line {}", index + 1))
+            })
+            .collect::<Result<_>>()?
+    };
+    values.into_iter().map(ReportFinding::from_value).collect()
+}
+
+pub fn load_findings(path: &Path) -> Result<Vec<ReportFinding>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    parse_findings(&content).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// A finding present in both files whose similarity changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreChange {
+    pub old: ReportFinding,
+    pub new: ReportFinding,
+}
+
+impl ScoreChange {
+    /// New similarity minus the old one
+    pub fn delta(&self) -> f64 {
+        self.new.similarity.unwrap_or(0.0) - self.old.similarity.unwrap_or(0.0)
+    }
+}
+
+/// Findings introduced, resolved and rescored between two result files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReportDiff {
+    pub introduced: Vec<ReportFinding>,
+    pub resolved: Vec<ReportFinding>,
+    pub changed: Vec<ScoreChange>,
+    /// Findings in both files with the same similarity
+    pub unchanged: usize,
+}
+
+impl ReportDiff {
+    /// e.g. "+3 new duplicates, -1 resolved, 2 score changes"
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "+{} new {}, -{} resolved",
+            self.introduced.len(),
+            if self.introduced.len() == 1 { "duplicate" } else { "duplicates" },
+            self.resolved.len()
+        );
+        if !self.changed.is_empty() {
+            summary.push_str(&format!(
+                ", {} score {}",
+                self.changed.len(),
+                if self.changed.len() == 1 { "change" } else { "changes" }
+            ));
+        }
+        summary
+    }
+
+    pub fn to_json(&self) -> Value {
+        let findings = |findings: &[ReportFinding]| {
+            findings.iter().map(|f| f.value.clone()).collect::<Vec<_>>()
+        };
+        json!({
+            "summary": {
+                "new": self.introduced.len(),
+                "resolved": self.resolved.len(),
+                "changed": self.changed.len(),
+                "unchanged": self.unchanged,
+            },
+            "new": findings(&self.introduced),
+            "resolved": findings(&self.resolved),
+            "changed": self.changed.iter().map(|change| json!({
+                "old": change.old.value,
+                "new": change.new.value,
+                "delta": change.delta(),
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Compare the findings of an earlier and a later run
+pub fn diff_reports(old: Vec<ReportFinding>, new: Vec<ReportFinding>) -> ReportDiff {
+    let mut old: Vec<Option<ReportFinding>> = old.into_iter().map(Some).collect();
+    let mut by_id: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, finding) in old.iter().enumerate().rev() {
+        if let Some(finding) = finding {
+            by_id.entry(finding.id.clone()).or_default().push(index);
+        }
+    }
+
+    let mut matched = Vec::new();
+    let mut unmatched = Vec::new();
+    for finding in new {
+        match by_id.get_mut(&finding.id).and_then(Vec::pop) {
+            Some(index) => matched.push((old[index].take().unwrap(), finding)),
+            None => unmatched.push(finding),
+        }
+    }
+
+    // Drifted content IDs, closest pairs first
+    let mut candidates: Vec<(u32, usize, usize)> = unmatched
+        .iter()
+        .enumerate()
+        .flat_map(|(new_index, finding)| {
+            old.iter().enumerate().filter_map(move |(old_index, earlier)| {
+                let distance = content_id_distance(&finding.id, &earlier.as_ref()?.id)?;
+                (distance <= MAX_DRIFT_BITS).then_some((distance, new_index, old_index))
+            })
+        })
+        .collect();
+    candidates.sort_unstable();
+    let mut drifted = vec![None; unmatched.len()];
+    for (_, new_index, old_index) in candidates {
+        if drifted[new_index].is_none() && old[old_index].is_some() {
+            drifted[new_index] = old[old_index].take();
+        }
+    }
+
+    let mut diff = ReportDiff::default();
+    for (finding, earlier) in unmatched.into_iter().zip(drifted) {
+        match earlier {
+            Some(earlier) => matched.push((earlier, finding)),
+            None => diff.introduced.push(finding),
+        }
+    }
+    diff.resolved = old.into_iter().flatten().collect();
+    for (old, new) in matched {
+        match (old.similarity, new.similarity) {
+            (Some(before), Some(after)) if (after - before).abs() >= SCORE_EPSILON => {
+                diff.changed.push(ScoreChange { old, new })
+            }
+            _ => diff.unchanged += 1,
+        }
+    }
+    diff.changed.sort_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(id: &str, file: &str, similarity: f64) -> String {
+        json!({
+            "id": id,
+            "file": file,
+            "line": 1,
+            "severity": "warning",
+            "message": format!("Function 'a' is {:.0}% similar to 'b'", similarity * 100.0),
+            "similarity": similarity,
+            "related": { "file": "src/b.ts", "line": 1 },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_new_resolved_and_changed_findings() {
+        let old = [
+            finding("00000000000000ff", "src/a.ts", 0.9),
+            finding("1111111111111111", "src/c.ts", 0.95),
+            finding("2222222222222222", "src/d.ts", 0.88),
+        ]
+        .join("\n");
+        let new = [
+            // Moved, and its content drifted by one bit
+            finding("00000000000000fe", "lib/a.ts", 0.93),
+            finding("2222222222222222", "src/d.ts", 0.88),
+            finding("f0f0f0f0f0f0f0f0", "src/e.ts", 0.97),
+        ]
+        .join("\n");
+
+        let diff = diff_reports(parse_findings(&old).unwrap(), parse_findings(&new).unwrap());
+        assert_eq!(diff.introduced.len(), 1);
+        assert_eq!(diff.introduced[0].file, "src/e.ts");
+        assert_eq!(diff.resolved.len(), 1);
+        assert_eq!(diff.resolved[0].file, "src/c.ts");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].new.file, "lib/a.ts");
+        assert!((diff.changed[0].delta() - 0.03).abs() < 1e-9);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.summary(), "+1 new duplicate, -1 resolved, 1 score change");
+    }
+
+    #[test]
+    fn test_findings_without_ids() {
+        let old = r#"[{"file":"a.css","line":3,"severity":"info","message":"Rule '.btn' is 91% similar to '.button'","related":{"file":"b.css","line":7}}]"#;
+        let new = r#"{"file":"a.css","line":5,"severity":"info","message":"Rule '.btn' is 96% similar to '.button'","related":{"file":"b.css","line":9}}"#;
+        let (old, new) = (parse_findings(old).unwrap(), parse_findings(new).unwrap());
+        assert_eq!(old[0].similarity, Some(0.91));
+        assert_eq!(old[0].id, new[0].id);
+
+        let diff = diff_reports(old, new);
+        assert!(diff.introduced.is_empty() && diff.resolved.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.summary(), "+0 new duplicates, -0 resolved, 1 score change");
+    }
+
+    #[test]
+    fn test_invalid_lines_are_reported() {
+        let error = parse_findings("{\"file\":\"a.ts\",\"message\":\"m\"}\n{oops").unwrap_err();
+        assert_eq!(error.to_string(), "line 2");
+        assert!(parse_findings(r#"{"file":"a.ts"}"#).is_err());
+    }
+}
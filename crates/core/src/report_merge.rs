@@ -0,0 +1,86 @@
+//! Merging of the JSON result files of a sharded run (`--shard`).
+//!
+//! Each shard reports the duplicates among the comparisons it owns, and the
+//! analyses that are not partitioned may be reported by several shards. The
+//! merged report holds every finding once: findings with the same `id` at the
+//! same two locations are one finding, of which the highest similarity is
+//! kept. The locations matter because copies of the same code share an `id`.
+
+use crate::report_diff::ReportFinding;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Location, related location and ID of a finding
+type FindingKey = (String, usize, Option<String>, Option<u64>, String);
+
+fn finding_key(finding: &ReportFinding) -> FindingKey {
+    let related = finding.value.get("related");
+    (
+        finding.file.clone(),
+        finding.line,
+        related.and_then(|r| r.get("file")).and_then(Value::as_str).map(str::to_string),
+        related.and_then(|r| r.get("line")).and_then(Value::as_u64),
+        finding.id.clone(),
+    )
+}
+
+/// Findings of all `reports` without duplicates, ordered by file and line
+pub fn merge_reports(reports: Vec<Vec<ReportFinding>>) -> Vec<ReportFinding> {
+    let mut merged: Vec<ReportFinding> = Vec::new();
+    let mut by_key: HashMap<FindingKey, usize> = HashMap::new();
+    for finding in reports.into_iter().flatten() {
+        let key = finding_key(&finding);
+        match by_key.get(&key) {
+            Some(&index) => {
+                let kept = &mut merged[index];
+                if finding.similarity.unwrap_or(0.0) > kept.similarity.unwrap_or(0.0) {
+                    *kept = finding;
+                }
+            }
+            None => {
+                by_key.insert(key, merged.len());
+                merged.push(finding);
+            }
+        }
+    }
+    merged.sort_by_cached_key(finding_key);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report_diff::parse_findings;
+    use serde_json::json;
+
+    fn finding(id: &str, file: &str, line: usize, similarity: f64) -> String {
+        json!({
+            "id": id,
+            "file": file,
+            "line": line,
+            "severity": "warning",
+            "message": format!("Function 'a' is {:.0}% similar to 'b'", similarity * 100.0),
+            "similarity": similarity,
+            "related": { "file": "src/c.ts", "line": 1 },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_merge_keeps_each_finding_once() {
+        let first = [finding("aaaa", "src/b.ts", 4, 0.9), finding("bbbb", "src/a.ts", 1, 0.95)];
+        let second = [finding("aaaa", "src/b.ts", 4, 0.92), finding("cccc", "src/a.ts", 9, 0.88)];
+        // A copy of the same code elsewhere is another finding
+        let copy = finding("aaaa", "src/b.ts", 4, 0.9).replace("src/c.ts", "src/d.ts");
+        let merged = merge_reports(vec![
+            parse_findings(&first.join("\n")).unwrap(),
+            parse_findings(&second.join("\n")).unwrap(),
+            parse_findings(&copy).unwrap(),
+        ]);
+
+        let ids: Vec<&str> = merged.iter().map(|finding| finding.id.as_str()).collect();
+        assert_eq!(ids, ["bbbb", "cccc", "aaaa", "aaaa"]);
+        assert_eq!(merged[2].similarity, Some(0.92));
+        assert_eq!(merged[3].value["related"]["file"], "src/d.ts");
+    }
+}
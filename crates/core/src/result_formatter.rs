@@ -0,0 +1,245 @@
+//! Plugin interface for output formats.
+//!
+//! A [`ResultFormatter`] renders the findings of a run. Crates embedding the
+//! library, or wrapper CLIs, implement the trait and register it in a
+//! [`FormatterRegistry`] to add an output format without forking; the
+//! built-in `text`, `vscode` and `json` formats are formatters themselves.
+//! Line-oriented formats only implement [`ResultFormatter::format_finding`];
+//! formats wrapping all findings in one document override
+//! [`ResultFormatter::write_findings`] as well, and formats with room for the
+//! details some analyzers add (content IDs, symbols, property diffs)
+//! override [`ResultFormatter::format_detailed`].
+
+use crate::output_schema::JsonFinding;
+use crate::quickfix::Fix;
+use crate::severity::Severity;
+use std::io::{self, Write};
+
+/// A finding as handed to formatters: a location, what was found there and
+/// the location it relates to (the other side of a duplicate pair)
+#[derive(Debug, Clone, Copy)]
+pub struct Finding<'a> {
+    pub file: &'a str,
+    pub line: usize,
+    pub severity: Severity,
+    pub message: &'a str,
+    pub related_file: &'a str,
+    pub related_line: usize,
+    /// Edits resolving the finding, when the analyzer knows them
+    pub fixes: &'a [Fix],
+}
+
+/// Extension point for output formats
+pub trait ResultFormatter {
+    /// Unique name used to select the format (e.g. `--output json`)
+    fn name(&self) -> &str;
+
+    /// Render one finding, for output written while the analysis runs
+    fn format_finding(&self, finding: &Finding) -> String;
+
+    /// Render one finding with the details its analyzer knows. Defaults to
+    /// [`format_finding`](Self::format_finding), leaving the details out.
+    fn format_detailed(&self, finding: &JsonFinding) -> String {
+        self.format_finding(&finding.as_finding())
+    }
+
+    /// Write all findings of a run. Defaults to one finding per line.
+    fn write_findings(&self, findings: &[Finding], out: &mut dyn Write) -> io::Result<()> {
+        for finding in findings {
+            writeln!(out, "{}", self.format_finding(finding))?;
+        }
+        Ok(())
+    }
+}
+
+/// Human-readable findings, one block per finding
+pub struct TextFormatter;
+
+impl ResultFormatter for TextFormatter {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    fn format_finding(&self, finding: &Finding) -> String {
+        format!(
+            "{}: {}\n  --> {}:{}\n  --> {}:{}",
+            finding.severity,
+            finding.message,
+            finding.file,
+            finding.line,
+            finding.related_file,
+            finding.related_line
+        )
+    }
+}
+
+/// One line per finding, matched by the default VSCode problem matcher. Both
+/// locations are emitted as `file:line:col` so each one can be linked.
+pub struct VscodeFormatter;
+
+impl ResultFormatter for VscodeFormatter {
+    fn name(&self) -> &str {
+        "vscode"
+    }
+
+    fn format_finding(&self, finding: &Finding) -> String {
+        format!(
+            "{}:{}:1: {}: {} at {}:{}:1",
+            finding.file,
+            finding.line,
+            finding.severity,
+            finding.message,
+            finding.related_file,
+            finding.related_line
+        )
+    }
+}
+
+/// One JSON object per line (see `finding_json_schema`), with the fixes that
+/// resolve the finding
+pub struct JsonFormatter;
+
+impl JsonFormatter {
+    /// The JSON object of a finding, for callers adding fields of their own
+    #[must_use]
+    pub fn value(finding: &Finding) -> serde_json::Value {
+        serde_json::to_value(JsonFinding::new(finding)).unwrap_or_default()
+    }
+}
+
+impl ResultFormatter for JsonFormatter {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn format_finding(&self, finding: &Finding) -> String {
+        JsonFinding::new(finding).to_string()
+    }
+
+    fn format_detailed(&self, finding: &JsonFinding) -> String {
+        finding.to_string()
+    }
+}
+
+/// Output formats available by name
+#[derive(Default)]
+pub struct FormatterRegistry {
+    formatters: Vec<Box<dyn ResultFormatter>>,
+}
+
+impl FormatterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with the `text`, `vscode` and `json` formats
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(TextFormatter));
+        registry.register(Box::new(VscodeFormatter));
+        registry.register(Box::new(JsonFormatter));
+        registry
+    }
+
+    /// Register a formatter, replacing any previous one with the same name
+    pub fn register(&mut self, formatter: Box<dyn ResultFormatter>) {
+        self.formatters.retain(|existing| existing.name() != formatter.name());
+        self.formatters.push(formatter);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn ResultFormatter> {
+        self.formatters.iter().find(|formatter| formatter.name() == name).map(|f| f.as_ref())
+    }
+
+    /// Registered format names in registration order
+    pub fn names(&self) -> Vec<&str> {
+        self.formatters.iter().map(|formatter| formatter.name()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Findings as rows of a CSV document with a header
+    struct CsvFormatter;
+
+    impl ResultFormatter for CsvFormatter {
+        fn name(&self) -> &str {
+            "csv"
+        }
+
+        fn format_finding(&self, finding: &Finding) -> String {
+            format!(
+                "{},{},{},{},{}",
+                finding.file,
+                finding.line,
+                finding.related_file,
+                finding.related_line,
+                finding.severity
+            )
+        }
+
+        fn write_findings(&self, findings: &[Finding], out: &mut dyn Write) -> io::Result<()> {
+            writeln!(out, "file,line,related_file,related_line,severity")?;
+            for finding in findings {
+                writeln!(out, "{}", self.format_finding(finding))?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_formatters_are_registered_next_to_the_builtins() {
+        let finding = Finding {
+            file: "a.ts",
+            line: 3,
+            severity: Severity::Error,
+            message: "Function 'add' is 95% similar to 'sum'",
+            related_file: "b.ts",
+            related_line: 7,
+            fixes: &[],
+        };
+        let mut registry = FormatterRegistry::with_builtins();
+        registry.register(Box::new(CsvFormatter));
+        assert_eq!(registry.names(), ["text", "vscode", "json", "csv"]);
+
+        let render = |name: &str| {
+            let mut out = Vec::new();
+            registry.get(name).unwrap().write_findings(&[finding, finding], &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(render("csv"), "file,line,related_file,related_line,severity\na.ts,3,b.ts,7,error\na.ts,3,b.ts,7,error\n");
+        assert_eq!(
+            render("vscode").lines().next(),
+            Some("a.ts:3:1: error: Function 'add' is 95% similar to 'sum' at b.ts:7:1")
+        );
+        assert_eq!(render("text").lines().count(), 6);
+        assert!(registry.get("sarif").is_none());
+
+        registry.register(Box::new(CsvFormatter));
+        assert_eq!(registry.names().len(), 4);
+    }
+
+    #[test]
+    fn test_details_reach_formatters_with_room_for_them() {
+        let fixes = Vec::new();
+        let mut detailed = JsonFinding::new(&Finding {
+            file: "a.ts",
+            line: 3,
+            severity: Severity::Warning,
+            message: "Function 'add' is 95% similar to 'sum'",
+            related_file: "b.ts",
+            related_line: 7,
+            fixes: &fixes,
+        });
+        detailed.id = Some("f746c479fc763a78".to_string());
+        detailed.similarity = Some(0.95);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&JsonFormatter.format_detailed(&detailed)).unwrap();
+        assert_eq!(json["id"], "f746c479fc763a78");
+        assert_eq!(json["similarity"], 0.95);
+        assert_eq!(CsvFormatter.format_detailed(&detailed), "a.ts,3,b.ts,7,warning");
+    }
+}
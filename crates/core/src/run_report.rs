@@ -0,0 +1,285 @@
+//! Files left out of an analysis run, and the severity of its findings.
+//!
+//! Analyzers record every file they skip together with the reason, so a run
+//! can end with a summary of what was not analyzed instead of ignoring it
+//! silently. A file skipped by several analyzers is reported once, with the
+//! first recorded reason. Files with syntax errors whose remaining code was
+//! still analyzed are reported as partially parsed.
+//!
+//! Analyzers also classify every finding they report through the run's
+//! severity rules, so the exit code can depend on the worst finding of any
+//! analyzer. Findings allowed by the rules in `similarity.toml` are dropped
+//! before that and only counted.
+
+use crate::severity::{Severity, SeverityPolicy};
+use crate::suppression::Suppressions;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The file has syntax the parser rejects; holds the parser's message
+    ParseError(String),
+    /// The file could not be read; holds the I/O error
+    Unreadable(String),
+    /// The file matched an `--exclude` pattern
+    Excluded,
+    /// The file was given explicitly but its extension is not analyzed
+    UnsupportedExtension,
+}
+
+impl SkipReason {
+    fn category(&self) -> &'static str {
+        match self {
+            SkipReason::ParseError(_) => "parse errors",
+            SkipReason::Unreadable(_) => "unreadable",
+            SkipReason::Excluded => "excluded",
+            SkipReason::UnsupportedExtension => "unsupported extension",
+        }
+    }
+
+    fn detail(&self) -> Option<&str> {
+        match self {
+            SkipReason::ParseError(detail) | SkipReason::Unreadable(detail) => Some(detail),
+            SkipReason::Excluded | SkipReason::UnsupportedExtension => None,
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.detail() {
+            Some(detail) => write!(f, "{}: {}", self.category(), detail),
+            None => f.write_str(self.category()),
+        }
+    }
+}
+
+/// Collects skipped files and finding severities across analyzers; safe to share between threads
+#[derive(Debug, Default)]
+pub struct RunReport {
+    skipped: Mutex<BTreeMap<String, SkipReason>>,
+    partial: Mutex<BTreeSet<String>>,
+    severity_policy: SeverityPolicy,
+    /// Findings per severity and analyzer
+    findings: Mutex<BTreeMap<(Severity, String), usize>>,
+    suppressions: Suppressions,
+    /// Suppressed findings per analyzer
+    suppressed: Mutex<BTreeMap<String, usize>>,
+}
+
+impl RunReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report classifying findings with the given severity rules
+    pub fn with_severity_policy(severity_policy: SeverityPolicy) -> Self {
+        Self { severity_policy, ..Self::default() }
+    }
+
+    /// Whether findings are classified by configured rules rather than all being warnings
+    pub fn has_severity_rules(&self) -> bool {
+        self.severity_policy.is_configured()
+    }
+
+    /// Assign a severity to a reported finding of `analyzer` and count it
+    pub fn classify(&self, analyzer: &str, similarity: f64) -> Severity {
+        let severity = self.severity_policy.assign(analyzer, similarity);
+        let mut findings = self.findings.lock().unwrap_or_else(|e| e.into_inner());
+        *findings.entry((severity, analyzer.to_string())).or_default() += 1;
+        severity
+    }
+
+    /// Number of classified findings at `severity` or above
+    pub fn findings_at_least(&self, severity: Severity) -> usize {
+        let findings = self.findings.lock().unwrap_or_else(|e| e.into_inner());
+        findings.iter().filter(|((level, _), _)| *level >= severity).map(|(_, count)| count).sum()
+    }
+
+    /// Classified findings per severity, most severe first, broken down by
+    /// analyzer; `None` without severity rules or findings
+    pub fn severity_summary(&self) -> Option<String> {
+        let findings = self.findings.lock().unwrap_or_else(|e| e.into_inner());
+        if !self.has_severity_rules() || findings.is_empty() {
+            return None;
+        }
+
+        let mut lines = vec!["Findings by severity:".to_string()];
+        for severity in Severity::ALL.into_iter().rev() {
+            let analyzers: Vec<_> = findings
+                .iter()
+                .filter(|((level, _), _)| *level == severity)
+                .map(|((_, analyzer), count)| (analyzer, *count))
+                .collect();
+            if analyzers.is_empty() {
+                continue;
+            }
+            let total: usize = analyzers.iter().map(|(_, count)| count).sum();
+            let breakdown: Vec<_> = analyzers
+                .iter()
+                .map(|(analyzer, count)| format!("{}: {}", analyzer, count))
+                .collect();
+            lines.push(format!("  {}: {} ({})", severity, total, breakdown.join(", ")));
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Report dropping findings the given allow rules permit
+    pub fn with_suppressions(self, suppressions: Suppressions) -> Self {
+        Self { suppressions, ..self }
+    }
+
+    /// Whether a finding of `analyzer` between two `(file, symbol)` sides is
+    /// allowed; allowed findings are counted and must not be reported
+    pub fn suppress(&self, analyzer: &str, first: (&Path, &str), second: (&Path, &str)) -> bool {
+        if !self.suppressions.allows(first, second) {
+            return false;
+        }
+        let mut suppressed = self.suppressed.lock().unwrap_or_else(|e| e.into_inner());
+        *suppressed.entry(analyzer.to_string()).or_default() += 1;
+        true
+    }
+
+    /// Suppressed findings by analyzer; `None` when nothing was suppressed
+    pub fn suppression_summary(&self) -> Option<String> {
+        let suppressed = self.suppressed.lock().unwrap_or_else(|e| e.into_inner());
+        if suppressed.is_empty() {
+            return None;
+        }
+        let total: usize = suppressed.values().sum();
+        let breakdown: Vec<_> =
+            suppressed.iter().map(|(analyzer, count)| format!("{}: {}", analyzer, count)).collect();
+        Some(format!(
+            "Suppressed {} finding(s) by allow rules in similarity.toml ({})",
+            total,
+            breakdown.join(", ")
+        ))
+    }
+
+    /// Record that `file` was skipped, unless it already was
+    pub fn skip(&self, file: &str, reason: SkipReason) {
+        let mut skipped = self.skipped.lock().unwrap_or_else(|e| e.into_inner());
+        skipped.entry(file.to_string()).or_insert(reason);
+    }
+
+    /// Skipped files and their reasons, ordered by path
+    pub fn skipped(&self) -> Vec<(String, SkipReason)> {
+        let skipped = self.skipped.lock().unwrap_or_else(|e| e.into_inner());
+        skipped.iter().map(|(file, reason)| (file.clone(), reason.clone())).collect()
+    }
+
+    /// Record that `file` has syntax errors but the rest of it was analyzed
+    pub fn mark_partial(&self, file: &str) {
+        let mut partial = self.partial.lock().unwrap_or_else(|e| e.into_inner());
+        partial.insert(file.to_string());
+    }
+
+    /// Partially parsed files, ordered by path
+    pub fn partially_parsed(&self) -> Vec<String> {
+        let partial = self.partial.lock().unwrap_or_else(|e| e.into_inner());
+        partial.iter().cloned().collect()
+    }
+
+    /// Number of files skipped because they failed to parse
+    pub fn parse_failures(&self) -> usize {
+        self.skipped()
+            .iter()
+            .filter(|(_, reason)| matches!(reason, SkipReason::ParseError(_)))
+            .count()
+    }
+
+    /// Skipped file counts per reason, listing the files that failed to parse or read,
+    /// followed by the partially parsed files; `None` when every file was fully analyzed
+    pub fn summary(&self) -> Option<String> {
+        let skipped = self.skipped();
+        let partial = self.partially_parsed();
+        if skipped.is_empty() && partial.is_empty() {
+            return None;
+        }
+
+        let mut lines = Vec::new();
+        if !skipped.is_empty() {
+            lines.push(Self::skipped_summary(&skipped));
+        }
+        if !partial.is_empty() {
+            lines.push(format!(
+                "Partially parsed {} file(s) with syntax errors; their findings are marked (partial):",
+                partial.len()
+            ));
+            lines.extend(partial.iter().map(|file| format!("  {}", file)));
+        }
+        Some(lines.join("\n"))
+    }
+
+    fn skipped_summary(skipped: &[(String, SkipReason)]) -> String {
+        let mut by_category: BTreeMap<&str, Vec<&(String, SkipReason)>> = BTreeMap::new();
+        for entry in skipped {
+            by_category.entry(entry.1.category()).or_default().push(entry);
+        }
+
+        let mut summary = format!("Skipped {} file(s):", skipped.len());
+        for (category, entries) in by_category {
+            summary.push_str(&format!("\n  {}: {}", category, entries.len()));
+            for (file, reason) in entries {
+                if let Some(detail) = reason.detail() {
+                    summary.push_str(&format!("\n    {}: {}", file, detail));
+                }
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_groups_reasons() {
+        let report = RunReport::new();
+        assert!(report.summary().is_none());
+
+        report.skip("src/b.ts", SkipReason::Excluded);
+        report.skip("src/a.ts", SkipReason::ParseError("Unexpected token at 2:10".to_string()));
+        report.skip("src/c.ts", SkipReason::Excluded);
+        // The first reason recorded for a file wins
+        report.skip("src/a.ts", SkipReason::Unreadable("permission denied".to_string()));
+
+        assert_eq!(report.parse_failures(), 1);
+        assert_eq!(
+            report.summary().unwrap(),
+            "Skipped 3 file(s):\n  excluded: 2\n  parse errors: 1\n    src/a.ts: Unexpected token at 2:10"
+        );
+
+        report.mark_partial("src/d.ts");
+        assert!(report.summary().unwrap().ends_with("marked (partial):\n  src/d.ts"));
+    }
+
+    #[test]
+    fn test_severity_tally() {
+        let report = RunReport::new();
+        assert_eq!(report.classify("functions", 0.5), Severity::Warning);
+        assert_eq!(report.findings_at_least(Severity::Warning), 1);
+        assert!(report.severity_summary().is_none());
+
+        let rules: crate::severity::SeverityConfig = toml::from_str(
+            "[[severity]]\nmin_similarity = 1.0\nlevel = \"error\"\n\n[[severity]]\nmin_similarity = 0.9\nlevel = \"warning\"\n",
+        )
+        .unwrap();
+        let report = RunReport::with_severity_policy(SeverityPolicy::new(rules.severity));
+        report.classify("functions", 1.0);
+        report.classify("types", 1.0);
+        report.classify("functions", 0.95);
+        report.classify("functions", 0.85);
+
+        assert_eq!(report.findings_at_least(Severity::Error), 2);
+        assert_eq!(report.findings_at_least(Severity::Info), 4);
+        assert_eq!(
+            report.severity_summary().unwrap(),
+            "Findings by severity:\n  error: 2 (functions: 1, types: 1)\n  warning: 1 (functions: 1)\n  info: 1 (functions: 1)"
+        );
+    }
+}
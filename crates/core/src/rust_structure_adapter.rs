@@ -1,3 +1,4 @@
+use crate::interner::Symbol;
 use crate::language_parser::GenericTypeDef;
 use crate::structure_comparator::{
     ComparisonOptions, SourceLocation, Structure, StructureComparator, StructureComparisonResult,
@@ -28,20 +29,25 @@ impl From<GenericTypeDef> for Structure {
                     (field.clone(), "unknown".to_string())
                 };
 
-                StructureMember { name, value_type, modifiers: vec![], nested: None }
+                StructureMember {
+                    name,
+                    value_type: value_type.into(),
+                    modifiers: vec![],
+                    nested: None,
+                }
             })
             .collect();
 
         Structure {
             identifier: StructureIdentifier {
-                name: type_def.name.clone(),
+                name: type_def.name.into(),
                 kind,
                 namespace: None, // Could be module path
             },
             members,
             metadata: StructureMetadata {
                 location: SourceLocation {
-                    file_path: String::new(), // Would need to pass this separately
+                    file_path: Symbol::default(), // Would need to pass this separately
                     start_line: type_def.start_line as usize,
                     end_line: type_def.end_line as usize,
                 },
@@ -110,7 +116,7 @@ impl From<RustStructDef> for Structure {
             .into_iter()
             .map(|field| StructureMember {
                 name: field.name,
-                value_type: field.field_type,
+                value_type: field.field_type.into(),
                 modifiers: field.visibility.map(|v| vec![v]).unwrap_or_default(),
                 nested: None,
             })
@@ -120,7 +126,7 @@ impl From<RustStructDef> for Structure {
         if !struct_def.derives.is_empty() {
             members.push(StructureMember {
                 name: "@derives".to_string(),
-                value_type: struct_def.derives.join(", "),
+                value_type: struct_def.derives.join(", ").into(),
                 modifiers: vec!["attribute".to_string()],
                 nested: None,
             });
@@ -130,7 +136,7 @@ impl From<RustStructDef> for Structure {
         if !struct_def.attributes.is_empty() {
             members.push(StructureMember {
                 name: "@attributes".to_string(),
-                value_type: struct_def.attributes.join(", "),
+                value_type: struct_def.attributes.join(", ").into(),
                 modifiers: vec!["attribute".to_string()],
                 nested: None,
             });
@@ -138,14 +144,14 @@ impl From<RustStructDef> for Structure {
 
         Structure {
             identifier: StructureIdentifier {
-                name: struct_def.name.clone(),
+                name: struct_def.name.into(),
                 kind: StructureKind::RustStruct,
-                namespace: Some(struct_def.file_path.clone()),
+                namespace: Some(struct_def.file_path.as_str().into()),
             },
             members,
             metadata: StructureMetadata {
                 location: SourceLocation {
-                    file_path: struct_def.file_path,
+                    file_path: struct_def.file_path.into(),
                     start_line: struct_def.start_line,
                     end_line: struct_def.end_line,
                 },
@@ -178,7 +184,7 @@ impl From<RustEnumDef> for Structure {
 
                 StructureMember {
                     name: variant.name,
-                    value_type,
+                    value_type: value_type.into(),
                     modifiers: vec!["variant".to_string()],
                     nested: None,
                 }
@@ -189,7 +195,7 @@ impl From<RustEnumDef> for Structure {
         if !enum_def.derives.is_empty() {
             members.push(StructureMember {
                 name: "@derives".to_string(),
-                value_type: enum_def.derives.join(", "),
+                value_type: enum_def.derives.join(", ").into(),
                 modifiers: vec!["attribute".to_string()],
                 nested: None,
             });
@@ -199,7 +205,7 @@ impl From<RustEnumDef> for Structure {
         if !enum_def.attributes.is_empty() {
             members.push(StructureMember {
                 name: "@attributes".to_string(),
-                value_type: enum_def.attributes.join(", "),
+                value_type: enum_def.attributes.join(", ").into(),
                 modifiers: vec!["attribute".to_string()],
                 nested: None,
             });
@@ -207,14 +213,14 @@ impl From<RustEnumDef> for Structure {
 
         Structure {
             identifier: StructureIdentifier {
-                name: enum_def.name.clone(),
+                name: enum_def.name.into(),
                 kind: StructureKind::RustEnum,
-                namespace: Some(enum_def.file_path.clone()),
+                namespace: Some(enum_def.file_path.as_str().into()),
             },
             members,
             metadata: StructureMetadata {
                 location: SourceLocation {
-                    file_path: enum_def.file_path,
+                    file_path: enum_def.file_path.into(),
                     start_line: enum_def.start_line,
                     end_line: enum_def.end_line,
                 },
@@ -0,0 +1,656 @@
+use crate::analyzer::{AnalysisUnit, Analyzer, AnalyzerError, AnalyzerFinding};
+use crate::cli_output::format_function_output;
+use crate::config_parser::{parse_json, parse_yaml, ConfigNode, ConfigValue};
+use crate::structure_comparator::{
+    ComparisonOptions, SourceLocation, Structure, StructureComparator, StructureIdentifier,
+    StructureKind, StructureMember, StructureMetadata,
+};
+use crate::tree::TreeNode;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// スキーマの種類（オブジェクト型と列挙型）
+const SCHEMA_OBJECT: &str = "schema";
+const SCHEMA_ENUM: &str = "schema-enum";
+
+/// GraphQL/OpenAPIの型名を共通の語彙に正規化
+fn normalize_scalar(name: &str) -> String {
+    match name {
+        "Int" | "integer" => "integer".to_string(),
+        "Float" | "number" => "number".to_string(),
+        "String" | "ID" | "string" => "string".to_string(),
+        "Boolean" | "boolean" => "boolean".to_string(),
+        "DateTime" | "date-time" => "datetime".to_string(),
+        "Date" | "date" => "date".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn schema_structure(
+    name: &str,
+    kind: &str,
+    members: Vec<StructureMember>,
+    extends: Vec<String>,
+    (file_path, start_line, end_line): (&str, usize, usize),
+) -> Structure {
+    Structure {
+        identifier: StructureIdentifier {
+            name: name.into(),
+            kind: StructureKind::Generic(kind.to_string()),
+            namespace: Some(file_path.into()),
+        },
+        members,
+        metadata: StructureMetadata {
+            location: SourceLocation { file_path: file_path.into(), start_line, end_line },
+            extends,
+            ..Default::default()
+        },
+    }
+}
+
+fn member(name: &str, value_type: String, required: bool) -> StructureMember {
+    StructureMember {
+        name: name.to_string(),
+        value_type: value_type.into(),
+        modifiers: if required { vec!["required".to_string()] } else { vec![] },
+        nested: None,
+    }
+}
+
+/// OpenAPI/Swaggerのスキーマ定義を構造に変換
+#[must_use]
+pub fn extract_openapi_schemas(document: &ConfigNode, file_path: &str) -> Vec<Structure> {
+    let is_openapi = document.get("openapi").is_some() || document.get("swagger").is_some();
+    if !is_openapi {
+        return Vec::new();
+    }
+
+    let schemas = document
+        .get("components")
+        .and_then(|components| components.get("schemas"))
+        .or_else(|| document.get("definitions"));
+    let Some(ConfigValue::Mapping(entries)) = schemas.map(|node| &node.value) else {
+        return Vec::new();
+    };
+
+    entries.iter().filter_map(|(name, schema)| openapi_schema(name, schema, file_path)).collect()
+}
+
+fn openapi_schema(name: &str, schema: &ConfigNode, file_path: &str) -> Option<Structure> {
+    let location = (file_path, schema.start_line, schema.end_line);
+
+    if let Some(ConfigValue::Sequence(values)) = schema.get("enum").map(|node| &node.value) {
+        let members =
+            values.iter().filter_map(enum_value).map(|v| member(&v, "value".to_string(), false));
+        return Some(schema_structure(name, SCHEMA_ENUM, members.collect(), vec![], location));
+    }
+
+    // allOf: 参照は継承として、インラインのプロパティはメンバーとして扱う
+    let mut parts = vec![schema];
+    let mut extends = Vec::new();
+    if let Some(ConfigValue::Sequence(all_of)) = schema.get("allOf").map(|node| &node.value) {
+        for part in all_of {
+            match part.get("$ref").map(|node| &node.value) {
+                Some(ConfigValue::String(reference)) => extends.push(ref_name(reference)),
+                _ => parts.push(part),
+            }
+        }
+    }
+
+    let mut members = Vec::new();
+    for part in parts {
+        let required: Vec<String> = match part.get("required").map(|node| &node.value) {
+            Some(ConfigValue::Sequence(items)) => items.iter().filter_map(enum_value).collect(),
+            _ => vec![],
+        };
+        if let Some(ConfigValue::Mapping(properties)) = part.get("properties").map(|n| &n.value) {
+            for (property, definition) in properties {
+                let value_type = openapi_type(definition);
+                members.push(member(property, value_type, required.contains(property)));
+            }
+        }
+    }
+
+    if members.is_empty() && extends.is_empty() {
+        return None;
+    }
+    Some(schema_structure(name, SCHEMA_OBJECT, members, extends, location))
+}
+
+fn enum_value(node: &ConfigNode) -> Option<String> {
+    match &node.value {
+        ConfigValue::String(value) | ConfigValue::Number(value) => Some(value.clone()),
+        ConfigValue::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn ref_name(reference: &str) -> String {
+    reference.rsplit('/').next().unwrap_or(reference).to_string()
+}
+
+/// プロパティ定義の型（`$ref`は参照先の名前、配列は`T[]`）
+fn openapi_type(definition: &ConfigNode) -> String {
+    if let Some(ConfigValue::String(reference)) = definition.get("$ref").map(|n| &n.value) {
+        return ref_name(reference);
+    }
+    if definition.get("allOf").is_some()
+        || definition.get("oneOf").is_some()
+        || definition.get("anyOf").is_some()
+    {
+        return "composite".to_string();
+    }
+
+    let type_name = match definition.get("type").map(|n| &n.value) {
+        Some(ConfigValue::String(type_name)) => type_name.as_str(),
+        _ if definition.get("properties").is_some() => "object",
+        _ => "any",
+    };
+    match type_name {
+        "array" => match definition.get("items") {
+            Some(items) => format!("{}[]", openapi_type(items)),
+            None => "any[]".to_string(),
+        },
+        "string" => match definition.get("format").map(|n| &n.value) {
+            Some(ConfigValue::String(format)) if format == "date-time" || format == "date" => {
+                normalize_scalar(format)
+            }
+            _ => "string".to_string(),
+        },
+        other => normalize_scalar(other),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum GraphqlToken {
+    Name(String),
+    Punct(char),
+}
+
+/// GraphQL SDLのトークン化（コメント・説明文字列・カンマは読み飛ばす）
+fn tokenize_graphql(source: &str) -> Vec<(GraphqlToken, usize)> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() || c == ',' {
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '"' {
+            let block = chars.get(i + 1) == Some(&'"') && chars.get(i + 2) == Some(&'"');
+            i += if block { 3 } else { 1 };
+            while i < chars.len() {
+                if block
+                    && chars[i] == '"'
+                    && chars.get(i + 1) == Some(&'"')
+                    && chars.get(i + 2) == Some(&'"')
+                {
+                    i += 3;
+                    break;
+                }
+                if !block && chars[i] == '"' {
+                    i += 1;
+                    break;
+                }
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                if chars.get(i) == Some(&'\n') {
+                    line += 1;
+                }
+                i += 1;
+            }
+        } else if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '-' | '.'))
+            {
+                i += 1;
+            }
+            tokens.push((GraphqlToken::Name(chars[start..i].iter().collect()), line));
+        } else {
+            tokens.push((GraphqlToken::Punct(c), line));
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+/// GraphQL SDLの型定義（type/input/interface/enum）を構造に変換
+#[must_use]
+pub fn extract_graphql_types(source: &str, file_path: &str) -> Vec<Structure> {
+    let tokens = tokenize_graphql(source);
+    let mut structures = Vec::new();
+    let mut i = 0;
+
+    let name_at = |i: usize| match tokens.get(i) {
+        Some((GraphqlToken::Name(name), _)) => Some(name.as_str()),
+        _ => None,
+    };
+    let punct_at =
+        |i: usize, c: char| matches!(tokens.get(i), Some((GraphqlToken::Punct(p), _)) if *p == c);
+
+    // `@directive(...)` を読み飛ばす
+    let skip_directives = |mut i: usize| {
+        while punct_at(i, '@') {
+            i += 2;
+            if punct_at(i, '(') {
+                i = skip_group(&tokens, i, '(', ')');
+            }
+        }
+        i
+    };
+
+    while i < tokens.len() {
+        let keyword = name_at(i);
+        let keyword = if keyword == Some("extend") {
+            i += 1;
+            name_at(i)
+        } else {
+            keyword
+        };
+
+        let Some(kind @ ("type" | "input" | "interface" | "enum")) = keyword else {
+            i += 1;
+            continue;
+        };
+        let start_line = tokens[i].1;
+        let Some(name) = name_at(i + 1).map(str::to_string) else {
+            i += 1;
+            continue;
+        };
+        i += 2;
+
+        let mut extends = Vec::new();
+        if name_at(i) == Some("implements") {
+            i += 1;
+            while let Some(interface) = name_at(i) {
+                extends.push(interface.to_string());
+                i += 1;
+                if punct_at(i, '&') {
+                    i += 1;
+                }
+            }
+        }
+        i = skip_directives(i);
+        if !punct_at(i, '{') {
+            continue;
+        }
+        i += 1;
+
+        let mut members = Vec::new();
+        while i < tokens.len() && !punct_at(i, '}') {
+            let Some(field) = name_at(i).map(str::to_string) else {
+                i += 1;
+                continue;
+            };
+            i += 1;
+
+            if kind == "enum" {
+                members.push(member(&field, "value".to_string(), false));
+                i = skip_directives(i);
+                continue;
+            }
+
+            if punct_at(i, '(') {
+                i = skip_group(&tokens, i, '(', ')');
+            }
+            if !punct_at(i, ':') {
+                continue;
+            }
+            let (value_type, required, next) = graphql_type(&tokens, i + 1);
+            members.push(member(&field, value_type, required));
+            i = next;
+            // input fields may have default values
+            if punct_at(i, '=') {
+                i += 2;
+            }
+            i = skip_directives(i);
+        }
+
+        let end_line = tokens.get(i).map_or(start_line, |(_, line)| *line);
+        i += 1;
+
+        let structure_kind = if kind == "enum" { SCHEMA_ENUM } else { SCHEMA_OBJECT };
+        structures.push(schema_structure(
+            &name,
+            structure_kind,
+            members,
+            extends,
+            (file_path, start_line, end_line),
+        ));
+    }
+
+    structures
+}
+
+/// `open`の位置から対応する`close`の次の位置を返す
+fn skip_group(
+    tokens: &[(GraphqlToken, usize)],
+    open_index: usize,
+    open: char,
+    close: char,
+) -> usize {
+    let mut depth = 0;
+    for (index, (token, _)) in tokens.iter().enumerate().skip(open_index) {
+        match token {
+            GraphqlToken::Punct(c) if *c == open => depth += 1,
+            GraphqlToken::Punct(c) if *c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return index + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// `[User!]!` のような型を読み、(正規化した型, 非null, 次の位置) を返す
+fn graphql_type(tokens: &[(GraphqlToken, usize)], mut i: usize) -> (String, bool, usize) {
+    let is_bang = |i: usize| matches!(tokens.get(i), Some((GraphqlToken::Punct('!'), _)));
+
+    let value_type = match tokens.get(i) {
+        Some((GraphqlToken::Punct('['), _)) => {
+            let (inner, _, next) = graphql_type(tokens, i + 1);
+            i = next;
+            if matches!(tokens.get(i), Some((GraphqlToken::Punct(']'), _))) {
+                i += 1;
+            }
+            format!("{}[]", inner)
+        }
+        Some((GraphqlToken::Name(name), _)) => {
+            i += 1;
+            normalize_scalar(name)
+        }
+        _ => "any".to_string(),
+    };
+
+    let required = is_bang(i);
+    if required {
+        i += 1;
+    }
+    (value_type, required, i)
+}
+
+/// OpenAPIとGraphQLのスキーマを横断して重複を検出する解析器
+pub struct SchemaAnalyzer {
+    min_members: usize,
+    comparator: RefCell<StructureComparator>,
+}
+
+impl Default for SchemaAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchemaAnalyzer {
+    pub fn new() -> Self {
+        Self::with_options(ComparisonOptions { ignore_order: true, ..Default::default() }, 3)
+    }
+
+    /// `min_members` 未満のフィールドしか持たない型は比較しない
+    pub fn with_options(options: ComparisonOptions, min_members: usize) -> Self {
+        Self { min_members, comparator: RefCell::new(StructureComparator::new(options)) }
+    }
+
+    fn structures(file: &str, source: &str) -> Result<Vec<Structure>, String> {
+        let extension = file.rsplit('.').next().unwrap_or("");
+        Ok(match extension {
+            "graphql" | "graphqls" | "gql" => extract_graphql_types(source, file),
+            "json" => extract_openapi_schemas(&parse_json(source)?, file),
+            _ => parse_yaml(source)?
+                .iter()
+                .flat_map(|document| extract_openapi_schemas(document, file))
+                .collect(),
+        })
+    }
+}
+
+impl Analyzer for SchemaAnalyzer {
+    fn name(&self) -> &str {
+        "schema"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["yaml", "yml", "json", "graphql", "graphqls", "gql"]
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        // OpenAPI以外のYAML/JSONは対象外なので、読めないファイルも無視する
+        let structures = Self::structures(file, source).unwrap_or_default();
+
+        Ok(structures
+            .into_iter()
+            .filter(|structure| structure.members.len() >= self.min_members)
+            .map(|structure| {
+                let mut tree =
+                    TreeNode::new(structure.identifier.name.to_string(), String::new(), 0);
+                for (id, member) in structure.members.iter().enumerate() {
+                    tree.add_child(Rc::new(TreeNode::new(
+                        member.name.clone(),
+                        member.value_type.to_string(),
+                        id + 1,
+                    )));
+                }
+                let location = &structure.metadata.location;
+                AnalysisUnit {
+                    name: structure.identifier.name.to_string(),
+                    file: file.to_string(),
+                    start_line: location.start_line as u32,
+                    end_line: location.end_line as u32,
+                    tree: Rc::new(tree),
+                    structure: Some(structure),
+                }
+            })
+            .collect())
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        match (&first.structure, &second.structure) {
+            (Some(s1), Some(s2)) if s1.identifier.kind == s2.identifier.kind => {
+                self.comparator.borrow_mut().compare(s1, s2).overall_similarity
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn report(&self, findings: &[AnalyzerFinding], out: &mut dyn Write) -> io::Result<()> {
+        if findings.is_empty() {
+            return writeln!(out, "No duplicated schema definitions found.");
+        }
+
+        writeln!(out, "Duplicated schema definitions:")?;
+        writeln!(out, "{}", "-".repeat(60))?;
+        for finding in findings {
+            let (first, second) = (&finding.first, &finding.second);
+            writeln!(out, "\nSimilarity: {:.2}%", finding.similarity * 100.0)?;
+            writeln!(
+                out,
+                "  {}",
+                format_function_output(&first.file, &first.name, first.start_line, first.end_line)
+            )?;
+            writeln!(
+                out,
+                "  {}",
+                format_function_output(
+                    &second.file,
+                    &second.name,
+                    second.start_line,
+                    second.end_line
+                )
+            )?;
+            writeln!(out, "  Suggestion: {}", consolidation_hint(finding))?;
+        }
+        writeln!(out, "\nTotal duplicated schema pairs: {}", findings.len())
+    }
+}
+
+fn is_graphql_file(file: &str) -> bool {
+    matches!(file.rsplit('.').next(), Some("graphql" | "graphqls" | "gql"))
+}
+
+/// 共有スキーマへの統合案
+fn consolidation_hint(finding: &AnalyzerFinding) -> String {
+    let (first, second) = (&finding.first, &finding.second);
+    let name = if first.name.len() <= second.name.len() { &first.name } else { &second.name };
+
+    match (is_graphql_file(&first.file), is_graphql_file(&second.file)) {
+        (false, false) => format!(
+            "move '{}' into a shared components file and reference it with $ref: '#/components/schemas/{}'",
+            name, name
+        ),
+        (true, true) => format!(
+            "define '{}' once in a shared schema module (or as an interface both types implement)",
+            name
+        ),
+        _ => format!(
+            "generate the OpenAPI schema and GraphQL type '{}' from a single shared definition",
+            name
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::run_analyzer;
+
+    const USERS_OPENAPI: &str = r##"openapi: 3.0.0
+info:
+  title: Users
+paths: {}
+components:
+  schemas:
+    User:
+      type: object
+      required: [id, email]
+      properties:
+        id:
+          type: integer
+        email:
+          type: string
+        createdAt:
+          type: string
+          format: date-time
+        roles:
+          type: array
+          items:
+            $ref: '#/components/schemas/Role'
+    Role:
+      type: string
+      enum: [admin, member]
+"##;
+
+    const USERS_GRAPHQL: &str = r#"
+"""A registered user"""
+type Account implements Node & Entity @key(fields: "id") {
+  id: Int!
+  email: String! # login
+  createdAt: DateTime
+  roles(first: Int = 10): [Role!]
+}
+
+enum Role {
+  admin
+  member @deprecated(reason: "use admin")
+}
+
+scalar DateTime
+"#;
+
+    #[test]
+    fn test_extract_openapi_schemas() {
+        let document = parse_yaml(USERS_OPENAPI).unwrap().remove(0);
+        let schemas = extract_openapi_schemas(&document, "users.yaml");
+        assert_eq!(schemas.len(), 2);
+
+        let user = &schemas[0];
+        assert_eq!(user.identifier.name, "User");
+        let types: Vec<_> = user
+            .members
+            .iter()
+            .map(|m| (m.name.as_str(), m.value_type.as_str(), m.modifiers.len()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("id", "integer", 1),
+                ("email", "string", 1),
+                ("createdAt", "datetime", 0),
+                ("roles", "Role[]", 0)
+            ]
+        );
+        assert_eq!(user.metadata.location.start_line, 8);
+        assert_eq!(schemas[1].identifier.kind, StructureKind::Generic(SCHEMA_ENUM.to_string()));
+
+        // Plain YAML files are not schemas
+        let config = parse_yaml("name: ci\non: push\n").unwrap().remove(0);
+        assert!(extract_openapi_schemas(&config, "ci.yml").is_empty());
+    }
+
+    #[test]
+    fn test_extract_graphql_types() {
+        let types = extract_graphql_types(USERS_GRAPHQL, "users.graphql");
+        assert_eq!(types.len(), 2);
+
+        let account = &types[0];
+        assert_eq!(account.identifier.name, "Account");
+        assert_eq!(account.metadata.extends, vec!["Node", "Entity"]);
+        assert_eq!(
+            (account.metadata.location.start_line, account.metadata.location.end_line),
+            (3, 8)
+        );
+        let types_of: Vec<_> = account
+            .members
+            .iter()
+            .map(|m| (m.name.as_str(), m.value_type.as_str(), m.modifiers.len()))
+            .collect();
+        assert_eq!(
+            types_of,
+            vec![
+                ("id", "integer", 1),
+                ("email", "string", 1),
+                ("createdAt", "datetime", 0),
+                ("roles", "Role[]", 0)
+            ]
+        );
+
+        let role: Vec<_> = types[1].members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(role, vec!["admin", "member"]);
+    }
+
+    #[test]
+    fn test_reports_duplicates_across_openapi_and_graphql() {
+        let files = vec![
+            ("users/openapi.yaml".to_string(), USERS_OPENAPI.to_string()),
+            ("accounts/schema.graphql".to_string(), USERS_GRAPHQL.to_string()),
+            ("ci.yml".to_string(), "jobs:\n  build:\n    runs-on: ubuntu\n".to_string()),
+        ];
+
+        let mut analyzer = SchemaAnalyzer::new();
+        let findings = run_analyzer(&mut analyzer, &files, 0.7).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].first.name, "User");
+        assert_eq!(findings[0].second.name, "Account");
+
+        let mut out = Vec::new();
+        analyzer.report(&findings, &mut out).unwrap();
+        let report = String::from_utf8(out).unwrap();
+        assert!(report.contains("users/openapi.yaml:8-21 User"));
+        assert!(report.contains("Suggestion: generate the OpenAPI schema and GraphQL type 'User'"));
+    }
+}
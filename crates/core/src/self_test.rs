@@ -0,0 +1,150 @@
+//! Checks of the parsers linked into a build and of the directories a run
+//! writes to, for the `doctor` subcommands of the CLIs.
+//!
+//! Each parser extracts the functions of a small sample; a parser that fails
+//! or finds nothing explains runs that report no duplicates at all.
+
+use crate::function_extractor::extract_functions;
+use crate::generic_tree_sitter_parser::GenericTreeSitterParser;
+use crate::language_parser::LanguageParser;
+use std::fs;
+use std::path::Path;
+
+/// How a sample is parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParserBackend {
+    Oxc,
+    TreeSitter(&'static str),
+}
+
+/// A parser and the sample it must extract functions from
+#[derive(Debug)]
+pub struct ParserSample {
+    pub language: &'static str,
+    pub extensions: &'static [&'static str],
+    pub backend: ParserBackend,
+    filename: &'static str,
+    source: &'static str,
+}
+
+pub const PARSER_SAMPLES: &[ParserSample] = &[
+    ParserSample {
+        language: "TypeScript",
+        extensions: &["ts", "tsx", "mts", "cts"],
+        backend: ParserBackend::Oxc,
+        filename: "sample.tsx",
+        source: "export function add(a: number, b: number): number {\n  return a + b;\n}\nconst View = () => <div />;\n",
+    },
+    ParserSample {
+        language: "JavaScript",
+        extensions: &["js", "jsx", "mjs", "cjs"],
+        backend: ParserBackend::Oxc,
+        filename: "sample.js",
+        source: "function add(a, b) {\n  return a + b;\n}\n",
+    },
+    ParserSample {
+        language: "Go",
+        extensions: &["go"],
+        backend: ParserBackend::TreeSitter("go"),
+        filename: "sample.go",
+        source: "package main\n\nfunc add(a int, b int) int {\n\treturn a + b\n}\n",
+    },
+    ParserSample {
+        language: "Java",
+        extensions: &["java"],
+        backend: ParserBackend::TreeSitter("java"),
+        filename: "Sample.java",
+        source: "class Sample {\n  int add(int a, int b) {\n    return a + b;\n  }\n}\n",
+    },
+    ParserSample {
+        language: "C",
+        extensions: &["c", "h"],
+        backend: ParserBackend::TreeSitter("c"),
+        filename: "sample.c",
+        source: "int add(int a, int b) {\n  return a + b;\n}\n",
+    },
+    ParserSample {
+        language: "C++",
+        extensions: &["cpp", "cc", "cxx", "hpp"],
+        backend: ParserBackend::TreeSitter("cpp"),
+        filename: "sample.cpp",
+        source: "int add(int a, int b) {\n  return a + b;\n}\n",
+    },
+    ParserSample {
+        language: "C#",
+        extensions: &["cs"],
+        backend: ParserBackend::TreeSitter("csharp"),
+        filename: "Sample.cs",
+        source: "class Sample {\n  int Add(int a, int b) {\n    return a + b;\n  }\n}\n",
+    },
+    ParserSample {
+        language: "Ruby",
+        extensions: &["rb"],
+        backend: ParserBackend::TreeSitter("ruby"),
+        filename: "sample.rb",
+        source: "def add(a, b)\n  a + b\nend\n",
+    },
+    ParserSample {
+        language: "Bash",
+        extensions: &["sh", "bash"],
+        backend: ParserBackend::TreeSitter("bash"),
+        filename: "sample.sh",
+        source: "add() {\n  echo $(($1 + $2))\n}\n",
+    },
+];
+
+impl ParserSample {
+    /// Parse the sample; the number of functions found, which is never zero on success
+    pub fn check(&self) -> Result<usize, String> {
+        let found = match self.backend {
+            ParserBackend::Oxc => extract_functions(self.filename, self.source)?.len(),
+            ParserBackend::TreeSitter(name) => {
+                let mut parser =
+                    GenericTreeSitterParser::from_language_name(name).map_err(|e| e.to_string())?;
+                parser
+                    .extract_functions(self.source, self.filename)
+                    .map_err(|e| e.to_string())?
+                    .len()
+            }
+        };
+        if found == 0 {
+            return Err("no functions found in the sample".to_string());
+        }
+        Ok(found)
+    }
+}
+
+/// Whether files can be created in `dir`, or in its closest existing
+/// ancestor when it does not exist yet (it is created on first use)
+pub fn check_writable(dir: &Path) -> Result<(), String> {
+    // Relative paths end in an empty ancestor, the current directory
+    let existing = dir.ancestors().find(|ancestor| ancestor.is_dir()).unwrap_or(Path::new("."));
+    let probe = existing.join(format!(".similarity-doctor-{}", std::process::id()));
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .map_err(|error| format!("cannot write to {}: {}", existing.display(), error))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_linked_parser_extracts_its_sample() {
+        for sample in PARSER_SAMPLES {
+            assert!(sample.check().is_ok(), "{}: {:?}", sample.language, sample.check());
+        }
+    }
+
+    #[test]
+    fn test_missing_directories_are_checked_through_their_ancestor() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(check_writable(&dir.path().join("not/yet/created")), Ok(()));
+        assert!(!dir.path().join("not").exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,431 @@
+use crate::config_loader::ConfigLoader;
+use crate::structure_comparator::MemberComparisonStrategy;
+use std::collections::HashMap;
+
+/// `[type_synonyms]` table of `similarity.toml`, mapping project type names
+/// to the type they stand for (e.g. `UserId = "string"`)
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct SemanticTypeConfig {
+    #[serde(default)]
+    pub type_synonyms: HashMap<String, String>,
+}
+
+impl ConfigLoader for SemanticTypeConfig {}
+
+impl SemanticTypeConfig {
+    /// The synonyms `strategy` compares with: those of `similarity.toml` for
+    /// the semantic strategy, none without reading it otherwise
+    #[must_use]
+    pub fn synonyms_for(strategy: MemberComparisonStrategy) -> HashMap<String, String> {
+        match strategy {
+            MemberComparisonStrategy::Semantic => Self::find_and_load().type_synonyms,
+            MemberComparisonStrategy::Exact | MemberComparisonStrategy::Normalized => {
+                HashMap::new()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumericFamily {
+    Integer,
+    Float,
+    /// Languages with a single number type (TypeScript `number`)
+    Number,
+}
+
+/// Language-independent form of a type annotation
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanonicalType {
+    Named(String),
+    Numeric(NumericFamily),
+    Array(Box<CanonicalType>),
+    /// Promise/Future of the inner type
+    Async(Box<CanonicalType>),
+    /// Object literal fields, sorted by name
+    Object(Vec<(String, CanonicalType)>),
+    /// Union members, sorted and deduplicated; `null` stands for all nullish types
+    Union(Vec<CanonicalType>),
+}
+
+impl CanonicalType {
+    fn render(&self) -> String {
+        match self {
+            CanonicalType::Named(name) => name.clone(),
+            CanonicalType::Numeric(NumericFamily::Integer) => "int".to_string(),
+            CanonicalType::Numeric(NumericFamily::Float) => "float".to_string(),
+            CanonicalType::Numeric(NumericFamily::Number) => "number".to_string(),
+            CanonicalType::Array(inner) => format!("{}[]", inner.render()),
+            CanonicalType::Async(inner) => format!("async {}", inner.render()),
+            CanonicalType::Object(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, field_type)| format!("{}:{}", name, field_type.render()))
+                    .collect();
+                format!("{{{}}}", fields.join(","))
+            }
+            CanonicalType::Union(members) => {
+                members.iter().map(CanonicalType::render).collect::<Vec<_>>().join("|")
+            }
+        }
+    }
+}
+
+const MAX_SYNONYM_DEPTH: usize = 8;
+
+/// Resolves type annotations from different languages to comparable forms
+#[derive(Debug, Clone, Default)]
+pub struct SemanticTypeResolver {
+    synonyms: HashMap<String, String>,
+}
+
+impl SemanticTypeResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use a project-specific synonym table in addition to the built-in aliases
+    pub fn with_synonyms(synonyms: HashMap<String, String>) -> Self {
+        Self { synonyms }
+    }
+
+    /// Similarity of two type annotations in `[0.0, 1.0]`
+    pub fn similarity(&self, type1: &str, type2: &str) -> f64 {
+        if type1 == type2 {
+            return 1.0;
+        }
+        canonical_similarity(&self.canonicalize(type1), &self.canonicalize(type2))
+    }
+
+    pub fn canonicalize(&self, type_str: &str) -> CanonicalType {
+        self.canonicalize_at(type_str, 0)
+    }
+
+    fn canonicalize_at(&self, type_str: &str, depth: usize) -> CanonicalType {
+        let type_str = strip_modifiers(type_str);
+
+        if depth < MAX_SYNONYM_DEPTH {
+            if let Some(target) = self.synonyms.get(type_str) {
+                return self.canonicalize_at(target, depth + 1);
+            }
+        }
+
+        let members = split_top_level(type_str, '|');
+        if members.len() > 1 {
+            return union(members.iter().map(|member| self.canonicalize_at(member, depth)));
+        }
+
+        if let Some(element) = type_str.strip_suffix("[]") {
+            return CanonicalType::Array(Box::new(self.canonicalize_at(element, depth)));
+        }
+
+        if let Some(inner) = type_str.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            // Rust slices and arrays: [T] and [T; N]
+            let element = split_top_level(inner, ';').into_iter().next().unwrap_or_default();
+            return CanonicalType::Array(Box::new(self.canonicalize_at(element, depth)));
+        }
+
+        if let Some(body) = type_str.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            return self.canonicalize_object(body, depth);
+        }
+
+        if let Some((base, args)) = split_generic(type_str) {
+            return self.canonicalize_generic(base, &args, depth);
+        }
+
+        builtin_alias(type_str).unwrap_or_else(|| CanonicalType::Named(type_str.to_string()))
+    }
+
+    fn canonicalize_object(&self, body: &str, depth: usize) -> CanonicalType {
+        let mut fields = Vec::new();
+
+        for field in split_top_level_any(body, &[';', ',']) {
+            let Some((name, field_type)) = field.split_once(':') else {
+                continue;
+            };
+            let name = strip_modifiers(name).trim_end_matches('?').trim().to_string();
+            fields.push((name, self.canonicalize_at(field_type, depth)));
+        }
+
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        CanonicalType::Object(fields)
+    }
+
+    fn canonicalize_generic(&self, base: &str, args: &[&str], depth: usize) -> CanonicalType {
+        let last_arg = || {
+            let arg = args.last().copied().unwrap_or_default();
+            // Future<Output = T>
+            let arg = arg.split_once('=').map_or(arg, |(_, value)| value);
+            self.canonicalize_at(arg, depth)
+        };
+
+        match base {
+            "Array" | "ReadonlyArray" | "Vec" | "VecDeque" | "List" | "list" | "Sequence" => {
+                CanonicalType::Array(Box::new(last_arg()))
+            }
+            "Promise" | "PromiseLike" | "Future" | "impl Future" | "Awaitable" => {
+                CanonicalType::Async(Box::new(last_arg()))
+            }
+            "Box" | "Rc" | "Arc" | "Cow" | "Readonly" => last_arg(),
+            "Option" | "Optional" => {
+                union([last_arg(), CanonicalType::Named("null".to_string())].into_iter())
+            }
+            _ => {
+                let args: Vec<String> =
+                    args.iter().map(|arg| self.canonicalize_at(arg, depth).render()).collect();
+                let base = builtin_alias(base).map_or_else(|| base.to_string(), |c| c.render());
+                CanonicalType::Named(format!("{}<{}>", base, args.join(",")))
+            }
+        }
+    }
+}
+
+fn union(members: impl Iterator<Item = CanonicalType>) -> CanonicalType {
+    let mut flattened = Vec::new();
+    for member in members {
+        match member {
+            CanonicalType::Union(inner) => flattened.extend(inner),
+            other => flattened.push(other),
+        }
+    }
+    flattened.sort_by_key(CanonicalType::render);
+    flattened.dedup();
+
+    if flattened.len() == 1 {
+        flattened.pop().unwrap()
+    } else {
+        CanonicalType::Union(flattened)
+    }
+}
+
+/// Primitive aliases shared by TypeScript, Rust, Python and friends
+fn builtin_alias(name: &str) -> Option<CanonicalType> {
+    let canonical = match name {
+        "string" | "String" | "str" | "char" => CanonicalType::Named("string".to_string()),
+        "boolean" | "Boolean" | "bool" => CanonicalType::Named("boolean".to_string()),
+        "null" | "undefined" | "None" | "nil" | "void" | "()" => {
+            CanonicalType::Named("null".to_string())
+        }
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128"
+        | "usize" | "int" | "Int" | "integer" | "long" | "bigint" | "BigInt" => {
+            CanonicalType::Numeric(NumericFamily::Integer)
+        }
+        "f32" | "f64" | "float" | "Float" | "double" | "Double" | "decimal" => {
+            CanonicalType::Numeric(NumericFamily::Float)
+        }
+        "number" | "Number" => CanonicalType::Numeric(NumericFamily::Number),
+        _ => return None,
+    };
+    Some(canonical)
+}
+
+fn canonical_similarity(type1: &CanonicalType, type2: &CanonicalType) -> f64 {
+    use CanonicalType::{Array, Async, Numeric, Object, Union};
+
+    if type1 == type2 {
+        return 1.0;
+    }
+
+    match (type1, type2) {
+        // Same numeric family is caught by equality above
+        (Numeric(_), Numeric(_)) => 0.8,
+        (Array(inner1), Array(inner2)) | (Async(inner1), Async(inner2)) => {
+            canonical_similarity(inner1, inner2)
+        }
+        (Async(inner), other) | (other, Async(inner)) => 0.9 * canonical_similarity(inner, other),
+        (Object(fields1), Object(fields2)) => object_similarity(fields1, fields2),
+        (Union(_), _) | (_, Union(_)) => union_similarity(type1, type2),
+        _ => 0.0,
+    }
+}
+
+/// Structural equivalence of object literal types: matching field names with similar types
+fn object_similarity(
+    fields1: &[(String, CanonicalType)],
+    fields2: &[(String, CanonicalType)],
+) -> f64 {
+    let max_fields = fields1.len().max(fields2.len());
+    if max_fields == 0 {
+        return 1.0;
+    }
+
+    let total: f64 = fields1
+        .iter()
+        .filter_map(|(name, field_type)| {
+            let (_, other_type) = fields2.iter().find(|(other_name, _)| other_name == name)?;
+            Some(canonical_similarity(field_type, other_type))
+        })
+        .sum();
+    total / max_fields as f64
+}
+
+/// Average best-match similarity of union members, in both directions
+fn union_similarity(type1: &CanonicalType, type2: &CanonicalType) -> f64 {
+    fn members(canonical: &CanonicalType) -> Vec<&CanonicalType> {
+        match canonical {
+            CanonicalType::Union(members) => members.iter().collect(),
+            other => vec![other],
+        }
+    }
+    fn directed(from: &[&CanonicalType], to: &[&CanonicalType]) -> f64 {
+        let total: f64 = from
+            .iter()
+            .map(|a| to.iter().map(|b| canonical_similarity(a, b)).fold(0.0, f64::max))
+            .sum();
+        total / from.len() as f64
+    }
+
+    let (members1, members2) = (members(type1), members(type2));
+    (directed(&members1, &members2) + directed(&members2, &members1)) / 2.0
+}
+
+/// Remove reference, mutability, lifetime and readonly markers
+fn strip_modifiers(type_str: &str) -> &str {
+    let mut current = type_str.trim();
+    loop {
+        let next = if let Some(rest) = current.strip_prefix('&') {
+            rest
+        } else if let Some(rest) = current.strip_prefix("mut ") {
+            rest
+        } else if let Some(rest) = current.strip_prefix("readonly ") {
+            rest
+        } else if current.starts_with('\'') {
+            // Lifetime such as 'a
+            current.split_once(' ').map_or(current, |(_, rest)| rest)
+        } else {
+            return current;
+        }
+        .trim();
+
+        if next == current {
+            return current;
+        }
+        current = next;
+    }
+}
+
+/// Split `Base<A, B>` or `Base[A, B]` into its base and top-level arguments
+fn split_generic(type_str: &str) -> Option<(&str, Vec<&str>)> {
+    let open = type_str.find(['<', '['])?;
+    let close = match type_str.as_bytes()[open] {
+        b'<' => '>',
+        _ => ']',
+    };
+    let inner = type_str[open + 1..].strip_suffix(close)?;
+    let base = type_str[..open].trim();
+    if base.is_empty() {
+        return None;
+    }
+    Some((base, split_top_level(inner, ',')))
+}
+
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    split_top_level_any(text, &[separator])
+}
+
+/// Split on separators that are not nested inside brackets
+fn split_top_level_any<'a>(text: &'a str, separators: &[char]) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+
+    for (index, c) in text.char_indices() {
+        match c {
+            '<' | '[' | '{' | '(' => depth += 1,
+            '>' | ']' | '}' | ')' => depth -= 1,
+            _ if depth == 0 && separators.contains(&c) => {
+                parts.push(text[start..index].trim());
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_primitive_aliases_across_languages() {
+        let resolver = SemanticTypeResolver::new();
+        assert_eq!(resolver.similarity("string", "String"), 1.0);
+        assert_eq!(resolver.similarity("&'a str", "string"), 1.0);
+        assert_eq!(resolver.similarity("bool", "boolean"), 1.0);
+        assert_eq!(resolver.similarity("string", "boolean"), 0.0);
+    }
+
+    #[test]
+    fn groups_numeric_types_into_families() {
+        let resolver = SemanticTypeResolver::new();
+        assert_eq!(resolver.similarity("i32", "u64"), 1.0);
+        assert_eq!(resolver.similarity("f32", "double"), 1.0);
+        assert_eq!(resolver.similarity("usize", "number"), 0.8);
+        assert_eq!(resolver.similarity("i64", "f64"), 0.8);
+    }
+
+    #[test]
+    fn unwraps_arrays_promises_and_smart_pointers() {
+        let resolver = SemanticTypeResolver::new();
+        assert_eq!(resolver.similarity("string[]", "Vec<String>"), 1.0);
+        assert_eq!(resolver.similarity("Array<number>", "List[float]"), 0.8);
+        assert_eq!(resolver.similarity("&[u8]", "number[]"), 0.8);
+        assert_eq!(resolver.similarity("Promise<User>", "impl Future<Output = User>"), 1.0);
+        assert_eq!(resolver.similarity("Promise<User>", "User"), 0.9);
+        assert_eq!(resolver.similarity("Box<User>", "User"), 1.0);
+    }
+
+    #[test]
+    fn compares_object_literals_structurally() {
+        let resolver = SemanticTypeResolver::new();
+        assert_eq!(
+            resolver.similarity("{ id: number; name: string }", "{ name: String, id: number }"),
+            1.0
+        );
+        assert_eq!(
+            resolver.similarity("{ id: number; name: string }", "{ id: number; email: string }"),
+            0.5
+        );
+    }
+
+    #[test]
+    fn treats_optional_types_as_nullable_unions() {
+        let resolver = SemanticTypeResolver::new();
+        assert_eq!(resolver.similarity("Option<String>", "string | null"), 1.0);
+        assert_eq!(resolver.similarity("Optional[str]", "string | undefined"), 1.0);
+        assert_eq!(resolver.similarity("string | null", "string"), 0.75);
+    }
+
+    #[test]
+    fn applies_configured_synonyms() {
+        let resolver = SemanticTypeResolver::new();
+        assert_eq!(resolver.similarity("UserId", "string"), 0.0);
+
+        let synonyms = HashMap::from([
+            ("UserId".to_string(), "string".to_string()),
+            ("Timestamp".to_string(), "i64".to_string()),
+        ]);
+        let resolver = SemanticTypeResolver::with_synonyms(synonyms);
+        assert_eq!(resolver.similarity("UserId", "String"), 1.0);
+        assert_eq!(resolver.similarity("Timestamp[]", "Vec<u64>"), 1.0);
+    }
+
+    #[test]
+    fn loads_synonyms_from_config() {
+        let config: SemanticTypeConfig =
+            toml::from_str("threshold = 0.8\n\n[type_synonyms]\nUserId = \"string\"\n").unwrap();
+        assert_eq!(config.type_synonyms.get("UserId").map(String::as_str), Some("string"));
+    }
+
+    #[test]
+    fn test_strategies_parse_and_only_semantic_reads_synonyms() {
+        assert_eq!(MemberComparisonStrategy::default(), MemberComparisonStrategy::Normalized);
+        assert_eq!("semantic".parse(), Ok(MemberComparisonStrategy::Semantic));
+        assert!("fuzzy".parse::<MemberComparisonStrategy>().is_err());
+        assert!(SemanticTypeConfig::synonyms_for(MemberComparisonStrategy::Normalized).is_empty());
+        assert!(SemanticTypeConfig::synonyms_for(MemberComparisonStrategy::Exact).is_empty());
+    }
+}
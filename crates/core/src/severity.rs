@@ -0,0 +1,161 @@
+//! Severity of findings, assigned by rules in `similarity.toml`.
+//!
+//! Each rule gives a level to findings at or above a similarity, optionally
+//! for one analyzer only:
+//!
+//! ```toml
+//! [[severity]]
+//! min_similarity = 1.0
+//! level = "error"
+//!
+//! [[severity]]
+//! min_similarity = 0.9
+//! level = "warning"
+//!
+//! [[severity]]
+//! analyzer = "types"
+//! min_similarity = 0.8
+//! level = "info"
+//! ```
+//!
+//! A finding gets the highest level among the rules it matches, so the order
+//! of the rules does not matter. Without rules every finding is a warning.
+
+use crate::config_loader::ConfigLoader;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 3] = [Severity::Info, Severity::Warning, Severity::Error];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Severity::ALL
+            .into_iter()
+            .find(|severity| severity.as_str() == s)
+            .ok_or_else(|| format!("unknown severity '{}' (expected info, warning or error)", s))
+    }
+}
+
+/// Level for findings at or above a similarity
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SeverityRule {
+    /// Analyzer the rule is limited to (functions, types, classes, ...)
+    #[serde(default)]
+    pub analyzer: Option<String>,
+    /// Lowest similarity the rule applies to (0.0-1.0)
+    pub min_similarity: f64,
+    pub level: Severity,
+}
+
+/// `[[severity]]` tables of `similarity.toml`
+#[derive(Debug, Default, Deserialize)]
+pub struct SeverityConfig {
+    #[serde(default)]
+    pub severity: Vec<SeverityRule>,
+}
+
+impl ConfigLoader for SeverityConfig {}
+
+/// Severity rules of a run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SeverityPolicy {
+    rules: Vec<SeverityRule>,
+}
+
+impl SeverityPolicy {
+    #[must_use]
+    pub fn new(rules: Vec<SeverityRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Whether any rule is configured
+    #[must_use]
+    pub fn is_configured(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    /// Level of a finding of `analyzer`: the highest level among matching
+    /// rules, `Info` when no rule matches and `Warning` without rules
+    #[must_use]
+    pub fn assign(&self, analyzer: &str, similarity: f64) -> Severity {
+        if self.rules.is_empty() {
+            return Severity::Warning;
+        }
+        self.rules
+            .iter()
+            .filter(|rule| rule.analyzer.as_deref().is_none_or(|name| name == analyzer))
+            .filter(|rule| similarity >= rule.min_similarity)
+            .map(|rule| rule.level)
+            .max()
+            .unwrap_or(Severity::Info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highest_matching_rule_wins() {
+        let config: SeverityConfig = toml::from_str(
+            r#"
+            [[severity]]
+            min_similarity = 0.9
+            level = "warning"
+
+            [[severity]]
+            min_similarity = 1.0
+            level = "error"
+
+            [[severity]]
+            analyzer = "types"
+            min_similarity = 0.95
+            level = "error"
+            "#,
+        )
+        .unwrap();
+        let policy = SeverityPolicy::new(config.severity);
+
+        assert!(policy.is_configured());
+        assert_eq!(policy.assign("functions", 1.0), Severity::Error);
+        assert_eq!(policy.assign("functions", 0.96), Severity::Warning);
+        assert_eq!(policy.assign("types", 0.96), Severity::Error);
+        assert_eq!(policy.assign("functions", 0.85), Severity::Info);
+        assert_eq!(SeverityPolicy::default().assign("functions", 0.5), Severity::Warning);
+
+        assert_eq!("error".parse(), Ok(Severity::Error));
+        assert!("fatal".parse::<Severity>().is_err());
+        assert!(Severity::Error > Severity::Warning && Severity::Warning > Severity::Info);
+    }
+}
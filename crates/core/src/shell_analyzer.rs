@@ -0,0 +1,223 @@
+//! Duplicate detection for shell scripts.
+//!
+//! Deploy and build scripts are rarely organised into functions, so besides
+//! every function definition the analyzer also extracts top-level command
+//! blocks: runs of consecutive commands that are not separated by a blank
+//! line. Both are converted with the tree-sitter-bash grammar and compared
+//! with TSED.
+
+use crate::analyzer::{AnalysisUnit, Analyzer, AnalyzerError};
+use crate::generic_parser_config::GenericParserConfig;
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, TSEDOptions};
+use std::rc::Rc;
+use tree_sitter::{Node, Parser};
+
+/// Top-level blocks with fewer commands are not reported
+pub const DEFAULT_SHELL_MIN_BLOCK_COMMANDS: usize = 3;
+
+pub struct ShellAnalyzer {
+    parser: Parser,
+    value_nodes: Vec<String>,
+    options: TSEDOptions,
+    min_block_commands: usize,
+}
+
+impl ShellAnalyzer {
+    pub fn new() -> Result<Self, AnalyzerError> {
+        Self::with_options(
+            TSEDOptions { min_lines: 1, size_penalty: false, ..TSEDOptions::default() },
+            DEFAULT_SHELL_MIN_BLOCK_COMMANDS,
+        )
+    }
+
+    /// Use custom TSED options; `min_block_commands` limits the size of reported top-level blocks
+    pub fn with_options(
+        options: TSEDOptions,
+        min_block_commands: usize,
+    ) -> Result<Self, AnalyzerError> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_bash::LANGUAGE.into())
+            .map_err(|e| format!("Failed to set language: {:?}", e))?;
+        Ok(Self {
+            parser,
+            value_nodes: GenericParserConfig::bash().value_nodes,
+            options,
+            min_block_commands: min_block_commands.max(1),
+        })
+    }
+
+    fn convert_node(&self, node: Node, source: &str, id_counter: &mut usize) -> TreeNode {
+        let value = if self.value_nodes.iter().any(|kind| kind == node.kind()) {
+            node.utf8_text(source.as_bytes()).unwrap_or("").to_string()
+        } else {
+            String::new()
+        };
+        let mut tree = TreeNode::new(node.kind().to_string(), value, *id_counter);
+        *id_counter += 1;
+
+        for child in node.children(&mut node.walk()) {
+            tree.add_child(Rc::new(self.convert_node(child, source, id_counter)));
+        }
+        tree
+    }
+
+    fn collect_functions(
+        &self,
+        node: Node,
+        file: &str,
+        source: &str,
+        units: &mut Vec<AnalysisUnit>,
+    ) {
+        if node.kind() == "function_definition" {
+            let name = node
+                .child_by_field_name("name")
+                .and_then(|n| n.utf8_text(source.as_bytes()).ok())
+                .unwrap_or("<anonymous>");
+            if let Some(body) = node.child_by_field_name("body") {
+                let lines = node.end_position().row - node.start_position().row + 1;
+                if lines as u32 >= self.options.min_lines {
+                    units.push(AnalysisUnit {
+                        name: name.to_string(),
+                        file: file.to_string(),
+                        start_line: node.start_position().row as u32 + 1,
+                        end_line: node.end_position().row as u32 + 1,
+                        tree: Rc::new(self.convert_node(body, source, &mut 0)),
+                        structure: None,
+                    });
+                }
+            }
+        }
+
+        for child in node.children(&mut node.walk()) {
+            self.collect_functions(child, file, source, units);
+        }
+    }
+
+    fn block_unit(&self, commands: &[Node], file: &str, source: &str) -> Option<AnalysisUnit> {
+        if commands.len() < self.min_block_commands {
+            return None;
+        }
+        let mut id_counter = 1;
+        let mut tree = TreeNode::new("block".to_string(), String::new(), 0);
+        for command in commands {
+            tree.add_child(Rc::new(self.convert_node(*command, source, &mut id_counter)));
+        }
+        Some(AnalysisUnit {
+            name: "top-level block".to_string(),
+            file: file.to_string(),
+            start_line: commands.first()?.start_position().row as u32 + 1,
+            end_line: commands.last()?.end_position().row as u32 + 1,
+            tree: Rc::new(tree),
+            structure: None,
+        })
+    }
+}
+
+/// Whether a blank line separates two top-level nodes
+fn separated_by_blank_line(lines: &[&str], previous: Node, next: Node) -> bool {
+    let start = previous.end_position().row + 1;
+    let end = next.start_position().row.min(lines.len());
+    start < end && lines[start..end].iter().any(|line| line.trim().is_empty())
+}
+
+impl Analyzer for ShellAnalyzer {
+    fn name(&self) -> &str {
+        "bash"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sh", "bash"]
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let tree = self.parser.parse(source, None).ok_or("Failed to parse source")?;
+        let root = tree.root_node();
+
+        let mut units = Vec::new();
+        self.collect_functions(root, file, source, &mut units);
+
+        // Group top-level commands into blocks; blank lines and functions end a block,
+        // comments between commands do not
+        let lines: Vec<&str> = source.lines().collect();
+        let mut block: Vec<Node> = Vec::new();
+        for child in root.named_children(&mut root.walk()) {
+            if child.kind() == "comment" {
+                continue;
+            }
+            let ends_block = child.kind() == "function_definition"
+                || block.last().is_some_and(|last| separated_by_blank_line(&lines, *last, child));
+            if ends_block {
+                units.extend(self.block_unit(&block, file, source));
+                block.clear();
+            }
+            if child.kind() != "function_definition" {
+                block.push(child);
+            }
+        }
+        units.extend(self.block_unit(&block, file, source));
+
+        Ok(units)
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        calculate_tsed(&first.tree, &second.tree, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::run_analyzer;
+
+    const DEPLOY: &str = r#"#!/bin/bash
+set -euo pipefail
+
+build_image() {
+  local tag="$1"
+  docker build -t "app:$tag" .
+  docker push "app:$tag"
+}
+
+# Deploy
+cd "$(dirname "$0")"
+npm ci
+npm run build
+aws s3 sync dist/ "s3://$BUCKET"
+"#;
+
+    #[test]
+    fn extracts_functions_and_top_level_blocks() {
+        let mut analyzer = ShellAnalyzer::new().unwrap();
+        let units = analyzer.extract("deploy.sh", DEPLOY).unwrap();
+        let names: Vec<_> =
+            units.iter().map(|u| (u.name.as_str(), u.start_line, u.end_line)).collect();
+
+        // `set -euo pipefail` alone is too small to be a block
+        assert_eq!(names, vec![("build_image", 4, 8), ("top-level block", 11, 14)]);
+    }
+
+    #[test]
+    fn detects_scripts_copied_between_directories() {
+        let copy = DEPLOY
+            .replace("build_image", "publish_image")
+            .replace("local tag", "local version")
+            .replace("$tag", "$version")
+            .replace("# Deploy\n", "# Deploy the frontend\n")
+            .replace("dist/", "build/");
+        let files = vec![
+            ("service-a/deploy.sh".to_string(), DEPLOY.to_string()),
+            ("service-b/deploy.sh".to_string(), copy),
+        ];
+
+        let mut analyzer = ShellAnalyzer::new().unwrap();
+        let findings = run_analyzer(&mut analyzer, &files, 0.9).unwrap();
+        let pairs: Vec<_> =
+            findings.iter().map(|f| (f.first.name.as_str(), f.second.name.as_str())).collect();
+
+        assert_eq!(pairs.len(), 2);
+        assert!(pairs.contains(&("build_image", "publish_image")));
+        assert!(pairs.contains(&("top-level block", "top-level block")));
+    }
+}
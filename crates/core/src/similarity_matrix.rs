@@ -0,0 +1,145 @@
+//! Pairwise similarities exported as a sparse edge list.
+//!
+//! Every compared pair at or above a floor is written as one CSV row while
+//! the comparison runs, so the export of a large repository never has to fit
+//! in memory. Rows come in comparison order, which varies between parallel
+//! runs; sort the file when a stable order matters.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Default floor of `--matrix-floor`
+pub const DEFAULT_MATRIX_FLOOR: f64 = 0.5;
+
+const HEADER: &str =
+    "kind,file1,name1,start_line1,end_line1,file2,name2,start_line2,end_line2,similarity";
+
+/// One side of an exported pair: file, name, start and end line
+pub type MatrixEndpoint<'a> = (&'a str, &'a str, usize, usize);
+
+/// Writes compared pairs to a CSV edge list; shared by the comparison threads
+pub struct MatrixExporter {
+    floor: f64,
+    writer: Mutex<Box<dyn Write + Send>>,
+    rows: AtomicUsize,
+    error: Mutex<Option<std::io::Error>>,
+}
+
+impl MatrixExporter {
+    /// Create the file at `path` and write the header
+    pub fn create(path: &Path, floor: f64) -> anyhow::Result<Self> {
+        let file = File::create(path)
+            .map_err(|error| anyhow::anyhow!("Failed to create {}: {}", path.display(), error))?;
+        Self::new(BufWriter::new(file), floor)
+            .map_err(|error| anyhow::anyhow!("Failed to write {}: {}", path.display(), error))
+    }
+
+    pub fn new(writer: impl Write + Send + 'static, floor: f64) -> std::io::Result<Self> {
+        let mut writer: Box<dyn Write + Send> = Box::new(writer);
+        writeln!(writer, "{HEADER}")?;
+        Ok(Self {
+            floor,
+            writer: Mutex::new(writer),
+            rows: AtomicUsize::new(0),
+            error: Mutex::new(None),
+        })
+    }
+
+    /// Lowest similarity written
+    #[must_use]
+    pub fn floor(&self) -> f64 {
+        self.floor
+    }
+
+    /// Threshold to compare at so that pairs down to the floor are seen
+    #[must_use]
+    pub fn comparison_threshold(&self, threshold: f64) -> f64 {
+        self.floor.min(threshold)
+    }
+
+    /// Write a compared pair when it reaches the floor. The first write error
+    /// is kept for [`MatrixExporter::finish`] and later rows are dropped.
+    pub fn record(
+        &self,
+        kind: &str,
+        first: MatrixEndpoint<'_>,
+        second: MatrixEndpoint<'_>,
+        similarity: f64,
+    ) {
+        if similarity < self.floor {
+            return;
+        }
+        let mut error = self.error.lock().expect("matrix error lock poisoned");
+        if error.is_some() {
+            return;
+        }
+        let row = format!(
+            "{},{},{},{},{},{},{},{},{},{:.4}",
+            csv_field(kind),
+            csv_field(first.0),
+            csv_field(first.1),
+            first.2,
+            first.3,
+            csv_field(second.0),
+            csv_field(second.1),
+            second.2,
+            second.3,
+            similarity
+        );
+        let mut writer = self.writer.lock().expect("matrix writer lock poisoned");
+        match writeln!(writer, "{row}") {
+            Ok(()) => {
+                self.rows.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => *error = Some(e),
+        }
+    }
+
+    /// Flush the remaining rows; the number of rows written
+    pub fn finish(self) -> std::io::Result<usize> {
+        if let Some(error) = self.error.into_inner().expect("matrix error lock poisoned") {
+            return Err(error);
+        }
+        self.writer.into_inner().expect("matrix writer lock poisoned").flush()?;
+        Ok(self.rows.into_inner())
+    }
+}
+
+/// Quote a field containing separators, quotes or line breaks
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_pairs_below_the_floor_are_not_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("matrix.csv");
+        let exporter = MatrixExporter::create(&path, 0.6).unwrap();
+        assert_eq!(exporter.comparison_threshold(0.87), 0.6);
+
+        exporter.record("function", ("a.ts", "add", 1, 3), ("b.ts", "sum", 4, 6), 0.75);
+        exporter.record("function", ("a.ts", "add", 1, 3), ("c.ts", "log", 1, 2), 0.4);
+        exporter.record("type", ("a.ts", "Pair<A, B>", 5, 8), ("b.ts", "Tuple", 8, 9), 1.0);
+        assert_eq!(exporter.finish().unwrap(), 2);
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!(
+                "{HEADER}\nfunction,a.ts,add,1,3,b.ts,sum,4,6,0.7500\n\
+                 type,a.ts,\"Pair<A, B>\",5,8,b.ts,Tuple,8,9,1.0000\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Penalty for comparing code of different sizes.
+//!
+//! Comparators scale a similarity by a factor of `smaller / larger` size (tree
+//! nodes for functions, members for structures). Each comparator has a
+//! built-in curve; the other curves are shared by all comparators, and the
+//! floor bounds how much any curve can take away.
+
+use crate::config_loader::ConfigLoader;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PenaltyCurve {
+    /// The comparator's own curve
+    #[default]
+    Builtin,
+    /// No penalty
+    None,
+    /// Factor equal to the size ratio
+    Linear,
+    /// Factor equal to the squared size ratio
+    Quadratic,
+}
+
+impl PenaltyCurve {
+    pub const ALL: [PenaltyCurve; 4] =
+        [PenaltyCurve::Builtin, PenaltyCurve::None, PenaltyCurve::Linear, PenaltyCurve::Quadratic];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PenaltyCurve::Builtin => "builtin",
+            PenaltyCurve::None => "none",
+            PenaltyCurve::Linear => "linear",
+            PenaltyCurve::Quadratic => "quadratic",
+        }
+    }
+}
+
+impl fmt::Display for PenaltyCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PenaltyCurve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PenaltyCurve::ALL.into_iter().find(|curve| curve.as_str() == s).ok_or_else(|| {
+            format!("unknown penalty curve '{}' (expected builtin, none, linear or quadratic)", s)
+        })
+    }
+}
+
+/// Size ratio penalty: a curve and the lowest factor it may yield
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SizePenalty {
+    pub curve: PenaltyCurve,
+    /// Lowest factor (0.0-1.0)
+    pub floor: f64,
+}
+
+impl SizePenalty {
+    /// Factor for `ratio` (smaller size / larger size); `builtin` is the
+    /// comparator's own curve
+    #[must_use]
+    pub fn factor(&self, ratio: f64, builtin: impl FnOnce(f64) -> f64) -> f64 {
+        let ratio = ratio.clamp(0.0, 1.0);
+        let factor = match self.curve {
+            PenaltyCurve::Builtin => builtin(ratio),
+            PenaltyCurve::None => 1.0,
+            PenaltyCurve::Linear => ratio,
+            PenaltyCurve::Quadratic => ratio * ratio,
+        };
+        factor.max(self.floor.clamp(0.0, 1.0)).min(1.0)
+    }
+}
+
+/// `[size_penalty]` table of `similarity.toml` (e.g. `curve = "linear"`, `floor = 0.5`)
+#[derive(Debug, Default, Deserialize)]
+pub struct SizePenaltyConfig {
+    #[serde(default)]
+    pub size_penalty: SizePenalty,
+}
+
+impl ConfigLoader for SizePenaltyConfig {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curves_and_floor() {
+        let builtin = |ratio: f64| ratio.sqrt();
+        let penalty = |curve, floor| SizePenalty { curve, floor };
+
+        assert_eq!(SizePenalty::default().factor(0.25, builtin), 0.5);
+        assert_eq!(penalty(PenaltyCurve::None, 0.0).factor(0.25, builtin), 1.0);
+        assert_eq!(penalty(PenaltyCurve::Linear, 0.0).factor(0.25, builtin), 0.25);
+        assert_eq!(penalty(PenaltyCurve::Quadratic, 0.0).factor(0.5, builtin), 0.25);
+        assert_eq!(penalty(PenaltyCurve::Quadratic, 0.4).factor(0.5, builtin), 0.4);
+        assert_eq!(penalty(PenaltyCurve::Linear, 0.4).factor(0.8, builtin), 0.8);
+
+        let config: SizePenaltyConfig =
+            toml::from_str("[size_penalty]\ncurve = \"quadratic\"\nfloor = 0.3\n").unwrap();
+        assert_eq!(config.size_penalty, penalty(PenaltyCurve::Quadratic, 0.3));
+        assert_eq!("linear".parse(), Ok(PenaltyCurve::Linear));
+        assert!("cubic".parse::<PenaltyCurve>().is_err());
+    }
+}
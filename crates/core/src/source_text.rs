@@ -0,0 +1,141 @@
+//! Shared source buffers.
+//!
+//! Extractors copy the text of a file once into a [`SharedSource`] and hand
+//! out [`SourceSlice`]s of it for method bodies and decorator arguments,
+//! instead of an owned `String` per definition. Type strings, which are
+//! mostly synthesized and repeat across files, are interned as
+//! [`Symbol`](crate::interner::Symbol)s instead. Slices become `String`s only
+//! where they leave the library, such as in serialized output.
+
+use oxc_span::Span;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Text of a file, shared by everything extracted from it
+pub type SharedSource = Arc<str>;
+
+/// Range of a shared source buffer
+#[derive(Clone)]
+pub struct SourceSlice {
+    source: SharedSource,
+    start: u32,
+    end: u32,
+}
+
+impl SourceSlice {
+    /// Slice of `source` covered by `span`, clamped to the buffer
+    #[must_use]
+    pub fn new(source: &SharedSource, span: Span) -> Self {
+        let end = span.end.min(source.len() as u32);
+        Self { source: Arc::clone(source), start: span.start.min(end), end }
+    }
+
+    /// Slice owning `text`, for text that is not part of a source buffer
+    #[must_use]
+    pub fn owned(text: &str) -> Self {
+        Self { source: Arc::from(text), start: 0, end: text.len() as u32 }
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.source[self.start as usize..self.end as usize]
+    }
+}
+
+impl Default for SourceSlice {
+    fn default() -> Self {
+        Self::owned("")
+    }
+}
+
+impl Deref for SourceSlice {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SourceSlice {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for SourceSlice {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SourceSlice {}
+
+impl Hash for SourceSlice {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl PartialEq<str> for SourceSlice {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for SourceSlice {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for SourceSlice {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl fmt::Debug for SourceSlice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for SourceSlice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl Serialize for SourceSlice {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl From<SourceSlice> for String {
+    fn from(slice: SourceSlice) -> Self {
+        slice.as_str().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slices_share_the_source() {
+        let source: SharedSource = Arc::from("class A { run() { return 1; } }");
+        let body = SourceSlice::new(&source, Span::new(16, 29));
+        let clamped = SourceSlice::new(&source, Span::new(30, 100));
+
+        assert_eq!(body, "{ return 1; }");
+        assert_eq!(clamped, "}");
+        assert_eq!(Arc::strong_count(&source), 3);
+        assert_eq!(body, SourceSlice::owned("{ return 1; }"));
+        assert_eq!(format!("{body}"), "{ return 1; }");
+        assert!(SourceSlice::default().is_empty());
+    }
+}
@@ -0,0 +1,663 @@
+//! Duplicate detection for SQL files.
+//!
+//! Statements (including stored procedure bodies) and the CTEs of `WITH`
+//! queries are turned into clause-level trees and compared with TSED.
+//! Keywords and identifiers are case-folded, whitespace and comments are
+//! dropped and literals are abstracted, so queries that only differ in
+//! formatting or constants are reported as duplicates.
+
+use crate::analyzer::{AnalysisUnit, Analyzer, AnalyzerError};
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, TSEDOptions};
+use crate::APTEDOptions;
+use std::rc::Rc;
+
+/// Statements with fewer tokens (e.g. `COMMIT;`) are not reported
+pub const DEFAULT_SQL_MIN_TOKENS: u32 = 8;
+
+const KEYWORDS: &[&str] = &[
+    "ALL",
+    "ALTER",
+    "AND",
+    "AS",
+    "ASC",
+    "BEGIN",
+    "BETWEEN",
+    "BY",
+    "CASE",
+    "CREATE",
+    "CROSS",
+    "DECLARE",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "DROP",
+    "ELSE",
+    "END",
+    "EXCEPT",
+    "EXISTS",
+    "FOR",
+    "FROM",
+    "FULL",
+    "FUNCTION",
+    "GROUP",
+    "HAVING",
+    "IF",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "JOIN",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "LOOP",
+    "MATERIALIZED",
+    "NATURAL",
+    "NOT",
+    "NULL",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "PROCEDURE",
+    "RECURSIVE",
+    "REPEAT",
+    "REPLACE",
+    "RETURN",
+    "RETURNING",
+    "RETURNS",
+    "RIGHT",
+    "SELECT",
+    "SET",
+    "TABLE",
+    "THEN",
+    "TRIGGER",
+    "UNION",
+    "UPDATE",
+    "USING",
+    "VALUES",
+    "VIEW",
+    "WHEN",
+    "WHERE",
+    "WHILE",
+    "WITH",
+];
+
+/// Keywords that start a clause node in the tree
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "CROSS",
+    "EXCEPT",
+    "FROM",
+    "FULL",
+    "GROUP",
+    "HAVING",
+    "INNER",
+    "INTERSECT",
+    "INTO",
+    "JOIN",
+    "LEFT",
+    "LIMIT",
+    "NATURAL",
+    "OFFSET",
+    "ORDER",
+    "RETURNING",
+    "RIGHT",
+    "SELECT",
+    "SET",
+    "UNION",
+    "USING",
+    "VALUES",
+    "WHERE",
+    "WITH",
+];
+
+/// Keywords that may precede JOIN within the same clause
+const JOIN_MODIFIERS: &[&str] = &["CROSS", "FULL", "INNER", "LEFT", "NATURAL", "OUTER", "RIGHT"];
+
+/// Object kinds named in `CREATE` statements
+const OBJECT_KEYWORDS: &[&str] = &["FUNCTION", "INDEX", "PROCEDURE", "TABLE", "TRIGGER", "VIEW"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum SqlToken {
+    /// Upper-cased keyword
+    Keyword(String),
+    /// Lower-cased identifier with quotes removed
+    Identifier(String),
+    /// String, number, boolean or bind parameter
+    Literal,
+    Symbol(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: SqlToken,
+    line: u32,
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.kind, SqlToken::Keyword(k) if k == keyword)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(&self.kind, SqlToken::Symbol(s) if s == symbol)
+    }
+
+    fn identifier(&self) -> Option<&str> {
+        match &self.kind {
+            SqlToken::Identifier(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// Tokenize SQL, dropping whitespace and comments
+fn tokenize(source: &str, first_line: u32) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = first_line;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '-' && next == Some('-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '\'' {
+            let start_line = line;
+            i += 1;
+            while i < chars.len() {
+                if chars[i] == '\'' {
+                    // '' is an escaped quote
+                    if chars.get(i + 1) == Some(&'\'') {
+                        i += 2;
+                        continue;
+                    }
+                    break;
+                }
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token { kind: SqlToken::Literal, line: start_line });
+        } else if c == '"' || c == '`' || c == '[' {
+            let close = if c == '[' { ']' } else { c };
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i] != close {
+                i += 1;
+            }
+            let name: String = chars[start..i.min(chars.len())].iter().collect();
+            tokens.push(Token { kind: SqlToken::Identifier(name.to_lowercase()), line });
+            i += 1;
+        } else if c == '$' && next.is_some_and(|n| n == '$' || n.is_alphabetic() || n == '_') {
+            // Dollar-quoted body ($$ ... $$ or $tag$ ... $tag$): tokenize its contents
+            let tag_end = (i + 1..chars.len()).find(|&j| chars[j] == '$');
+            let Some(tag_end) = tag_end else {
+                i += 1;
+                continue;
+            };
+            let tag: String = chars[i..=tag_end].iter().collect();
+            let body_start = tag_end + 1;
+            let rest: String = chars[body_start..].iter().collect();
+            let body_len = rest.find(&tag).map_or(rest.len(), |pos| rest[..pos].chars().count());
+            let body: String = chars[body_start..body_start + body_len].iter().collect();
+
+            tokens.push(Token { kind: SqlToken::Symbol("$$".to_string()), line });
+            tokens.extend(tokenize(&body, line));
+            line += body.matches('\n').count() as u32;
+            tokens.push(Token { kind: SqlToken::Symbol("$$".to_string()), line });
+            i = body_start + body_len + tag.chars().count();
+        } else if c.is_ascii_digit()
+            || (c == '.' && next.is_some_and(|n| n.is_ascii_digit()))
+            || ((c == '$' || c == ':') && next.is_some_and(|n| n.is_alphanumeric()))
+            || c == '?'
+        {
+            // Numbers and bind parameters ($1, :name, ?)
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '.' || chars[i] == '_')
+            {
+                i += 1;
+            }
+            tokens.push(Token { kind: SqlToken::Literal, line });
+        } else if c.is_alphabetic() || c == '_' || c == '@' || c == '#' {
+            let start = i;
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+            {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let upper = word.to_uppercase();
+            let kind = if upper == "TRUE" || upper == "FALSE" {
+                SqlToken::Literal
+            } else if KEYWORDS.contains(&upper.as_str()) {
+                SqlToken::Keyword(upper)
+            } else {
+                SqlToken::Identifier(word.to_lowercase())
+            };
+            tokens.push(Token { kind, line });
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let symbol = if matches!(two.as_str(), "<=" | ">=" | "<>" | "!=" | "||" | "::") {
+                two
+            } else {
+                c.to_string()
+            };
+            i += symbol.chars().count();
+            tokens.push(Token { kind: SqlToken::Symbol(symbol), line });
+        }
+    }
+
+    tokens
+}
+
+/// Split tokens into top-level statements. Semicolons inside parentheses,
+/// `BEGIN ... END` blocks of `CREATE` statements, `CASE ... END` and
+/// dollar-quoted bodies do not end a statement.
+fn split_statements(tokens: Vec<Token>) -> Vec<Vec<Token>> {
+    let mut statements = Vec::new();
+    let mut current: Vec<Token> = Vec::new();
+    let mut depth = 0i32;
+    let mut in_dollar_body = false;
+
+    for index in 0..tokens.len() {
+        let token = &tokens[index];
+        let is_create = current.first().is_some_and(|first| first.is_keyword("CREATE"));
+
+        match &token.kind {
+            SqlToken::Symbol(s) if s == "(" => depth += 1,
+            SqlToken::Symbol(s) if s == ")" => depth -= 1,
+            SqlToken::Symbol(s) if s == "$$" => in_dollar_body = !in_dollar_body,
+            SqlToken::Keyword(k) if k == "CASE" => depth += 1,
+            SqlToken::Keyword(k) if k == "BEGIN" && is_create => depth += 1,
+            SqlToken::Keyword(k) if k == "END" => {
+                // END IF / END LOOP close blocks that never opened a level
+                let closes_control_flow = tokens.get(index + 1).is_some_and(|next| {
+                    ["IF", "LOOP", "WHILE", "REPEAT", "FOR"].iter().any(|k| next.is_keyword(k))
+                });
+                if !closes_control_flow {
+                    depth -= 1;
+                }
+            }
+            _ => {}
+        }
+
+        let ends_statement = token.is_symbol(";") && depth <= 0 && !in_dollar_body;
+        current.push(token.clone());
+        if ends_statement {
+            statements.push(std::mem::take(&mut current));
+            depth = 0;
+        }
+    }
+
+    if !current.is_empty() {
+        statements.push(current);
+    }
+    statements
+}
+
+/// Index of the parenthesis closing the one at `open`
+fn matching_paren(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        if token.is_symbol("(") {
+            depth += 1;
+        } else if token.is_symbol(")") {
+            depth -= 1;
+            if depth == 0 {
+                return Some(index);
+            }
+        }
+    }
+    None
+}
+
+/// Build the tree of one statement: clause nodes at every parenthesis level
+fn build_statement_tree(tokens: &[Token]) -> Rc<TreeNode> {
+    let mut next_id = 0;
+    let mut root = new_node("statement", "", &mut next_id);
+    for child in build_level(tokens, &mut next_id) {
+        root.add_child(child);
+    }
+    Rc::new(root)
+}
+
+fn new_node(label: &str, value: &str, next_id: &mut usize) -> TreeNode {
+    let node = TreeNode::new(label.to_string(), value.to_string(), *next_id);
+    *next_id += 1;
+    node
+}
+
+fn build_level(tokens: &[Token], next_id: &mut usize) -> Vec<Rc<TreeNode>> {
+    let mut nodes: Vec<Rc<TreeNode>> = Vec::new();
+    let mut clause: Option<TreeNode> = None;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let node = if token.is_symbol("(") {
+            let close = matching_paren(tokens, i).unwrap_or(tokens.len());
+            let mut group = new_node("group", "", next_id);
+            for child in build_level(&tokens[i + 1..close.min(tokens.len())], next_id) {
+                group.add_child(child);
+            }
+            i = close + 1;
+            group
+        } else {
+            i += 1;
+            match &token.kind {
+                SqlToken::Keyword(keyword) if CLAUSE_KEYWORDS.contains(&keyword.as_str()) => {
+                    // LEFT OUTER JOIN stays a single clause
+                    let continues_join = clause.as_ref().is_some_and(|current| {
+                        JOIN_MODIFIERS.contains(&current.label.as_str())
+                            && current
+                                .children
+                                .iter()
+                                .all(|c| JOIN_MODIFIERS.contains(&c.label.as_str()))
+                    });
+                    if !continues_join {
+                        if let Some(done) = clause.take() {
+                            nodes.push(Rc::new(done));
+                        }
+                        clause = Some(new_node(keyword, "", next_id));
+                        continue;
+                    }
+                    new_node(keyword, "", next_id)
+                }
+                SqlToken::Keyword(keyword) => new_node(keyword, "", next_id),
+                SqlToken::Identifier(name) => new_node("identifier", name, next_id),
+                SqlToken::Literal => new_node("literal", "?", next_id),
+                SqlToken::Symbol(symbol) => new_node(symbol, "", next_id),
+            }
+        };
+
+        match clause.as_mut() {
+            Some(current) => current.add_child(Rc::new(node)),
+            None => nodes.push(Rc::new(node)),
+        }
+    }
+
+    if let Some(done) = clause {
+        nodes.push(Rc::new(done));
+    }
+    nodes
+}
+
+/// Human-readable name such as `SELECT users` or `CREATE PROCEDURE get_user`
+fn statement_name(tokens: &[Token]) -> String {
+    let identifier_after = |keyword: &str| {
+        tokens
+            .iter()
+            .position(|token| token.is_keyword(keyword))
+            .and_then(|pos| tokens[pos + 1..].iter().find_map(Token::identifier))
+    };
+    let with_target = |kind: &str, target: Option<&str>| match target {
+        Some(target) => format!("{} {}", kind, target),
+        None => kind.to_string(),
+    };
+
+    let Some(first) = tokens.first() else {
+        return "statement".to_string();
+    };
+    match &first.kind {
+        SqlToken::Keyword(k) if k == "SELECT" => with_target("SELECT", identifier_after("FROM")),
+        SqlToken::Keyword(k) if k == "INSERT" => with_target("INSERT", identifier_after("INTO")),
+        SqlToken::Keyword(k) if k == "UPDATE" => with_target("UPDATE", identifier_after("UPDATE")),
+        SqlToken::Keyword(k) if k == "DELETE" => with_target("DELETE", identifier_after("FROM")),
+        SqlToken::Keyword(k) if k == "CREATE" || k == "ALTER" || k == "DROP" => {
+            match OBJECT_KEYWORDS.iter().find(|object| tokens.iter().any(|t| t.is_keyword(object)))
+            {
+                Some(object) => with_target(&format!("{} {}", k, object), identifier_after(object)),
+                None => k.clone(),
+            }
+        }
+        SqlToken::Keyword(k) => k.clone(),
+        _ => "statement".to_string(),
+    }
+}
+
+/// `(name, body)` of each CTE in a `WITH` statement, and the index where the
+/// main query starts
+fn extract_ctes(tokens: &[Token]) -> (Vec<(String, &[Token])>, usize) {
+    let mut ctes = Vec::new();
+    if !tokens.first().is_some_and(|first| first.is_keyword("WITH")) {
+        return (ctes, 0);
+    }
+
+    let mut i = 1;
+    if tokens.get(i).is_some_and(|t| t.is_keyword("RECURSIVE")) {
+        i += 1;
+    }
+
+    while let Some(name) = tokens.get(i).and_then(Token::identifier) {
+        i += 1;
+        // Optional column list
+        if tokens.get(i).is_some_and(|t| t.is_symbol("(")) {
+            i = matching_paren(tokens, i).map_or(tokens.len(), |close| close + 1);
+        }
+        if !tokens.get(i).is_some_and(|t| t.is_keyword("AS")) {
+            break;
+        }
+        i += 1;
+        while tokens.get(i).is_some_and(|t| t.is_keyword("NOT") || t.is_keyword("MATERIALIZED")) {
+            i += 1;
+        }
+        let Some(close) =
+            tokens.get(i).filter(|t| t.is_symbol("(")).and_then(|_| matching_paren(tokens, i))
+        else {
+            break;
+        };
+        ctes.push((name.to_string(), &tokens[i + 1..close]));
+        i = close + 1;
+
+        if !tokens.get(i).is_some_and(|t| t.is_symbol(",")) {
+            break;
+        }
+        i += 1;
+    }
+
+    (ctes, i.min(tokens.len()))
+}
+
+/// Analyzer reporting near-duplicate SQL statements and CTEs
+pub struct SqlAnalyzer {
+    options: TSEDOptions,
+    min_tokens: usize,
+}
+
+impl SqlAnalyzer {
+    /// Identifiers are compared by value; size penalties tuned for functions are disabled
+    pub fn new() -> Self {
+        Self::with_options(TSEDOptions {
+            apted_options: APTEDOptions {
+                compare_values: true,
+                ..TSEDOptions::default().apted_options
+            },
+            min_lines: 1,
+            min_tokens: Some(DEFAULT_SQL_MIN_TOKENS),
+            size_penalty: false,
+            ..TSEDOptions::default()
+        })
+    }
+
+    /// Use custom TSED options; `min_tokens` limits the size of reported statements
+    pub fn with_options(options: TSEDOptions) -> Self {
+        let min_tokens = options.min_tokens.unwrap_or(DEFAULT_SQL_MIN_TOKENS) as usize;
+        Self { options, min_tokens }
+    }
+
+    fn unit(&self, name: String, file: &str, tokens: &[Token]) -> Option<AnalysisUnit> {
+        let meaningful = tokens.iter().filter(|t| !t.is_symbol(";")).count();
+        if meaningful < self.min_tokens {
+            return None;
+        }
+        Some(AnalysisUnit {
+            name,
+            file: file.to_string(),
+            start_line: tokens.first()?.line,
+            end_line: tokens.last()?.line,
+            tree: build_statement_tree(tokens),
+            structure: None,
+        })
+    }
+}
+
+impl Default for SqlAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Analyzer for SqlAnalyzer {
+    fn name(&self) -> &str {
+        "sql"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sql"]
+    }
+
+    fn extract(&mut self, file: &str, source: &str) -> Result<Vec<AnalysisUnit>, AnalyzerError> {
+        let mut units = Vec::new();
+        for statement in split_statements(tokenize(source, 1)) {
+            let (ctes, main_query) = extract_ctes(&statement);
+            for (name, body) in ctes {
+                units.extend(self.unit(format!("CTE {}", name), file, body));
+            }
+            let name = statement_name(&statement[main_query..]);
+            units.extend(self.unit(name, file, &statement));
+        }
+        Ok(units)
+    }
+
+    fn compare(&self, first: &AnalysisUnit, second: &AnalysisUnit) -> f64 {
+        calculate_tsed(&first.tree, &second.tree, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::run_analyzer;
+
+    fn units(source: &str) -> Vec<AnalysisUnit> {
+        SqlAnalyzer::new().extract("queries.sql", source).unwrap()
+    }
+
+    #[test]
+    fn normalizes_casing_whitespace_comments_and_literals() {
+        let analyzer = SqlAnalyzer::new();
+        let first = units("SELECT id, name FROM users WHERE status = 'active' AND age > 18;");
+        let second = units(
+            "select ID,\n       Name\n  from \"Users\" -- active adults\n where status = 'banned' and age > 21;",
+        );
+
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].name, "SELECT users");
+        assert_eq!(second[0].start_line, 1);
+        assert_eq!(second[0].end_line, 4);
+        assert_eq!(analyzer.compare(&first[0], &second[0]), 1.0);
+
+        let other = units("SELECT id, name FROM orders WHERE status = 'active' AND age > 18;");
+        let similarity = analyzer.compare(&first[0], &other[0]);
+        assert!(similarity < 1.0 && similarity > 0.9, "{similarity}");
+    }
+
+    #[test]
+    fn splits_statements_and_keeps_procedure_bodies_whole() {
+        let source = r#"
+BEGIN;
+INSERT INTO audit_log (user_id, action) VALUES (1, 'login');
+COMMIT;
+
+CREATE PROCEDURE archive_user(IN target INT)
+BEGIN
+  UPDATE users SET archived = TRUE WHERE id = target;
+  IF target > 0 THEN
+    DELETE FROM sessions WHERE user_id = target;
+  END IF;
+END;
+
+SELECT CASE WHEN total > 100 THEN 'big' ELSE 'small' END AS size, id FROM orders;
+"#;
+        let names: Vec<_> =
+            units(source).into_iter().map(|u| (u.name, u.start_line, u.end_line)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("INSERT audit_log".to_string(), 3, 3),
+                ("CREATE PROCEDURE archive_user".to_string(), 6, 12),
+                ("SELECT orders".to_string(), 14, 14),
+            ]
+        );
+    }
+
+    #[test]
+    fn extracts_ctes() {
+        let source = r#"
+WITH RECURSIVE active_users AS (
+  SELECT id, email FROM users WHERE deleted_at IS NULL
+), recent_orders (user_id, total) AS (
+  SELECT user_id, SUM(total) FROM orders WHERE created_at > $1 GROUP BY user_id
+)
+SELECT a.email, r.total FROM active_users a JOIN recent_orders r ON r.user_id = a.id;
+"#;
+        let names: Vec<_> = units(source).into_iter().map(|u| (u.name, u.start_line)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("CTE active_users".to_string(), 3),
+                ("CTE recent_orders".to_string(), 5),
+                ("SELECT active_users".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_near_duplicate_queries_across_files() {
+        let files = vec![
+            (
+                "reports/daily.sql".to_string(),
+                "SELECT u.id, COUNT(*) FROM users u LEFT OUTER JOIN orders o ON o.user_id = u.id WHERE o.created_at > '2024-01-01' GROUP BY u.id ORDER BY 2 DESC;\nDROP TABLE tmp;".to_string(),
+            ),
+            (
+                "reports/weekly.sql".to_string(),
+                "select u.id, count(*)\nfrom users u\nleft outer join orders o on o.user_id = u.id\nwhere o.created_at > '2023-06-01'\ngroup by u.id\norder by 2 desc;".to_string(),
+            ),
+        ];
+
+        let findings = run_analyzer(&mut SqlAnalyzer::new(), &files, 0.9).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].similarity, 1.0);
+        assert_eq!(findings[0].first.name, "SELECT users");
+        assert_eq!(findings[0].second.file, "reports/weekly.sql");
+        assert_eq!((findings[0].second.start_line, findings[0].second.end_line), (1, 6));
+    }
+}
@@ -0,0 +1,190 @@
+//! Canonical order of independent statements in TypeScript trees.
+//!
+//! Clones often declare the same values in a different order. Before
+//! comparison, runs of adjacent side-effect free variable declarations are
+//! sorted by their shape, as far as the names they declare and read allow, so
+//! reordered clones produce the same tree.
+
+use crate::tree::TreeNode;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// Kinds of nodes whose children are statements run in order
+const BLOCK_KINDS: &[&str] = &["Program", "BlockStatement"];
+
+/// Kinds that may run code or hide the names they read, so a declaration
+/// containing one keeps its position
+const OPAQUE_KINDS: &[&str] = &["CallExpression", "Expression", "Statement"];
+
+/// Names a statement declares and reads
+struct Bindings<'a> {
+    declares: HashSet<&'a str>,
+    reads: HashSet<&'a str>,
+}
+
+impl Bindings<'_> {
+    fn depends_on(&self, earlier: &Bindings<'_>) -> bool {
+        let overlaps = |a: &HashSet<&str>, b: &HashSet<&str>| !a.is_disjoint(b);
+        overlaps(&earlier.declares, &self.reads)
+            || overlaps(&earlier.declares, &self.declares)
+            || overlaps(&earlier.reads, &self.declares)
+    }
+}
+
+/// Return the tree with each run of independent declarations in canonical
+/// order. Unchanged subtrees are shared instead of copied.
+#[must_use]
+pub fn canonicalize_statement_order(tree: &Rc<TreeNode>) -> Rc<TreeNode> {
+    let mut changed = false;
+    let mut children: Vec<Rc<TreeNode>> = tree
+        .children
+        .iter()
+        .map(|child| {
+            let canonical = canonicalize_statement_order(child);
+            changed |= !Rc::ptr_eq(&canonical, child);
+            canonical
+        })
+        .collect();
+
+    if BLOCK_KINDS.contains(&tree.value.as_str()) {
+        changed |= sort_declaration_runs(&mut children);
+    }
+
+    if !changed {
+        return Rc::clone(tree);
+    }
+    Rc::new(TreeNode {
+        label: tree.label.clone(),
+        value: tree.value.clone(),
+        children,
+        id: tree.id,
+        subtree_size: None,
+        weight: tree.weight,
+    })
+}
+
+/// Sort every maximal run of movable declarations; whether anything moved
+fn sort_declaration_runs(statements: &mut [Rc<TreeNode>]) -> bool {
+    let mut changed = false;
+    let mut start = 0;
+    while start < statements.len() {
+        let end = (start..statements.len())
+            .find(|&index| bindings(&statements[index]).is_none())
+            .unwrap_or(statements.len());
+        if end - start > 1 {
+            changed |= sort_run(&mut statements[start..end]);
+        }
+        start = end + 1;
+    }
+    changed
+}
+
+/// Topological order of a run, taking the ready statement of the smallest
+/// shape first (and the earlier one among equal shapes)
+fn sort_run(run: &mut [Rc<TreeNode>]) -> bool {
+    let all_bindings: Vec<Bindings<'_>> =
+        run.iter().map(|statement| bindings(statement).unwrap_or_else(empty_bindings)).collect();
+    let shapes: Vec<String> = run.iter().map(|statement| shape(statement)).collect();
+
+    let mut placed = vec![false; run.len()];
+    let mut order = Vec::with_capacity(run.len());
+    while order.len() < run.len() {
+        let next = (0..run.len())
+            .filter(|&index| !placed[index])
+            .filter(|&index| {
+                (0..index).all(|earlier| {
+                    placed[earlier] || !all_bindings[index].depends_on(&all_bindings[earlier])
+                })
+            })
+            .min_by(|&a, &b| shapes[a].cmp(&shapes[b]).then(a.cmp(&b)))
+            .expect("the first unplaced statement is always ready");
+        placed[next] = true;
+        order.push(next);
+    }
+
+    if order.iter().enumerate().all(|(position, &index)| position == index) {
+        return false;
+    }
+    let sorted: Vec<Rc<TreeNode>> = order.iter().map(|&index| Rc::clone(&run[index])).collect();
+    run.clone_from_slice(&sorted);
+    true
+}
+
+fn empty_bindings<'a>() -> Bindings<'a> {
+    Bindings { declares: HashSet::new(), reads: HashSet::new() }
+}
+
+/// Bindings of a variable declaration free of side effects, `None` for any
+/// other statement
+fn bindings(statement: &TreeNode) -> Option<Bindings<'_>> {
+    if statement.value != "VariableDeclaration" {
+        return None;
+    }
+    let mut found = empty_bindings();
+    for declarator in &statement.children {
+        found.declares.insert(declarator.label.as_str());
+        for init in &declarator.children {
+            if !collect_reads(init, &mut found.reads) {
+                return None;
+            }
+        }
+    }
+    Some(found)
+}
+
+/// Add the identifiers read under `node`; false if it contains an opaque node
+fn collect_reads<'a>(node: &'a TreeNode, reads: &mut HashSet<&'a str>) -> bool {
+    if OPAQUE_KINDS.contains(&node.value.as_str()) {
+        return false;
+    }
+    if node.value == "Identifier" {
+        reads.insert(node.label.as_str());
+    }
+    node.children.iter().all(|child| collect_reads(child, reads))
+}
+
+/// Node kinds in preorder, independent of names
+fn shape(node: &TreeNode) -> String {
+    let mut output = node.value.clone();
+    for child in &node.children {
+        output.push('(');
+        output.push_str(&shape(child));
+        output.push(')');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_and_convert_to_tree;
+
+    fn labels(tree: &TreeNode) -> Vec<&str> {
+        tree.children.iter().map(|child| child.children[0].label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_independent_declarations_get_the_same_order() {
+        let first = parse_and_convert_to_tree("a.ts", "const a = x + 1;\nconst b = true;").unwrap();
+        let second =
+            parse_and_convert_to_tree("b.ts", "const q = true;\nconst p = y + 1;").unwrap();
+
+        let (first, second) =
+            (canonicalize_statement_order(&first), canonicalize_statement_order(&second));
+        assert_eq!(shape(&first), shape(&second));
+        assert_eq!(labels(&second), ["p", "q"]);
+    }
+
+    #[test]
+    fn test_dependencies_and_side_effects_keep_their_order() {
+        // `b` reads `a`, so it cannot move before it
+        let dependent =
+            parse_and_convert_to_tree("a.ts", "const a = 1;\nconst b = a + a;").unwrap();
+        assert!(Rc::ptr_eq(&canonicalize_statement_order(&dependent), &dependent));
+
+        // Calls may have effects and split runs
+        let effects =
+            parse_and_convert_to_tree("b.ts", "const a = x + 1;\nlog();\nconst b = true;").unwrap();
+        assert!(Rc::ptr_eq(&canonicalize_statement_order(&effects), &effects));
+    }
+}
@@ -0,0 +1,233 @@
+//! Structural search by example.
+//!
+//! A pattern is a TypeScript function whose `__ANY__` identifiers are holes:
+//!
+//! ```ts
+//! function __ANY__(__ANY__) {
+//!     const result = [];
+//!     __ANY__;
+//!     return result;
+//! }
+//! ```
+//!
+//! A hole standing for a statement, an argument or a parameter matches any
+//! run of sibling nodes, including none; a hole naming a node with children
+//! (the function above) matches any name. The rest of the pattern is compared
+//! with the same tree edit distance as the duplicate search, and a function
+//! scores `1 - distance / pattern size`, where the size leaves holes out.
+
+use crate::apted::APTEDOptions;
+use crate::function_extractor::{extract_functions, parse_function_tree, FunctionDefinition};
+use crate::tree::TreeNode;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Identifier marking a hole in a pattern
+pub const HOLE: &str = "__ANY__";
+
+/// Function of a pattern file
+#[derive(Debug, Clone)]
+pub struct StructuralPattern {
+    pub name: String,
+    tree: Rc<TreeNode>,
+    /// Nodes of the tree that are not holes
+    size: usize,
+}
+
+/// Function matching a pattern
+#[derive(Debug, Clone)]
+pub struct StructuralMatch<'a> {
+    pub function: FunctionDefinition,
+    pub pattern: &'a StructuralPattern,
+    pub similarity: f64,
+}
+
+impl StructuralPattern {
+    /// Patterns of every function in a pattern file
+    pub fn parse(file: &str, source_text: &str) -> Result<Vec<Self>, String> {
+        let patterns: Vec<Self> = extract_functions(file, source_text)?
+            .iter()
+            .filter_map(|function| {
+                let tree = parse_function_tree(function, source_text)?.without_comments();
+                Some(Self { name: function.name.to_string(), size: concrete_size(&tree), tree })
+            })
+            .collect();
+        if patterns.is_empty() {
+            return Err(format!("{} has no functions to use as patterns", file));
+        }
+        Ok(patterns)
+    }
+
+    /// Similarity of a function tree to the pattern, 1.0 when the pattern
+    /// matches it exactly
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn similarity(&self, tree: &Rc<TreeNode>, options: &APTEDOptions) -> f64 {
+        if self.size == 0 {
+            return 1.0;
+        }
+        let tree = tree.without_comments();
+        let mut memo = HashMap::new();
+        let distance = distance(&self.tree, &tree, options, &mut memo);
+        (1.0 - distance / self.size as f64).max(0.0)
+    }
+}
+
+/// Functions of a source file scoring at least `threshold` against a
+/// pattern, each with its best pattern
+pub fn search_source<'a>(
+    patterns: &'a [StructuralPattern],
+    file: &str,
+    source_text: &str,
+    threshold: f64,
+    options: &APTEDOptions,
+) -> Result<Vec<StructuralMatch<'a>>, String> {
+    let mut matches = Vec::new();
+    for function in extract_functions(file, source_text)? {
+        if function.has_ignore_directive {
+            continue;
+        }
+        let Some(tree) = parse_function_tree(&function, source_text) else {
+            continue;
+        };
+
+        let best = patterns
+            .iter()
+            .map(|pattern| (pattern, pattern.similarity(&tree, options)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((pattern, similarity)) = best {
+            if similarity >= threshold {
+                matches.push(StructuralMatch { function, pattern, similarity });
+            }
+        }
+    }
+    Ok(matches)
+}
+
+fn is_hole(node: &TreeNode) -> bool {
+    node.label == HOLE
+}
+
+/// Holes without children stand for any run of siblings
+fn is_sequence_hole(node: &TreeNode) -> bool {
+    is_hole(node) && node.children.is_empty()
+}
+
+fn concrete_size(node: &TreeNode) -> usize {
+    let own = usize::from(!is_hole(node));
+    own + node.children.iter().map(|child| concrete_size(child)).sum::<usize>()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn distance(
+    pattern: &Rc<TreeNode>,
+    target: &Rc<TreeNode>,
+    options: &APTEDOptions,
+    memo: &mut HashMap<(usize, usize), f64>,
+) -> f64 {
+    if is_sequence_hole(pattern) {
+        return 0.0;
+    }
+    let key = (pattern.id, target.id);
+    if let Some(&cost) = memo.get(&key) {
+        return cost;
+    }
+
+    let labels_match = is_hole(pattern)
+        || (pattern.label == target.label
+            && (!options.compare_values || pattern.value == target.value));
+    let rename_cost = if labels_match { 0.0 } else { options.rename_cost };
+    let replace_cost = options.delete_cost * concrete_size(pattern) as f64
+        + options.insert_cost * target.get_subtree_size() as f64;
+    let cost = (rename_cost + align_children(&pattern.children, &target.children, options, memo))
+        .min(replace_cost);
+
+    memo.insert(key, cost);
+    cost
+}
+
+/// Cost of aligning the children of a pattern node with those of a target
+/// node, in order; a sequence hole absorbs any run of target children for free
+#[allow(clippy::cast_precision_loss)]
+fn align_children(
+    pattern: &[Rc<TreeNode>],
+    target: &[Rc<TreeNode>],
+    options: &APTEDOptions,
+    memo: &mut HashMap<(usize, usize), f64>,
+) -> f64 {
+    let n = target.len();
+    let insert = |node: &TreeNode| options.insert_cost * node.get_subtree_size() as f64;
+
+    // previous[j] = cost of aligning the pattern children so far with the first j target children
+    let mut previous = vec![0.0; n + 1];
+    for j in 1..=n {
+        previous[j] = previous[j - 1] + insert(&target[j - 1]);
+    }
+
+    for child in pattern {
+        let mut current = vec![0.0; n + 1];
+        if is_sequence_hole(child) {
+            let mut best = f64::MAX;
+            for j in 0..=n {
+                best = best.min(previous[j]);
+                current[j] = best;
+            }
+        } else {
+            let delete = options.delete_cost * concrete_size(child) as f64;
+            current[0] = previous[0] + delete;
+            for j in 1..=n {
+                current[j] = (previous[j] + delete)
+                    .min(current[j - 1] + insert(&target[j - 1]))
+                    .min(previous[j - 1] + distance(child, &target[j - 1], options, memo));
+            }
+        }
+        previous = current;
+    }
+    previous[n]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERN: &str = r#"
+function __ANY__(__ANY__) {
+    const result = [];
+    __ANY__;
+    return result;
+}
+"#;
+
+    #[test]
+    fn test_holes_match_any_code() {
+        let patterns = StructuralPattern::parse("pattern.ts", PATTERN).unwrap();
+        let source = r#"
+function collectNames(users, prefix) {
+    const result = [];
+    for (const item of users) {
+        const name = prefix + item.name;
+        result.push(name);
+    }
+    return result;
+}
+
+function sum(values) {
+    let total = 0;
+    values.forEach((value) => {
+        total += value;
+    });
+    return total;
+}
+"#;
+        let options = APTEDOptions { compare_values: false, ..Default::default() };
+
+        let matches = search_source(&patterns, "users.ts", source, 0.9, &options).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].function.name, "collectNames");
+        assert!((matches[0].similarity - 1.0).abs() < f64::EPSILON);
+
+        let all = search_source(&patterns, "users.ts", source, 0.0, &options).unwrap();
+        let sum = all.iter().find(|found| found.function.name == "sum").unwrap();
+        assert!(sum.similarity < 0.9, "{}", sum.similarity);
+    }
+}
@@ -1,4 +1,11 @@
-use std::collections::HashMap;
+use crate::interner::Symbol;
+use crate::name_similarity::{name_similarity, NameSimilarityMetric};
+use crate::semantic_types::SemanticTypeResolver;
+use crate::size_penalty::SizePenalty;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
 /// 一般化された構造定義
 #[derive(Debug, Clone)]
@@ -15,12 +22,12 @@ pub struct Structure {
 
 #[derive(Debug, Clone)]
 pub struct StructureIdentifier {
-    pub name: String,
+    pub name: Symbol,
     pub kind: StructureKind,
-    pub namespace: Option<String>,
+    pub namespace: Option<Symbol>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum StructureKind {
     TypeScriptInterface,
     TypeScriptTypeAlias,
@@ -36,7 +43,7 @@ pub enum StructureKind {
 #[derive(Debug, Clone)]
 pub struct StructureMember {
     pub name: String,
-    pub value_type: String,
+    pub value_type: Symbol,
     pub modifiers: Vec<String>,
     pub nested: Option<Box<Structure>>,
 }
@@ -51,7 +58,7 @@ pub struct StructureMetadata {
 
 #[derive(Debug, Clone, Default)]
 pub struct SourceLocation {
-    pub file_path: String,
+    pub file_path: Symbol,
     pub start_line: usize,
     pub end_line: usize,
 }
@@ -89,8 +96,11 @@ pub struct ComparisonOptions {
     pub ignore_order: bool,
     pub fuzzy_matching: bool,
     pub threshold: f64,
-    pub strict_size_check: bool,  // サイズチェックを厳格にする
-    pub require_type_match: bool, // 型の一致を要求する
+    pub strict_size_check: bool,           // サイズチェックを厳格にする
+    pub require_type_match: bool,          // 型の一致を要求する
+    pub name_metric: NameSimilarityMetric, // 名前の類似度に使う指標
+    pub type_synonyms: HashMap<String, String>, // Semantic戦略で使う型の別名表
+    pub size_ratio_penalty: SizePenalty,   // メンバー数の比によるペナルティ曲線
 }
 
 impl Default for ComparisonOptions {
@@ -104,26 +114,76 @@ impl Default for ComparisonOptions {
             threshold: 0.7,
             strict_size_check: true,
             require_type_match: false,
+            name_metric: NameSimilarityMetric::default(),
+            type_synonyms: HashMap::new(),
+            size_ratio_penalty: SizePenalty::default(),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// How member types are compared, chosen with `--member-comparison`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum MemberComparisonStrategy {
+    /// Types as written
     Exact,
+    /// Equal types, or partly for types of the same category (string, array, ...)
+    #[default]
     Normalized,
+    /// Types through aliases, unwrapped containers, numeric families and the
+    /// `[type_synonyms]` of `similarity.toml`
     Semantic,
 }
 
+impl MemberComparisonStrategy {
+    pub const ALL: [MemberComparisonStrategy; 3] = [
+        MemberComparisonStrategy::Exact,
+        MemberComparisonStrategy::Normalized,
+        MemberComparisonStrategy::Semantic,
+    ];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MemberComparisonStrategy::Exact => "exact",
+            MemberComparisonStrategy::Normalized => "normalized",
+            MemberComparisonStrategy::Semantic => "semantic",
+        }
+    }
+}
+
+impl fmt::Display for MemberComparisonStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MemberComparisonStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MemberComparisonStrategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown member comparison '{}' (expected exact, normalized or semantic)",
+                    s
+                )
+            })
+    }
+}
+
 /// 汎用構造比較エンジン
 pub struct StructureComparator {
     options: ComparisonOptions,
-    fingerprint_cache: HashMap<String, String>,
+    semantic_types: SemanticTypeResolver,
 }
 
 impl StructureComparator {
     pub fn new(options: ComparisonOptions) -> Self {
-        Self { options, fingerprint_cache: HashMap::new() }
+        let semantic_types = SemanticTypeResolver::with_synonyms(options.type_synonyms.clone());
+        Self { options, semantic_types }
     }
 
     pub fn compare(&mut self, s1: &Structure, s2: &Structure) -> StructureComparisonResult {
@@ -161,30 +221,33 @@ impl StructureComparator {
         }
 
         let ratio = min_size / max_size;
-
-        if self.options.strict_size_check {
-            // 厳格モード: より強いペナルティ
-            if ratio < 0.3 {
-                // 30%未満: 非常に強いペナルティ
-                ratio * ratio * 0.5
-            } else if ratio < 0.5 {
-                // 30-50%: 強いペナルティ
-                ratio * ratio
-            } else if ratio < 0.7 {
-                // 50-70%: 中程度のペナルティ
-                0.4 + (ratio * 0.6)
-            } else {
-                // 70%以上: 軽いペナルティ
-                0.7 + (ratio * 0.3)
-            }
-        } else {
-            // 通常モード: 従来のペナルティ
-            if ratio < 0.5 {
-                ratio * ratio
+        let strict = self.options.strict_size_check;
+
+        self.options.size_ratio_penalty.factor(ratio, |ratio| {
+            if strict {
+                // 厳格モード: より強いペナルティ
+                if ratio < 0.3 {
+                    // 30%未満: 非常に強いペナルティ
+                    ratio * ratio * 0.5
+                } else if ratio < 0.5 {
+                    // 30-50%: 強いペナルティ
+                    ratio * ratio
+                } else if ratio < 0.7 {
+                    // 50-70%: 中程度のペナルティ
+                    0.4 + (ratio * 0.6)
+                } else {
+                    // 70%以上: 軽いペナルティ
+                    0.7 + (ratio * 0.3)
+                }
             } else {
-                0.25 + (ratio * 0.75)
+                // 通常モード: 従来のペナルティ
+                if ratio < 0.5 {
+                    ratio * ratio
+                } else {
+                    0.25 + (ratio * 0.75)
+                }
             }
-        }
+        })
     }
 
     fn compare_identifiers(&self, id1: &StructureIdentifier, id2: &StructureIdentifier) -> f64 {
@@ -192,7 +255,7 @@ impl StructureComparator {
         let kind_factor = if id1.kind == id2.kind { 1.0 } else { 0.8 };
 
         // 名前の類似性
-        let name_similarity = calculate_string_similarity(&id1.name, &id2.name);
+        let name_similarity = name_similarity(&id1.name, &id2.name, self.options.name_metric);
 
         name_similarity * kind_factor
     }
@@ -255,7 +318,7 @@ impl StructureComparator {
                 let m1 = members1.iter().find(|member| member.name == m.member1)?;
                 let m2 = members2.iter().find(|member| member.name == m.member2)?;
                 if m1.value_type != m2.value_type {
-                    Some((m.member1.clone(), m1.value_type.clone(), m2.value_type.clone()))
+                    Some((m.member1.clone(), m1.value_type.to_string(), m2.value_type.to_string()))
                 } else {
                     None
                 }
@@ -292,7 +355,7 @@ impl StructureComparator {
     }
 
     fn compare_single_member(&self, m1: &StructureMember, m2: &StructureMember) -> f64 {
-        let name_sim = calculate_string_similarity(&m1.name, &m2.name);
+        let name_sim = name_similarity(&m1.name, &m2.name, self.options.name_metric);
 
         let type_sim = match self.options.member_comparison {
             MemberComparisonStrategy::Exact => {
@@ -306,8 +369,8 @@ impl StructureComparator {
                 calculate_type_similarity(&m1.value_type, &m2.value_type)
             }
             MemberComparisonStrategy::Semantic => {
-                // 意味的な類似性（将来実装）
-                calculate_type_similarity(&m1.value_type, &m2.value_type)
+                // 別名解決・配列/Promiseの展開・数値型ファミリーを考慮
+                self.semantic_types.similarity(&m1.value_type, &m2.value_type)
             }
         };
 
@@ -318,149 +381,251 @@ impl StructureComparator {
         0.4 * name_sim + 0.5 * type_sim + 0.1 * modifier_sim
     }
 
-    pub fn generate_fingerprint(&mut self, structure: &Structure) -> String {
-        let key = format!(
-            "{}::{}",
-            structure.identifier.namespace.as_deref().unwrap_or(""),
-            structure.identifier.name
-        );
+    pub fn generate_fingerprint(&self, structure: &Structure) -> StructureFingerprint {
+        compute_structure_fingerprint(structure)
+    }
+}
+
+/// メンバー数の大まかな分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SizeCategory {
+    Empty,
+    Single,
+    Small,
+    Medium,
+    Large,
+    Huge,
+}
+
+impl SizeCategory {
+    pub fn from_member_count(count: usize) -> Self {
+        match count {
+            0 => SizeCategory::Empty,
+            1 => SizeCategory::Single,
+            2..=3 => SizeCategory::Small,
+            4..=6 => SizeCategory::Medium,
+            7..=10 => SizeCategory::Large,
+            _ => SizeCategory::Huge,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SizeCategory::Empty => "empty",
+            SizeCategory::Single => "single",
+            SizeCategory::Small => "small",
+            SizeCategory::Medium => "medium",
+            SizeCategory::Large => "large",
+            SizeCategory::Huge => "huge",
+        }
+    }
 
-        self.fingerprint_cache
-            .entry(key)
-            .or_insert_with(|| compute_structure_fingerprint(structure))
-            .clone()
+    fn distance(self, other: Self) -> usize {
+        (self as usize).abs_diff(other as usize)
     }
 }
 
-/// 構造のフィンガープリントを計算
-pub fn compute_structure_fingerprint(structure: &Structure) -> String {
-    let mut parts = Vec::new();
-
-    // 種類
-    parts.push(format!("kind:{:?}", structure.identifier.kind));
-
-    // メンバー数（より細かい分類）
-    let member_count = structure.members.len();
-    let member_category = match member_count {
-        0 => "empty",
-        1 => "single",
-        2..=3 => "small",
-        4..=6 => "medium",
-        7..=10 => "large",
-        _ => "huge",
-    };
-    parts.push(format!("size:{}", member_category));
-    parts.push(format!("members:{}", member_count));
+/// 正規化した型の分類（表示順に並べる）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TypeCategory {
+    Array,
+    Boolean,
+    Number,
+    Object,
+    Other,
+    String,
+}
 
-    // 型の分布を計算
-    let mut type_counts: HashMap<String, usize> = HashMap::new();
-    for member in &structure.members {
-        let normalized_type = normalize_type(&member.value_type);
-        *type_counts.entry(normalized_type).or_insert(0) += 1;
+impl TypeCategory {
+    pub const ALL: [TypeCategory; 6] = [
+        TypeCategory::Array,
+        TypeCategory::Boolean,
+        TypeCategory::Number,
+        TypeCategory::Object,
+        TypeCategory::Other,
+        TypeCategory::String,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TypeCategory::Array => "array",
+            TypeCategory::Boolean => "boolean",
+            TypeCategory::Number => "number",
+            TypeCategory::Object => "object",
+            TypeCategory::Other => "other",
+            TypeCategory::String => "string",
+        }
     }
+}
 
-    // ソートして一貫性を保つ
-    let mut type_entries: Vec<_> = type_counts.iter().collect();
-    type_entries.sort_by_key(|(k, _)| k.as_str());
+/// 構造のフィンガープリント。文字列を組み立てずにバケットのキーとして使う
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StructureFingerprint {
+    pub kind: StructureKind,
+    pub size: SizeCategory,
+    pub members: usize,
+    /// 型の分布（`TypeCategory::ALL` の順）
+    pub type_counts: [usize; TypeCategory::ALL.len()],
+    pub generics: usize,
+}
 
-    for (type_name, count) in type_entries {
-        parts.push(format!("{}:{}", type_name, count));
+/// `kind:TypeScriptInterface,size:small,members:3,array:1,string:2` の形式で表示する
+impl fmt::Display for StructureFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "kind:{:?},size:{},members:{}", self.kind, self.size.as_str(), self.members)?;
+        for (category, count) in TypeCategory::ALL.iter().zip(self.type_counts) {
+            if count > 0 {
+                write!(f, ",{}:{}", category.as_str(), count)?;
+            }
+        }
+        if self.generics > 0 {
+            write!(f, ",generics:{}", self.generics)?;
+        }
+        Ok(())
     }
+}
 
-    // ジェネリクスがあれば追加
-    if !structure.metadata.generics.is_empty() {
-        parts.push(format!("generics:{}", structure.metadata.generics.len()));
+/// 構造のフィンガープリントを計算
+pub fn compute_structure_fingerprint(structure: &Structure) -> StructureFingerprint {
+    // 型の分布を計算
+    let mut type_counts = [0; TypeCategory::ALL.len()];
+    for member in &structure.members {
+        type_counts[normalize_type(&member.value_type) as usize] += 1;
     }
 
-    parts.join(",")
+    StructureFingerprint {
+        kind: structure.identifier.kind.clone(),
+        size: SizeCategory::from_member_count(structure.members.len()),
+        members: structure.members.len(),
+        type_counts,
+        generics: structure.metadata.generics.len(),
+    }
 }
 
 /// フィンガープリントが比較対象として妥当かチェック
-pub fn should_compare_fingerprints(fp1: &str, fp2: &str) -> bool {
-    let parts1 = parse_fingerprint(fp1);
-    let parts2 = parse_fingerprint(fp2);
-
+pub fn should_compare_fingerprints(fp1: &StructureFingerprint, fp2: &StructureFingerprint) -> bool {
     // 種類が違う場合は比較しない（TypeScriptInterfaceとRustStructなど）
-    if let (Some(kind1), Some(kind2)) = (parts1.get("kind"), parts2.get("kind")) {
-        if kind1 != kind2 {
-            return false;
-        }
+    if fp1.kind != fp2.kind {
+        return false;
     }
 
     // サイズカテゴリが大きく異なる場合は比較しない
-    if let (Some(size1), Some(size2)) = (parts1.get("size"), parts2.get("size")) {
-        let size_diff = size_category_distance(size1, size2);
-        if size_diff > 2 {
-            return false;
-        }
+    if fp1.size.distance(fp2.size) > 2 {
+        return false;
     }
 
     // メンバー数が大きく異なる場合は比較しない
-    if let (Some(members1), Some(members2)) = (parts1.get("members"), parts2.get("members")) {
-        if let (Ok(count1), Ok(count2)) = (members1.parse::<usize>(), members2.parse::<usize>()) {
-            let min = count1.min(count2);
-            let max = count1.max(count2);
-            if max > 0 && (min as f64 / max as f64) < 0.3 {
-                return false;
-            }
-        }
+    let min = fp1.members.min(fp2.members);
+    let max = fp1.members.max(fp2.members);
+    if max > 0 && (min as f64 / max as f64) < 0.3 {
+        return false;
     }
 
     true
 }
 
-fn parse_fingerprint(fp: &str) -> HashMap<String, String> {
-    fp.split(',')
-        .filter_map(|part| {
-            let mut iter = part.split(':');
-            Some((iter.next()?.to_string(), iter.next()?.to_string()))
-        })
-        .collect()
+/// フィンガープリントのバケット統計（リコール調査用）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BucketStats {
+    pub structure_count: usize,
+    pub bucket_count: usize,
+    pub largest_bucket: usize,
+    /// 比較対象になったバケットの組み合わせ数（同一バケットを含む）
+    pub compared_bucket_pairs: usize,
+    /// フィンガープリントが離れているためスキップした組み合わせ数
+    pub skipped_bucket_pairs: usize,
+    /// 重複を除いた比較候補ペア数
+    pub candidate_pairs: usize,
 }
 
-fn size_category_distance(cat1: &str, cat2: &str) -> usize {
-    let categories = ["empty", "single", "small", "medium", "large", "huge"];
-    let pos1 = categories.iter().position(|&c| c == cat1).unwrap_or(0);
-    let pos2 = categories.iter().position(|&c| c == cat2).unwrap_or(0);
-    pos1.abs_diff(pos2)
+/// 構造をフィンガープリントでグループ化し、安定したインデックスで保持する
+#[derive(Debug, Clone, Default)]
+pub struct FingerprintBuckets {
+    structures: Vec<Structure>,
+    locations: HashSet<(Symbol, Symbol, usize, usize)>,
+    buckets: BTreeMap<StructureFingerprint, Vec<usize>>,
 }
 
-/// 型を正規化
-fn normalize_type(type_str: &str) -> String {
-    // Check for array patterns first (before checking for the base type)
-    if type_str.contains("[]") || type_str.contains("Array") {
-        return "array".to_string();
+impl FingerprintBuckets {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    match type_str {
-        s if s.contains("string") => "string".to_string(),
-        s if s.contains("number") => "number".to_string(),
-        s if s.contains("boolean") => "boolean".to_string(),
-        s if s.contains("{") && s.contains("}") => "object".to_string(),
-        _ => "other".to_string(),
+    /// 構造を追加する。同じ位置の構造が登録済みなら追加せず false を返す
+    pub fn insert(&mut self, structure: Structure, fingerprint: StructureFingerprint) -> bool {
+        let location = &structure.metadata.location;
+        let key =
+            (structure.identifier.name, location.file_path, location.start_line, location.end_line);
+        if !self.locations.insert(key) {
+            return false;
+        }
+
+        self.buckets.entry(fingerprint).or_default().push(self.structures.len());
+        self.structures.push(structure);
+        true
     }
-}
 
-/// 文字列の類似性を計算
-fn calculate_string_similarity(s1: &str, s2: &str) -> f64 {
-    if s1 == s2 {
-        return 1.0;
+    pub fn structures(&self) -> &[Structure] {
+        &self.structures
     }
 
-    let len1 = s1.len();
-    let len2 = s2.len();
-    let max_len = len1.max(len2) as f64;
+    /// 比較すべき順序なしペアを (i, j), i < j の形で一度ずつ返す
+    pub fn candidate_pairs(&self) -> Vec<(usize, usize)> {
+        self.collect_candidates().0
+    }
 
-    if max_len == 0.0 {
-        return 1.0;
+    pub fn stats(&self) -> BucketStats {
+        self.collect_candidates().1
+    }
+
+    fn collect_candidates(&self) -> (Vec<(usize, usize)>, BucketStats) {
+        let buckets: Vec<(&StructureFingerprint, &Vec<usize>)> = self.buckets.iter().collect();
+        let mut stats = BucketStats {
+            structure_count: self.structures.len(),
+            bucket_count: buckets.len(),
+            largest_bucket: buckets.iter().map(|(_, indices)| indices.len()).max().unwrap_or(0),
+            ..Default::default()
+        };
+
+        // バケットは互いに素なので、各ペアは一度しか現れない
+        let mut pairs = Vec::new();
+
+        for (i, (fp1, indices1)) in buckets.iter().enumerate() {
+            for (offset, (fp2, indices2)) in buckets[i..].iter().enumerate() {
+                if !should_compare_fingerprints(fp1, fp2) {
+                    stats.skipped_bucket_pairs += 1;
+                    continue;
+                }
+                stats.compared_bucket_pairs += 1;
+
+                for (position, &a) in indices1.iter().enumerate() {
+                    let others =
+                        if offset == 0 { &indices2[position + 1..] } else { &indices2[..] };
+                    pairs.extend(others.iter().map(|&b| (a.min(b), a.max(b))));
+                }
+            }
+        }
+
+        pairs.sort_unstable();
+        stats.candidate_pairs = pairs.len();
+        (pairs, stats)
     }
+}
 
-    // 簡単なレーベンシュタイン距離の近似
-    let common_prefix = s1.chars().zip(s2.chars()).take_while(|(a, b)| a == b).count();
-    let common_suffix = s1.chars().rev().zip(s2.chars().rev()).take_while(|(a, b)| a == b).count();
-    let common = (common_prefix + common_suffix).min(len1.min(len2));
+/// 型を正規化
+fn normalize_type(type_str: &str) -> TypeCategory {
+    // Check for array patterns first (before checking for the base type)
+    if type_str.contains("[]") || type_str.contains("Array") {
+        return TypeCategory::Array;
+    }
 
-    common as f64 / max_len
+    match type_str {
+        s if s.contains("string") => TypeCategory::String,
+        s if s.contains("number") => TypeCategory::Number,
+        s if s.contains("boolean") => TypeCategory::Boolean,
+        s if s.contains("{") && s.contains("}") => TypeCategory::Object,
+        _ => TypeCategory::Other,
+    }
 }
 
 /// 型の類似性を計算
@@ -1,5 +1,6 @@
 #[cfg(test)]
 mod tests {
+    use crate::interner::Symbol;
     use crate::structure_comparator::*;
     use crate::type_extractor::{PropertyDefinition, TypeDefinition, TypeKind};
     use crate::typescript_structure_adapter::*;
@@ -10,20 +11,20 @@ mod tests {
 
         let struct1 = Structure {
             identifier: StructureIdentifier {
-                name: "User".to_string(),
+                name: "User".into(),
                 kind: StructureKind::TypeScriptInterface,
-                namespace: Some("test.ts".to_string()),
+                namespace: Some("test.ts".into()),
             },
             members: vec![
                 StructureMember {
                     name: "id".to_string(),
-                    value_type: "string".to_string(),
+                    value_type: "string".into(),
                     modifiers: vec![],
                     nested: None,
                 },
                 StructureMember {
                     name: "name".to_string(),
-                    value_type: "string".to_string(),
+                    value_type: "string".into(),
                     modifiers: vec![],
                     nested: None,
                 },
@@ -33,20 +34,20 @@ mod tests {
 
         let struct2 = Structure {
             identifier: StructureIdentifier {
-                name: "Person".to_string(),
+                name: "Person".into(),
                 kind: StructureKind::TypeScriptInterface,
-                namespace: Some("test.ts".to_string()),
+                namespace: Some("test.ts".into()),
             },
             members: vec![
                 StructureMember {
                     name: "id".to_string(),
-                    value_type: "string".to_string(),
+                    value_type: "string".into(),
                     modifiers: vec![],
                     nested: None,
                 },
                 StructureMember {
                     name: "name".to_string(),
-                    value_type: "string".to_string(),
+                    value_type: "string".into(),
                     modifiers: vec![],
                     nested: None,
                 },
@@ -68,26 +69,26 @@ mod tests {
     fn test_fingerprint_generation() {
         let structure = Structure {
             identifier: StructureIdentifier {
-                name: "User".to_string(),
+                name: "User".into(),
                 kind: StructureKind::TypeScriptInterface,
-                namespace: Some("test.ts".to_string()),
+                namespace: Some("test.ts".into()),
             },
             members: vec![
                 StructureMember {
                     name: "id".to_string(),
-                    value_type: "string".to_string(),
+                    value_type: "string".into(),
                     modifiers: vec![],
                     nested: None,
                 },
                 StructureMember {
                     name: "age".to_string(),
-                    value_type: "number".to_string(),
+                    value_type: "number".into(),
                     modifiers: vec![],
                     nested: None,
                 },
                 StructureMember {
                     name: "tags".to_string(),
-                    value_type: "string[]".to_string(),
+                    value_type: "string[]".into(),
                     modifiers: vec![],
                     nested: None,
                 },
@@ -95,7 +96,7 @@ mod tests {
             metadata: StructureMetadata::default(),
         };
 
-        let fingerprint = compute_structure_fingerprint(&structure);
+        let fingerprint = compute_structure_fingerprint(&structure).to_string();
 
         println!("Fingerprint: {}", fingerprint);
 
@@ -115,11 +116,11 @@ mod tests {
         let mut comparator = TypeScriptStructureComparator::new();
 
         let type1 = TypeDefinition {
-            name: "User".to_string(),
+            name: "User".into(),
             kind: TypeKind::Interface,
             properties: vec![PropertyDefinition {
                 name: "id".to_string(),
-                type_annotation: "string".to_string(),
+                type_annotation: "string".into(),
                 optional: false,
                 readonly: false,
             }],
@@ -127,16 +128,17 @@ mod tests {
             extends: vec![],
             start_line: 1,
             end_line: 5,
-            file_path: "test.ts".to_string(),
+            file_path: "test.ts".into(),
             has_ignore_directive: false,
+            partial: false,
         };
 
         let type2 = TypeDefinition {
-            name: "User".to_string(),
+            name: "User".into(),
             kind: TypeKind::Interface,
             properties: vec![PropertyDefinition {
                 name: "id".to_string(),
-                type_annotation: "string".to_string(),
+                type_annotation: "string".into(),
                 optional: false,
                 readonly: false,
             }],
@@ -144,8 +146,9 @@ mod tests {
             extends: vec![],
             start_line: 10,
             end_line: 15,
-            file_path: "test.ts".to_string(),
+            file_path: "test.ts".into(),
             has_ignore_directive: false,
+            partial: false,
         };
 
         let result = comparator.compare_types(&type1, &type2);
@@ -156,4 +159,137 @@ mod tests {
         assert!(result.differences.missing_members.is_empty());
         assert!(result.differences.extra_members.is_empty());
     }
+
+    fn structure(
+        name: &str,
+        kind: StructureKind,
+        start_line: usize,
+        members: &[(&str, &str)],
+    ) -> Structure {
+        Structure {
+            identifier: StructureIdentifier {
+                name: name.into(),
+                kind,
+                namespace: Some("test.ts".into()),
+            },
+            members: members
+                .iter()
+                .map(|(name, value_type)| StructureMember {
+                    name: name.to_string(),
+                    value_type: (*value_type).into(),
+                    modifiers: vec![],
+                    nested: None,
+                })
+                .collect(),
+            metadata: StructureMetadata {
+                location: SourceLocation {
+                    file_path: "test.ts".into(),
+                    start_line,
+                    end_line: start_line + members.len() + 1,
+                },
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_buckets_yield_each_pair_once() {
+        let strings = [("id", "string"), ("name", "string"), ("email", "string")];
+        let mixed = [("id", "string"), ("name", "string"), ("age", "number")];
+
+        let mut buckets = FingerprintBuckets::new();
+        for (index, members) in [&strings, &mixed, &strings, &mixed].iter().enumerate() {
+            let structure = structure(
+                &format!("T{}", index),
+                StructureKind::TypeScriptInterface,
+                index * 10,
+                *members,
+            );
+            let fingerprint = compute_structure_fingerprint(&structure);
+            assert!(buckets.insert(structure, fingerprint));
+        }
+
+        let pairs = buckets.candidate_pairs();
+        assert_eq!(pairs, vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]);
+
+        let stats = buckets.stats();
+        assert_eq!(stats.structure_count, 4);
+        assert_eq!(stats.bucket_count, 2);
+        assert_eq!(stats.largest_bucket, 2);
+        assert_eq!(stats.compared_bucket_pairs, 3);
+        assert_eq!(stats.skipped_bucket_pairs, 0);
+        assert_eq!(stats.candidate_pairs, 6);
+    }
+
+    #[test]
+    fn test_fingerprint_buckets_skip_incompatible_kinds_and_duplicates() {
+        let members = [("id", "string"), ("name", "string")];
+        let interface = structure("User", StructureKind::TypeScriptInterface, 1, &members);
+        let rust_struct = structure("User", StructureKind::RustStruct, 20, &members);
+
+        let mut buckets = FingerprintBuckets::new();
+        let fingerprint = compute_structure_fingerprint(&interface);
+        assert!(buckets.insert(interface.clone(), fingerprint.clone()));
+        assert!(!buckets.insert(interface, fingerprint));
+        let fingerprint = compute_structure_fingerprint(&rust_struct);
+        assert!(buckets.insert(rust_struct, fingerprint));
+
+        assert!(buckets.candidate_pairs().is_empty());
+        let stats = buckets.stats();
+        assert_eq!(stats.structure_count, 2);
+        assert_eq!(stats.skipped_bucket_pairs, 1);
+    }
+
+    #[test]
+    fn test_batch_comparator_reports_each_pair_once() {
+        let make_type = |name: &str, start_line: usize| TypeDefinition {
+            name: name.into(),
+            kind: TypeKind::Interface,
+            properties: ["id", "name", "email"]
+                .iter()
+                .map(|prop| PropertyDefinition {
+                    name: prop.to_string(),
+                    type_annotation: "string".into(),
+                    optional: false,
+                    readonly: false,
+                })
+                .collect(),
+            generics: vec![],
+            extends: vec![],
+            start_line,
+            end_line: start_line + 4,
+            file_path: "test.ts".into(),
+            has_ignore_directive: false,
+            partial: false,
+        };
+        let structures: Vec<Structure> =
+            vec![make_type("User", 1), make_type("Person", 10), make_type("Member", 20)]
+                .into_iter()
+                .map(Structure::from)
+                .collect();
+
+        let mut batch = BatchComparator::new();
+        batch.group_by_fingerprint(structures.clone());
+        // Registering the same structures again must not produce duplicate findings
+        batch.group_by_fingerprint(structures);
+
+        let results = batch.find_similar_structures(0.5);
+        assert_eq!(results.len(), 3);
+
+        let mut pairs: Vec<(Symbol, Symbol)> = results
+            .iter()
+            .map(|(s1, s2, _)| {
+                let (a, b) = (s1.identifier.name, s2.identifier.name);
+                if a < b {
+                    (a, b)
+                } else {
+                    (b, a)
+                }
+            })
+            .collect();
+        pairs.sort();
+        pairs.dedup();
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(batch.bucket_stats().structure_count, 3);
+    }
 }
@@ -1,7 +1,7 @@
 use crate::tree::TreeNode;
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
 use std::rc::Rc;
+use xxhash_rust::xxh3::{xxh3_64, xxh3_64_with_seed};
 
 /// Fingerprint for a subtree in the AST
 #[derive(Debug, Clone)]
@@ -23,6 +23,19 @@ pub struct SubtreeFingerprint {
 }
 
 impl SubtreeFingerprint {
+    /// Stand-in for a fingerprint that is filled in later
+    fn placeholder() -> Self {
+        Self {
+            weight: 0,
+            hash: 0,
+            child_hashes: Vec::new(),
+            start_line: 0,
+            end_line: 0,
+            node_type: String::new(),
+            depth: 0,
+        }
+    }
+
     /// Check if two fingerprints might represent similar subtrees
     pub fn might_be_similar(&self, other: &SubtreeFingerprint, size_tolerance: f64) -> bool {
         // Quick hash check for exact matches
@@ -88,14 +101,14 @@ impl IndexedFunction {
 
     /// Add a subtree fingerprint to the index
     pub fn add_subtree(&mut self, fingerprint: SubtreeFingerprint) {
-        // Update hash index
-        self.subtree_index.entry(fingerprint.hash).or_default().push(fingerprint.clone());
+        // Update bloom filter
+        self.update_bloom_filter(&fingerprint);
 
         // Update size index
         self.size_index.entry(fingerprint.weight).or_default().push(fingerprint.clone());
 
-        // Update bloom filter
-        self.update_bloom_filter(&fingerprint);
+        // Update hash index
+        self.subtree_index.entry(fingerprint.hash).or_default().push(fingerprint);
     }
 
     /// Get all subtrees of a specific size
@@ -173,48 +186,47 @@ impl Default for OverlapOptions {
 }
 
 /// Generate fingerprint for a tree node and all its subtrees
+///
+/// Hashes are computed bottom-up, each node combining its label and value
+/// with the hashes of its children, so every node is hashed once. Subtrees
+/// are listed in pre-order.
 pub fn generate_subtree_fingerprints(
     node: &Rc<TreeNode>,
     depth: u32,
     parent_line_offset: u32,
 ) -> (SubtreeFingerprint, Vec<SubtreeFingerprint>) {
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
     let mut all_fingerprints = Vec::new();
-    let mut child_hashes = Vec::new();
-    let mut total_weight = 1u32; // Current node counts as 1
+    let fingerprint = fingerprint_subtree(node, depth, parent_line_offset, &mut all_fingerprints);
+    (fingerprint, all_fingerprints)
+}
 
-    // Hash the node type/label
-    node.label.hash(&mut hasher);
+fn fingerprint_subtree(
+    node: &TreeNode,
+    depth: u32,
+    parent_line_offset: u32,
+    all_fingerprints: &mut Vec<SubtreeFingerprint>,
+) -> SubtreeFingerprint {
+    let mut child_hashes = Vec::with_capacity(node.children.len());
+    let mut total_weight = 1u32; // Current node counts as 1
 
-    // Process children
     for child in &node.children {
-        let (child_fp, child_subtrees) =
-            generate_subtree_fingerprints(child, depth + 1, parent_line_offset);
+        // Reserve the child's slot so that it precedes its own subtrees
+        let slot = all_fingerprints.len();
+        all_fingerprints.push(SubtreeFingerprint::placeholder());
+        let child_fp = fingerprint_subtree(child, depth + 1, parent_line_offset, all_fingerprints);
 
-        // Add child's hash to our list
         child_hashes.push(child_fp.hash);
-        child_fp.hash.hash(&mut hasher);
-
-        // Add child's weight to total
         total_weight += child_fp.weight;
-
-        // Collect all subtree fingerprints
-        all_fingerprints.push(child_fp);
-        all_fingerprints.extend(child_subtrees);
-    }
-
-    // Hash the value if not empty
-    if !node.value.is_empty() {
-        node.value.hash(&mut hasher);
+        all_fingerprints[slot] = child_fp;
     }
 
-    let hash = hasher.finish();
+    let hash = hash_node(&node.label, &node.value, &child_hashes);
 
     // Calculate line numbers (simplified - using node id as proxy for line numbers)
     let start_line = parent_line_offset + node.id as u32;
     let end_line = start_line + total_weight;
 
-    let fingerprint = SubtreeFingerprint {
+    SubtreeFingerprint {
         weight: total_weight,
         hash,
         child_hashes,
@@ -222,9 +234,23 @@ pub fn generate_subtree_fingerprints(
         end_line,
         node_type: node.label.clone(),
         depth,
-    };
+    }
+}
 
-    (fingerprint, all_fingerprints)
+/// Hash of a node from its label, the hashes of its children and its value
+fn hash_node(label: &str, value: &str, child_hashes: &[u64]) -> u64 {
+    let mut hash = xxh3_64(label.as_bytes());
+    for child_hash in child_hashes {
+        hash = combine_hash(hash, *child_hash);
+    }
+    if !value.is_empty() {
+        hash = xxh3_64_with_seed(value.as_bytes(), hash);
+    }
+    hash
+}
+
+fn combine_hash(hash: u64, next: u64) -> u64 {
+    xxh3_64_with_seed(&next.to_le_bytes(), hash)
 }
 
 /// Create sliding windows of subtrees
@@ -243,18 +269,18 @@ pub fn create_sliding_windows(
     for i in 0..all_subtrees.len() {
         let mut current_weight = 0;
         let mut window_hashes = Vec::new();
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        let mut window_hash = 0;
 
         for j in i..all_subtrees.len() {
             current_weight += all_subtrees[j].weight;
             window_hashes.push(all_subtrees[j].hash);
-            all_subtrees[j].hash.hash(&mut hasher);
+            window_hash = combine_hash(window_hash, all_subtrees[j].hash);
 
             if current_weight >= window_size {
                 // Create a synthetic fingerprint for this window
                 let window_fp = SubtreeFingerprint {
                     weight: current_weight,
-                    hash: hasher.finish(),
+                    hash: window_hash,
                     child_hashes: window_hashes.clone(),
                     start_line: all_subtrees[i].start_line,
                     end_line: all_subtrees[j].end_line,
@@ -454,6 +480,37 @@ mod tests {
         assert!(!fp1.might_be_similar(&fp3, 0.2));
     }
 
+    #[test]
+    fn test_generate_subtree_fingerprints() {
+        let statement = |id: usize, value: &str| {
+            let mut node = TreeNode::new("ExpressionStatement".to_string(), String::new(), id);
+            node.add_child(Rc::new(TreeNode::new(
+                "Identifier".to_string(),
+                value.to_string(),
+                id + 1,
+            )));
+            Rc::new(node)
+        };
+        let mut root = TreeNode::new("Block".to_string(), String::new(), 0);
+        root.add_child(statement(1, "a"));
+        root.add_child(statement(3, "a"));
+        root.add_child(statement(5, "b"));
+
+        let (root_fp, subtrees) = generate_subtree_fingerprints(&Rc::new(root), 0, 0);
+
+        assert_eq!(root_fp.weight, 7);
+        assert_eq!(
+            root_fp.child_hashes,
+            subtrees.iter().filter(|fp| fp.depth == 1).map(|fp| fp.hash).collect::<Vec<_>>()
+        );
+        // Subtrees are listed in pre-order, each statement before its identifier
+        let types: Vec<&str> = subtrees.iter().map(|fp| fp.node_type.as_str()).collect();
+        assert_eq!(types, ["ExpressionStatement", "Identifier"].repeat(3));
+        // Equal subtrees hash equally, and values are part of the hash
+        assert_eq!(subtrees[0].hash, subtrees[2].hash);
+        assert_ne!(subtrees[0].hash, subtrees[4].hash);
+    }
+
     #[test]
     fn test_indexed_function() {
         let root_fp = SubtreeFingerprint {
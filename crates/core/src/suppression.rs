@@ -0,0 +1,157 @@
+//! Allow rules for known, acceptable duplication.
+//!
+//! Intentional forks and vendored helpers are suppressed by rules in
+//! `similarity.toml`, so the decision is reviewed with the code instead of
+//! living in a local baseline:
+//!
+//! ```toml
+//! allow_symbols = ["deepClone", "escapeHtml"]
+//!
+//! [[allow]]
+//! from = "src/legacy/**"
+//! to = "src/v2/**"
+//! ```
+//!
+//! A pair is suppressed when one side matches `from` and the other `to`, in
+//! either order, or when either side is named in `allow_symbols`. Path
+//! patterns use gitignore syntax, relative to the directory of
+//! `similarity.toml`. `allow_symbols` must come before the first `[[allow]]`
+//! table, or TOML reads it as part of that table.
+
+use crate::config_loader::ConfigLoader;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Pair of path patterns whose duplication is allowed
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AllowRule {
+    pub from: String,
+    pub to: String,
+}
+
+/// `allow_symbols` and `[[allow]]` tables of `similarity.toml`
+#[derive(Debug, Default, Deserialize)]
+pub struct SuppressionConfig {
+    #[serde(default)]
+    pub allow: Vec<AllowRule>,
+    #[serde(default)]
+    pub allow_symbols: Vec<String>,
+}
+
+impl ConfigLoader for SuppressionConfig {}
+
+#[derive(Debug)]
+struct PathRule {
+    from: Gitignore,
+    to: Gitignore,
+}
+
+/// Compiled allow rules of a run
+#[derive(Debug, Default)]
+pub struct Suppressions {
+    root: PathBuf,
+    rules: Vec<PathRule>,
+    symbols: HashSet<String>,
+}
+
+impl Suppressions {
+    /// Compile rules whose path patterns are relative to `root`
+    pub fn new(root: &Path, config: SuppressionConfig) -> anyhow::Result<Self> {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let compile = |pattern: &str| -> anyhow::Result<Gitignore> {
+            let mut builder = GitignoreBuilder::new(&root);
+            builder.add_line(None, pattern).map_err(|error| {
+                anyhow::anyhow!("Invalid allow pattern '{}': {}", pattern, error)
+            })?;
+            Ok(builder.build()?)
+        };
+        let rules = config
+            .allow
+            .iter()
+            .map(|rule| Ok(PathRule { from: compile(&rule.from)?, to: compile(&rule.to)? }))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { root, rules, symbols: config.allow_symbols.into_iter().collect() })
+    }
+
+    /// Rules of the closest `similarity.toml`; none when there is no config or it is invalid
+    #[must_use]
+    pub fn discover() -> Self {
+        let Some(path) = SuppressionConfig::find_config_file() else {
+            return Self::default();
+        };
+        let root = path.parent().unwrap_or(Path::new("."));
+        SuppressionConfig::load_from_file(path.clone())
+            .and_then(|config| Self::new(root, config))
+            .unwrap_or_else(|error| {
+                eprintln!("Warning: could not load allow rules from {}: {error}", path.display());
+                Self::default()
+            })
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.symbols.is_empty()
+    }
+
+    /// Whether duplication between two `(file, symbol)` sides is allowed
+    #[must_use]
+    pub fn allows(&self, first: (&Path, &str), second: (&Path, &str)) -> bool {
+        if self.symbols.contains(first.1) || self.symbols.contains(second.1) {
+            return true;
+        }
+        if self.rules.is_empty() {
+            return false;
+        }
+
+        let first = self.relative(first.0);
+        let second = self.relative(second.0);
+        let (Some(first), Some(second)) = (first, second) else {
+            return false;
+        };
+        let matches = |matcher: &Gitignore, path: &Path| {
+            matcher.matched_path_or_any_parents(path, false).is_ignore()
+        };
+        self.rules.iter().any(|rule| {
+            (matches(&rule.from, &first) && matches(&rule.to, &second))
+                || (matches(&rule.from, &second) && matches(&rule.to, &first))
+        })
+    }
+
+    fn relative(&self, path: &Path) -> Option<PathBuf> {
+        let absolute = path.canonicalize().unwrap_or_else(|_| self.root.join(path));
+        absolute.strip_prefix(&self.root).ok().map(Path::to_path_buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_pairs_and_symbols() {
+        let config: SuppressionConfig = toml::from_str(
+            r#"
+            allow_symbols = ["deepClone"]
+
+            [[allow]]
+            from = "src/legacy/**"
+            to = "src/v2/**"
+            "#,
+        )
+        .unwrap();
+        let suppressions = Suppressions::new(Path::new("/repo"), config).unwrap();
+        let legacy = Path::new("src/legacy/cart.ts");
+        let v2 = Path::new("src/v2/cart.ts");
+        let other = Path::new("src/shop/cart.ts");
+
+        assert!(!suppressions.is_empty());
+        assert!(suppressions.allows((legacy, "total"), (v2, "total")));
+        assert!(suppressions.allows((v2, "total"), (legacy, "total")));
+        assert!(!suppressions.allows((legacy, "total"), (other, "total")));
+        assert!(!suppressions.allows((v2, "total"), (v2, "sum")));
+        assert!(suppressions.allows((other, "deepClone"), (v2, "clone")));
+        assert!(Suppressions::default().is_empty());
+    }
+}
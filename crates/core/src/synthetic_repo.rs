@@ -0,0 +1,220 @@
+//! Deterministic synthetic TypeScript repositories with a controlled share of
+//! cloned functions, used by the benchmark suite and for runtime estimates.
+
+/// Shape of a synthetic repository
+#[derive(Debug, Clone)]
+pub struct SyntheticRepoOptions {
+    /// Total number of functions
+    pub functions: usize,
+    /// Share of the functions (0.0-1.0) that are renamed copies of another one
+    pub clone_ratio: f64,
+    /// Functions written to each file
+    pub functions_per_file: usize,
+    /// Seed of the generator; the same options always give the same repository
+    pub seed: u64,
+}
+
+impl Default for SyntheticRepoOptions {
+    fn default() -> Self {
+        SyntheticRepoOptions { functions: 1000, clone_ratio: 0.1, functions_per_file: 20, seed: 42 }
+    }
+}
+
+/// A generated repository
+#[derive(Debug, Clone)]
+pub struct SyntheticRepo {
+    /// `(path, source)` pairs, as taken by the analysis functions
+    pub files: Vec<(String, String)>,
+    /// Number of functions that are clones of an earlier one
+    pub clones: usize,
+}
+
+/// Statement templates; `{a}`, `{b}` and `{c}` are replaced by identifiers
+/// that differ between a function and its clones
+const STATEMENTS: &[&str] = &[
+    "const {a} = {b}.filter((item) => item.active);",
+    "let {c} = 0;",
+    "for (const item of {b}) {\n    {c} += item.value;\n  }",
+    "if ({a}.length === 0) {\n    return [];\n  }",
+    "const {c} = {a}.map((entry) => ({ id: entry.id, total: entry.value * 2 }));",
+    "while ({c} < {b}.length) {\n    {c}++;\n  }",
+    "const {a} = Object.keys({b}).sort();",
+    "try {\n    JSON.parse({a});\n  } catch (error) {\n    console.error(error);\n  }",
+    "switch ({a}.kind) {\n    case \"start\":\n      {c} = 1;\n      break;\n    default:\n      {c} = -1;\n  }",
+    "const {c} = await fetch(`/api/${{a}}`);",
+    "for (let index = 0; index < {b}.length; index += 2) {\n    console.log({b}[index]);\n  }",
+    "const {a} = {b}.reduce((sum, value) => sum + value, {c});",
+];
+
+/// Identifier stems combined into the names of generated functions and variables
+const STEMS: &[&str] = &[
+    "order", "user", "item", "cart", "price", "token", "entry", "record", "event", "node",
+    "report", "batch", "queue", "stock", "score", "label",
+];
+
+/// splitmix64, so generation needs no dependency and is stable across platforms
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Statement template indices of one original function
+type Shape = Vec<usize>;
+
+fn random_shape(rng: &mut Rng) -> Shape {
+    (0..4 + rng.below(6)).map(|_| rng.below(STATEMENTS.len())).collect()
+}
+
+fn render_function(name: &str, shape: &Shape, rng: &mut Rng) -> String {
+    let mut pick = || format!("{}{}", STEMS[rng.below(STEMS.len())], rng.below(100));
+    let (a, b, c) = (pick(), pick(), pick());
+    let mut body = String::new();
+    for &statement in shape {
+        body.push_str("  ");
+        body.push_str(
+            &STATEMENTS[statement].replace("{a}", &a).replace("{b}", &b).replace("{c}", &c),
+        );
+        body.push('\n');
+    }
+    format!("export async function {name}({a}: any, {b}: any[]) {{\n  let {c}: any;\n{body}  return {c};\n}}\n")
+}
+
+/// Generate a repository of `options.functions` functions, of which
+/// `clone_ratio` are copies of an earlier function with renamed identifiers
+pub fn generate_synthetic_repo(options: &SyntheticRepoOptions) -> SyntheticRepo {
+    let mut rng = Rng(options.seed);
+    let clone_target = ((options.functions as f64 * options.clone_ratio.clamp(0.0, 1.0)) as usize)
+        .min(options.functions.saturating_sub(1));
+    let per_file = options.functions_per_file.max(1);
+
+    // Clones are spread over the repository; the first function is always an original
+    let mut positions: Vec<usize> = (1..options.functions).collect();
+    for index in 0..clone_target.min(positions.len()) {
+        let swap = index + rng.below(positions.len() - index);
+        positions.swap(index, swap);
+    }
+    let mut is_clone = vec![false; options.functions];
+    for &position in positions.iter().take(clone_target) {
+        is_clone[position] = true;
+    }
+
+    let mut shapes: Vec<Shape> = Vec::new();
+    let mut functions = Vec::with_capacity(options.functions);
+    for (index, &clone) in is_clone.iter().enumerate() {
+        let shape = if clone {
+            shapes[rng.below(shapes.len())].clone()
+        } else {
+            let shape = random_shape(&mut rng);
+            shapes.push(shape.clone());
+            shape
+        };
+        functions.push(render_function(&format!("generated{index}"), &shape, &mut rng));
+    }
+
+    let files = functions
+        .chunks(per_file)
+        .enumerate()
+        .map(|(index, chunk)| {
+            (format!("src/module{}/file{index}.ts", index % 10), chunk.join("\n"))
+        })
+        .collect();
+    SyntheticRepo { files, clones: options.functions - shapes.len() }
+}
+
+/// Reference timing of one benchmark configuration
+#[derive(Debug, Clone, Copy)]
+pub struct ReferenceTiming {
+    pub functions: usize,
+    pub clone_ratio: f64,
+    /// Analysis configuration, as named in the benchmark suite
+    pub algorithm: &'static str,
+    pub millis: f64,
+}
+
+/// End-to-end timings of `cargo bench --bench synthetic_repo_benchmark`
+/// (single core, Linux), to estimate runtime for a repository size
+pub const REFERENCE_TIMINGS: &[ReferenceTiming] = &[
+    ReferenceTiming { functions: 100, clone_ratio: 0.1, algorithm: "tsed", millis: 607.0 },
+    ReferenceTiming {
+        functions: 100,
+        clone_ratio: 0.1,
+        algorithm: "tsed-no-penalty",
+        millis: 562.9,
+    },
+    ReferenceTiming { functions: 100, clone_ratio: 0.1, algorithm: "fingerprint", millis: 188.4 },
+    ReferenceTiming { functions: 100, clone_ratio: 0.3, algorithm: "tsed", millis: 506.2 },
+    ReferenceTiming {
+        functions: 100,
+        clone_ratio: 0.3,
+        algorithm: "tsed-no-penalty",
+        millis: 497.5,
+    },
+    ReferenceTiming { functions: 100, clone_ratio: 0.3, algorithm: "fingerprint", millis: 195.9 },
+    ReferenceTiming { functions: 400, clone_ratio: 0.1, algorithm: "tsed", millis: 10125.0 },
+    ReferenceTiming {
+        functions: 400,
+        clone_ratio: 0.1,
+        algorithm: "tsed-no-penalty",
+        millis: 8890.2,
+    },
+    ReferenceTiming { functions: 400, clone_ratio: 0.1, algorithm: "fingerprint", millis: 3953.6 },
+    ReferenceTiming { functions: 400, clone_ratio: 0.3, algorithm: "tsed", millis: 10613.0 },
+    ReferenceTiming {
+        functions: 400,
+        clone_ratio: 0.3,
+        algorithm: "tsed-no-penalty",
+        millis: 10587.0,
+    },
+    ReferenceTiming { functions: 400, clone_ratio: 0.3, algorithm: "fingerprint", millis: 3310.7 },
+];
+
+/// Table of [`REFERENCE_TIMINGS`], printed by `--version --verbose`
+pub fn format_reference_timings() -> String {
+    let mut output =
+        String::from("Reference timings (synthetic repos, single core):\n  functions  clones  algorithm        time\n");
+    for timing in REFERENCE_TIMINGS {
+        output.push_str(&format!(
+            "  {:>9}  {:>5.0}%  {:<15}  {:>8.1} ms\n",
+            timing.functions,
+            timing.clone_ratio * 100.0,
+            timing.algorithm,
+            timing.millis
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract_functions;
+
+    #[test]
+    fn test_generation_is_deterministic_with_requested_clones() {
+        let options =
+            SyntheticRepoOptions { functions: 200, clone_ratio: 0.25, ..Default::default() };
+        let repo = generate_synthetic_repo(&options);
+
+        assert_eq!(repo.clones, 50);
+        assert_eq!(repo.files.len(), 10);
+        assert_eq!(repo.files, generate_synthetic_repo(&options).files);
+
+        let extracted: usize = repo
+            .files
+            .iter()
+            .map(|(path, source)| extract_functions(path, source).unwrap().len())
+            .sum();
+        assert_eq!(extracted, 200);
+    }
+}
@@ -0,0 +1,370 @@
+//! Duplicate detection for test code.
+//!
+//! The callbacks of test framework calls (`describe`, `it`, `test` and setup
+//! hooks such as `beforeEach`) are units of their own: they are compared with
+//! units of the same kind only, and never take part in the function analysis
+//! of production code. Units are named by their suite path, e.g.
+//! `math > adds numbers`.
+
+use crate::cli_output::format_function_output;
+use crate::parser::parse_function_body;
+use crate::tolerant_parse::parse_tolerant;
+use crate::tree::TreeNode;
+use crate::tsed::{calculate_tsed, TSEDOptions};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{Argument, CallExpression, Expression, Statement};
+use oxc_span::{SourceType, Span};
+use std::fmt;
+use std::rc::Rc;
+
+const SUITE_FUNCTIONS: [&str; 5] = ["describe", "context", "suite", "fdescribe", "xdescribe"];
+const TEST_FUNCTIONS: [&str; 5] = ["it", "test", "specify", "fit", "xit"];
+const HOOK_FUNCTIONS: [&str; 6] =
+    ["beforeEach", "afterEach", "beforeAll", "afterAll", "before", "after"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestUnitKind {
+    /// `describe` and its aliases
+    Suite,
+    /// `it`, `test` and their aliases
+    Test,
+    /// Setup and teardown hooks such as `beforeEach`
+    Hook,
+}
+
+impl fmt::Display for TestUnitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TestUnitKind::Suite => "suite",
+            TestUnitKind::Test => "test",
+            TestUnitKind::Hook => "hook",
+        })
+    }
+}
+
+/// The kind of a test framework call, including modifiers and tables:
+/// `it.only(...)`, `test.concurrent(...)`, `describe.each(table)(...)`
+pub(crate) fn test_call_kind(call: &CallExpression) -> Option<TestUnitKind> {
+    let mut callee = call.callee.get_inner_expression();
+    let name = loop {
+        match callee {
+            Expression::Identifier(ident) => break ident.name.as_str(),
+            Expression::StaticMemberExpression(member) => {
+                callee = member.object.get_inner_expression();
+            }
+            Expression::CallExpression(inner) => callee = inner.callee.get_inner_expression(),
+            _ => return None,
+        }
+    };
+    if SUITE_FUNCTIONS.contains(&name) {
+        Some(TestUnitKind::Suite)
+    } else if TEST_FUNCTIONS.contains(&name) {
+        Some(TestUnitKind::Test)
+    } else if HOOK_FUNCTIONS.contains(&name) {
+        Some(TestUnitKind::Hook)
+    } else {
+        None
+    }
+}
+
+/// A suite, test or hook callback
+#[derive(Debug, Clone)]
+pub struct TestUnit {
+    pub kind: TestUnitKind,
+    /// Suite path and title, e.g. `math > adds numbers`; hooks use the hook name as title
+    pub name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub source: String,
+    pub tree: Rc<TreeNode>,
+}
+
+impl TestUnit {
+    #[must_use]
+    pub fn line_count(&self) -> u32 {
+        self.end_line - self.start_line + 1
+    }
+
+    /// Whether `other` is nested in this unit
+    fn contains(&self, other: &TestUnit) -> bool {
+        self.file_path == other.file_path
+            && self.start_line <= other.start_line
+            && other.end_line <= self.end_line
+            && (self.start_line, self.end_line) != (other.start_line, other.end_line)
+    }
+}
+
+/// Extract the suites, tests and hooks of a TypeScript/JavaScript file
+///
+/// # Errors
+///
+/// Returns the parse errors when nothing of the file can be parsed
+pub fn extract_test_units(file_path: &str, source_text: &str) -> Result<Vec<TestUnit>, String> {
+    let allocator = Allocator::default();
+    let source_type = SourceType::from_path(file_path).unwrap_or(SourceType::tsx());
+    let parsed = parse_tolerant(&allocator, source_text, source_type)?;
+
+    let mut units = Vec::new();
+    collect_units(&parsed.program.body, "", file_path, source_text, &mut units);
+    Ok(units)
+}
+
+fn collect_units(
+    statements: &[Statement],
+    suite_path: &str,
+    file_path: &str,
+    source_text: &str,
+    units: &mut Vec<TestUnit>,
+) {
+    for statement in statements {
+        let Statement::ExpressionStatement(statement) = statement else {
+            continue;
+        };
+        let call = match statement.expression.get_inner_expression() {
+            Expression::CallExpression(call) => call,
+            Expression::AwaitExpression(await_expr) => match &await_expr.argument {
+                Expression::CallExpression(call) => call,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        let Some(kind) = test_call_kind(call) else {
+            continue;
+        };
+
+        // The callback is the last function argument: `it(title, fn)`, `it(title, options, fn)`
+        let callback = call.arguments.iter().rev().find_map(|argument| {
+            match argument.as_expression().map(Expression::get_inner_expression) {
+                Some(Expression::ArrowFunctionExpression(arrow)) => {
+                    Some((arrow.span, &*arrow.body, arrow.expression))
+                }
+                Some(Expression::FunctionExpression(func)) => {
+                    func.body.as_deref().map(|body| (func.span, body, false))
+                }
+                _ => None,
+            }
+        });
+        let Some((span, body, expression)) = callback else {
+            continue;
+        };
+
+        let title = title(call, source_text).unwrap_or_else(|| callee_text(call, source_text));
+        let name = if suite_path.is_empty() { title } else { format!("{suite_path} > {title}") };
+        let Ok(tree) = parse_function_body(file_path, slice(source_text, body.span), expression)
+        else {
+            continue;
+        };
+        units.push(TestUnit {
+            kind,
+            name: name.clone(),
+            file_path: file_path.to_string(),
+            start_line: line_number(source_text, call.span.start),
+            end_line: line_number(source_text, call.span.end),
+            source: slice(source_text, span).to_string(),
+            tree,
+        });
+
+        if kind == TestUnitKind::Suite && !expression {
+            collect_units(&body.statements, &name, file_path, source_text, units);
+        }
+    }
+}
+
+/// Title of a test: its leading string or template literal argument
+fn title(call: &CallExpression, source_text: &str) -> Option<String> {
+    match call.arguments.first().and_then(Argument::as_expression)? {
+        Expression::StringLiteral(literal) => Some(literal.value.to_string()),
+        Expression::TemplateLiteral(literal) => {
+            Some(slice(source_text, literal.span).trim_matches('`').to_string())
+        }
+        _ => None,
+    }
+}
+
+fn callee_text(call: &CallExpression, source_text: &str) -> String {
+    use oxc_span::GetSpan;
+    slice(source_text, call.callee.span()).to_string()
+}
+
+fn slice(text: &str, span: Span) -> &str {
+    text.get(span.start as usize..span.end as usize).unwrap_or_default()
+}
+
+fn line_number(source_text: &str, offset: u32) -> u32 {
+    source_text[..(offset as usize).min(source_text.len())].matches('\n').count() as u32 + 1
+}
+
+/// A pair of similar test units
+#[derive(Debug, Clone)]
+pub struct TestDuplicate<'a> {
+    pub first: &'a TestUnit,
+    pub second: &'a TestUnit,
+    pub similarity: f64,
+}
+
+/// Find pairs of units of the same kind at least `threshold` similar, most
+/// similar first. Units nested in a pair of similar suites are reported with
+/// those suites only. Test bodies are short, so the size penalty of
+/// `options` is not applied.
+#[must_use]
+pub fn find_similar_tests<'a>(
+    units: &'a [TestUnit],
+    threshold: f64,
+    options: &TSEDOptions,
+) -> Vec<TestDuplicate<'a>> {
+    let options = TSEDOptions { size_penalty: false, ..options.clone() };
+    let candidates: Vec<&TestUnit> = units
+        .iter()
+        .filter(|unit| match options.min_tokens {
+            Some(min_tokens) => unit.tree.get_subtree_size() as u32 >= min_tokens,
+            None => unit.line_count() >= options.min_lines,
+        })
+        .collect();
+
+    let mut duplicates = Vec::new();
+    for (i, first) in candidates.iter().enumerate() {
+        for second in &candidates[i + 1..] {
+            if first.kind != second.kind || first.contains(second) || second.contains(first) {
+                continue;
+            }
+            let similarity = calculate_tsed(&first.tree, &second.tree, &options);
+            if similarity >= threshold {
+                duplicates.push(TestDuplicate { first, second, similarity });
+            }
+        }
+    }
+
+    let suites: Vec<(&TestUnit, &TestUnit)> = duplicates
+        .iter()
+        .filter(|duplicate| duplicate.first.kind == TestUnitKind::Suite)
+        .map(|duplicate| (duplicate.first, duplicate.second))
+        .collect();
+    duplicates.retain(|duplicate| {
+        !suites.iter().any(|(suite1, suite2)| {
+            (suite1.contains(duplicate.first) && suite2.contains(duplicate.second))
+                || (suite1.contains(duplicate.second) && suite2.contains(duplicate.first))
+        })
+    });
+
+    duplicates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    duplicates
+}
+
+fn unit_label(unit: &TestUnit) -> String {
+    format_function_output(
+        &unit.file_path,
+        &format!("{}: {}", unit.kind, unit.name),
+        unit.start_line,
+        unit.end_line,
+    )
+}
+
+/// Print similar test units; returns the number of pairs
+pub fn report_similar_tests(duplicates: &[TestDuplicate], print: bool) -> usize {
+    if duplicates.is_empty() {
+        println!("\nNo duplicate tests found!");
+        return 0;
+    }
+
+    println!("\nDuplicate tests:");
+    println!("{}", "-".repeat(60));
+    for duplicate in duplicates {
+        println!("\nSimilarity: {:.2}%", duplicate.similarity * 100.0);
+        println!("  {}", unit_label(duplicate.first));
+        println!("  {}", unit_label(duplicate.second));
+
+        if print {
+            for unit in [duplicate.first, duplicate.second] {
+                println!(
+                    "\n\x1b[36m--- {}:{} ---\x1b[0m\n{}",
+                    unit.file_path, unit.name, unit.source
+                );
+            }
+        }
+    }
+    println!("\nTotal duplicate test pairs found: {}", duplicates.len());
+
+    duplicates.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"
+import { describe, it, expect } from "vitest";
+
+describe("cart", () => {
+    beforeEach(() => {
+        cart.clear();
+    });
+
+    it("adds items", () => {
+        const cart = new Cart();
+        cart.add({ id: 1, price: 10 });
+        cart.add({ id: 2, price: 5 });
+        expect(cart.total()).toBe(15);
+    });
+
+    it.each([[1], [2]])(`removes item %i`, async (id) => {
+        const cart = await loadCart(id);
+        for (const item of cart.items) {
+            if (item.id === id) {
+                await cart.remove(item);
+            }
+        }
+        expect(cart.items).toHaveLength(0);
+    });
+});
+
+test("sums prices", function () {
+    const order = new Order();
+    order.add({ id: 1, price: 10 });
+    order.add({ id: 2, price: 5 });
+    expect(order.total()).toBe(15);
+});
+"#;
+
+    #[test]
+    fn test_extract_test_units() {
+        let units = extract_test_units("cart.test.ts", SPEC).unwrap();
+        let names: Vec<(TestUnitKind, &str)> =
+            units.iter().map(|unit| (unit.kind, unit.name.as_str())).collect();
+        assert_eq!(
+            names,
+            [
+                (TestUnitKind::Suite, "cart"),
+                (TestUnitKind::Hook, "cart > beforeEach"),
+                (TestUnitKind::Test, "cart > adds items"),
+                (TestUnitKind::Test, "cart > removes item %i"),
+                (TestUnitKind::Test, "sums prices"),
+            ]
+        );
+        assert_eq!((units[2].start_line, units[2].end_line), (9, 14));
+    }
+
+    #[test]
+    fn test_find_similar_tests() {
+        let units = extract_test_units("cart.test.ts", SPEC).unwrap();
+        let options = TSEDOptions { min_lines: 3, ..Default::default() };
+
+        let duplicates = find_similar_tests(&units, 0.9, &options);
+        let pairs: Vec<(&str, &str)> = duplicates
+            .iter()
+            .map(|duplicate| (duplicate.first.name.as_str(), duplicate.second.name.as_str()))
+            .collect();
+        assert_eq!(pairs, [("cart > adds items", "sums prices")]);
+
+        // Tests of duplicated suites are reported with the suites
+        let copy = SPEC.replace("cart", "basket");
+        let mut units = units;
+        units.extend(extract_test_units("basket.test.ts", &copy).unwrap());
+        let duplicates = find_similar_tests(&units, 0.9, &options);
+        assert!(duplicates.iter().any(|duplicate| duplicate.first.kind == TestUnitKind::Suite));
+        assert!(!duplicates.iter().any(|duplicate| {
+            duplicate.first.name == "cart > adds items"
+                && duplicate.second.name == "basket > adds items"
+        }));
+    }
+}
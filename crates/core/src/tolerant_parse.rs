@@ -0,0 +1,140 @@
+//! Parsing that survives localized syntax errors.
+//!
+//! oxc recovers from some syntax errors by itself but aborts on most, leaving
+//! an empty program. When it aborts, the top-level chunk around the error is
+//! blanked out (replaced by spaces, keeping newlines so offsets and line
+//! numbers stay valid) and the file is parsed again, so functions and types
+//! elsewhere in the file can still be extracted. Top-level chunks are found by
+//! layout: they start at lines beginning in the first column.
+
+use crate::parser::parse_errors_message;
+use oxc_allocator::Allocator;
+use oxc_ast::ast::Program;
+use oxc_diagnostics::OxcDiagnostic;
+use oxc_parser::Parser;
+use oxc_span::{SourceType, Span};
+
+/// Chunks blanked out before giving up on a file
+const MAX_BLANKED_CHUNKS: usize = 8;
+
+pub(crate) struct TolerantParse<'a> {
+    pub program: Program<'a>,
+    /// `Parse errors: ...` message of the original source, `None` when it parsed cleanly
+    pub errors: Option<String>,
+    /// Byte ranges that were blanked out or hold a recovered error
+    damaged: Vec<(u32, u32)>,
+}
+
+impl TolerantParse<'_> {
+    /// Whether code in `span` may be missing or misparsed
+    pub fn is_damaged(&self, span: Span) -> bool {
+        self.damaged.iter().any(|&(start, end)| span.start < end.max(start + 1) && start < span.end)
+    }
+}
+
+/// Parse `source_text`, blanking out top-level chunks the parser cannot recover from.
+///
+/// Fails with the original parse errors when nothing but blank code is left.
+pub(crate) fn parse_tolerant<'a>(
+    allocator: &'a Allocator,
+    source_text: &'a str,
+    source_type: SourceType,
+) -> Result<TolerantParse<'a>, String> {
+    let mut ret = Parser::new(allocator, source_text, source_type).parse();
+    if ret.errors.is_empty() {
+        return Ok(TolerantParse { program: ret.program, errors: None, damaged: Vec::new() });
+    }
+
+    let message = parse_errors_message(&ret.errors, source_text);
+    let mut damaged = Vec::new();
+    let mut text = source_text.to_string();
+    for _ in 0..MAX_BLANKED_CHUNKS {
+        if !ret.panicked {
+            damaged.extend(error_ranges(&ret.errors));
+            return Ok(TolerantParse { program: ret.program, errors: Some(message), damaged });
+        }
+        let Some((offset, _)) = error_ranges(&ret.errors).next() else {
+            break;
+        };
+        let (start, end) = top_level_chunk(&text, offset as usize);
+        if text[start..end].trim().is_empty() {
+            break;
+        }
+        blank(&mut text, start, end);
+        damaged.push((start as u32, end as u32));
+        if text.trim().is_empty() {
+            break;
+        }
+        ret = Parser::new(allocator, allocator.alloc_str(&text), source_type).parse();
+    }
+    Err(message)
+}
+
+fn error_ranges(errors: &[OxcDiagnostic]) -> impl Iterator<Item = (u32, u32)> + '_ {
+    errors.iter().flat_map(|error| error.labels.iter().flatten()).map(|label| {
+        let start = label.offset() as u32;
+        (start, start + label.len() as u32)
+    })
+}
+
+/// Byte range of the top-level chunk holding `offset`. An error at the very
+/// start of a chunk is blamed on the chunk before, which was left unterminated.
+fn top_level_chunk(text: &str, offset: usize) -> (usize, usize) {
+    let mut starts: Vec<usize> = vec![0];
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let starts_chunk = line
+            .chars()
+            .next()
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, '}' | ')' | ']'));
+        if starts_chunk && line_start > 0 {
+            starts.push(line_start);
+        }
+        line_start += line.len();
+    }
+
+    let offset = offset.min(text.len());
+    let mut index = starts.partition_point(|&start| start <= offset) - 1;
+    if index > 0 && text[starts[index]..offset].trim().is_empty() {
+        index -= 1;
+    }
+    (starts[index], starts.get(index + 1).copied().unwrap_or(text.len()))
+}
+
+/// Replace everything but line breaks in `start..end` with spaces
+fn blank(text: &mut String, start: usize, end: usize) {
+    let blanked: String =
+        text[start..end].chars().map(|c| if c == '\n' || c == '\r' { c } else { ' ' }).collect();
+    text.replace_range(start..end, &blanked);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<(usize, Option<String>), String> {
+        let allocator = Allocator::default();
+        let parsed = parse_tolerant(&allocator, source, SourceType::tsx())?;
+        Ok((parsed.program.body.len(), parsed.errors))
+    }
+
+    #[test]
+    fn test_blanks_chunk_around_error() {
+        let source = "function broken( {\n  return 1;\n}\n\nfunction fine() {\n  return 2;\n}\n";
+        let (statements, errors) = parse(source).unwrap();
+        assert_eq!(statements, 1);
+        assert!(errors.unwrap().starts_with("Parse errors: "));
+
+        // An unterminated function is blamed for the error at the next chunk
+        let source = "function open() {\n  if (x) {\n    return 1;\n}\nconst y = {;\n";
+        assert!(parse(source).is_err());
+        let source = "function open() {\n  return [1;\n}\nfunction fine() {\n  return 2;\n}\n";
+        assert_eq!(parse(source).unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_clean_and_hopeless_sources() {
+        assert_eq!(parse("const a = 1;\n").unwrap(), (1, None));
+        assert!(parse("function ( {\n").is_err());
+    }
+}
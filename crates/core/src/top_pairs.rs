@@ -0,0 +1,133 @@
+//! Bounded collection of the most similar pairs.
+//!
+//! Ranking mode reports the N best pairs regardless of threshold. Instead of
+//! materializing every compared pair, the comparison loops offer each result
+//! to a `TopPairs`, a min-heap that holds at most N entries and evicts its
+//! weakest entry when a better one arrives. Per-thread collectors are merged,
+//! so the loops can run with rayon `fold`/`reduce`.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+struct Ranked<T> {
+    score: f64,
+    /// Insertion order, so equal scores keep the earlier pair
+    sequence: usize,
+    item: T,
+}
+
+impl<T> PartialEq for Ranked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Ranked<T> {}
+
+impl<T> PartialOrd for Ranked<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Ranked<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score.total_cmp(&other.score).then(other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// The `capacity` highest scoring items offered so far
+pub struct TopPairs<T> {
+    capacity: usize,
+    heap: BinaryHeap<Reverse<Ranked<T>>>,
+    offered: usize,
+}
+
+impl<T> TopPairs<T> {
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, heap: BinaryHeap::with_capacity(capacity.min(1024)), offered: 0 }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Lowest score still kept, `None` while there is room left
+    #[must_use]
+    pub fn min_score(&self) -> Option<f64> {
+        (self.heap.len() >= self.capacity)
+            .then(|| self.heap.peek().map_or(f64::INFINITY, |e| e.0.score))
+    }
+
+    /// Whether an item scoring `score` would be kept
+    #[must_use]
+    pub fn accepts(&self, score: f64) -> bool {
+        self.capacity > 0 && self.min_score().is_none_or(|min| score > min)
+    }
+
+    /// Offer an item; `make` only runs when the item is kept
+    pub fn offer(&mut self, score: f64, make: impl FnOnce() -> T) {
+        let sequence = self.offered;
+        self.offered += 1;
+        if !self.accepts(score) {
+            return;
+        }
+        if self.heap.len() >= self.capacity {
+            self.heap.pop();
+        }
+        self.heap.push(Reverse(Ranked { score, sequence, item: make() }));
+    }
+
+    /// Keep the best items of both collectors
+    #[must_use]
+    pub fn merge(mut self, other: Self) -> Self {
+        for Reverse(entry) in other.heap {
+            self.offer(entry.score, || entry.item);
+        }
+        self
+    }
+
+    /// Kept items, highest score first
+    #[must_use]
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap.into_sorted_vec().into_iter().map(|Reverse(entry)| entry.item).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_best_items_in_order() {
+        let mut top = TopPairs::new(3);
+        for (score, name) in [(0.5, "a"), (0.9, "b"), (0.1, "c"), (0.7, "d"), (0.9, "e")] {
+            top.offer(score, || name);
+        }
+        assert_eq!(top.min_score(), Some(0.7));
+        assert!(!top.accepts(0.7));
+        assert_eq!(top.into_sorted_vec(), ["b", "e", "d"]);
+    }
+
+    #[test]
+    fn test_merge_and_lazy_items() {
+        let mut first = TopPairs::new(2);
+        first.offer(0.4, || 1);
+        first.offer(0.8, || 2);
+        let mut second = TopPairs::new(1);
+        second.offer(0.6, || 3);
+        second.offer(0.2, || panic!("below the kept scores"));
+
+        let merged = first.merge(second);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged.into_sorted_vec(), [2, 3]);
+        assert!(TopPairs::<u8>::new(0).into_sorted_vec().is_empty());
+    }
+}
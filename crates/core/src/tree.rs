@@ -1,5 +1,11 @@
 use std::rc::Rc;
 
+/// Whether a node label denotes a comment (or a docstring) in any supported language
+#[must_use]
+pub fn is_comment_label(label: &str) -> bool {
+    label.contains("comment") || label.contains("Comment") || label == "docstring"
+}
+
 #[derive(Debug, Clone)]
 pub struct TreeNode {
     pub label: String,
@@ -7,12 +13,14 @@ pub struct TreeNode {
     pub children: Vec<Rc<TreeNode>>,
     pub id: usize,
     pub subtree_size: Option<usize>,
+    /// Cost of inserting or deleting the node, 1 unless node weights apply
+    pub weight: f64,
 }
 
 impl TreeNode {
     #[must_use]
     pub fn new(label: String, value: String, id: usize) -> Self {
-        TreeNode { label, value, children: Vec::new(), id, subtree_size: None }
+        TreeNode { label, value, children: Vec::new(), id, subtree_size: None, weight: 1.0 }
     }
 
     pub fn add_child(&mut self, child: Rc<TreeNode>) {
@@ -30,4 +38,52 @@ impl TreeNode {
         }
         size
     }
+
+    /// Sum of the weights of the node and its descendants; the subtree size
+    /// when no node weights apply
+    #[must_use]
+    pub fn get_subtree_weight(&self) -> f64 {
+        self.weight + self.children.iter().map(|child| child.get_subtree_weight()).sum::<f64>()
+    }
+
+    #[must_use]
+    pub fn is_comment(&self) -> bool {
+        is_comment_label(&self.label)
+    }
+
+    /// Return the tree with comment and docstring nodes removed.
+    /// Subtrees without comments are shared instead of copied.
+    #[must_use]
+    pub fn without_comments(self: &Rc<Self>) -> Rc<Self> {
+        strip_comments(self).map_or_else(|| Rc::clone(self), Rc::new)
+    }
+}
+
+/// Rebuild a node without comment descendants, or `None` if nothing was removed
+fn strip_comments(node: &TreeNode) -> Option<TreeNode> {
+    let mut changed = false;
+    let mut children = Vec::with_capacity(node.children.len());
+
+    for child in &node.children {
+        if child.is_comment() {
+            changed = true;
+            continue;
+        }
+        match strip_comments(child) {
+            Some(stripped) => {
+                changed = true;
+                children.push(Rc::new(stripped));
+            }
+            None => children.push(Rc::clone(child)),
+        }
+    }
+
+    changed.then(|| TreeNode {
+        label: node.label.clone(),
+        value: node.value.clone(),
+        children,
+        id: node.id,
+        subtree_size: None,
+        weight: node.weight,
+    })
 }
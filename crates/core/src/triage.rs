@@ -0,0 +1,313 @@
+//! Triage states for findings, persisted in `.similarity/triage.json`.
+//!
+//! Every finding gets a stable ID derived from the content of the functions it
+//! covers: a 64-bit simhash of their normalized trees, without comments and
+//! with each function's own name masked. The ID survives file renames and
+//! moves, and a small edit only flips a few of its bits, so a finding without
+//! an entry of its own picks up the state of the nearest recorded ID. IDs of
+//! earlier versions, derived from file and function names, still resolve.
+
+use crate::file_similarity::simhash;
+use crate::tree::TreeNode;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Default location of the triage file, relative to the working directory
+pub const DEFAULT_TRIAGE_PATH: &str = ".similarity/triage.json";
+
+/// Decision recorded for a finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TriageState {
+    /// Not triaged yet
+    New,
+    /// Intentional duplication that will not be refactored
+    Wontfix,
+    /// Reviewed and tolerated for now
+    Accepted,
+    /// Confirmed duplication that should be refactored
+    Fixme,
+}
+
+impl TriageState {
+    pub const ALL: [TriageState; 4] =
+        [TriageState::New, TriageState::Wontfix, TriageState::Accepted, TriageState::Fixme];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TriageState::New => "new",
+            TriageState::Wontfix => "wontfix",
+            TriageState::Accepted => "accepted",
+            TriageState::Fixme => "fixme",
+        }
+    }
+}
+
+impl fmt::Display for TriageState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TriageState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TriageState::ALL.into_iter().find(|state| state.as_str() == s).ok_or_else(|| {
+            format!("unknown triage state '{}' (expected new, wontfix, accepted or fixme)", s)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriageEntry {
+    pub state: TriageState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TriageFile {
+    findings: BTreeMap<String, TriageEntry>,
+}
+
+/// Triage states keyed by finding ID
+#[derive(Debug)]
+pub struct TriageStore {
+    path: PathBuf,
+    findings: BTreeMap<String, TriageEntry>,
+}
+
+impl TriageStore {
+    /// Load the triage file; a missing file is an empty store
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let findings = match fs::read_to_string(&path) {
+            Ok(content) => {
+                serde_json::from_str::<TriageFile>(&content)
+                    .with_context(|| format!("Failed to parse {}", path.display()))?
+                    .findings
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        Ok(Self { path, findings })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = TriageFile { findings: self.findings.clone() };
+        fs::write(&self.path, serde_json::to_string_pretty(&file)? + "\n")
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// State of a finding; findings without an entry are `New`
+    #[must_use]
+    pub fn state(&self, id: &str) -> TriageState {
+        self.findings.get(id).map_or(TriageState::New, |entry| entry.state)
+    }
+
+    #[must_use]
+    pub fn entry(&self, id: &str) -> Option<&TriageEntry> {
+        self.findings.get(id)
+    }
+
+    /// Record a state; marking a finding `New` removes its entry
+    pub fn mark(&mut self, id: &str, state: TriageState, note: Option<String>) {
+        if state == TriageState::New {
+            self.findings.remove(id);
+        } else {
+            self.findings.insert(id.to_string(), TriageEntry { state, note });
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &TriageEntry)> {
+        self.findings.iter().map(|(id, entry)| (id.as_str(), entry))
+    }
+
+    /// Entry applying to a finding and the ID it was recorded under: the
+    /// finding's own `id`, its name-based `legacy_id`, or else the nearest
+    /// content ID at most [`MAX_DRIFT_BITS`] bits away
+    #[must_use]
+    pub fn resolve(&self, id: &str, legacy_id: &str) -> Option<(&str, &TriageEntry)> {
+        let exact = |key: &str| self.findings.get_key_value(key);
+        if let Some((key, entry)) = exact(id).or_else(|| exact(legacy_id)) {
+            return Some((key.as_str(), entry));
+        }
+
+        let simhash = parse_content_id(id)?;
+        self.findings
+            .iter()
+            .filter_map(|(key, entry)| {
+                let distance = (parse_content_id(key)? ^ simhash).count_ones();
+                (distance <= MAX_DRIFT_BITS).then_some((distance, key, entry))
+            })
+            .min_by_key(|(distance, _, _)| *distance)
+            .map(|(_, key, entry)| (key.as_str(), entry))
+    }
+
+    /// State applying to a finding, see [`TriageStore::resolve`]
+    #[must_use]
+    pub fn resolve_state(&self, id: &str, legacy_id: &str) -> TriageState {
+        self.resolve(id, legacy_id).map_or(TriageState::New, |(_, entry)| entry.state)
+    }
+}
+
+/// Most bits a drifted finding's content ID may differ from a recorded one
+/// and still take over its triage state
+pub const MAX_DRIFT_BITS: u32 = 4;
+
+/// Content ID of a finding covering functions with the given trees and names,
+/// independent of their order and of the files they are in
+#[must_use]
+pub fn content_finding_id<'a>(
+    members: impl IntoIterator<Item = (&'a TreeNode, &'a str)>,
+) -> String {
+    let mut members: Vec<Vec<String>> =
+        members.into_iter().map(|(tree, name)| normalized_tokens(tree, name)).collect();
+    members.sort_unstable();
+    let tokens = members.join(&[String::from("|")][..]);
+    format!("{:016x}", simhash(&tokens))
+}
+
+/// Number of bits two content IDs differ in, `None` unless both are content IDs
+#[must_use]
+pub fn content_id_distance(a: &str, b: &str) -> Option<u32> {
+    Some((parse_content_id(a)? ^ parse_content_id(b)?).count_ones())
+}
+
+fn parse_content_id(id: &str) -> Option<u64> {
+    (id.len() == 16).then(|| u64::from_str_radix(id, 16).ok()).flatten()
+}
+
+/// Labels and values of the tree in preorder, subtrees closed by `)`, with
+/// comments dropped and `name` masked so renaming the function keeps the ID
+fn normalized_tokens(tree: &TreeNode, name: &str) -> Vec<String> {
+    fn visit(node: &TreeNode, name: &str, tokens: &mut Vec<String>) {
+        if node.is_comment() {
+            return;
+        }
+        let mask = |text: &str| if text == name { "$name".to_string() } else { text.to_string() };
+        tokens.push(mask(&node.label));
+        if node.value != node.label {
+            tokens.push(mask(&node.value));
+        }
+        if !node.children.is_empty() {
+            for child in &node.children {
+                visit(child, name, tokens);
+            }
+            tokens.push(")".to_string());
+        }
+    }
+
+    let mut tokens = Vec::new();
+    visit(tree, name, &mut tokens);
+    tokens
+}
+
+/// Name-based ID of a finding covering the given `(file, name)` members, as
+/// used before content IDs, independent of their order
+#[must_use]
+pub fn finding_id<'a>(members: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut members: Vec<_> = members.into_iter().collect();
+    members.sort_unstable();
+
+    // FNV-1a keeps IDs stable across builds, unlike `DefaultHasher`
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (file, name) in members {
+        for byte in file.bytes().chain([0]).chain(name.bytes()).chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{:08x}", hash as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn finding_ids_ignore_member_order() {
+        let id = finding_id([("src/a.ts", "sum"), ("src/b.ts", "total")]);
+        assert_eq!(id.len(), 8);
+        assert_eq!(id, finding_id([("src/b.ts", "total"), ("src/a.ts", "sum")]));
+        assert_ne!(id, finding_id([("src/a.ts", "sum"), ("src/c.ts", "total")]));
+    }
+
+    fn content_id(members: &[(&str, &str)]) -> String {
+        let trees: Vec<_> = members
+            .iter()
+            .map(|(source, name)| {
+                (crate::parser::parse_and_convert_to_tree("a.ts", source).unwrap(), *name)
+            })
+            .collect();
+        content_finding_id(trees.iter().map(|(tree, name)| (tree.as_ref(), *name)))
+    }
+
+    const TOTAL: &str = "function total(items) {
+        let sum = 0;
+        for (const item of items) { sum += item.price * item.quantity; }
+        if (sum > 100) { sum = sum * 0.9; }
+        return Math.round(sum * 100) / 100;
+    }";
+
+    #[test]
+    fn content_ids_follow_renames_and_drift() {
+        let id = content_id(&[(TOTAL, "total"), (TOTAL, "total")]);
+        assert_eq!(id.len(), 16);
+
+        // Renamed function, moved comment: same ID
+        let renamed = TOTAL.replace("total", "cartTotal").replace("{\n", "{ // sum up\n");
+        assert_eq!(id, content_id(&[(&renamed, "cartTotal"), (TOTAL, "total")]));
+
+        // Small edit: another ID within reach of the recorded one
+        let edited = TOTAL.replace("return", "log(sum);\n        return");
+        let drifted = content_id(&[(TOTAL, "total"), (&edited, "total")]);
+        assert_ne!(id, drifted);
+
+        let mut store = TriageStore::load("unused.json").unwrap();
+        store.mark(&id, TriageState::Wontfix, None);
+        store.mark("1a2b3c4d", TriageState::Fixme, None);
+        assert_eq!(store.resolve(&drifted, "ffffffff").map(|(key, _)| key), Some(id.as_str()));
+        assert_eq!(store.resolve_state("0000000000000000", "1a2b3c4d"), TriageState::Fixme);
+
+        let unrelated = content_id(&[("function f(a, b) { return a.map(b); }", "f")]);
+        assert_eq!(store.resolve_state(&unrelated, "ffffffff"), TriageState::New);
+    }
+
+    #[test]
+    fn marks_round_trip_through_the_triage_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".similarity/triage.json");
+
+        let mut store = TriageStore::load(&path).unwrap();
+        assert_eq!(store.state("1a2b3c4d"), TriageState::New);
+        store.mark("1a2b3c4d", TriageState::Wontfix, Some("generated code".to_string()));
+        store.mark("5e6f7a8b", TriageState::Fixme, None);
+        store.save().unwrap();
+
+        let mut store = TriageStore::load(&path).unwrap();
+        assert_eq!(store.state("1a2b3c4d"), TriageState::Wontfix);
+        assert_eq!(store.state("5e6f7a8b"), TriageState::Fixme);
+
+        store.mark("5e6f7a8b", TriageState::New, None);
+        assert_eq!(store.entries().count(), 1);
+        assert_eq!("accepted".parse::<TriageState>(), Ok(TriageState::Accepted));
+        assert!("ignored".parse::<TriageState>().is_err());
+    }
+}
@@ -0,0 +1,392 @@
+//! `compilerOptions.paths` and `baseUrl` of tsconfig.json files.
+//!
+//! Suggested imports use the aliases a project already imports through
+//! (`@app/utils/format`) rather than long relative paths. Each file is
+//! governed by the closest tsconfig.json above it; relative `extends` are
+//! followed, while configs extended from packages are not. tsconfig.json is
+//! read as JSON with comments and trailing commas.
+//!
+//! When an alias maps to the target, the shortest such specifier is used.
+//! Without one, a target under `baseUrl` that a relative specifier would
+//! reach through `../` is imported by its path from `baseUrl`.
+
+use crate::refactor_patch::module_specifier;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+const TSCONFIG: &str = "tsconfig.json";
+/// How many `extends` are followed before giving up on a config
+const MAX_EXTENDS_DEPTH: usize = 8;
+const INDEX_SUFFIX: &str = "/index";
+
+/// Alias patterns and their target patterns, in declaration order
+type PathPatterns = Vec<(String, Vec<String>)>;
+
+/// Aliases declared by one tsconfig.json
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AliasConfig {
+    /// Directory of the tsconfig.json, which governs the files below it
+    /// unless a closer one does
+    root: PathBuf,
+    base_url: Option<PathBuf>,
+    /// Directory the `paths` targets are relative to
+    paths_base: PathBuf,
+    paths: PathPatterns,
+}
+
+/// Path aliases of the tsconfig.json files governing a set of files
+#[derive(Debug, Clone, Default)]
+pub struct PathAliases {
+    configs: Vec<AliasConfig>,
+}
+
+impl PathAliases {
+    /// Load the closest tsconfig.json of each file
+    #[must_use]
+    pub fn discover(files: &[PathBuf]) -> Self {
+        let mut by_dir: HashMap<PathBuf, Option<PathBuf>> = HashMap::new();
+        let mut loaded = HashSet::new();
+        let mut configs = Vec::new();
+        for file in files {
+            let Some(dir) = absolute(file).parent().map(Path::to_path_buf) else {
+                continue;
+            };
+            let tsconfig = by_dir.entry(dir).or_insert_with_key(|dir| {
+                dir.ancestors().map(|ancestor| ancestor.join(TSCONFIG)).find(|p| p.is_file())
+            });
+            let Some(tsconfig) = tsconfig.as_ref() else {
+                continue;
+            };
+            if !loaded.insert(tsconfig.clone()) {
+                continue;
+            }
+            match load_compiler_options(tsconfig, 0) {
+                Ok(options) => configs.push(AliasConfig::new(tsconfig, options)),
+                Err(e) => {
+                    tracing::info!(file = %tsconfig.display(), error = %e, "skipping tsconfig");
+                }
+            }
+        }
+        Self { configs }
+    }
+
+    /// Parse the content of a tsconfig.json located in `dir`, without
+    /// following `extends`
+    pub fn parse(dir: &Path, content: &str) -> Result<Self, String> {
+        let dir = absolute(dir);
+        let options = compiler_options(&dir, content)?;
+        Ok(Self { configs: vec![AliasConfig::new(&dir.join(TSCONFIG), options)] })
+    }
+
+    /// Whether no config declares `paths` or `baseUrl`
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.configs.iter().all(|config| config.base_url.is_none() && config.paths.is_empty())
+    }
+
+    /// Specifier importing `target` from `from_file`: an alias of the config
+    /// governing `from_file` when one applies, the relative path otherwise
+    #[must_use]
+    pub fn specifier(&self, from_file: &Path, target: &Path) -> String {
+        let relative = module_specifier(from_file, target);
+        let from = absolute(from_file);
+        let Some(config) = self
+            .configs
+            .iter()
+            .filter(|config| from.starts_with(&config.root))
+            .max_by_key(|config| config.root.components().count())
+        else {
+            return relative;
+        };
+        let stem = absolute(&target.with_extension(""));
+        if let Some(alias) = config.alias(&stem) {
+            return alias;
+        }
+        if relative.starts_with("../") {
+            if let Some(path) = config.base_url.as_ref().and_then(|base| relative_to(&stem, base)) {
+                return path.strip_suffix(INDEX_SUFFIX).unwrap_or(&path).to_string();
+            }
+        }
+        relative
+    }
+}
+
+impl AliasConfig {
+    fn new(tsconfig: &Path, options: CompilerOptions) -> Self {
+        let root = absolute(tsconfig).parent().unwrap_or(Path::new("")).to_path_buf();
+        let (paths, paths_dir) = options.paths.unwrap_or_else(|| (Vec::new(), root.clone()));
+        Self {
+            paths_base: options.base_url.clone().unwrap_or(paths_dir),
+            base_url: options.base_url,
+            paths,
+            root,
+        }
+    }
+
+    /// Shortest alias resolving to `stem`, a target path without extension
+    fn alias(&self, stem: &Path) -> Option<String> {
+        let path = relative_to(stem, &self.paths_base)?;
+        // `src/utils/index` is also reachable as `src/utils`
+        let mut candidates = vec![path.clone()];
+        if let Some(dir) = path.strip_suffix(INDEX_SUFFIX) {
+            candidates.push(dir.to_string());
+        }
+
+        let mut found: Option<String> = None;
+        for (alias, targets) in &self.paths {
+            for target in targets {
+                let target = strip_script_extension(target.trim_start_matches("./"));
+                for candidate in &candidates {
+                    let Some(specifier) = substitute(alias, target, candidate) else {
+                        continue;
+                    };
+                    if found.as_ref().is_none_or(|best| specifier.len() < best.len()) {
+                        found = Some(specifier);
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+/// `alias` with its `*` replaced by what `*` of `target` matches in `path`
+fn substitute(alias: &str, target: &str, path: &str) -> Option<String> {
+    match target.split_once('*') {
+        Some((prefix, suffix)) => {
+            let captured = path.strip_prefix(prefix)?.strip_suffix(suffix)?;
+            if captured.is_empty() || !alias.contains('*') {
+                return None;
+            }
+            Some(alias.replacen('*', captured, 1))
+        }
+        None => (target == path && !alias.contains('*')).then(|| alias.to_string()),
+    }
+}
+
+fn strip_script_extension(target: &str) -> &str {
+    [".d.ts", ".tsx", ".ts", ".jsx", ".js", ".mts", ".cts", ".mjs", ".cjs"]
+        .iter()
+        .find_map(|extension| target.strip_suffix(extension))
+        .unwrap_or(target)
+}
+
+#[derive(Debug, Default)]
+struct CompilerOptions {
+    base_url: Option<PathBuf>,
+    /// Patterns and the directory of the config declaring them
+    paths: Option<(PathPatterns, PathBuf)>,
+}
+
+/// Options of `tsconfig` merged over those of the configs it extends
+fn load_compiler_options(tsconfig: &Path, depth: usize) -> Result<CompilerOptions, String> {
+    if depth > MAX_EXTENDS_DEPTH {
+        return Err("too many nested extends".to_string());
+    }
+    let content = fs::read_to_string(tsconfig).map_err(|e| e.to_string())?;
+    let dir = absolute(tsconfig).parent().unwrap_or(Path::new("")).to_path_buf();
+    let json: serde_json::Value =
+        serde_json::from_str(&strip_jsonc(&content)).map_err(|e| e.to_string())?;
+
+    let extends: Vec<&str> = match json.get("extends") {
+        Some(serde_json::Value::String(parent)) => vec![parent.as_str()],
+        Some(serde_json::Value::Array(parents)) => {
+            parents.iter().filter_map(serde_json::Value::as_str).collect()
+        }
+        _ => Vec::new(),
+    };
+    let mut options = CompilerOptions::default();
+    for parent in extends.into_iter().filter(|parent| parent.starts_with('.')) {
+        let mut path = dir.join(parent);
+        if !path.is_file() {
+            // `./tsconfig.base` names `./tsconfig.base.json`
+            path = dir.join(format!("{}.json", parent));
+        }
+        let inherited = load_compiler_options(&path, depth + 1)?;
+        options.base_url = inherited.base_url.or(options.base_url);
+        options.paths = inherited.paths.or(options.paths);
+    }
+
+    let own = compiler_options(&dir, &content)?;
+    options.base_url = own.base_url.or(options.base_url);
+    options.paths = own.paths.or(options.paths);
+    Ok(options)
+}
+
+/// `baseUrl` and `paths` declared directly in a config located in `dir`
+fn compiler_options(dir: &Path, content: &str) -> Result<CompilerOptions, String> {
+    let json: serde_json::Value =
+        serde_json::from_str(&strip_jsonc(content)).map_err(|e| e.to_string())?;
+    let Some(compiler) = json.get("compilerOptions") else {
+        return Ok(CompilerOptions::default());
+    };
+    let base_url = compiler
+        .get("baseUrl")
+        .and_then(serde_json::Value::as_str)
+        .map(|base| normalize(&dir.join(base)));
+    let paths = compiler.get("paths").and_then(serde_json::Value::as_object).map(|paths| {
+        let patterns = paths
+            .iter()
+            .map(|(alias, targets)| {
+                let targets = targets
+                    .as_array()
+                    .map(|targets| {
+                        targets.iter().filter_map(|t| t.as_str().map(str::to_string)).collect()
+                    })
+                    .unwrap_or_default();
+                (alias.clone(), targets)
+            })
+            .collect();
+        (patterns, dir.to_path_buf())
+    });
+    Ok(CompilerOptions { base_url, paths })
+}
+
+/// JSON with comments and trailing commas, as tsconfig.json allows, turned
+/// into plain JSON
+fn strip_jsonc(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&next| next != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    // Drop commas directly followed (up to whitespace) by a closing bracket
+    let mut result = String::with_capacity(out.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in out.char_indices() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = out[index + 1..].trim_start().chars().next();
+            if matches!(next, Some('}' | ']')) {
+                continue;
+            }
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// `path` relative to `base` with `/` separators, if it lies under `base`
+fn relative_to(path: &Path, base: &Path) -> Option<String> {
+    let relative = path.strip_prefix(base).ok()?;
+    let parts: Vec<String> =
+        relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Absolute form of `path` with `.` and `..` resolved lexically, so paths of
+/// files that do not exist yet compare with existing ones
+fn absolute(path: &Path) -> PathBuf {
+    normalize(&std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()))
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TSCONFIG_CONTENT: &str = r#"{
+        // Aliases used across the app
+        "compilerOptions": {
+            "baseUrl": "src",
+            "paths": {
+                "@app/*": ["app/*"],
+                "@shared": ["shared/index.ts"], /* the barrel */
+                "@shared/*": ["shared/*"],
+            },
+        },
+    }"#;
+
+    #[test]
+    fn test_aliases_replace_relative_specifiers() {
+        let root = Path::new("/repo");
+        let aliases = PathAliases::parse(root, TSCONFIG_CONTENT).unwrap();
+        let from = root.join("src/app/features/cart/view.ts");
+
+        let specifier = |target: &str| aliases.specifier(&from, &root.join(target));
+        assert_eq!(specifier("src/shared/format/price.ts"), "@shared/format/price");
+        assert_eq!(specifier("src/shared/index.ts"), "@shared");
+        assert_eq!(specifier("src/app/utils/math.ts"), "@app/utils/math");
+        // Under baseUrl without an alias, and out of reach of both
+        assert_eq!(specifier("src/lib/log.ts"), "lib/log");
+        assert_eq!(specifier("scripts/build.ts"), "../../../../scripts/build");
+        // Files outside the config keep relative specifiers
+        assert_eq!(aliases.specifier(Path::new("/other/a.ts"), Path::new("/other/b.ts")), "./b");
+    }
+
+    #[test]
+    fn test_extends_and_closest_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::write(
+            root.join("tsconfig.base.json"),
+            r#"{ "compilerOptions": { "paths": { "~/*": ["./src/*"] } } }"#,
+        )
+        .unwrap();
+        fs::write(root.join(TSCONFIG), r#"{ "extends": "./tsconfig.base" }"#).unwrap();
+        fs::create_dir_all(root.join("src/a")).unwrap();
+        fs::create_dir_all(root.join("tools")).unwrap();
+        fs::write(root.join("tools").join(TSCONFIG), "{}").unwrap();
+
+        let files = [root.join("src/a/x.ts"), root.join("tools/run.ts")];
+        let aliases = PathAliases::discover(&files);
+        assert_eq!(aliases.configs.len(), 2);
+        assert_eq!(aliases.specifier(&files[0], &root.join("src/b/y.ts")), "~/b/y");
+        assert_eq!(aliases.specifier(&files[1], &root.join("src/b/y.ts")), "../src/b/y");
+    }
+
+    #[test]
+    fn test_strip_jsonc_keeps_strings() {
+        let json = strip_jsonc(r#"{ "a": "http://x/*y*/", "b": [1, 2,], } // end"#);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["a"], "http://x/*y*/");
+        assert_eq!(value["b"], serde_json::json!([1, 2]));
+    }
+}
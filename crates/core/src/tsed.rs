@@ -1,5 +1,11 @@
 use crate::apted::{compute_edit_distance, compute_edit_distance_with_cutoff, APTEDOptions};
+use crate::node_weights::NodeWeights;
+use crate::size_penalty::SizePenalty;
 use crate::tree::TreeNode;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
@@ -9,6 +15,14 @@ pub struct TSEDOptions {
     pub min_tokens: Option<u32>, // Minimum number of tokens (AST nodes) for a function to be considered
     pub size_penalty: bool,      // Apply penalty for short functions
     pub skip_test: bool,         // Skip test functions (language-specific)
+    pub include_comments: bool,  // Keep comment and docstring nodes when comparing trees
+    pub size_ratio_penalty: SizePenalty, // Curve for trees of different sizes (with size_penalty)
+    pub max_tree_nodes: Option<usize>, // Larger trees use the fallback similarity instead of APTED
+    pub canonicalize_order: bool, // Sort independent declarations before comparing (TypeScript)
+    pub normalize_async: bool, // Compare .then() chains and callbacks as their await form (TypeScript)
+    pub normalize_collections: bool, // Compare collection loops as map/filter/reduce (TypeScript)
+    pub strip_types: bool,     // Compare TypeScript with its type syntax removed, as JavaScript
+    pub node_weights: NodeWeights, // Edit cost of each node kind, 1 for kinds without an entry
 }
 
 impl Default for TSEDOptions {
@@ -24,15 +38,129 @@ impl Default for TSEDOptions {
             min_tokens: None,   // No token limit by default
             size_penalty: true, // Enable size penalty by default
             skip_test: false,   // Don't skip test functions by default
+            include_comments: false, // Functions differing only in comments are identical
+            size_ratio_penalty: SizePenalty::default(),
+            max_tree_nodes: Some(DEFAULT_MAX_TREE_NODES),
+            canonicalize_order: false,
+            normalize_async: false,
+            normalize_collections: false,
+            strip_types: false,
+            node_weights: NodeWeights::default(),
         }
     }
 }
 
+/// Trees above this many nodes are compared with the fallback similarity, as
+/// tree edit distance grows too slow for them
+pub const DEFAULT_MAX_TREE_NODES: usize = 2000;
+
+/// How the similarity of two trees was computed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ComparisonAlgorithm {
+    /// Tree edit distance
+    #[default]
+    Tsed,
+    /// Cosine similarity of node label counts, for trees above `max_tree_nodes`
+    Fallback,
+}
+
+impl ComparisonAlgorithm {
+    /// Algorithm used for trees of these sizes
+    #[must_use]
+    pub fn for_sizes(size1: usize, size2: usize, options: &TSEDOptions) -> Self {
+        match options.max_tree_nodes {
+            Some(max) if size1.max(size2) > max => ComparisonAlgorithm::Fallback,
+            _ => ComparisonAlgorithm::Tsed,
+        }
+    }
+}
+
+impl fmt::Display for ComparisonAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComparisonAlgorithm::Tsed => write!(f, "tsed"),
+            ComparisonAlgorithm::Fallback => write!(f, "fallback"),
+        }
+    }
+}
+
+/// Number of nodes of each kind in a tree
+fn label_counts(tree: &TreeNode) -> HashMap<&str, f64> {
+    let mut counts = HashMap::new();
+    let mut stack = vec![tree];
+    while let Some(node) = stack.pop() {
+        *counts.entry(node.label.as_str()).or_insert(0.0) += node.weight;
+        stack.extend(node.children.iter().map(Rc::as_ref));
+    }
+    counts
+}
+
+/// Similarity of trees too large for tree edit distance: the cosine of their
+/// node label counts, scaled by the size ratio, which also bounds TSED
+#[allow(clippy::cast_precision_loss)]
+fn fallback_similarity(tree1: &TreeNode, tree2: &TreeNode, options: &TSEDOptions) -> f64 {
+    let (counts1, counts2) = (label_counts(tree1), label_counts(tree2));
+    let dot: f64 = counts1
+        .iter()
+        .filter_map(|(label, count)| counts2.get(label).map(|other| count * other))
+        .sum();
+    let norm =
+        |counts: &HashMap<&str, f64>| counts.values().map(|count| count * count).sum::<f64>();
+    let cosine = dot / (norm(&counts1) * norm(&counts2)).sqrt();
+
+    let size1 = tree1.get_subtree_size() as f64;
+    let size2 = tree2.get_subtree_size() as f64;
+    let size_ratio = size1.min(size2) / size1.max(size2);
+    let mut similarity = cosine * size_ratio;
+    if options.size_penalty {
+        similarity *= size_ratio_factor(options, size_ratio);
+    }
+    similarity
+}
+
+/// Strip comment nodes from both trees unless the options ask to keep them,
+/// and weight their nodes when the options have node weights
+fn comparable_trees(
+    tree1: &Rc<TreeNode>,
+    tree2: &Rc<TreeNode>,
+    options: &TSEDOptions,
+) -> (Rc<TreeNode>, Rc<TreeNode>) {
+    let (tree1, tree2) = if options.include_comments {
+        (Rc::clone(tree1), Rc::clone(tree2))
+    } else {
+        (tree1.without_comments(), tree2.without_comments())
+    };
+    if options.node_weights.is_empty() {
+        (tree1, tree2)
+    } else {
+        (options.node_weights.apply(&tree1), options.node_weights.apply(&tree2))
+    }
+}
+
+/// Penalty factor for trees of different sizes. The built-in curve only
+/// penalizes a tree less than half the size of the other, as such trees are
+/// likely fundamentally different.
+fn size_ratio_factor(options: &TSEDOptions, size_ratio: f64) -> f64 {
+    options
+        .size_ratio_penalty
+        .factor(size_ratio, |ratio| if ratio < 0.5 { ratio.powf(0.5) } else { 1.0 })
+}
+
 /// Calculate TSED (Tree Structure Edit Distance) similarity between two trees
 /// Returns a value between 0.0 and 1.0, where 1.0 means identical
 #[must_use]
 #[allow(clippy::cast_precision_loss)]
 pub fn calculate_tsed(tree1: &Rc<TreeNode>, tree2: &Rc<TreeNode>, options: &TSEDOptions) -> f64 {
+    let (tree1, tree2) = comparable_trees(tree1, tree2, options);
+    let (tree1, tree2) = (&tree1, &tree2);
+
+    if ComparisonAlgorithm::for_sizes(tree1.get_subtree_size(), tree2.get_subtree_size(), options)
+        == ComparisonAlgorithm::Fallback
+    {
+        return fallback_similarity(tree1, tree2, options);
+    }
+
     let distance = compute_edit_distance(tree1, tree2, &options.apted_options);
 
     let size1 = tree1.get_subtree_size() as f64;
@@ -42,9 +170,12 @@ pub fn calculate_tsed(tree1: &Rc<TreeNode>, tree2: &Rc<TreeNode>, options: &TSED
     // This ensures that when comparing trees of different sizes,
     // the similarity reflects how much of the larger tree matches
     let max_size = size1.max(size2);
+    // Distances are in node weights, which sum to the size without weights
+    let max_weight = tree1.get_subtree_weight().max(tree2.get_subtree_weight());
 
     // Calculate base TSED similarity
-    let tsed_similarity = if max_size > 0.0 { (1.0 - distance / max_size).max(0.0) } else { 1.0 };
+    let tsed_similarity =
+        if max_weight > 0.0 { (1.0 - distance / max_weight).max(0.0) } else { 1.0 };
 
     // If distance is 0 but trees have different sizes, check more carefully
     // This can happen when compare_values is false and structure is similar
@@ -104,11 +235,7 @@ pub fn calculate_tsed(tree1: &Rc<TreeNode>, tree2: &Rc<TreeNode>, options: &TSED
         }
 
         // Size difference penalty
-        if size_ratio < 0.5 {
-            // If one tree is less than half the size of the other,
-            // they're likely fundamentally different
-            similarity *= size_ratio.powf(0.5);
-        }
+        similarity *= size_ratio_factor(options, size_ratio);
     }
 
     similarity
@@ -124,18 +251,28 @@ pub fn calculate_tsed_with_threshold(
     options: &TSEDOptions,
     threshold: f64,
 ) -> f64 {
+    let (tree1, tree2) = comparable_trees(tree1, tree2, options);
+    let (tree1, tree2) = (&tree1, &tree2);
+
+    if ComparisonAlgorithm::for_sizes(tree1.get_subtree_size(), tree2.get_subtree_size(), options)
+        == ComparisonAlgorithm::Fallback
+    {
+        return fallback_similarity(tree1, tree2, options);
+    }
+
     let size1 = tree1.get_subtree_size() as f64;
     let size2 = tree2.get_subtree_size() as f64;
     let max_size = size1.max(size2);
+    let max_weight = tree1.get_subtree_weight().max(tree2.get_subtree_weight());
 
-    if max_size == 0.0 {
+    if max_weight == 0.0 {
         return 1.0;
     }
 
     // Maximum distance that could still yield similarity >= threshold
-    // similarity = 1.0 - distance / max_size >= threshold
-    // => distance <= max_size * (1.0 - threshold)
-    let max_distance = max_size * (1.0 - threshold);
+    // similarity = 1.0 - distance / max_weight >= threshold
+    // => distance <= max_weight * (1.0 - threshold)
+    let max_distance = max_weight * (1.0 - threshold);
 
     let distance =
         compute_edit_distance_with_cutoff(tree1, tree2, &options.apted_options, max_distance);
@@ -146,7 +283,7 @@ pub fn calculate_tsed_with_threshold(
     }
 
     // Apply the same normalization and penalties as calculate_tsed
-    let tsed_similarity = (1.0 - distance / max_size).max(0.0);
+    let tsed_similarity = (1.0 - distance / max_weight).max(0.0);
 
     let tsed_similarity = if distance == 0.0 && size1 != size2 {
         let size_ratio = size1.min(size2) / size1.max(size2);
@@ -192,9 +329,7 @@ pub fn calculate_tsed_with_threshold(
             }
         }
 
-        if size_ratio < 0.5 {
-            similarity *= size_ratio.powf(0.5);
-        }
+        similarity *= size_ratio_factor(options, size_ratio);
     }
 
     similarity
@@ -263,4 +398,129 @@ mod tests {
         // Should have lower similarity due to structural differences
         assert!(similarity < 0.7);
     }
+
+    #[test]
+    fn test_comments_are_stripped_by_default() {
+        let code = TreeNode::new("FunctionBody".to_string(), String::new(), 0);
+        let mut with_comment = code.clone();
+        with_comment.add_child(Rc::new(TreeNode::new(
+            "comment".to_string(),
+            "// explain".to_string(),
+            1,
+        )));
+        let (plain, commented) = (Rc::new(code), Rc::new(with_comment));
+
+        let options = TSEDOptions { size_penalty: false, ..Default::default() };
+        assert!((calculate_tsed(&plain, &commented, &options) - 1.0).abs() < f64::EPSILON);
+
+        let options =
+            TSEDOptions { size_penalty: false, include_comments: true, ..Default::default() };
+        assert!(calculate_tsed(&plain, &commented, &options) < 1.0);
+    }
+
+    #[test]
+    fn test_large_trees_use_fallback_similarity() {
+        let tree = |kinds: &[&str]| {
+            let mut root = TreeNode::new("Block".to_string(), String::new(), 0);
+            for (id, kind) in kinds.iter().enumerate() {
+                root.add_child(Rc::new(TreeNode::new((*kind).to_string(), String::new(), id + 1)));
+            }
+            Rc::new(root)
+        };
+        let first = tree(&["If", "Return", "Call", "Call"]);
+        let reordered = tree(&["Call", "Call", "If", "Return"]);
+        let different = tree(&["While", "While", "Throw", "Throw"]);
+
+        let options = TSEDOptions { size_penalty: false, ..Default::default() };
+        assert_eq!(ComparisonAlgorithm::for_sizes(5, 5, &options), ComparisonAlgorithm::Tsed);
+        assert!(calculate_tsed(&first, &reordered, &options) < 1.0);
+
+        let capped =
+            TSEDOptions { size_penalty: false, max_tree_nodes: Some(4), ..Default::default() };
+        assert_eq!(ComparisonAlgorithm::for_sizes(5, 5, &capped), ComparisonAlgorithm::Fallback);
+        assert!((calculate_tsed(&first, &reordered, &capped) - 1.0).abs() < 1e-9);
+        assert!(calculate_tsed(&first, &different, &capped) < 0.5);
+        assert!(
+            (calculate_tsed_with_threshold(&first, &reordered, &capped, 0.9) - 1.0).abs() < 1e-9
+        );
+
+        let uncapped = TSEDOptions { max_tree_nodes: None, ..capped };
+        assert_eq!(ComparisonAlgorithm::for_sizes(5000, 5, &uncapped), ComparisonAlgorithm::Tsed);
+    }
+
+    #[test]
+    fn test_size_ratio_penalty_curves() {
+        use crate::size_penalty::PenaltyCurve;
+
+        let tree = |children: usize| {
+            let mut root = TreeNode::new("Block".to_string(), String::new(), 0);
+            for id in 1..=children {
+                root.add_child(Rc::new(TreeNode::new("Statement".to_string(), String::new(), id)));
+            }
+            Rc::new(root)
+        };
+        // 41 against 101 nodes: less than half the size
+        let (small, large) = (tree(40), tree(100));
+        let similarity = |curve, floor| {
+            let size_ratio_penalty = SizePenalty { curve, floor };
+            calculate_tsed(
+                &small,
+                &large,
+                &TSEDOptions { size_ratio_penalty, ..Default::default() },
+            )
+        };
+
+        let builtin = similarity(PenaltyCurve::Builtin, 0.0);
+        let none = similarity(PenaltyCurve::None, 0.0);
+        let quadratic = similarity(PenaltyCurve::Quadratic, 0.0);
+        assert!(quadratic < builtin && builtin < none);
+        assert!((builtin / none - (41.0f64 / 101.0).sqrt()).abs() < 1e-9);
+        assert!((similarity(PenaltyCurve::Quadratic, 1.0) - none).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_node_weights_favor_shared_control_flow() {
+        use crate::node_weights::NodeWeights;
+
+        // `Label(child, child)` into a tree with distinct ids
+        fn parse(shape: &mut &str, next_id: &mut usize) -> Rc<TreeNode> {
+            let end = shape.find(['(', ',', ')']).unwrap_or(shape.len());
+            let mut node = TreeNode::new(shape[..end].to_string(), String::new(), *next_id);
+            *next_id += 1;
+            *shape = &shape[end..];
+            if let Some(rest) = shape.strip_prefix('(') {
+                *shape = rest;
+                loop {
+                    node.add_child(parse(shape, next_id));
+                    let (separator, rest) = shape.split_at(1);
+                    *shape = rest;
+                    if separator == ")" {
+                        break;
+                    }
+                }
+            }
+            Rc::new(node)
+        }
+        let tree = |mut shape: &str| parse(&mut shape, &mut 0);
+
+        let base =
+            tree("Block(IfStatement(x,ReturnStatement(Add(x,y))),ReturnStatement(Sub(x,y)))");
+        let arithmetic =
+            tree("Block(IfStatement(x,ReturnStatement(Mul(x,z))),ReturnStatement(Div(x,y)))");
+        let branching =
+            tree("Block(WhileStatement(x,ThrowStatement(Add(x,y))),ReturnStatement(Sub(x,y)))");
+
+        let similarity = |weights: NodeWeights, other: &Rc<TreeNode>| {
+            let options =
+                TSEDOptions { size_penalty: false, node_weights: weights, ..Default::default() };
+            calculate_tsed(&base, other, &options)
+        };
+        let plain = NodeWeights::default();
+        assert!(similarity(plain.clone(), &arithmetic) < similarity(plain, &branching));
+
+        let control_flow = NodeWeights::new([("@control-flow".to_string(), 3.0)].into());
+        let (arithmetic, branching) =
+            (similarity(control_flow.clone(), &arithmetic), similarity(control_flow, &branching));
+        assert!(arithmetic > branching, "{arithmetic} should exceed {branching}");
+    }
 }
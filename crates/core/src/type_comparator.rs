@@ -1,8 +1,11 @@
-use crate::type_extractor::{TypeDefinition, TypeLiteralDefinition};
+use crate::comparison_budget::ComparisonBudget;
+use crate::type_extractor::{PropertyDefinition, TypeDefinition, TypeLiteralDefinition};
 use crate::type_normalizer::{
     calculate_property_similarity, find_property_matches, normalize_type, NormalizationOptions,
     NormalizedType, PropertyMatch,
 };
+use schemars::JsonSchema;
+use serde::Serialize;
 use std::collections::HashSet;
 
 #[derive(Debug, Clone)]
@@ -227,6 +230,102 @@ fn identify_differences(
     }
 }
 
+/// How a property lines up between two compared types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyDiffKind {
+    Matched,
+    /// Present in both, but the type or optionality differs
+    TypeMismatch,
+    /// Only in the first type
+    Missing,
+    /// Only in the second type
+    Extra,
+}
+
+/// One aligned row of a property diff; `left`/`right` render as `name?: type`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct PropertyDiffEntry {
+    pub kind: PropertyDiffKind,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+/// Align the properties of two types using the differences of a comparison result.
+/// Rows follow the declaration order of the first type; extra properties come last.
+#[must_use]
+pub fn diff_type_properties(
+    props1: &[PropertyDefinition],
+    props2: &[PropertyDefinition],
+    result: &TypeComparisonResult,
+) -> Vec<PropertyDiffEntry> {
+    let differences = &result.differences;
+    let mismatched: HashSet<&str> = differences
+        .type_mismatches
+        .iter()
+        .map(|mismatch| mismatch.property.as_str())
+        .chain(differences.optionality_differences.iter().map(String::as_str))
+        .filter_map(|pair| pair.split(" -> ").next())
+        .collect();
+
+    let mut entries = Vec::new();
+    let mut used2 = HashSet::new();
+
+    for prop1 in props1 {
+        let key = normalized_property_name(&prop1.name);
+        let counterpart = if differences.missing_properties.contains(&key) {
+            None
+        } else {
+            props2.iter().enumerate().find(|(index, prop2)| {
+                !used2.contains(index) && normalized_property_name(&prop2.name) == key
+            })
+        };
+        let Some((index, prop2)) = counterpart else {
+            entries.push(PropertyDiffEntry {
+                kind: PropertyDiffKind::Missing,
+                left: Some(format_property(prop1)),
+                right: None,
+            });
+            continue;
+        };
+        used2.insert(index);
+
+        let kind = if mismatched.contains(key.as_str()) {
+            PropertyDiffKind::TypeMismatch
+        } else {
+            PropertyDiffKind::Matched
+        };
+        entries.push(PropertyDiffEntry {
+            kind,
+            left: Some(format_property(prop1)),
+            right: Some(format_property(prop2)),
+        });
+    }
+
+    for (index, prop2) in props2.iter().enumerate() {
+        if !used2.contains(&index) {
+            entries.push(PropertyDiffEntry {
+                kind: PropertyDiffKind::Extra,
+                left: None,
+                right: Some(format_property(prop2)),
+            });
+        }
+    }
+
+    entries
+}
+
+/// Property names are matched case-insensitively, as in `normalize_type`
+fn normalized_property_name(name: &str) -> String {
+    name.to_lowercase().trim().to_string()
+}
+
+fn format_property(prop: &PropertyDefinition) -> String {
+    let modifiers = if prop.readonly { "readonly " } else { "" };
+    let optional = if prop.optional { "?" } else { "" };
+    format!("{}{}{}: {}", modifiers, prop.name, optional, prop.type_annotation)
+}
+
 /// Create an empty comparison result for cases where comparison is not possible
 fn create_empty_comparison_result() -> TypeComparisonResult {
     TypeComparisonResult {
@@ -262,6 +361,16 @@ pub fn find_similar_types(
     types: &[TypeDefinition],
     threshold: f64,
     options: &TypeComparisonOptions,
+) -> Vec<SimilarTypePair> {
+    find_similar_types_with_budget(types, threshold, options, &ComparisonBudget::unlimited())
+}
+
+/// Find similar type pairs, stopping when the budget runs out
+pub fn find_similar_types_with_budget(
+    types: &[TypeDefinition],
+    threshold: f64,
+    options: &TypeComparisonOptions,
+    budget: &ComparisonBudget,
 ) -> Vec<SimilarTypePair> {
     let mut similar_pairs = Vec::new();
 
@@ -274,6 +383,9 @@ pub fn find_similar_types(
             if type1.name == type2.name && type1.file_path == type2.file_path {
                 continue;
             }
+            if !budget.try_compare() {
+                continue;
+            }
 
             let result = compare_types(type1, type2, options);
 
@@ -370,15 +482,16 @@ pub fn compare_type_literal_with_type(
 ) -> TypeComparisonResult {
     // Convert type literal to TypeDefinition for comparison
     let temp_type_def = TypeDefinition {
-        name: type_literal.name.clone(),
+        name: type_literal.name,
         kind: crate::type_extractor::TypeKind::TypeLiteral,
         properties: type_literal.properties.clone(),
         generics: Vec::new(),
         extends: Vec::new(),
         start_line: type_literal.start_line,
         end_line: type_literal.end_line,
-        file_path: type_literal.file_path.clone(),
+        file_path: type_literal.file_path,
         has_ignore_directive: false,
+        partial: type_literal.partial,
     };
 
     compare_types(&temp_type_def, type_definition, options)
@@ -390,6 +503,23 @@ pub fn find_similar_type_literals(
     type_definitions: &[TypeDefinition],
     threshold: f64,
     options: &TypeComparisonOptions,
+) -> Vec<TypeLiteralComparisonPair> {
+    find_similar_type_literals_with_budget(
+        type_literals,
+        type_definitions,
+        threshold,
+        options,
+        &ComparisonBudget::unlimited(),
+    )
+}
+
+/// Find type literals similar to type definitions, stopping when the budget runs out
+pub fn find_similar_type_literals_with_budget(
+    type_literals: &[TypeLiteralDefinition],
+    type_definitions: &[TypeDefinition],
+    threshold: f64,
+    options: &TypeComparisonOptions,
+    budget: &ComparisonBudget,
 ) -> Vec<TypeLiteralComparisonPair> {
     let mut similar_pairs = Vec::new();
 
@@ -407,6 +537,9 @@ pub fn find_similar_type_literals(
                     continue;
                 }
             }
+            if !budget.try_compare() {
+                continue;
+            }
 
             let result = compare_type_literal_with_type(type_literal, type_definition, options);
 
@@ -431,6 +564,21 @@ pub fn find_similar_type_literals_pairs(
     type_literals: &[TypeLiteralDefinition],
     threshold: f64,
     options: &TypeComparisonOptions,
+) -> Vec<(TypeLiteralDefinition, TypeLiteralDefinition, TypeComparisonResult)> {
+    find_similar_type_literals_pairs_with_budget(
+        type_literals,
+        threshold,
+        options,
+        &ComparisonBudget::unlimited(),
+    )
+}
+
+/// Find type literals similar to each other, stopping when the budget runs out
+pub fn find_similar_type_literals_pairs_with_budget(
+    type_literals: &[TypeLiteralDefinition],
+    threshold: f64,
+    options: &TypeComparisonOptions,
+    budget: &ComparisonBudget,
 ) -> Vec<(TypeLiteralDefinition, TypeLiteralDefinition, TypeComparisonResult)> {
     let mut similar_pairs = Vec::new();
 
@@ -445,19 +593,23 @@ pub fn find_similar_type_literals_pairs(
             {
                 continue;
             }
+            if !budget.try_compare() {
+                continue;
+            }
 
             let result = compare_type_literal_with_type(
                 type_literal1,
                 &TypeDefinition {
-                    name: type_literal2.name.clone(),
+                    name: type_literal2.name,
                     kind: crate::type_extractor::TypeKind::TypeLiteral,
                     properties: type_literal2.properties.clone(),
                     generics: Vec::new(),
                     extends: Vec::new(),
                     start_line: type_literal2.start_line,
                     end_line: type_literal2.end_line,
-                    file_path: type_literal2.file_path.clone(),
+                    file_path: type_literal2.file_path,
                     has_ignore_directive: false,
+                    partial: type_literal2.partial,
                 },
                 options,
             );
@@ -481,13 +633,13 @@ mod tests {
 
     fn create_test_type(name: &str, properties: Vec<(&str, &str, bool, bool)>) -> TypeDefinition {
         TypeDefinition {
-            name: name.to_string(),
+            name: name.into(),
             kind: TypeKind::Interface,
             properties: properties
                 .into_iter()
                 .map(|(name, type_annotation, optional, readonly)| PropertyDefinition {
                     name: name.to_string(),
-                    type_annotation: type_annotation.to_string(),
+                    type_annotation: type_annotation.into(),
                     optional,
                     readonly,
                 })
@@ -496,8 +648,9 @@ mod tests {
             extends: Vec::new(),
             start_line: 1,
             end_line: 10,
-            file_path: "test.ts".to_string(),
+            file_path: "test.ts".into(),
             has_ignore_directive: false,
+            partial: false,
         }
     }
 
@@ -538,6 +691,47 @@ mod tests {
         assert_eq!(result.matched_properties.len(), 1); // Only "id" matches
     }
 
+    #[test]
+    fn test_diff_type_properties() {
+        let type1 = create_test_type(
+            "User",
+            vec![
+                ("id", "string", false, false),
+                ("email", "string", false, false),
+                ("age", "number", false, false),
+                ("name", "string", false, false),
+            ],
+        );
+        let type2 = create_test_type(
+            "Account",
+            vec![
+                ("id", "string", false, false),
+                ("email", "string", true, false),
+                ("age", "string", false, false),
+                ("createdAt", "Date", false, false),
+            ],
+        );
+
+        let result = compare_types(&type1, &type2, &TypeComparisonOptions::default());
+        let diff = diff_type_properties(&type1.properties, &type2.properties, &result);
+
+        let kinds: Vec<_> = diff.iter().map(|entry| entry.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                PropertyDiffKind::Matched,
+                PropertyDiffKind::TypeMismatch,
+                PropertyDiffKind::TypeMismatch,
+                PropertyDiffKind::Missing,
+                PropertyDiffKind::Extra,
+            ]
+        );
+        assert_eq!(diff[1].right.as_deref(), Some("email?: string"));
+        assert_eq!(diff[3].left.as_deref(), Some("name: string"));
+        assert_eq!(diff[3].right, None);
+        assert_eq!(diff[4].right.as_deref(), Some("createdAt: Date"));
+    }
+
     #[test]
     fn test_compare_different_types() {
         let type1 = create_test_type(
@@ -0,0 +1,76 @@
+//! Type compatibility of function signatures.
+//!
+//! Two similar functions are easier to merge when their parameters and
+//! return types line up. Each parameter position and the return type is a
+//! slot scored with the semantic type comparison: annotated types by their
+//! similarity, two unannotated ones as compatible, one annotated and one not
+//! as half compatible, and a parameter only one function has as incompatible.
+//! The compatibility is the mean over all slots.
+
+use crate::function_extractor::FunctionDefinition;
+use crate::interner::Symbol;
+use crate::semantic_types::SemanticTypeResolver;
+
+/// Score of a slot where only one function has a type annotation
+const ONE_SIDED_ANNOTATION: f64 = 0.5;
+
+/// Compatibility of the signatures of two functions in `[0.0, 1.0]`
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn signature_compatibility(
+    func1: &FunctionDefinition,
+    func2: &FunctionDefinition,
+    resolver: &SemanticTypeResolver,
+) -> f64 {
+    let arity = func1.parameter_types.len().max(func2.parameter_types.len());
+    let parameters: f64 = (0..arity)
+        .map(|index| match (func1.parameter_types.get(index), func2.parameter_types.get(index)) {
+            (Some(type1), Some(type2)) => slot_compatibility(type1, type2, resolver),
+            _ => 0.0,
+        })
+        .sum();
+    let return_type = slot_compatibility(&func1.return_type, &func2.return_type, resolver);
+    (parameters + return_type) / (arity + 1) as f64
+}
+
+fn slot_compatibility(
+    type1: &Option<Symbol>,
+    type2: &Option<Symbol>,
+    resolver: &SemanticTypeResolver,
+) -> f64 {
+    match (type1, type2) {
+        (Some(type1), Some(type2)) => resolver.similarity(type1, type2),
+        (None, None) => 1.0,
+        _ => ONE_SIDED_ANNOTATION,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function_extractor::extract_functions;
+
+    #[test]
+    fn test_signature_compatibility() {
+        let source = r#"
+function total(items: Item[], rate: number): number { return 0; }
+function sum(values: Item[], factor: number): number { return 0; }
+function format(items: Item[], locale: string): string { return ""; }
+function plain(items, rate) { return 0; }
+function single(items: Item[]): number { return 0; }
+"#;
+        let functions = extract_functions("test.ts", source).unwrap();
+        let resolver = SemanticTypeResolver::new();
+        let score =
+            |a: usize, b: usize| signature_compatibility(&functions[a], &functions[b], &resolver);
+
+        assert_eq!(functions[0].parameter_types[0].as_deref(), Some("Item[]"));
+        assert_eq!(functions[0].return_type.as_deref(), Some("number"));
+        assert!((score(0, 1) - 1.0).abs() < f64::EPSILON);
+        assert!(score(0, 2) < score(0, 1));
+        assert!((score(0, 3) - 0.5).abs() < f64::EPSILON);
+        assert!((score(3, 3) - 1.0).abs() < f64::EPSILON);
+        // A parameter only one function has counts as incompatible
+        assert!((score(0, 4) - 2.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -1,25 +1,28 @@
+use crate::interner::Symbol;
 use oxc_allocator::Allocator;
 use oxc_ast::ast::{
     Expression, PropertyKey, Statement, TSInterfaceDeclaration, TSPropertySignature, TSType,
     TSTypeAliasDeclaration, VariableDeclarator,
 };
-use oxc_parser::Parser;
-use oxc_span::SourceType;
+use oxc_span::{GetSpan, SourceType};
 use std::collections::HashMap;
 
 use crate::ignore_directive::has_similarity_ignore_directive;
+use crate::tolerant_parse::parse_tolerant;
 
 #[derive(Debug, Clone)]
 pub struct TypeDefinition {
-    pub name: String,
+    pub name: Symbol,
     pub kind: TypeKind,
     pub properties: Vec<PropertyDefinition>,
     pub generics: Vec<String>,
     pub extends: Vec<String>,
     pub start_line: usize,
     pub end_line: usize,
-    pub file_path: String,
+    pub file_path: Symbol,
     pub has_ignore_directive: bool,
+    /// Extracted from a file with syntax errors elsewhere
+    pub partial: bool,
 }
 #[derive(Debug, Clone, PartialEq)]
 pub enum TypeKind {
@@ -31,19 +34,21 @@ pub enum TypeKind {
 #[derive(Debug, Clone)]
 pub struct PropertyDefinition {
     pub name: String,
-    pub type_annotation: String,
+    pub type_annotation: Symbol,
     pub optional: bool,
     pub readonly: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct TypeLiteralDefinition {
-    pub name: String, // Function name, variable name, etc.
+    pub name: Symbol, // Function name, variable name, etc.
     pub context: TypeLiteralContext,
     pub properties: Vec<PropertyDefinition>,
     pub start_line: usize,
     pub end_line: usize,
-    pub file_path: String,
+    pub file_path: Symbol,
+    /// Extracted from a file with syntax errors elsewhere
+    pub partial: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -56,14 +61,14 @@ pub enum TypeLiteralContext {
 
 pub struct TypeExtractor {
     source_text: String,
-    file_path: String,
+    file_path: Symbol,
     line_offsets: Vec<usize>,
 }
 
 impl TypeExtractor {
     pub fn new(source_text: String, file_path: String) -> Self {
         let line_offsets = Self::calculate_line_offsets(&source_text);
-        Self { source_text, file_path, line_offsets }
+        Self { source_text, file_path: file_path.into(), line_offsets }
     }
 
     fn calculate_line_offsets(source: &str) -> Vec<usize> {
@@ -85,19 +90,12 @@ impl TypeExtractor {
 
     pub fn extract_types(&self) -> Result<Vec<TypeDefinition>, String> {
         let allocator = Allocator::default();
-        let source_type = SourceType::from_path(&self.file_path).unwrap_or(SourceType::tsx());
-        let ret = Parser::new(&allocator, &self.source_text, source_type).parse();
-
-        if !ret.errors.is_empty() {
-            // Create a more readable error message
-            let error_messages: Vec<String> =
-                ret.errors.iter().map(|e| e.message.to_string()).collect();
-            return Err(format!("Parse errors: {}", error_messages.join(", ")));
-        }
+        let source_type = SourceType::from_path(self.file_path).unwrap_or(SourceType::tsx());
+        let parsed = parse_tolerant(&allocator, &self.source_text, source_type)?;
 
         let mut types = Vec::new();
 
-        for stmt in &ret.program.body {
+        for stmt in parsed.program.body.iter().filter(|stmt| !parsed.is_damaged(stmt.span())) {
             match stmt {
                 Statement::TSInterfaceDeclaration(interface) => {
                     if let Some(type_def) = self.extract_interface(interface) {
@@ -130,27 +128,30 @@ impl TypeExtractor {
             }
         }
 
+        if parsed.errors.is_some() {
+            for type_def in &mut types {
+                type_def.partial = true;
+            }
+        }
         Ok(types)
     }
 
     pub fn extract_type_literals(&self) -> Result<Vec<TypeLiteralDefinition>, String> {
         let allocator = Allocator::default();
-        let source_type = SourceType::from_path(&self.file_path).unwrap_or(SourceType::tsx());
-        let ret = Parser::new(&allocator, &self.source_text, source_type).parse();
-
-        if !ret.errors.is_empty() {
-            // Create a more readable error message
-            let error_messages: Vec<String> =
-                ret.errors.iter().map(|e| e.message.to_string()).collect();
-            return Err(format!("Parse errors: {}", error_messages.join(", ")));
-        }
+        let source_type = SourceType::from_path(self.file_path).unwrap_or(SourceType::tsx());
+        let parsed = parse_tolerant(&allocator, &self.source_text, source_type)?;
 
         let mut type_literals = Vec::new();
 
-        for stmt in &ret.program.body {
+        for stmt in parsed.program.body.iter().filter(|stmt| !parsed.is_damaged(stmt.span())) {
             self.extract_type_literals_from_statement(stmt, &mut type_literals);
         }
 
+        if parsed.errors.is_some() {
+            for type_literal in &mut type_literals {
+                type_literal.partial = true;
+            }
+        }
         Ok(type_literals)
     }
 
@@ -164,15 +165,16 @@ impl TypeExtractor {
         let extends = self.extract_extends(Some(&interface.extends));
 
         Some(TypeDefinition {
-            name,
+            name: name.into(),
             kind: TypeKind::Interface,
             properties,
             generics,
             extends,
             start_line,
             end_line,
-            file_path: self.file_path.clone(),
+            file_path: self.file_path,
             has_ignore_directive: has_similarity_ignore_directive(&self.source_text, start_line),
+            partial: false,
         })
     }
 
@@ -185,15 +187,16 @@ impl TypeExtractor {
         let generics = self.extract_generics(type_alias.type_parameters.as_ref());
 
         Some(TypeDefinition {
-            name,
+            name: name.into(),
             kind: TypeKind::TypeAlias,
             properties,
             generics,
             extends: Vec::new(), // Type aliases don't have extends
             start_line,
             end_line,
-            file_path: self.file_path.clone(),
+            file_path: self.file_path,
             has_ignore_directive: has_similarity_ignore_directive(&self.source_text, start_line),
+            partial: false,
         })
     }
 
@@ -249,7 +252,7 @@ impl TypeExtractor {
 
         Some(PropertyDefinition {
             name,
-            type_annotation,
+            type_annotation: type_annotation.into(),
             optional: prop_sig.optional,
             readonly: prop_sig.readonly,
         })
@@ -277,7 +280,7 @@ impl TypeExtractor {
 
         Some(PropertyDefinition {
             name,
-            type_annotation,
+            type_annotation: type_annotation.into(),
             optional: method_sig.optional,
             readonly: false,
         })
@@ -462,12 +465,13 @@ impl TypeExtractor {
                 let end_line = self.get_line_number(type_literal.span.end as usize);
 
                 Some(TypeLiteralDefinition {
-                    name: self.get_context_name(&context),
+                    name: self.get_context_name(&context).into(),
                     context,
                     properties,
                     start_line,
                     end_line,
-                    file_path: self.file_path.clone(),
+                    file_path: self.file_path,
+                    partial: false,
                 })
             }
             _ => None,
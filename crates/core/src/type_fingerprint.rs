@@ -179,24 +179,24 @@ mod tests {
     #[test]
     fn test_generate_fingerprint() {
         let type_def = TypeDefinition {
-            name: "User".to_string(),
+            name: "User".into(),
             kind: TypeKind::Interface,
             properties: vec![
                 PropertyDefinition {
                     name: "id".to_string(),
-                    type_annotation: "number".to_string(),
+                    type_annotation: "number".into(),
                     optional: false,
                     readonly: false,
                 },
                 PropertyDefinition {
                     name: "name".to_string(),
-                    type_annotation: "string".to_string(),
+                    type_annotation: "string".into(),
                     optional: false,
                     readonly: false,
                 },
                 PropertyDefinition {
                     name: "email".to_string(),
-                    type_annotation: "string".to_string(),
+                    type_annotation: "string".into(),
                     optional: false,
                     readonly: false,
                 },
@@ -205,8 +205,9 @@ mod tests {
             extends: vec![],
             start_line: 1,
             end_line: 5,
-            file_path: "test.ts".to_string(),
+            file_path: "test.ts".into(),
             has_ignore_directive: false,
+            partial: false,
         };
 
         let fingerprint = generate_type_fingerprint(&type_def);
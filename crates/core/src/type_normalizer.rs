@@ -42,7 +42,7 @@ pub fn normalize_type(type_def: &TypeDefinition, options: &NormalizationOptions)
         let normalized_type = if options.normalize_type_names {
             normalize_type_name(&prop.type_annotation)
         } else {
-            prop.type_annotation.clone()
+            prop.type_annotation.to_string()
         };
 
         properties.insert(normalized_prop_name.clone(), normalized_type);
@@ -69,7 +69,7 @@ pub fn normalize_type(type_def: &TypeDefinition, options: &NormalizationOptions)
         optional_properties,
         readonly_properties,
         signature,
-        original_name: type_def.name.clone(),
+        original_name: type_def.name.to_string(),
         kind: type_def.kind.clone(),
     }
 }
@@ -429,13 +429,13 @@ mod tests {
 
     fn create_test_type(name: &str, properties: Vec<(&str, &str, bool, bool)>) -> TypeDefinition {
         TypeDefinition {
-            name: name.to_string(),
+            name: name.into(),
             kind: TypeKind::Interface,
             properties: properties
                 .into_iter()
                 .map(|(name, type_annotation, optional, readonly)| PropertyDefinition {
                     name: name.to_string(),
-                    type_annotation: type_annotation.to_string(),
+                    type_annotation: type_annotation.into(),
                     optional,
                     readonly,
                 })
@@ -444,8 +444,9 @@ mod tests {
             extends: Vec::new(),
             start_line: 1,
             end_line: 10,
-            file_path: "test.ts".to_string(),
+            file_path: "test.ts".into(),
             has_ignore_directive: false,
+            partial: false,
         }
     }
 
@@ -1,7 +1,8 @@
 use crate::class_extractor::{ClassDefinition, ClassMethod, ClassProperty};
 use crate::structure_comparator::{
-    ComparisonOptions, SourceLocation, Structure, StructureComparator, StructureComparisonResult,
-    StructureIdentifier, StructureKind, StructureMember, StructureMetadata,
+    BucketStats, ComparisonOptions, FingerprintBuckets, SourceLocation, Structure,
+    StructureComparator, StructureComparisonResult, StructureIdentifier, StructureKind,
+    StructureMember, StructureMetadata,
 };
 use crate::type_extractor::{PropertyDefinition, TypeDefinition, TypeKind, TypeLiteralDefinition};
 
@@ -16,9 +17,9 @@ impl From<TypeDefinition> for Structure {
 
         Structure {
             identifier: StructureIdentifier {
-                name: type_def.name.clone(),
+                name: type_def.name,
                 kind,
-                namespace: Some(type_def.file_path.clone()),
+                namespace: Some(type_def.file_path),
             },
             members: type_def.properties.into_iter().map(property_to_member).collect(),
             metadata: StructureMetadata {
@@ -40,9 +41,9 @@ impl From<TypeLiteralDefinition> for Structure {
     fn from(literal: TypeLiteralDefinition) -> Self {
         Structure {
             identifier: StructureIdentifier {
-                name: literal.name.clone(),
+                name: literal.name,
                 kind: StructureKind::TypeScriptTypeLiteral,
-                namespace: Some(literal.file_path.clone()),
+                namespace: Some(literal.file_path),
             },
             members: literal.properties.into_iter().map(property_to_member).collect(),
             metadata: StructureMetadata {
@@ -78,7 +79,7 @@ impl From<ClassDefinition> for Structure {
         for (i, param) in class.constructor_params.iter().enumerate() {
             members.push(StructureMember {
                 name: format!("constructor_param_{}", i),
-                value_type: param.clone(),
+                value_type: param.into(),
                 modifiers: vec!["constructor".to_string()],
                 nested: None,
             });
@@ -86,9 +87,9 @@ impl From<ClassDefinition> for Structure {
 
         Structure {
             identifier: StructureIdentifier {
-                name: class.name.clone(),
+                name: class.name,
                 kind: StructureKind::TypeScriptClass,
-                namespace: Some(class.file_path.clone()),
+                namespace: Some(class.file_path),
             },
             members,
             metadata: StructureMetadata {
@@ -122,6 +123,9 @@ fn class_property_to_member(prop: ClassProperty) -> StructureMember {
     if prop.is_private {
         modifiers.push("private".to_string());
     }
+    if prop.is_protected {
+        modifiers.push("protected".to_string());
+    }
     if prop.is_static {
         modifiers.push("static".to_string());
     }
@@ -140,6 +144,9 @@ fn class_method_to_member(method: ClassMethod) -> StructureMember {
     if method.is_private {
         modifiers.push("private".to_string());
     }
+    if method.is_protected {
+        modifiers.push("protected".to_string());
+    }
     if method.is_static {
         modifiers.push("static".to_string());
     }
@@ -151,7 +158,7 @@ fn class_method_to_member(method: ClassMethod) -> StructureMember {
     // メソッドシグネチャを型として表現
     let signature = format!("({}) => {}", method.parameters.join(", "), method.return_type);
 
-    StructureMember { name: method.name, value_type: signature, modifiers, nested: None }
+    StructureMember { name: method.name, value_type: signature.into(), modifiers, nested: None }
 }
 
 /// TypeScript用の比較エンジン
@@ -238,7 +245,7 @@ impl TypeScriptStructureComparator {
 /// 複数の構造を効率的に比較
 pub struct BatchComparator {
     comparator: TypeScriptStructureComparator,
-    fingerprint_cache: std::collections::HashMap<String, Vec<Structure>>,
+    buckets: FingerprintBuckets,
 }
 
 impl Default for BatchComparator {
@@ -251,66 +258,38 @@ impl BatchComparator {
     pub fn new() -> Self {
         Self {
             comparator: TypeScriptStructureComparator::new(),
-            fingerprint_cache: std::collections::HashMap::new(),
+            buckets: FingerprintBuckets::new(),
         }
     }
 
-    /// 構造をフィンガープリントでグループ化
+    /// 構造をフィンガープリントでグループ化（同じ位置の構造は一度だけ登録）
     pub fn group_by_fingerprint(&mut self, structures: Vec<Structure>) {
         for structure in structures {
             let fingerprint = self.comparator.comparator.generate_fingerprint(&structure);
-            self.fingerprint_cache.entry(fingerprint).or_default().push(structure);
+            self.buckets.insert(structure, fingerprint);
         }
     }
 
-    /// 類似構造を検出
-    pub fn find_similar_structures(&mut self, threshold: f64) -> Vec<(Structure, Structure, f64)> {
-        use crate::structure_comparator::should_compare_fingerprints;
+    /// バケットの統計情報
+    pub fn bucket_stats(&self) -> BucketStats {
+        self.buckets.stats()
+    }
 
+    /// 類似構造を検出（各ペアは一度だけ比較・報告される）
+    pub fn find_similar_structures(&mut self, threshold: f64) -> Vec<(Structure, Structure, f64)> {
         let mut results = Vec::new();
 
-        // フィンガープリントのリストを取得
-        let fingerprints: Vec<String> = self.fingerprint_cache.keys().cloned().collect();
-
-        // フィンガープリントが類似している組み合わせのみ比較
-        for i in 0..fingerprints.len() {
-            for j in i..fingerprints.len() {
-                let fp1 = &fingerprints[i];
-                let fp2 = &fingerprints[j];
-
-                // フィンガープリントが比較対象として妥当かチェック
-                if !should_compare_fingerprints(fp1, fp2) {
-                    continue;
-                }
-
-                let structures1 = &self.fingerprint_cache[fp1];
-                let structures2 = &self.fingerprint_cache[fp2];
-
-                // 同じグループ内または異なるグループ間で比較
-                for s1 in structures1 {
-                    let start_idx = if i == j {
-                        // 同じグループ内の場合、自己比較を避ける
-                        structures2
-                            .iter()
-                            .position(|s| std::ptr::eq(s, s1))
-                            .map(|pos| pos + 1)
-                            .unwrap_or(0)
-                    } else {
-                        0
-                    };
-
-                    for s2 in &structures2[start_idx..] {
-                        let result = self.comparator.compare_any(s1.clone(), s2.clone());
-
-                        if result.overall_similarity >= threshold {
-                            results.push((s1.clone(), s2.clone(), result.overall_similarity));
-                        }
-                    }
-                }
+        for (i, j) in self.buckets.candidate_pairs() {
+            let s1 = &self.buckets.structures()[i];
+            let s2 = &self.buckets.structures()[j];
+            let result = self.comparator.comparator.compare(s1, s2);
+
+            if result.overall_similarity >= threshold {
+                results.push((s1.clone(), s2.clone(), result.overall_similarity));
             }
         }
 
-        // 類似度でソート
+        // 類似度でソート（同率の場合は登録順）
         results.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
         results
     }
@@ -323,18 +302,18 @@ mod tests {
     #[test]
     fn test_type_to_structure_conversion() {
         let type_def = TypeDefinition {
-            name: "User".to_string(),
+            name: "User".into(),
             kind: TypeKind::Interface,
             properties: vec![
                 PropertyDefinition {
                     name: "id".to_string(),
-                    type_annotation: "string".to_string(),
+                    type_annotation: "string".into(),
                     optional: false,
                     readonly: true,
                 },
                 PropertyDefinition {
                     name: "name".to_string(),
-                    type_annotation: "string".to_string(),
+                    type_annotation: "string".into(),
                     optional: false,
                     readonly: false,
                 },
@@ -343,8 +322,9 @@ mod tests {
             extends: vec![],
             start_line: 1,
             end_line: 5,
-            file_path: "user.ts".to_string(),
+            file_path: "user.ts".into(),
             has_ignore_directive: false,
+            partial: false,
         };
 
         let structure = Structure::from(type_def);
@@ -360,18 +340,18 @@ mod tests {
         let mut comparator = TypeScriptStructureComparator::new();
 
         let type1 = TypeDefinition {
-            name: "User".to_string(),
+            name: "User".into(),
             kind: TypeKind::Interface,
             properties: vec![
                 PropertyDefinition {
                     name: "id".to_string(),
-                    type_annotation: "string".to_string(),
+                    type_annotation: "string".into(),
                     optional: false,
                     readonly: false,
                 },
                 PropertyDefinition {
                     name: "name".to_string(),
-                    type_annotation: "string".to_string(),
+                    type_annotation: "string".into(),
                     optional: false,
                     readonly: false,
                 },
@@ -380,23 +360,24 @@ mod tests {
             extends: vec![],
             start_line: 1,
             end_line: 5,
-            file_path: "user.ts".to_string(),
+            file_path: "user.ts".into(),
             has_ignore_directive: false,
+            partial: false,
         };
 
         let type2 = TypeDefinition {
-            name: "Person".to_string(),
+            name: "Person".into(),
             kind: TypeKind::Interface,
             properties: vec![
                 PropertyDefinition {
                     name: "id".to_string(),
-                    type_annotation: "string".to_string(),
+                    type_annotation: "string".into(),
                     optional: false,
                     readonly: false,
                 },
                 PropertyDefinition {
                     name: "name".to_string(),
-                    type_annotation: "string".to_string(),
+                    type_annotation: "string".into(),
                     optional: false,
                     readonly: false,
                 },
@@ -405,8 +386,9 @@ mod tests {
             extends: vec![],
             start_line: 10,
             end_line: 15,
-            file_path: "person.ts".to_string(),
+            file_path: "person.ts".into(),
             has_ignore_directive: false,
+            partial: false,
         };
 
         let result = comparator.compare_types(&type1, &type2);
@@ -1,3 +1,4 @@
+use crate::comparison_budget::ComparisonBudget;
 use crate::structure_comparator::{ComparisonOptions, Structure};
 use crate::type_comparator::{
     compare_type_literal_with_type, compare_types, TypeComparisonOptions, TypeComparisonResult,
@@ -86,15 +87,16 @@ fn compare_unified_types(
 /// Convert type literal to type definition for comparison
 fn type_literal_to_type_def(literal: &TypeLiteralDefinition) -> TypeDefinition {
     TypeDefinition {
-        name: literal.name.clone(),
+        name: literal.name,
         kind: TypeKind::TypeLiteral,
         properties: literal.properties.clone(),
         generics: Vec::new(),
         extends: Vec::new(),
         start_line: literal.start_line,
         end_line: literal.end_line,
-        file_path: literal.file_path.clone(),
+        file_path: literal.file_path,
         has_ignore_directive: false,
+        partial: literal.partial,
     }
 }
 
@@ -132,6 +134,23 @@ pub fn find_similar_unified_types(
     type_literals: &[TypeLiteralDefinition],
     threshold: f64,
     options: &TypeComparisonOptions,
+) -> Vec<UnifiedTypeComparisonPair> {
+    find_similar_unified_types_with_budget(
+        type_definitions,
+        type_literals,
+        threshold,
+        options,
+        &ComparisonBudget::unlimited(),
+    )
+}
+
+/// Find all similar types (unified comparison), stopping when the budget runs out
+pub fn find_similar_unified_types_with_budget(
+    type_definitions: &[TypeDefinition],
+    type_literals: &[TypeLiteralDefinition],
+    threshold: f64,
+    options: &TypeComparisonOptions,
+    budget: &ComparisonBudget,
 ) -> Vec<UnifiedTypeComparisonPair> {
     // Combine all types into unified list
     let mut all_types = Vec::new();
@@ -152,7 +171,7 @@ pub fn find_similar_unified_types(
             let type1 = &all_types[i];
             let type2 = &all_types[j];
 
-            if !should_compare(type1, type2) {
+            if !should_compare(type1, type2) || !budget.try_compare() {
                 continue;
             }
 
@@ -180,6 +199,23 @@ pub fn find_similar_unified_types_structured(
     type_literals: &[TypeLiteralDefinition],
     threshold: f64,
     options: Option<ComparisonOptions>,
+) -> Vec<UnifiedTypeComparisonPair> {
+    find_similar_unified_types_structured_with_budget(
+        type_definitions,
+        type_literals,
+        threshold,
+        options,
+        &ComparisonBudget::unlimited(),
+    )
+}
+
+/// Structured unified comparison, stopping when the budget runs out
+pub fn find_similar_unified_types_structured_with_budget(
+    type_definitions: &[TypeDefinition],
+    type_literals: &[TypeLiteralDefinition],
+    threshold: f64,
+    options: Option<ComparisonOptions>,
+    budget: &ComparisonBudget,
 ) -> Vec<UnifiedTypeComparisonPair> {
     let mut comparator = if let Some(opts) = options {
         TypeScriptStructureComparator::with_options(opts)
@@ -210,7 +246,7 @@ pub fn find_similar_unified_types_structured(
             let (unified1, struct1) = &all_structures[i];
             let (unified2, struct2) = &all_structures[j];
 
-            if !should_compare(unified1, unified2) {
+            if !should_compare(unified1, unified2) || !budget.try_compare() {
                 continue;
             }
 
@@ -0,0 +1,23 @@
+//! Scores of the bundled clone benchmark (`tests/fixtures/clone_benchmark`).
+//!
+//! Run with `--nocapture` to see the precision/recall table. A change to the
+//! algorithm (rename cost, size penalty, tree comparison) that lowers these
+//! baselines should be a conscious decision.
+
+use similarity_core::{evaluate, CloneType, Dataset, TSEDOptions};
+
+#[test]
+fn test_clone_benchmark_baseline() {
+    let dataset =
+        Dataset::load(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/clone_benchmark"))
+            .unwrap();
+    // The fragments are short, which the size penalty is designed to discount
+    let options = TSEDOptions { size_penalty: false, ..Default::default() };
+    let report = evaluate(&dataset, 0.87, &options).unwrap();
+    println!("{}", report);
+
+    assert_eq!(report.stats(CloneType::Type1).recall(), Some(1.0));
+    assert_eq!(report.stats(CloneType::Type2).recall(), Some(1.0));
+    assert_eq!(report.stats(CloneType::None).detected, 0);
+    assert_eq!(report.precision(None), Some(1.0));
+}
@@ -0,0 +1,7 @@
+export async function fetchUser(id: string): Promise<User> {
+  const response = await fetch(`/api/users/${id}`);
+  if (!response.ok) {
+    throw new Error("Failed to load user");
+  }
+  return response.json();
+}
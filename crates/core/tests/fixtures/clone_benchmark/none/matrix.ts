@@ -0,0 +1,9 @@
+export function transpose(matrix: number[][]): number[][] {
+  const rows = matrix.length;
+  const columns = rows === 0 ? 0 : matrix[0].length;
+  const result: number[][] = [];
+  for (let c = 0; c < columns; c++) {
+    result.push(matrix.map((row) => row[c]));
+  }
+  return result;
+}
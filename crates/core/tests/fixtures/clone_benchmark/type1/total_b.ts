@@ -0,0 +1,9 @@
+// Sum of all line items
+export function calculateTotal(items: Item[]): number {
+    let total = 0;
+
+    for (const item of items) {
+        total += item.price * item.quantity; // price per unit
+    }
+    return total;
+}
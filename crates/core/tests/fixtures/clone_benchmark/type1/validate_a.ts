@@ -0,0 +1,7 @@
+export function validateEmail(email: string): boolean {
+  if (!email) {
+    return false;
+  }
+  const at = email.indexOf("@");
+  return at > 0 && at < email.length - 1;
+}
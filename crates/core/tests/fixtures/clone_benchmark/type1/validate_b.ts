@@ -0,0 +1,6 @@
+export function validateEmail(email: string): boolean {
+  if (!email) return false;
+  const at = email.indexOf("@");
+  /* the domain must not be empty */
+  return at > 0 && at < email.length - 1;
+}
@@ -0,0 +1,7 @@
+export async function loadProject(key: string): Promise<Project> {
+  const res = await fetch(`/v2/projects/${key}`);
+  if (!res.ok) {
+    throw new Error("Project request failed");
+  }
+  return res.json();
+}
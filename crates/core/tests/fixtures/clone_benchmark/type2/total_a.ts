@@ -0,0 +1,7 @@
+export function calculateTotal(items: Item[]): number {
+  let total = 0;
+  for (const item of items) {
+    total += item.price * item.quantity;
+  }
+  return total;
+}
@@ -0,0 +1,7 @@
+export function sumOrder(lines: OrderLine[]): number {
+  let sum = 0;
+  for (const line of lines) {
+    sum += line.cost * line.count;
+  }
+  return sum;
+}
@@ -0,0 +1,11 @@
+export async function fetchUser(id: string, token: string): Promise<User> {
+  const response = await fetch(`/api/users/${id}`, {
+    headers: { Authorization: `Bearer ${token}` },
+  });
+  if (!response.ok) {
+    console.error(response.status);
+    throw new Error("Failed to load user");
+  }
+  const user = await response.json();
+  return user;
+}
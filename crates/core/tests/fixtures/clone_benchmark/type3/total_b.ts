@@ -0,0 +1,10 @@
+export function calculateTotal(items: Item[], discount: number): number {
+  let total = 0;
+  for (const item of items) {
+    if (item.quantity <= 0) {
+      continue;
+    }
+    total += item.price * item.quantity;
+  }
+  return total * (1 - discount);
+}
@@ -0,0 +1,3 @@
+export function calculateTotal(items: Item[]): number {
+  return items.reduce((sum, { price, quantity }) => sum + price * quantity, 0);
+}
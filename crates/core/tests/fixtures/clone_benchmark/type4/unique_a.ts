@@ -0,0 +1,9 @@
+export function unique<T>(values: T[]): T[] {
+  const result: T[] = [];
+  for (let i = 0; i < values.length; i++) {
+    if (result.indexOf(values[i]) === -1) {
+      result.push(values[i]);
+    }
+  }
+  return result;
+}
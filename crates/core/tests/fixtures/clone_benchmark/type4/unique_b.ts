@@ -0,0 +1,3 @@
+export function unique<T>(values: T[]): T[] {
+  return [...new Set(values)];
+}
@@ -412,3 +412,200 @@ const processDataArrow = (data: number[]): number => {
         assert!(pair.similarity < 0.9, "Different functions should not have very high similarity");
     }
 }
+
+#[test]
+fn test_canonicalize_order_finds_reordered_clones() {
+    let code = r#"
+export function quote(price: number, quantity: number): number {
+    const subtotal = price * quantity;
+    const label = "quote";
+    const shipping = 5;
+    const discount = price > 100;
+    const tax = subtotal * 0.1;
+    return subtotal + tax + shipping;
+}
+
+export function estimate(cost: number, count: number): number {
+    const freight = 5;
+    const reduced = cost > 100;
+    const base = cost * count;
+    const name = "quote";
+    const duty = base * 0.1;
+    return base + duty + freight;
+}
+"#;
+
+    let similarity = |canonicalize_order: bool| {
+        let options = TSEDOptions {
+            size_penalty: false,
+            min_lines: 3,
+            canonicalize_order,
+            ..Default::default()
+        };
+        let result = find_similar_functions_in_file("test.ts", code, 0.0, &options).unwrap();
+        assert_eq!(result.len(), 1);
+        result[0].similarity
+    };
+
+    let (plain, canonical) = (similarity(false), similarity(true));
+    assert!(canonical > plain, "canonical {canonical} should exceed plain {plain}");
+    assert!(canonical > 0.85, "reordered clones should be found (got {canonical})");
+    assert!(plain < 0.85, "without canonicalization the clone is missed (got {plain})");
+}
+
+#[test]
+fn test_normalize_async_matches_then_chains_and_callbacks() {
+    let code = r#"
+export async function loadUser(id: string) {
+    const response = await fetch(id);
+    const body = await parse(response);
+    if (body) {
+        store(body);
+    }
+    return body;
+}
+
+export function loadUserThen(key: string) {
+    return fetch(key)
+        .then((res) => parse(res))
+        .then((data) => {
+            if (data) {
+                store(data);
+            }
+            return data;
+        });
+}
+
+export function loadUserCallback(key: string) {
+    fetchWith(key, (err, res) => {
+        const data = parse(res);
+        if (data) {
+            store(data);
+        }
+        return data;
+    });
+}
+"#;
+
+    let similarity = |normalize_async: bool, other: &str| {
+        let options = TSEDOptions {
+            size_penalty: false,
+            min_lines: 3,
+            normalize_async,
+            ..Default::default()
+        };
+        let result = find_similar_functions_in_file("test.ts", code, 0.0, &options).unwrap();
+        result
+            .iter()
+            .find(|r| r.func1.name == "loadUser" && r.func2.name == other)
+            .map_or(0.0, |r| r.similarity)
+    };
+
+    for other in ["loadUserThen", "loadUserCallback"] {
+        let (plain, normalized) = (similarity(false, other), similarity(true, other));
+        assert!(normalized > plain, "{other}: normalized {normalized} should exceed {plain}");
+        assert!(normalized > 0.85, "{other}: async styles should match (got {normalized})");
+    }
+}
+
+#[test]
+fn test_normalize_collections_matches_loops_and_array_methods() {
+    let code = r#"
+export function totalPrice(items: Item[]): number {
+    return items.reduce((sum, item) => sum + item.price * item.count, 0);
+}
+
+export function totalPriceLoop(entries: Item[]): number {
+    let total = 0;
+    for (const entry of entries) {
+        total += entry.price * entry.count;
+    }
+    return total;
+}
+
+export function activeNames(users: User[]): string[] {
+    const active = users.filter((user) => user.active && user.verified);
+    return active.map((user) => user.name.trim());
+}
+
+export function activeNamesLoop(people: User[]): string[] {
+    const active = [];
+    people.forEach((person) => {
+        if (person.active && person.verified) {
+            active.push(person);
+        }
+    });
+    const names = [];
+    for (const person of active) {
+        names.push(person.name.trim());
+    }
+    return names;
+}
+"#;
+
+    let similarity = |normalize_collections: bool, first: &str, second: &str| {
+        let options = TSEDOptions {
+            size_penalty: false,
+            min_lines: 3,
+            normalize_collections,
+            ..Default::default()
+        };
+        let result = find_similar_functions_in_file("test.ts", code, 0.0, &options).unwrap();
+        result
+            .iter()
+            .find(|r| r.func1.name == first && r.func2.name == second)
+            .map_or(0.0, |r| r.similarity)
+    };
+
+    for (first, second) in [("totalPrice", "totalPriceLoop"), ("activeNames", "activeNamesLoop")] {
+        let plain = similarity(false, first, second);
+        let normalized = similarity(true, first, second);
+        assert!(normalized > plain, "{second}: normalized {normalized} should exceed {plain}");
+        assert!(normalized > 0.85, "{second}: collection styles should match (got {normalized})");
+    }
+}
+
+#[test]
+fn test_strip_types_matches_typescript_to_its_javascript() {
+    let js = r#"
+export function parseConfig(raw) {
+    const data = JSON.parse(raw);
+    const port = data.port;
+    if (!port) {
+        throw new Error("missing port");
+    }
+    return { host: data.host, port };
+}
+"#;
+    let ts = r#"
+interface Config {
+    host: string;
+    port: number;
+}
+
+export function parseConfig(raw: string): Config {
+    type Raw = Partial<Config>;
+    const data = JSON.parse(raw) as Raw;
+    const port = data.port!;
+    if (!port) {
+        throw new Error("missing port");
+    }
+    return { host: data.host, port } satisfies Config;
+}
+"#;
+
+    let similarity = |strip_types: bool| {
+        let options =
+            TSEDOptions { size_penalty: false, min_lines: 3, strip_types, ..Default::default() };
+        let files = vec![
+            ("config.js".to_string(), js.to_string()),
+            ("config.ts".to_string(), ts.to_string()),
+        ];
+        let result = find_similar_functions_across_files(&files, 0.0, &options).unwrap();
+        result.iter().map(|(_, r, _)| r.similarity).fold(0.0, f64::max)
+    };
+
+    let (plain, stripped) = (similarity(false), similarity(true));
+    assert!(stripped > plain, "stripped {stripped} should exceed {plain}");
+    assert_eq!(stripped, 1.0);
+}
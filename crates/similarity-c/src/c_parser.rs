@@ -0,0 +1,317 @@
+use crate::preprocessor::Preprocessor;
+use similarity_core::generic_parser_config::GenericParserConfig;
+use similarity_core::generic_tree_sitter_parser::GenericTreeSitterParser;
+use similarity_core::language_parser::{
+    GenericFunctionDef, GenericTypeDef, Language, LanguageParser,
+};
+use similarity_core::structure_comparator::{
+    SourceLocation, Structure, StructureIdentifier, StructureKind, StructureMember,
+    StructureMetadata,
+};
+use similarity_core::tree::TreeNode;
+use std::error::Error;
+use std::path::Path;
+use std::rc::Rc;
+use tree_sitter::{Node, Parser};
+
+/// Which grammar a file is parsed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CDialect {
+    C,
+    Cpp,
+}
+
+impl CDialect {
+    /// `.c` and `.h` files are parsed as C, everything else as C++
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("c" | "h") => CDialect::C,
+            _ => CDialect::Cpp,
+        }
+    }
+
+    fn language(self) -> tree_sitter::Language {
+        match self {
+            CDialect::C => tree_sitter_c::LANGUAGE.into(),
+            CDialect::Cpp => tree_sitter_cpp::LANGUAGE.into(),
+        }
+    }
+}
+
+/// C/C++ parser that runs the preprocessor pass before handing the source to tree-sitter
+pub struct CParser {
+    dialect: CDialect,
+    functions: GenericTreeSitterParser,
+    parser: Parser,
+    preprocessor: Preprocessor,
+}
+
+impl CParser {
+    pub fn new(
+        dialect: CDialect,
+        preprocessor: Preprocessor,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let config = match dialect {
+            CDialect::C => GenericParserConfig::c(),
+            CDialect::Cpp => GenericParserConfig::cpp(),
+        };
+        let functions = GenericTreeSitterParser::new(dialect.language(), config)?;
+
+        let mut parser = Parser::new();
+        parser
+            .set_language(&dialect.language())
+            .map_err(|e| format!("Failed to set C/C++ language: {e:?}"))?;
+
+        Ok(Self { dialect, functions, parser, preprocessor })
+    }
+
+    /// Apply the configured preprocessor pass; line numbers are preserved
+    pub fn preprocess(&self, source: &str) -> String {
+        self.preprocessor.process(source)
+    }
+
+    /// Extract structs, unions and classes with their fields
+    pub fn extract_structures(
+        &mut self,
+        source: &str,
+        filename: &str,
+    ) -> Result<Vec<Structure>, Box<dyn Error + Send + Sync>> {
+        let source = self.preprocess(source);
+        let tree = self.parser.parse(&source, None).ok_or("Failed to parse source")?;
+
+        let mut structures = Vec::new();
+        collect_structures(tree.root_node(), &source, filename, &mut structures);
+        Ok(structures)
+    }
+}
+
+fn collect_structures(node: Node, source: &str, filename: &str, structures: &mut Vec<Structure>) {
+    if matches!(node.kind(), "struct_specifier" | "union_specifier" | "class_specifier") {
+        if let Some(structure) = to_structure(node, source, filename) {
+            structures.push(structure);
+        }
+    }
+
+    for child in node.children(&mut node.walk()) {
+        collect_structures(child, source, filename, structures);
+    }
+}
+
+fn text<'a>(node: Node, source: &'a str) -> &'a str {
+    node.utf8_text(source.as_bytes()).unwrap_or("")
+}
+
+fn to_structure(node: Node, source: &str, filename: &str) -> Option<Structure> {
+    // Only definitions have a body; `struct point p;` is just a use
+    let body = node.child_by_field_name("body")?;
+
+    // Anonymous `typedef struct { ... } name;` takes the typedef name
+    let name = match node.child_by_field_name("name") {
+        Some(name) => text(name, source).to_string(),
+        None => node
+            .parent()
+            .filter(|parent| parent.kind() == "type_definition")
+            .and_then(|parent| parent.child_by_field_name("declarator"))
+            .map(|declarator| text(declarator, source).to_string())?,
+    };
+
+    let mut members = Vec::new();
+    let mut visibility: Option<String> = None;
+    for child in body.named_children(&mut body.walk()) {
+        match child.kind() {
+            "access_specifier" => visibility = Some(text(child, source).to_string()),
+            "field_declaration" => {
+                let Some(field_type_node) = child.child_by_field_name("type") else { continue };
+                // `const`/`volatile` are siblings of the type node
+                let mut field_type: Vec<&str> = child
+                    .named_children(&mut child.walk())
+                    .filter(|n| n.kind() == "type_qualifier")
+                    .map(|n| text(n, source))
+                    .collect();
+                field_type.extend(text(field_type_node, source).split_whitespace());
+                let mut cursor = child.walk();
+                for declarator in child.children_by_field_name("declarator", &mut cursor) {
+                    // Methods declared in the class body are not data members
+                    if declarator.kind() == "function_declarator" {
+                        continue;
+                    }
+                    let (name, suffix) = split_declarator(declarator, source);
+                    members.push(StructureMember {
+                        name,
+                        value_type: format!("{}{}", field_type.join(" "), suffix).into(),
+                        modifiers: visibility.iter().cloned().collect(),
+                        nested: None,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let kind = node.kind().trim_end_matches("_specifier");
+    let extends = node
+        .named_children(&mut node.walk())
+        .filter(|child| child.kind() == "base_class_clause")
+        .flat_map(|clause| {
+            let mut cursor = clause.walk();
+            clause
+                .named_children(&mut cursor)
+                .filter(|base| base.kind() != "access_specifier")
+                .map(|base| text(base, source).to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    Some(Structure {
+        identifier: StructureIdentifier {
+            name: name.into(),
+            kind: StructureKind::Generic(kind.to_string()),
+            namespace: None,
+        },
+        members,
+        metadata: StructureMetadata {
+            location: SourceLocation {
+                file_path: filename.into(),
+                start_line: node.start_position().row + 1,
+                end_line: node.end_position().row + 1,
+            },
+            extends,
+            ..Default::default()
+        },
+    })
+}
+
+/// Split `*name[16]` into the field name and the pointer/array part of its type
+fn split_declarator(declarator: Node, source: &str) -> (String, String) {
+    let mut suffix = String::new();
+    let mut current = declarator;
+    loop {
+        match current.kind() {
+            "pointer_declarator" => suffix.push('*'),
+            "reference_declarator" => suffix.push('&'),
+            "array_declarator" => suffix.push_str("[]"),
+            _ => return (text(current, source).to_string(), suffix),
+        }
+        match current.child_by_field_name("declarator").or_else(|| current.named_child(0)) {
+            Some(inner) => current = inner,
+            None => return (text(current, source).to_string(), suffix),
+        }
+    }
+}
+
+impl LanguageParser for CParser {
+    fn parse(
+        &mut self,
+        source: &str,
+        filename: &str,
+    ) -> Result<Rc<TreeNode>, Box<dyn Error + Send + Sync>> {
+        let source = self.preprocess(source);
+        self.functions.parse(&source, filename)
+    }
+
+    fn extract_functions(
+        &mut self,
+        source: &str,
+        filename: &str,
+    ) -> Result<Vec<GenericFunctionDef>, Box<dyn Error + Send + Sync>> {
+        let source = self.preprocess(source);
+        self.functions.extract_functions(&source, filename)
+    }
+
+    fn extract_types(
+        &mut self,
+        source: &str,
+        filename: &str,
+    ) -> Result<Vec<GenericTypeDef>, Box<dyn Error + Send + Sync>> {
+        Ok(self
+            .extract_structures(source, filename)?
+            .into_iter()
+            .map(|structure| GenericTypeDef {
+                name: structure.identifier.name.to_string(),
+                kind: match structure.identifier.kind {
+                    StructureKind::Generic(kind) => kind,
+                    _ => "struct".to_string(),
+                },
+                start_line: structure.metadata.location.start_line as u32,
+                end_line: structure.metadata.location.end_line as u32,
+                fields: structure.members.into_iter().map(|member| member.name).collect(),
+            })
+            .collect())
+    }
+
+    fn language(&self) -> Language {
+        match self.dialect {
+            CDialect::C => Language::C,
+            CDialect::Cpp => Language::Cpp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessor::PreprocessMode;
+
+    #[test]
+    fn extracts_structs_and_classes_with_fields() {
+        let mut parser = CParser::new(CDialect::Cpp, Preprocessor::default()).unwrap();
+        let source = r#"
+typedef struct {
+    const char *name;
+    int ports[4];
+} service_t;
+
+class Connection : public Base {
+public:
+    std::string host;
+    int port;
+    void open();
+private:
+    bool secure;
+};
+"#;
+        let structures = parser.extract_structures(source, "net.hpp").unwrap();
+        assert_eq!(structures.len(), 2);
+
+        let service = &structures[0];
+        assert_eq!(service.identifier.name, "service_t");
+        let fields: Vec<_> =
+            service.members.iter().map(|m| (m.name.as_str(), m.value_type.as_str())).collect();
+        assert_eq!(fields, vec![("name", "const char*"), ("ports", "int[]")]);
+
+        let connection = &structures[1];
+        assert_eq!(connection.identifier.name, "Connection");
+        assert_eq!(connection.metadata.extends, vec!["Base"]);
+        let fields: Vec<_> = connection.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(fields, vec!["host", "port", "secure"]);
+        assert_eq!(connection.members[2].modifiers, vec!["private"]);
+    }
+
+    #[test]
+    fn preprocessor_pass_applies_before_parsing() {
+        let source = r#"
+int checksum(const char *buf, int len) {
+    int sum = 0;
+#ifdef USE_SIMD
+    sum = simd_sum(buf, len);
+#else
+    for (int i = 0; i < len; i++) {
+        sum += buf[i];
+    }
+#endif
+    return sum;
+}
+"#;
+        let mut plain = CParser::new(CDialect::C, Preprocessor::default()).unwrap();
+        let mut stripped =
+            CParser::new(CDialect::C, Preprocessor::new(PreprocessMode::Strip)).unwrap();
+
+        let functions = stripped.extract_functions(source, "sum.c").unwrap();
+        assert_eq!(functions[0].name, "checksum");
+        assert_eq!((functions[0].start_line, functions[0].end_line), (2, 12));
+
+        let full = plain.parse(source, "sum.c").unwrap();
+        let reduced = stripped.parse(source, "sum.c").unwrap();
+        assert!(reduced.get_subtree_size() < full.get_subtree_size());
+    }
+}
@@ -0,0 +1,198 @@
+#![allow(clippy::uninlined_format_args)]
+
+use crate::c_parser::{CDialect, CParser};
+use crate::preprocessor::Preprocessor;
+use similarity_core::{
+    cli_file_utils::collect_files,
+    cli_output::{format_function_output, show_function_code},
+    language_parser::{GenericFunctionDef, LanguageParser},
+    tree::TreeNode,
+    tsed::calculate_tsed,
+    TSEDOptions,
+};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+pub const DEFAULT_EXTENSIONS: &[&str] = &["c", "h", "cpp", "cc", "cxx", "hpp", "hxx"];
+
+/// A function together with the tree of its (preprocessed) body
+struct ParsedFunction {
+    file: PathBuf,
+    function: GenericFunctionDef,
+    tree: Rc<TreeNode>,
+}
+
+struct DuplicateResult<'a> {
+    func1: &'a ParsedFunction,
+    func2: &'a ParsedFunction,
+    similarity: f64,
+}
+
+impl DuplicateResult<'_> {
+    fn priority(&self) -> f64 {
+        // Score = Similarity × Average lines
+        let lines = |f: &GenericFunctionDef| (f.end_line - f.start_line + 1) as f64;
+        self.similarity * (lines(&self.func1.function) + lines(&self.func2.function)) / 2.0
+    }
+}
+
+/// Parse every C/C++ file and keep functions that meet the minimum size
+fn parse_functions(
+    files: &[PathBuf],
+    preprocessor: &Preprocessor,
+    options: &TSEDOptions,
+) -> Vec<ParsedFunction> {
+    let mut parsed = Vec::new();
+
+    for file in files {
+        let content = match std::fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let mut parser = match CParser::new(CDialect::from_path(file), preprocessor.clone()) {
+            Ok(parser) => parser,
+            Err(e) => {
+                eprintln!("Error creating parser for {}: {}", file.display(), e);
+                continue;
+            }
+        };
+
+        // Bodies are taken from the preprocessed source so that inactive branches are gone
+        let filename = file.to_string_lossy();
+        let code = parser.preprocess(&content);
+        let Ok(functions) = parser.extract_functions(&code, &filename) else {
+            continue;
+        };
+        let lines: Vec<&str> = code.lines().collect();
+
+        for function in functions {
+            if function.end_line - function.start_line + 1 < options.min_lines {
+                continue;
+            }
+            let start = function.body_start_line.saturating_sub(1) as usize;
+            let end = (function.body_end_line as usize).min(lines.len());
+            if start >= end {
+                continue;
+            }
+            let body = lines[start..end].join("\n");
+            if let Ok(tree) = parser.parse(&body, &filename) {
+                parsed.push(ParsedFunction { file: file.clone(), function, tree });
+            }
+        }
+    }
+
+    parsed
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn check_paths(
+    paths: Vec<String>,
+    threshold: f64,
+    rename_cost: f64,
+    extensions: Option<&Vec<String>>,
+    min_lines: u32,
+    min_tokens: Option<u32>,
+    no_size_penalty: bool,
+    print: bool,
+    preprocessor: &Preprocessor,
+    filter_function: Option<&String>,
+    include_comments: bool,
+) -> anyhow::Result<usize> {
+    let exts: Vec<&str> =
+        extensions.map_or(DEFAULT_EXTENSIONS.to_vec(), |v| v.iter().map(String::as_str).collect());
+
+    let files = collect_files(&paths, &exts)?;
+
+    if files.is_empty() {
+        println!("No C/C++ files found in the specified paths.");
+        return Ok(0);
+    }
+
+    println!("Checking {} files for duplicates...", files.len());
+
+    let mut options = TSEDOptions::default();
+    options.apted_options.rename_cost = rename_cost;
+    options.min_lines = min_lines;
+    options.min_tokens = min_tokens;
+    options.size_penalty = !no_size_penalty;
+    options.include_comments = include_comments;
+
+    let functions = parse_functions(&files, preprocessor, &options);
+
+    // Compare all pairs, within and across files
+    let mut all_results = Vec::new();
+    for i in 0..functions.len() {
+        for j in (i + 1)..functions.len() {
+            let (func1, func2) = (&functions[i], &functions[j]);
+            if let Some(filter) = filter_function {
+                if !func1.function.name.contains(filter.as_str())
+                    && !func2.function.name.contains(filter.as_str())
+                {
+                    continue;
+                }
+            }
+
+            let similarity = calculate_tsed(&func1.tree, &func2.tree, &options);
+            if similarity >= threshold {
+                all_results.push(DuplicateResult { func1, func2, similarity });
+            }
+        }
+    }
+
+    Ok(display_all_results(all_results, print))
+}
+
+/// Display similarity results grouped by the file of the first function
+fn display_all_results(mut all_results: Vec<DuplicateResult>, print: bool) -> usize {
+    if all_results.is_empty() {
+        println!("\nNo duplicate functions found!");
+        return 0;
+    }
+
+    // Sort by priority (higher similarity × larger functions first)
+    all_results.sort_by(|a, b| b.priority().total_cmp(&a.priority()));
+
+    let mut file_groups: BTreeMap<String, Vec<DuplicateResult>> = BTreeMap::new();
+    for dup in all_results {
+        file_groups.entry(dup.func1.file.to_string_lossy().to_string()).or_default().push(dup);
+    }
+
+    let mut total_count = 0;
+    for (file_path, duplicates) in file_groups {
+        println!("\nDuplicates in {}:", file_path);
+        println!("{}", "-".repeat(60));
+
+        for dup in &duplicates {
+            let (file1, func1) = (&file_path, &dup.func1.function);
+            let file2 = dup.func2.file.to_string_lossy();
+            let func2 = &dup.func2.function;
+
+            println!(
+                "  {} <-> {}",
+                format_function_output(file1, &func1.name, func1.start_line, func1.end_line),
+                format_function_output(&file2, &func2.name, func2.start_line, func2.end_line)
+            );
+            println!("  Similarity: {:.2}%", dup.similarity * 100.0);
+
+            if let (Some(class1), Some(class2)) = (&func1.class_name, &func2.class_name) {
+                println!("  Classes: {} <-> {}", class1, class2);
+            }
+
+            if print {
+                show_function_code(file1, &func1.name, func1.start_line, func1.end_line);
+                show_function_code(&file2, &func2.name, func2.start_line, func2.end_line);
+                println!();
+            }
+
+            total_count += 1;
+        }
+    }
+
+    println!("\nTotal duplicate pairs found: {}", total_count);
+
+    total_count
+}
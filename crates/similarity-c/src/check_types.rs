@@ -0,0 +1,106 @@
+#![allow(clippy::uninlined_format_args)]
+
+use crate::c_parser::{CDialect, CParser};
+use crate::check::DEFAULT_EXTENSIONS;
+use crate::preprocessor::Preprocessor;
+use similarity_core::{
+    cli_file_utils::collect_files,
+    cli_output::{format_function_output, show_function_code},
+    ComparisonOptions, Structure, StructureComparator, StructureKind,
+};
+
+/// Types with fewer fields carry too little structure to compare
+const MIN_FIELDS: usize = 2;
+
+fn type_label(structure: &Structure) -> String {
+    let kind = match &structure.identifier.kind {
+        StructureKind::Generic(kind) => kind.as_str(),
+        _ => "struct",
+    };
+    format!("{} {}", kind, structure.identifier.name)
+}
+
+/// Check for similar structs, unions and classes across files
+pub fn check_types(
+    paths: Vec<String>,
+    threshold: f64,
+    extensions: Option<&Vec<String>>,
+    print: bool,
+    preprocessor: &Preprocessor,
+) -> anyhow::Result<usize> {
+    let exts: Vec<&str> =
+        extensions.map_or(DEFAULT_EXTENSIONS.to_vec(), |v| v.iter().map(String::as_str).collect());
+
+    let files = collect_files(&paths, &exts)?;
+
+    let mut structures = Vec::new();
+    for file in &files {
+        let Ok(content) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let Ok(mut parser) = CParser::new(CDialect::from_path(file), preprocessor.clone()) else {
+            continue;
+        };
+        if let Ok(found) = parser.extract_structures(&content, &file.to_string_lossy()) {
+            structures.extend(found.into_iter().filter(|s| s.members.len() >= MIN_FIELDS));
+        }
+    }
+
+    println!("Found {} types in {} files", structures.len(), files.len());
+
+    let mut comparator = StructureComparator::new(ComparisonOptions::default());
+    let mut similar_pairs = Vec::new();
+    for i in 0..structures.len() {
+        for j in (i + 1)..structures.len() {
+            let result = comparator.compare(&structures[i], &structures[j]);
+            if result.overall_similarity >= threshold {
+                similar_pairs.push((&structures[i], &structures[j], result.overall_similarity));
+            }
+        }
+    }
+
+    if similar_pairs.is_empty() {
+        println!("\nNo similar types found!");
+        return Ok(0);
+    }
+
+    similar_pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    println!("\nSimilar types found:");
+    println!("{}", "-".repeat(60));
+    for (type1, type2, similarity) in &similar_pairs {
+        let (loc1, loc2) = (&type1.metadata.location, &type2.metadata.location);
+        println!(
+            "\n  {} <-> {}",
+            format_function_output(
+                &loc1.file_path,
+                &type_label(type1),
+                loc1.start_line as u32,
+                loc1.end_line as u32
+            ),
+            format_function_output(
+                &loc2.file_path,
+                &type_label(type2),
+                loc2.start_line as u32,
+                loc2.end_line as u32
+            )
+        );
+        println!("  Similarity: {:.2}%", similarity * 100.0);
+
+        if print {
+            for (structure, location) in [(type1, loc1), (type2, loc2)] {
+                show_function_code(
+                    &location.file_path,
+                    &structure.identifier.name,
+                    location.start_line as u32,
+                    location.end_line as u32,
+                );
+            }
+            println!();
+        }
+    }
+
+    println!("\nTotal similar type pairs found: {}", similar_pairs.len());
+
+    Ok(similar_pairs.len())
+}
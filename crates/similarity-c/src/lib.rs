@@ -0,0 +1,5 @@
+pub mod c_parser;
+pub mod preprocessor;
+
+pub use c_parser::{CDialect, CParser};
+pub use preprocessor::{PreprocessMode, Preprocessor};
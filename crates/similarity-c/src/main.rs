@@ -0,0 +1,124 @@
+use anyhow::Result;
+use clap::Parser;
+
+mod c_parser;
+mod check;
+mod check_types;
+mod preprocessor;
+
+use preprocessor::{PreprocessMode, Preprocessor};
+
+#[derive(Parser)]
+#[command(name = "similarity-c")]
+#[command(about = "C/C++ code similarity analyzer")]
+#[command(version)]
+struct Cli {
+    /// Paths to analyze (files or directories)
+    #[arg(default_value = ".")]
+    paths: Vec<String>,
+
+    /// Print code in output
+    #[arg(short, long)]
+    print: bool,
+
+    /// Similarity threshold (0.0-1.0)
+    #[arg(short, long, default_value = "0.85")]
+    threshold: f64,
+
+    /// File extensions to check
+    #[arg(short, long, value_delimiter = ',')]
+    extensions: Option<Vec<String>>,
+
+    /// Minimum lines for functions to be considered
+    #[arg(short, long, default_value = "3")]
+    min_lines: u32,
+
+    /// Minimum tokens for functions to be considered
+    #[arg(long)]
+    min_tokens: Option<u32>,
+
+    /// Rename cost for APTED algorithm
+    #[arg(short, long, default_value = "0.3")]
+    rename_cost: f64,
+
+    /// Disable size penalty for very different sized functions
+    #[arg(long)]
+    no_size_penalty: bool,
+
+    /// Filter functions by name (substring match)
+    #[arg(long)]
+    filter_function: Option<String>,
+
+    /// How to handle #if/#ifdef blocks before comparing
+    #[arg(long, value_enum, default_value = "off")]
+    preprocess: PreprocessMode,
+
+    /// Define a macro for --preprocess evaluate (NAME or NAME=VALUE)
+    #[arg(short = 'D', long = "define", value_name = "NAME[=VALUE]")]
+    defines: Vec<String>,
+
+    /// Disable function similarity checking
+    #[arg(long)]
+    no_functions: bool,
+
+    /// Enable similarity checking for structs, unions and classes
+    #[arg(long = "experimental-types")]
+    types: bool,
+
+    /// Keep comments in the compared trees (stripped by default)
+    #[arg(long)]
+    include_comments: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    if cli.no_functions && !cli.types {
+        eprintln!("Error: At least one analyzer must be enabled. Use --experimental-types to enable type checking, or remove --no-functions.");
+        return Err(anyhow::anyhow!("No analyzer enabled"));
+    }
+
+    if !cli.defines.is_empty() && cli.preprocess != PreprocessMode::Evaluate {
+        eprintln!("Warning: --define only has an effect with --preprocess evaluate");
+    }
+
+    let preprocessor = Preprocessor::new(cli.preprocess).with_defines(&cli.defines);
+
+    println!("Analyzing C/C++ code similarity...\n");
+
+    let separator = "-".repeat(60);
+
+    if !cli.no_functions {
+        println!("=== Function Similarity ===");
+        check::check_paths(
+            cli.paths.clone(),
+            cli.threshold,
+            cli.rename_cost,
+            cli.extensions.as_ref(),
+            cli.min_lines,
+            cli.min_tokens,
+            cli.no_size_penalty,
+            cli.print,
+            &preprocessor,
+            cli.filter_function.as_ref(),
+            cli.include_comments,
+        )?;
+    }
+
+    if cli.types && !cli.no_functions {
+        println!("\n{separator}\n");
+    }
+
+    if cli.types {
+        println!("=== Type Similarity (Structs & Classes) ===");
+        check_types::check_types(
+            cli.paths,
+            cli.threshold,
+            cli.extensions.as_ref(),
+            cli.print,
+            &preprocessor,
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+
+/// How conditional compilation (`#if`/`#ifdef`/...) is handled before parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum PreprocessMode {
+    /// Parse the source as written; both branches of a conditional end up in the tree
+    #[default]
+    Off,
+    /// Drop every conditional block and keep only unconditional code (include guards are kept)
+    Strip,
+    /// Keep the branches that are active for the given defines
+    Evaluate,
+}
+
+/// Lightweight conditional-compilation pass.
+///
+/// Only `#if`, `#ifdef`, `#ifndef`, `#elif`, `#else`, `#endif`, `#define` and
+/// `#undef` are interpreted; macros are not expanded in code. Removed lines
+/// are replaced by empty lines so that line numbers stay valid.
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    mode: PreprocessMode,
+    defines: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+struct Frame {
+    /// Whether the enclosing region is emitted
+    parent_active: bool,
+    /// Whether a branch of this conditional has already been taken
+    taken: bool,
+    active: bool,
+}
+
+impl Preprocessor {
+    pub fn new(mode: PreprocessMode) -> Self {
+        Self { mode, defines: HashMap::new() }
+    }
+
+    /// Add defines given as `NAME` or `NAME=VALUE` (like `-D` on a compiler command line)
+    pub fn with_defines<I, S>(mut self, defines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for define in defines {
+            let (name, value) = define.as_ref().split_once('=').unwrap_or((define.as_ref(), "1"));
+            self.defines.insert(name.trim().to_string(), value.trim().to_string());
+        }
+        self
+    }
+
+    /// Apply the pass to a source file. The result has the same number of lines.
+    pub fn process(&self, source: &str) -> String {
+        if self.mode == PreprocessMode::Off {
+            return source.to_string();
+        }
+
+        let lines: Vec<&str> = source.lines().collect();
+        let mut output: Vec<String> = Vec::with_capacity(lines.len());
+        let mut defines = self.defines.clone();
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut index = 0;
+
+        while index < lines.len() {
+            let active = stack.last().is_none_or(|frame| frame.active);
+
+            // Join directive continuation lines
+            let mut text = lines[index].to_string();
+            let mut consumed = 1;
+            if text.trim_start().starts_with('#') {
+                while text.ends_with('\\') && index + consumed < lines.len() {
+                    text.pop();
+                    text.push(' ');
+                    text.push_str(lines[index + consumed]);
+                    consumed += 1;
+                }
+            }
+
+            match parse_directive(&text) {
+                Some((directive @ ("if" | "ifdef" | "ifndef"), argument)) => {
+                    let condition = match directive {
+                        "ifdef" => defines.contains_key(first_word(argument)),
+                        "ifndef" => !defines.contains_key(first_word(argument)),
+                        _ => evaluate(argument, &defines) != 0,
+                    };
+                    let is_guard = directive == "ifndef"
+                        && is_include_guard(&lines[index + consumed..], first_word(argument));
+                    let branch = match self.mode {
+                        PreprocessMode::Strip => is_guard,
+                        _ => condition,
+                    };
+                    stack.push(Frame {
+                        parent_active: active,
+                        taken: branch,
+                        active: active && branch,
+                    });
+                    blank(&mut output, consumed);
+                }
+                Some(("elif", argument)) => {
+                    if let Some(frame) = stack.last_mut() {
+                        let condition = self.mode == PreprocessMode::Evaluate
+                            && !frame.taken
+                            && evaluate(argument, &defines) != 0;
+                        frame.active = frame.parent_active && condition;
+                        frame.taken |= condition;
+                    }
+                    blank(&mut output, consumed);
+                }
+                Some(("else", _)) => {
+                    if let Some(frame) = stack.last_mut() {
+                        let condition = self.mode == PreprocessMode::Evaluate && !frame.taken;
+                        frame.active = frame.parent_active && condition;
+                        frame.taken = true;
+                    }
+                    blank(&mut output, consumed);
+                }
+                Some(("endif", _)) => {
+                    stack.pop();
+                    blank(&mut output, consumed);
+                }
+                Some((directive, argument)) if active => {
+                    if directive == "define" {
+                        let name: String = argument
+                            .chars()
+                            .take_while(|c| c.is_alphanumeric() || *c == '_')
+                            .collect();
+                        let rest = &argument[name.len()..];
+                        // Function-like macros are recorded as defined without a value
+                        let value = if rest.starts_with('(') { "" } else { rest.trim() };
+                        defines.insert(name, value.to_string());
+                    } else if directive == "undef" {
+                        defines.remove(first_word(argument));
+                    }
+                    output.extend(lines[index..index + consumed].iter().map(|l| l.to_string()));
+                }
+                _ if active => {
+                    output.extend(lines[index..index + consumed].iter().map(|l| l.to_string()));
+                }
+                _ => blank(&mut output, consumed),
+            }
+
+            index += consumed;
+        }
+
+        let mut result = output.join("\n");
+        if source.ends_with('\n') {
+            result.push('\n');
+        }
+        result
+    }
+}
+
+fn blank(output: &mut Vec<String>, count: usize) {
+    output.extend(std::iter::repeat_n(String::new(), count));
+}
+
+/// Split `#  directive argument` into its parts
+fn parse_directive(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start();
+    let end = rest.find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len());
+    let (directive, argument) = rest.split_at(end);
+    Some((directive, strip_comment(argument).trim()))
+}
+
+fn strip_comment(text: &str) -> &str {
+    let end = [text.find("//"), text.find("/*")].into_iter().flatten().min();
+    end.map_or(text, |end| &text[..end])
+}
+
+fn first_word(text: &str) -> &str {
+    text.split_whitespace().next().unwrap_or("")
+}
+
+/// `#ifndef NAME` followed by `#define NAME` (ignoring blank lines and comments)
+fn is_include_guard(following: &[&str], name: &str) -> bool {
+    following
+        .iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with("/*"))
+        .and_then(parse_directive)
+        .is_some_and(|(directive, argument)| directive == "define" && first_word(argument) == name)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Op(&'static str),
+}
+
+const OPERATORS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<<", ">>", "(", ")", "!", "<", ">", "+", "-", "*", "/",
+    "%", "&", "|", "^", "~", "?", ":", ",",
+];
+
+fn tokenize(expression: &str) -> Vec<Token> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            let literal: String = chars[start..i].iter().collect();
+            tokens.push(Token::Number(parse_number(&literal)));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '\'' {
+            // Character literal such as 'a'
+            let value = chars.get(i + 1).map_or(0, |c| *c as i64);
+            i += chars[i + 1..].iter().position(|c| *c == '\'').map_or(chars.len(), |p| p + 2);
+            tokens.push(Token::Number(value));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            match OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                Some(op) => {
+                    tokens.push(Token::Op(op));
+                    i += op.len();
+                }
+                None => i += 1,
+            }
+        }
+    }
+
+    tokens
+}
+
+fn parse_number(literal: &str) -> i64 {
+    let digits = literal.trim_end_matches(['u', 'U', 'l', 'L']);
+    if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).unwrap_or(0)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        i64::from_str_radix(&digits[1..], 8).unwrap_or(0)
+    } else {
+        digits.parse().unwrap_or(0)
+    }
+}
+
+/// Evaluate an `#if` expression; undefined identifiers are 0 as in C
+fn evaluate(expression: &str, defines: &HashMap<String, String>) -> i64 {
+    let tokens = tokenize(expression);
+    let mut parser = ExpressionParser { tokens, position: 0, defines, depth: 0 };
+    parser.conditional()
+}
+
+struct ExpressionParser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    defines: &'a HashMap<String, String>,
+    depth: usize,
+}
+
+impl ExpressionParser<'_> {
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if self.peek_op() == Some(op) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn conditional(&mut self) -> i64 {
+        let condition = self.binary(0);
+        if self.eat("?") {
+            let then = self.conditional();
+            self.eat(":");
+            let otherwise = self.conditional();
+            if condition != 0 {
+                then
+            } else {
+                otherwise
+            }
+        } else {
+            condition
+        }
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> i64 {
+        let mut left = self.unary();
+        while let Some(op) = self.peek_op() {
+            let Some(precedence) = precedence(op) else { break };
+            if precedence < min_precedence {
+                break;
+            }
+            self.position += 1;
+            let right = self.binary(precedence + 1);
+            left = apply(op, left, right);
+        }
+        left
+    }
+
+    fn unary(&mut self) -> i64 {
+        if self.eat("!") {
+            return (self.unary() == 0) as i64;
+        }
+        if self.eat("-") {
+            return self.unary().wrapping_neg();
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        if self.eat("~") {
+            return !self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> i64 {
+        let Some(token) = self.tokens.get(self.position).cloned() else {
+            return 0;
+        };
+        self.position += 1;
+
+        match token {
+            Token::Number(value) => value,
+            Token::Op("(") => {
+                let value = self.conditional();
+                self.eat(")");
+                value
+            }
+            Token::Ident(name) if name == "defined" => {
+                let parenthesized = self.eat("(");
+                let defined = match self.tokens.get(self.position) {
+                    Some(Token::Ident(name)) => self.defines.contains_key(name),
+                    _ => false,
+                };
+                self.position += 1;
+                if parenthesized {
+                    self.eat(")");
+                }
+                defined as i64
+            }
+            Token::Ident(name) => {
+                // Function-like macro invocations evaluate to 0
+                if self.peek_op() == Some("(") {
+                    let mut depth = 0;
+                    while let Some(token) = self.tokens.get(self.position) {
+                        self.position += 1;
+                        match token {
+                            Token::Op("(") => depth += 1,
+                            Token::Op(")") => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    return 0;
+                }
+                self.macro_value(&name)
+            }
+            Token::Op(_) => 0,
+        }
+    }
+
+    /// Value of an object-like macro, expanding macros that refer to other macros
+    fn macro_value(&mut self, name: &str) -> i64 {
+        match self.defines.get(name) {
+            Some(value) if self.depth < 16 => {
+                let mut nested = ExpressionParser {
+                    tokens: tokenize(value),
+                    position: 0,
+                    defines: self.defines,
+                    depth: self.depth + 1,
+                };
+                nested.conditional()
+            }
+            _ => 0,
+        }
+    }
+}
+
+fn precedence(op: &str) -> Option<u8> {
+    Some(match op {
+        "||" => 1,
+        "&&" => 2,
+        "|" => 3,
+        "^" => 4,
+        "&" => 5,
+        "==" | "!=" => 6,
+        "<" | ">" | "<=" | ">=" => 7,
+        "<<" | ">>" => 8,
+        "+" | "-" => 9,
+        "*" | "/" | "%" => 10,
+        _ => return None,
+    })
+}
+
+fn apply(op: &str, left: i64, right: i64) -> i64 {
+    match op {
+        "||" => (left != 0 || right != 0) as i64,
+        "&&" => (left != 0 && right != 0) as i64,
+        "|" => left | right,
+        "^" => left ^ right,
+        "&" => left & right,
+        "==" => (left == right) as i64,
+        "!=" => (left != right) as i64,
+        "<" => (left < right) as i64,
+        ">" => (left > right) as i64,
+        "<=" => (left <= right) as i64,
+        ">=" => (left >= right) as i64,
+        "<<" => left.wrapping_shl(right as u32),
+        ">>" => left.wrapping_shr(right as u32),
+        "+" => left.wrapping_add(right),
+        "-" => left.wrapping_sub(right),
+        "*" => left.wrapping_mul(right),
+        "/" => left.checked_div(right).unwrap_or(0),
+        "%" => left.checked_rem(right).unwrap_or(0),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"#ifndef UTIL_H
+#define UTIL_H
+int read_config(const char *path) {
+#ifdef _WIN32
+    HANDLE h = CreateFileA(path, GENERIC_READ, 0, NULL, OPEN_EXISTING, 0, NULL);
+#elif defined(__APPLE__) && MAC_VERSION >= 11
+    int fd = open(path, O_RDONLY | O_CLOEXEC);
+#else
+    int fd = open(path, O_RDONLY);
+#endif
+    return parse(fd);
+}
+#endif
+"#;
+
+    fn kept(processed: &str) -> Vec<&str> {
+        processed.lines().map(str::trim).filter(|l| !l.is_empty()).collect()
+    }
+
+    #[test]
+    fn off_mode_leaves_source_untouched() {
+        assert_eq!(Preprocessor::new(PreprocessMode::Off).process(SOURCE), SOURCE);
+    }
+
+    #[test]
+    fn strip_mode_drops_conditional_blocks_but_keeps_include_guards() {
+        let processed = Preprocessor::new(PreprocessMode::Strip).process(SOURCE);
+
+        assert_eq!(processed.lines().count(), SOURCE.lines().count());
+        assert_eq!(
+            kept(&processed),
+            vec!["#define UTIL_H", "int read_config(const char *path) {", "return parse(fd);", "}"]
+        );
+    }
+
+    #[test]
+    fn evaluate_mode_selects_active_branch() {
+        let linux = Preprocessor::new(PreprocessMode::Evaluate).process(SOURCE);
+        assert!(linux.contains("open(path, O_RDONLY);"));
+        assert!(!linux.contains("CreateFileA") && !linux.contains("O_CLOEXEC"));
+
+        let mac = Preprocessor::new(PreprocessMode::Evaluate)
+            .with_defines(["__APPLE__", "MAC_VERSION=12"])
+            .process(SOURCE);
+        assert!(mac.contains("O_CLOEXEC"));
+        assert!(!mac.contains("open(path, O_RDONLY);"));
+
+        let windows =
+            Preprocessor::new(PreprocessMode::Evaluate).with_defines(["_WIN32"]).process(SOURCE);
+        assert!(windows.contains("CreateFileA"));
+        assert_eq!(windows.lines().nth(4), SOURCE.lines().nth(4));
+    }
+
+    #[test]
+    fn evaluates_expressions_and_local_defines() {
+        let source = "#define LEVEL (2 + 1)\n#if LEVEL > 2 && !defined NO_LOG\nlog();\n#endif\n#undef LEVEL\n#if LEVEL\nunreachable();\n#endif\n";
+        let processed = Preprocessor::new(PreprocessMode::Evaluate).process(source);
+        assert!(processed.contains("log();"));
+        assert!(!processed.contains("unreachable"));
+
+        let defines = HashMap::new();
+        assert_eq!(evaluate("0x10 == 16 ? 3 : 4", &defines), 3);
+        assert_eq!(evaluate("UNKNOWN_MACRO(1) || 1L", &defines), 1);
+    }
+}
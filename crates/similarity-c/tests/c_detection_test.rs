@@ -0,0 +1,158 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn create_test_file(dir: &TempDir, filename: &str, content: &str) {
+    fs::write(dir.path().join(filename), content).unwrap();
+}
+
+const LINUX_COPY: &str = r#"#include <stdio.h>
+
+int load_settings(const char *path, struct settings *out) {
+    FILE *fp = fopen(path, "r");
+    if (!fp) {
+        return -1;
+    }
+    int count = 0;
+    char line[256];
+    while (fgets(line, sizeof(line), fp)) {
+        if (parse_line(line, out)) {
+            count++;
+        }
+    }
+    fclose(fp);
+    return count;
+}
+"#;
+
+const PORTABLE_COPY: &str = r#"#include <stdio.h>
+
+int read_settings(const char *file, struct settings *cfg) {
+#ifdef _WIN32
+    FILE *fp = NULL;
+    if (fopen_s(&fp, file, "r") != 0) {
+        log_error("cannot open %s", file);
+        return -1;
+    }
+#else
+    FILE *fp = fopen(file, "r");
+    if (!fp) {
+        return -1;
+    }
+#endif
+    int count = 0;
+    char line[256];
+    while (fgets(line, sizeof(line), fp)) {
+        if (parse_line(line, cfg)) {
+            count++;
+        }
+    }
+    fclose(fp);
+    return count;
+}
+"#;
+
+#[test]
+fn test_cross_file_function_similarity() {
+    let dir = TempDir::new().unwrap();
+    create_test_file(&dir, "config.c", LINUX_COPY);
+    create_test_file(&dir, "settings.cpp", &LINUX_COPY.replace("load_settings", "parse_config"));
+
+    let mut cmd = Command::cargo_bin("similarity-c").unwrap();
+    cmd.arg(dir.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Checking 2 files for duplicates"))
+        .stdout(predicate::str::contains("config.c:3-17 load_settings"))
+        .stdout(predicate::str::contains("settings.cpp:3-17 parse_config"))
+        .stdout(predicate::str::contains("Total duplicate pairs found: 1"));
+}
+
+#[test]
+fn test_ifdef_branches_hide_duplicates_without_preprocessing() {
+    let dir = TempDir::new().unwrap();
+    create_test_file(&dir, "a.c", LINUX_COPY);
+    create_test_file(&dir, "b.c", PORTABLE_COPY);
+
+    let mut cmd = Command::cargo_bin("similarity-c").unwrap();
+    cmd.arg(dir.path()).arg("--threshold").arg("0.97");
+    cmd.assert().success().stdout(predicate::str::contains("No duplicate functions found!"));
+
+    let mut cmd = Command::cargo_bin("similarity-c").unwrap();
+    cmd.arg(dir.path()).arg("--threshold").arg("0.97").arg("--preprocess").arg("evaluate");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("a.c:3-17 load_settings"))
+        .stdout(predicate::str::contains("b.c:3-25 read_settings"))
+        .stdout(predicate::str::contains("Similarity: 100.00%"));
+
+    // Evaluating for Windows keeps the other branch, which no longer matches exactly
+    let mut cmd = Command::cargo_bin("similarity-c").unwrap();
+    cmd.arg(dir.path())
+        .arg("--threshold")
+        .arg("0.97")
+        .arg("--preprocess")
+        .arg("evaluate")
+        .arg("-D")
+        .arg("_WIN32");
+    cmd.assert().success().stdout(predicate::str::contains("No duplicate functions found!"));
+}
+
+#[test]
+fn test_strip_mode_compares_unconditional_code() {
+    let dir = TempDir::new().unwrap();
+    create_test_file(&dir, "a.c", PORTABLE_COPY);
+    create_test_file(
+        &dir,
+        "b.c",
+        &PORTABLE_COPY.replace("log_error", "report").replace("fopen_s", "open_file"),
+    );
+
+    let mut cmd = Command::cargo_bin("similarity-c").unwrap();
+    cmd.arg(dir.path()).arg("--threshold").arg("0.99").arg("--preprocess").arg("strip");
+
+    cmd.assert().success().stdout(predicate::str::contains("Similarity: 100.00%"));
+}
+
+#[test]
+fn test_struct_similarity() {
+    let dir = TempDir::new().unwrap();
+    create_test_file(
+        &dir,
+        "server.h",
+        "struct server_config {\n    const char *host;\n    int port;\n    int timeout_ms;\n    bool tls;\n};\n",
+    );
+    create_test_file(
+        &dir,
+        "client.hpp",
+        "class ClientConfig {\npublic:\n    const char *host;\n    int port;\n    int timeout_ms;\n    bool tls;\n};\n",
+    );
+
+    let mut cmd = Command::cargo_bin("similarity-c").unwrap();
+    cmd.arg(dir.path())
+        .arg("--no-functions")
+        .arg("--experimental-types")
+        .arg("--threshold")
+        .arg("0.7");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Found 2 types in 2 files"))
+        .stdout(predicate::str::contains("struct server_config"))
+        .stdout(predicate::str::contains("class ClientConfig"))
+        .stdout(predicate::str::contains("Total similar type pairs found: 1"));
+}
+
+#[test]
+fn test_requires_an_analyzer() {
+    let dir = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("similarity-c").unwrap();
+    cmd.arg(dir.path()).arg("--no-functions");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("At least one analyzer must be enabled"));
+}
@@ -0,0 +1,155 @@
+//! Rules grouped into BEM components.
+//!
+//! The analyzer pairs up variations of a BEM block one by one. This report
+//! gathers every element and modifier of a block instead, with the
+//! declarations all of them share and the ones each adds or changes. The
+//! componentization score is the mean share of a member's declarations that
+//! every member has: a block scoring close to 1 repeats one base in all its
+//! variations and is a candidate for a single parameterized component.
+
+use crate::{CssRule, SelectorAnalysis};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// A rule of a BEM block
+#[derive(Debug, Clone, Serialize)]
+pub struct BemMember {
+    pub selector: String,
+    pub element: Option<String>,
+    pub modifier: Option<String>,
+    pub file: String,
+    pub start_line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub at_rule: Option<String>,
+    /// Declarations not shared by every member of the block
+    pub divergent: Vec<(String, String)>,
+}
+
+/// All rules of a BEM block with at least two distinct selectors
+#[derive(Debug, Clone, Serialize)]
+pub struct BemComponent {
+    pub block: String,
+    /// Declarations every member has
+    pub shared: Vec<(String, String)>,
+    pub members: Vec<BemMember>,
+    pub componentization_score: f64,
+}
+
+/// Group the rules by BEM block, highest componentization score first
+pub fn group_bem_components(rules: &[CssRule]) -> Vec<BemComponent> {
+    let mut blocks: BTreeMap<String, Vec<(&CssRule, SelectorAnalysis)>> = BTreeMap::new();
+    for rule in rules {
+        let analysis = SelectorAnalysis::new(&rule.selector);
+        if let Some(bem) = &analysis.bem_parts {
+            blocks.entry(bem.block.clone()).or_default().push((rule, analysis));
+        }
+    }
+
+    let mut components: Vec<BemComponent> = blocks
+        .into_iter()
+        .filter(|(_, members)| {
+            members.iter().map(|(rule, _)| rule.selector.as_str()).collect::<HashSet<_>>().len() > 1
+        })
+        .map(|(block, members)| component(block, &members))
+        .collect();
+    components.sort_by(|a, b| {
+        b.componentization_score
+            .total_cmp(&a.componentization_score)
+            .then_with(|| b.members.len().cmp(&a.members.len()))
+            .then_with(|| a.block.cmp(&b.block))
+    });
+    components
+}
+
+fn component(block: String, members: &[(&CssRule, SelectorAnalysis)]) -> BemComponent {
+    let declaration_sets: Vec<BTreeSet<(String, String)>> = members
+        .iter()
+        .map(|(rule, _)| {
+            rule.declarations.iter().map(|(property, value)| normalize(property, value)).collect()
+        })
+        .collect();
+    let shared: BTreeSet<(String, String)> =
+        declaration_sets.iter().skip(1).fold(declaration_sets[0].clone(), |shared, set| {
+            shared.intersection(set).cloned().collect()
+        });
+
+    let members: Vec<BemMember> = members
+        .iter()
+        .zip(&declaration_sets)
+        .map(|((rule, analysis), declarations)| {
+            let bem = analysis.bem_parts.as_ref().expect("members have BEM parts");
+            BemMember {
+                selector: rule.selector.clone(),
+                element: bem.element.clone(),
+                modifier: bem.modifier.clone(),
+                file: rule.file_path.clone(),
+                start_line: rule.start_line,
+                at_rule: rule.at_rule.clone(),
+                divergent: declarations.difference(&shared).cloned().collect(),
+            }
+        })
+        .collect();
+    let componentization_score = declaration_sets
+        .iter()
+        .map(|declarations| shared.len() as f64 / declarations.len().max(1) as f64)
+        .sum::<f64>()
+        / declaration_sets.len() as f64;
+
+    BemComponent { block, shared: shared.into_iter().collect(), members, componentization_score }
+}
+
+fn normalize(property: &str, value: &str) -> (String, String) {
+    (property.trim().to_lowercase(), value.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_css_to_rules;
+
+    #[test]
+    fn test_variations_are_grouped_by_block() {
+        let css = r#"
+.btn {
+  padding: 8px;
+  border: 0;
+}
+.btn--primary {
+  padding: 8px;
+  border: 0;
+  color: blue;
+}
+.btn__icon {
+  padding: 8px;
+  border: 0;
+}
+.card {
+  margin: 0;
+}
+.card__title {
+  font-weight: bold;
+}
+.lonely {
+  color: red;
+}
+"#;
+        let rules = parse_css_to_rules(css, "test.css").unwrap();
+        let components = group_bem_components(&rules);
+
+        assert_eq!(components.len(), 2);
+        let btn = &components[0];
+        assert_eq!(btn.block, "btn");
+        assert_eq!(
+            btn.shared,
+            [("border".to_string(), "0".to_string()), ("padding".to_string(), "8px".to_string())]
+        );
+        assert_eq!(btn.members[1].modifier.as_deref(), Some("primary"));
+        assert_eq!(btn.members[1].divergent, [("color".to_string(), "blue".to_string())]);
+        assert_eq!(btn.members[2].element.as_deref(), Some("icon"));
+        // (1 + 2/3 + 1) / 3
+        assert!((btn.componentization_score - 8.0 / 9.0).abs() < 1e-9);
+
+        assert_eq!(components[1].block, "card");
+        assert_eq!(components[1].componentization_score, 0.0);
+    }
+}
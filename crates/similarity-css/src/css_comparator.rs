@@ -12,6 +12,10 @@ pub struct CssRule {
     pub tree: Rc<TreeNode>,
     pub start_line: usize,
     pub end_line: usize,
+    /// Stylesheet the rule was read from
+    pub file_path: String,
+    /// Enclosing conditional at-rules, e.g. `@media (min-width: 768px)`
+    pub at_rule: Option<String>,
 }
 
 // Serializable version of CssRule for JSON output
@@ -21,6 +25,9 @@ pub struct SerializableCssRule {
     pub declarations: Vec<(String, String)>,
     pub start_line: usize,
     pub end_line: usize,
+    pub file_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at_rule: Option<String>,
 }
 
 impl From<&CssRule> for SerializableCssRule {
@@ -30,6 +37,8 @@ impl From<&CssRule> for SerializableCssRule {
             declarations: rule.declarations.clone(),
             start_line: rule.start_line,
             end_line: rule.end_line,
+            file_path: rule.file_path.clone(),
+            at_rule: rule.at_rule.clone(),
         }
     }
 }
@@ -92,17 +101,36 @@ pub fn calculate_rule_similarity(rule1: &CssRule, rule2: &CssRule) -> f64 {
     let declaration_similarity =
         calculate_declaration_similarity(&expanded_decls1, &expanded_decls2);
 
-    let weights = CssSimilarityWeights { selector: 0.05, ast: 0.0, declarations: 0.95 };
+    WEIGHTS.combine(selector_similarity, ast_similarity, declaration_similarity)
+}
+
+/// Same as [`calculate_rule_similarity`] for already expanded declarations.
+///
+/// The AST term carries no weight, so skipping it gives identical scores.
+pub(crate) fn calculate_expanded_rule_similarity(
+    selector1: &str,
+    selector2: &str,
+    expanded_decls1: &[(String, String)],
+    expanded_decls2: &[(String, String)],
+) -> f64 {
+    let selector_similarity = calculate_selector_similarity(selector1, selector2);
+    let declaration_similarity = calculate_declaration_similarity(expanded_decls1, expanded_decls2);
+    WEIGHTS.combine(selector_similarity, 0.0, declaration_similarity)
+}
+
+pub(crate) const WEIGHTS: CssSimilarityWeights =
+    CssSimilarityWeights { selector: 0.05, ast: 0.0, declarations: 0.95 };
 
-    weights.selector * selector_similarity
-        + weights.ast * ast_similarity
-        + weights.declarations * declaration_similarity
+pub(crate) struct CssSimilarityWeights {
+    pub selector: f64,
+    pub ast: f64,
+    pub declarations: f64,
 }
 
-struct CssSimilarityWeights {
-    selector: f64,
-    ast: f64,
-    declarations: f64,
+impl CssSimilarityWeights {
+    fn combine(&self, selector: f64, ast: f64, declarations: f64) -> f64 {
+        self.selector * selector + self.ast * ast + self.declarations * declarations
+    }
 }
 
 pub fn calculate_selector_similarity(selector1: &str, selector2: &str) -> f64 {
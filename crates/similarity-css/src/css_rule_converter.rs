@@ -3,8 +3,9 @@ use similarity_core::language_parser::{GenericFunctionDef, LanguageParser};
 use similarity_core::tree::TreeNode;
 use std::rc::Rc;
 
-/// Convert GenericFunctionDef to CssRule
-pub fn convert_to_css_rule(func: &GenericFunctionDef, content: &str) -> CssRule {
+/// Convert GenericFunctionDef of `file_path` to CssRule; the parsers pass the
+/// enclosing at-rules in `class_name`
+pub fn convert_to_css_rule(func: &GenericFunctionDef, content: &str, file_path: &str) -> CssRule {
     // For SCSS, we might already have declarations from flatten_scss_rules
     // Check if we need to extract declarations
     let declarations = if func.decorators.is_empty() {
@@ -28,11 +29,13 @@ pub fn convert_to_css_rule(func: &GenericFunctionDef, content: &str) -> CssRule
     let tree = create_rule_tree_node(&func.name, &declarations);
 
     CssRule {
-        selector: func.name.clone(),
+        selector: func.name.to_string(),
         declarations,
         tree,
         start_line: func.body_start_line as usize,
         end_line: func.body_end_line as usize,
+        file_path: file_path.to_string(),
+        at_rule: func.class_name.as_ref().map(|context| context.to_string()),
     }
 }
 
@@ -78,7 +81,10 @@ fn extract_declarations(func: &GenericFunctionDef, content: &str) -> Vec<(String
 }
 
 /// Create a tree node for CSS rule
-fn create_rule_tree_node(selector: &str, declarations: &[(String, String)]) -> Rc<TreeNode> {
+pub(crate) fn create_rule_tree_node(
+    selector: &str,
+    declarations: &[(String, String)],
+) -> Rc<TreeNode> {
     let mut rule_node = TreeNode::new(selector.to_string(), String::new(), 0);
 
     // Add declaration nodes as children
@@ -99,7 +105,7 @@ pub fn parse_css_to_rules(
     let mut parser = CssParser::new();
     let functions = parser.extract_functions(content, file_path)?;
 
-    Ok(functions.iter().map(|func| convert_to_css_rule(func, content)).collect())
+    Ok(functions.iter().map(|func| convert_to_css_rule(func, content, file_path)).collect())
 }
 
 #[cfg(test)]
@@ -115,7 +121,7 @@ mod tests {
 }"#;
 
         let func = GenericFunctionDef {
-            name: ".btn".to_string(),
+            name: ".btn".into(),
             start_line: 1,
             end_line: 5,
             body_start_line: 2,
@@ -144,7 +150,7 @@ mod tests {
 }"#;
 
         let func = GenericFunctionDef {
-            name: ".card".to_string(),
+            name: ".card".into(),
             start_line: 1,
             end_line: 4,
             body_start_line: 2,
@@ -157,7 +163,7 @@ mod tests {
             decorators: vec![],
         };
 
-        let rule = convert_to_css_rule(&func, content);
+        let rule = convert_to_css_rule(&func, content, "test.css");
 
         assert_eq!(rule.selector, ".card");
         assert_eq!(rule.declarations.len(), 2);
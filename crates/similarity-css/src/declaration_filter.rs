@@ -0,0 +1,156 @@
+//! Declaration filtering before comparison.
+//!
+//! Tiny rules such as `.hidden { display: none }` are similar to many others
+//! without being worth consolidating, and some declarations (vendor-prefixed
+//! fallbacks, commented-out lines) inflate the similarity of rules that only
+//! share boilerplate. The filter drops blocklisted declarations and then rules
+//! left with fewer than the minimum number of declarations, the same way for
+//! every comparison engine.
+
+use crate::css_rule_converter::create_rule_tree_node;
+use crate::CssRule;
+
+const VENDOR_PREFIXES: [&str; 4] = ["-webkit-", "-moz-", "-ms-", "-o-"];
+
+/// A blocklist entry of `--ignore-properties`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyPattern {
+    /// `vendor-prefixes`: vendor-prefixed properties and values (`-webkit-box`)
+    VendorPrefixes,
+    /// `comments`: commented-out declarations picked up from the source
+    Comments,
+    /// `custom-properties`: `--*` variables
+    CustomProperties,
+    /// A property name ending in `*`, e.g. `animation-*`
+    Prefix(String),
+    Name(String),
+}
+
+impl PropertyPattern {
+    pub fn parse(pattern: &str) -> Self {
+        let pattern = pattern.trim().to_lowercase();
+        match pattern.as_str() {
+            "vendor-prefixes" => PropertyPattern::VendorPrefixes,
+            "comments" => PropertyPattern::Comments,
+            "custom-properties" => PropertyPattern::CustomProperties,
+            _ => match pattern.strip_suffix('*') {
+                Some(prefix) => PropertyPattern::Prefix(prefix.to_string()),
+                None => PropertyPattern::Name(pattern),
+            },
+        }
+    }
+
+    fn matches(&self, property: &str, value: &str) -> bool {
+        match self {
+            PropertyPattern::VendorPrefixes => {
+                let value = value.trim_start();
+                VENDOR_PREFIXES
+                    .iter()
+                    .any(|prefix| property.starts_with(prefix) || value.starts_with(prefix))
+            }
+            PropertyPattern::Comments => {
+                ["/*", "*", "//"].iter().any(|marker| property.starts_with(marker))
+                    || value.contains("*/")
+            }
+            PropertyPattern::CustomProperties => property.starts_with("--"),
+            PropertyPattern::Prefix(prefix) => property.starts_with(prefix.as_str()),
+            PropertyPattern::Name(name) => property == name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeclarationFilter {
+    min_declarations: usize,
+    ignored: Vec<PropertyPattern>,
+}
+
+impl DeclarationFilter {
+    /// Keep rules with at least `min_declarations` declarations outside the `ignored` patterns
+    pub fn new(min_declarations: usize, ignored: &[String]) -> Self {
+        Self {
+            min_declarations,
+            ignored: ignored.iter().map(|pattern| PropertyPattern::parse(pattern)).collect(),
+        }
+    }
+
+    pub fn is_ignored(&self, property: &str, value: &str) -> bool {
+        let property = property.trim().to_lowercase();
+        self.ignored.iter().any(|pattern| pattern.matches(&property, value))
+    }
+
+    /// The rule without ignored declarations, or `None` when too few remain
+    pub fn apply(&self, rule: &CssRule) -> Option<CssRule> {
+        let declarations: Vec<(String, String)> = rule
+            .declarations
+            .iter()
+            .filter(|(property, value)| !self.is_ignored(property, value))
+            .cloned()
+            .collect();
+        if declarations.is_empty() || declarations.len() < self.min_declarations {
+            return None;
+        }
+        if declarations.len() == rule.declarations.len() {
+            return Some(rule.clone());
+        }
+        Some(CssRule {
+            selector: rule.selector.clone(),
+            tree: create_rule_tree_node(&rule.selector, &declarations),
+            declarations,
+            start_line: rule.start_line,
+            end_line: rule.end_line,
+            file_path: rule.file_path.clone(),
+            at_rule: rule.at_rule.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_css_to_rules;
+
+    const CSS: &str = r#"
+.hidden {
+  display: none;
+}
+.box {
+  -webkit-box-shadow: 0 0 1px black;
+  display: -webkit-box;
+  /* color: red; */
+  --gap: 4px;
+  animation-name: fade;
+  margin: 0;
+  padding: 0;
+}
+"#;
+
+    #[test]
+    fn test_min_declarations() {
+        let rules = parse_css_to_rules(CSS, "test.css").unwrap();
+        let filter = DeclarationFilter::new(2, &[]);
+        let kept: Vec<String> =
+            rules.iter().filter_map(|rule| filter.apply(rule)).map(|rule| rule.selector).collect();
+        assert_eq!(kept, [".box"]);
+    }
+
+    #[test]
+    fn test_property_blocklist() {
+        let rules = parse_css_to_rules(CSS, "test.css").unwrap();
+        let ignored: Vec<String> =
+            ["vendor-prefixes", "comments", "custom-properties", "animation-*"]
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect();
+        let filter = DeclarationFilter::new(1, &ignored);
+
+        let filtered = filter.apply(&rules[1]).unwrap();
+        let properties: Vec<&str> =
+            filtered.declarations.iter().map(|(property, _)| property.as_str()).collect();
+        assert_eq!(properties, ["margin", "padding"]);
+        assert_eq!(filtered.tree.children.len(), 2);
+
+        let filter = DeclarationFilter::new(1, &["display".to_string()]);
+        assert!(filter.apply(&rules[0]).is_none());
+    }
+}
@@ -0,0 +1,140 @@
+//! Index of exact declarations across all parsed rules.
+//!
+//! Each `property: value` pair is mapped to the rules containing it. The most
+//! repeated declarations are candidates for utility classes, and the share of
+//! repeated declarations gives the scale of duplication.
+
+use crate::CssRule;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeclarationOccurrence {
+    pub file: String,
+    pub selector: String,
+    pub start_line: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RepeatedDeclaration {
+    pub property: String,
+    pub value: String,
+    pub occurrences: Vec<DeclarationOccurrence>,
+}
+
+#[derive(Debug, Default)]
+pub struct DeclarationIndex {
+    declarations: HashMap<(String, String), Vec<DeclarationOccurrence>>,
+    total: usize,
+}
+
+impl DeclarationIndex {
+    /// Index the declarations of `(file, rule)` pairs
+    pub fn build<'a>(rules: impl IntoIterator<Item = (&'a str, &'a CssRule)>) -> Self {
+        let mut index = Self::default();
+        for (file, rule) in rules {
+            for (property, value) in &rule.declarations {
+                index.total += 1;
+                index.declarations.entry(normalize(property, value)).or_default().push(
+                    DeclarationOccurrence {
+                        file: file.to_string(),
+                        selector: rule.selector.clone(),
+                        start_line: rule.start_line,
+                    },
+                );
+            }
+        }
+        index
+    }
+
+    /// Rules containing `property: value`
+    pub fn occurrences(&self, property: &str, value: &str) -> &[DeclarationOccurrence] {
+        self.declarations.get(&normalize(property, value)).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn total_declarations(&self) -> usize {
+        self.total
+    }
+
+    pub fn distinct_declarations(&self) -> usize {
+        self.declarations.len()
+    }
+
+    /// Declarations that repeat an earlier identical one
+    pub fn repeated_declarations(&self) -> usize {
+        self.total - self.declarations.len()
+    }
+
+    /// The `limit` declarations appearing most often, at least twice
+    pub fn most_repeated(&self, limit: usize) -> Vec<RepeatedDeclaration> {
+        let mut repeated: Vec<_> =
+            self.declarations.iter().filter(|(_, occurrences)| occurrences.len() > 1).collect();
+        repeated.sort_by(
+            |((property1, value1), occurrences1), ((property2, value2), occurrences2)| {
+                occurrences2
+                    .len()
+                    .cmp(&occurrences1.len())
+                    .then_with(|| property1.cmp(property2))
+                    .then_with(|| value1.cmp(value2))
+            },
+        );
+        repeated
+            .into_iter()
+            .take(limit)
+            .map(|((property, value), occurrences)| RepeatedDeclaration {
+                property: property.clone(),
+                value: value.clone(),
+                occurrences: occurrences.clone(),
+            })
+            .collect()
+    }
+}
+
+fn normalize(property: &str, value: &str) -> (String, String) {
+    (property.trim().to_lowercase(), value.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_css_to_rules;
+
+    #[test]
+    fn test_most_repeated_declarations() {
+        let css = r#"
+.a {
+  display: flex;
+  color: red;
+}
+.b {
+  display: flex;
+  border: 1px  solid red;
+}
+.c {
+  DISPLAY: flex;
+  border: 1px solid red;
+  color: blue;
+}
+"#;
+        let rules = parse_css_to_rules(css, "test.css").unwrap();
+        let index = DeclarationIndex::build(rules.iter().map(|rule| ("test.css", rule)));
+        assert_eq!(index.total_declarations(), 7);
+        assert_eq!(index.distinct_declarations(), 4);
+        assert_eq!(index.repeated_declarations(), 3);
+
+        let top = index.most_repeated(10);
+        let summary: Vec<(&str, &str, usize)> = top
+            .iter()
+            .map(|decl| (decl.property.as_str(), decl.value.as_str(), decl.occurrences.len()))
+            .collect();
+        assert_eq!(summary, [("display", "flex", 3), ("border", "1px solid red", 2)]);
+        assert_eq!(index.most_repeated(1).len(), 1);
+
+        let selectors: Vec<&str> = index
+            .occurrences("border", "1px solid red")
+            .iter()
+            .map(|occurrence| occurrence.selector.as_str())
+            .collect();
+        assert_eq!(selectors, [".b", ".c"]);
+    }
+}
@@ -0,0 +1,311 @@
+//! Design-token suggestions from repeated literal values.
+//!
+//! Colors, spacings, shadows and font stacks written out literally in many
+//! rules are candidates for custom properties. Values are normalized before
+//! counting (`#FFF` and `#ffffff` are the same color), and every value
+//! repeated at least the given number of times becomes a suggested token
+//! together with the declarations that would use it.
+
+use crate::CssRule;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenCategory {
+    Color,
+    Spacing,
+    Shadow,
+    FontStack,
+}
+
+impl TokenCategory {
+    /// Custom property prefix of the category's tokens
+    fn prefix(self) -> &'static str {
+        match self {
+            TokenCategory::Color => "color",
+            TokenCategory::Spacing => "spacing",
+            TokenCategory::Shadow => "shadow",
+            TokenCategory::FontStack => "font",
+        }
+    }
+}
+
+impl fmt::Display for TokenCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TokenCategory::Color => "color",
+            TokenCategory::Spacing => "spacing",
+            TokenCategory::Shadow => "shadow",
+            TokenCategory::FontStack => "font stack",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenOccurrence {
+    pub file: String,
+    pub selector: String,
+    pub property: String,
+    pub start_line: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DesignToken {
+    /// Suggested custom property, e.g. `--color-1`
+    pub name: String,
+    pub category: TokenCategory,
+    pub value: String,
+    pub occurrences: Vec<TokenOccurrence>,
+}
+
+/// Suggest tokens for values repeated at least `min_occurrences` times among `(file, rule)` pairs.
+///
+/// Tokens are numbered per category, most repeated value first.
+pub fn extract_design_tokens<'a>(
+    rules: impl IntoIterator<Item = (&'a str, &'a CssRule)>,
+    min_occurrences: usize,
+) -> Vec<DesignToken> {
+    let mut values: BTreeMap<(TokenCategory, String), Vec<TokenOccurrence>> = BTreeMap::new();
+    for (file, rule) in rules {
+        for (property, value) in &rule.declarations {
+            let property = property.to_lowercase();
+            for (category, value) in token_values(&property, value) {
+                values.entry((category, value)).or_default().push(TokenOccurrence {
+                    file: file.to_string(),
+                    selector: rule.selector.clone(),
+                    property: property.clone(),
+                    start_line: rule.start_line,
+                });
+            }
+        }
+    }
+
+    let mut repeated: Vec<((TokenCategory, String), Vec<TokenOccurrence>)> = values
+        .into_iter()
+        .filter(|(_, occurrences)| occurrences.len() >= min_occurrences.max(1))
+        .collect();
+    repeated.sort_by(|((category1, _), occurrences1), ((category2, _), occurrences2)| {
+        category1.cmp(category2).then_with(|| occurrences2.len().cmp(&occurrences1.len()))
+    });
+
+    let mut counters: BTreeMap<TokenCategory, usize> = BTreeMap::new();
+    repeated
+        .into_iter()
+        .map(|((category, value), occurrences)| {
+            let counter = counters.entry(category).or_default();
+            *counter += 1;
+            DesignToken {
+                name: format!("--{}-{}", category.prefix(), counter),
+                category,
+                value,
+                occurrences,
+            }
+        })
+        .collect()
+}
+
+/// A `:root` block defining the tokens
+pub fn root_block(tokens: &[DesignToken]) -> String {
+    let mut block = String::from(":root {\n");
+    for token in tokens {
+        block.push_str(&format!("  {}: {};\n", token.name, token.value));
+    }
+    block.push('}');
+    block
+}
+
+/// Literal values of a declaration worth a token, normalized
+fn token_values(property: &str, value: &str) -> Vec<(TokenCategory, String)> {
+    let value = value.replace("!important", "").trim().to_lowercase();
+    if value.is_empty() || value.starts_with("var(") {
+        return Vec::new();
+    }
+
+    match property {
+        "box-shadow" | "text-shadow" => {
+            if matches!(value.as_str(), "none" | "inherit" | "initial" | "unset") {
+                return Vec::new();
+            }
+            vec![(TokenCategory::Shadow, normalize_spacing(&value))]
+        }
+        "font-family" if value.contains(',') => {
+            vec![(TokenCategory::FontStack, normalize_spacing(&value))]
+        }
+        _ => {
+            let is_spacing = ["margin", "padding", "gap", "row-gap", "column-gap"]
+                .iter()
+                .any(|prefix| property == *prefix || property.starts_with(&format!("{prefix}-")));
+            split_components(&value)
+                .into_iter()
+                .filter_map(|component| {
+                    if let Some(color) = normalize_color(component) {
+                        Some((TokenCategory::Color, color))
+                    } else if is_spacing && is_length(component) {
+                        Some((TokenCategory::Spacing, component.to_string()))
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+/// Split a value on whitespace and commas outside parentheses
+fn split_components(value: &str) -> Vec<&str> {
+    let mut components = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if depth == 0 && (c.is_whitespace() || c == ',') => {
+                if start < i {
+                    components.push(&value[start..i]);
+                }
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    if start < value.len() {
+        components.push(&value[start..]);
+    }
+    components
+}
+
+/// Hex colors in six-digit form and `rgb()`/`hsl()` without inner spacing
+fn normalize_color(component: &str) -> Option<String> {
+    if let Some(hex) = component.strip_prefix('#') {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        return match hex.len() {
+            3 | 4 => Some(format!("#{}", hex.chars().flat_map(|c| [c, c]).collect::<String>())),
+            6 | 8 => Some(component.to_string()),
+            _ => None,
+        };
+    }
+    ["rgb(", "rgba(", "hsl(", "hsla("]
+        .iter()
+        .any(|function| component.starts_with(function))
+        .then(|| normalize_spacing(component))
+}
+
+/// Single spaces between components, `, ` between list items and no spaces inside parentheses
+/// around commas, e.g. `0 1px  2px rgba(0, 0, 0, .2),0 0 1px red` becomes
+/// `0 1px 2px rgba(0,0,0,.2), 0 0 1px red`
+fn normalize_spacing(value: &str) -> String {
+    let mut normalized = String::new();
+    let mut depth = 0usize;
+    let mut pending_space = false;
+    for c in value.trim().chars() {
+        match c {
+            c if c.is_whitespace() => pending_space = true,
+            ',' => {
+                normalized.push(',');
+                if depth == 0 {
+                    normalized.push(' ');
+                }
+                pending_space = false;
+            }
+            _ => {
+                if pending_space
+                    && c != ')'
+                    && !normalized.ends_with([' ', '('])
+                    && !normalized.ends_with(',')
+                {
+                    normalized.push(' ');
+                }
+                pending_space = false;
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                normalized.push(c);
+            }
+        }
+    }
+    normalized
+}
+
+/// Non-zero lengths such as `8px`, `1.5rem` or `2em`
+fn is_length(component: &str) -> bool {
+    let unit_start = component
+        .find(|c: char| !c.is_ascii_digit() && c != '.' && c != '-')
+        .unwrap_or(component.len());
+    let (number, unit) = component.split_at(unit_start);
+    matches!(unit, "px" | "rem" | "em") && number.parse::<f64>().is_ok_and(|n| n != 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert_to_css_rule, CssParser};
+    use similarity_core::language_parser::LanguageParser;
+
+    fn tokens_in(css: &str, min_occurrences: usize) -> Vec<DesignToken> {
+        let mut parser = CssParser::new();
+        let functions = parser.extract_functions(css, "test.css").unwrap();
+        let rules: Vec<CssRule> =
+            functions.iter().map(|func| convert_to_css_rule(func, css, "test.css")).collect();
+        extract_design_tokens(rules.iter().map(|rule| ("test.css", rule)), min_occurrences)
+    }
+
+    const CSS: &str = r#"
+.btn {
+  color: #3498DB;
+  padding: 8px 16px;
+  box-shadow: 0 1px 2px rgba(0, 0, 0, 0.2);
+}
+.link {
+  color: #3498db;
+  margin: 0 8px;
+  font-family: Inter, sans-serif;
+}
+.card {
+  border: 1px solid #3498db;
+  padding: 8px;
+  box-shadow: 0 1px  2px rgba(0,0,0,0.2);
+  font-family: Inter,sans-serif;
+}
+.badge {
+  color: #fff;
+  background: #FFFFFF;
+}
+"#;
+
+    #[test]
+    fn test_repeated_values_become_tokens() {
+        let tokens = tokens_in(CSS, 2);
+        let summary: Vec<(&str, &str, usize)> = tokens
+            .iter()
+            .map(|token| (token.name.as_str(), token.value.as_str(), token.occurrences.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("--color-1", "#3498db", 3),
+                ("--color-2", "#ffffff", 2),
+                ("--spacing-1", "8px", 3),
+                ("--shadow-1", "0 1px 2px rgba(0,0,0,0.2)", 2),
+                ("--font-1", "inter, sans-serif", 2),
+            ]
+        );
+        let selectors: Vec<&str> =
+            tokens[0].occurrences.iter().map(|occurrence| occurrence.selector.as_str()).collect();
+        assert_eq!(selectors, [".btn", ".link", ".card"]);
+        assert_eq!(tokens[0].occurrences[2].property, "border");
+    }
+
+    #[test]
+    fn test_min_occurrences_and_root_block() {
+        let tokens = tokens_in(CSS, 3);
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(root_block(&tokens), ":root {\n  --color-1: #3498db;\n  --spacing-1: 8px;\n}");
+    }
+}
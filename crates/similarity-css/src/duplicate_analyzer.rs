@@ -1,12 +1,22 @@
-use crate::{calculate_rule_similarity, CssRule, SelectorAnalysis, SerializableCssRule};
+use crate::css_comparator::{calculate_expanded_rule_similarity, WEIGHTS};
+use crate::{
+    check_merge_safety, expand_shorthand_properties, CssRule, SelectorAnalysis, SerializableCssRule,
+};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Slack for floating-point error in the similarity upper bounds
+const EPSILON: f64 = 1e-9;
 
 /// Represents a potential duplicate CSS rule
 #[derive(Debug, Clone)]
 pub struct DuplicateRule {
     pub rule1: CssRule,
     pub rule2: CssRule,
+    /// Positions of `rule1` and `rule2` in the analyzed rules, i.e. in document order
+    pub index1: usize,
+    pub index2: usize,
     pub similarity: f64,
     pub duplicate_type: DuplicateType,
 }
@@ -20,6 +30,14 @@ pub struct SerializableDuplicateRule {
     pub duplicate_type: DuplicateType,
 }
 
+impl DuplicateRule {
+    /// Similarity × average rule length in lines, the order similarity-ts reports findings in
+    pub fn priority(&self) -> f64 {
+        let lines = |rule: &CssRule| (rule.end_line + 1).saturating_sub(rule.start_line) as f64;
+        self.similarity * (lines(&self.rule1) + lines(&self.rule2)) / 2.0
+    }
+}
+
 impl From<&DuplicateRule> for SerializableDuplicateRule {
     fn from(dup: &DuplicateRule) -> Self {
         SerializableDuplicateRule {
@@ -45,6 +63,33 @@ pub enum DuplicateType {
     SpecificityOverride { winner: String, loser: String },
 }
 
+/// Per-rule data computed once and shared by all comparisons of the rule
+struct RuleFingerprint<'a> {
+    selector: &'a str,
+    expanded: Vec<(String, String)>,
+    /// Distinct property names after shorthand expansion
+    properties: BTreeSet<String>,
+    analysis: SelectorAnalysis,
+}
+
+impl<'a> RuleFingerprint<'a> {
+    fn new(rule: &'a CssRule) -> Self {
+        let expanded = expand_shorthand_properties(&rule.declarations);
+        let properties = expanded.iter().map(|(property, _)| property.clone()).collect();
+        Self {
+            selector: &rule.selector,
+            expanded,
+            properties,
+            analysis: SelectorAnalysis::new(&rule.selector),
+        }
+    }
+}
+
+/// Highest rule similarity possible when `shared` of `total` distinct properties match
+fn max_similarity(shared: usize, total: usize) -> f64 {
+    WEIGHTS.selector + WEIGHTS.declarations * shared as f64 / total as f64
+}
+
 /// Analyzes CSS rules for various types of duplicates and conflicts
 pub struct DuplicateAnalyzer {
     rules: Vec<CssRule>,
@@ -56,99 +101,137 @@ impl DuplicateAnalyzer {
         Self { rules, threshold }
     }
 
-    /// Find all types of duplicates in the ruleset
+    /// The analyzed rules; [`DuplicateRule::index1`] and `index2` point into them
+    pub fn rules(&self) -> &[CssRule] {
+        &self.rules
+    }
+
+    /// Find all types of duplicates in the ruleset.
+    ///
+    /// Only candidate pairs are compared: rules sharing a selector or a BEM
+    /// block, and rules whose property sets overlap enough to possibly reach
+    /// the threshold. Specificity overrides are reported among those pairs.
     pub fn analyze(&self) -> DuplicateAnalysisResult {
+        self.analyze_where(|_, _| true)
+    }
+
+    /// Like [`Self::analyze`], limited to the rule index pairs `(i, j)`, `i < j`,
+    /// accepted by `include`
+    pub fn analyze_where(&self, include: impl Fn(usize, usize) -> bool) -> DuplicateAnalysisResult {
         let mut exact_duplicates = Vec::new();
         let mut selector_conflicts = Vec::new();
         let mut style_duplicates = Vec::new();
         let mut bem_variations = Vec::new();
         let mut specificity_overrides = Vec::new();
 
-        // Compare all pairs of rules
-        for (i, rule1) in self.rules.iter().enumerate() {
-            for (j, rule2) in self.rules.iter().enumerate() {
-                if i >= j {
-                    continue;
-                }
-
-                let similarity = calculate_rule_similarity(rule1, rule2);
-                let sel_analysis1 = SelectorAnalysis::new(&rule1.selector);
-                let sel_analysis2 = SelectorAnalysis::new(&rule2.selector);
-
-                // Track BEM variations independently from similarity threshold.
-                if let (Some(bem1), Some(bem2)) =
-                    (&sel_analysis1.bem_parts, &sel_analysis2.bem_parts)
-                {
-                    if bem1.block == bem2.block && rule1.selector != rule2.selector {
-                        bem_variations.push(DuplicateRule {
-                            rule1: rule1.clone(),
-                            rule2: rule2.clone(),
-                            similarity,
-                            duplicate_type: DuplicateType::BemVariation {
-                                component: bem1.block.clone(),
-                            },
-                        });
-                    }
-                }
-
-                // Check for exact duplicates
-                if rule1.selector == rule2.selector && similarity > 0.99 {
-                    exact_duplicates.push(DuplicateRule {
-                        rule1: rule1.clone(),
-                        rule2: rule2.clone(),
-                        similarity,
-                        duplicate_type: DuplicateType::ExactDuplicate,
-                    });
-                }
-                // Check for selector conflicts (same selector, different styles)
-                else if rule1.selector == rule2.selector && similarity < 0.99 {
-                    selector_conflicts.push(DuplicateRule {
-                        rule1: rule1.clone(),
-                        rule2: rule2.clone(),
-                        similarity,
-                        duplicate_type: DuplicateType::SelectorConflict {
-                            declaration_similarity: similarity,
-                        },
-                    });
-                }
-                // Check for style duplicates (different selector, same styles)
-                else if rule1.selector != rule2.selector && similarity >= self.threshold {
-                    style_duplicates.push(DuplicateRule {
+        let fingerprints: Vec<RuleFingerprint> =
+            self.rules.iter().map(RuleFingerprint::new).collect();
+        let mut candidates = self.candidate_pairs(&fingerprints);
+        candidates.retain(|&(i, j)| include(i, j));
+        let similarities: Vec<f64> = candidates
+            .par_iter()
+            .map(|&(i, j)| {
+                let (fp1, fp2) = (&fingerprints[i], &fingerprints[j]);
+                calculate_expanded_rule_similarity(
+                    fp1.selector,
+                    fp2.selector,
+                    &fp1.expanded,
+                    &fp2.expanded,
+                )
+            })
+            .collect();
+
+        for (&(i, j), similarity) in candidates.iter().zip(similarities) {
+            let (rule1, rule2) = (&self.rules[i], &self.rules[j]);
+            let sel_analysis1 = &fingerprints[i].analysis;
+            let sel_analysis2 = &fingerprints[j].analysis;
+
+            // Track BEM variations independently from similarity threshold.
+            if let (Some(bem1), Some(bem2)) = (&sel_analysis1.bem_parts, &sel_analysis2.bem_parts) {
+                if bem1.block == bem2.block && rule1.selector != rule2.selector {
+                    bem_variations.push(DuplicateRule {
                         rule1: rule1.clone(),
                         rule2: rule2.clone(),
+                        index1: i,
+                        index2: j,
                         similarity,
-                        duplicate_type: DuplicateType::StyleDuplicate {
-                            selector1: rule1.selector.clone(),
-                            selector2: rule2.selector.clone(),
+                        duplicate_type: DuplicateType::BemVariation {
+                            component: bem1.block.clone(),
                         },
                     });
                 }
+            }
 
-                // Check for specificity overrides
-                if sel_analysis1.overrides(&sel_analysis2)
-                    || sel_analysis2.overrides(&sel_analysis1)
-                {
-                    let (winner, loser) = if sel_analysis1.overrides(&sel_analysis2) {
-                        (&rule1.selector, &rule2.selector)
-                    } else {
-                        (&rule2.selector, &rule1.selector)
-                    };
+            // The same selector under another at-rule applies in different conditions
+            let same_selector = rule1.selector == rule2.selector && rule1.at_rule == rule2.at_rule;
+
+            // Check for exact duplicates
+            if same_selector && similarity > 0.99 {
+                exact_duplicates.push(DuplicateRule {
+                    rule1: rule1.clone(),
+                    rule2: rule2.clone(),
+                    index1: i,
+                    index2: j,
+                    similarity,
+                    duplicate_type: DuplicateType::ExactDuplicate,
+                });
+            }
+            // Check for selector conflicts (same selector, different styles)
+            else if same_selector && similarity < 0.99 {
+                selector_conflicts.push(DuplicateRule {
+                    rule1: rule1.clone(),
+                    rule2: rule2.clone(),
+                    index1: i,
+                    index2: j,
+                    similarity,
+                    duplicate_type: DuplicateType::SelectorConflict {
+                        declaration_similarity: similarity,
+                    },
+                });
+            }
+            // Check for style duplicates (different selector, same styles)
+            else if rule1.selector != rule2.selector && similarity >= self.threshold {
+                style_duplicates.push(DuplicateRule {
+                    rule1: rule1.clone(),
+                    rule2: rule2.clone(),
+                    index1: i,
+                    index2: j,
+                    similarity,
+                    duplicate_type: DuplicateType::StyleDuplicate {
+                        selector1: rule1.selector.clone(),
+                        selector2: rule2.selector.clone(),
+                    },
+                });
+            }
 
-                    specificity_overrides.push(DuplicateRule {
-                        rule1: rule1.clone(),
-                        rule2: rule2.clone(),
-                        similarity,
-                        duplicate_type: DuplicateType::SpecificityOverride {
-                            winner: winner.clone(),
-                            loser: loser.clone(),
-                        },
-                    });
-                }
+            // Check for specificity overrides
+            if sel_analysis1.overrides(sel_analysis2) || sel_analysis2.overrides(sel_analysis1) {
+                let (winner, loser) = if sel_analysis1.overrides(sel_analysis2) {
+                    (&rule1.selector, &rule2.selector)
+                } else {
+                    (&rule2.selector, &rule1.selector)
+                };
+
+                specificity_overrides.push(DuplicateRule {
+                    rule1: rule1.clone(),
+                    rule2: rule2.clone(),
+                    index1: i,
+                    index2: j,
+                    similarity,
+                    duplicate_type: DuplicateType::SpecificityOverride {
+                        winner: winner.clone(),
+                        loser: loser.clone(),
+                    },
+                });
             }
         }
 
-        let summary =
-            self.generate_summary(&exact_duplicates, &selector_conflicts, &style_duplicates);
+        let summary = self.generate_summary(
+            &exact_duplicates,
+            &selector_conflicts,
+            &style_duplicates,
+            candidates.len(),
+        );
 
         DuplicateAnalysisResult {
             exact_duplicates,
@@ -160,12 +243,73 @@ impl DuplicateAnalyzer {
         }
     }
 
+    /// Pairs `(i, j)` with `i < j`, in the order a full double loop would visit them
+    fn candidate_pairs(&self, fingerprints: &[RuleFingerprint]) -> Vec<(usize, usize)> {
+        let mut pairs = HashSet::new();
+        let mut add_all = |members: &[usize], others: &[usize]| {
+            for &i in members {
+                for &j in others {
+                    if i != j {
+                        pairs.insert((i.min(j), i.max(j)));
+                    }
+                }
+            }
+        };
+
+        // Same selector key or BEM block: compared regardless of declarations
+        let mut by_selector: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut by_block: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, fingerprint) in fingerprints.iter().enumerate() {
+            by_selector.entry(fingerprint.selector).or_default().push(index);
+            if let Some(bem) = &fingerprint.analysis.bem_parts {
+                by_block.entry(bem.block.as_str()).or_default().push(index);
+            }
+        }
+        for members in by_selector.values().chain(by_block.values()) {
+            add_all(members, members);
+        }
+
+        // Property-set buckets, smallest sets first
+        let mut by_properties: HashMap<&BTreeSet<String>, Vec<usize>> = HashMap::new();
+        for (index, fingerprint) in fingerprints.iter().enumerate() {
+            by_properties.entry(&fingerprint.properties).or_default().push(index);
+        }
+        let mut buckets: Vec<(&BTreeSet<String>, Vec<usize>)> = by_properties.into_iter().collect();
+        buckets.sort_by_key(|(properties, members)| (properties.len(), members[0]));
+
+        for (a, (properties_a, members_a)) in buckets.iter().enumerate() {
+            for (properties_b, members_b) in &buckets[a..] {
+                // Declaration-count band: buckets only grow from here on
+                if !properties_b.is_empty()
+                    && max_similarity(properties_a.len(), properties_b.len()) + EPSILON
+                        < self.threshold
+                {
+                    break;
+                }
+                let shared = properties_a.intersection(properties_b).count();
+                let bound = if properties_b.is_empty() {
+                    max_similarity(1, 1)
+                } else {
+                    max_similarity(shared, properties_a.len().max(properties_b.len()))
+                };
+                if bound + EPSILON >= self.threshold {
+                    add_all(members_a, members_b);
+                }
+            }
+        }
+
+        let mut pairs: Vec<(usize, usize)> = pairs.into_iter().collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
     /// Generate a summary of the analysis
     fn generate_summary(
         &self,
         exact_duplicates: &[DuplicateRule],
         selector_conflicts: &[DuplicateRule],
         style_duplicates: &[DuplicateRule],
+        compared_pairs: usize,
     ) -> DuplicateSummary {
         let mut selector_usage = HashMap::new();
         for rule in &self.rules {
@@ -181,6 +325,7 @@ impl DuplicateAnalyzer {
             selector_conflict_count: selector_conflicts.len(),
             style_duplicate_count: style_duplicates.len(),
             repeated_selectors,
+            compared_pairs,
         }
     }
 
@@ -191,14 +336,16 @@ impl DuplicateAnalyzer {
         // Exact duplicates
         if !result.exact_duplicates.is_empty() {
             recommendations.push(format!(
-                "Found {} exact duplicate rules that can be safely removed",
+                "Found {} exact duplicate rules that can be removed",
                 result.exact_duplicates.len()
             ));
 
             for dup in &result.exact_duplicates {
                 recommendations.push(format!(
-                    "  - Remove duplicate '{}' at line {}",
-                    dup.rule2.selector, dup.rule2.start_line
+                    "  - Remove duplicate '{}' at line {} ({})",
+                    dup.rule2.selector,
+                    dup.rule2.start_line,
+                    check_merge_safety(&self.rules, dup).safety
                 ));
             }
         }
@@ -236,10 +383,11 @@ impl DuplicateAnalyzer {
                 if let DuplicateType::StyleDuplicate { selector1, selector2 } = &dup.duplicate_type
                 {
                     recommendations.push(format!(
-                        "  - '{}' and '{}' have {:.0}% similar styles",
+                        "  - '{}' and '{}' have {:.0}% similar styles ({})",
                         selector1,
                         selector2,
-                        dup.similarity * 100.0
+                        dup.similarity * 100.0,
+                        check_merge_safety(&self.rules, dup).safety
                     ));
                 }
             }
@@ -291,6 +439,8 @@ pub struct DuplicateSummary {
     pub selector_conflict_count: usize,
     pub style_duplicate_count: usize,
     pub repeated_selectors: Vec<(String, usize)>,
+    /// Rule pairs left after candidate pruning
+    pub compared_pairs: usize,
 }
 
 #[cfg(test)]
@@ -310,6 +460,8 @@ mod tests {
             tree: Rc::new(TreeNode::new(selector.to_string(), String::new(), 0)),
             start_line: line,
             end_line: line + declarations.len(),
+            file_path: "test.css".to_string(),
+            at_rule: None,
         }
     }
 
@@ -328,6 +480,23 @@ mod tests {
         assert_eq!(result.exact_duplicates[0].duplicate_type, DuplicateType::ExactDuplicate);
     }
 
+    #[test]
+    fn test_same_selector_under_another_at_rule_is_not_a_duplicate() {
+        let mut responsive = create_test_rule(".btn", vec![("color", "blue")], 5);
+        responsive.at_rule = Some("@media (min-width: 768px)".to_string());
+        let rules = vec![
+            create_test_rule(".btn", vec![("color", "blue")], 1),
+            responsive.clone(),
+            responsive,
+        ];
+
+        let result = DuplicateAnalyzer::new(rules, 0.8).analyze();
+
+        assert_eq!(result.exact_duplicates.len(), 1);
+        assert_eq!(result.exact_duplicates[0].index1, 1);
+        assert!(result.selector_conflicts.is_empty());
+    }
+
     #[test]
     fn test_style_duplicate_detection() {
         let rules = vec![
@@ -351,6 +520,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_pruning_keeps_every_style_duplicate() {
+        let rules = vec![
+            create_test_rule(".card", vec![("padding", "20px"), ("background", "white")], 1),
+            create_test_rule(".panel", vec![("padding", "20px"), ("background", "#fff")], 5),
+            create_test_rule(
+                ".tile",
+                vec![("padding", "20px"), ("background", "white"), ("border", "0")],
+                9,
+            ),
+            create_test_rule(".link", vec![("color", "blue")], 13),
+            create_test_rule(".anchor", vec![("color", "navy")], 15),
+            create_test_rule(".grid", vec![("display", "grid"), ("gap", "8px")], 17),
+            create_test_rule(".card", vec![("padding", "16px"), ("background", "white")], 21),
+        ];
+        let threshold = 0.6;
+
+        let result = DuplicateAnalyzer::new(rules.clone(), threshold).analyze();
+
+        let mut expected = Vec::new();
+        for i in 0..rules.len() {
+            for j in (i + 1)..rules.len() {
+                let similarity = crate::calculate_rule_similarity(&rules[i], &rules[j]);
+                if rules[i].selector != rules[j].selector && similarity >= threshold {
+                    expected.push((rules[i].start_line, rules[j].start_line, similarity));
+                }
+            }
+        }
+        let found: Vec<_> = result
+            .style_duplicates
+            .iter()
+            .map(|dup| (dup.rule1.start_line, dup.rule2.start_line, dup.similarity))
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(found, expected);
+        assert_eq!(result.selector_conflicts.len(), 1);
+        assert!(result.summary.compared_pairs < rules.len() * (rules.len() - 1) / 2);
+    }
+
     #[test]
     fn test_recommendations() {
         let rules = vec![
@@ -365,5 +573,7 @@ mod tests {
 
         assert!(!recommendations.is_empty());
         assert!(recommendations[0].contains("exact duplicate"));
+        // No rule sits between the two copies
+        assert!(recommendations[1].ends_with("(safe)"));
     }
 }
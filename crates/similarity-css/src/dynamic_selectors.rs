@@ -0,0 +1,161 @@
+//! Selectors generated at build or run time.
+//!
+//! Interpolated selectors (`.icon-#{$name}` in SCSS, `.icon-@{name}` in Less,
+//! `.${prefix}-button` in CSS-in-JS templates) and class names hashed by
+//! CSS-in-JS libraries and CSS Modules (`.css-1hxv4y3`, `.sc-bdVaJa`,
+//! `.jsx-2813702845`, `.Button_root__3xZ2a`) do not spell out the selector a
+//! page will use, so comparing their text is meaningless. They can be left
+//! out, or compared as templates with every generated part replaced by `*`.
+
+use crate::css_rule_converter::create_rule_tree_node;
+use crate::CssRule;
+
+/// Placeholder for the generated parts of a selector template
+const PLACEHOLDER: &str = "*";
+
+/// How rules with dynamic selectors are analyzed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DynamicSelectors {
+    /// Leave the rules out
+    Skip,
+    /// Compare the selectors with generated parts replaced by a placeholder
+    Template,
+}
+
+impl DynamicSelectors {
+    /// The rule as it is analyzed, `None` when it is left out. Rules with
+    /// hand-written selectors are returned unchanged.
+    pub fn apply(self, mut rule: CssRule) -> Option<CssRule> {
+        if !is_dynamic_selector(&rule.selector) {
+            return Some(rule);
+        }
+        match self {
+            DynamicSelectors::Skip => None,
+            DynamicSelectors::Template => {
+                rule.selector = selector_template(&rule.selector);
+                rule.tree = create_rule_tree_node(&rule.selector, &rule.declarations);
+                Some(rule)
+            }
+        }
+    }
+}
+
+/// Whether the selector is interpolated or contains a generated class name
+pub fn is_dynamic_selector(selector: &str) -> bool {
+    selector_template(selector) != selector
+}
+
+/// The selector with interpolations and class name hashes replaced by `*`
+pub fn selector_template(selector: &str) -> String {
+    let mut template = String::with_capacity(selector.len());
+    let mut rest = selector;
+    while !rest.is_empty() {
+        if let Some(length) = interpolation_length(rest) {
+            template.push_str(PLACEHOLDER);
+            rest = &rest[length..];
+        } else if let Some(class) = rest.strip_prefix('.') {
+            let end = class
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(class.len());
+            template.push('.');
+            template.push_str(&class_template(&class[..end]));
+            rest = &class[end..];
+        } else {
+            let next = rest.chars().next().expect("rest is not empty");
+            template.push(next);
+            rest = &rest[next.len_utf8()..];
+        }
+    }
+    template
+}
+
+/// Length of a `#{...}`, `@{...}` or `${...}` interpolation at the start of `text`
+fn interpolation_length(text: &str) -> Option<usize> {
+    if !["#{", "@{", "${"].iter().any(|opening| text.starts_with(opening)) {
+        return None;
+    }
+    let mut depth = 0;
+    for (index, c) in text.char_indices().skip(1) {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The class name with a generated hash replaced by the placeholder
+fn class_template(class: &str) -> String {
+    // emotion `css-1hxv4y3` (optionally `-Label`), styled-jsx `jsx-2813702845`
+    for (prefix, is_hash) in [("css-", is_lowercase_hash as fn(&str) -> bool), ("jsx-", is_number)]
+    {
+        if let Some(rest) = class.strip_prefix(prefix) {
+            let (hash, label) = rest.split_at(rest.find('-').unwrap_or(rest.len()));
+            if is_hash(hash) {
+                return format!("{prefix}{PLACEHOLDER}{label}");
+            }
+        }
+    }
+    // styled-components `sc-bdVaJa`
+    if let Some(rest) = class.strip_prefix("sc-") {
+        if rest.len() >= 5
+            && rest.chars().all(|c| c.is_ascii_alphabetic())
+            && rest.chars().any(|c| c.is_ascii_uppercase())
+        {
+            return format!("sc-{PLACEHOLDER}");
+        }
+    }
+    // CSS Modules `[name]_[local]__[hash:base64:5]`
+    if let Some((local, hash)) = class.rsplit_once("__") {
+        if is_base64_hash(hash) {
+            return format!("{local}__{PLACEHOLDER}");
+        }
+    }
+    class.to_string()
+}
+
+fn is_lowercase_hash(text: &str) -> bool {
+    text.len() >= 5
+        && text.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && text.chars().any(|c| c.is_ascii_digit())
+}
+
+fn is_number(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Mixed-case hashes with a digit, so BEM elements such as `card__col12` are
+/// not mistaken for one
+fn is_base64_hash(text: &str) -> bool {
+    (5..=8).contains(&text.len())
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && text.chars().any(|c| c.is_ascii_digit())
+        && text.chars().any(|c| c.is_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_parts_become_placeholders() {
+        assert_eq!(selector_template(".icon-#{$name}"), ".icon-*");
+        assert_eq!(selector_template(".icon-@{name}:hover"), ".icon-*:hover");
+        assert_eq!(selector_template(".${prefix}-btn > .${prefix}-label"), ".*-btn > .*-label");
+        assert_eq!(selector_template("#{map-get($m, #{$k})} a"), "* a");
+        assert_eq!(selector_template(".css-1hxv4y3-Button"), ".css-*-Button");
+        assert_eq!(selector_template("div.jsx-2813702845"), "div.jsx-*");
+        assert_eq!(selector_template(".sc-bdVaJa .Button_root__3xZ2a"), ".sc-* .Button_root__*");
+
+        for selector in [".card__title", ".grid__col12", ".css-grid", ".sc-header", ".btn--primary"]
+        {
+            assert!(!is_dynamic_selector(selector), "{selector}");
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! Incremental duplicate analysis for watch mode and build-tool plugins.
+//!
+//! Rules are indexed per file and findings per file pair. Updating a file
+//! re-analyzes only the pairs involving that file and returns which findings
+//! appeared or disappeared, so a Vite or webpack plugin can report just the
+//! changes instead of the whole analysis.
+
+use crate::{convert_to_css_rule, CssParser, CssRule, DuplicateAnalyzer, DuplicateRule};
+use serde::Serialize;
+use similarity_core::language_parser::LanguageParser;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    ExactDuplicate,
+    SelectorConflict,
+    StyleDuplicate,
+    BemVariation,
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FindingKind::ExactDuplicate => "exact duplicate",
+            FindingKind::SelectorConflict => "selector conflict",
+            FindingKind::StyleDuplicate => "style duplicate",
+            FindingKind::BemVariation => "BEM variation",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct RuleLocation {
+    pub file: String,
+    pub selector: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl RuleLocation {
+    fn new(file: &str, rule: &CssRule) -> Self {
+        Self {
+            file: file.to_string(),
+            selector: rule.selector.clone(),
+            start_line: rule.start_line,
+            end_line: rule.end_line,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub rule1: RuleLocation,
+    pub rule2: RuleLocation,
+    pub similarity: f64,
+}
+
+impl Finding {
+    /// Identity of the finding; a changed similarity alone is not a new finding
+    fn key(&self) -> (FindingKind, &RuleLocation, &RuleLocation) {
+        (self.kind, &self.rule1, &self.rule2)
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ({}:{}) ~ {} ({}:{}) {:.2}%",
+            self.kind,
+            self.rule1.selector,
+            self.rule1.file,
+            self.rule1.start_line,
+            self.rule2.selector,
+            self.rule2.file,
+            self.rule2.start_line,
+            self.similarity * 100.0
+        )
+    }
+}
+
+/// Findings that appeared or disappeared after a file changed
+#[derive(Debug, Default, Serialize)]
+pub struct FindingDelta {
+    pub added: Vec<Finding>,
+    pub removed: Vec<Finding>,
+}
+
+impl FindingDelta {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn extend(&mut self, other: FindingDelta) {
+        self.added.extend(other.added);
+        self.removed.extend(other.removed);
+    }
+}
+
+/// Keeps the analysis of a set of stylesheets up to date as they change
+pub struct IncrementalAnalyzer {
+    threshold: f64,
+    rules: BTreeMap<String, Vec<CssRule>>,
+    /// Findings keyed by file pair, smaller path first
+    findings: BTreeMap<(String, String), Vec<Finding>>,
+    css_parser: CssParser,
+    scss_parser: CssParser,
+}
+
+impl IncrementalAnalyzer {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            rules: BTreeMap::new(),
+            findings: BTreeMap::new(),
+            css_parser: CssParser::new(),
+            scss_parser: CssParser::new_scss(),
+        }
+    }
+
+    /// Add or replace a stylesheet (`.scss` paths are parsed as SCSS)
+    pub fn update_file(&mut self, path: &str, content: &str) -> Result<FindingDelta, String> {
+        let parser =
+            if path.ends_with(".scss") { &mut self.scss_parser } else { &mut self.css_parser };
+        let functions = parser.extract_functions(content, path).map_err(|e| e.to_string())?;
+        let rules = functions.iter().map(|func| convert_to_css_rule(func, content, path)).collect();
+        self.rules.insert(path.to_string(), rules);
+
+        let previous = self.take_findings_of(path);
+        let others: Vec<String> = self.rules.keys().cloned().collect();
+        for other in others {
+            let key = if other.as_str() <= path {
+                (other, path.to_string())
+            } else {
+                (path.to_string(), other)
+            };
+            let findings = pair_findings(
+                self.threshold,
+                &key.0,
+                &self.rules[&key.0],
+                &key.1,
+                &self.rules[&key.1],
+            );
+            if !findings.is_empty() {
+                self.findings.insert(key, findings);
+            }
+        }
+
+        let current: Vec<&Finding> = self.findings_of(path).collect();
+        let previous_keys: HashSet<_> = previous.iter().map(Finding::key).collect();
+        let current_keys: HashSet<_> = current.iter().map(|finding| finding.key()).collect();
+        let added = current
+            .iter()
+            .filter(|finding| !previous_keys.contains(&finding.key()))
+            .map(|finding| (*finding).clone())
+            .collect();
+        let removed = previous
+            .iter()
+            .filter(|finding| !current_keys.contains(&finding.key()))
+            .cloned()
+            .collect();
+        Ok(FindingDelta { added, removed })
+    }
+
+    /// Forget a deleted stylesheet
+    pub fn remove_file(&mut self, path: &str) -> FindingDelta {
+        self.rules.remove(path);
+        FindingDelta { added: Vec::new(), removed: self.take_findings_of(path) }
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.rules.keys().map(String::as_str)
+    }
+
+    pub fn findings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.values().flatten()
+    }
+
+    fn findings_of<'a>(&'a self, path: &'a str) -> impl Iterator<Item = &'a Finding> {
+        self.findings
+            .iter()
+            .filter(move |((file1, file2), _)| file1 == path || file2 == path)
+            .flat_map(|(_, findings)| findings)
+    }
+
+    fn take_findings_of(&mut self, path: &str) -> Vec<Finding> {
+        let keys: Vec<(String, String)> = self
+            .findings
+            .keys()
+            .filter(|(file1, file2)| file1 == path || file2 == path)
+            .cloned()
+            .collect();
+        keys.iter().filter_map(|key| self.findings.remove(key)).flatten().collect()
+    }
+}
+
+/// Findings between the rules of two files, or within one file when both are the same
+fn pair_findings(
+    threshold: f64,
+    file1: &str,
+    rules1: &[CssRule],
+    file2: &str,
+    rules2: &[CssRule],
+) -> Vec<Finding> {
+    let split = rules1.len();
+    let result = if file1 == file2 {
+        DuplicateAnalyzer::new(rules1.to_vec(), threshold).analyze()
+    } else {
+        let rules = rules1.iter().chain(rules2).cloned().collect();
+        // Each pair has its first rule in file1 and its second in file2
+        DuplicateAnalyzer::new(rules, threshold).analyze_where(|i, j| i < split && j >= split)
+    };
+
+    let to_finding = |kind: FindingKind, dup: &DuplicateRule| Finding {
+        kind,
+        rule1: RuleLocation::new(file1, &dup.rule1),
+        rule2: RuleLocation::new(file2, &dup.rule2),
+        similarity: dup.similarity,
+    };
+    let categories = [
+        (FindingKind::ExactDuplicate, &result.exact_duplicates),
+        (FindingKind::SelectorConflict, &result.selector_conflicts),
+        (FindingKind::StyleDuplicate, &result.style_duplicates),
+        (FindingKind::BemVariation, &result.bem_variations),
+    ];
+    categories
+        .into_iter()
+        .flat_map(|(kind, duplicates)| duplicates.iter().map(move |dup| to_finding(kind, dup)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUTTONS: &str = ".btn {\n  padding: 8px;\n  color: white;\n  background: blue;\n}\n";
+    const LINKS: &str = ".link {\n  padding: 8px;\n  color: white;\n  background: blue;\n}\n";
+
+    #[test]
+    fn test_updates_report_only_changed_findings() {
+        let mut analyzer = IncrementalAnalyzer::new(0.8);
+        assert!(analyzer.update_file("buttons.css", BUTTONS).unwrap().is_empty());
+
+        let delta = analyzer.update_file("links.css", LINKS).unwrap();
+        assert_eq!(delta.added.len(), 1);
+        assert!(delta.removed.is_empty());
+        let finding = &delta.added[0];
+        assert_eq!(finding.kind, FindingKind::StyleDuplicate);
+        assert_eq!(finding.rule1.file, "buttons.css");
+        assert_eq!(finding.rule2.file, "links.css");
+
+        // Saving without a relevant change reports nothing
+        assert!(analyzer.update_file("links.css", LINKS).unwrap().is_empty());
+
+        let delta = analyzer
+            .update_file("links.css", ".link {\n  text-decoration: underline;\n}\n")
+            .unwrap();
+        assert!(delta.added.is_empty());
+        assert_eq!(delta.removed.len(), 1);
+        assert_eq!(analyzer.findings().count(), 0);
+    }
+
+    #[test]
+    fn test_removing_a_file_drops_its_findings() {
+        let mut analyzer = IncrementalAnalyzer::new(0.8);
+        analyzer.update_file("buttons.css", BUTTONS).unwrap();
+        analyzer.update_file("links.css", LINKS).unwrap();
+        analyzer.update_file("more.css", &format!("{BUTTONS}{BUTTONS}")).unwrap();
+        let total = analyzer.findings().count();
+
+        let delta = analyzer.remove_file("more.css");
+        assert!(delta.added.is_empty());
+        assert_eq!(analyzer.findings().count(), total - delta.removed.len());
+        assert!(delta.removed.iter().any(|finding| finding.kind == FindingKind::ExactDuplicate));
+        assert_eq!(analyzer.files().collect::<Vec<_>>(), ["buttons.css", "links.css"]);
+    }
+}
@@ -0,0 +1,242 @@
+//! `@keyframes` animations as first-class entities.
+//!
+//! Each animation is normalized to its steps in offset order (`from` is `0%`,
+//! `to` is `100%`, and a step listing several offsets is split into one step
+//! per offset), so two animations compare equal regardless of their name and
+//! how their steps were written.
+
+use crate::css_comparator::calculate_declaration_similarity;
+use serde::Serialize;
+use tree_sitter::Node;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyframeStep {
+    /// Offset in percent
+    pub offset: f64,
+    pub declarations: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Keyframes {
+    pub name: String,
+    pub file: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Steps sorted by offset
+    pub steps: Vec<KeyframeStep>,
+}
+
+impl Keyframes {
+    fn step_at(&self, offset: f64) -> Option<&Vec<(String, String)>> {
+        self.steps.iter().find(|step| step.offset == offset).map(|step| &step.declarations)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyframesDuplicate {
+    pub first: Keyframes,
+    pub second: Keyframes,
+    pub similarity: f64,
+}
+
+impl KeyframesDuplicate {
+    /// The same animation registered under another name
+    pub fn is_renamed_copy(&self) -> bool {
+        self.similarity >= 1.0 && self.first.name != self.second.name
+    }
+}
+
+/// Collect the `@keyframes` blocks below `node`
+pub(crate) fn extract_keyframes(
+    node: &Node,
+    source: &str,
+    file: &str,
+    keyframes: &mut Vec<Keyframes>,
+) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.kind() != "keyframes_statement" {
+            extract_keyframes(&child, source, file, keyframes);
+            continue;
+        }
+        let text = |node: Node| node.utf8_text(source.as_bytes()).unwrap_or("").trim().to_string();
+        let mut name = String::new();
+        let mut steps: Vec<KeyframeStep> = Vec::new();
+
+        let mut statement_cursor = child.walk();
+        for part in child.children(&mut statement_cursor) {
+            match part.kind() {
+                "keyframes_name" => name = text(part),
+                "keyframe_block_list" => {
+                    let mut block_cursor = part.walk();
+                    for block in part.children(&mut block_cursor) {
+                        if block.kind() == "keyframe_block" {
+                            add_block_steps(&block, source, &mut steps);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        steps.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+        keyframes.push(Keyframes {
+            name,
+            file: file.to_string(),
+            start_line: child.start_position().row + 1,
+            end_line: child.end_position().row + 1,
+            steps,
+        });
+    }
+}
+
+fn add_block_steps(block: &Node, source: &str, steps: &mut Vec<KeyframeStep>) {
+    let mut offsets = Vec::new();
+    let mut declarations = Vec::new();
+
+    let mut cursor = block.walk();
+    for child in block.children(&mut cursor) {
+        match child.kind() {
+            "from" => offsets.push(0.0),
+            "to" => offsets.push(100.0),
+            "integer_value" | "float_value" => {
+                let text = child.utf8_text(source.as_bytes()).unwrap_or("");
+                if let Ok(offset) = text.trim_end_matches('%').parse::<f64>() {
+                    offsets.push(offset);
+                }
+            }
+            "block" => {
+                let mut decl_cursor = child.walk();
+                for decl in child.children(&mut decl_cursor) {
+                    if decl.kind() != "declaration" {
+                        continue;
+                    }
+                    let text = decl.utf8_text(source.as_bytes()).unwrap_or("");
+                    if let Some((property, value)) = text.split_once(':') {
+                        declarations.push((
+                            property.trim().to_lowercase(),
+                            value.trim().trim_end_matches(';').trim().to_lowercase(),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for offset in offsets {
+        // A repeated offset extends the earlier step, later declarations winning
+        match steps.iter_mut().find(|step| step.offset == offset) {
+            Some(step) => step.declarations.extend(declarations.iter().cloned()),
+            None => steps.push(KeyframeStep { offset, declarations: declarations.clone() }),
+        }
+    }
+}
+
+/// Similarity of two animations: declaration similarity averaged over all offsets of either
+pub fn keyframes_similarity(a: &Keyframes, b: &Keyframes) -> f64 {
+    let mut offsets: Vec<f64> = a.steps.iter().chain(&b.steps).map(|step| step.offset).collect();
+    offsets.sort_by(f64::total_cmp);
+    offsets.dedup();
+    if offsets.is_empty() {
+        return 1.0;
+    }
+
+    let total: f64 = offsets
+        .iter()
+        .map(|&offset| match (a.step_at(offset), b.step_at(offset)) {
+            (Some(decls1), Some(decls2)) => calculate_declaration_similarity(decls1, decls2),
+            _ => 0.0,
+        })
+        .sum();
+    total / offsets.len() as f64
+}
+
+/// Pairs of animations at least `threshold` similar, most similar first
+pub fn find_duplicate_keyframes(
+    keyframes: &[Keyframes],
+    threshold: f64,
+) -> Vec<KeyframesDuplicate> {
+    let mut duplicates = Vec::new();
+    for (i, first) in keyframes.iter().enumerate() {
+        for second in &keyframes[i + 1..] {
+            let similarity = keyframes_similarity(first, second);
+            if similarity >= threshold {
+                duplicates.push(KeyframesDuplicate {
+                    first: first.clone(),
+                    second: second.clone(),
+                    similarity,
+                });
+            }
+        }
+    }
+    duplicates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CssParser;
+
+    fn keyframes_in(css: &str, file: &str) -> Vec<Keyframes> {
+        CssParser::new().extract_keyframes(css, file).unwrap()
+    }
+
+    #[test]
+    fn test_steps_are_normalized() {
+        let css = r#"
+@keyframes pulse {
+  to { transform: scale(1); }
+  0%, 100% { opacity: 1; }
+  from { transform: scale(0.9); }
+  50% { opacity: 0.5; }
+}
+"#;
+        let keyframes = keyframes_in(css, "a.css");
+        assert_eq!(keyframes.len(), 1);
+        assert_eq!(keyframes[0].name, "pulse");
+        assert_eq!(keyframes[0].start_line, 2);
+        let offsets: Vec<f64> = keyframes[0].steps.iter().map(|step| step.offset).collect();
+        assert_eq!(offsets, [0.0, 50.0, 100.0]);
+        assert_eq!(
+            keyframes[0].steps[0].declarations,
+            [
+                ("opacity".to_string(), "1".to_string()),
+                ("transform".to_string(), "scale(0.9)".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_identical_animation_under_another_name() {
+        let mut keyframes = keyframes_in(
+            "@keyframes fade-in {\n  from { opacity: 0; }\n  to { opacity: 1; }\n}\n",
+            "a.css",
+        );
+        keyframes.extend(keyframes_in(
+            "@keyframes appear {\n  0% { opacity: 0; }\n  100% { opacity: 1; }\n}\n\
+             @keyframes spin {\n  from { transform: rotate(0deg); }\n  to { transform: rotate(360deg); }\n}\n",
+            "b.css",
+        ));
+
+        let duplicates = find_duplicate_keyframes(&keyframes, 0.8);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].first.name, "fade-in");
+        assert_eq!(duplicates[0].second.name, "appear");
+        assert_eq!(duplicates[0].second.file, "b.css");
+        assert!(duplicates[0].is_renamed_copy());
+    }
+
+    #[test]
+    fn test_near_duplicate_animation() {
+        let keyframes = keyframes_in(
+            "@keyframes slide {\n  from { transform: translateX(-100%); opacity: 0; }\n  to { transform: translateX(0); opacity: 1; }\n}\n\
+             @keyframes slide-in {\n  from { transform: translateX(-100%); opacity: 0; }\n  to { transform: translateX(0); opacity: 0.9; }\n}\n",
+            "a.css",
+        );
+        let similarity = keyframes_similarity(&keyframes[0], &keyframes[1]);
+        assert!(similarity > 0.8 && similarity < 1.0);
+        assert!(!find_duplicate_keyframes(&keyframes, 0.8)[0].is_renamed_copy());
+    }
+}
@@ -1,22 +1,49 @@
+pub mod bem_report;
 pub mod css_comparator;
 pub mod css_parser;
 pub mod css_rule_converter;
+pub mod declaration_filter;
+pub mod declaration_index;
+pub mod design_tokens;
 pub mod duplicate_analyzer;
+pub mod dynamic_selectors;
+pub mod incremental;
+pub mod keyframes;
+pub mod merge_safety;
 pub mod parser;
+pub mod scope;
 pub mod scss_flattener;
 pub mod scss_simple_flattener;
 pub mod shorthand_expander;
 pub mod specificity;
+pub mod specificity_war;
 
+pub use bem_report::{group_bem_components, BemComponent, BemMember};
 pub use css_comparator::{
     calculate_rule_similarity, compare_css_rules, CssRule, CssSimilarityResult, SerializableCssRule,
 };
 pub use css_rule_converter::{convert_to_css_rule, parse_css_to_rules};
+pub use declaration_filter::{DeclarationFilter, PropertyPattern};
+pub use declaration_index::{DeclarationIndex, DeclarationOccurrence, RepeatedDeclaration};
+pub use design_tokens::{
+    extract_design_tokens, root_block, DesignToken, TokenCategory, TokenOccurrence,
+};
 pub use duplicate_analyzer::{
     DuplicateAnalysisResult, DuplicateAnalyzer, DuplicateRule, DuplicateType,
     SerializableDuplicateRule,
 };
+pub use dynamic_selectors::{is_dynamic_selector, selector_template, DynamicSelectors};
+pub use incremental::{Finding, FindingDelta, FindingKind, IncrementalAnalyzer, RuleLocation};
+pub use keyframes::{
+    find_duplicate_keyframes, keyframes_similarity, KeyframeStep, Keyframes, KeyframesDuplicate,
+};
+pub use merge_safety::{check_merge_safety, MergeSafety, MergeSafetyReport};
 pub use parser::CssParser;
+pub use scope::{
+    extract_vue_styles, is_cross_scope, rule_scope, stylesheet_scope, take_cross_scope_duplicates,
+    StyleScope, VueStyleBlock,
+};
 pub use scss_flattener::{flatten_scss_rules, FlatRule};
 pub use shorthand_expander::expand_shorthand_properties;
 pub use specificity::{calculate_specificity, SelectorAnalysis, Specificity};
+pub use specificity_war::{find_specificity_wars, SpecificityWar, WarStep, MIN_WAR_LENGTH};
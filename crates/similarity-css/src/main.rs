@@ -1,9 +1,20 @@
 use clap::Parser as ClapParser;
-use ignore::WalkBuilder;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use similarity_core::cli_file_utils::walk_dir;
 use similarity_core::css_structure_adapter::{CssBatchComparator, CssStructDef};
 use similarity_core::language_parser::LanguageParser;
-use similarity_css::{convert_to_css_rule, CssParser, DuplicateAnalyzer};
-use std::path::PathBuf;
+use similarity_core::{Edit, Fix};
+use similarity_css::{
+    check_merge_safety, convert_to_css_rule, extract_design_tokens, extract_vue_styles,
+    find_duplicate_keyframes, find_specificity_wars, group_bem_components, root_block, rule_scope,
+    stylesheet_scope, take_cross_scope_duplicates, BemComponent, CssParser, DeclarationFilter,
+    DeclarationIndex, DesignToken, DuplicateAnalyzer, DuplicateRule, DuplicateType,
+    DynamicSelectors, FindingDelta, IncrementalAnalyzer, KeyframesDuplicate, MergeSafety,
+    MergeSafetyReport, SpecificityWar, StyleScope,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 #[derive(ClapParser, Debug)]
 #[command(author, version, about = "Find similar CSS rules and declarations", long_about = None)]
@@ -18,7 +29,7 @@ struct Args {
         short,
         long,
         default_value = "standard",
-        help = "Output format (standard, vscode, json)"
+        help = "Output format (standard, vscode, json, stylelint)"
     )]
     output: String,
 
@@ -37,29 +48,146 @@ struct Args {
 
     #[arg(
         long,
+        visible_alias = "min-size",
         default_value = "3",
         help = "Minimum rule size (in declarations) to consider for comparison"
     )]
-    min_size: usize,
+    min_declarations: usize,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Declarations to ignore when comparing: property names, prefixes like animation-*, or vendor-prefixes, comments, custom-properties"
+    )]
+    ignore_properties: Vec<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Skip rules with interpolated or generated selectors (.icon-#{$name}, .css-1hxv4y3), or compare them as templates with the generated parts as *"
+    )]
+    dynamic_selectors: Option<DynamicSelectors>,
 
     #[arg(long, help = "Use structure-based comparison instead of AST-based comparison")]
     use_structure_comparison: bool,
+
+    #[arg(long, help = "Keep running and report findings added or removed as files change")]
+    watch: bool,
+
+    #[arg(
+        long,
+        help = "Report chains of rules escalating specificity or !important on the same elements"
+    )]
+    specificity_wars: bool,
+
+    #[arg(
+        long,
+        help = "Suggest custom-property tokens for colors, spacings, shadows and font stacks repeated across rules"
+    )]
+    tokens: bool,
+
+    #[arg(
+        long,
+        default_value = "3",
+        help = "Minimum number of occurrences of a value to suggest a token for it (with --tokens)"
+    )]
+    token_min_occurrences: usize,
+
+    #[arg(
+        long,
+        help = "Report the most repeated declarations (property: value) and the rules containing them"
+    )]
+    declaration_frequency: bool,
+
+    #[arg(
+        long,
+        default_value = "20",
+        help = "Number of declarations to list (with --declaration-frequency)"
+    )]
+    top: usize,
+
+    #[arg(
+        long,
+        help = "Report BEM blocks with the declarations their elements and modifiers share or change, and a componentization score"
+    )]
+    bem_report: bool,
+
+    #[arg(
+        long,
+        help = "Report duplicates between different component scopes (Vue scoped, CSS Modules, shadow DOM, Angular) separately"
+    )]
+    separate_scopes: bool,
+
+    #[arg(
+        long,
+        help = "Exclude directories matching the given patterns (can be specified multiple times)"
+    )]
+    exclude: Vec<String>,
+
+    #[arg(long, help = "Print the code of both rules of each duplicate")]
+    print: bool,
+
+    #[arg(long, help = "Exit with code 1 if duplicates are found")]
+    fail_on_duplicates: bool,
+}
+
+/// How often watch mode checks the stylesheets for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+fn create_exclude_matcher(exclude_patterns: &[String]) -> Option<GlobSet> {
+    if exclude_patterns.is_empty() {
+        return None;
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in exclude_patterns {
+        // Add the pattern as-is
+        if let Ok(glob) = Glob::new(pattern) {
+            builder.add(glob);
+        }
+
+        // If the pattern doesn't start with **, also match it in any directory
+        // and everything below it, so "legacy" excludes "src/legacy/a.css"
+        if !pattern.starts_with("**") {
+            let suffixed = format!("{}/**", pattern.trim_end_matches('/'));
+            for variant in [format!("**/{pattern}"), format!("**/{suffixed}"), suffixed] {
+                if let Ok(glob) = Glob::new(&variant) {
+                    builder.add(glob);
+                }
+            }
+        }
+    }
+
+    builder.build().ok()
+}
+
+/// Whether `path`, or its path relative to the current directory, is excluded
+fn is_excluded(matcher: &GlobSet, path: &Path) -> bool {
+    matcher.is_match(path)
+        || std::env::current_dir()
+            .ok()
+            .and_then(|current_dir| path.strip_prefix(current_dir).ok().map(Path::to_path_buf))
+            .is_some_and(|relative| matcher.is_match(relative))
 }
 
-fn find_files(path: &str, extension: &str) -> Vec<PathBuf> {
+/// Stylesheets under `path`, walked respecting .gitignore and .similarityignore
+fn find_files(path: &str, extension: &str, exclude: Option<&GlobSet>) -> Vec<PathBuf> {
     let mut files = Vec::new();
-    let target_path = std::path::Path::new(path);
+    let target_path = Path::new(path);
 
     if target_path.is_file() {
         if target_path.extension().and_then(|s| s.to_str()) == Some(extension) {
             files.push(target_path.to_path_buf());
         }
     } else if target_path.is_dir() {
-        let walker = WalkBuilder::new(target_path).follow_links(false).build();
+        let walker = walk_dir(target_path);
 
         for entry in walker.flatten() {
             let path = entry.path();
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some(extension) {
+            if path.is_file()
+                && path.extension().and_then(|s| s.to_str()) == Some(extension)
+                && !exclude.is_some_and(|matcher| is_excluded(matcher, path))
+            {
                 files.push(path.to_path_buf());
             }
         }
@@ -72,119 +200,288 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
     let extension = if args.scss { "scss" } else { &args.extension };
-    let files = find_files(&args.target, extension);
+    if args.watch {
+        return watch(&args, extension);
+    }
+    let exclude = create_exclude_matcher(&args.exclude);
+    let files = find_files(&args.target, extension, exclude.as_ref());
 
     if files.is_empty() {
         eprintln!("No {extension} files found in the specified path");
         return Ok(());
     }
 
-    // For now, just print files found
-    println!("Found {} {} files", files.len(), extension);
+    // Progress goes to stderr when stdout carries a JSON document
+    let machine_readable = matches!(args.output.as_str(), "json" | "stylelint");
+    let status = |message: String| {
+        if machine_readable {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+    };
+
+    status(format!("Found {} {} files", files.len(), extension));
     for file in &files {
-        println!("  {}", file.display());
+        status(format!("  {}", file.display()));
     }
 
-    // Parse all CSS/SCSS files
+    // Parse all CSS/SCSS files; `.vue` files contribute their `<style>` blocks
     let mut all_rules = Vec::new();
-    let mut parser = if args.scss { CssParser::new_scss() } else { CssParser::new() };
+    let mut scopes = Vec::new();
+    let mut all_keyframes = Vec::new();
+    let mut css_parser = CssParser::new();
+    let mut scss_parser = CssParser::new_scss();
 
     for file in &files {
         let content = std::fs::read_to_string(file)?;
         let file_str = file.to_string_lossy();
+        let stylesheets: Vec<(String, bool, bool)> = if extension == "vue" {
+            extract_vue_styles(&content)
+                .into_iter()
+                .map(|block| (block.content, block.is_scss, block.scoped))
+                .collect()
+        } else {
+            vec![(content, args.scss, false)]
+        };
 
-        match parser.extract_functions(&content, &file_str) {
-            Ok(functions) => {
-                for func in functions {
-                    let css_rule = convert_to_css_rule(&func, &content);
-                    all_rules.push((file_str.to_string(), css_rule));
+        for (content, is_scss, scoped) in stylesheets {
+            let parser = if is_scss { &mut scss_parser } else { &mut css_parser };
+            match parser.extract_functions(&content, &file_str) {
+                Ok(functions) => {
+                    let rules: Vec<_> = functions
+                        .iter()
+                        .map(|func| convert_to_css_rule(func, &content, &file_str))
+                        .collect();
+                    let stylesheet = stylesheet_scope(&file_str, scoped, &rules);
+                    for css_rule in rules {
+                        let css_rule = match args.dynamic_selectors {
+                            Some(mode) => match mode.apply(css_rule) {
+                                Some(css_rule) => css_rule,
+                                None => continue,
+                            },
+                            None => css_rule,
+                        };
+                        scopes.push(rule_scope(&css_rule.selector, &stylesheet));
+                        all_rules.push(css_rule);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error parsing {file_str}: {e}");
                 }
             }
-            Err(e) => {
-                eprintln!("Error parsing {file_str}: {e}");
+            if let Ok(keyframes) = parser.extract_keyframes(&content, &file_str) {
+                all_keyframes.extend(keyframes);
             }
         }
     }
 
     if all_rules.is_empty() {
-        println!("\nNo CSS rules found to analyze");
+        status("\nNo CSS rules found to analyze".to_string());
+        if args.output == "stylelint" {
+            output_stylelint(&files, &[])?;
+        }
         return Ok(());
     }
 
-    println!("\nFound {} CSS rules to analyze", all_rules.len());
+    status(format!("\nFound {} CSS rules to analyze", all_rules.len()));
+
+    if args.tokens {
+        let tokens = extract_design_tokens(
+            all_rules.iter().map(|rule| (rule.file_path.as_str(), rule)),
+            args.token_min_occurrences,
+        );
+        return output_tokens(&tokens, &args.output);
+    }
+
+    if args.declaration_frequency {
+        let index =
+            DeclarationIndex::build(all_rules.iter().map(|rule| (rule.file_path.as_str(), rule)));
+        return output_declaration_frequency(&index, args.top, &args.output);
+    }
+
+    if args.bem_report {
+        return output_bem_report(&group_bem_components(&all_rules), &args.output);
+    }
+
+    // Both engines compare the same rules: blocklisted declarations removed,
+    // rules left too small skipped
+    let filter = DeclarationFilter::new(args.min_declarations, &args.ignore_properties);
+    let mut compared_rules = Vec::new();
+    let mut compared_scopes = Vec::new();
+    for (rule, scope) in all_rules.iter().zip(&scopes) {
+        if let Some(rule) = filter.apply(rule) {
+            compared_rules.push(rule);
+            compared_scopes.push(scope.clone());
+        }
+    }
+    status(format!(
+        "Comparing {} rules with at least {} declarations",
+        compared_rules.len(),
+        args.min_declarations
+    ));
 
-    if args.use_structure_comparison {
+    let duplicate_count = if args.use_structure_comparison {
         // Use structure-based comparison
-        println!("\nUsing structure-based comparison...");
-        analyze_with_structure_comparison(&all_rules, args.threshold, &args.output)?;
+        status("\nUsing structure-based comparison...".to_string());
+        analyze_with_structure_comparison(&compared_rules, args.threshold, &args.output)?
     } else {
         // Analyze duplicates with traditional method
-        let css_rules: Vec<_> = all_rules.iter().map(|(_, rule)| rule.clone()).collect();
-        let analyzer = DuplicateAnalyzer::new(css_rules, args.threshold);
-        let result = analyzer.analyze();
+        let analyzer = DuplicateAnalyzer::new(compared_rules, args.threshold);
+        let mut result = analyzer.analyze();
+        // Most similar and largest duplicates first, as similarity-ts orders findings
+        for duplicates in [&mut result.exact_duplicates, &mut result.style_duplicates] {
+            duplicates.sort_by(|a, b| b.priority().total_cmp(&a.priority()));
+        }
+        let cross_scope = if args.separate_scopes {
+            take_cross_scope_duplicates(&mut result, &compared_scopes)
+        } else {
+            Vec::new()
+        };
+        let wars = args.specificity_wars.then(|| {
+            find_specificity_wars(all_rules.iter().map(|rule| (rule.file_path.as_str(), rule)))
+        });
+        let animations = find_duplicate_keyframes(&all_keyframes, args.threshold);
 
         // Output results
         match args.output.as_str() {
             "json" => {
-                output_json(&result, &all_rules)?;
+                output_json(
+                    &result,
+                    analyzer.rules(),
+                    &animations,
+                    wars.as_deref(),
+                    args.separate_scopes.then_some((&cross_scope[..], &compared_scopes[..])),
+                )?;
+            }
+            "stylelint" => {
+                let findings: Vec<(&str, &DuplicateRule)> = [
+                    ("similarity/exact-duplicate", &result.exact_duplicates),
+                    ("similarity/style-duplicate", &result.style_duplicates),
+                    ("similarity/selector-conflict", &result.selector_conflicts),
+                ]
+                .into_iter()
+                .flat_map(|(rule, duplicates)| duplicates.iter().map(move |dup| (rule, dup)))
+                .collect();
+                output_stylelint(&files, &findings)?;
             }
             "vscode" => {
-                output_vscode(&result, &all_rules);
+                output_vscode(&result, analyzer.rules());
+                output_cross_scope_vscode(&cross_scope, &compared_scopes);
+                output_keyframes_vscode(&animations);
+                if let Some(wars) = &wars {
+                    output_wars_vscode(wars);
+                }
             }
             _ => {
-                output_standard(&result, &all_rules, args.threshold);
+                output_standard(&result, analyzer.rules(), args.threshold, args.print);
+                output_keyframes_standard(&animations);
+                if args.separate_scopes {
+                    output_cross_scope_standard(&cross_scope, &compared_scopes);
+                }
+                if let Some(wars) = &wars {
+                    output_wars_standard(wars);
+                }
             }
         }
+        result.exact_duplicates.len() + result.style_duplicates.len() + animations.len()
+    };
+
+    // Exit with code 1 if duplicates found and --fail-on-duplicates is set
+    if args.fail_on_duplicates && duplicate_count > 0 {
+        std::process::exit(1);
     }
 
     Ok(())
 }
 
+/// Poll the stylesheets and print the findings each change adds or removes
+fn watch(args: &Args, extension: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut analyzer = IncrementalAnalyzer::new(args.threshold);
+    let mut modified: HashMap<PathBuf, SystemTime> = HashMap::new();
+    let exclude = create_exclude_matcher(&args.exclude);
+    eprintln!("Watching {} for changes to .{} files...", args.target, extension);
+
+    loop {
+        let files = find_files(&args.target, extension, exclude.as_ref());
+        let mut delta = FindingDelta::default();
+
+        for file in &files {
+            let Ok(mtime) = std::fs::metadata(file).and_then(|meta| meta.modified()) else {
+                continue;
+            };
+            if modified.get(file) == Some(&mtime) {
+                continue;
+            }
+            modified.insert(file.clone(), mtime);
+            let content = std::fs::read_to_string(file)?;
+            match analyzer.update_file(&file.to_string_lossy(), &content) {
+                Ok(change) => delta.extend(change),
+                Err(e) => eprintln!("Error parsing {}: {e}", file.display()),
+            }
+        }
+
+        let deleted: Vec<PathBuf> =
+            modified.keys().filter(|file| !files.contains(file)).cloned().collect();
+        for file in deleted {
+            modified.remove(&file);
+            delta.extend(analyzer.remove_file(&file.to_string_lossy()));
+        }
+
+        if !delta.is_empty() {
+            output_delta(&delta, &args.output)?;
+        }
+        std::thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+fn output_delta(delta: &FindingDelta, format: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if format == "json" {
+        // One JSON document per line, for build-tool plugins reading stdout
+        println!("{}", serde_json::to_string(delta)?);
+    } else {
+        for finding in &delta.removed {
+            println!("- {finding}");
+        }
+        for finding in &delta.added {
+            println!("+ {finding}");
+        }
+    }
+    Ok(())
+}
+
 fn output_standard(
     result: &similarity_css::DuplicateAnalysisResult,
-    all_rules: &[(String, similarity_css::CssRule)],
+    rules: &[similarity_css::CssRule],
     threshold: f64,
+    print: bool,
 ) {
     println!("\n=== CSS Similarity Analysis Results ===");
 
     if !result.exact_duplicates.is_empty() {
         println!("\n## Exact Duplicates Found: {}", result.exact_duplicates.len());
         for (i, dup) in result.exact_duplicates.iter().enumerate() {
-            let empty_string = String::new();
-            let file1 = all_rules
-                .iter()
-                .find(|(_, r)| r.selector == dup.rule1.selector)
-                .map(|(f, _)| f)
-                .unwrap_or(&empty_string);
-            let file2 = all_rules
-                .iter()
-                .find(|(_, r)| r.selector == dup.rule2.selector)
-                .map(|(f, _)| f)
-                .unwrap_or(&empty_string);
+            let (file1, file2) = (&dup.rule1.file_path, &dup.rule2.file_path);
 
             println!("\n{}. {} and {}", i + 1, dup.rule1.selector, dup.rule2.selector);
             println!("   Files: {file1} and {file2}");
+            print_at_rules(dup);
             println!(
                 "   Lines: {}-{} and {}-{}",
                 dup.rule1.start_line, dup.rule1.end_line, dup.rule2.start_line, dup.rule2.end_line
             );
+            print_merge_safety(&check_merge_safety(rules, dup));
+            if print {
+                show_rule_code(file1, &dup.rule1);
+                show_rule_code(file2, &dup.rule2);
+            }
         }
     }
 
     if !result.style_duplicates.is_empty() {
         println!("\n## Similar Styles Found: {}", result.style_duplicates.len());
         for (i, dup) in result.style_duplicates.iter().enumerate() {
-            let empty_string = String::new();
-            let file1 = all_rules
-                .iter()
-                .find(|(_, r)| r.selector == dup.rule1.selector)
-                .map(|(f, _)| f)
-                .unwrap_or(&empty_string);
-            let file2 = all_rules
-                .iter()
-                .find(|(_, r)| r.selector == dup.rule2.selector)
-                .map(|(f, _)| f)
-                .unwrap_or(&empty_string);
+            let (file1, file2) = (&dup.rule1.file_path, &dup.rule2.file_path);
 
             println!(
                 "\n{}. {} and {} (similarity: {:.2}%)",
@@ -194,10 +491,16 @@ fn output_standard(
                 dup.similarity * 100.0
             );
             println!("   Files: {file1} and {file2}");
+            print_at_rules(dup);
             println!(
                 "   Lines: {}-{} and {}-{}",
                 dup.rule1.start_line, dup.rule1.end_line, dup.rule2.start_line, dup.rule2.end_line
             );
+            print_merge_safety(&check_merge_safety(rules, dup));
+            if print {
+                show_rule_code(file1, &dup.rule1);
+                show_rule_code(file2, &dup.rule2);
+            }
         }
     }
 
@@ -216,92 +519,492 @@ fn output_standard(
 
     // Summary
     println!("\n## Summary");
-    println!("Total rules analyzed: {}", all_rules.len());
+    println!("Total rules analyzed: {}", rules.len());
     println!("Exact duplicates: {}", result.exact_duplicates.len());
     println!("Similar styles: {}", result.style_duplicates.len());
     println!("BEM components: {}", result.bem_variations.len());
+    let total_pairs = rules.len() * rules.len().saturating_sub(1) / 2;
+    println!("Rule pairs compared: {} of {}", result.summary.compared_pairs, total_pairs);
+}
+
+/// The enclosing at-rules of a pair, when either rule has one
+fn print_at_rules(dup: &DuplicateRule) {
+    if dup.rule1.at_rule.is_some() || dup.rule2.at_rule.is_some() {
+        let context = |rule: &similarity_css::CssRule| rule.at_rule.clone().unwrap_or("-".into());
+        println!("   At-rules: {} and {}", context(&dup.rule1), context(&dup.rule2));
+    }
+}
+
+/// Display the source lines of a rule
+fn show_rule_code(file_path: &str, rule: &similarity_css::CssRule) {
+    match std::fs::read_to_string(file_path) {
+        Ok(content) => {
+            let code: Vec<&str> = content
+                .lines()
+                .skip(rule.start_line.saturating_sub(1))
+                .take(rule.end_line + 1 - rule.start_line.max(1))
+                .collect();
+            println!(
+                "\n\x1b[36m--- {}:{} (lines {}-{}) ---\x1b[0m",
+                file_path, rule.selector, rule.start_line, rule.end_line
+            );
+            println!("{}", code.join("\n"));
+        }
+        Err(e) => {
+            eprintln!("Error reading file {file_path}: {e}");
+        }
+    }
+}
+
+fn output_tokens(tokens: &[DesignToken], format: &str) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        "json" => {
+            let output = serde_json::json!({ "root": root_block(tokens), "tokens": tokens });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        "vscode" => {
+            for token in tokens {
+                for occurrence in &token.occurrences {
+                    println!(
+                        "{}:{}:1: warning: Repeated {} {} in {} could use var({})",
+                        occurrence.file,
+                        occurrence.start_line,
+                        token.category,
+                        token.value,
+                        occurrence.property,
+                        token.name
+                    );
+                }
+            }
+        }
+        _ => {
+            println!("\n## Suggested Design Tokens: {}", tokens.len());
+            if tokens.is_empty() {
+                return Ok(());
+            }
+            println!("\n{}", root_block(tokens));
+            for token in tokens {
+                println!(
+                    "\n{}: {} ({}, {} occurrences)",
+                    token.name,
+                    token.value,
+                    token.category,
+                    token.occurrences.len()
+                );
+                for occurrence in &token.occurrences {
+                    println!(
+                        "   {} {{ {} }}  {}:{}",
+                        occurrence.selector,
+                        occurrence.property,
+                        occurrence.file,
+                        occurrence.start_line
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn output_cross_scope_standard(cross_scope: &[DuplicateRule], scopes: &[StyleScope]) {
+    println!("\n## Cross-Scope Duplicates (lower priority): {}", cross_scope.len());
+    for (i, dup) in cross_scope.iter().enumerate() {
+        println!(
+            "\n{}. {} and {} (similarity: {:.2}%)",
+            i + 1,
+            dup.rule1.selector,
+            dup.rule2.selector,
+            dup.similarity * 100.0
+        );
+        println!("   Scopes: {} and {}", scopes[dup.index1], scopes[dup.index2]);
+        println!("   Files: {} and {}", dup.rule1.file_path, dup.rule2.file_path);
+        println!(
+            "   Lines: {}-{} and {}-{}",
+            dup.rule1.start_line, dup.rule1.end_line, dup.rule2.start_line, dup.rule2.end_line
+        );
+    }
+}
+
+fn output_cross_scope_vscode(cross_scope: &[DuplicateRule], scopes: &[StyleScope]) {
+    for dup in cross_scope {
+        println!(
+            "{}:{}:1: warning: Cross-scope duplicate of {} in {} ({:.0}% similarity) at {}:{}",
+            dup.rule1.file_path,
+            dup.rule1.start_line,
+            dup.rule2.selector,
+            scopes[dup.index2],
+            dup.similarity * 100.0,
+            dup.rule2.file_path,
+            dup.rule2.start_line
+        );
+    }
+}
+
+fn print_merge_safety(report: &MergeSafetyReport) {
+    println!("   Merge: {}", report.safety);
+    for reason in &report.reasons {
+        println!("     - {reason}");
+    }
+}
+
+/// Rules listed per declaration in the standard output
+const MAX_LISTED_RULES: usize = 5;
+
+fn output_declaration_frequency(
+    index: &DeclarationIndex,
+    top: usize,
+    format: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let repeated = index.most_repeated(top);
+    match format {
+        "json" => {
+            let output = serde_json::json!({
+                "total_declarations": index.total_declarations(),
+                "distinct_declarations": index.distinct_declarations(),
+                "repeated_declarations": index.repeated_declarations(),
+                "most_repeated": repeated,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        "vscode" => {
+            for declaration in &repeated {
+                let first = &declaration.occurrences[0];
+                println!(
+                    "{}:{}:1: warning: Declaration '{}: {}' is repeated in {} rules",
+                    first.file,
+                    first.start_line,
+                    declaration.property,
+                    declaration.value,
+                    declaration.occurrences.len()
+                );
+            }
+        }
+        _ => {
+            let total = index.total_declarations();
+            println!("\n## Declaration Frequency");
+            println!(
+                "{} declarations, {} distinct; {} ({:.1}%) repeat an identical declaration",
+                total,
+                index.distinct_declarations(),
+                index.repeated_declarations(),
+                index.repeated_declarations() as f64 * 100.0 / total.max(1) as f64
+            );
+            for (i, declaration) in repeated.iter().enumerate() {
+                println!(
+                    "\n{}. {}: {} ({} rules)",
+                    i + 1,
+                    declaration.property,
+                    declaration.value,
+                    declaration.occurrences.len()
+                );
+                for occurrence in declaration.occurrences.iter().take(MAX_LISTED_RULES) {
+                    println!(
+                        "   {}  {}:{}",
+                        occurrence.selector, occurrence.file, occurrence.start_line
+                    );
+                }
+                if declaration.occurrences.len() > MAX_LISTED_RULES {
+                    println!(
+                        "   ... and {} more",
+                        declaration.occurrences.len() - MAX_LISTED_RULES
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn output_bem_report(
+    components: &[BemComponent],
+    format: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let declarations = |declarations: &[(String, String)]| {
+        declarations
+            .iter()
+            .map(|(property, value)| format!("{property}: {value}"))
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+    match format {
+        "json" => {
+            let output = serde_json::json!({ "bem_components": components });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        "vscode" => {
+            for component in components {
+                let first = &component.members[0];
+                println!(
+                    "{}:{}:1: warning: BEM block '{}' has {} rules sharing {} declarations (componentization score {:.2})",
+                    first.file,
+                    first.start_line,
+                    component.block,
+                    component.members.len(),
+                    component.shared.len(),
+                    component.componentization_score
+                );
+            }
+        }
+        _ => {
+            println!("\n## BEM Components: {}", components.len());
+            for (i, component) in components.iter().enumerate() {
+                println!(
+                    "\n{}. {} ({} rules, componentization score: {:.2})",
+                    i + 1,
+                    component.block,
+                    component.members.len(),
+                    component.componentization_score
+                );
+                if !component.shared.is_empty() {
+                    println!("   Shared: {}", declarations(&component.shared));
+                }
+                for member in &component.members {
+                    let at_rule = member
+                        .at_rule
+                        .as_ref()
+                        .map(|at_rule| format!(" ({at_rule})"))
+                        .unwrap_or_default();
+                    println!(
+                        "   {}{}  {}:{}",
+                        member.selector, at_rule, member.file, member.start_line
+                    );
+                    if !member.divergent.is_empty() {
+                        println!("      + {}", declarations(&member.divergent));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn output_keyframes_standard(animations: &[KeyframesDuplicate]) {
+    if animations.is_empty() {
+        return;
+    }
+    println!("\n## Duplicate Animations Found: {}", animations.len());
+    for (i, dup) in animations.iter().enumerate() {
+        let kind = if dup.is_renamed_copy() { " (same animation, different name)" } else { "" };
+        println!(
+            "\n{}. @keyframes {} and {} (similarity: {:.2}%){}",
+            i + 1,
+            dup.first.name,
+            dup.second.name,
+            dup.similarity * 100.0,
+            kind
+        );
+        println!("   Files: {} and {}", dup.first.file, dup.second.file);
+        println!(
+            "   Lines: {}-{} and {}-{}",
+            dup.first.start_line, dup.first.end_line, dup.second.start_line, dup.second.end_line
+        );
+    }
+}
+
+fn output_keyframes_vscode(animations: &[KeyframesDuplicate]) {
+    for dup in animations {
+        println!(
+            "{}:{}:1: warning: @keyframes {} is {:.0}% similar to {} at {}:{}",
+            dup.first.file,
+            dup.first.start_line,
+            dup.first.name,
+            dup.similarity * 100.0,
+            dup.second.name,
+            dup.second.file,
+            dup.second.start_line
+        );
+    }
+}
+
+fn output_wars_standard(wars: &[SpecificityWar]) {
+    println!("\n## Specificity Wars Found: {}", wars.len());
+    for (i, war) in wars.iter().enumerate() {
+        println!(
+            "\n{}. {} ({}): {} escalating rules",
+            i + 1,
+            war.target,
+            war.properties.join(", "),
+            war.steps.len()
+        );
+        for step in &war.steps {
+            let important = if step.important { " !important" } else { "" };
+            println!(
+                "   {}{}  {}  {}:{}",
+                step.specificity, important, step.selector, step.file, step.start_line
+            );
+        }
+    }
+}
+
+fn output_wars_vscode(wars: &[SpecificityWar]) {
+    for war in wars {
+        let Some(last) = war.steps.last() else {
+            continue;
+        };
+        println!(
+            "{}:{}:1: warning: Specificity war on {} ({}): {} escalating rules",
+            last.file,
+            last.start_line,
+            war.target,
+            war.properties.join(", "),
+            war.steps.len()
+        );
+    }
 }
 
 fn output_vscode(
     result: &similarity_css::DuplicateAnalysisResult,
-    all_rules: &[(String, similarity_css::CssRule)],
+    rules: &[similarity_css::CssRule],
 ) {
     // VSCode problem matcher format
-    let empty_string = String::new();
     for dup in &result.exact_duplicates {
-        let file1 = all_rules
-            .iter()
-            .find(|(_, r)| r.selector == dup.rule1.selector)
-            .map(|(f, _)| f)
-            .unwrap_or(&empty_string);
-        let file2 = all_rules
-            .iter()
-            .find(|(_, r)| r.selector == dup.rule2.selector)
-            .map(|(f, _)| f)
-            .unwrap_or(&empty_string);
+        let (file1, file2) = (&dup.rule1.file_path, &dup.rule2.file_path);
 
         println!(
-            "{}:{}:1: warning: Exact duplicate of {} at {}:{}",
-            file1, dup.rule1.start_line, dup.rule2.selector, file2, dup.rule2.start_line
+            "{}:{}:1: warning: Exact duplicate of {} at {}:{} (merge: {})",
+            file1,
+            dup.rule1.start_line,
+            dup.rule2.selector,
+            file2,
+            dup.rule2.start_line,
+            check_merge_safety(rules, dup).safety
         );
     }
 
     for dup in &result.style_duplicates {
-        let file1 = all_rules
-            .iter()
-            .find(|(_, r)| r.selector == dup.rule1.selector)
-            .map(|(f, _)| f)
-            .unwrap_or(&empty_string);
-        let file2 = all_rules
-            .iter()
-            .find(|(_, r)| r.selector == dup.rule2.selector)
-            .map(|(f, _)| f)
-            .unwrap_or(&empty_string);
+        let (file1, file2) = (&dup.rule1.file_path, &dup.rule2.file_path);
 
         println!(
-            "{}:{}:1: warning: Similar to {} ({:.0}% similarity) at {}:{}",
+            "{}:{}:1: warning: Similar to {} ({:.0}% similarity) at {}:{} (merge: {})",
             file1,
             dup.rule1.start_line,
             dup.rule2.selector,
             dup.similarity * 100.0,
             file2,
-            dup.rule2.start_line
+            dup.rule2.start_line,
+            check_merge_safety(rules, dup).safety
         );
     }
 }
 
+/// Results in the shape of stylelint's JSON formatter, one entry per analyzed
+/// file; each finding is reported on the later rule of its pair
+fn output_stylelint(
+    files: &[PathBuf],
+    findings: &[(&str, &DuplicateRule)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    use serde_json::json;
+
+    let mut warnings: BTreeMap<String, Vec<serde_json::Value>> =
+        files.iter().map(|file| (file.to_string_lossy().to_string(), Vec::new())).collect();
+    for &(rule, dup) in findings {
+        let message = match &dup.duplicate_type {
+            DuplicateType::ExactDuplicate => format!(
+                "Duplicate of {} at {}:{}",
+                dup.rule1.selector, dup.rule1.file_path, dup.rule1.start_line
+            ),
+            DuplicateType::SelectorConflict { declaration_similarity } => format!(
+                "Selector also declared at {}:{} with {:.0}% similar declarations",
+                dup.rule1.file_path,
+                dup.rule1.start_line,
+                declaration_similarity * 100.0
+            ),
+            _ => format!(
+                "Similar to {} at {}:{} ({:.0}% similarity)",
+                dup.rule1.selector,
+                dup.rule1.file_path,
+                dup.rule1.start_line,
+                dup.similarity * 100.0
+            ),
+        };
+        warnings.entry(dup.rule2.file_path.clone()).or_default().push(json!({
+            "line": dup.rule2.start_line,
+            "column": 1,
+            "endLine": dup.rule2.end_line,
+            "rule": rule,
+            "severity": "warning",
+            "text": format!("{message} ({rule})"),
+        }));
+    }
+
+    let results: Vec<_> = warnings
+        .into_iter()
+        .map(|(source, warnings)| {
+            json!({
+                "source": source,
+                "deprecations": [],
+                "invalidOptionWarnings": [],
+                "parseErrors": [],
+                "errored": false,
+                "warnings": warnings,
+            })
+        })
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&results)?);
+    Ok(())
+}
+
+/// Deleting the later copy of an exact duplicate, unless its lines are shared
+/// with another rule (nested or minified stylesheets)
+fn removal_fix(rules: &[similarity_css::CssRule], dup: &DuplicateRule) -> Vec<Fix> {
+    let rule = &rules[dup.index2];
+    let shares_lines = rules.iter().enumerate().any(|(index, other)| {
+        index != dup.index2
+            && other.file_path == rule.file_path
+            && other.start_line <= rule.end_line
+            && rule.start_line <= other.end_line
+    });
+    if shares_lines {
+        return Vec::new();
+    }
+    vec![Fix {
+        title: format!(
+            "Remove duplicate '{}' (same as line {})",
+            rule.selector, dup.rule1.start_line
+        ),
+        edits: vec![Edit::DeleteRange {
+            file: rule.file_path.clone(),
+            start_line: rule.start_line as u32,
+            end_line: rule.end_line as u32,
+        }],
+    }]
+}
+
 fn output_json(
     result: &similarity_css::DuplicateAnalysisResult,
-    all_rules: &[(String, similarity_css::CssRule)],
+    rules: &[similarity_css::CssRule],
+    animations: &[KeyframesDuplicate],
+    wars: Option<&[SpecificityWar]>,
+    cross_scope: Option<(&[DuplicateRule], &[StyleScope])>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use serde_json::json;
 
     let mut duplicates = Vec::new();
-    let empty_string = String::new();
 
     for dup in &result.exact_duplicates {
-        let file1 = all_rules
-            .iter()
-            .find(|(_, r)| r.selector == dup.rule1.selector)
-            .map(|(f, _)| f)
-            .unwrap_or(&empty_string);
-        let file2 = all_rules
-            .iter()
-            .find(|(_, r)| r.selector == dup.rule2.selector)
-            .map(|(f, _)| f)
-            .unwrap_or(&empty_string);
+        let (file1, file2) = (&dup.rule1.file_path, &dup.rule2.file_path);
 
+        let merge_safety = check_merge_safety(rules, dup);
+        let fixes = if merge_safety.safety == MergeSafety::Safe {
+            removal_fix(rules, dup)
+        } else {
+            Vec::new()
+        };
         duplicates.push(json!({
             "type": "exact",
+            "merge_safety": merge_safety,
+            "fixes": fixes,
             "rule1": {
                 "selector": dup.rule1.selector,
                 "file": file1,
+                "at_rule": dup.rule1.at_rule,
                 "start_line": dup.rule1.start_line,
                 "end_line": dup.rule1.end_line,
             },
             "rule2": {
                 "selector": dup.rule2.selector,
                 "file": file2,
+                "at_rule": dup.rule2.at_rule,
                 "start_line": dup.rule2.start_line,
                 "end_line": dup.rule2.end_line,
             }
@@ -309,29 +1012,23 @@ fn output_json(
     }
 
     for dup in &result.style_duplicates {
-        let file1 = all_rules
-            .iter()
-            .find(|(_, r)| r.selector == dup.rule1.selector)
-            .map(|(f, _)| f)
-            .unwrap_or(&empty_string);
-        let file2 = all_rules
-            .iter()
-            .find(|(_, r)| r.selector == dup.rule2.selector)
-            .map(|(f, _)| f)
-            .unwrap_or(&empty_string);
+        let (file1, file2) = (&dup.rule1.file_path, &dup.rule2.file_path);
 
         duplicates.push(json!({
             "type": "similar",
             "similarity": dup.similarity,
+            "merge_safety": check_merge_safety(rules, dup),
             "rule1": {
                 "selector": dup.rule1.selector,
                 "file": file1,
+                "at_rule": dup.rule1.at_rule,
                 "start_line": dup.rule1.start_line,
                 "end_line": dup.rule1.end_line,
             },
             "rule2": {
                 "selector": dup.rule2.selector,
                 "file": file2,
+                "at_rule": dup.rule2.at_rule,
                 "start_line": dup.rule2.start_line,
                 "end_line": dup.rule2.end_line,
             }
@@ -341,37 +1038,63 @@ fn output_json(
     // For BEM variations, just output count for now
     let bem_count = result.bem_variations.len();
 
-    let output = json!({
+    let mut output = json!({
         "duplicates": duplicates,
         "bem_variations_count": bem_count,
+        "keyframes_duplicates": animations,
         "summary": {
-            "total_rules": all_rules.len(),
+            "total_rules": rules.len(),
             "exact_duplicates": result.exact_duplicates.len(),
             "similar_styles": result.style_duplicates.len(),
             "bem_components": bem_count,
         }
     });
+    if let Some(wars) = wars {
+        output["specificity_wars"] = serde_json::to_value(wars)?;
+    }
+    if let Some((cross_scope, scopes)) = cross_scope {
+        let location = |index: usize, rule: &similarity_css::CssRule| {
+            json!({
+                "selector": rule.selector,
+                "file": rule.file_path,
+                "at_rule": rule.at_rule,
+                "scope": scopes[index].to_string(),
+                "start_line": rule.start_line,
+                "end_line": rule.end_line,
+            })
+        };
+        output["cross_scope_duplicates"] = cross_scope
+            .iter()
+            .map(|dup| {
+                json!({
+                    "similarity": dup.similarity,
+                    "rule1": location(dup.index1, &dup.rule1),
+                    "rule2": location(dup.index2, &dup.rule2),
+                })
+            })
+            .collect();
+    }
 
     println!("{}", serde_json::to_string_pretty(&output)?);
     Ok(())
 }
 
 fn analyze_with_structure_comparison(
-    all_rules: &[(String, similarity_css::CssRule)],
+    rules: &[similarity_css::CssRule],
     threshold: f64,
     output_format: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<usize, Box<dyn std::error::Error>> {
     // Convert CSS rules to CssStructDef
     let mut css_structs = Vec::new();
 
-    for (file_path, rule) in all_rules {
+    for rule in rules {
         let css_struct = CssStructDef {
             selector: rule.selector.clone(),
             declarations: rule.declarations.clone(),
-            file_path: file_path.clone(),
+            file_path: rule.file_path.clone(),
             start_line: rule.start_line,
             end_line: rule.end_line,
-            media_query: None,
+            media_query: rule.at_rule.clone(),
             parent_selectors: vec![],
         };
         css_structs.push(css_struct);
@@ -395,7 +1118,7 @@ fn analyze_with_structure_comparison(
         }
     }
 
-    Ok(())
+    Ok(similar_rules.len())
 }
 
 fn output_structure_standard(
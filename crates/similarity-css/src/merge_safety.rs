@@ -0,0 +1,246 @@
+//! Cascade safety of duplicate-removal and merge recommendations.
+//!
+//! Removing the later copy of an exact duplicate moves its declarations back
+//! to the earlier copy, and merging a style duplicate into `a, b { ... }` at
+//! the later rule's position moves the earlier rule's declarations forward.
+//! Either move changes the computed style only when a rule in between sets the
+//! same property on the same elements with an equal weight (`!important` and
+//! specificity), because then document order decided the winner. Merging also
+//! changes styles when the two declaration blocks differ.
+//!
+//! Rules are taken in the order analyzed, so rules of different files assume
+//! the stylesheets are loaded in that order. Selectors whose subjects have
+//! unrelated class sets (`.btn` and `.link`) are assumed to match different
+//! elements; selectors without classes may match anything.
+
+use crate::{expand_shorthand_properties, CssRule, DuplicateRule, SelectorAnalysis};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeSafety {
+    Safe,
+    ManualReview,
+}
+
+impl fmt::Display for MergeSafety {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MergeSafety::Safe => "safe",
+            MergeSafety::ManualReview => "manual review",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeSafetyReport {
+    pub safety: MergeSafety,
+    /// Why the recommendation needs manual review
+    pub reasons: Vec<String>,
+}
+
+/// Check whether acting on `duplicate`, found among `rules`, keeps the computed styles.
+///
+/// Exact duplicates are checked for removing the later copy, other duplicates
+/// for merging both rules at the later rule's position.
+pub fn check_merge_safety(rules: &[CssRule], duplicate: &DuplicateRule) -> MergeSafetyReport {
+    let (first, second) =
+        (duplicate.index1.min(duplicate.index2), duplicate.index1.max(duplicate.index2));
+    let (earlier, later) = (&rules[first], &rules[second]);
+    let earlier_declarations = effective_declarations(earlier);
+    let later_declarations = effective_declarations(later);
+    let mut reasons = Vec::new();
+
+    let moved = if earlier.selector == later.selector {
+        // The later copy's declarations fall back to the earlier position
+        later
+    } else {
+        if earlier_declarations != later_declarations {
+            reasons.push(format!(
+                "declarations differ, so merging changes the styles of '{}' or '{}'",
+                earlier.selector, later.selector
+            ));
+        }
+        if [earlier, later]
+            .iter()
+            .any(|rule| rule.selector.contains(":-") || rule.selector.contains("::-"))
+        {
+            reasons.push(
+                "a vendor-prefixed pseudo selector invalidates the whole merged selector list in other browsers"
+                    .to_string(),
+            );
+        }
+        // The earlier rule's declarations move to the later position
+        earlier
+    };
+
+    let moved_declarations = effective_declarations(moved);
+    for between in &rules[first + 1..second] {
+        for (property, (value, important)) in effective_declarations(between) {
+            let Some((moved_value, moved_important)) = moved_declarations.get(&property) else {
+                continue;
+            };
+            if *moved_value == value {
+                continue;
+            }
+            for selector in moved.selector.split(',').map(str::trim) {
+                let moved_analysis = SelectorAnalysis::new(selector);
+                let tie = between.selector.split(',').map(str::trim).find(|other| {
+                    let analysis = SelectorAnalysis::new(other);
+                    may_match_same_elements(&moved_analysis, &analysis)
+                        && (*moved_important, moved_analysis.specificity)
+                            == (important, analysis.specificity)
+                });
+                if let Some(other) = tie {
+                    reasons.push(format!(
+                        "'{}' (line {}) sets {} with the same specificity between the two rules, so document order decides it",
+                        other, between.start_line, property
+                    ));
+                }
+            }
+        }
+    }
+
+    let safety = if reasons.is_empty() { MergeSafety::Safe } else { MergeSafety::ManualReview };
+    MergeSafetyReport { safety, reasons }
+}
+
+/// Winning value and `!important` flag of each property after shorthand expansion
+fn effective_declarations(rule: &CssRule) -> BTreeMap<String, (String, bool)> {
+    expand_shorthand_properties(&rule.declarations)
+        .into_iter()
+        .map(|(property, value)| {
+            let important = value.contains("!important");
+            let value = value.replace("!important", "").trim().to_string();
+            (property, (value, important))
+        })
+        .collect()
+}
+
+/// Whether one subject's class set contains the other's, or either has no class
+fn may_match_same_elements(a: &SelectorAnalysis, b: &SelectorAnalysis) -> bool {
+    let classes = |analysis: &SelectorAnalysis| {
+        analysis.target().map(|target| {
+            let compound = target.split(':').next().unwrap_or("").to_string();
+            compound
+                .split('.')
+                .filter(|class| !class.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+    };
+    match (classes(a), classes(b)) {
+        (Some(a), Some(b)) => {
+            a.iter().all(|class| b.contains(class)) || b.iter().all(|class| a.contains(class))
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert_to_css_rule, CssParser, DuplicateAnalyzer};
+    use similarity_core::language_parser::LanguageParser;
+
+    fn rules_in(css: &str) -> Vec<CssRule> {
+        let mut parser = CssParser::new();
+        let functions = parser.extract_functions(css, "test.css").unwrap();
+        functions.iter().map(|func| convert_to_css_rule(func, css, "test.css")).collect()
+    }
+
+    #[test]
+    fn test_exact_duplicate_with_tie_in_between_needs_review() {
+        let css = r#"
+.btn {
+  color: red;
+  padding: 4px;
+}
+.btn {
+  color: blue;
+}
+.btn {
+  color: red;
+  padding: 4px;
+}
+.link {
+  color: green;
+  padding: 4px;
+}
+"#;
+        let rules = rules_in(css);
+        let result = DuplicateAnalyzer::new(rules.clone(), 0.8).analyze();
+        assert_eq!(result.exact_duplicates.len(), 1);
+
+        let report = check_merge_safety(&rules, &result.exact_duplicates[0]);
+        assert_eq!(report.safety, MergeSafety::ManualReview);
+        assert_eq!(report.reasons.len(), 1);
+        assert!(report.reasons[0].contains("line 6"));
+    }
+
+    #[test]
+    fn test_exact_duplicate_without_conflict_is_safe() {
+        let css = r#"
+.btn {
+  color: red;
+  padding: 4px;
+}
+.link {
+  color: blue;
+}
+.toolbar .btn {
+  color: blue;
+}
+.btn {
+  color: red;
+  padding: 4px;
+}
+"#;
+        let rules = rules_in(css);
+        let result = DuplicateAnalyzer::new(rules.clone(), 0.8).analyze();
+        let report = check_merge_safety(&rules, &result.exact_duplicates[0]);
+        assert_eq!(report.safety, MergeSafety::Safe);
+        assert!(report.reasons.is_empty());
+    }
+
+    #[test]
+    fn test_style_duplicate_merge() {
+        let css = r#"
+.card {
+  color: red;
+  padding: 8px;
+  margin: 0;
+}
+.panel {
+  color: red;
+  padding: 8px;
+  margin: 0;
+}
+.card {
+  color: blue;
+}
+.tile {
+  color: red;
+  padding: 8px;
+  margin: 0;
+}
+"#;
+        let rules = rules_in(css);
+        let result = DuplicateAnalyzer::new(rules.clone(), 0.8).analyze();
+        let report_for = |selector1: &str, selector2: &str| {
+            let duplicate = result
+                .style_duplicates
+                .iter()
+                .find(|dup| dup.rule1.selector == selector1 && dup.rule2.selector == selector2)
+                .unwrap();
+            check_merge_safety(&rules, duplicate)
+        };
+
+        assert_eq!(report_for(".card", ".panel").safety, MergeSafety::Safe);
+        // Moving `.card` past its own later override would undo it
+        assert_eq!(report_for(".card", ".tile").safety, MergeSafety::ManualReview);
+        assert_eq!(report_for(".panel", ".tile").safety, MergeSafety::Safe);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::keyframes::{self, Keyframes};
 use crate::scss_simple_flattener::simple_flatten_scss;
 use similarity_core::language_parser::{
     GenericFunctionDef, GenericTypeDef, Language, LanguageParser,
@@ -31,6 +32,22 @@ impl CssParser {
         Self { parser, is_scss: true }
     }
 
+    /// Extract the `@keyframes` animations of a stylesheet
+    pub fn extract_keyframes(
+        &mut self,
+        content: &str,
+        file_path: &str,
+    ) -> Result<Vec<Keyframes>, Box<dyn Error + Send + Sync>> {
+        let tree = self
+            .parser
+            .parse(content, None)
+            .ok_or_else(|| Box::<dyn Error + Send + Sync>::from("Failed to parse CSS/SCSS"))?;
+
+        let mut animations = Vec::new();
+        keyframes::extract_keyframes(&tree.root_node(), content, file_path, &mut animations);
+        Ok(animations)
+    }
+
     #[allow(clippy::only_used_in_recursion)]
     fn convert_node(&self, node: Node, source: &str, id_counter: &mut usize) -> TreeNode {
         let current_id = *id_counter;
@@ -90,14 +107,14 @@ impl LanguageParser for CssParser {
                     .collect();
 
                 functions.push(GenericFunctionDef {
-                    name: rule.selector,
+                    name: rule.selector.into(),
                     start_line: rule.start_line,
                     end_line: rule.end_line,
                     body_start_line: rule.start_line,
                     body_end_line: rule.end_line,
                     parameters: vec![],
                     is_method: false,
-                    class_name: None,
+                    class_name: rule.at_rule.map(Into::into),
                     is_async: false,
                     is_generator: false,
                     decorators,
@@ -111,7 +128,7 @@ impl LanguageParser for CssParser {
 
             let root_node = tree.root_node();
             let mut supplemental = Vec::new();
-            extract_rules(&root_node, content, &mut supplemental);
+            extract_rules(&root_node, content, None, &mut supplemental);
 
             for function in supplemental {
                 let is_at_rule = function.name.starts_with('@');
@@ -138,7 +155,7 @@ impl LanguageParser for CssParser {
         let root_node = tree.root_node();
         let mut functions = Vec::new();
 
-        extract_rules(&root_node, content, &mut functions);
+        extract_rules(&root_node, content, None, &mut functions);
 
         Ok(functions)
     }
@@ -157,7 +174,14 @@ impl LanguageParser for CssParser {
     }
 }
 
-fn extract_rules(node: &Node, source: &str, functions: &mut Vec<GenericFunctionDef>) {
+/// Collect rules under `node`; rules nested in `@media`, `@supports` and other
+/// block at-rules get the enclosing preludes, outermost first, as `class_name`
+fn extract_rules(
+    node: &Node,
+    source: &str,
+    context: Option<&str>,
+    functions: &mut Vec<GenericFunctionDef>,
+) {
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
         // Debug: print node kinds to understand the structure
@@ -192,14 +216,14 @@ fn extract_rules(node: &Node, source: &str, functions: &mut Vec<GenericFunctionD
                     }
 
                     functions.push(GenericFunctionDef {
-                        name: selector_text.to_string(),
+                        name: selector_text.into(),
                         start_line: child.start_position().row as u32 + 1,
                         end_line: child.end_position().row as u32 + 1,
                         body_start_line: child.start_position().row as u32 + 1,
                         body_end_line: child.end_position().row as u32 + 1,
                         parameters: vec![],
                         is_method: false,
-                        class_name: None,
+                        class_name: context.map(Into::into),
                         is_async: false,
                         is_generator: false,
                         decorators,
@@ -207,6 +231,25 @@ fn extract_rules(node: &Node, source: &str, functions: &mut Vec<GenericFunctionD
                 }
             }
             "media_statement" | "supports_statement" | "at_rule" => {
+                let mut nested = Vec::new();
+                let mut block_cursor = child.walk();
+                let block = child.children(&mut block_cursor).find(|n| n.kind() == "block");
+                if let Some(block) = block {
+                    let prelude = at_rule_prelude(&child, &block, source);
+                    let nested_context = match context {
+                        Some(outer) => format!("{outer} {prelude}"),
+                        None => prelude,
+                    };
+                    extract_rules(&block, source, Some(&nested_context), &mut nested);
+                }
+
+                // Blocks of rules are compared through their rules; the
+                // at-rule itself only for blocks of declarations (`@font-face`)
+                if !nested.is_empty() {
+                    functions.extend(nested);
+                    continue;
+                }
+
                 let at_keyword = child
                     .child_by_field_name("at_keyword")
                     .or_else(|| child.child(0))
@@ -214,14 +257,14 @@ fn extract_rules(node: &Node, source: &str, functions: &mut Vec<GenericFunctionD
                     .unwrap_or("@rule");
 
                 functions.push(GenericFunctionDef {
-                    name: at_keyword.to_string(),
+                    name: at_keyword.into(),
                     start_line: child.start_position().row as u32 + 1,
                     end_line: child.end_position().row as u32 + 1,
                     body_start_line: child.start_position().row as u32 + 1,
                     body_end_line: child.end_position().row as u32 + 1,
                     parameters: vec![],
                     is_method: false,
-                    class_name: None,
+                    class_name: context.map(Into::into),
                     is_async: false,
                     is_generator: false,
                     decorators: vec![],
@@ -232,7 +275,7 @@ fn extract_rules(node: &Node, source: &str, functions: &mut Vec<GenericFunctionD
                     let name = name_node.utf8_text(source.as_bytes()).unwrap_or("mixin");
 
                     functions.push(GenericFunctionDef {
-                        name: format!("@mixin {name}"),
+                        name: format!("@mixin {name}").into(),
                         start_line: child.start_position().row as u32 + 1,
                         end_line: child.end_position().row as u32 + 1,
                         body_start_line: child.start_position().row as u32 + 1,
@@ -247,8 +290,16 @@ fn extract_rules(node: &Node, source: &str, functions: &mut Vec<GenericFunctionD
                 }
             }
             _ => {
-                extract_rules(&child, source, functions);
+                extract_rules(&child, source, context, functions);
             }
         }
     }
 }
+
+/// Text of an at-rule up to its block, with whitespace collapsed
+fn at_rule_prelude(at_rule: &Node, block: &Node, source: &str) -> String {
+    source[at_rule.start_byte()..block.start_byte()]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,188 @@
+//! Style scoping contexts.
+//!
+//! Component-scoped styles (Vue `<style scoped>`, CSS Modules, shadow DOM
+//! stylesheets using `:host`, and the compiled attribute selectors of Angular's
+//! emulated encapsulation or Vue) cannot leak into other components, so two
+//! components repeating a rule is often deliberate. Duplicates between two
+//! different component scopes can be set apart from global duplicates.
+
+use crate::{CssRule, DuplicateAnalysisResult, DuplicateRule};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StyleScope {
+    Global,
+    /// Styles confined to one component, named by its file or scoping attribute
+    Component(String),
+}
+
+impl fmt::Display for StyleScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StyleScope::Global => f.write_str("global"),
+            StyleScope::Component(name) => f.write_str(name),
+        }
+    }
+}
+
+/// A `<style>` block of a Vue single-file component
+#[derive(Debug, Clone)]
+pub struct VueStyleBlock {
+    /// The block's stylesheet, preceded by blank lines so line numbers match the `.vue` file
+    pub content: String,
+    /// `scoped` or `module`
+    pub scoped: bool,
+    pub is_scss: bool,
+}
+
+/// Extract the `<style>` blocks of a `.vue` file
+pub fn extract_vue_styles(content: &str) -> Vec<VueStyleBlock> {
+    let mut blocks = Vec::new();
+    let mut rest = 0;
+    while let Some(offset) = content[rest..].find("<style") {
+        let tag_start = rest + offset;
+        let Some(tag_len) = content[tag_start..].find('>') else {
+            break;
+        };
+        let attributes = &content[tag_start + "<style".len()..tag_start + tag_len];
+        let body_start = tag_start + tag_len + 1;
+        let body_end =
+            content[body_start..].find("</style>").map_or(content.len(), |end| body_start + end);
+
+        let preceding_lines = content[..body_start].matches('\n').count();
+        blocks.push(VueStyleBlock {
+            content: format!("{}{}", "\n".repeat(preceding_lines), &content[body_start..body_end]),
+            scoped: attributes.split_whitespace().any(|attribute| {
+                attribute == "scoped" || attribute == "module" || attribute.starts_with("module=")
+            }),
+            is_scss: attributes.contains("lang=\"scss\"") || attributes.contains("lang='scss'"),
+        });
+        rest = body_end;
+    }
+    blocks
+}
+
+/// Scope of a whole stylesheet: component-scoped when `scoped` (e.g. a Vue
+/// `<style scoped>` block), a CSS Module, or a shadow DOM stylesheet using `:host`
+pub fn stylesheet_scope(path: &str, scoped: bool, rules: &[CssRule]) -> StyleScope {
+    let is_module = [".module.css", ".module.scss"].iter().any(|suffix| path.ends_with(suffix));
+    let uses_host = rules.iter().any(|rule| rule.selector.contains(":host"));
+    if scoped || is_module || uses_host {
+        StyleScope::Component(path.to_string())
+    } else {
+        StyleScope::Global
+    }
+}
+
+/// Scope of a rule: the component of a scoping attribute in its selector
+/// (`[_ngcontent-ng-c12]`, `[_nghost-ng-c12]`, `[data-v-7ba5bd90]`), otherwise its stylesheet's
+pub fn rule_scope(selector: &str, stylesheet: &StyleScope) -> StyleScope {
+    for prefix in ["[_ngcontent-", "[_nghost-", "[data-v-"] {
+        if let Some(start) = selector.find(prefix) {
+            let id = &selector[start + prefix.len()..];
+            let id = &id[..id.find([']', '=']).unwrap_or(id.len())];
+            let framework = if prefix == "[data-v-" { "vue" } else { "angular" };
+            return StyleScope::Component(format!("{framework} {id}"));
+        }
+    }
+    stylesheet.clone()
+}
+
+/// Whether two rules live in different component scopes
+pub fn is_cross_scope(scope1: &StyleScope, scope2: &StyleScope) -> bool {
+    matches!((scope1, scope2), (StyleScope::Component(a), StyleScope::Component(b)) if a != b)
+}
+
+/// Move exact duplicates, selector conflicts and style duplicates between two
+/// component scopes out of `result`; `scopes` holds the scope of each analyzed rule
+pub fn take_cross_scope_duplicates(
+    result: &mut DuplicateAnalysisResult,
+    scopes: &[StyleScope],
+) -> Vec<DuplicateRule> {
+    let mut cross_scope = Vec::new();
+    for duplicates in
+        [&mut result.exact_duplicates, &mut result.selector_conflicts, &mut result.style_duplicates]
+    {
+        let (cross, same): (Vec<_>, Vec<_>) = std::mem::take(duplicates)
+            .into_iter()
+            .partition(|dup| is_cross_scope(&scopes[dup.index1], &scopes[dup.index2]));
+        *duplicates = same;
+        cross_scope.extend(cross);
+    }
+    cross_scope
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert_to_css_rule, CssParser, DuplicateAnalyzer};
+    use similarity_core::language_parser::LanguageParser;
+
+    const STYLES: &str = ".btn {\n  padding: 8px;\n  color: white;\n  background: blue;\n}\n";
+
+    fn rules_in(css: &str) -> Vec<CssRule> {
+        let mut parser = CssParser::new();
+        let functions = parser.extract_functions(css, "test.css").unwrap();
+        functions.iter().map(|func| convert_to_css_rule(func, css, "test.css")).collect()
+    }
+
+    #[test]
+    fn test_vue_style_blocks_keep_line_numbers() {
+        let vue = "<template>\n  <button class=\"btn\" />\n</template>\n\n<style scoped>\n.btn {\n  padding: 8px;\n}\n</style>\n<style lang=\"scss\">\n.x { color: red; }\n</style>\n";
+        let blocks = extract_vue_styles(vue);
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks[0].scoped && !blocks[0].is_scss);
+        assert!(!blocks[1].scoped && blocks[1].is_scss);
+
+        let rules = rules_in(&blocks[0].content);
+        assert_eq!(rules[0].start_line, 6);
+        assert_eq!(
+            stylesheet_scope("Button.vue", blocks[0].scoped, &rules),
+            StyleScope::Component("Button.vue".to_string())
+        );
+    }
+
+    #[test]
+    fn test_scopes_from_files_and_attributes() {
+        let global = StyleScope::Global;
+        assert_eq!(stylesheet_scope("a.css", false, &rules_in(STYLES)), global);
+        assert!(matches!(stylesheet_scope("a.module.css", false, &[]), StyleScope::Component(_)));
+        let host = rules_in(":host {\n  display: block;\n}\n");
+        assert!(matches!(stylesheet_scope("el.css", false, &host), StyleScope::Component(_)));
+
+        assert_eq!(
+            rule_scope(".btn[_ngcontent-ng-c12]", &global),
+            StyleScope::Component("angular ng-c12".to_string())
+        );
+        assert_eq!(
+            rule_scope("[_nghost-ng-c12]", &global),
+            rule_scope("p[_ngcontent-ng-c12]", &global)
+        );
+        assert_eq!(
+            rule_scope(".a[data-v-7ba5bd90]", &global),
+            StyleScope::Component("vue 7ba5bd90".to_string())
+        );
+        assert_eq!(rule_scope(".a", &global), global);
+    }
+
+    #[test]
+    fn test_cross_scope_duplicates_are_set_apart() {
+        let rules: Vec<CssRule> = rules_in(&STYLES.repeat(4));
+        let scopes = [
+            StyleScope::Component("A.vue".to_string()),
+            StyleScope::Component("B.vue".to_string()),
+            StyleScope::Component("A.vue".to_string()),
+            StyleScope::Global,
+        ];
+        let mut result = DuplicateAnalyzer::new(rules, 0.8).analyze();
+        assert_eq!(result.exact_duplicates.len(), 6);
+
+        let cross_scope = take_cross_scope_duplicates(&mut result, &scopes);
+        let pairs: Vec<(usize, usize)> =
+            cross_scope.iter().map(|dup| (dup.index1, dup.index2)).collect();
+        assert_eq!(pairs, [(0, 1), (1, 2)]);
+        assert_eq!(result.exact_duplicates.len(), 4);
+    }
+}
@@ -7,8 +7,13 @@ pub struct SimpleFlatRule {
     pub declarations: Vec<(String, String)>,
     pub start_line: u32,
     pub end_line: u32,
+    /// Enclosing conditional at-rules, outermost first
+    pub at_rule: Option<String>,
 }
 
+/// At-rules whose nested rules only apply under a condition or in a layer
+const CONTEXT_AT_RULES: &[&str] = &["@media", "@supports", "@container", "@layer"];
+
 /// Simple regex-based SCSS flattener
 pub fn simple_flatten_scss(
     content: &str,
@@ -22,6 +27,8 @@ pub fn simple_flatten_scss(
 
     for (line_num, line) in content.lines().enumerate() {
         let line_num = line_num as u32 + 1;
+        let masked = mask_interpolations(line);
+        let line = masked.as_str();
         let trimmed = line.trim();
 
         if trimmed.is_empty() || trimmed.starts_with("//") {
@@ -52,6 +59,7 @@ pub fn simple_flatten_scss(
                 line.split('{').next().unwrap_or("").trim().to_string()
             };
 
+            let selector_part = unmask_interpolations(&selector_part);
             if !selector_part.is_empty() {
                 // Save any pending rule
                 if !current_declarations.is_empty() && !selector_stack.is_empty() {
@@ -63,6 +71,7 @@ pub fn simple_flatten_scss(
                                     declarations: current_declarations.clone(),
                                     start_line: rule_start_line,
                                     end_line: line_num - 1,
+                                    at_rule: at_rule_context(&selector_stack),
                                 });
                             }
                         }
@@ -70,7 +79,12 @@ pub fn simple_flatten_scss(
                 }
 
                 // Parse the new selector(s)
-                let selectors: Vec<&str> = selector_part.split(',').map(|s| s.trim()).collect();
+                // At-rule preludes such as `@media screen, print` are kept whole
+                let selectors: Vec<&str> = if selector_part.starts_with('@') {
+                    vec![selector_part.as_str()]
+                } else {
+                    selector_part.split(',').map(|s| s.trim()).collect()
+                };
                 let mut expanded_selectors = Vec::new();
 
                 for selector in selectors {
@@ -124,7 +138,8 @@ pub fn simple_flatten_scss(
                         let property = parts[0].trim();
                         let value = parts[1].trim();
                         if !property.is_empty() && !value.is_empty() && !property.starts_with('@') {
-                            current_declarations.push((property.to_string(), value.to_string()));
+                            current_declarations
+                                .push((property.to_string(), unmask_interpolations(value)));
                         }
                     }
                 }
@@ -136,7 +151,7 @@ pub fn simple_flatten_scss(
                 let property = parts[0].trim();
                 let value = strip_inline_comment(parts[1]).trim_end_matches(';').trim();
                 if !property.is_empty() && !value.is_empty() && !property.starts_with('@') {
-                    current_declarations.push((property.to_string(), value.to_string()));
+                    current_declarations.push((property.to_string(), unmask_interpolations(value)));
                 }
             }
         }
@@ -153,6 +168,7 @@ pub fn simple_flatten_scss(
                                     declarations: current_declarations.clone(),
                                     start_line: rule_start_line,
                                     end_line: line_num,
+                                    at_rule: at_rule_context(&selector_stack),
                                 });
                             }
                         }
@@ -175,6 +191,50 @@ pub fn simple_flatten_scss(
     Ok(rules)
 }
 
+/// Stand-ins for the braces of `#{...}` interpolations, so that only block
+/// braces are counted
+const INTERPOLATION_OPEN: char = '\u{1}';
+const INTERPOLATION_CLOSE: char = '\u{2}';
+
+fn mask_interpolations(line: &str) -> String {
+    let mut masked = String::with_capacity(line.len());
+    let mut depth = 0;
+    let mut previous = None;
+    for c in line.chars() {
+        match c {
+            '{' if depth > 0 || previous == Some('#') => {
+                depth += 1;
+                masked.push(INTERPOLATION_OPEN);
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                masked.push(INTERPOLATION_CLOSE);
+            }
+            _ => masked.push(c),
+        }
+        previous = Some(c);
+    }
+    masked
+}
+
+fn unmask_interpolations(text: &str) -> String {
+    text.replace(INTERPOLATION_OPEN, "{").replace(INTERPOLATION_CLOSE, "}")
+}
+
+fn at_rule_context(selector_stack: &[Vec<String>]) -> Option<String> {
+    let preludes: Vec<&str> = selector_stack
+        .iter()
+        .flatten()
+        .filter(|entry| {
+            CONTEXT_AT_RULES.iter().any(|at_rule| {
+                entry.strip_prefix(at_rule).is_some_and(|rest| rest.starts_with([' ', '(']))
+            })
+        })
+        .map(String::as_str)
+        .collect();
+    (!preludes.is_empty()).then(|| preludes.join(" "))
+}
+
 fn strip_inline_comment(value: &str) -> &str {
     value
         .find(" //")
@@ -388,4 +448,47 @@ mod tests {
         assert!(rules.iter().any(|r| r.selector == ".form-group textarea.error:focus"));
         assert!(rules.iter().any(|r| r.selector == ".form-group select.error:focus"));
     }
+
+    #[test]
+    fn test_rules_keep_their_at_rule_context() {
+        let scss = r#"
+.card {
+    padding: 8px;
+}
+@media screen, print {
+    @supports (display: grid) {
+        .card {
+            padding: 16px;
+        }
+    }
+}"#;
+
+        let rules = simple_flatten_scss(scss).unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].at_rule, None);
+        assert_eq!(rules[1].selector, ".card");
+        assert_eq!(
+            rules[1].at_rule.as_deref(),
+            Some("@media screen, print @supports (display: grid)")
+        );
+    }
+
+    #[test]
+    fn test_interpolated_selectors() {
+        let scss = r#"
+@each $name in home, user {
+    .icon-#{$name} {
+        width: #{$size}px;
+    }
+}
+.btn-#{$size} { padding: 4px; }"#;
+
+        let rules = simple_flatten_scss(scss).unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].selector, ".icon-#{$name}");
+        assert_eq!(rules[0].declarations, [("width".to_string(), "#{$size}px".to_string())]);
+        assert_eq!(rules[1].selector, ".btn-#{$size}");
+    }
 }
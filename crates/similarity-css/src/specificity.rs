@@ -5,7 +5,7 @@
 /// - b = number of class selectors, attributes, and pseudo-classes
 /// - c = number of type selectors and pseudo-elements
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
 pub struct Specificity {
     pub ids: u32,
     pub classes: u32,
@@ -235,6 +235,29 @@ impl SelectorAnalysis {
     pub fn overrides(&self, other: &SelectorAnalysis) -> bool {
         self.specificity > other.specificity
     }
+
+    /// Effective target: the sorted class set of the subject (rightmost compound
+    /// selector) with its pseudo-classes, e.g. `.btn.primary:hover`. `None` when
+    /// the subject has no class
+    pub fn target(&self) -> Option<String> {
+        let normalized = normalize_selector(&self.selector);
+        let subject =
+            split_selector_parts(&normalized).into_iter().rfind(|part| !is_combinator(part))?;
+        let (compound, pseudo) = subject.split_at(subject.find(':').unwrap_or(subject.len()));
+
+        let mut classes: Vec<&str> = compound
+            .split('.')
+            .skip(1)
+            .filter_map(|class| class.split(['#', '[']).next())
+            .filter(|class| !class.is_empty())
+            .collect();
+        if classes.is_empty() {
+            return None;
+        }
+        classes.sort_unstable();
+        classes.dedup();
+        Some(format!(".{}{}", classes.join("."), pseudo))
+    }
 }
 
 /// Parse BEM notation from a selector
@@ -329,6 +352,15 @@ mod tests {
         assert_eq!(bem.modifier.unwrap(), "modifier");
     }
 
+    #[test]
+    fn test_selector_target() {
+        let target = |selector: &str| SelectorAnalysis::new(selector).target();
+        assert_eq!(target(".page .toolbar .btn").as_deref(), Some(".btn"));
+        assert_eq!(target("#app > div.primary.btn:hover").as_deref(), Some(".btn.primary:hover"));
+        assert_eq!(target(".btn[disabled]").as_deref(), Some(".btn"));
+        assert_eq!(target(".nav a"), None);
+    }
+
     #[test]
     fn test_specificity_comparison() {
         let spec1 = Specificity::new(1, 0, 0);
@@ -0,0 +1,188 @@
+//! Specificity war detection.
+//!
+//! Pairwise overrides miss the pattern where a property of the same element
+//! is overridden again and again, each time with a more specific selector or
+//! `!important`. Declarations are grouped by their effective target (the
+//! class set of the selector's subject) and property, and each group is
+//! walked in source order keeping the declarations that had to escalate to
+//! win. Chains of at least [`MIN_WAR_LENGTH`] steps are reported.
+
+use crate::{CssRule, SelectorAnalysis, Specificity};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Shortest escalation chain reported as a specificity war
+pub const MIN_WAR_LENGTH: usize = 3;
+
+/// File, line and selector of a step
+type StepLocation = (String, usize, String);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WarStep {
+    pub file: String,
+    pub selector: String,
+    pub specificity: Specificity,
+    pub important: bool,
+    pub start_line: usize,
+}
+
+impl WarStep {
+    fn weight(&self) -> (bool, Specificity) {
+        (self.important, self.specificity)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpecificityWar {
+    /// Class set of the targeted elements, e.g. `.btn.primary:hover`
+    pub target: String,
+    /// Properties fought over by the same chain of rules
+    pub properties: Vec<String>,
+    /// Escalating declarations in source order
+    pub steps: Vec<WarStep>,
+}
+
+/// Find specificity wars among `(file, rule)` pairs given in source order, longest first
+pub fn find_specificity_wars<'a>(
+    rules: impl IntoIterator<Item = (&'a str, &'a CssRule)>,
+) -> Vec<SpecificityWar> {
+    let mut groups: BTreeMap<(String, String), Vec<WarStep>> = BTreeMap::new();
+    for (file, rule) in rules {
+        for selector in rule.selector.split(',').map(str::trim) {
+            let analysis = SelectorAnalysis::new(selector);
+            let Some(target) = analysis.target() else {
+                continue;
+            };
+            for (property, value) in &rule.declarations {
+                groups.entry((target.clone(), property.clone())).or_default().push(WarStep {
+                    file: file.to_string(),
+                    selector: selector.to_string(),
+                    specificity: analysis.specificity,
+                    important: value.contains("!important"),
+                    start_line: rule.start_line,
+                });
+            }
+        }
+    }
+
+    // Properties escalated by the same chain of rules are reported together
+    let mut wars: BTreeMap<(String, Vec<StepLocation>), SpecificityWar> = BTreeMap::new();
+    for ((target, property), steps) in groups {
+        let chain = escalation_chain(steps);
+        if chain.len() < MIN_WAR_LENGTH {
+            continue;
+        }
+        let locations = chain
+            .iter()
+            .map(|step| (step.file.clone(), step.start_line, step.selector.clone()))
+            .collect();
+        wars.entry((target.clone(), locations))
+            .or_insert_with(|| SpecificityWar { target, properties: Vec::new(), steps: chain })
+            .properties
+            .push(property);
+    }
+
+    let mut wars: Vec<SpecificityWar> = wars.into_values().collect();
+    wars.sort_by(|a, b| b.steps.len().cmp(&a.steps.len()).then_with(|| a.target.cmp(&b.target)));
+    wars
+}
+
+/// Declarations that outrank every earlier one, i.e. the ones that had to escalate to win
+fn escalation_chain(steps: Vec<WarStep>) -> Vec<WarStep> {
+    let mut chain: Vec<WarStep> = Vec::new();
+    for step in steps {
+        if chain.last().is_none_or(|last| step.weight() > last.weight()) {
+            chain.push(step);
+        }
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert_to_css_rule, CssParser};
+    use similarity_core::language_parser::LanguageParser;
+
+    fn wars_in(css: &str) -> Vec<SpecificityWar> {
+        let mut parser = CssParser::new();
+        let functions = parser.extract_functions(css, "test.css").unwrap();
+        let rules: Vec<CssRule> =
+            functions.iter().map(|func| convert_to_css_rule(func, css, "test.css")).collect();
+        find_specificity_wars(rules.iter().map(|rule| ("test.css", rule)))
+    }
+
+    #[test]
+    fn test_escalating_chain_is_reported() {
+        let css = r#"
+.btn {
+  color: black;
+  padding: 4px;
+}
+.toolbar .btn {
+  color: gray;
+  padding: 4px;
+}
+.page .toolbar .btn {
+  color: white;
+  padding: 4px;
+}
+.btn {
+  color: red !important;
+}
+.other {
+  color: blue;
+}
+"#;
+        let wars = wars_in(css);
+        assert_eq!(wars.len(), 2);
+
+        let color = &wars[0];
+        assert_eq!(color.target, ".btn");
+        assert_eq!(color.properties, ["color"]);
+        let selectors: Vec<&str> = color.steps.iter().map(|step| step.selector.as_str()).collect();
+        assert_eq!(selectors, [".btn", ".toolbar .btn", ".page .toolbar .btn", ".btn"]);
+        assert!(color.steps[3].important);
+
+        assert_eq!(wars[1].properties, ["padding"]);
+        assert_eq!(wars[1].steps.len(), 3);
+    }
+
+    #[test]
+    fn test_properties_with_the_same_chain_are_merged() {
+        let css = r#"
+.card:hover {
+  color: black;
+  background: white;
+}
+main .card:hover {
+  color: gray;
+  background: gray;
+}
+#app main .card:hover {
+  color: red;
+  background: red;
+}
+"#;
+        let wars = wars_in(css);
+        assert_eq!(wars.len(), 1);
+        assert_eq!(wars[0].target, ".card:hover");
+        assert_eq!(wars[0].properties, ["background", "color"]);
+    }
+
+    #[test]
+    fn test_later_weaker_rules_do_not_escalate() {
+        let css = r#"
+#app .btn {
+  color: black;
+}
+.toolbar .btn {
+  color: gray;
+}
+.btn {
+  color: white;
+}
+"#;
+        assert!(wars_in(css).is_empty());
+    }
+}
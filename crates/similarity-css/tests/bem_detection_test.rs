@@ -75,7 +75,7 @@ fn test_bem_exact_duplicate_detection() {
 
     // Convert to CssRule for easier testing
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Test exact duplicates
     let card_rules: Vec<&CssRule> = css_rules.iter().filter(|r| r.selector == ".card").collect();
@@ -285,7 +285,7 @@ fn test_rule_level_duplicate_analysis() {
     let rules = parser.extract_functions(scss_content, "test.scss").unwrap();
 
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Analyze duplicates
     let mut exact_duplicates = Vec::new();
@@ -0,0 +1,137 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+const SIMILAR_RULES: &str = ".card {
+  color: red;
+  padding: 4px;
+  margin: 0;
+}
+
+.panel {
+  color: red;
+  padding: 4px;
+  margin: 0;
+}
+";
+
+#[test]
+fn test_print_and_fail_on_duplicates() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.css"), SIMILAR_RULES).unwrap();
+
+    Command::cargo_bin("similarity-css")
+        .unwrap()
+        .arg(dir.path())
+        .arg("--print")
+        .arg("--fail-on-duplicates")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("## Similar Styles Found: 1"))
+        .stdout(predicate::str::contains(":.panel (lines 7-11) ---"))
+        .stdout(predicate::str::contains(".panel {\n  color: red;"));
+
+    // Without the flag duplicates do not fail the run
+    Command::cargo_bin("similarity-css").unwrap().arg(dir.path()).assert().success();
+}
+
+#[test]
+fn test_findings_name_the_file_and_at_rule_of_each_rule() {
+    let dir = tempdir().unwrap();
+    // `.btn` in a.css shares its selector, not its styles, with `.btn` in b.css
+    fs::write(dir.path().join("a.css"), ".btn {\n  color: blue;\n  margin: 0;\n  border: 0;\n}\n")
+        .unwrap();
+    fs::write(
+        dir.path().join("b.css"),
+        ".btn {\n  color: red;\n  padding: 4px;\n  display: block;\n}\n\n\
+         @media (min-width: 600px) {\n  .link {\n    color: red;\n    padding: 4px;\n    display: block;\n  }\n}\n",
+    )
+    .unwrap();
+    let b = dir.path().join("b.css").display().to_string();
+
+    Command::cargo_bin("similarity-css")
+        .unwrap()
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1. .btn and .link"))
+        .stdout(predicate::str::contains(format!("Files: {b} and {b}")))
+        .stdout(predicate::str::contains("At-rules: - and @media (min-width: 600px)"));
+}
+
+#[test]
+fn test_dynamic_selectors_are_skipped_or_compared_as_templates() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("a.scss"),
+        ".icon-#{$small} {\n  width: 16px;\n  height: 16px;\n  display: inline-block;\n}\n\n\
+         .icon-#{$large} {\n  width: 16px;\n  height: 16px;\n  display: inline-block;\n}\n",
+    )
+    .unwrap();
+    let run = |mode: &str| {
+        Command::cargo_bin("similarity-css")
+            .unwrap()
+            .args(["--scss", "--extension", "scss", "--dynamic-selectors", mode])
+            .arg(dir.path())
+            .assert()
+            .success()
+    };
+
+    run("template").stdout(predicate::str::contains("## Exact Duplicates Found: 1"));
+    run("template").stdout(predicate::str::contains("1. .icon-* and .icon-*"));
+    run("skip").stdout(predicate::str::contains("No CSS rules found to analyze"));
+}
+
+#[test]
+fn test_stylelint_output_has_one_result_per_file() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.css"), SIMILAR_RULES).unwrap();
+    fs::write(dir.path().join("b.css"), ".empty {}\n").unwrap();
+
+    let output = Command::cargo_bin("similarity-css")
+        .unwrap()
+        .args(["--output", "stylelint"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let results = results.as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    let warnings = results[0]["warnings"].as_array().unwrap();
+    assert!(results[0]["source"].as_str().unwrap().ends_with("a.css"));
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["rule"], "similarity/style-duplicate");
+    assert_eq!(warnings[0]["line"], 7);
+    assert!(warnings[0]["text"].as_str().unwrap().ends_with("(similarity/style-duplicate)"));
+    assert_eq!(results[1]["warnings"], serde_json::json!([]));
+}
+
+#[test]
+fn test_excluded_and_gitignored_files_are_skipped() {
+    let dir = tempdir().unwrap();
+    let status = std::process::Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    fs::write(dir.path().join(".gitignore"), "vendor/\n").unwrap();
+    for subdir in ["src", "legacy", "vendor"] {
+        fs::create_dir(dir.path().join(subdir)).unwrap();
+        fs::write(dir.path().join(subdir).join("styles.css"), SIMILAR_RULES).unwrap();
+    }
+
+    let output = Command::cargo_bin("similarity-css")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--exclude", "legacy"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Found 1 css files"), "{stdout}");
+    assert!(stdout.contains("src/styles.css"), "{stdout}");
+    assert!(!stdout.contains("legacy"), "{stdout}");
+    assert!(!stdout.contains("vendor"), "{stdout}");
+}
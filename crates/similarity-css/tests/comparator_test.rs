@@ -21,6 +21,8 @@ fn create_test_rule(selector: &str, declarations: Vec<(&str, &str)>) -> CssRule
         tree: Rc::new(tree),
         start_line: 1,
         end_line: 10,
+        file_path: "test.css".to_string(),
+        at_rule: None,
     }
 }
 
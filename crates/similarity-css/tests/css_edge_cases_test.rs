@@ -192,7 +192,7 @@ fn test_keyframes_and_animations() {
     let rules = parser.extract_functions(scss_content, "test.scss").unwrap();
 
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Find animation rules
     let animation_rules: Vec<_> = css_rules
@@ -311,7 +311,7 @@ fn test_css_grid_and_flexbox_complex() {
     let rules = parser.extract_functions(scss_content, "test.scss").unwrap();
 
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Check for grid properties
     let grid_rules: Vec<_> = css_rules
@@ -429,7 +429,7 @@ fn test_css_functions_and_modern_features() {
     let rules = parser.extract_functions(scss_content, "test.scss").unwrap();
 
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Check modern CSS functions
     let modern_functions = ["clamp(", "min(", "max(", "rgb(", "hsl(", "hwb(", "var("];
@@ -662,7 +662,7 @@ $breakpoint-xl: 1200px;
     let rules = parser.extract_functions(scss_content, "test.scss").unwrap();
 
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Check media query rules
     let media_rules: Vec<_> = rules.iter().filter(|r| r.name.contains("@media")).collect();
@@ -98,7 +98,7 @@ fn test_full_duplicate_analysis_workflow() {
 
     // Convert to CssRule format with proper tree nodes
     let css_rules: Vec<_> =
-        functions.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        functions.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Analyze duplicates
     let analyzer = DuplicateAnalyzer::new(css_rules, 0.8);
@@ -221,7 +221,7 @@ fn test_specificity_based_override_detection() {
 
     // Create rules for override analysis
     let css_rules: Vec<_> =
-        functions.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        functions.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     let analyzer = DuplicateAnalyzer::new(css_rules, 0.8);
     let result = analyzer.analyze();
@@ -31,7 +31,10 @@ fn test_parse_css_rules() {
 
     assert_eq!(functions[0].name, ".button");
     assert_eq!(functions[1].name, "#header");
-    assert_eq!(functions[2].name, "@media");
+    // Rules inside at-rules carry the at-rule as context
+    assert_eq!(functions[2].name, ".button");
+    assert_eq!(functions[2].class_name.as_deref(), Some("@media (max-width: 768px)"));
+    assert_eq!(functions[0].class_name, None);
 }
 
 #[test]
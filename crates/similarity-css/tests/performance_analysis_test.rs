@@ -110,7 +110,7 @@ $base-margin: 1rem;
     // Convert to CSS rules
     let start = Instant::now();
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, &scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, &scss_content, "large.scss")).collect();
     let convert_time = start.elapsed();
 
     println!("Converting to CSS rules took {convert_time:?}");
@@ -274,8 +274,10 @@ $container-max-widths: (
     let mut parser = CssParser::new_scss();
     let rules = parser.extract_functions(scss_content, "bootstrap.scss").unwrap();
 
-    let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+    let css_rules: Vec<_> = rules
+        .iter()
+        .map(|func| convert_to_css_rule(func, scss_content, "bootstrap.scss"))
+        .collect();
 
     // Analyze patterns
     let analyzer = DuplicateAnalyzer::new(css_rules.clone(), 0.8);
@@ -15,14 +15,16 @@ fn create_rules_from_functions(
             let declarations = extract_declarations_from_name(&func.name);
 
             // Create a simple tree for testing
-            let tree = TreeNode::new("rule".to_string(), func.name.clone(), 0);
+            let tree = TreeNode::new("rule".to_string(), func.name.to_string(), 0);
 
             CssRule {
-                selector: func.name,
+                selector: func.name.to_string(),
                 declarations,
                 tree: Rc::new(tree),
                 start_line: func.body_start_line as usize,
                 end_line: func.body_end_line as usize,
+                file_path: "test.css".to_string(),
+                at_rule: func.class_name.map(|context| context.to_string()),
             }
         })
         .collect()
@@ -218,11 +220,12 @@ fn test_responsive_utilities() {
     let mut parser = CssParser::new();
     let functions = parser.extract_functions(css, "utilities.css").unwrap();
 
-    // Should detect regular rules and media queries
-    assert!(functions.len() > 7); // All utility classes plus media queries
+    // Should detect regular rules and the rules inside media queries
+    assert_eq!(functions.len(), 10);
 
-    // Check for media queries
-    assert!(functions.iter().any(|f| f.name.contains("@media")));
+    // Rules inside media queries carry their media query
+    let mobile_only = functions.iter().find(|f| f.name == ".mobile-only").unwrap();
+    assert_eq!(mobile_only.class_name.as_deref(), Some("@media (max-width: 767px)"));
 }
 
 #[test]
@@ -52,7 +52,7 @@ $border-width: 2px;
 
     // Convert to CssRule
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Find button rules
     let button_rules: Vec<_> =
@@ -131,7 +131,7 @@ fn test_nested_media_queries() {
     let rules = parser.extract_functions(scss_content, "test.scss").unwrap();
 
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Check for base grid rules
     let base_grid_rules: Vec<_> =
@@ -237,7 +237,7 @@ fn test_complex_selector_combinations() {
     let rules = parser.extract_functions(scss_content, "test.scss").unwrap();
 
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     println!("Complex selectors found:");
     for rule in &css_rules {
@@ -360,7 +360,7 @@ fn test_mixin_like_patterns() {
     let rules = parser.extract_functions(scss_content, "test.scss").unwrap();
 
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Debug output
     println!("Total CSS rules found: {}", css_rules.len());
@@ -567,7 +567,7 @@ fn test_attribute_selectors_and_combinators() {
     }
 
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Check attribute selectors
     let attr_selectors: Vec<_> = css_rules
@@ -663,7 +663,7 @@ fn test_css_custom_properties_and_calculations() {
     let rules = parser.extract_functions(scss_content, "test.scss").unwrap();
 
     let css_rules: Vec<_> =
-        rules.iter().map(|func| convert_to_css_rule(func, scss_content)).collect();
+        rules.iter().map(|func| convert_to_css_rule(func, scss_content, "test.scss")).collect();
 
     // Check CSS custom properties
     let custom_prop_rules: Vec<_> = css_rules
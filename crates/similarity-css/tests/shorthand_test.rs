@@ -21,6 +21,8 @@ fn create_test_rule_with_declarations(selector: &str, declarations: Vec<(&str, &
         tree: Rc::new(tree),
         start_line: 1,
         end_line: 1,
+        file_path: "test.css".to_string(),
+        at_rule: None,
     }
 }
 
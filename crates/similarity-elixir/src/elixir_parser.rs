@@ -1,3 +1,4 @@
+use similarity_core::interner::Symbol;
 use similarity_core::language_parser::{
     GenericFunctionDef, GenericTypeDef, Language, LanguageParser,
 };
@@ -117,14 +118,14 @@ impl ElixirParser {
         let params = self.extract_parameters(params_node, source);
 
         Some(GenericFunctionDef {
-            name: name_string,
+            name: name_string.into(),
             start_line: node.start_position().row as u32 + 1,
             end_line: node.end_position().row as u32 + 1,
             body_start_line: body_node.map(|n| n.start_position().row as u32 + 1).unwrap_or(0),
             body_end_line: body_node.map(|n| n.end_position().row as u32 + 1).unwrap_or(0),
             parameters: params,
             is_method: module_name.is_some(),
-            class_name: module_name.map(String::from),
+            class_name: module_name.map(Symbol::from),
             is_async: false,
             is_generator: false,
             decorators: Vec::new(),
@@ -69,6 +69,10 @@ struct Cli {
     /// Size tolerance for overlap detection (0.0-1.0)
     #[arg(long, default_value = "0.25")]
     overlap_size_tolerance: f64,
+
+    /// Keep comments and docstrings in the compared trees (stripped by default)
+    #[arg(long)]
+    include_comments: bool,
 }
 
 fn main() -> Result<()> {
@@ -96,6 +100,7 @@ fn main() -> Result<()> {
             !cli.no_fast,
             cli.filter_function.as_ref(),
             cli.filter_function_body.as_ref(),
+            cli.include_comments,
         )?;
     }
 
@@ -131,7 +136,7 @@ fn check_overlaps(
     size_tolerance: f64,
 ) -> anyhow::Result<()> {
     use crate::elixir_parser::ElixirParser;
-    use ignore::WalkBuilder;
+    use similarity_core::cli_file_utils::walk_dir;
     use similarity_core::{find_overlaps_across_files_generic, OverlapOptions};
     use std::collections::{HashMap, HashSet};
     use std::fs;
@@ -162,8 +167,8 @@ fn check_overlaps(
                 }
             }
         } else if path.is_dir() {
-            // If it's a directory, walk it respecting .gitignore
-            let walker = WalkBuilder::new(path).follow_links(false).build();
+            // If it's a directory, walk it respecting .gitignore and .similarityignore
+            let walker = walk_dir(path);
 
             for entry in walker {
                 let entry = entry?;
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use similarity_core::cli_file_utils::collect_matching_files;
+use similarity_core::generic_tree_sitter_parser::GenericTreeSitterParser;
+use similarity_core::size_penalty::SizePenalty;
+use similarity_core::tsed::{TSEDOptions, DEFAULT_MAX_TREE_NODES};
+use similarity_core::{
+    run_analyzer, APTEDOptions, AnalyzerRegistry, ComposeAnalyzer, ConfigAnalyzer,
+    DockerfileAnalyzer, HclAnalyzer, IdlAnalyzer, LanguageParserAnalyzer, LanguageTypeAnalyzer,
+    SchemaAnalyzer, ShellAnalyzer, SqlAnalyzer,
+};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Built-in languages exposed through the analyzer interface
+const BUILTIN_ANALYZERS: &[(&str, &[&str])] = &[
+    ("go", &["go"]),
+    ("java", &["java"]),
+    ("c", &["c", "h"]),
+    ("cpp", &["cpp", "cc", "cxx", "hpp", "hxx"]),
+    ("csharp", &["cs"]),
+    ("ruby", &["rb"]),
+];
+
+/// Languages whose type definitions carry fields, compared as `<language>-types`
+const TYPE_ANALYZERS: &[(&str, &str, &[&str])] =
+    &[("csharp-types", "csharp", &["cs"]), ("ruby-types", "ruby", &["rb"])];
+
+/// Registry of analyzers compiled into this binary
+pub fn builtin_registry(include_comments: bool) -> Result<AnalyzerRegistry> {
+    let options = TSEDOptions {
+        apted_options: APTEDOptions {
+            rename_cost: 0.3,
+            delete_cost: 1.0,
+            insert_cost: 1.0,
+            compare_values: false,
+        },
+        min_lines: 1,
+        min_tokens: None,
+        size_penalty: false,
+        skip_test: false,
+        include_comments,
+        size_ratio_penalty: SizePenalty::default(),
+        max_tree_nodes: Some(DEFAULT_MAX_TREE_NODES),
+        canonicalize_order: false,
+        normalize_async: false,
+        normalize_collections: false,
+        strip_types: false,
+        node_weights: Default::default(),
+    };
+
+    let mut registry = AnalyzerRegistry::new();
+    for (name, extensions) in BUILTIN_ANALYZERS {
+        let parser = GenericTreeSitterParser::from_language_name(name)
+            .map_err(|e| anyhow::anyhow!("Failed to create parser for {}: {}", name, e))?;
+        registry.register(Box::new(LanguageParserAnalyzer::new(
+            name,
+            extensions.to_vec(),
+            Box::new(parser),
+            options.clone(),
+        )));
+    }
+    for (name, language, extensions) in TYPE_ANALYZERS {
+        let parser = GenericTreeSitterParser::from_language_name(language)
+            .map_err(|e| anyhow::anyhow!("Failed to create parser for {}: {}", language, e))?;
+        registry.register(Box::new(LanguageTypeAnalyzer::new(
+            name,
+            extensions.to_vec(),
+            Box::new(parser),
+        )));
+    }
+    let shell = ShellAnalyzer::new()
+        .map_err(|e| anyhow::anyhow!("Failed to create parser for bash: {}", e))?;
+    registry.register(Box::new(shell));
+    registry.register(Box::new(SqlAnalyzer::new()));
+    registry.register(Box::new(ConfigAnalyzer::yaml()));
+    registry.register(Box::new(ConfigAnalyzer::json()));
+    registry.register(Box::new(SchemaAnalyzer::new()));
+    registry.register(Box::new(HclAnalyzer::new()));
+    registry.register(Box::new(DockerfileAnalyzer::new()));
+    registry.register(Box::new(ComposeAnalyzer::new()));
+    registry.register(Box::new(IdlAnalyzer::new()));
+
+    Ok(registry)
+}
+
+/// Run the named analyzer over a file or directory and print its report
+pub fn run_named_analyzer(
+    registry: &mut AnalyzerRegistry,
+    name: &str,
+    path: &Path,
+    threshold: f64,
+) -> Result<()> {
+    let available = registry.names().join(", ");
+    let analyzer = registry.get_mut(name).ok_or_else(|| {
+        anyhow::anyhow!("Unknown analyzer: {}. Available analyzers: {}", name, available)
+    })?;
+
+    let files = collect_matching_files(&[path.to_string_lossy().to_string()], |file| {
+        analyzer.matches_file(file)
+    })?;
+
+    let mut sources = Vec::new();
+    for file in files {
+        let content = fs::read_to_string(&file)?;
+        sources.push((file.to_string_lossy().to_string(), content));
+    }
+
+    let findings = run_analyzer(analyzer, &sources, threshold)
+        .map_err(|e| anyhow::anyhow!("Analyzer '{}' failed: {}", name, e))?;
+    analyzer.report(&findings, &mut io::stdout().lock())?;
+
+    Ok(())
+}
@@ -3,11 +3,14 @@ use clap::Parser;
 use similarity_core::generic_parser_config::GenericParserConfig;
 use similarity_core::generic_tree_sitter_parser::GenericTreeSitterParser;
 use similarity_core::language_parser::LanguageParser;
-use similarity_core::tsed::{calculate_tsed, TSEDOptions};
+use similarity_core::size_penalty::SizePenalty;
+use similarity_core::tsed::{calculate_tsed, TSEDOptions, DEFAULT_MAX_TREE_NODES};
 use similarity_core::APTEDOptions;
 use std::fs;
 use std::path::PathBuf;
 
+mod analyzers;
+
 // Include auto-generated language configs
 include!(concat!(env!("OUT_DIR"), "/language_configs.rs"));
 
@@ -16,7 +19,7 @@ include!(concat!(env!("OUT_DIR"), "/language_configs.rs"));
 #[command(about = "Generic code similarity analyzer using tree-sitter")]
 struct Cli {
     /// Path to analyze
-    #[arg(required_unless_present_any = ["supported", "show_config"])]
+    #[arg(required_unless_present_any = ["supported", "show_config", "list_analyzers"])]
     path: Option<PathBuf>,
 
     /// Language configuration file (JSON)
@@ -58,6 +61,18 @@ struct Cli {
     /// Size tolerance for overlap detection (0.0-1.0)
     #[arg(long, default_value = "0.25")]
     overlap_size_tolerance: f64,
+
+    /// Keep comments in the compared trees (stripped by default)
+    #[arg(long)]
+    include_comments: bool,
+
+    /// Run a registered analyzer over a file or directory
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["config", "language", "overlap"])]
+    analyzer: Option<String>,
+
+    /// Show registered analyzers
+    #[arg(long)]
+    list_analyzers: bool,
 }
 
 fn main() -> Result<()> {
@@ -72,6 +87,7 @@ fn main() -> Result<()> {
         println!("  cpp        - C++ language");
         println!("  csharp     - C# language");
         println!("  ruby       - Ruby language");
+        println!("  bash       - Bash/shell scripts");
         println!();
         println!("Note: For Python, TypeScript, and Rust, use the dedicated implementations:");
         println!("  similarity-py  - Optimized Python analyzer");
@@ -80,6 +96,15 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if cli.list_analyzers {
+        let registry = analyzers::builtin_registry(cli.include_comments)?;
+        println!("Registered analyzers:");
+        for name in registry.names() {
+            println!("  {name}");
+        }
+        return Ok(());
+    }
+
     // Handle --show-config option
     if let Some(lang) = &cli.show_config {
         let config = match lang.as_str() {
@@ -89,6 +114,7 @@ fn main() -> Result<()> {
             "cpp" | "c++" => GenericParserConfig::cpp(),
             "csharp" | "cs" => GenericParserConfig::csharp(),
             "ruby" | "rb" => GenericParserConfig::ruby(),
+            "bash" | "sh" => GenericParserConfig::bash(),
             _ => {
                 return Err(anyhow::anyhow!(
                     "Unknown language: {}. Use --supported to see available languages.",
@@ -105,6 +131,11 @@ fn main() -> Result<()> {
     // Normal parsing mode
     let path = cli.path.ok_or_else(|| anyhow::anyhow!("Path is required"))?;
 
+    if let Some(name) = &cli.analyzer {
+        let mut registry = analyzers::builtin_registry(cli.include_comments)?;
+        return analyzers::run_named_analyzer(&mut registry, name, &path, cli.threshold);
+    }
+
     let config = if let Some(config_path) = &cli.config {
         GenericParserConfig::from_file(config_path)
             .map_err(|e| anyhow::anyhow!("Failed to load config: {}", e))?
@@ -118,6 +149,7 @@ fn main() -> Result<()> {
                 "cs" => LANGUAGE_CONFIGS.get("csharp"),
                 "ruby" => LANGUAGE_CONFIGS.get("ruby"),
                 "rb" => LANGUAGE_CONFIGS.get("ruby"),
+                "sh" => LANGUAGE_CONFIGS.get("bash"),
                 _ => None,
             })
         {
@@ -132,6 +164,7 @@ fn main() -> Result<()> {
                 "cpp" | "c++" => GenericParserConfig::cpp(),
                 "csharp" | "cs" => GenericParserConfig::csharp(),
                 "ruby" | "rb" => GenericParserConfig::ruby(),
+                "bash" | "sh" => GenericParserConfig::bash(),
                 _ => {
                     eprintln!("Error: Language '{lang}' is not supported by similarity-generic.");
                     eprintln!("Use --supported to see available languages.");
@@ -166,6 +199,7 @@ fn main() -> Result<()> {
         "cpp" => tree_sitter_cpp::LANGUAGE.into(),
         "csharp" => tree_sitter_c_sharp::LANGUAGE.into(),
         "ruby" => tree_sitter_ruby::LANGUAGE.into(),
+        "bash" => tree_sitter_bash::LANGUAGE.into(),
         _ => return Err(anyhow::anyhow!("Unsupported language: {}", config.language)),
     };
 
@@ -217,6 +251,14 @@ fn main() -> Result<()> {
                 min_tokens: None,
                 size_penalty: false,
                 skip_test: false,
+                include_comments: cli.include_comments,
+                size_ratio_penalty: SizePenalty::default(),
+                max_tree_nodes: Some(DEFAULT_MAX_TREE_NODES),
+                canonicalize_order: false,
+                normalize_async: false,
+                normalize_collections: false,
+                strip_types: false,
+                node_weights: Default::default(),
             };
 
             for i in 0..functions.len() {
@@ -0,0 +1,41 @@
+use similarity_core::generic_parser_config::GenericParserConfig;
+use similarity_core::generic_tree_sitter_parser::GenericTreeSitterParser;
+use similarity_core::language_parser::LanguageParser;
+
+#[test]
+fn test_bash_function_detection() {
+    let config = GenericParserConfig::bash();
+    let mut parser = GenericTreeSitterParser::new(tree_sitter_bash::LANGUAGE.into(), config)
+        .expect("Failed to create parser");
+
+    let code = r#"#!/usr/bin/env bash
+
+# Should be detected: POSIX-style function
+log() {
+  echo "[$(date +%T)] $*"
+}
+
+# Should be detected: function keyword
+function deploy {
+  log "deploying $1"
+  kubectl apply -f "$1"
+}
+
+# Should be detected: function keyword with parentheses
+function cleanup() {
+  rm -rf "$TMP_DIR"
+}
+
+# Should NOT be detected: top-level commands
+trap cleanup EXIT
+deploy manifests/
+"#;
+
+    let functions =
+        parser.extract_functions(code, "deploy.sh").expect("Failed to extract functions");
+    let function_names: Vec<&str> = functions.iter().map(|f| f.name.as_str()).collect();
+
+    assert_eq!(function_names, vec!["log", "deploy", "cleanup"]);
+    assert_eq!((functions[1].start_line, functions[1].end_line), (9, 12));
+    assert!(functions.iter().all(|f| !f.is_method));
+}
@@ -319,3 +319,49 @@ public class EdgeCases
     let has_local_function = function_names.iter().any(|n| n.contains("LocalFunction"));
     println!("Has local function: {has_local_function}");
 }
+
+#[test]
+fn test_csharp_type_fields() {
+    let config = GenericParserConfig::csharp();
+    let mut parser = GenericTreeSitterParser::new(tree_sitter_c_sharp::LANGUAGE.into(), config)
+        .expect("Failed to create parser");
+
+    let code = r#"
+namespace Shop.Dtos
+{
+    public record OrderDto(int Id, string Customer, decimal Total);
+
+    public class OrderResponse
+    {
+        public const int MaxItems = 100;
+        private readonly ILogger logger;
+        public int Id { get; set; }
+        public string Customer { get; set; }
+        public decimal Total { get; init; }
+
+        public decimal Tax() => Total * 0.1m;
+
+        public class Line
+        {
+            public string Sku { get; set; }
+        }
+    }
+
+    public record struct Money(decimal Amount)
+    {
+        public string Currency { get; init; }
+    }
+}
+"#;
+
+    let types = parser.extract_types(code, "Orders.cs").expect("Failed to extract types");
+    let fields = |name: &str| {
+        types.iter().find(|t| t.name == name).map(|t| t.fields.clone()).unwrap_or_default()
+    };
+
+    assert_eq!(fields("OrderDto"), vec!["Id", "Customer", "Total"]);
+    // Constants, methods and nested types are not fields
+    assert_eq!(fields("OrderResponse"), vec!["logger", "Id", "Customer", "Total"]);
+    assert_eq!(fields("Line"), vec!["Sku"]);
+    assert_eq!(fields("Money"), vec!["Amount", "Currency"]);
+}
@@ -349,3 +349,306 @@ fn test_python_language_redirect() {
 
     cmd.assert().failure().stderr(predicate::str::contains("similarity-py"));
 }
+
+#[test]
+fn test_list_analyzers() {
+    let mut cmd = Command::cargo_bin("similarity-generic").unwrap();
+    cmd.arg("--list-analyzers");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Registered analyzers:"))
+        .stdout(predicate::str::contains("  go"))
+        .stdout(predicate::str::contains("  ruby"));
+}
+
+#[test]
+fn test_analyzer_across_directory() {
+    let dir = TempDir::new().unwrap();
+    create_test_file(
+        &dir,
+        "a.go",
+        r#"
+package main
+
+func add(a, b int) int {
+    return a + b
+}
+"#,
+    );
+    create_test_file(
+        &dir,
+        "b.go",
+        r#"
+package main
+
+func sum(x, y int) int {
+    return x + y
+}
+"#,
+    );
+    create_test_file(&dir, "ignored.rb", "def add(a, b)\n  a + b\nend\n");
+
+    let mut cmd = Command::cargo_bin("similarity-generic").unwrap();
+    cmd.arg(dir.path()).arg("--analyzer").arg("go").arg("--threshold").arg("0.8");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Duplicates found by analyzer 'go':"))
+        .stdout(predicate::str::contains("a.go:4-6 add"))
+        .stdout(predicate::str::contains("b.go:4-6 sum"))
+        .stdout(predicate::str::contains("Total duplicate pairs found: 1"));
+}
+
+#[test]
+fn test_unknown_analyzer() {
+    let dir = TempDir::new().unwrap();
+
+    let mut cmd = Command::cargo_bin("similarity-generic").unwrap();
+    cmd.arg(dir.path()).arg("--analyzer").arg("kotlin");
+
+    cmd.assert().failure().stderr(predicate::str::contains("Unknown analyzer: kotlin")).stderr(
+        predicate::str::contains("go, java, c, cpp, csharp, ruby, csharp-types, ruby-types, bash"),
+    );
+}
+
+#[test]
+fn test_bash_analyzer() {
+    let dir = TempDir::new().unwrap();
+    let script = r#"#!/bin/bash
+set -e
+
+cd "$(dirname "$0")"
+npm ci
+npm run build
+rsync -az dist/ "deploy@$HOST:/srv/app"
+"#;
+    fs::create_dir(dir.path().join("web")).unwrap();
+    fs::create_dir(dir.path().join("admin")).unwrap();
+    create_test_file(&dir, "web/deploy.sh", script);
+    create_test_file(&dir, "admin/deploy.sh", &script.replace("/srv/app", "/srv/admin"));
+
+    let mut cmd = Command::cargo_bin("similarity-generic").unwrap();
+    cmd.arg(dir.path()).arg("--analyzer").arg("bash");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Duplicates found by analyzer 'bash':"))
+        .stdout(predicate::str::contains("deploy.sh:4-7 top-level block"))
+        .stdout(predicate::str::contains("Total duplicate pairs found: 1"));
+}
+
+#[test]
+fn test_csharp_types_analyzer() {
+    let dir = TempDir::new().unwrap();
+    create_test_file(
+        &dir,
+        "OrderDto.cs",
+        r#"
+namespace Shop.Api
+{
+    public record OrderDto(int Id, string CustomerName, decimal Total, DateTime CreatedAt);
+}
+"#,
+    );
+    create_test_file(
+        &dir,
+        "OrderViewModel.cs",
+        r#"
+namespace Shop.Web
+{
+    public class OrderViewModel
+    {
+        public int Id { get; set; }
+        public string CustomerName { get; set; }
+        public decimal Total { get; set; }
+        public DateTime CreatedAt { get; set; }
+    }
+
+    public class PagingOptions
+    {
+        public int Page { get; set; }
+        public int PageSize { get; set; }
+        public string SortBy { get; set; }
+    }
+}
+"#,
+    );
+
+    let mut cmd = Command::cargo_bin("similarity-generic").unwrap();
+    cmd.arg(dir.path()).arg("--analyzer").arg("csharp-types").arg("--threshold").arg("0.8");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Duplicates found by analyzer 'csharp-types':"))
+        .stdout(predicate::str::contains("OrderDto.cs:4-4 OrderDto"))
+        .stdout(predicate::str::contains("OrderViewModel.cs:4-10 OrderViewModel"))
+        .stdout(predicate::str::contains("PagingOptions").not())
+        .stdout(predicate::str::contains("Total duplicate pairs found: 1"));
+}
+
+#[test]
+fn test_ruby_types_analyzer() {
+    let dir = TempDir::new().unwrap();
+    create_test_file(
+        &dir,
+        "customer.rb",
+        r#"class Customer
+  attr_reader :name, :email, :phone
+
+  def initialize(name, email, phone)
+    @name = name
+    @email = email
+    @phone = phone
+  end
+end
+"#,
+    );
+    create_test_file(
+        &dir,
+        "contact.rb",
+        r#"module Crm
+  class Contact
+    attr_accessor :name, :email, :phone
+  end
+end
+"#,
+    );
+
+    let mut cmd = Command::cargo_bin("similarity-generic").unwrap();
+    cmd.arg(dir.path()).arg("--analyzer").arg("ruby-types").arg("--threshold").arg("0.8");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Duplicates found by analyzer 'ruby-types':"))
+        .stdout(predicate::str::contains("customer.rb:1-9 Customer"))
+        .stdout(predicate::str::contains("contact.rb:2-4 Contact"))
+        .stdout(predicate::str::contains("Total duplicate pairs found: 1"));
+}
+
+#[test]
+fn test_sql_analyzer() {
+    let dir = TempDir::new().unwrap();
+    create_test_file(
+        &dir,
+        "daily.sql",
+        "SELECT id, email FROM users WHERE created_at > '2024-01-01' ORDER BY id;\nCOMMIT;\n",
+    );
+    create_test_file(
+        &dir,
+        "weekly.sql",
+        "-- copied from daily.sql\nselect ID, Email\n  from Users\n where created_at > '2023-12-25'\n order by id;\n",
+    );
+
+    let mut cmd = Command::cargo_bin("similarity-generic").unwrap();
+    cmd.arg(dir.path()).arg("--analyzer").arg("sql");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Similarity: 100.00%"))
+        .stdout(predicate::str::contains("daily.sql:1-1 SELECT users"))
+        .stdout(predicate::str::contains("weekly.sql:2-5 SELECT users"))
+        .stdout(predicate::str::contains("Total duplicate pairs found: 1"));
+}
+
+#[test]
+fn test_yaml_analyzer() {
+    let dir = TempDir::new().unwrap();
+    create_test_file(
+        &dir,
+        "deploy-api.yaml",
+        r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: api
+  labels: { app: api }
+spec:
+  replicas: 3
+  template:
+    spec:
+      containers:
+        - name: api
+          image: registry.example.com/api:1.4
+          ports: [8080]
+          resources: &resources
+            limits: { cpu: 500m, memory: 512Mi }
+"#,
+    );
+    create_test_file(
+        &dir,
+        "deploy-worker.yml",
+        r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: worker
+  labels:
+    app: worker
+spec:
+  replicas: 2
+  template:
+    spec:
+      containers:
+        - name: worker
+          image: registry.example.com/worker:2.0
+          ports: [9090]
+          resources: {}
+"#,
+    );
+
+    let mut cmd = Command::cargo_bin("similarity-generic").unwrap();
+    cmd.arg(dir.path()).arg("--analyzer").arg("yaml").arg("--threshold").arg("0.8");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("deploy-api.yaml:1-15 document"))
+        .stdout(predicate::str::contains("deploy-worker.yml:1-15 document"))
+        .stdout(predicate::str::contains("Total duplicate pairs found: 1"));
+}
+
+#[test]
+fn test_schema_analyzer() {
+    let dir = TempDir::new().unwrap();
+    create_test_file(
+        &dir,
+        "orders.yaml",
+        r#"openapi: 3.0.0
+components:
+  schemas:
+    Address:
+      type: object
+      properties:
+        street: { type: string }
+        city: { type: string }
+        zip: { type: string }
+"#,
+    );
+    create_test_file(
+        &dir,
+        "billing.json",
+        r#"{
+  "openapi": "3.0.0",
+  "components": {
+    "schemas": {
+      "BillingAddress": {
+        "type": "object",
+        "properties": {
+          "street": { "type": "string" },
+          "city": { "type": "string" },
+          "zip": { "type": "string" }
+        }
+      }
+    }
+  }
+}"#,
+    );
+
+    let mut cmd = Command::cargo_bin("similarity-generic").unwrap();
+    cmd.arg(dir.path()).arg("--analyzer").arg("schema").arg("--threshold").arg("0.8");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Duplicated schema definitions:"))
+        .stdout(predicate::str::contains("BillingAddress"))
+        .stdout(predicate::str::contains("$ref: '#/components/schemas/Address'"))
+        .stdout(predicate::str::contains("Total duplicated schema pairs: 1"));
+}
@@ -350,3 +350,43 @@ end
     assert!(!function_names.contains(&"new_name"), "alias_method should not create new detection");
     assert!(!function_names.contains(&"another_name"), "alias should not create new detection");
 }
+
+#[test]
+fn test_ruby_type_fields() {
+    let config = GenericParserConfig::ruby();
+    let mut parser = GenericTreeSitterParser::new(tree_sitter_ruby::LANGUAGE.into(), config)
+        .expect("Failed to create parser");
+
+    let code = r#"
+class User
+  attr_accessor :name, :email
+  attr_reader :id
+
+  def initialize(id, name, email)
+    @id = id
+    @name = name
+    @email = email
+    @created_at = Time.now
+  end
+
+  def display_name
+    @display_name ||= name.capitalize
+  end
+end
+
+module Auditable
+  def audit!
+    puts "audited"
+  end
+end
+"#;
+
+    let types = parser.extract_types(code, "user.rb").expect("Failed to extract types");
+    let fields = |name: &str| {
+        types.iter().find(|t| t.name == name).map(|t| t.fields.clone()).unwrap_or_default()
+    };
+
+    // attr_* declarations first, then instance variables set in initialize
+    assert_eq!(fields("User"), vec!["name", "email", "id", "created_at"]);
+    assert!(fields("Auditable").is_empty(), "Modules without state have no fields");
+}
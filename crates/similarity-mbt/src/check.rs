@@ -41,6 +41,7 @@ pub fn check_paths(
     _fast_mode: bool,
     filter_function: Option<&String>,
     filter_function_body: Option<&String>,
+    include_comments: bool,
 ) -> anyhow::Result<usize> {
     let default_extensions = vec!["mbt"];
     let exts: Vec<&str> =
@@ -60,6 +61,7 @@ pub fn check_paths(
     options.min_lines = min_lines;
     options.min_tokens = min_tokens;
     options.size_penalty = !no_size_penalty;
+    options.include_comments = include_comments;
 
     let mut all_results = Vec::new();
 
@@ -57,6 +57,10 @@ struct Cli {
     /// Exit with code 1 if duplicates are found
     #[arg(long)]
     fail_on_duplicates: bool,
+
+    /// Keep comments and docstrings in the compared trees (stripped by default)
+    #[arg(long)]
+    include_comments: bool,
 }
 
 fn main() -> Result<()> {
@@ -77,6 +81,7 @@ fn main() -> Result<()> {
         !cli.no_fast,
         cli.filter_function.as_ref(),
         cli.filter_function_body.as_ref(),
+        cli.include_comments,
     )?;
 
     if cli.fail_on_duplicates && duplicate_count > 0 {
@@ -1,5 +1,6 @@
 #![allow(clippy::io_other_error)]
 
+use similarity_core::interner::Symbol;
 use similarity_core::language_parser::{
     GenericFunctionDef, GenericTypeDef, Language, LanguageParser,
 };
@@ -87,7 +88,7 @@ impl MoonBitParser {
                             params.map(|p| extract_params(p, source)).unwrap_or_default();
 
                         functions.push(GenericFunctionDef {
-                            name,
+                            name: name.into(),
                             start_line: node.start_position().row as u32 + 1,
                             end_line: node.end_position().row as u32 + 1,
                             body_start_line: body
@@ -98,7 +99,7 @@ impl MoonBitParser {
                                 .unwrap_or(0),
                             parameters: param_list,
                             is_method: true,
-                            class_name: type_name,
+                            class_name: type_name.map(Symbol::from),
                             is_async: false,
                             is_generator: false,
                             decorators: Vec::new(),
@@ -126,14 +127,14 @@ impl MoonBitParser {
             let param_list = params.map(|p| extract_params(p, source)).unwrap_or_default();
 
             Some(GenericFunctionDef {
-                name,
+                name: name.into(),
                 start_line: node.start_position().row as u32 + 1,
                 end_line: node.end_position().row as u32 + 1,
                 body_start_line: body.map(|n| n.start_position().row as u32 + 1).unwrap_or(0),
                 body_end_line: body.map(|n| n.end_position().row as u32 + 1).unwrap_or(0),
                 parameters: param_list,
                 is_method: class_name.is_some(),
-                class_name,
+                class_name: class_name.map(Symbol::from),
                 is_async: false,
                 is_generator: false,
                 decorators: Vec::new(),
@@ -539,7 +540,7 @@ impl Show for Point with to_string(self) {
         assert_eq!(functions.len(), 1);
         assert_eq!(functions[0].name, "to_string");
         assert!(functions[0].is_method);
-        assert_eq!(functions[0].class_name, Some("Point".to_string()));
+        assert_eq!(functions[0].class_name, Some("Point".into()));
         assert_eq!(functions[0].parameters, vec!["self"]);
     }
 
@@ -133,6 +133,13 @@ pub fn check_within_file_duplicates_parallel(
                                 .map(|func| {
                                     let body = extract_function_body(&lines, func);
                                     parser.parse(&body, "body.mbt").ok().map(|tree| {
+                                        // Strip comments up front so size and fingerprint
+                                        // pre-filters ignore them too
+                                        let tree = if options.include_comments {
+                                            tree
+                                        } else {
+                                            tree.without_comments()
+                                        };
                                         let size = tree.get_subtree_size();
                                         let fingerprint = build_fingerprint(&tree);
                                         ParsedFunc { tree, size, fingerprint }
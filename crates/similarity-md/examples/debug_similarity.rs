@@ -41,6 +41,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         char_levenshtein_weight: 0.5,
         word_levenshtein_weight: 0.3,
         morphological_weight: 0.0,
+        shingle_weight: 0.0,
+        shingle_size: 3,
         title_weight: 0.1,
         length_weight: 0.1,
         min_length_ratio: 0.1,
@@ -2,6 +2,7 @@ pub mod levenshtein;
 pub mod markdown_parser;
 pub mod morphological_similarity;
 pub mod section_extractor;
+pub mod shingle;
 pub mod similarity_calculator;
 
 pub use levenshtein::{
@@ -13,6 +14,7 @@ pub use morphological_similarity::{
     MorphemeToken, MorphologicalSimilarityCalculator, PosSimilarity,
 };
 pub use section_extractor::{ExtractedSection, SectionExtractor, SimilarTitlePair};
+pub use shingle::{jaccard_similarity, shingle_similarity, shingles};
 pub use similarity_calculator::{
     SimilarSectionPair, SimilarityCalculator, SimilarityOptions, SimilarityResult,
 };
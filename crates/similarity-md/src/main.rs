@@ -56,6 +56,14 @@ struct Cli {
     #[arg(long, default_value = "0.1")]
     length_weight: f64,
 
+    /// Weight for shingle-based Jaccard similarity (0.0-1.0)
+    #[arg(long, default_value = "0.0")]
+    shingle_weight: f64,
+
+    /// Number of consecutive words per shingle
+    #[arg(long, default_value = "3")]
+    shingle_size: usize,
+
     /// Enable morphological analysis for Japanese text
     #[arg(long)]
     use_morphological: bool,
@@ -127,6 +135,8 @@ fn main() -> Result<()> {
         char_levenshtein_weight: cli.char_weight,
         word_levenshtein_weight: cli.word_weight,
         morphological_weight: cli.morphological_weight,
+        shingle_weight: cli.shingle_weight,
+        shingle_size: cli.shingle_size,
         title_weight: cli.title_weight,
         length_weight: cli.length_weight,
         min_length_ratio: 0.3,
@@ -219,7 +229,11 @@ fn find_markdown_files(
                 }
             }
         } else if path.is_dir() {
-            let walker = WalkBuilder::new(path).follow_links(false).build();
+            // Honor .similarityignore files like similarity-core's walker does
+            let walker = WalkBuilder::new(path)
+                .follow_links(false)
+                .add_custom_ignore_filename(".similarityignore")
+                .build();
 
             for entry in walker {
                 let entry = entry?;
@@ -292,10 +306,11 @@ fn output_text(similar_pairs: &[similarity_md::SimilarSectionPair], print_conten
 
         // Show detailed similarity breakdown
         println!(
-            "   Character-level: {:.2}%, Word-level: {:.2}%, Morphological: {:.2}%, Title: {:.2}%, Length: {:.2}%",
+            "   Character-level: {:.2}%, Word-level: {:.2}%, Morphological: {:.2}%, Shingle: {:.2}%, Title: {:.2}%, Length: {:.2}%",
             pair.result.char_levenshtein_similarity * 100.0,
             pair.result.word_levenshtein_similarity * 100.0,
             pair.result.morphological_similarity * 100.0,
+            pair.result.shingle_similarity * 100.0,
             pair.result.title_similarity * 100.0,
             pair.result.length_similarity * 100.0
         );
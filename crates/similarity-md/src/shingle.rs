@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+
+/// Build the set of k-shingles (contiguous token windows) for a text
+/// For Japanese text, characters are used as tokens since words are not separated by whitespace
+pub fn shingles(text: &str, size: usize) -> HashSet<String> {
+    let size = size.max(1);
+    let tokens: Vec<String> = if contains_japanese(text) {
+        text.chars().filter(|c| !c.is_whitespace()).map(|c| c.to_string()).collect()
+    } else {
+        text.split_whitespace().map(str::to_string).collect()
+    };
+
+    if tokens.is_empty() {
+        return HashSet::new();
+    }
+
+    // Short texts still produce a single shingle so they can be compared
+    if tokens.len() < size {
+        return std::iter::once(tokens.join(" ")).collect();
+    }
+
+    tokens.windows(size).map(|window| window.join(" ")).collect()
+}
+
+/// Calculate Jaccard similarity between two shingle sets (0.0 to 1.0)
+pub fn jaccard_similarity(set1: &HashSet<String>, set2: &HashSet<String>) -> f64 {
+    if set1.is_empty() && set2.is_empty() {
+        return 1.0;
+    }
+
+    let intersection = set1.intersection(set2).count();
+    let union = set1.len() + set2.len() - intersection;
+
+    intersection as f64 / union as f64
+}
+
+/// Calculate shingle-based Jaccard similarity between two texts
+/// Unlike Levenshtein, this is insensitive to paragraph reordering and runs in linear time
+pub fn shingle_similarity(s1: &str, s2: &str, size: usize) -> f64 {
+    jaccard_similarity(&shingles(s1, size), &shingles(s2, size))
+}
+
+fn contains_japanese(text: &str) -> bool {
+    text.chars().any(|c| {
+        // ひらがな (U+3040-U+309F)
+        ('\u{3040}'..='\u{309F}').contains(&c) ||
+        // カタカナ (U+30A0-U+30FF)
+        ('\u{30A0}'..='\u{30FF}').contains(&c) ||
+        // 漢字 (U+4E00-U+9FAF)
+        ('\u{4E00}'..='\u{9FAF}').contains(&c)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shingles() {
+        let set = shingles("the quick brown fox", 2);
+        assert_eq!(set.len(), 3);
+        assert!(set.contains("quick brown"));
+
+        assert_eq!(shingles("hello world", 3).len(), 1);
+        assert!(shingles("", 3).is_empty());
+        assert_eq!(shingles("機械学習", 2).len(), 3);
+    }
+
+    #[test]
+    fn test_shingle_similarity() {
+        assert_eq!(shingle_similarity("a b c d", "a b c d", 2), 1.0);
+        assert_eq!(shingle_similarity("", "", 3), 1.0);
+        assert_eq!(shingle_similarity("a b c", "x y z", 2), 0.0);
+
+        // {a b, b c, c d} vs {a b, b c, c e}: 2 shared out of 4
+        assert!((shingle_similarity("a b c d", "a b c e", 2) - 0.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_reordered_paragraphs_stay_similar() {
+        let first = "install the package with cargo then run the binary on your docs directory";
+        let second = "run the binary on your docs directory then install the package with cargo";
+        assert!(shingle_similarity(first, second, 3) > 0.5);
+    }
+}
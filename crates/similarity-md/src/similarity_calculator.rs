@@ -1,6 +1,7 @@
 use crate::levenshtein::{levenshtein_similarity, word_levenshtein_similarity};
 use crate::morphological_similarity::MorphologicalSimilarityCalculator;
 use crate::section_extractor::ExtractedSection;
+use crate::shingle::shingle_similarity;
 use serde::{Deserialize, Serialize};
 
 /// Options for similarity calculation
@@ -12,6 +13,10 @@ pub struct SimilarityOptions {
     pub word_levenshtein_weight: f64,
     /// Weight for morphological similarity (0.0-1.0)
     pub morphological_weight: f64,
+    /// Weight for shingle-based Jaccard similarity (0.0-1.0)
+    pub shingle_weight: f64,
+    /// Number of consecutive words (characters for Japanese) per shingle
+    pub shingle_size: usize,
     /// Weight for title similarity (0.0-1.0)
     pub title_weight: f64,
     /// Weight for content length similarity (0.0-1.0)
@@ -36,6 +41,8 @@ impl Default for SimilarityOptions {
             char_levenshtein_weight: 0.3,
             word_levenshtein_weight: 0.2,
             morphological_weight: 0.3,
+            shingle_weight: 0.0,
+            shingle_size: 3,
             title_weight: 0.1,
             length_weight: 0.1,
             min_length_ratio: 0.3,
@@ -54,6 +61,7 @@ impl SimilarityOptions {
         let total_weight = self.char_levenshtein_weight
             + self.word_levenshtein_weight
             + self.morphological_weight
+            + self.shingle_weight
             + self.title_weight
             + self.length_weight;
 
@@ -76,6 +84,8 @@ pub struct SimilarityResult {
     pub word_levenshtein_similarity: f64,
     /// Morphological similarity (Japanese text analysis)
     pub morphological_similarity: f64,
+    /// Shingle-based Jaccard similarity
+    pub shingle_similarity: f64,
     /// Title similarity
     pub title_similarity: f64,
     /// Content length similarity
@@ -140,6 +150,7 @@ impl SimilarityCalculator {
                 char_levenshtein_similarity: 0.0,
                 word_levenshtein_similarity: 0.0,
                 morphological_similarity: 0.0,
+                shingle_similarity: 0.0,
                 title_similarity: 0.0,
                 length_similarity: 0.0,
                 same_level: false,
@@ -173,8 +184,22 @@ impl SimilarityCalculator {
         };
 
         // Calculate individual similarities
-        let char_levenshtein_similarity = levenshtein_similarity(&content1, &content2);
-        let word_levenshtein_similarity = word_levenshtein_similarity(&content1, &content2);
+        // Levenshtein is quadratic in section length, so skip it when it does not contribute
+        let char_levenshtein_similarity = if self.options.char_levenshtein_weight > 0.0 {
+            levenshtein_similarity(&content1, &content2)
+        } else {
+            0.0
+        };
+        let word_levenshtein_similarity = if self.options.word_levenshtein_weight > 0.0 {
+            word_levenshtein_similarity(&content1, &content2)
+        } else {
+            0.0
+        };
+        let shingle_similarity = if self.options.shingle_weight > 0.0 {
+            shingle_similarity(&content1, &content2, self.options.shingle_size)
+        } else {
+            0.0
+        };
         let title_similarity = levenshtein_similarity(&title1, &title2);
         let length_similarity = self.calculate_length_similarity(section1, section2);
 
@@ -201,6 +226,7 @@ impl SimilarityCalculator {
         let similarity = (char_levenshtein_similarity * self.options.char_levenshtein_weight
             + word_levenshtein_similarity * self.options.word_levenshtein_weight
             + morphological_similarity * self.options.morphological_weight
+            + shingle_similarity * self.options.shingle_weight
             + title_similarity * self.options.title_weight
             + length_similarity * self.options.length_weight)
             * hierarchy_penalty;
@@ -210,6 +236,7 @@ impl SimilarityCalculator {
             char_levenshtein_similarity,
             word_levenshtein_similarity,
             morphological_similarity,
+            shingle_similarity,
             title_similarity,
             length_similarity,
             same_level,
@@ -440,6 +467,39 @@ mod tests {
         assert!(similar_pairs[0].result.similarity > 0.6);
     }
 
+    #[test]
+    fn test_shingle_only_similarity() {
+        let options = SimilarityOptions {
+            char_levenshtein_weight: 0.0,
+            word_levenshtein_weight: 0.0,
+            morphological_weight: 0.0,
+            shingle_weight: 0.8,
+            title_weight: 0.1,
+            length_weight: 0.1,
+            ..Default::default()
+        };
+        let calculator = SimilarityCalculator::with_options(options).unwrap();
+
+        let section1 = create_test_section(
+            "Contributing",
+            "Fork the repository. Run cargo test before opening a pull request.",
+            2,
+            "README.md",
+        );
+        let section2 = create_test_section(
+            "How to Contribute",
+            "Run cargo test before opening a pull request. Fork the repository.",
+            2,
+            "CONTRIBUTING.md",
+        );
+
+        let result = calculator.calculate_similarity(&section1, &section2);
+
+        assert!(result.shingle_similarity > 0.6);
+        assert_eq!(result.char_levenshtein_similarity, 0.0);
+        assert!(result.similarity > 0.6);
+    }
+
     #[test]
     fn test_similarity_options_validation() {
         let mut options = SimilarityOptions::default();
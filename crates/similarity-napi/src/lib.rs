@@ -0,0 +1,211 @@
+//! N-API bindings for Node.js, Bun and Deno.
+//!
+//! JavaScript toolchains (ESLint plugins, build scripts, bundler plugins)
+//! call the engine in-process through these functions instead of spawning
+//! `similarity-ts` and parsing its output. Bun and Deno load the same
+//! module through their Node-API support, so one build serves all three.
+//! Names are exported in camelCase: `analyzeProject` and `compareFunctions`.
+
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Error, Result, Task};
+use napi_derive::napi;
+use similarity_core::cli_file_utils::collect_files;
+use similarity_core::tsed::{calculate_tsed_from_code, TSEDOptions};
+use similarity_core::{
+    find_similar_functions_across_extracted, visit_similar_functions_in_extracted,
+    ComparisonBudget, ExtractedFile, FunctionDefinition, SimilarityResult,
+};
+
+/// Extensions analyzed when none are given, as in `similarity-ts`
+const DEFAULT_EXTENSIONS: [&str; 8] = ["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+const DEFAULT_THRESHOLD: f64 = 0.87;
+
+/// Options of `analyzeProject` and `compareFunctions`; unset fields take the
+/// `similarity-ts` defaults
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzeOptions {
+    /// Similarity from which a pair is reported, 0.87 by default
+    pub threshold: Option<f64>,
+    /// Functions shorter than this are skipped, 5 by default
+    pub min_lines: Option<u32>,
+    pub min_tokens: Option<u32>,
+    pub rename_cost: Option<f64>,
+    /// Disable the penalty for short functions
+    pub no_size_penalty: Option<bool>,
+    /// Extensions of the files to analyze, without the dot
+    pub extensions: Option<Vec<String>>,
+}
+
+impl AnalyzeOptions {
+    fn tsed_options(&self) -> TSEDOptions {
+        let mut options = TSEDOptions::default();
+        if let Some(min_lines) = self.min_lines {
+            options.min_lines = min_lines;
+        }
+        options.min_tokens = self.min_tokens;
+        if let Some(rename_cost) = self.rename_cost {
+            options.apted_options.rename_cost = rename_cost;
+        }
+        options.size_penalty = !self.no_size_penalty.unwrap_or(false);
+        options
+    }
+}
+
+/// Where a function is defined
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct FunctionLocation {
+    pub file: String,
+    pub name: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+impl FunctionLocation {
+    fn new(file: &str, function: &FunctionDefinition) -> Self {
+        Self {
+            file: file.to_string(),
+            name: function.name.to_string(),
+            start_line: function.start_line,
+            end_line: function.end_line,
+        }
+    }
+}
+
+/// Two similar functions
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct SimilarPair {
+    pub first: FunctionLocation,
+    pub second: FunctionLocation,
+    pub similarity: f64,
+    /// Lines that merging the pair would remove
+    pub impact: u32,
+}
+
+impl SimilarPair {
+    fn new(first_file: &str, result: &SimilarityResult, second_file: &str) -> Self {
+        Self {
+            first: FunctionLocation::new(first_file, &result.func1),
+            second: FunctionLocation::new(second_file, &result.func2),
+            similarity: result.similarity,
+            impact: result.impact,
+        }
+    }
+}
+
+/// Similar functions within and across `sources`, most impactful first
+pub fn analyze_sources(
+    sources: &[(String, String)],
+    options: &AnalyzeOptions,
+) -> std::result::Result<Vec<SimilarPair>, String> {
+    let threshold = options.threshold.unwrap_or(DEFAULT_THRESHOLD);
+    let tsed_options = options.tsed_options();
+    let budget = ComparisonBudget::unlimited();
+    // Each file is parsed once; files that do not parse are skipped, as the CLI does
+    let files: Vec<ExtractedFile> = sources
+        .iter()
+        .filter_map(|(file, source)| ExtractedFile::extract(file, source).ok())
+        .collect();
+
+    let mut pairs = Vec::new();
+    for file in &files {
+        visit_similar_functions_in_extracted(
+            file,
+            threshold,
+            &tsed_options,
+            &budget,
+            |func1, func2, similarity, algorithm| {
+                let result = SimilarityResult::new(func1.clone(), func2.clone(), similarity)
+                    .with_algorithm(algorithm);
+                pairs.push(SimilarPair::new(file.filename, &result, file.filename));
+            },
+        )?;
+    }
+    let across =
+        find_similar_functions_across_extracted(&files, threshold, &tsed_options, &budget)?;
+    pairs.extend(
+        across.iter().map(|(first, result, second)| SimilarPair::new(first, result, second)),
+    );
+    pairs.sort_by(|a, b| {
+        b.impact.cmp(&a.impact).then_with(|| b.similarity.total_cmp(&a.similarity))
+    });
+    Ok(pairs)
+}
+
+pub struct AnalyzeProject {
+    paths: Vec<String>,
+    options: AnalyzeOptions,
+}
+
+impl Task for AnalyzeProject {
+    type Output = Vec<SimilarPair>;
+    type JsValue = Vec<SimilarPair>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let extensions: Vec<&str> = match &self.options.extensions {
+            Some(extensions) => extensions.iter().map(String::as_str).collect(),
+            None => DEFAULT_EXTENSIONS.to_vec(),
+        };
+        let files = collect_files(&self.paths, &extensions)
+            .map_err(|e| Error::from_reason(e.to_string()))?;
+        let sources: Vec<(String, String)> = files
+            .iter()
+            .filter_map(|path| {
+                let source = std::fs::read_to_string(path).ok()?;
+                Some((path.to_string_lossy().into_owned(), source))
+            })
+            .collect();
+        analyze_sources(&sources, &self.options).map_err(Error::from_reason)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Find similar functions in the files under `paths`. Resolves once the
+/// analysis, which runs off the JavaScript thread, is done.
+#[napi(ts_return_type = "Promise<Array<SimilarPair>>")]
+pub fn analyze_project(
+    paths: Vec<String>,
+    options: Option<AnalyzeOptions>,
+) -> AsyncTask<AnalyzeProject> {
+    AsyncTask::new(AnalyzeProject { paths, options: options.unwrap_or_default() })
+}
+
+/// Similarity between 0 and 1 of two snippets of TypeScript or JavaScript
+#[napi]
+pub fn compare_functions(
+    code1: String,
+    code2: String,
+    options: Option<AnalyzeOptions>,
+) -> Result<f64> {
+    let options = options.unwrap_or_default().tsed_options();
+    calculate_tsed_from_code(&code1, &code2, "a.ts", "b.ts", &options).map_err(Error::from_reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs_within_and_across_files_are_reported() {
+        let body = |name: &str| {
+            format!("function {name}(items) {{\n  let total = 0;\n  for (const item of items) {{\n    total += item.price * item.quantity;\n  }}\n  return total;\n}}\n")
+        };
+        let sources = vec![
+            ("a.ts".to_string(), format!("{}\n{}", body("sumA"), body("sumB"))),
+            ("b.ts".to_string(), body("sumC")),
+            ("broken.ts".to_string(), "function (".to_string()),
+        ];
+        let options = AnalyzeOptions { no_size_penalty: Some(true), ..AnalyzeOptions::default() };
+        let pairs = analyze_sources(&sources, &options).unwrap();
+
+        assert_eq!(pairs.len(), 3);
+        assert!(pairs.iter().any(|pair| pair.first.file == pair.second.file));
+        assert!(pairs.iter().any(|pair| pair.second.file == "b.ts" && pair.second.name == "sumC"));
+        assert_eq!(compare_functions(body("f"), body("f"), Some(options)).unwrap(), 1.0);
+    }
+}
@@ -69,6 +69,10 @@ struct Cli {
     /// Size tolerance for overlap detection (0.0-1.0)
     #[arg(long, default_value = "0.25")]
     overlap_size_tolerance: f64,
+
+    /// Keep comments and docstrings in the compared trees (stripped by default)
+    #[arg(long)]
+    include_comments: bool,
 }
 
 fn main() -> Result<()> {
@@ -96,6 +100,7 @@ fn main() -> Result<()> {
             !cli.no_fast,
             cli.filter_function.as_ref(),
             cli.filter_function_body.as_ref(),
+            cli.include_comments,
         )?;
     }
 
@@ -1,3 +1,4 @@
+use similarity_core::interner::Symbol;
 use similarity_core::language_parser::{
     GenericFunctionDef, GenericTypeDef, Language, LanguageParser,
 };
@@ -86,7 +87,7 @@ impl PhpParser {
                             };
 
                             functions.push(GenericFunctionDef {
-                                name: full_name,
+                                name: full_name.into(),
                                 start_line: node.start_position().row as u32 + 1,
                                 end_line: node.end_position().row as u32 + 1,
                                 body_start_line: body_node
@@ -97,7 +98,7 @@ impl PhpParser {
                                     .unwrap_or(0),
                                 parameters: params,
                                 is_method: class_name.is_some(),
-                                class_name: class_name.map(|s| s.to_string()),
+                                class_name: class_name.map(Symbol::from),
                                 is_async: false, // PHP doesn't have async/await syntax
                                 is_generator: is_generator_function(node, source),
                                 decorators: Vec::new(), // PHP doesn't have decorators like Python
@@ -119,7 +120,7 @@ impl PhpParser {
                             let method_name = format!("{}::{}", class_name.unwrap_or(""), name);
 
                             functions.push(GenericFunctionDef {
-                                name: method_name,
+                                name: method_name.into(),
                                 start_line: node.start_position().row as u32 + 1,
                                 end_line: node.end_position().row as u32 + 1,
                                 body_start_line: body_node
@@ -130,7 +131,7 @@ impl PhpParser {
                                     .unwrap_or(0),
                                 parameters: params,
                                 is_method: true,
-                                class_name: class_name.map(|s| s.to_string()),
+                                class_name: class_name.map(Symbol::from),
                                 is_async: false,
                                 is_generator: is_generator_function(node, source),
                                 decorators: vec![
@@ -560,8 +561,8 @@ function standalone_function() {
         let method2 = functions.iter().find(|f| f.name.contains("method2")).unwrap();
         let standalone = functions.iter().find(|f| f.name == "standalone_function").unwrap();
 
-        assert_eq!(method1.class_name, Some("TestClass".to_string()));
-        assert_eq!(method2.class_name, Some("TestClass".to_string()));
+        assert_eq!(method1.class_name, Some("TestClass".into()));
+        assert_eq!(method2.class_name, Some("TestClass".into()));
         assert_eq!(standalone.class_name, None);
 
         assert!(method1.is_method);
@@ -42,6 +42,7 @@ pub fn check_paths(
     _fast_mode: bool, // Python doesn't support fast mode yet
     filter_function: Option<&String>,
     filter_function_body: Option<&String>,
+    include_comments: bool,
 ) -> anyhow::Result<usize> {
     let default_extensions = vec!["py"];
     let exts: Vec<&str> =
@@ -61,6 +62,7 @@ pub fn check_paths(
     options.min_lines = min_lines;
     options.min_tokens = min_tokens;
     options.size_penalty = !no_size_penalty;
+    options.include_comments = include_comments;
 
     let mut all_results = Vec::new();
 
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use clap::Parser;
+use similarity_core::TSEDOptions;
 
 mod check;
 mod parallel;
@@ -70,9 +71,17 @@ struct Cli {
     #[arg(long, default_value = "0.25")]
     overlap_size_tolerance: f64,
 
+    /// Compare code cells of Python Jupyter notebooks (.ipynb)
+    #[arg(long)]
+    notebooks: bool,
+
     /// Exit with code 1 if duplicates are found
     #[arg(long)]
     fail_on_duplicates: bool,
+
+    /// Keep comments and docstrings in the compared trees (stripped by default)
+    #[arg(long)]
+    include_comments: bool,
 }
 
 fn main() -> Result<()> {
@@ -101,6 +110,7 @@ fn main() -> Result<()> {
             !cli.no_fast,
             cli.filter_function.as_ref(),
             cli.filter_function_body.as_ref(),
+            cli.include_comments,
         )?;
         total_duplicates += duplicate_count;
     }
@@ -113,7 +123,7 @@ fn main() -> Result<()> {
     if overlap_enabled {
         println!("=== Overlap Detection ===");
         let overlap_duplicate_count = check_overlaps(
-            cli.paths,
+            cli.paths.clone(),
             cli.threshold,
             cli.extensions.as_ref(),
             cli.print,
@@ -124,6 +134,18 @@ fn main() -> Result<()> {
         total_duplicates += overlap_duplicate_count;
     }
 
+    if cli.notebooks {
+        println!("\n{separator}\n");
+        println!("=== Notebook Cell Similarity ===");
+        let mut options = TSEDOptions::default();
+        options.apted_options.rename_cost = cli.rename_cost;
+        options.min_lines = cli.min_lines.unwrap_or(3);
+        options.min_tokens = cli.min_tokens;
+        options.size_penalty = !cli.no_size_penalty;
+        options.include_comments = cli.include_comments;
+        total_duplicates += check_notebooks(&cli.paths, cli.threshold, &options, cli.print)?;
+    }
+
     // Exit with code 1 if duplicates found and --fail-on-duplicates is set
     if cli.fail_on_duplicates && total_duplicates > 0 {
         std::process::exit(1);
@@ -143,7 +165,7 @@ fn check_overlaps(
     size_tolerance: f64,
 ) -> anyhow::Result<usize> {
     use crate::python_parser::PythonParser;
-    use ignore::WalkBuilder;
+    use similarity_core::cli_file_utils::walk_dir;
     use similarity_core::{find_overlaps_across_files_generic, OverlapOptions};
     use std::collections::{HashMap, HashSet};
     use std::fs;
@@ -174,8 +196,8 @@ fn check_overlaps(
                 }
             }
         } else if path.is_dir() {
-            // If it's a directory, walk it respecting .gitignore
-            let walker = WalkBuilder::new(path).follow_links(false).build();
+            // If it's a directory, walk it respecting .gitignore and .similarityignore
+            let walker = walk_dir(path);
 
             for entry in walker {
                 let entry = entry?;
@@ -304,6 +326,61 @@ fn check_overlaps(
     Ok(overlaps.len())
 }
 
+fn check_notebooks(
+    paths: &[String],
+    threshold: f64,
+    options: &TSEDOptions,
+    print: bool,
+) -> anyhow::Result<usize> {
+    use crate::python_parser::PythonParser;
+    use similarity_core::{
+        cli_file_utils::collect_files, find_duplicate_cells, language_parser::LanguageParser,
+        parse_notebook, report_duplicate_cells, ParsedCell,
+    };
+
+    let files = collect_files(paths, &["ipynb"])?;
+    let mut parser = PythonParser::new()
+        .map_err(|e| anyhow::anyhow!("Failed to create Python parser: {}", e))?;
+    let mut cells = Vec::new();
+    let mut notebook_count = 0;
+
+    for file in &files {
+        let notebook_path = get_relative_path(&file.to_string_lossy());
+        let notebook = match std::fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|content| parse_notebook(&content))
+        {
+            Ok(notebook) => notebook,
+            Err(e) => {
+                eprintln!("Error parsing {}: {}", notebook_path, e);
+                continue;
+            }
+        };
+        if !notebook.is_language(&["python"]) {
+            continue;
+        }
+        notebook_count += 1;
+
+        for cell in notebook.cells {
+            match parser.parse(&cell.source, &notebook_path) {
+                Ok(tree) => {
+                    cells.push(ParsedCell { notebook: notebook_path.clone(), cell, tree });
+                }
+                Err(e) => eprintln!("Error parsing {} cell {}: {}", notebook_path, cell.number, e),
+            }
+        }
+    }
+
+    if notebook_count == 0 {
+        println!("No Python notebooks found in the specified paths.");
+        return Ok(0);
+    }
+
+    println!("Checking {} code cells in {} notebooks...", cells.len(), notebook_count);
+    let duplicates = find_duplicate_cells(&cells, threshold, options);
+    Ok(report_duplicate_cells(&duplicates, print))
+}
+
 fn get_relative_path(file_path: &str) -> String {
     if let Ok(current_dir) = std::env::current_dir() {
         std::path::Path::new(file_path)
@@ -1,5 +1,6 @@
 #![allow(clippy::io_other_error)]
 
+use similarity_core::interner::Symbol;
 use similarity_core::language_parser::{
     GenericFunctionDef, GenericTypeDef, Language, LanguageParser,
 };
@@ -30,7 +31,9 @@ impl PythonParser {
         let current_id = *id_counter;
         *id_counter += 1;
 
-        let label = node.kind().to_string();
+        // Docstrings are labeled like comments so comparisons can strip them
+        let label =
+            if is_docstring(node) { "docstring".to_string() } else { node.kind().to_string() };
         let value = match node.kind() {
             "identifier" | "string" | "integer" | "float" | "true" | "false" | "none" => {
                 node.utf8_text(source.as_bytes()).unwrap_or("").to_string()
@@ -73,7 +76,7 @@ impl PythonParser {
                             let params = extract_params(params_node, source);
 
                             functions.push(GenericFunctionDef {
-                                name: name.to_string(),
+                                name: name.into(),
                                 start_line: node.start_position().row as u32 + 1,
                                 end_line: node.end_position().row as u32 + 1,
                                 body_start_line: body_node
@@ -84,7 +87,7 @@ impl PythonParser {
                                     .unwrap_or(0),
                                 parameters: params,
                                 is_method: class_name.is_some(),
-                                class_name: class_name.map(|s| s.to_string()),
+                                class_name: class_name.map(Symbol::from),
                                 is_async: is_async_def(node, source),
                                 is_generator: is_generator_def(node, source),
                                 decorators: extract_decorators(node, source),
@@ -104,7 +107,7 @@ impl PythonParser {
                                     let params = extract_params(params_node, source);
 
                                     functions.push(GenericFunctionDef {
-                                        name: name.to_string(),
+                                        name: name.into(),
                                         start_line: node.start_position().row as u32 + 1,
                                         end_line: node.end_position().row as u32 + 1,
                                         body_start_line: body_node
@@ -115,7 +118,7 @@ impl PythonParser {
                                             .unwrap_or(0),
                                         parameters: params,
                                         is_method: class_name.is_some(),
-                                        class_name: class_name.map(|s| s.to_string()),
+                                        class_name: class_name.map(Symbol::from),
                                         is_async: is_async_def(child, source),
                                         is_generator: is_generator_def(child, source),
                                         decorators: extract_decorators(child, source),
@@ -222,6 +225,29 @@ impl PythonParser {
     }
 }
 
+/// A string expression that is the first statement of a module or block
+fn is_docstring(node: Node) -> bool {
+    if node.kind() != "expression_statement"
+        || node.named_child_count() != 1
+        || node.named_child(0).is_none_or(|child| child.kind() != "string")
+    {
+        return false;
+    }
+
+    if !node.parent().is_some_and(|parent| matches!(parent.kind(), "module" | "block")) {
+        return false;
+    }
+
+    let mut sibling = node.prev_named_sibling();
+    while let Some(prev) = sibling {
+        if prev.kind() != "comment" {
+            return false;
+        }
+        sibling = prev.prev_named_sibling();
+    }
+    true
+}
+
 impl LanguageParser for PythonParser {
     fn parse(
         &mut self,
@@ -379,7 +405,7 @@ class Calculator:
         assert!(!functions[1].is_method);
         assert_eq!(functions[2].name, "__init__");
         assert!(functions[2].is_method);
-        assert_eq!(functions[2].class_name, Some("Calculator".to_string()));
+        assert_eq!(functions[2].class_name, Some("Calculator".into()));
         assert_eq!(functions[3].name, "add");
         assert!(functions[3].is_method);
     }
@@ -163,3 +163,52 @@ def longer_func2():
         .stdout(predicate::str::contains("longer_func2"))
         .stdout(predicate::str::contains("f1").not());
 }
+
+#[test]
+fn test_notebook_cells() {
+    let dir = tempdir().unwrap();
+    let notebook_path = dir.path().join("analysis.ipynb");
+
+    let content = r###"{
+  "cells": [
+    { "cell_type": "code", "metadata": {}, "outputs": [], "source": ["!pip install pandas\n", "import pandas as pd"] },
+    {
+      "cell_type": "code", "metadata": {}, "outputs": [],
+      "source": [
+        "sales = pd.read_csv('sales.csv')\n",
+        "sales = sales[sales['amount'] > 0]\n",
+        "sales['total'] = sales['amount'] * sales['price']\n",
+        "sales.groupby('region')['total'].sum()"
+      ]
+    },
+    { "cell_type": "markdown", "metadata": {}, "source": ["## Costs"] },
+    {
+      "cell_type": "code", "metadata": {}, "outputs": [],
+      "source": [
+        "costs = pd.read_csv('costs.csv')\n",
+        "costs = costs[costs['amount'] > 0]\n",
+        "costs['total'] = costs['amount'] * costs['rate']\n",
+        "costs.groupby('region')['total'].sum()"
+      ]
+    },
+    { "cell_type": "code", "metadata": {}, "outputs": [], "source": "%%bash\nls -la\nwc -l *.csv\necho done" }
+  ],
+  "metadata": { "kernelspec": { "name": "python3", "language": "python" } },
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"###;
+
+    fs::write(&notebook_path, content).unwrap();
+
+    Command::cargo_bin("similarity-py")
+        .unwrap()
+        .arg(dir.path())
+        .arg("--notebooks")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("=== Notebook Cell Similarity ==="))
+        .stdout(predicate::str::contains("Checking 3 code cells in 1 notebooks"))
+        .stdout(predicate::str::contains("analysis.ipynb:1-4 cell 2"))
+        .stdout(predicate::str::contains("analysis.ipynb:1-4 cell 4"))
+        .stdout(predicate::str::contains("Total duplicate cell pairs found: 1"));
+}
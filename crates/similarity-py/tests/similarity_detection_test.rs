@@ -1,7 +1,7 @@
 use similarity_core::language_parser::LanguageParser;
 use similarity_core::{
     calculate_enhanced_similarity, tsed::calculate_tsed, APTEDOptions, EnhancedSimilarityOptions,
-    TSEDOptions,
+    SizePenalty, TSEDOptions, DEFAULT_MAX_TREE_NODES,
 };
 use similarity_py::python_parser::PythonParser;
 
@@ -46,6 +46,14 @@ def compute_total(values):
         min_tokens: None,
         size_penalty: false, // Disable for this test
         skip_test: false,
+        include_comments: false,
+        size_ratio_penalty: SizePenalty::default(),
+        max_tree_nodes: Some(DEFAULT_MAX_TREE_NODES),
+        canonicalize_order: false,
+        normalize_async: false,
+        normalize_collections: false,
+        strip_types: false,
+        node_weights: Default::default(),
     };
 
     let similarity = calculate_tsed(&tree1, &tree2, &tsed_options);
@@ -120,6 +128,14 @@ def filter_positive(numbers):
         min_tokens: None,
         size_penalty: true, // Enable size penalty
         skip_test: false,
+        include_comments: false,
+        size_ratio_penalty: SizePenalty::default(),
+        max_tree_nodes: Some(DEFAULT_MAX_TREE_NODES),
+        canonicalize_order: false,
+        normalize_async: false,
+        normalize_collections: false,
+        strip_types: false,
+        node_weights: Default::default(),
     };
 
     let similarity = calculate_tsed(&tree1, &tree2, &tsed_options);
@@ -166,6 +182,14 @@ class MathOperations:
         min_tokens: None,
         size_penalty: false,
         skip_test: false,
+        include_comments: false,
+        size_ratio_penalty: SizePenalty::default(),
+        max_tree_nodes: Some(DEFAULT_MAX_TREE_NODES),
+        canonicalize_order: false,
+        normalize_async: false,
+        normalize_collections: false,
+        strip_types: false,
+        node_weights: Default::default(),
     };
 
     let similarity = calculate_tsed(&tree1, &tree2, &tsed_options);
@@ -203,6 +227,14 @@ def title(self):
         min_tokens: None,
         size_penalty: false,
         skip_test: false,
+        include_comments: false,
+        size_ratio_penalty: SizePenalty::default(),
+        max_tree_nodes: Some(DEFAULT_MAX_TREE_NODES),
+        canonicalize_order: false,
+        normalize_async: false,
+        normalize_collections: false,
+        strip_types: false,
+        node_weights: Default::default(),
     };
 
     let similarity = calculate_tsed(&tree1, &tree2, &tsed_options);
@@ -278,6 +310,14 @@ def get_numbers(n):
         min_tokens: None,
         size_penalty: true,
         skip_test: false,
+        include_comments: false,
+        size_ratio_penalty: SizePenalty::default(),
+        max_tree_nodes: Some(DEFAULT_MAX_TREE_NODES),
+        canonicalize_order: false,
+        normalize_async: false,
+        normalize_collections: false,
+        strip_types: false,
+        node_weights: Default::default(),
     };
 
     let similarity = calculate_tsed(&tree1, &tree2, &tsed_options);
@@ -316,6 +356,14 @@ async def get_data(endpoint):
         min_tokens: None,
         size_penalty: false,
         skip_test: false,
+        include_comments: false,
+        size_ratio_penalty: SizePenalty::default(),
+        max_tree_nodes: Some(DEFAULT_MAX_TREE_NODES),
+        canonicalize_order: false,
+        normalize_async: false,
+        normalize_collections: false,
+        strip_types: false,
+        node_weights: Default::default(),
     };
 
     let similarity = calculate_tsed(&tree1, &tree2, &tsed_options);
@@ -324,3 +372,36 @@ async def get_data(endpoint):
     // Similar async functions should be detected
     assert!(similarity > 0.8, "Similar async functions were not detected: {similarity}");
 }
+
+#[test]
+fn test_docstrings_and_comments_are_ignored_by_default() {
+    let code1 = r#"
+def normalize(values):
+    """Scale values so that they sum to one."""
+    total = sum(values)
+    # avoid division by zero
+    if total == 0:
+        return values
+    return [v / total for v in values]
+"#;
+
+    let code2 = r#"
+def normalize(values):
+    total = sum(values)
+    if total == 0:
+        return values
+    return [v / total for v in values]
+"#;
+
+    let mut parser = PythonParser::new().unwrap();
+    let tree1 = parser.parse(code1, "test1.py").unwrap();
+    let tree2 = parser.parse(code2, "test2.py").unwrap();
+
+    let options = TSEDOptions { size_penalty: false, ..Default::default() };
+    let similarity = calculate_tsed(&tree1, &tree2, &options);
+    assert!((similarity - 1.0).abs() < f64::EPSILON, "Expected identical trees: {similarity}");
+
+    let options = TSEDOptions { size_penalty: false, include_comments: true, ..Default::default() };
+    let similarity = calculate_tsed(&tree1, &tree2, &options);
+    assert!(similarity < 1.0, "Docstring should count when comments are included: {similarity}");
+}
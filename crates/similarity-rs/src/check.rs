@@ -6,7 +6,7 @@ use similarity_core::{
     cli_output::{format_function_output, show_function_code},
     cli_parallel::SimilarityResult,
     language_parser::GenericFunctionDef,
-    TSEDOptions,
+    NodeWeights, SizePenalty, TSEDOptions,
 };
 use std::path::PathBuf;
 
@@ -38,12 +38,16 @@ pub fn check_paths(
     min_lines: u32,
     min_tokens: Option<u32>,
     no_size_penalty: bool,
+    size_ratio_penalty: SizePenalty,
+    node_weights: NodeWeights,
     print: bool,
     _fast_mode: bool, // Rust doesn't support fast mode yet
     filter_function: Option<&String>,
     filter_function_body: Option<&String>,
     _exclude_patterns: &[String],
     skip_test: bool,
+    include_comments: bool,
+    expand_macros: bool,
 ) -> anyhow::Result<usize> {
     let default_extensions = vec!["rs"];
     let exts: Vec<&str> =
@@ -64,12 +68,16 @@ pub fn check_paths(
     options.min_lines = min_lines;
     options.min_tokens = min_tokens;
     options.size_penalty = !no_size_penalty;
+    options.size_ratio_penalty = size_ratio_penalty;
+    options.node_weights = node_weights;
     options.skip_test = skip_test;
+    options.include_comments = include_comments;
 
     let mut all_results = Vec::new();
 
     // Check within each file
-    let within_file_results = check_within_file_duplicates_parallel(&files, threshold, &options);
+    let within_file_results =
+        check_within_file_duplicates_parallel(&files, threshold, &options, expand_macros);
 
     // Collect within-file duplicates
     for (file, similar_pairs) in within_file_results {
@@ -0,0 +1,270 @@
+//! Similarity of `impl` blocks.
+//!
+//! Trait implementations are copied from type to type (manual serde impls,
+//! `From` conversions, builders) and differ mostly in names, which the
+//! function check misses when each method alone is too small to report.
+//! Blocks implementing the same trait are compared method by method: methods
+//! are paired by name and their bodies compared as trees, and the block's
+//! similarity is the average weighted by method length, with methods only
+//! one block has counting as completely different. Inherent impls are
+//! compared with each other the same way.
+
+use anyhow::Result;
+use similarity_core::cli_file_utils::collect_files;
+use similarity_core::cli_output::{format_function_output, show_function_code};
+use similarity_core::language_parser::LanguageParser;
+use similarity_core::tree::TreeNode;
+use similarity_core::tsed::{calculate_tsed, TSEDOptions};
+use std::collections::HashMap;
+use std::fs;
+use std::rc::Rc;
+
+use crate::rust_parser::{ImplBlock, RustParser};
+
+/// An impl block and the trees of its methods
+struct ExtractedImpl {
+    file: String,
+    block: ImplBlock,
+    methods: HashMap<String, (Rc<TreeNode>, u32)>,
+}
+
+/// Similarity of two impl blocks and of each method pair by name
+struct ImplDuplicate {
+    first: usize,
+    second: usize,
+    similarity: f64,
+    methods: Vec<(String, f64)>,
+}
+
+impl ExtractedImpl {
+    fn extract(file: &str, content: &str, parser: &mut RustParser) -> Vec<Self> {
+        let lines: Vec<&str> = content.lines().collect();
+        let blocks = parser.extract_impls(content).unwrap_or_default();
+        blocks
+            .into_iter()
+            .map(|block| {
+                let methods = block
+                    .methods
+                    .iter()
+                    .filter_map(|method| {
+                        let start = (method.start_line as usize).saturating_sub(1);
+                        let end = (method.end_line as usize).min(lines.len());
+                        let tree = parser.parse(&lines[start..end].join("\n"), file).ok()?;
+                        let length = method.end_line - method.start_line + 1;
+                        Some((method.name.to_string(), (tree, length)))
+                    })
+                    .collect();
+                Self { file: file.to_string(), block, methods }
+            })
+            .collect()
+    }
+}
+
+/// Compare the methods of two blocks; `None` when they share no method
+fn compare_impls(
+    first: &ExtractedImpl,
+    second: &ExtractedImpl,
+    options: &TSEDOptions,
+) -> Option<(f64, Vec<(String, f64)>)> {
+    let mut methods = Vec::new();
+    let mut weighted = 0.0;
+    let mut total = 0.0;
+    for (name, (tree, length)) in &first.methods {
+        match second.methods.get(name) {
+            Some((other, other_length)) => {
+                let similarity = calculate_tsed(tree, other, options);
+                let weight = f64::from(length + other_length) / 2.0;
+                weighted += similarity * weight;
+                total += weight;
+                methods.push((name.clone(), similarity));
+            }
+            None => total += f64::from(*length),
+        }
+    }
+    if methods.is_empty() {
+        return None;
+    }
+    total += second
+        .methods
+        .iter()
+        .filter(|(name, _)| !first.methods.contains_key(*name))
+        .map(|(_, (_, length))| f64::from(*length))
+        .sum::<f64>();
+    methods.sort_by(|a, b| a.0.cmp(&b.0));
+    Some((weighted / total, methods))
+}
+
+/// Check for similar impl blocks of the same trait across files
+#[allow(clippy::too_many_arguments)]
+pub fn check_impls(
+    paths: Vec<String>,
+    threshold: f64,
+    rename_cost: f64,
+    extensions: Option<&Vec<String>>,
+    min_lines: u32,
+    print: bool,
+    exclude_patterns: &[String],
+    include_comments: bool,
+) -> Result<usize> {
+    let default_extensions = vec!["rs"];
+    let exts: Vec<&str> =
+        extensions.map_or(default_extensions, |v| v.iter().map(String::as_str).collect());
+
+    let exclude_matcher = crate::create_exclude_matcher(exclude_patterns);
+    let files: Vec<_> = collect_files(&paths, &exts)?
+        .into_iter()
+        .filter(|file| exclude_matcher.as_ref().is_none_or(|matcher| !matcher.is_match(file)))
+        .collect();
+
+    if files.is_empty() {
+        println!("No Rust files found in specified paths");
+        return Ok(0);
+    }
+
+    println!("Checking {} files for similar trait impls...\n", files.len());
+
+    let mut options = TSEDOptions::default();
+    options.apted_options.rename_cost = rename_cost;
+    options.apted_options.compare_values = true;
+    options.min_lines = 1;
+    options.size_penalty = false;
+    options.include_comments = include_comments;
+
+    let mut parser = RustParser::new()
+        .map_err(|e| anyhow::anyhow!("Failed to create parser: {}", e))?
+        .with_comments(include_comments);
+    let impls: Vec<ExtractedImpl> = files
+        .iter()
+        .filter_map(|file| {
+            Some((file.to_string_lossy().to_string(), fs::read_to_string(file).ok()?))
+        })
+        .flat_map(|(file, content)| ExtractedImpl::extract(&file, &content, &mut parser))
+        .filter(|extracted| {
+            let block = &extracted.block;
+            !extracted.methods.is_empty() && block.end_line - block.start_line + 1 >= min_lines
+        })
+        .collect();
+
+    // Blocks of the same trait, inherent impls together
+    let mut by_trait: HashMap<Option<&str>, Vec<usize>> = HashMap::new();
+    for (index, extracted) in impls.iter().enumerate() {
+        by_trait.entry(extracted.block.trait_name.as_deref()).or_default().push(index);
+    }
+
+    let mut duplicates = Vec::new();
+    for indices in by_trait.values() {
+        for (position, &first) in indices.iter().enumerate() {
+            for &second in &indices[position + 1..] {
+                // Inherent impls of one type split across blocks are not copies
+                let (a, b) = (&impls[first].block, &impls[second].block);
+                if a.trait_name.is_none() && a.type_name == b.type_name {
+                    continue;
+                }
+                if let Some((similarity, methods)) =
+                    compare_impls(&impls[first], &impls[second], &options)
+                {
+                    if similarity >= threshold {
+                        duplicates.push(ImplDuplicate { first, second, similarity, methods });
+                    }
+                }
+            }
+        }
+    }
+    duplicates.sort_by(|a, b| {
+        b.similarity.total_cmp(&a.similarity).then((a.first, a.second).cmp(&(b.first, b.second)))
+    });
+
+    if duplicates.is_empty() {
+        println!("No similar trait impls found with threshold {:.0}%", threshold * 100.0);
+        return Ok(0);
+    }
+
+    println!("Similar trait impls found:");
+    println!("{}", "-".repeat(60));
+    for duplicate in &duplicates {
+        println!("\nSimilarity: {:.2}%", duplicate.similarity * 100.0);
+        for extracted in [&impls[duplicate.first], &impls[duplicate.second]] {
+            println!(
+                "  {}",
+                format_function_output(
+                    &extracted.file,
+                    &extracted.block.header,
+                    extracted.block.start_line,
+                    extracted.block.end_line
+                )
+            );
+        }
+        let methods: Vec<String> = duplicate
+            .methods
+            .iter()
+            .map(|(name, similarity)| format!("{} {:.2}%", name, similarity * 100.0))
+            .collect();
+        println!("  Methods: {}", methods.join(", "));
+
+        if print {
+            for extracted in [&impls[duplicate.first], &impls[duplicate.second]] {
+                show_function_code(
+                    &extracted.file,
+                    &extracted.block.header,
+                    extracted.block.start_line,
+                    extracted.block.end_line,
+                );
+            }
+        }
+    }
+
+    println!("\n{}", "-".repeat(60));
+    println!("Total similar impl pairs found: {}", duplicates.len());
+
+    Ok(duplicates.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(source: &str) -> Vec<ExtractedImpl> {
+        let mut parser = RustParser::new().unwrap();
+        ExtractedImpl::extract("src/lib.rs", source, &mut parser)
+    }
+
+    #[test]
+    fn test_methods_are_paired_by_name() {
+        let impls = extract(
+            r#"
+impl fmt::Display for Meters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} m", self.0)
+    }
+}
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} s", self.0)
+    }
+}
+
+impl Seconds {
+    fn fmt(&self) -> String {
+        String::new()
+    }
+
+    fn total(&self) -> f64 {
+        self.0
+    }
+}
+"#,
+        );
+        assert_eq!(impls[0].block.header, "impl fmt::Display for Meters");
+        let options = TSEDOptions { size_penalty: false, ..TSEDOptions::default() };
+
+        let (similarity, methods) = compare_impls(&impls[0], &impls[1], &options).unwrap();
+        assert!(similarity > 0.9);
+        assert_eq!(methods.len(), 1);
+        assert_eq!(methods[0].0, "fmt");
+
+        // `total` only exists in the inherent impl and counts as different
+        let (similarity, _) = compare_impls(&impls[1], &impls[2], &options).unwrap();
+        assert!(similarity < 0.5);
+    }
+}
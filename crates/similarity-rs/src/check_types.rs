@@ -1,9 +1,12 @@
 use anyhow::Result;
-use ignore::WalkBuilder;
 use rayon::prelude::*;
+use similarity_core::cli_file_utils::walk_dir;
 use similarity_core::language_parser::{GenericTypeDef, LanguageParser};
 use similarity_core::tsed::{calculate_tsed, TSEDOptions};
-use similarity_core::{ComparisonOptions, RustStructureComparator};
+use similarity_core::{
+    ComparisonOptions, MemberComparisonStrategy, RustStructureComparator, SemanticTypeConfig,
+    SizePenalty,
+};
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
@@ -157,6 +160,7 @@ fn get_relative_path(file_path: &str) -> String {
 }
 
 /// Check for similar types (structs, enums) across files
+#[allow(clippy::too_many_arguments)]
 pub fn check_types(
     paths: Vec<String>,
     threshold: f64,
@@ -164,6 +168,8 @@ pub fn check_types(
     print: bool,
     exclude_patterns: &[String],
     use_structure_comparison: bool,
+    member_comparison: MemberComparisonStrategy,
+    size_ratio_penalty: SizePenalty,
 ) -> Result<usize> {
     let default_extensions = vec!["rs".to_string()];
     let exts = extensions.unwrap_or(&default_extensions);
@@ -188,7 +194,7 @@ pub fn check_types(
                 }
             }
         } else if path.is_dir() {
-            let walker = WalkBuilder::new(path).follow_links(false).build();
+            let walker = walk_dir(path);
 
             for entry in walker {
                 let entry = entry?;
@@ -276,6 +282,9 @@ pub fn check_types(
             name_weight: 0.3,
             structure_weight: 0.7,
             threshold,
+            member_comparison,
+            type_synonyms: SemanticTypeConfig::synonyms_for(member_comparison),
+            size_ratio_penalty,
             ..Default::default()
         };
         let mut comparator = RustStructureComparator::with_options(structure_options);
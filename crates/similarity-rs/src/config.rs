@@ -1,5 +1,7 @@
 use clap::Parser;
-use similarity_core::ConfigLoader;
+use similarity_core::{
+    ConfigLoader, MemberComparisonStrategy, NodeWeights, PenaltyCurve, SizePenalty,
+};
 
 #[derive(Debug, Parser)]
 #[command(name = "similarity-rs")]
@@ -38,6 +40,14 @@ pub struct Cli {
     #[arg(long)]
     pub no_size_penalty: bool,
 
+    /// Size ratio penalty curve: builtin, none, linear or quadratic
+    #[arg(long, value_name = "CURVE")]
+    pub size_penalty_curve: Option<PenaltyCurve>,
+
+    /// Lowest factor the size ratio penalty may yield (0.0-1.0)
+    #[arg(long, value_name = "FLOOR")]
+    pub size_penalty_floor: Option<f64>,
+
     /// Filter functions by name (substring match)
     #[arg(long)]
     pub filter_function: Option<String>,
@@ -58,6 +68,15 @@ pub struct Cli {
     #[arg(long)]
     pub skip_test: bool,
 
+    /// Keep comments and doc comments in the compared trees (stripped by default)
+    #[arg(long)]
+    pub include_comments: bool,
+
+    /// Also compare functions generated by the file's own macro_rules! macros,
+    /// reported at the macro invocation
+    #[arg(long)]
+    pub expand_macros: bool,
+
     /// Enable experimental overlap detection mode
     #[arg(long = "experimental-overlap")]
     pub overlap: bool,
@@ -82,6 +101,10 @@ pub struct Cli {
     #[arg(long = "experimental-types")]
     pub types: bool,
 
+    /// Compare trait impl blocks method by method
+    #[arg(long)]
+    pub impls: bool,
+
     /// Disable function similarity checking
     #[arg(long = "no-functions")]
     pub no_functions: bool,
@@ -89,6 +112,12 @@ pub struct Cli {
     /// Use new generalized structure comparison framework (experimental)
     #[arg(long)]
     pub use_structure_comparison: bool,
+
+    /// How the structure comparison compares member types: exact, normalized (default) or
+    /// semantic, which resolves aliases, containers, numeric families and the
+    /// [type_synonyms] of similarity.toml
+    #[arg(long, value_name = "STRATEGY")]
+    pub member_comparison: Option<MemberComparisonStrategy>,
 }
 
 #[derive(Debug, Default, serde::Deserialize)]
@@ -99,19 +128,27 @@ pub struct Config {
     pub min_tokens: Option<u32>,
     pub rename_cost: Option<f64>,
     pub no_size_penalty: Option<bool>,
+    /// `[size_penalty]` table with `curve` and `floor`
+    pub size_penalty: Option<SizePenalty>,
+    /// `[node_weights]` table of node kind weights
+    pub node_weights: Option<NodeWeights>,
     pub filter_function: Option<String>,
     pub filter_function_body: Option<String>,
     pub no_fast: Option<bool>,
     pub exclude: Option<Vec<String>>,
     pub skip_test: Option<bool>,
+    pub include_comments: Option<bool>,
+    pub expand_macros: Option<bool>,
     pub overlap: Option<bool>,
     pub overlap_min_window: Option<u32>,
     pub overlap_max_window: Option<u32>,
     pub overlap_size_tolerance: Option<f64>,
     pub fail_on_duplicates: Option<bool>,
     pub types: Option<bool>,
+    pub impls: Option<bool>,
     pub no_functions: Option<bool>,
     pub use_structure_comparison: Option<bool>,
+    pub member_comparison: Option<MemberComparisonStrategy>,
 }
 
 impl ConfigLoader for Config {}
@@ -123,19 +160,25 @@ pub struct ResolvedConfig {
     pub min_tokens: Option<u32>,
     pub rename_cost: f64,
     pub no_size_penalty: bool,
+    pub size_ratio_penalty: SizePenalty,
+    pub node_weights: NodeWeights,
     pub filter_function: Option<String>,
     pub filter_function_body: Option<String>,
     pub no_fast: bool,
     pub exclude: Vec<String>,
     pub skip_test: bool,
+    pub include_comments: bool,
+    pub expand_macros: bool,
     pub overlap: bool,
     pub overlap_min_window: u32,
     pub overlap_max_window: u32,
     pub overlap_size_tolerance: f64,
     pub fail_on_duplicates: bool,
     pub types: bool,
+    pub impls: bool,
     pub no_functions: bool,
     pub use_structure_comparison: bool,
+    pub member_comparison: MemberComparisonStrategy,
 }
 
 fn resolve_value<T>(cli: Option<T>, config: Option<T>, default: T) -> T {
@@ -155,6 +198,14 @@ impl ResolvedConfig {
         let mut exclude = config.exclude.unwrap_or_default();
         exclude.extend(cli.exclude);
 
+        let mut size_ratio_penalty = config.size_penalty.unwrap_or_default();
+        if let Some(curve) = cli.size_penalty_curve {
+            size_ratio_penalty.curve = curve;
+        }
+        if let Some(floor) = cli.size_penalty_floor {
+            size_ratio_penalty.floor = floor;
+        }
+
         Self {
             threshold: resolve_value(cli.threshold, config.threshold, 0.85),
             extensions: cli.extensions.or(config.extensions),
@@ -162,11 +213,15 @@ impl ResolvedConfig {
             min_tokens: resolve_option(cli.min_tokens, config.min_tokens, Some(30)),
             rename_cost: resolve_value(cli.rename_cost, config.rename_cost, 0.3),
             no_size_penalty: resolve_flag(cli.no_size_penalty, config.no_size_penalty),
+            size_ratio_penalty,
+            node_weights: config.node_weights.unwrap_or_default(),
             filter_function: cli.filter_function.or(config.filter_function),
             filter_function_body: cli.filter_function_body.or(config.filter_function_body),
             no_fast: resolve_flag(cli.no_fast, config.no_fast),
             exclude,
             skip_test: resolve_flag(cli.skip_test, config.skip_test),
+            include_comments: resolve_flag(cli.include_comments, config.include_comments),
+            expand_macros: resolve_flag(cli.expand_macros, config.expand_macros),
             overlap: resolve_flag(cli.overlap, config.overlap),
             overlap_min_window: resolve_value(cli.overlap_min_window, config.overlap_min_window, 8),
             overlap_max_window: resolve_value(
@@ -181,11 +236,16 @@ impl ResolvedConfig {
             ),
             fail_on_duplicates: resolve_flag(cli.fail_on_duplicates, config.fail_on_duplicates),
             types: resolve_flag(cli.types, config.types),
+            impls: resolve_flag(cli.impls, config.impls),
             no_functions: resolve_flag(cli.no_functions, config.no_functions),
             use_structure_comparison: resolve_flag(
                 cli.use_structure_comparison,
                 config.use_structure_comparison,
             ),
+            member_comparison: cli
+                .member_comparison
+                .or(config.member_comparison)
+                .unwrap_or_default(),
         }
     }
 }
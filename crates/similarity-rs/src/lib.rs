@@ -1 +1,2 @@
+pub mod macro_expand;
 pub mod rust_parser;
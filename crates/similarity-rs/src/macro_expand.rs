@@ -0,0 +1,715 @@
+//! Partial expansion of `macro_rules!` macros.
+//!
+//! Functions generated by a declarative macro are invisible to the function
+//! extraction, which only sees the invocation. Invocations of the macros
+//! defined in the same file are expanded the way rustc expands common
+//! macros: the first rule whose matcher accepts the input is transcribed
+//! with its metavariables and repetitions substituted. Fragments such as
+//! `expr` or `ty` are not parsed but matched up to the token following them
+//! in the rule, and macros invoked by an expansion stay unexpanded, so
+//! unusual macros may not expand and are skipped. An expansion keeps the
+//! layout of the macro body and the lines of its invocation, which findings
+//! point to.
+
+use std::collections::HashMap;
+use tree_sitter::{Node, Parser};
+
+/// Code generated by one macro invocation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    pub macro_name: String,
+    /// Lines of the invocation
+    pub start_line: u32,
+    pub end_line: u32,
+    pub source: String,
+}
+
+/// Expand the invocations of the `macro_rules!` macros defined in `source`
+pub fn expand_macros(source: &str) -> Vec<Expansion> {
+    let mut parser = Parser::new();
+    if parser.set_language(&tree_sitter_rust::LANGUAGE.into()).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+
+    let mut macros: HashMap<String, Vec<Rule>> = HashMap::new();
+    let mut invocations = Vec::new();
+    collect_macros(tree.root_node(), source, &mut macros, &mut invocations);
+
+    invocations
+        .into_iter()
+        .filter_map(|node| {
+            let name = node.child_by_field_name("macro")?.utf8_text(source.as_bytes()).ok()?;
+            let rules = macros.get(name)?;
+            let arguments = node.children(&mut node.walk()).find(|n| n.kind() == "token_tree")?;
+            Some(Expansion {
+                macro_name: name.to_string(),
+                start_line: node.start_position().row as u32 + 1,
+                end_line: node.end_position().row as u32 + 1,
+                source: expand(rules, &source[arguments.byte_range()])?,
+            })
+        })
+        .collect()
+}
+
+/// `macro_rules!` definitions and the invocations outside of them
+fn collect_macros<'a>(
+    node: Node<'a>,
+    source: &str,
+    macros: &mut HashMap<String, Vec<Rule>>,
+    invocations: &mut Vec<Node<'a>>,
+) {
+    match node.kind() {
+        "macro_definition" => {
+            if let Some(name) = node.child_by_field_name("name") {
+                let name = source[name.byte_range()].to_string();
+                macros.insert(name, parse_rules(&source[node.byte_range()]));
+            }
+            return;
+        }
+        "macro_invocation" => invocations.push(node),
+        _ => {}
+    }
+    for child in node.children(&mut node.walk()) {
+        collect_macros(child, source, macros, invocations);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Ident,
+    Lifetime,
+    Literal,
+    Punct,
+    /// Delimited by the given opening character
+    Group(char),
+}
+
+/// A token tree, located by byte offsets into the text it was read from
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    /// A group spans its delimiters
+    start: usize,
+    end: usize,
+    children: Vec<Token>,
+}
+
+impl Token {
+    fn is_punct(&self, text: &str, punct: &str) -> bool {
+        self.kind == TokenKind::Punct && &text[self.start..self.end] == punct
+    }
+}
+
+/// Token trees of Rust code; comments are skipped and unbalanced closing
+/// delimiters ignored
+fn tokenize(text: &str) -> Vec<Token> {
+    let bytes = text.as_bytes();
+    let mut groups: Vec<(char, usize, Vec<Token>)> = vec![(' ', 0, Vec::new())];
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let c = bytes[i];
+        let kind = match c {
+            _ if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = text[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = text[i + 2..].find("*/").map_or(bytes.len(), |n| i + n + 4);
+                continue;
+            }
+            b'(' | b'[' | b'{' => {
+                groups.push((c as char, i, Vec::new()));
+                i += 1;
+                continue;
+            }
+            b')' | b']' | b'}' => {
+                i += 1;
+                if groups.len() > 1 {
+                    let (open, start, children) = groups.pop().unwrap();
+                    let group = Token { kind: TokenKind::Group(open), start, end: i, children };
+                    groups.last_mut().unwrap().2.push(group);
+                }
+                continue;
+            }
+            b'"' => {
+                i = string_end(bytes, i + 1);
+                TokenKind::Literal
+            }
+            b'\'' => {
+                i = quote_end(text, i);
+                if bytes[i - 1] == b'\'' {
+                    TokenKind::Literal
+                } else {
+                    TokenKind::Lifetime
+                }
+            }
+            _ if c.is_ascii_digit() => {
+                i += 1;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric()
+                        || bytes[i] == b'_'
+                        || (bytes[i] == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)))
+                {
+                    i += 1;
+                }
+                TokenKind::Literal
+            }
+            _ if c.is_ascii_alphabetic()
+                || c == b'_'
+                || (c >= 0x80 && text[i..].starts_with(char::is_alphabetic)) =>
+            {
+                i = ident_end(text, i);
+                match (&text[start..i], bytes.get(i)) {
+                    ("b" | "c", Some(b'"')) => {
+                        i = string_end(bytes, i + 1);
+                        TokenKind::Literal
+                    }
+                    ("b", Some(b'\'')) => {
+                        i = quote_end(text, i);
+                        TokenKind::Literal
+                    }
+                    ("r" | "br" | "cr", Some(b'"' | b'#')) => {
+                        i = raw_string_end(text, i);
+                        TokenKind::Literal
+                    }
+                    _ => TokenKind::Ident,
+                }
+            }
+            _ => {
+                i += text[i..].chars().next().map_or(1, char::len_utf8);
+                TokenKind::Punct
+            }
+        };
+        groups.last_mut().unwrap().2.push(Token { kind, start, end: i, children: Vec::new() });
+    }
+
+    // Groups left open end with the text
+    while groups.len() > 1 {
+        let (open, start, children) = groups.pop().unwrap();
+        let group = Token { kind: TokenKind::Group(open), start, end: bytes.len(), children };
+        groups.last_mut().unwrap().2.push(group);
+    }
+    groups.pop().unwrap().2
+}
+
+fn ident_end(text: &str, start: usize) -> usize {
+    text[start..]
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(text.len(), |n| start + n)
+}
+
+/// End of a string whose contents start at `i`
+fn string_end(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// End of the `r#"..."#` string whose hashes or quote start at `i`
+fn raw_string_end(text: &str, i: usize) -> usize {
+    let hashes = text[i..].bytes().take_while(|&b| b == b'#').count();
+    let closing = format!("\"{}", "#".repeat(hashes));
+    let contents = (i + hashes + 1).min(text.len());
+    text[contents..].find(&closing).map_or(text.len(), |n| contents + n + closing.len())
+}
+
+/// End of the character literal or lifetime starting with the quote at `i`
+fn quote_end(text: &str, i: usize) -> usize {
+    let rest = &text[i + 1..];
+    if rest.starts_with('\\') {
+        return rest[2.min(rest.len())..].find('\'').map_or(text.len(), |n| i + 1 + 2 + n + 1);
+    }
+    match rest.chars().next() {
+        Some(c) if rest[c.len_utf8()..].starts_with('\'') => i + 1 + c.len_utf8() + 1,
+        Some(_) => ident_end(text, i + 1),
+        None => text.len(),
+    }
+}
+
+/// One part of a rule's matcher
+#[derive(Debug, Clone)]
+enum Matcher {
+    /// A token the input must repeat
+    Token(TokenKind, String),
+    Group(char, Vec<Matcher>),
+    /// `$name:fragment`
+    Fragment(String, String),
+    /// `$(...) separator op`
+    Repeat(Vec<Matcher>, Option<String>, char),
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    matchers: Vec<Matcher>,
+    /// Text of the rule's `{ ... }` body, delimiters included
+    body: String,
+}
+
+/// Rules of a `macro_rules! name { (matcher) => { body }; ... }` definition
+fn parse_rules(definition: &str) -> Vec<Rule> {
+    let tokens = tokenize(definition);
+    let Some(rules) = tokens.iter().rev().find(|t| matches!(t.kind, TokenKind::Group(_))) else {
+        return Vec::new();
+    };
+    rules
+        .children
+        .windows(4)
+        .filter_map(|window| match window {
+            [matcher, eq, gt, body]
+                if eq.is_punct(definition, "=")
+                    && gt.is_punct(definition, ">")
+                    && matches!(matcher.kind, TokenKind::Group(_))
+                    && matches!(body.kind, TokenKind::Group(_)) =>
+            {
+                Some(Rule {
+                    matchers: parse_matchers(&matcher.children, definition)?,
+                    body: definition[body.start..body.end].to_string(),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_matchers(tokens: &[Token], text: &str) -> Option<Vec<Matcher>> {
+    let mut matchers = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        if token.is_punct(text, "$") {
+            match tokens.get(i + 1) {
+                Some(name) if name.kind == TokenKind::Ident => {
+                    let fragment = tokens.get(i + 3).filter(|_| {
+                        tokens.get(i + 2).is_some_and(|colon| colon.is_punct(text, ":"))
+                    })?;
+                    matchers.push(Matcher::Fragment(
+                        text[name.start..name.end].to_string(),
+                        text[fragment.start..fragment.end].to_string(),
+                    ));
+                    i += 4;
+                }
+                Some(group) if group.kind == TokenKind::Group('(') => {
+                    let inner = parse_matchers(&group.children, text)?;
+                    let (separator, op, consumed) = repetition_operator(&tokens[i + 2..], text)?;
+                    matchers.push(Matcher::Repeat(inner, separator, op));
+                    i += 2 + consumed;
+                }
+                _ => return None,
+            }
+            continue;
+        }
+        matchers.push(match token.kind {
+            TokenKind::Group(open) => Matcher::Group(open, parse_matchers(&token.children, text)?),
+            kind => Matcher::Token(kind, text[token.start..token.end].to_string()),
+        });
+        i += 1;
+    }
+    Some(matchers)
+}
+
+/// Separator and operator following `$(...)`, and the number of tokens they take
+fn repetition_operator(tokens: &[Token], text: &str) -> Option<(Option<String>, char, usize)> {
+    let op = |token: &Token| ["*", "+", "?"].into_iter().find(|op| token.is_punct(text, op));
+    let first = tokens.first()?;
+    if let Some(op) = op(first) {
+        return Some((None, op.chars().next()?, 1));
+    }
+    let op = op(tokens.get(1)?)?;
+    Some((Some(text[first.start..first.end].to_string()), op.chars().next()?, 2))
+}
+
+#[derive(Debug, Clone)]
+enum Binding {
+    Text(String),
+    Repeat(Vec<Binding>),
+}
+
+type Bindings = HashMap<String, Binding>;
+
+/// Where a fragment such as `expr` stops
+#[derive(Clone, Copy)]
+enum Stop<'a> {
+    Token(TokenKind, &'a str),
+    Group(char),
+    End,
+}
+
+impl Stop<'_> {
+    fn matches(&self, token: &Token, text: &str) -> bool {
+        match *self {
+            Stop::Token(kind, expected) => {
+                token.kind == kind && &text[token.start..token.end] == expected
+            }
+            Stop::Group(open) => token.kind == TokenKind::Group(open),
+            Stop::End => false,
+        }
+    }
+}
+
+/// What stops a fragment followed by `rest`, or `outer` at the end of a sequence
+fn stop_after<'a>(rest: &'a [Matcher], outer: Stop<'a>) -> Stop<'a> {
+    match rest.first() {
+        Some(Matcher::Token(kind, text)) => Stop::Token(*kind, text),
+        Some(Matcher::Group(open, _)) => Stop::Group(*open),
+        Some(Matcher::Repeat(inner, separator, _)) => match (inner.first(), separator) {
+            (Some(Matcher::Token(kind, text)), _) => Stop::Token(*kind, text),
+            (_, Some(separator)) => Stop::Token(TokenKind::Punct, separator),
+            _ => Stop::End,
+        },
+        Some(Matcher::Fragment(..)) => Stop::End,
+        None => outer,
+    }
+}
+
+/// Match `matchers` against the input from `position`, returning where the match ends
+fn match_sequence(
+    matchers: &[Matcher],
+    input: &[Token],
+    text: &str,
+    mut position: usize,
+    outer: Stop,
+    bindings: &mut Bindings,
+) -> Option<usize> {
+    for (index, matcher) in matchers.iter().enumerate() {
+        let stop = stop_after(&matchers[index + 1..], outer);
+        match matcher {
+            Matcher::Token(kind, expected) => {
+                let token = input.get(position)?;
+                if token.kind != *kind || &text[token.start..token.end] != expected {
+                    return None;
+                }
+                position += 1;
+            }
+            Matcher::Group(open, inner) => {
+                let group = input.get(position)?;
+                if group.kind != TokenKind::Group(*open) {
+                    return None;
+                }
+                let end = match_sequence(inner, &group.children, text, 0, Stop::End, bindings)?;
+                if end != group.children.len() {
+                    return None;
+                }
+                position += 1;
+            }
+            Matcher::Fragment(name, fragment) => {
+                let end = match_fragment(fragment, input, text, position, stop)?;
+                let matched = if end > position {
+                    text[input[position].start..input[end - 1].end].to_string()
+                } else {
+                    String::new()
+                };
+                bindings.insert(name.clone(), Binding::Text(matched));
+                position = end;
+            }
+            Matcher::Repeat(inner, separator, op) => {
+                let separator_stop = separator.as_deref().map(|s| Stop::Token(TokenKind::Punct, s));
+                let mut iterations: Vec<Bindings> = Vec::new();
+                loop {
+                    let mut next = position;
+                    if let (false, Some(separator)) = (iterations.is_empty(), separator) {
+                        match input.get(next) {
+                            Some(token) if token.is_punct(text, separator) => next += 1,
+                            _ => break,
+                        }
+                    }
+                    let mut iteration = Bindings::new();
+                    let inner_stop = separator_stop.unwrap_or(stop);
+                    match match_sequence(inner, input, text, next, inner_stop, &mut iteration) {
+                        Some(end) if end > next => {
+                            iterations.push(iteration);
+                            position = end;
+                        }
+                        _ => break,
+                    }
+                    if *op == '?' {
+                        break;
+                    }
+                }
+                if *op == '+' && iterations.is_empty() {
+                    return None;
+                }
+                let mut names = Vec::new();
+                fragment_names(inner, &mut names);
+                for name in names {
+                    let values = iterations
+                        .iter_mut()
+                        .map(|iteration| {
+                            iteration.remove(&name).unwrap_or(Binding::Repeat(Vec::new()))
+                        })
+                        .collect();
+                    bindings.insert(name, Binding::Repeat(values));
+                }
+            }
+        }
+    }
+    Some(position)
+}
+
+/// End of the fragment of kind `fragment` starting at `position`
+fn match_fragment(
+    fragment: &str,
+    input: &[Token],
+    text: &str,
+    position: usize,
+    stop: Stop,
+) -> Option<usize> {
+    let token = input.get(position);
+    match fragment {
+        "tt" => token.map(|_| position + 1),
+        "ident" => token.filter(|t| t.kind == TokenKind::Ident).map(|_| position + 1),
+        "lifetime" => token.filter(|t| t.kind == TokenKind::Lifetime).map(|_| position + 1),
+        "block" => token.filter(|t| t.kind == TokenKind::Group('{')).map(|_| position + 1),
+        "literal" => {
+            let negative = token.is_some_and(|t| t.is_punct(text, "-"));
+            let literal = input.get(position + usize::from(negative))?;
+            let value = &text[literal.start..literal.end];
+            (literal.kind == TokenKind::Literal || value == "true" || value == "false")
+                .then_some(position + usize::from(negative) + 1)
+        }
+        "expr" | "ty" | "path" | "pat" | "pat_param" | "stmt" | "item" | "vis" | "meta" => {
+            // `<` and `>` of generics are not delimiters, so track them for types
+            let generics = matches!(fragment, "ty" | "path");
+            let mut depth = 0usize;
+            let mut end = position;
+            while let Some(token) = input.get(end) {
+                if depth == 0 && stop.matches(token, text) {
+                    break;
+                }
+                if generics && token.is_punct(text, "<") {
+                    depth += 1;
+                } else if generics && token.is_punct(text, ">") && depth > 0 {
+                    let arrow = end > 0 && input[end - 1].is_punct(text, "-");
+                    if !arrow {
+                        depth -= 1;
+                    }
+                }
+                end += 1;
+            }
+            (end > position || fragment == "vis").then_some(end)
+        }
+        _ => None,
+    }
+}
+
+fn fragment_names(matchers: &[Matcher], names: &mut Vec<String>) {
+    for matcher in matchers {
+        match matcher {
+            Matcher::Fragment(name, _) => names.push(name.clone()),
+            Matcher::Group(_, inner) | Matcher::Repeat(inner, _, _) => fragment_names(inner, names),
+            Matcher::Token(..) => {}
+        }
+    }
+}
+
+/// The body of the first rule matching `arguments`, a delimited token tree
+fn expand(rules: &[Rule], arguments: &str) -> Option<String> {
+    let tokens = tokenize(arguments);
+    let input = &tokens.first()?.children;
+    rules.iter().find_map(|rule| {
+        let mut bindings = Bindings::new();
+        let end = match_sequence(&rule.matchers, input, arguments, 0, Stop::End, &mut bindings)?;
+        if end != input.len() {
+            return None;
+        }
+        let body = tokenize(&rule.body);
+        let group = body.first()?;
+        let mut expansion = String::new();
+        let last =
+            transcribe(&group.children, &rule.body, group.start + 1, &bindings, &mut expansion)?;
+        expansion.push_str(&rule.body[last..group.end.saturating_sub(1).max(last)]);
+        Some(expansion)
+    })
+}
+
+/// Append `tokens` of `text` with metavariables substituted, keeping the
+/// text between tokens from `previous` on; returns where the copied text ends
+fn transcribe(
+    tokens: &[Token],
+    text: &str,
+    mut previous: usize,
+    bindings: &Bindings,
+    out: &mut String,
+) -> Option<usize> {
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        if token.is_punct(text, "$") {
+            out.push_str(&text[previous..token.start]);
+            match tokens.get(i + 1) {
+                Some(name) if name.kind == TokenKind::Ident => {
+                    match &text[name.start..name.end] {
+                        "crate" => out.push_str("crate"),
+                        name => match bindings.get(name)? {
+                            Binding::Text(value) => out.push_str(value),
+                            Binding::Repeat(_) => return None,
+                        },
+                    }
+                    previous = name.end;
+                    i += 2;
+                }
+                Some(group) if group.kind == TokenKind::Group('(') => {
+                    let (separator, _, consumed) = repetition_operator(&tokens[i + 2..], text)?;
+                    let mut names = Vec::new();
+                    variable_names(&group.children, text, &mut names);
+                    let repeated: Vec<(&String, &Vec<Binding>)> = names
+                        .iter()
+                        .filter_map(|name| match bindings.get(name) {
+                            Some(Binding::Repeat(values)) => Some((name, values)),
+                            _ => None,
+                        })
+                        .collect();
+                    let count = repeated.first()?.1.len();
+                    for iteration in 0..count {
+                        if let (true, Some(separator)) = (iteration > 0, &separator) {
+                            out.push_str(separator);
+                        }
+                        let mut inner = bindings.clone();
+                        for (name, values) in &repeated {
+                            inner.insert((*name).clone(), values.get(iteration)?.clone());
+                        }
+                        let last = transcribe(&group.children, text, group.start + 1, &inner, out)?;
+                        out.push_str(&text[last..group.end - 1]);
+                    }
+                    previous = tokens[i + 1 + consumed].end;
+                    i += 2 + consumed;
+                }
+                _ => return None,
+            }
+            continue;
+        }
+        if let TokenKind::Group(_) = token.kind {
+            out.push_str(&text[previous..token.start + 1]);
+            let last = transcribe(&token.children, text, token.start + 1, bindings, out)?;
+            out.push_str(&text[last..token.end]);
+        } else {
+            out.push_str(&text[previous..token.end]);
+        }
+        previous = token.end;
+        i += 1;
+    }
+    Some(previous)
+}
+
+/// Names of the `$name` metavariables used in a transcriber
+fn variable_names(tokens: &[Token], text: &str, names: &mut Vec<String>) {
+    for (index, token) in tokens.iter().enumerate() {
+        if token.is_punct(text, "$") {
+            if let Some(name) = tokens.get(index + 1).filter(|t| t.kind == TokenKind::Ident) {
+                names.push(text[name.start..name.end].to_string());
+            }
+        }
+        variable_names(&token.children, text, names);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expands_getters_at_invocation_lines() {
+        let source = r#"
+macro_rules! getter {
+    ($name:ident, $field:ident: $ty:ty) => {
+        pub fn $name(&self) -> &$ty {
+            &self.$field
+        }
+    };
+}
+
+impl User {
+    getter!(email, email_address: Option<String>);
+}
+"#;
+        let expansions = expand_macros(source);
+        assert_eq!(expansions.len(), 1);
+        assert_eq!(expansions[0].macro_name, "getter");
+        assert_eq!((expansions[0].start_line, expansions[0].end_line), (11, 11));
+        assert_eq!(
+            expansions[0].source.trim(),
+            "pub fn email(&self) -> &Option<String> {\n            &self.email_address\n        }"
+        );
+    }
+
+    #[test]
+    fn test_repetitions_and_rule_order() {
+        let source = r#"
+macro_rules! tests {
+    () => {};
+    ($($name:ident => $value:expr),+ $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!(parse($value), Ok(()));
+            }
+        )+
+    };
+}
+
+tests! {
+    parses_empty => "",
+    parses_list => "[1, 2]",
+}
+"#;
+        let expansions = expand_macros(source);
+        assert_eq!(expansions.len(), 1);
+        let expanded = &expansions[0].source;
+        assert!(expanded
+            .contains("fn parses_empty() {\n                assert_eq!(parse(\"\"), Ok(()));"));
+        assert!(expanded.contains("fn parses_list()"));
+        assert!(expanded.contains("parse(\"[1, 2]\")"));
+        assert_eq!((expansions[0].start_line, expansions[0].end_line), (14, 17));
+    }
+
+    #[test]
+    fn test_non_ascii_punctuation_is_a_single_token() {
+        let text = "make!(b → 2);";
+        let tokens = tokenize(text);
+        let arguments = &tokens[2].children;
+        let kinds: Vec<_> = arguments.iter().map(|token| &token.kind).collect();
+        assert_eq!(kinds, [&TokenKind::Ident, &TokenKind::Punct, &TokenKind::Literal]);
+        assert_eq!(&text[arguments[1].start..arguments[1].end], "→");
+
+        let source = r#"
+macro_rules! make {
+    ($name:ident → $value:literal) => { fn $name() -> i32 { $value } };
+}
+
+make!(b → 2);
+make!(é → 3);
+"#;
+        let expansions = expand_macros(source);
+        assert_eq!(expansions.len(), 2);
+        assert!(expansions[1].source.contains("fn é() -> i32 { 3 }"));
+    }
+
+    #[test]
+    fn test_unknown_or_unmatched_macros_are_skipped() {
+        let source = r#"
+macro_rules! pair {
+    ($a:literal, $b:literal) => { fn pair() -> (i32, i32) { ($a, $b) } };
+}
+
+pair!(1, "two", 3);
+println!("{}", 1);
+"#;
+        assert!(expand_macros(source).is_empty());
+        assert_eq!(
+            expand(&parse_rules("macro_rules! m { ($a:literal) => { $a }; }"), "(-1)"),
+            Some(" -1 ".to_string())
+        );
+    }
+}
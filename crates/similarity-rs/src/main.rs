@@ -3,6 +3,7 @@ use clap::Parser;
 use similarity_core::ConfigLoader;
 
 mod check;
+mod check_impls;
 mod check_types;
 mod config;
 mod parallel;
@@ -20,10 +21,11 @@ fn main() -> Result<()> {
     let functions_enabled = !resolved.no_functions;
     let types_enabled = resolved.types;
     let overlap_enabled = resolved.overlap;
+    let impls_enabled = resolved.impls;
 
     // Validate that at least one analyzer is enabled
-    if !functions_enabled && !types_enabled && !overlap_enabled {
-        eprintln!("Error: At least one analyzer must be enabled. Use --experimental-types to enable type checking, --experimental-overlap for overlap detection, or remove --no-functions.");
+    if !functions_enabled && !types_enabled && !overlap_enabled && !impls_enabled {
+        eprintln!("Error: At least one analyzer must be enabled. Use --experimental-types to enable type checking, --experimental-overlap for overlap detection, --impls for trait impl comparison, or remove --no-functions.");
         return Err(anyhow::anyhow!("No analyzer enabled"));
     }
 
@@ -43,12 +45,16 @@ fn main() -> Result<()> {
             resolved.min_lines,
             resolved.min_tokens,
             resolved.no_size_penalty,
+            resolved.size_ratio_penalty,
+            resolved.node_weights.clone(),
             print,
             !resolved.no_fast,
             resolved.filter_function.as_ref(),
             resolved.filter_function_body.as_ref(),
             &resolved.exclude,
             resolved.skip_test,
+            resolved.include_comments,
+            resolved.expand_macros,
         )?;
         total_duplicates += duplicate_count;
     }
@@ -67,12 +73,34 @@ fn main() -> Result<()> {
             print,
             &resolved.exclude,
             resolved.use_structure_comparison,
+            resolved.member_comparison,
+            resolved.size_ratio_penalty,
         )?;
         total_duplicates += type_duplicate_count;
     }
 
+    // Run impl analysis if enabled
+    if impls_enabled && (functions_enabled || types_enabled) {
+        println!("\n{separator}\n");
+    }
+
+    if impls_enabled {
+        println!("=== Trait Impl Similarity ===");
+        let impl_duplicate_count = check_impls::check_impls(
+            paths.clone(),
+            resolved.threshold,
+            resolved.rename_cost,
+            resolved.extensions.as_ref(),
+            resolved.min_lines,
+            print,
+            &resolved.exclude,
+            resolved.include_comments,
+        )?;
+        total_duplicates += impl_duplicate_count;
+    }
+
     // Run overlap analysis if enabled
-    if overlap_enabled && (functions_enabled || types_enabled) {
+    if overlap_enabled && (functions_enabled || types_enabled || impls_enabled) {
         println!("\n{separator}\n");
     }
 
@@ -111,7 +139,7 @@ fn check_overlaps(
     exclude_patterns: &[String],
 ) -> anyhow::Result<usize> {
     use crate::rust_parser::RustParser;
-    use ignore::WalkBuilder;
+    use similarity_core::cli_file_utils::walk_dir;
     use similarity_core::{find_overlaps_across_files_generic, OverlapOptions};
     use std::collections::{HashMap, HashSet};
     use std::fs;
@@ -143,8 +171,8 @@ fn check_overlaps(
                 }
             }
         } else if path.is_dir() {
-            // If it's a directory, walk it respecting .gitignore
-            let walker = WalkBuilder::new(path).follow_links(false).build();
+            // If it's a directory, walk it respecting .gitignore and .similarityignore
+            let walker = walk_dir(path);
 
             for entry in walker {
                 let entry = entry?;
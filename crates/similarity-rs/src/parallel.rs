@@ -5,7 +5,10 @@ use similarity_core::{
     cli_parallel::{FileData, SimilarityResult},
     language_parser::{GenericFunctionDef, LanguageParser},
     tsed::TSEDOptions,
+    Symbol,
 };
+use similarity_rs::macro_expand::expand_macros;
+use similarity_rs::rust_parser::RustParser;
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
@@ -24,7 +27,7 @@ pub fn load_files_parallel(files: &[PathBuf]) -> Vec<RustFileData> {
                 Ok(content) => {
                     let filename = file.to_string_lossy();
                     // Create Rust parser
-                    match similarity_rs::rust_parser::RustParser::new() {
+                    match RustParser::new() {
                         Ok(mut parser) => {
                             // Extract functions
                             match parser.extract_functions(&content, &filename) {
@@ -52,11 +55,51 @@ pub fn load_files_parallel(files: &[PathBuf]) -> Vec<RustFileData> {
         .collect()
 }
 
-/// Check for duplicates within Rust files in parallel
+/// A function to compare and its source
+struct Candidate {
+    function: GenericFunctionDef,
+    source: String,
+    /// Macro whose expansion generated the function
+    macro_name: Option<String>,
+}
+
+/// Functions generated by the `macro_rules!` invocations of a file, located
+/// at their invocation and named after the macro
+fn expanded_functions(parser: &mut RustParser, code: &str, file: &str) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    for expansion in expand_macros(code) {
+        let Ok(functions) = parser.extract_functions(&expansion.source, file) else {
+            continue;
+        };
+        let lines: Vec<&str> = expansion.source.lines().collect();
+        for mut function in functions {
+            let source = extract_function_source(&lines, &function);
+            function.name = Symbol::intern(&format!(
+                "{} (expanded from {}!)",
+                function.name, expansion.macro_name
+            ));
+            function.start_line = expansion.start_line;
+            function.end_line = expansion.end_line;
+            function.body_start_line = expansion.start_line;
+            function.body_end_line = expansion.end_line;
+            candidates.push(Candidate {
+                function,
+                source,
+                macro_name: Some(expansion.macro_name.clone()),
+            });
+        }
+    }
+    candidates
+}
+
+/// Check for duplicates within Rust files in parallel. With `expand_macros`,
+/// functions generated by the file's own `macro_rules!` macros are compared
+/// too, except with other functions generated by the same macro.
 pub fn check_within_file_duplicates_parallel(
     files: &[PathBuf],
     threshold: f64,
     options: &TSEDOptions,
+    expand_macros: bool,
 ) -> Vec<(PathBuf, Vec<SimilarityResult<GenericFunctionDef>>)> {
     files
         .par_iter()
@@ -65,14 +108,36 @@ pub fn check_within_file_duplicates_parallel(
                 let file_str = file.to_string_lossy();
 
                 // Create Rust parser
-                match similarity_rs::rust_parser::RustParser::new() {
+                match RustParser::new().map(|parser| parser.with_comments(options.include_comments))
+                {
                     Ok(mut parser) => {
                         // Extract functions
                         match parser.extract_functions(&code, &file_str) {
-                            Ok(mut functions) => {
+                            Ok(functions) => {
+                                // Extract full function source so Rust signatures
+                                // contribute to similarity, reducing false positives
+                                // on short functions with identical bodies.
+                                let lines: Vec<&str> = code.lines().collect();
+                                let mut candidates: Vec<Candidate> = functions
+                                    .into_iter()
+                                    .map(|function| Candidate {
+                                        source: extract_function_source(&lines, &function),
+                                        function,
+                                        macro_name: None,
+                                    })
+                                    .collect();
+                                if expand_macros {
+                                    candidates.extend(expanded_functions(
+                                        &mut parser,
+                                        &code,
+                                        &file_str,
+                                    ));
+                                }
+
                                 // Filter out test functions if skip_test is enabled
                                 if options.skip_test {
-                                    functions.retain(|f| {
+                                    candidates.retain(|c| {
+                                        let f = &c.function;
                                         // Skip if function name starts with "test_"
                                         if f.name.starts_with("test_") {
                                             return false;
@@ -84,30 +149,29 @@ pub fn check_within_file_duplicates_parallel(
                                 let mut similar_pairs = Vec::new();
 
                                 // Compare all pairs within the file
-                                for i in 0..functions.len() {
-                                    for j in (i + 1)..functions.len() {
-                                        let func1 = &functions[i];
-                                        let func2 = &functions[j];
+                                for i in 0..candidates.len() {
+                                    for j in (i + 1)..candidates.len() {
+                                        let (first, second) = (&candidates[i], &candidates[j]);
+                                        let (source1, source2) = (&first.source, &second.source);
 
-                                        // Skip if functions don't meet minimum requirements
-                                        if func1.end_line - func1.start_line + 1 < options.min_lines
-                                            || func2.end_line - func2.start_line + 1
-                                                < options.min_lines
+                                        // A macro's expansions are alike by design
+                                        if first.macro_name.is_some()
+                                            && first.macro_name == second.macro_name
                                         {
                                             continue;
                                         }
 
-                                        // Extract full function source so Rust signatures
-                                        // contribute to similarity, reducing false positives
-                                        // on short functions with identical bodies.
-                                        let lines: Vec<&str> = code.lines().collect();
-                                        let source1 = extract_function_source(&lines, func1);
-                                        let source2 = extract_function_source(&lines, func2);
+                                        // Skip if functions don't meet minimum requirements
+                                        if (source1.lines().count() as u32) < options.min_lines
+                                            || (source2.lines().count() as u32) < options.min_lines
+                                        {
+                                            continue;
+                                        }
 
                                         // Parse function source to trees
                                         let (tree1_opt, tree2_opt) = match (
-                                            parser.parse(&source1, &format!("{}:func1", file_str)),
-                                            parser.parse(&source2, &format!("{}:func2", file_str)),
+                                            parser.parse(source1, &format!("{}:func1", file_str)),
+                                            parser.parse(source2, &format!("{}:func2", file_str)),
                                         ) {
                                             (Ok(tree1), Ok(tree2)) => {
                                                 // Skip if either tree is empty
@@ -142,10 +206,8 @@ pub fn check_within_file_duplicates_parallel(
                                                     );
                                                 blend_rust_similarity(
                                                     body_similarity,
-                                                    &source1,
-                                                    &source2,
-                                                    func1,
-                                                    func2,
+                                                    source1,
+                                                    source2,
                                                 )
                                             }
                                             _ => 0.0,
@@ -153,8 +215,8 @@ pub fn check_within_file_duplicates_parallel(
 
                                         if similarity >= threshold {
                                             similar_pairs.push(SimilarityResult::new(
-                                                func1.clone(),
-                                                func2.clone(),
+                                                first.function.clone(),
+                                                second.function.clone(),
                                                 similarity,
                                             ));
                                         }
@@ -190,15 +252,8 @@ fn extract_function_source(lines: &[&str], func: &GenericFunctionDef) -> String
     lines[start_idx..end_idx].join("\n")
 }
 
-fn blend_rust_similarity(
-    body_similarity: f64,
-    source1: &str,
-    source2: &str,
-    func1: &GenericFunctionDef,
-    func2: &GenericFunctionDef,
-) -> f64 {
-    let max_lines =
-        (func1.end_line - func1.start_line + 1).max(func2.end_line - func2.start_line + 1);
+fn blend_rust_similarity(body_similarity: f64, source1: &str, source2: &str) -> f64 {
+    let max_lines = source1.lines().count().max(source2.lines().count());
     let signature_weight = if max_lines <= 8 { 0.35 } else { 0.2 };
     let signature_similarity = calculate_signature_similarity(source1, source2);
 
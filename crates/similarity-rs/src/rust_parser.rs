@@ -1,7 +1,8 @@
+use similarity_core::interner::Symbol;
 use similarity_core::language_parser::{
     GenericFunctionDef, GenericTypeDef, Language, LanguageParser,
 };
-use similarity_core::tree::TreeNode;
+use similarity_core::tree::{is_comment_label, TreeNode};
 use std::error::Error;
 use std::rc::Rc;
 use tree_sitter::{Node, Parser};
@@ -9,6 +10,8 @@ use tree_sitter::{Node, Parser};
 pub struct RustParser {
     parser: Parser,
     node_id_counter: usize,
+    /// Keep comment nodes, which tree-sitter parses as extras, in the trees
+    keep_comments: bool,
 }
 
 impl RustParser {
@@ -18,7 +21,14 @@ impl RustParser {
             Box::new(std::io::Error::other(format!("Failed to set Rust language: {e:?}")))
                 as Box<dyn Error + Send + Sync>
         })?;
-        Ok(RustParser { parser, node_id_counter: 0 })
+        Ok(RustParser { parser, node_id_counter: 0, keep_comments: false })
+    }
+
+    /// Keep comments in the parsed trees so they take part in comparisons
+    #[must_use]
+    pub fn with_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
     }
 
     fn extract_functions_from_node<'a>(
@@ -127,16 +137,10 @@ impl RustParser {
                 if let Some(impl_node) = parent.parent() {
                     if impl_node.kind() == "impl_item" {
                         is_method = true;
-                        // Extract type name from impl block
-                        for child in impl_node.children(&mut impl_node.walk()) {
-                            if child.kind() == "type_identifier" {
-                                class_name = Some(
-                                    source[child.byte_range().start..child.byte_range().end]
-                                        .to_string(),
-                                );
-                                break;
-                            }
-                        }
+                        // The implementing type, not the trait of `impl Trait for Type`
+                        class_name = impl_node
+                            .child_by_field_name("type")
+                            .map(|type_node| base_type_name(type_node, source));
                     }
                 }
             }
@@ -204,7 +208,7 @@ impl RustParser {
             }
 
             Some(GenericFunctionDef {
-                name,
+                name: name.into(),
                 start_line: (node.start_position().row + 1) as u32,
                 end_line: (node.end_position().row + 1) as u32,
                 body_start_line,
@@ -212,7 +216,7 @@ impl RustParser {
                 is_async,
                 is_generator: false, // Rust doesn't have generator functions like JS/Python
                 is_method,
-                class_name,
+                class_name: class_name.map(Symbol::from),
                 decorators,
                 parameters,
             })
@@ -250,7 +254,7 @@ impl RustParser {
         let mut tree_node = TreeNode::new(label, value, node_id);
 
         for child in node.children(&mut node.walk()) {
-            if !child.is_extra() {
+            if !child.is_extra() || (self.keep_comments && is_comment_label(child.kind())) {
                 tree_node.add_child(self.convert_node_to_tree(child, source));
             }
         }
@@ -389,6 +393,89 @@ impl RustParser {
     }
 }
 
+/// An `impl` block and its methods
+#[derive(Debug, Clone)]
+pub struct ImplBlock {
+    /// Trait of `impl Trait for Type`, without generic arguments; `None` for inherent impls
+    pub trait_name: Option<String>,
+    /// The implementing type, without generic arguments
+    pub type_name: String,
+    /// `impl Trait for Type` as written, generics included
+    pub header: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub methods: Vec<GenericFunctionDef>,
+}
+
+impl RustParser {
+    /// Impl blocks of a file, nested ones (e.g. in `mod tests`) included
+    pub fn extract_impls(
+        &mut self,
+        source: &str,
+    ) -> Result<Vec<ImplBlock>, Box<dyn Error + Send + Sync>> {
+        let tree = self.parser.parse(source, None).ok_or_else(|| {
+            Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, "Failed to parse source"))
+                as Box<dyn Error + Send + Sync>
+        })?;
+
+        let mut impls = Vec::new();
+        let mut stack = vec![tree.root_node()];
+        while let Some(node) = stack.pop() {
+            if node.kind() == "impl_item" {
+                if let Some(block) = self.extract_impl_block(node, source) {
+                    impls.push(block);
+                }
+            }
+            stack.extend(node.children(&mut node.walk()));
+        }
+        impls.sort_by_key(|block| block.start_line);
+        Ok(impls)
+    }
+
+    fn extract_impl_block(&self, node: Node, source: &str) -> Option<ImplBlock> {
+        let type_node = node.child_by_field_name("type")?;
+        let trait_node = node.child_by_field_name("trait");
+        let body = node.child_by_field_name("body");
+        let header_end = body.map_or(node.end_byte(), |body| body.start_byte());
+
+        let mut methods = Vec::new();
+        if let Some(body) = body {
+            for child in body.children(&mut body.walk()) {
+                if child.kind() == "function_item" {
+                    methods.extend(self.extract_function_definition(child, source));
+                }
+            }
+        }
+
+        Some(ImplBlock {
+            trait_name: trait_node.map(|trait_node| base_type_name(trait_node, source)),
+            type_name: base_type_name(type_node, source),
+            header: source[node.start_byte()..header_end]
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            start_line: (node.start_position().row + 1) as u32,
+            end_line: (node.end_position().row + 1) as u32,
+            methods,
+        })
+    }
+}
+
+/// Name of a type or trait path without generic arguments or references,
+/// e.g. `From` for `From<Vec<T>>` and `fmt::Display` for `fmt::Display`
+fn base_type_name(node: Node, source: &str) -> String {
+    let node = match node.kind() {
+        "generic_type" => node.child_by_field_name("type").unwrap_or(node),
+        "reference_type" => {
+            return node
+                .child_by_field_name("type")
+                .map_or_else(String::new, |inner| base_type_name(inner, source))
+        }
+        _ => node,
+    };
+    source[node.byte_range()].to_string()
+}
+
 fn find_first_function(node: Node) -> Option<Node> {
     if node.kind() == "function_item" {
         return Some(node);
@@ -573,13 +660,51 @@ impl MyStruct {
         // Check methods
         assert_eq!(functions[2].name, "new");
         assert!(functions[2].is_method);
-        assert_eq!(functions[2].class_name, Some("MyStruct".to_string()));
+        assert_eq!(functions[2].class_name, Some("MyStruct".into()));
 
         assert_eq!(functions[3].name, "get_value");
         assert!(functions[3].is_method);
         assert_eq!(functions[3].parameters, vec!["self"]);
     }
 
+    #[test]
+    fn test_rust_impl_blocks() {
+        let mut parser = RustParser::new().unwrap();
+        let source = r#"
+impl<T: Clone> From<Vec<T>> for Stack<T> {
+    fn from(items: Vec<T>) -> Self {
+        Stack { items }
+    }
+}
+
+impl fmt::Display for Celsius {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}°C", self.0)
+    }
+}
+
+impl Celsius {
+    fn new(value: f64) -> Self {
+        Celsius(value)
+    }
+}
+"#;
+
+        let impls = parser.extract_impls(source).unwrap();
+        assert_eq!(impls.len(), 3);
+        assert_eq!(impls[0].trait_name.as_deref(), Some("From"));
+        assert_eq!(impls[0].type_name, "Stack");
+        assert_eq!(impls[0].header, "impl<T: Clone> From<Vec<T>> for Stack<T>");
+        assert_eq!((impls[0].start_line, impls[0].end_line), (2, 6));
+        assert_eq!(impls[1].trait_name.as_deref(), Some("fmt::Display"));
+        assert_eq!(impls[1].methods[0].name, "fmt");
+        assert_eq!(impls[2].trait_name, None);
+
+        // Methods of trait impls belong to the implementing type
+        let functions = parser.extract_functions(source, "test.rs").unwrap();
+        assert_eq!(functions[1].class_name, Some("Celsius".into()));
+    }
+
     #[test]
     fn test_rust_types() {
         let mut parser = RustParser::new().unwrap();
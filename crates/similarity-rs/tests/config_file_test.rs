@@ -85,3 +85,36 @@ fn increment(y: i32) -> i32 {
         .success()
         .stdout(predicate::str::contains("No duplicate functions found!"));
 }
+
+#[test]
+fn test_similarity_toml_size_penalty_table() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("similarity.toml"),
+        r#"
+min_tokens = 1
+
+[size_penalty]
+curve = "quadratic"
+floor = 0.5
+"#,
+    )
+    .unwrap();
+    fs::write(dir.path().join("sample.rs"), "fn one() -> i32 {\n    1\n}\n").unwrap();
+
+    Command::cargo_bin("similarity-rs")
+        .unwrap()
+        .current_dir(dir.path())
+        .arg(".")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("could not load").not());
+
+    Command::cargo_bin("similarity-rs")
+        .unwrap()
+        .current_dir(dir.path())
+        .args([".", "--size-penalty-curve", "cubic"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown penalty curve 'cubic'"));
+}
@@ -270,3 +270,46 @@ fn longer_func2() -> i32 {
         .stdout(predicate::str::contains("longer_func2"))
         .stdout(predicate::str::contains("f1").not());
 }
+
+#[test]
+fn test_include_comments_compares_comments() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("comments.rs");
+
+    let content = r#"
+fn total_price(items: &[u32]) -> u32 {
+    // Sum the prices of every item in the cart before discounts
+    let mut total = 0;
+    for item in items {
+        total += item;
+    }
+    total
+}
+
+fn total_price_copy(items: &[u32]) -> u32 {
+    let mut total = 0;
+    for item in items {
+        /* Prices are already validated, so add them without checks */
+        total += item;
+        // One more note that the other function does not have
+    }
+    total
+}
+"#;
+
+    fs::write(&file_path, content).unwrap();
+
+    let run = |extra: &[&str]| {
+        Command::cargo_bin("similarity-rs")
+            .unwrap()
+            .arg(&file_path)
+            .args(["--min-lines", "1", "--min-tokens", "1", "--threshold", "0.9"])
+            .args(extra)
+            .assert()
+            .success()
+    };
+
+    // Comments are stripped by default, leaving only the differing names
+    run(&[]).stdout(predicate::str::contains("total_price_copy"));
+    run(&["--include-comments"]).stdout(predicate::str::contains("No duplicate functions found!"));
+}
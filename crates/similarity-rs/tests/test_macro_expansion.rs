@@ -0,0 +1,60 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+const SOURCE: &str = r#"macro_rules! total_fn {
+    ($name:ident, $field:ident) => {
+        fn $name(items: &[Item]) -> u64 {
+            let mut total = 0;
+            for item in items {
+                if item.active {
+                    total += item.$field * item.quantity;
+                }
+            }
+            total
+        }
+    };
+}
+
+total_fn!(total_price, price);
+
+fn total_weight(items: &[Item]) -> u64 {
+    let mut total = 0;
+    for item in items {
+        if item.active {
+            total += item.weight * item.quantity;
+        }
+    }
+    total
+}
+"#;
+
+#[test]
+fn test_expanded_functions_are_reported_at_invocation() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("totals.rs"), SOURCE).unwrap();
+
+    Command::cargo_bin("similarity-rs")
+        .unwrap()
+        .arg(dir.path())
+        .arg("--expand-macros")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("total_price (expanded from total_fn!)"))
+        .stdout(predicate::str::contains(":15-15"))
+        .stdout(predicate::str::contains("function total_weight"));
+}
+
+#[test]
+fn test_macros_are_not_expanded_by_default() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("totals.rs"), SOURCE).unwrap();
+
+    Command::cargo_bin("similarity-rs")
+        .unwrap()
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No duplicate functions found!"));
+}
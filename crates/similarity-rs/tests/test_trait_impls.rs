@@ -0,0 +1,78 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_detect_similar_trait_impls() {
+    let dir = tempdir().unwrap();
+
+    let impl_for = |type_name: &str, field: &str| {
+        format!(
+            r#"
+impl From<Row> for {type_name} {{
+    fn from(row: Row) -> Self {{
+        {type_name} {{
+            id: row.get("id"),
+            {field}: row.get("{field}"),
+            created_at: row.get("created_at"),
+        }}
+    }}
+}}
+
+impl Serialize for {type_name} {{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        let mut state = serializer.serialize_struct("{type_name}", 3)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("{field}", &self.{field})?;
+        state.serialize_field("created_at", &self.created_at)?;
+        state.end()
+    }}
+}}
+"#
+        )
+    };
+    fs::write(dir.path().join("user.rs"), impl_for("User", "email")).unwrap();
+    fs::write(dir.path().join("order.rs"), impl_for("Order", "total")).unwrap();
+
+    Command::cargo_bin("similarity-rs")
+        .unwrap()
+        .arg(dir.path())
+        .arg("--impls")
+        .arg("--no-functions")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("=== Trait Impl Similarity ==="))
+        .stdout(predicate::str::contains("impl From<Row> for User"))
+        .stdout(predicate::str::contains("impl Serialize for Order"))
+        .stdout(predicate::str::contains("Methods: serialize"))
+        .stdout(predicate::str::contains("Total similar impl pairs found: 2"));
+}
+
+#[test]
+fn test_impls_of_different_traits_are_not_compared() {
+    let dir = tempdir().unwrap();
+    let content = r#"
+impl fmt::Display for Celsius {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} degrees", self.0)
+    }
+}
+
+impl fmt::Debug for Fahrenheit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} degrees", self.0)
+    }
+}
+"#;
+    fs::write(dir.path().join("units.rs"), content).unwrap();
+
+    Command::cargo_bin("similarity-rs")
+        .unwrap()
+        .arg(dir.path())
+        .arg("--impls")
+        .arg("--no-functions")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No similar trait impls found"));
+}
@@ -1,7 +1,9 @@
 use similarity_core::language_parser::LanguageParser;
 use similarity_core::{
     apted::APTEDOptions,
+    size_penalty::SizePenalty,
     tsed::{calculate_tsed, TSEDOptions},
+    DEFAULT_MAX_TREE_NODES,
 };
 use similarity_rs::rust_parser::RustParser;
 
@@ -28,6 +30,14 @@ fn test_short_function_similarity() {
         min_tokens: None,
         size_penalty: true,
         skip_test: false,
+        include_comments: false,
+        size_ratio_penalty: SizePenalty::default(),
+        max_tree_nodes: Some(DEFAULT_MAX_TREE_NODES),
+        canonicalize_order: false,
+        normalize_async: false,
+        normalize_collections: false,
+        strip_types: false,
+        node_weights: Default::default(),
     };
 
     let sim12 = calculate_tsed(&tree1, &tree2, &options);
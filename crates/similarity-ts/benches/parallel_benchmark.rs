@@ -1,7 +1,7 @@
 #![allow(clippy::uninlined_format_args)]
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use similarity_core::TSEDOptions;
+use similarity_core::{ComparisonBudget, RunReport, TSEDOptions};
 use similarity_ts::parallel::{
     check_cross_file_duplicates_parallel, check_within_file_duplicates_parallel,
     load_files_parallel,
@@ -134,7 +134,7 @@ fn benchmark_load_files_comparison(c: &mut Criterion) {
 
         group.bench_with_input(BenchmarkId::new("parallel", num_files), &file_paths, |b, paths| {
             b.iter(|| {
-                let file_data = load_files_parallel(paths);
+                let file_data = load_files_parallel(paths, &RunReport::new(), None);
                 black_box(file_data)
             });
         });
@@ -171,7 +171,15 @@ fn benchmark_within_file_comparison(c: &mut Criterion) {
 
         group.bench_with_input(BenchmarkId::new("parallel", num_files), &file_paths, |b, paths| {
             b.iter(|| {
-                let results = check_within_file_duplicates_parallel(paths, 0.8, &options, false);
+                let results = check_within_file_duplicates_parallel(
+                    paths,
+                    0.8,
+                    &options,
+                    false,
+                    &ComparisonBudget::unlimited(),
+                    None,
+                    None,
+                );
                 black_box(results)
             });
         });
@@ -195,7 +203,7 @@ fn benchmark_cross_file_comparison(c: &mut Criterion) {
 
         // Pre-load file data for cross-file comparison
         let file_data_seq = load_files_sequential(&file_paths);
-        let file_data_par = load_files_parallel(&file_paths);
+        let file_data_par = load_files_parallel(&file_paths, &RunReport::new(), None);
 
         group.throughput(Throughput::Elements((num_files * num_files) as u64));
 
@@ -215,7 +223,15 @@ fn benchmark_cross_file_comparison(c: &mut Criterion) {
             &file_data_par,
             |b, data| {
                 b.iter(|| {
-                    let results = check_cross_file_duplicates_parallel(data, 0.8, &options, false);
+                    let results = check_cross_file_duplicates_parallel(
+                        data,
+                        0.8,
+                        &options,
+                        false,
+                        &ComparisonBudget::unlimited(),
+                        None,
+                        None,
+                    );
                     black_box(results)
                 });
             },
@@ -246,8 +262,15 @@ fn benchmark_scaling(c: &mut Criterion) {
                 // Set thread count for this iteration
                 rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap().install(
                     || {
-                        let results =
-                            check_within_file_duplicates_parallel(paths, 0.8, &options, false);
+                        let results = check_within_file_duplicates_parallel(
+                            paths,
+                            0.8,
+                            &options,
+                            false,
+                            &ComparisonBudget::unlimited(),
+                            None,
+                            None,
+                        );
                         black_box(results)
                     },
                 )
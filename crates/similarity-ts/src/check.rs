@@ -2,13 +2,164 @@
 
 use crate::parallel::{
     check_cross_file_duplicates_parallel, check_within_file_duplicates_parallel,
-    load_files_parallel,
+    load_files_parallel, rank_cross_file_pairs_parallel, rank_within_file_pairs_parallel,
 };
-use ignore::WalkBuilder;
-use similarity_core::{extract_functions, TSEDOptions};
-use std::collections::{HashMap, HashSet, VecDeque};
+use similarity_core::cli_file_utils::walk_dir;
+use similarity_core::cli_output::json_finding;
+use similarity_core::{
+    content_finding_id, extract_functions, extract_shared_function_patch, finding_id,
+    identical_function_fix, older_copy, parse_function_tree, render_pair_diff, run_key,
+    shared_module_path, AnalysisSession, BlameInfo, BoilerplateSet, CodeOwners,
+    ComparisonAlgorithm, ComparisonBudget, DiffLayout, DiffSide, DuplicationHeatmap, Finding,
+    FindingImport, FindingSymbol, FormatterRegistry, FunctionSite, FunctionType, GitBlamer,
+    ImportGraph, ImportInstead, JsonFinding, MatrixExporter, ModuleRelation, NodeWeights,
+    OlderCopy, PackageResolver, PatchSource, PathAliases, ResultFormatter, RunReport, SessionStart,
+    Severity, SizePenalty, SkipReason, TSEDOptions, TriageState, TriageStore,
+    MIN_SIDE_BY_SIDE_WIDTH, PATCH_SIMILARITY_THRESHOLD,
+};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// How the findings of a run are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable report
+    Standard,
+    /// One finding per line in a format of the formatter registry
+    Findings,
+    /// Markdown summary for a pull-request comment, printed when the run ends
+    PrComment,
+}
+
+/// The findings of a run in the `--output` format: printed as they are
+/// found, or held for the summary of `--output pr-comment`
+pub struct FindingOutput<'a> {
+    pub format: OutputFormat,
+    formatter: &'a dyn ResultFormatter,
+    held: Vec<String>,
+}
+
+impl<'a> FindingOutput<'a> {
+    /// Resolve `--output` against the formatter registry. The standard report
+    /// renders findings printed on their own as text, and the pull-request
+    /// comment is built from JSON findings.
+    pub fn resolve(name: &str, registry: &'a FormatterRegistry) -> anyhow::Result<Self> {
+        let (format, formatter) = match name {
+            "standard" => (OutputFormat::Standard, "text"),
+            "pr-comment" => (OutputFormat::PrComment, "json"),
+            name => (OutputFormat::Findings, name),
+        };
+        let formatter = registry.get(formatter).ok_or_else(|| {
+            anyhow::anyhow!(
+                "unknown output format '{}' (expected standard, pr-comment, {})",
+                name,
+                registry.names().join(", ")
+            )
+        })?;
+        Ok(Self { format, formatter, held: Vec::new() })
+    }
+
+    /// Print a rendered finding, or hold it for the summary of `--output pr-comment`
+    fn emit(&mut self, finding: String) {
+        if self.format == OutputFormat::PrComment {
+            self.held.push(finding);
+        } else {
+            println!("{}", finding);
+        }
+    }
+
+    /// Render a finding in the output format and [emit](Self::emit) it
+    pub fn print_finding(
+        &mut self,
+        file_path: &str,
+        line: usize,
+        severity: Severity,
+        message: &str,
+        other_file: &str,
+        other_line: usize,
+    ) {
+        let finding = self.formatter.format_finding(&Finding {
+            file: file_path,
+            line,
+            severity,
+            message,
+            related_file: other_file,
+            related_line: other_line,
+            fixes: &[],
+        });
+        self.emit(finding);
+    }
+
+    /// Render a finding with the details its analyzer knows and [emit](Self::emit) it
+    pub fn print_detailed(&mut self, finding: &JsonFinding) {
+        let finding = self.formatter.format_detailed(finding);
+        self.emit(finding);
+    }
+
+    /// The findings held for `--output pr-comment`, as JSON lines
+    pub fn into_held(self) -> String {
+        self.held.join("\n")
+    }
+}
+
+/// How to group the function report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// One section per CODEOWNERS owner
+    Owner,
+}
+
+/// Which duplicates to report relative to package boundaries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Scope {
+    /// Report every duplicate
+    All,
+    /// Only duplicates within a single package
+    PerPackage,
+    /// Only duplicates spanning packages (candidates for a shared library)
+    CrossPackage,
+}
+
+/// How `--print` lays out the code of a function pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PrintLayout {
+    /// Side by side on wide terminals, unified on narrow ones, sequential when not a terminal
+    Auto,
+    /// Both functions one after the other
+    Sequential,
+    /// Aligned columns with the differing tokens highlighted
+    SideBySide,
+    /// Unified diff with the differing tokens highlighted
+    Unified,
+}
+
+impl PrintLayout {
+    /// Diff layout for the terminal on stdout, `None` for sequential code blocks
+    pub fn resolve(self) -> Option<DiffLayout> {
+        let terminal = console::Term::stdout();
+        let width = terminal
+            .is_term()
+            .then(|| terminal.size_checked())
+            .flatten()
+            .map(|(_, columns)| usize::from(columns));
+        match self {
+            PrintLayout::Sequential => None,
+            PrintLayout::Unified => Some(DiffLayout::Unified),
+            PrintLayout::SideBySide => Some(DiffLayout::SideBySide {
+                width: width.unwrap_or(MIN_SIDE_BY_SIDE_WIDTH).max(MIN_SIDE_BY_SIDE_WIDTH),
+            }),
+            PrintLayout::Auto => match width {
+                Some(width) if width >= MIN_SIDE_BY_SIDE_WIDTH => {
+                    Some(DiffLayout::SideBySide { width })
+                }
+                Some(_) => Some(DiffLayout::Unified),
+                None => None,
+            },
+        }
+    }
+}
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 struct FunctionNodeKey {
@@ -84,11 +235,28 @@ fn extract_lines_from_content(content: &str, start_line: u32, end_line: u32) ->
 /// Format function output in VSCode-compatible format
 fn format_function_output(
     file_path: &str,
-    function_name: &str,
-    start_line: u32,
-    end_line: u32,
+    function: &similarity_core::FunctionDefinition,
 ) -> String {
-    format!("{}:{}-{} {}", file_path, start_line, end_line, function_name)
+    let location = format!(
+        "{}:{}-{} {}",
+        file_path,
+        function.start_line,
+        function.end_line,
+        function.display_name()
+    );
+    let markers: Vec<&str> = [
+        (function.exported, "exported"),
+        // The file has syntax errors elsewhere, so some of its code was not compared
+        (function.partial, "partial"),
+    ]
+    .into_iter()
+    .filter_map(|(present, marker)| present.then_some(marker))
+    .collect();
+    if markers.is_empty() {
+        location
+    } else {
+        format!("{} ({})", location, markers.join(", "))
+    }
 }
 
 /// Display code content for a function
@@ -108,6 +276,29 @@ fn show_function_code(file_path: &str, function_name: &str, start_line: u32, end
     }
 }
 
+/// Print the code of a pair as an aligned diff
+fn show_pair_diff(dup: &DuplicateResult, path1: &str, path2: &str, layout: DiffLayout) {
+    let (func1, func2) = (&dup.result.func1, &dup.result.func2);
+    let (Ok(content1), Ok(content2)) =
+        (fs::read_to_string(&dup.file1), fs::read_to_string(&dup.file2))
+    else {
+        eprintln!("Error reading files {} and {}", path1, path2);
+        return;
+    };
+    let code1 = extract_lines_from_content(&content1, func1.start_line, func1.end_line);
+    let code2 = extract_lines_from_content(&content2, func2.start_line, func2.end_line);
+    let label1 = format!("{}:{}", path1, func1.name);
+    let label2 = format!("{}:{}", path2, func2.name);
+    println!(
+        "\n{}",
+        render_pair_diff(
+            DiffSide { label: &label1, code: &code1, start_line: func1.start_line },
+            DiffSide { label: &label2, code: &code2, start_line: func2.start_line },
+            layout,
+        )
+    );
+}
+
 /// Structure to hold all similarity results
 #[derive(Debug, Clone)]
 struct DuplicateResult {
@@ -131,8 +322,8 @@ fn function_node_key(
 ) -> FunctionNodeKey {
     FunctionNodeKey {
         file: file.to_path_buf(),
-        name: function.name.clone(),
-        class_name: function.class_name.clone(),
+        name: function.name.to_string(),
+        class_name: function.class_name.map(String::from),
         start_line: function.start_line,
         end_line: function.end_line,
     }
@@ -146,6 +337,65 @@ fn relative_display_path(path: &Path) -> String {
     }
 }
 
+/// Triage IDs of a finding: its content ID and the name-based ID of earlier versions
+struct FindingId {
+    content: String,
+    legacy: String,
+}
+
+/// Triage IDs of a finding covering these functions. Functions whose source
+/// cannot be parsed again leave the finding with its name-based ID only.
+fn function_finding_id<'a>(
+    members: impl IntoIterator<Item = (&'a Path, &'a similarity_core::FunctionDefinition)>,
+    sources: &mut HashMap<PathBuf, Option<String>>,
+) -> FindingId {
+    let members: Vec<_> = members.into_iter().collect();
+    let keys: Vec<(String, String)> = members
+        .iter()
+        .map(|(file, function)| {
+            let name = match &function.class_name {
+                Some(class_name) => format!("{}.{}", class_name, function.name),
+                None => function.name.to_string(),
+            };
+            (relative_display_path(file), name)
+        })
+        .collect();
+    let legacy = finding_id(keys.iter().map(|(file, name)| (file.as_str(), name.as_str())));
+
+    let trees: Option<Vec<_>> = members
+        .iter()
+        .map(|(file, function)| {
+            let source = sources
+                .entry(file.to_path_buf())
+                .or_insert_with(|| fs::read_to_string(file).ok())
+                .as_deref()?;
+            Some((parse_function_tree(function, source)?, function.name.as_str()))
+        })
+        .collect();
+    let content = trees.map_or_else(
+        || legacy.clone(),
+        |trees| content_finding_id(trees.iter().map(|(tree, name)| (tree.as_ref(), *name))),
+    );
+    FindingId { content, legacy }
+}
+
+/// Print the finding ID, with its triage state once it has been triaged and
+/// the ID the state was recorded under when the finding has drifted since
+fn show_finding_id(triage: &TriageStore, id: &FindingId) {
+    let Some((recorded, entry)) = triage.resolve(&id.content, &id.legacy) else {
+        println!("  ID: {}", id.content);
+        return;
+    };
+    let origin =
+        if recorded == id.content { String::new() } else { format!(", recorded as {}", recorded) };
+    match &entry.note {
+        Some(note) => {
+            println!("  ID: {} (triage: {} - {}{})", id.content, entry.state, note, origin)
+        }
+        None => println!("  ID: {} (triage: {}{})", id.content, entry.state, origin),
+    }
+}
+
 fn cluster_duplicate_results(
     all_results: &[DuplicateResult],
 ) -> (Vec<DuplicateCluster>, Vec<DuplicateResult>) {
@@ -247,14 +497,30 @@ fn cluster_duplicate_results(
 }
 
 /// Display similarity results
+#[allow(clippy::too_many_arguments)]
 fn display_all_results(
     mut all_results: Vec<DuplicateResult>,
+    ranked: bool,
     print: bool,
+    diff_layout: Option<DiffLayout>,
     filter_function: Option<&String>,
     filter_function_body: Option<&String>,
+    output: &mut FindingOutput,
+    blame: bool,
+    suggest_patch: bool,
+    import_graph: Option<&ImportGraph>,
+    packages: Option<&HashMap<PathBuf, String>>,
+    owners: Option<&CodeOwners>,
+    group_by: Option<GroupBy>,
+    triage: &TriageStore,
+    triage_states: &[TriageState],
+    heatmap: &mut DuplicationHeatmap,
+    run_report: &RunReport,
 ) -> usize {
     if all_results.is_empty() {
-        println!("\nNo duplicate functions found!");
+        if output.format == OutputFormat::Standard {
+            println!("\nNo duplicate functions found!");
+        }
         return 0;
     }
 
@@ -310,14 +576,123 @@ fn display_all_results(
         });
     }
 
+    all_results.retain(|dup| {
+        !run_report.suppress(
+            "functions",
+            (&dup.file1, &dup.result.func1.name),
+            (&dup.file2, &dup.result.func2.name),
+        )
+    });
+
     if all_results.is_empty() {
-        println!("\nNo duplicate functions found matching the filters!");
+        if output.format == OutputFormat::Standard {
+            println!("\nNo duplicate functions found matching the filters!");
+        }
+        return 0;
+    }
+
+    for dup in &all_results {
+        heatmap.record_pair(
+            "functions",
+            (
+                &relative_display_path(&dup.file1),
+                dup.result.func1.start_line as usize,
+                dup.result.func1.end_line as usize,
+            ),
+            (
+                &relative_display_path(&dup.file2),
+                dup.result.func2.start_line as usize,
+                dup.result.func2.end_line as usize,
+            ),
+        );
+    }
+
+    // Ranked results are listed as collected, most similar first
+    let (clusters, standalone_pairs) = if ranked {
+        (Vec::new(), all_results.clone())
+    } else {
+        cluster_duplicate_results(&all_results)
+    };
+
+    // Keep only findings in the requested triage states
+    let keep = |id: &FindingId| {
+        triage_states.is_empty()
+            || triage_states.contains(&triage.resolve_state(&id.content, &id.legacy))
+    };
+    let mut sources = HashMap::new();
+    let clusters: Vec<(DuplicateCluster, FindingId)> = clusters
+        .into_iter()
+        .map(|cluster| {
+            let id = function_finding_id(
+                cluster.members.iter().map(|member| (member.file.as_path(), &member.function)),
+                &mut sources,
+            );
+            (cluster, id)
+        })
+        .filter(|(_, id)| keep(id))
+        .collect();
+    let standalone_pairs: Vec<(DuplicateResult, FindingId)> = standalone_pairs
+        .into_iter()
+        .map(|dup| {
+            let id = function_finding_id(
+                [
+                    (dup.file1.as_path(), &dup.result.func1),
+                    (dup.file2.as_path(), &dup.result.func2),
+                ],
+                &mut sources,
+            );
+            (dup, id)
+        })
+        .filter(|(_, id)| keep(id))
+        .collect();
+
+    if clusters.is_empty() && standalone_pairs.is_empty() {
+        if output.format == OutputFormat::Standard {
+            println!("\nNo duplicate functions found matching the triage states!");
+        }
         return 0;
     }
 
-    let (clusters, standalone_pairs) = cluster_duplicate_results(&all_results);
+    if output.format != OutputFormat::Standard {
+        let mut pairs: Vec<DuplicateResult> = clusters
+            .iter()
+            .flat_map(|(cluster, _)| cluster.pairs.iter().cloned())
+            .chain(standalone_pairs.iter().map(|(dup, _)| dup.clone()))
+            .collect();
+        display_machine_results(&mut pairs, output, run_report, import_graph);
+        return pairs.len();
+    }
+
+    // A cluster is as severe as its most similar pair
+    let clusters: Vec<(DuplicateCluster, FindingId, Severity)> = clusters
+        .into_iter()
+        .map(|(cluster, id)| {
+            let similarity =
+                cluster.pairs.iter().map(|pair| pair.result.similarity).fold(0.0, f64::max);
+            let severity = run_report.classify("functions", similarity);
+            (cluster, id, severity)
+        })
+        .collect();
+    let standalone_pairs: Vec<(DuplicateResult, FindingId, Severity)> = standalone_pairs
+        .into_iter()
+        .map(|(dup, id)| {
+            let severity = run_report.classify("functions", dup.result.similarity);
+            (dup, id, severity)
+        })
+        .collect();
+
+    let blamer = if blame {
+        all_results.first().and_then(|dup| GitBlamer::discover(&dup.file1))
+    } else {
+        None
+    };
+    if blame && blamer.is_none() {
+        eprintln!("Warning: --blame requires the analyzed files to be inside a git repository");
+    }
 
-    if !clusters.is_empty() {
+    if ranked {
+        println!("\nTop {} most similar pairs:", standalone_pairs.len());
+    } else if !clusters.is_empty() {
         let cluster_label = if clusters.len() == 1 { "cluster" } else { "clusters" };
         if standalone_pairs.is_empty() {
             println!("\nFound {} duplicate {}:", clusters.len(), cluster_label);
@@ -336,7 +711,142 @@ fn display_all_results(
     }
     println!("{}", "-".repeat(60));
 
-    for (index, cluster) in clusters.iter().enumerate() {
+    let mut report = ReportContext {
+        print,
+        diff_layout,
+        triage,
+        show_severity: run_report.has_severity_rules(),
+        import_graph,
+        packages,
+        owners,
+        blamer,
+    };
+
+    if group_by == Some(GroupBy::Owner) && owners.is_some() {
+        display_grouped_by_owner(&mut report, &clusters, &standalone_pairs);
+    } else {
+        for (index, (cluster, id, severity)) in clusters.iter().enumerate() {
+            report.show_cluster(index, cluster, id, *severity);
+        }
+        for (dup, id, severity) in &standalone_pairs {
+            report.show_pair(dup, id, *severity);
+        }
+    }
+
+    if suggest_patch {
+        show_suggested_patches(
+            clusters
+                .iter()
+                .flat_map(|(cluster, _, _)| cluster.pairs.iter())
+                .chain(standalone_pairs.iter().map(|(dup, _, _)| dup)),
+            import_graph.map(ImportGraph::aliases),
+        );
+    }
+
+    clusters.len() + standalone_pairs.len()
+}
+
+/// Whether a function can be moved to its own module as-is
+fn is_extractable(function: &similarity_core::FunctionDefinition) -> bool {
+    function.class_name.is_none()
+        && function.parent_function.is_none()
+        && matches!(function.function_type, FunctionType::Function | FunctionType::Arrow)
+}
+
+/// Print unified diffs extracting near-identical cross-file pairs into shared
+/// modules, easiest merges (similar structure and compatible types) first
+fn show_suggested_patches<'a>(
+    pairs: impl Iterator<Item = &'a DuplicateResult>,
+    aliases: Option<&PathAliases>,
+) {
+    println!("\n=== Suggested Patches ===");
+
+    let mut pairs: Vec<_> = pairs.collect();
+    pairs.sort_by(|a, b| b.result.merge_score().total_cmp(&a.result.merge_score()));
+
+    // Each function is extracted at most once, so the patches do not conflict
+    let mut used = HashSet::new();
+    let mut suggested = 0;
+    for dup in pairs {
+        let (func1, func2) = (&dup.result.func1, &dup.result.func2);
+        if dup.result.similarity < PATCH_SIMILARITY_THRESHOLD
+            || dup.file1 == dup.file2
+            || !is_extractable(func1)
+            || !is_extractable(func2)
+        {
+            continue;
+        }
+        let (key1, key2) =
+            (function_node_key(&dup.file1, func1), function_node_key(&dup.file2, func2));
+        if used.contains(&key1) || used.contains(&key2) {
+            continue;
+        }
+        let (Ok(source1), Ok(source2)) =
+            (fs::read_to_string(&dup.file1), fs::read_to_string(&dup.file2))
+        else {
+            continue;
+        };
+        used.insert(key1);
+        used.insert(key2);
+
+        let path1 = PathBuf::from(relative_display_path(&dup.file1));
+        let path2 = PathBuf::from(relative_display_path(&dup.file2));
+        let first = PatchSource {
+            path: &path1,
+            source: &source1,
+            name: &func1.name,
+            start_line: func1.start_line,
+            end_line: func1.end_line,
+        };
+        let second = PatchSource {
+            path: &path2,
+            source: &source2,
+            name: &func2.name,
+            start_line: func2.start_line,
+            end_line: func2.end_line,
+        };
+        let shared = shared_module_path(&path1, &path2, &func1.name);
+
+        println!(
+            "\n# Extract '{}' into {} (similarity {:.0}%, type compatibility {:.0}%)",
+            func1.name,
+            shared.display(),
+            dup.result.similarity * 100.0,
+            dup.result.type_compatibility * 100.0
+        );
+        print!("{}", extract_shared_function_patch(&first, &second, &shared, aliases));
+        suggested += 1;
+    }
+
+    if suggested == 0 {
+        println!(
+            "\nNo cross-file pairs of top-level functions above {:.0}% similarity to extract.",
+            PATCH_SIMILARITY_THRESHOLD * 100.0
+        );
+    }
+}
+
+/// Everything needed to print one finding
+struct ReportContext<'a> {
+    print: bool,
+    diff_layout: Option<DiffLayout>,
+    triage: &'a TriageStore,
+    /// Print each finding's severity, when severity rules are configured
+    show_severity: bool,
+    import_graph: Option<&'a ImportGraph>,
+    packages: Option<&'a HashMap<PathBuf, String>>,
+    owners: Option<&'a CodeOwners>,
+    blamer: Option<GitBlamer>,
+}
+
+impl ReportContext<'_> {
+    fn show_cluster(
+        &mut self,
+        index: usize,
+        cluster: &DuplicateCluster,
+        id: &FindingId,
+        severity: Severity,
+    ) {
         let avg_similarity = cluster.pairs.iter().map(|pair| pair.result.similarity).sum::<f64>()
             / cluster.pairs.len() as f64;
         let best_score = cluster.pairs.first().map(DuplicateResult::priority).unwrap_or(0.0);
@@ -349,21 +859,33 @@ fn display_all_results(
             avg_similarity * 100.0,
             best_score
         );
+        show_finding_id(self.triage, id);
+        if self.show_severity {
+            println!("  Severity: {}", severity);
+        }
+        if let Some(graph) = self.import_graph {
+            show_cluster_relations(graph, &cluster.pairs);
+            for dup in &cluster.pairs {
+                show_import_instead(graph, dup);
+            }
+        }
+        if let Some(packages) = self.packages {
+            show_packages(packages, cluster.members.iter().map(|member| member.file.as_path()));
+        }
+        if let Some(owners) = self.owners {
+            show_owners(owners, cluster.members.iter().map(|member| member.file.as_path()));
+        }
 
         for member in &cluster.members {
             let relative_path = relative_display_path(&member.file);
-            println!(
-                "  {}",
-                format_function_output(
-                    &relative_path,
-                    &member.function.name,
-                    member.function.start_line,
-                    member.function.end_line,
-                )
-            );
+            println!("  {}", format_function_output(&relative_path, &member.function));
         }
 
-        if print {
+        if let Some(blamer) = self.blamer.as_mut() {
+            show_cluster_provenance(blamer, &cluster.members);
+        }
+
+        if self.print {
             for member in &cluster.members {
                 let relative_path = relative_display_path(&member.file);
                 show_function_code(
@@ -376,7 +898,7 @@ fn display_all_results(
         }
     }
 
-    for dup in &standalone_pairs {
+    fn show_pair(&mut self, dup: &DuplicateResult, id: &FindingId, severity: Severity) {
         let relative_path1 = relative_display_path(&dup.file1);
         let relative_path2 = relative_display_path(&dup.file2);
 
@@ -395,26 +917,46 @@ fn display_all_results(
             max_lines,
             avg_lines
         );
-        println!(
-            "  {}",
-            format_function_output(
-                &relative_path1,
-                &dup.result.func1.name,
-                dup.result.func1.start_line,
-                dup.result.func1.end_line,
-            )
-        );
-        println!(
-            "  {}",
-            format_function_output(
-                &relative_path2,
-                &dup.result.func2.name,
-                dup.result.func2.start_line,
-                dup.result.func2.end_line,
-            )
-        );
+        show_finding_id(self.triage, id);
+        if self.show_severity {
+            println!("  Severity: {}", severity);
+        }
+        if dup.result.type_compatibility < 1.0 {
+            println!(
+                "  Type compatibility: {:.0}% (parameter or return types differ)",
+                dup.result.type_compatibility * 100.0
+            );
+        }
+        if dup.result.algorithm == ComparisonAlgorithm::Fallback {
+            println!("  Algorithm: fallback (node label counts; above --max-tree-nodes)");
+        }
+        if let Some(graph) = self.import_graph {
+            println!(
+                "  Modules: {}",
+                describe_relation(
+                    graph.relation(&dup.file1, &dup.file2),
+                    &relative_path1,
+                    &relative_path2
+                )
+            );
+            show_import_instead(graph, dup);
+        }
+        if let Some(packages) = self.packages {
+            show_packages(packages, [dup.file1.as_path(), dup.file2.as_path()]);
+        }
+        if let Some(owners) = self.owners {
+            show_owners(owners, [dup.file1.as_path(), dup.file2.as_path()]);
+        }
+        println!("  {}", format_function_output(&relative_path1, &dup.result.func1));
+        println!("  {}", format_function_output(&relative_path2, &dup.result.func2));
 
-        if print {
+        if let Some(blamer) = self.blamer.as_mut() {
+            show_pair_provenance(blamer, dup);
+        }
+
+        if let (true, Some(layout)) = (self.print, self.diff_layout) {
+            show_pair_diff(dup, &relative_path1, &relative_path2, layout);
+        } else if self.print {
             show_function_code(
                 &relative_path1,
                 &dup.result.func1.name,
@@ -429,30 +971,322 @@ fn display_all_results(
             );
         }
     }
+}
 
-    clusters.len() + standalone_pairs.len()
+/// Owners of a set of files, or `(unowned)` when none of them has an owner
+fn finding_owners<'a>(
+    owners: &CodeOwners,
+    files: impl IntoIterator<Item = &'a Path>,
+) -> Vec<String> {
+    let mut names: Vec<String> =
+        files.into_iter().flat_map(|file| owners.owners_of(file).iter().cloned()).collect();
+    names.sort_unstable();
+    names.dedup();
+    if names.is_empty() {
+        names.push(UNOWNED.to_string());
+    }
+    names
+}
+
+const UNOWNED: &str = "(unowned)";
+
+/// Print the owners of the given files
+fn show_owners<'a>(owners: &CodeOwners, files: impl IntoIterator<Item = &'a Path>) {
+    println!("  Owners: {}", finding_owners(owners, files).join(", "));
+}
+
+/// Print findings in one section per owner; a finding shared by several owners appears in each
+fn display_grouped_by_owner(
+    report: &mut ReportContext,
+    clusters: &[(DuplicateCluster, FindingId, Severity)],
+    standalone_pairs: &[(DuplicateResult, FindingId, Severity)],
+) {
+    let Some(owners) = report.owners else {
+        return;
+    };
+    let mut groups: BTreeMap<String, (Vec<usize>, Vec<usize>)> = BTreeMap::new();
+    for (index, (cluster, _, _)) in clusters.iter().enumerate() {
+        let files = cluster.members.iter().map(|member| member.file.as_path());
+        for owner in finding_owners(owners, files) {
+            groups.entry(owner).or_default().0.push(index);
+        }
+    }
+    for (index, (dup, _, _)) in standalone_pairs.iter().enumerate() {
+        for owner in finding_owners(owners, [dup.file1.as_path(), dup.file2.as_path()]) {
+            groups.entry(owner).or_default().1.push(index);
+        }
+    }
+
+    // Unowned findings come last
+    let unowned = groups.remove(UNOWNED);
+    for (owner, (cluster_indices, pair_indices)) in
+        groups.into_iter().chain(unowned.map(|group| (UNOWNED.to_string(), group)))
+    {
+        let count = cluster_indices.len() + pair_indices.len();
+        println!(
+            "\n=== Owner: {} ({} {}) ===",
+            owner,
+            count,
+            if count == 1 { "finding" } else { "findings" }
+        );
+        for index in cluster_indices {
+            let (cluster, id, severity) = &clusters[index];
+            report.show_cluster(index, cluster, id, *severity);
+        }
+        for index in pair_indices {
+            let (dup, id, severity) = &standalone_pairs[index];
+            report.show_pair(dup, id, *severity);
+        }
+    }
+}
+
+/// Scope path and export status of a function in JSON findings
+fn finding_symbol(function: &similarity_core::FunctionDefinition) -> FindingSymbol {
+    FindingSymbol { path: function.display_name().to_string(), exported: function.exported }
+}
+
+/// Whether one function of a pair is a private copy of the other, exported one
+fn pair_import_instead(graph: &ImportGraph, dup: &DuplicateResult) -> Option<ImportInstead> {
+    graph.import_instead(
+        (&dup.file1, dup.result.func1.name.as_str()),
+        (&dup.file2, dup.result.func2.name.as_str()),
+    )
+}
+
+/// Print the import that should replace the copy of an exported function
+fn show_import_instead(graph: &ImportGraph, dup: &DuplicateResult) {
+    let Some(found) = pair_import_instead(graph, dup) else {
+        return;
+    };
+    let (copy_file, copy) = if found.original_is_first {
+        (&dup.file2, &dup.result.func2)
+    } else {
+        (&dup.file1, &dup.result.func1)
+    };
+    println!(
+        "  Should import instead of duplicate: '{}' in {} copies an exported function; use `{}`",
+        copy.name,
+        relative_display_path(copy_file),
+        found.statement(copy.name.as_str(), false)
+    );
+}
+
+fn describe_relation(relation: ModuleRelation, path1: &str, path2: &str) -> String {
+    match relation {
+        ModuleRelation::SameFile => "same file".to_string(),
+        ModuleRelation::FirstImportsSecond => format!("{} imports {}", path1, path2),
+        ModuleRelation::SecondImportsFirst => format!("{} imports {}", path2, path1),
+        ModuleRelation::SameDirectory => "same directory, no import between them".to_string(),
+        ModuleRelation::Unrelated => "unrelated (no import between them)".to_string(),
+    }
+}
+
+/// Print how many pairs of a cluster are in files that import each other
+fn show_cluster_relations(graph: &ImportGraph, pairs: &[DuplicateResult]) {
+    let mut counts = [0usize; 4];
+    for dup in pairs {
+        let index = match graph.relation(&dup.file1, &dup.file2) {
+            ModuleRelation::SameFile => 0,
+            ModuleRelation::FirstImportsSecond | ModuleRelation::SecondImportsFirst => 1,
+            ModuleRelation::SameDirectory => 2,
+            ModuleRelation::Unrelated => 3,
+        };
+        counts[index] += 1;
+    }
+    let labels = ["same file", "importing", "same directory", "unrelated"];
+    let summary: Vec<String> = counts
+        .iter()
+        .zip(labels)
+        .filter(|(count, _)| **count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect();
+    println!("  Modules: {} pair(s)", summary.join(", "));
+}
+
+/// Print the distinct packages owning the given files
+fn show_packages<'a>(
+    packages: &HashMap<PathBuf, String>,
+    files: impl IntoIterator<Item = &'a Path>,
+) {
+    let mut names: Vec<&str> =
+        files.into_iter().filter_map(|file| packages.get(file)).map(String::as_str).collect();
+    names.sort_unstable();
+    names.dedup();
+    if !names.is_empty() {
+        println!("  Packages: {}", names.join(", "));
+    }
+}
+
+fn describe_blame(info: &BlameInfo) -> String {
+    if info.is_committed() {
+        format!("{} in {} ({})", info.author, info.short_commit(), info.date())
+    } else {
+        "uncommitted changes".to_string()
+    }
+}
+
+/// Print which copy of a pair is older and who introduced the newer one
+fn show_pair_provenance(blamer: &mut GitBlamer, dup: &DuplicateResult) {
+    let func1 = &dup.result.func1;
+    let func2 = &dup.result.func2;
+    let blame1 = blamer.blame_range(&dup.file1, func1.start_line as usize, func1.end_line as usize);
+    let blame2 = blamer.blame_range(&dup.file2, func2.start_line as usize, func2.end_line as usize);
+
+    let (Some(blame1), Some(blame2)) = (blame1, blame2) else {
+        println!("  Provenance: unavailable (not tracked by git)");
+        return;
+    };
+
+    let ((older_name, older), (newer_name, newer)) = match older_copy(&blame1, &blame2) {
+        OlderCopy::Same => {
+            println!("  Provenance: both copies last changed by {}", describe_blame(&blame1));
+            return;
+        }
+        OlderCopy::First => ((&func1.name, &blame1), (&func2.name, &blame2)),
+        OlderCopy::Second => ((&func2.name, &blame2), (&func1.name, &blame1)),
+    };
+    println!(
+        "  Provenance: '{}' is older ({}); newer copy '{}' introduced by {}",
+        older_name,
+        describe_blame(older),
+        newer_name,
+        describe_blame(newer)
+    );
+}
+
+/// Print when each member of a cluster was introduced, marking the oldest copy
+fn show_cluster_provenance(blamer: &mut GitBlamer, members: &[ClusterMember]) {
+    let blames: Vec<Option<BlameInfo>> = members
+        .iter()
+        .map(|member| {
+            blamer.blame_range(
+                &member.file,
+                member.function.start_line as usize,
+                member.function.end_line as usize,
+            )
+        })
+        .collect();
+    let oldest = blames
+        .iter()
+        .enumerate()
+        .filter_map(|(index, blame)| blame.as_ref().map(|blame| (index, blame.time)))
+        .min_by_key(|(_, time)| *time)
+        .map(|(index, _)| index);
+
+    println!("  Provenance:");
+    for (index, (member, blame)) in members.iter().zip(&blames).enumerate() {
+        let description =
+            blame.as_ref().map_or_else(|| "not tracked by git".to_string(), describe_blame);
+        let marker = if Some(index) == oldest { " (oldest copy)" } else { "" };
+        println!("    {}: {}{}", member.function.name, description, marker);
+    }
+}
+
+/// Print similarity results in the output format, detailed with fixes for
+/// identical functions and imports replacing copies of exported functions
+fn display_machine_results(
+    all_results: &mut [DuplicateResult],
+    output: &mut FindingOutput,
+    run_report: &RunReport,
+    import_graph: Option<&ImportGraph>,
+) {
+    all_results.sort_by(|a, b| {
+        b.priority().partial_cmp(&a.priority()).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut sources: HashMap<PathBuf, Option<String>> = HashMap::new();
+    for dup in all_results.iter() {
+        let message = format!(
+            "Function '{}' is {:.0}% similar to '{}'",
+            dup.result.func1.display_name(),
+            dup.result.similarity * 100.0,
+            dup.result.func2.display_name()
+        );
+        let (path1, path2) = (relative_display_path(&dup.file1), relative_display_path(&dup.file2));
+        let severity = run_report.classify("functions", dup.result.similarity);
+        let (line1, line2) =
+            (dup.result.func1.start_line as usize, dup.result.func2.start_line as usize);
+        for file in [&dup.file1, &dup.file2] {
+            sources.entry(file.clone()).or_insert_with(|| fs::read_to_string(file).ok());
+        }
+        let fix = match (&sources[&dup.file1], &sources[&dup.file2]) {
+            (Some(source1), Some(source2)) => identical_function_fix(
+                &FunctionSite { path: &path1, source: source1, function: &dup.result.func1 },
+                &FunctionSite { path: &path2, source: source2, function: &dup.result.func2 },
+                import_graph.map(ImportGraph::aliases),
+            ),
+            _ => None,
+        };
+        let id = function_finding_id(
+            [(dup.file1.as_path(), &dup.result.func1), (dup.file2.as_path(), &dup.result.func2)],
+            &mut sources,
+        );
+        let mut finding =
+            json_finding(&path1, line1, severity, &message, &path2, line2, fix.as_slice());
+        finding.id = Some(id.content);
+        finding.similarity = Some(dup.result.similarity);
+        finding.algorithm = Some(dup.result.algorithm);
+        finding.symbol = Some(finding_symbol(&dup.result.func1));
+        finding.related.symbol = Some(finding_symbol(&dup.result.func2));
+        if let Some(found) = import_graph.and_then(|graph| pair_import_instead(graph, dup)) {
+            let copy = if found.original_is_first { &dup.result.func2 } else { &dup.result.func1 };
+            finding.import_instead = Some(FindingImport {
+                file: if found.original_is_first { &path2 } else { &path1 }.clone(),
+                statement: found.statement(copy.name.as_str(), false),
+                module: found.specifier,
+            });
+        }
+        output.print_detailed(&finding);
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
 pub fn check_paths(
     paths: Vec<String>,
     threshold: f64,
+    top: Option<usize>,
     rename_cost: f64,
     extensions: Option<&Vec<String>>,
     min_lines: u32,
     min_tokens: Option<u32>,
+    min_complexity: Option<u32>,
     no_size_penalty: bool,
+    size_ratio_penalty: SizePenalty,
+    include_comments: bool,
+    max_tree_nodes: Option<usize>,
+    canonicalize_order: bool,
+    normalize_async: bool,
+    normalize_collections: bool,
+    node_weights: NodeWeights,
     print: bool,
+    print_layout: PrintLayout,
     fast_mode: bool,
     filter_function: Option<&String>,
     filter_function_body: Option<&String>,
     exclude_patterns: &[String],
     show_ignored: bool,
+    output: &mut FindingOutput,
+    blame: bool,
+    suggest_patch: bool,
+    only_unrelated_modules: bool,
+    scope: Scope,
+    group_by: Option<GroupBy>,
+    budget: &ComparisonBudget,
+    session_dir: Option<&Path>,
+    resume: bool,
+    triage: &TriageStore,
+    triage_states: &[TriageState],
+    boilerplate: &BoilerplateSet,
+    heatmap: &mut DuplicationHeatmap,
+    matrix: Option<&MatrixExporter>,
+    report: &RunReport,
 ) -> anyhow::Result<usize> {
     let default_extensions = vec!["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
     let exts: Vec<&str> =
         extensions.map_or(default_extensions, |v| v.iter().map(String::as_str).collect());
 
+    let started = Instant::now();
+
     // Create exclude matcher
     let exclude_matcher = create_exclude_matcher(exclude_patterns);
     let mut files = Vec::new();
@@ -472,17 +1306,15 @@ pub fn check_paths(
                                 files.push(path.to_path_buf());
                             }
                         }
+                    } else {
+                        tracing::info!(file = %path.display(), "skipping file: unsupported extension");
+                        report.skip(path_str, SkipReason::UnsupportedExtension);
                     }
                 }
             }
         } else if path.is_dir() {
-            // If it's a directory, walk it respecting .gitignore
-            let walker = WalkBuilder::new(path)
-                .follow_links(false)
-                .git_ignore(true) // Respect .gitignore files
-                .git_global(true) // Respect global gitignore
-                .git_exclude(true) // Respect .git/info/exclude
-                .build();
+            // If it's a directory, walk it respecting .gitignore and .similarityignore
+            let walker = walk_dir(path);
 
             for entry in walker {
                 let entry = entry?;
@@ -497,6 +1329,8 @@ pub fn check_paths(
                 if let Some(ref matcher) = exclude_matcher {
                     // Check both the full path and relative path from the search root
                     if matcher.is_match(entry_path) {
+                        tracing::debug!(file = %entry_path.display(), "skipping file: excluded");
+                        report.skip(&entry_path.display().to_string(), SkipReason::Excluded);
                         continue;
                     }
 
@@ -504,6 +1338,9 @@ pub fn check_paths(
                     if let Ok(current_dir) = std::env::current_dir() {
                         if let Ok(relative) = entry_path.strip_prefix(&current_dir) {
                             if matcher.is_match(relative) {
+                                tracing::debug!(file = %entry_path.display(), "skipping file: excluded");
+                                report
+                                    .skip(&entry_path.display().to_string(), SkipReason::Excluded);
                                 continue;
                             }
                         }
@@ -530,37 +1367,117 @@ pub fn check_paths(
 
     // Sort files for consistent output
     files.sort();
+    tracing::info!(files = files.len(), elapsed = ?started.elapsed(), "discovered files");
 
     if files.is_empty() {
-        println!("No TypeScript/JavaScript files found in the specified paths.");
+        if output.format == OutputFormat::Standard {
+            println!("No TypeScript/JavaScript files found in the specified paths.");
+        }
         return Ok(0);
     }
 
-    println!("Checking {} files for duplicates...", files.len());
+    if output.format == OutputFormat::Standard {
+        println!("Checking {} files for duplicates...", files.len());
+    }
+
+    for file in &files {
+        heatmap.add_file(&relative_display_path(file));
+    }
+
+    let mut resolver = PackageResolver::new();
+    let packages: HashMap<PathBuf, String> = files
+        .iter()
+        .filter_map(|file| Some((file.clone(), resolver.package_of(file)?.name)))
+        .collect();
+    let package_count = packages.values().collect::<HashSet<_>>().len();
 
     let mut options = TSEDOptions::default();
     options.apted_options.rename_cost = rename_cost;
     options.min_lines = min_lines;
     options.min_tokens = min_tokens;
     options.size_penalty = !no_size_penalty;
+    options.size_ratio_penalty = size_ratio_penalty;
+    options.include_comments = include_comments;
+    options.max_tree_nodes = max_tree_nodes;
+    options.canonicalize_order = canonicalize_order;
+    options.normalize_async = normalize_async;
+    options.normalize_collections = normalize_collections;
+    options.node_weights = node_weights;
+
+    let session = match session_dir {
+        Some(dir) => {
+            let settings = format!("{threshold}|{fast_mode}|{options:?}|{:?}", budget.shard());
+            Some(open_session(dir, run_key(&files, &settings), resume)?)
+        }
+        None => None,
+    };
 
     let mut all_results = Vec::new();
 
     // Check within each file in parallel
-    let within_file_results =
-        check_within_file_duplicates_parallel(&files, threshold, &options, fast_mode);
+    let started = Instant::now();
+    let within_file_ranked =
+        top.map(|top| rank_within_file_pairs_parallel(&files, &options, fast_mode, top, budget));
+    if top.is_none() {
+        let within_file_results = check_within_file_duplicates_parallel(
+            &files,
+            threshold,
+            &options,
+            fast_mode,
+            budget,
+            session.as_ref(),
+            matrix,
+        );
 
-    // Collect within-file duplicates
-    for (file, similar_pairs) in within_file_results {
-        for result in similar_pairs {
-            all_results.push(DuplicateResult { file1: file.clone(), file2: file.clone(), result });
+        // Collect within-file duplicates
+        for (file, similar_pairs) in within_file_results {
+            for result in similar_pairs {
+                all_results.push(DuplicateResult {
+                    file1: file.clone(),
+                    file2: file.clone(),
+                    result,
+                });
+            }
         }
     }
+    tracing::info!(elapsed = ?started.elapsed(), "compared functions within files");
 
     // Check across files in parallel
-    let file_data = load_files_parallel(&files);
-    let cross_file_results =
-        check_cross_file_duplicates_parallel(&file_data, threshold, &options, fast_mode);
+    let started = Instant::now();
+    let file_data = load_files_parallel(&files, report, session.as_ref());
+    tracing::info!(
+        files = file_data.len(),
+        skipped = files.len() - file_data.len(),
+        elapsed = ?started.elapsed(),
+        "parsed files"
+    );
+    let started = Instant::now();
+    let cross_file_results = match (top, within_file_ranked) {
+        // Ranking mode keeps the best pairs of both loops, most similar first
+        (Some(top), Some(within_file)) => within_file
+            .merge(rank_cross_file_pairs_parallel(&file_data, &options, top, budget))
+            .into_sorted_vec(),
+        _ => check_cross_file_duplicates_parallel(
+            &file_data,
+            threshold,
+            &options,
+            fast_mode,
+            budget,
+            session.as_ref(),
+            matrix,
+        ),
+    };
+    tracing::info!(elapsed = ?started.elapsed(), "compared functions across files");
+
+    if let Some(session) = &session {
+        session.save()?;
+        if !budget.coverage().is_complete() {
+            eprintln!(
+                "Progress saved to {}; rerun with --resume to continue",
+                session.dir().display()
+            );
+        }
+    }
 
     // Collect cross-file duplicates
     for (file1, result, file2) in cross_file_results {
@@ -571,17 +1488,137 @@ pub fn check_paths(
         });
     }
 
-    // Display all results together
-    let duplicate_count =
-        display_all_results(all_results, print, filter_function, filter_function_body);
+    // Drop trivial functions (getters, setters, simple mappers)
+    let started = Instant::now();
+    if let Some(min_complexity) = min_complexity {
+        all_results.retain(|dup| {
+            let kept = dup.result.func1.complexity >= min_complexity
+                && dup.result.func2.complexity >= min_complexity;
+            if !kept {
+                tracing::debug!(
+                    func1 = %dup.result.func1.name,
+                    func2 = %dup.result.func2.name,
+                    "pruned pair: below --min-complexity"
+                );
+            }
+            kept
+        });
+    }
+
+    if !boilerplate.is_empty() {
+        let before = all_results.len();
+        suppress_boilerplate(&mut all_results, boilerplate, threshold, &options);
+        let suppressed = before - all_results.len();
+        if suppressed > 0 && output.format == OutputFormat::Standard {
+            println!("Suppressed {} duplicate pair(s) matching known boilerplate", suppressed);
+        }
+    }
+
+    let import_graph = (!all_results.is_empty()).then(|| ImportGraph::build(&files));
+    if only_unrelated_modules {
+        if let Some(graph) = &import_graph {
+            all_results
+                .retain(|dup| graph.relation(&dup.file1, &dup.file2) == ModuleRelation::Unrelated);
+        }
+    }
+
+    if scope != Scope::All {
+        all_results.retain(|dup| {
+            let same_package = packages.get(&dup.file1) == packages.get(&dup.file2);
+            same_package == (scope == Scope::PerPackage)
+        });
+    }
 
-    if show_ignored {
+    tracing::info!(pairs = all_results.len(), elapsed = ?started.elapsed(), "filtered findings");
+
+    let owners = std::env::current_dir().ok().and_then(|dir| CodeOwners::discover(&dir));
+    if group_by == Some(GroupBy::Owner)
+        && owners.is_none()
+        && output.format == OutputFormat::Standard
+    {
+        eprintln!(
+            "Warning: --group-by owner requires a CODEOWNERS file; showing an ungrouped report"
+        );
+    }
+
+    // Display all results together
+    let duplicate_count = display_all_results(
+        all_results,
+        top.is_some(),
+        print,
+        print_layout.resolve(),
+        filter_function,
+        filter_function_body,
+        output,
+        blame,
+        suggest_patch,
+        import_graph.as_ref(),
+        (package_count > 1).then_some(&packages),
+        owners.as_ref(),
+        group_by,
+        triage,
+        triage_states,
+        heatmap,
+        report,
+    );
+
+    if show_ignored && output.format == OutputFormat::Standard {
         report_ignored_functions(&files);
     }
 
     Ok(duplicate_count)
 }
 
+/// Open the session in `dir` and report what is resumed from it
+fn open_session(dir: &Path, run_key: u64, resume: bool) -> anyhow::Result<AnalysisSession> {
+    let (session, start) = AnalysisSession::open(dir, run_key, resume)?;
+    match start {
+        SessionStart::Resumed => {
+            let progress = session.progress();
+            eprintln!(
+                "Resuming session in {}: {} files parsed, {} files and {} cross-file batches compared",
+                dir.display(),
+                progress.files_parsed,
+                progress.files_compared,
+                progress.batches_compared
+            );
+        }
+        SessionStart::Stale => eprintln!(
+            "Warning: session in {} was recorded for other files or options; starting over",
+            dir.display()
+        ),
+        SessionStart::Fresh if resume => {
+            eprintln!("No session to resume in {}; starting over", dir.display());
+        }
+        SessionStart::Fresh => {}
+    }
+    Ok(session)
+}
+
+/// Drop pairs whose functions are both instances of learned boilerplate
+fn suppress_boilerplate(
+    all_results: &mut Vec<DuplicateResult>,
+    boilerplate: &BoilerplateSet,
+    threshold: f64,
+    options: &TSEDOptions,
+) {
+    let mut sources: HashMap<PathBuf, String> = HashMap::new();
+    let mut matches: HashMap<FunctionNodeKey, bool> = HashMap::new();
+    let mut is_boilerplate = |file: &Path, function: &similarity_core::FunctionDefinition| {
+        *matches.entry(function_node_key(file, function)).or_insert_with(|| {
+            let source = sources
+                .entry(file.to_path_buf())
+                .or_insert_with(|| fs::read_to_string(file).unwrap_or_default());
+            boilerplate.find_match(function, source, threshold, options).is_some()
+        })
+    };
+
+    all_results.retain(|dup| {
+        !(is_boilerplate(&dup.file1, &dup.result.func1)
+            && is_boilerplate(&dup.file2, &dup.result.func2))
+    });
+}
+
 fn report_ignored_functions(files: &[PathBuf]) {
     let mut ignored = Vec::new();
 
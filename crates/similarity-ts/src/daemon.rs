@@ -0,0 +1,321 @@
+//! Long-running lint bridge for editor and ESLint integrations.
+//!
+//! `--daemon` indexes the functions of the analyzed paths once and then
+//! answers JSON-RPC 2.0 requests, one JSON object per line, on standard
+//! input and output. An ESLint plugin sends the current text of the file it
+//! lints and gets back the functions of that text resembling a function
+//! elsewhere in the repository; the index stays in memory between requests,
+//! so a lint only costs the comparisons of one file.
+//!
+//! Methods:
+//!
+//! - `lint` with `{ "file", "text" }`: findings of `text` against the index,
+//!   leaving out the functions indexed from `file` itself, which are then
+//!   replaced by those of `text` so later lints see the edited version
+//! - `lens` with `{ "file", "text" }`: every function of `text` with the
+//!   number of similar functions and the best of them, for a code lens
+//!   (see [`lens_json`]); `text` replaces the file's functions as in `lint`
+//! - `forget` with `{ "file" }`: drop a deleted file from the index
+//! - `shutdown`: answer `null` and stop reading
+//!
+//! Logs go to standard error; standard output carries only responses.
+
+use serde_json::{json, Value};
+use similarity_core::tsed::TSEDOptions;
+use similarity_core::{ComparisonBudget, FunctionIndex, FunctionLens, IndexMatch};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+pub struct Daemon {
+    index: FunctionIndex,
+    threshold: f64,
+    options: TSEDOptions,
+    /// Directory the indexed paths are relative to
+    root: PathBuf,
+}
+
+impl Daemon {
+    pub fn new(index: FunctionIndex, threshold: f64, options: TSEDOptions, root: PathBuf) -> Self {
+        Self { index, threshold, options, root }
+    }
+
+    /// Answer requests from `input` until it ends or `shutdown` is received
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (response, shutdown) = self.handle(&line);
+            if let Some(response) = response {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+            if shutdown {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// The response to one request line, if it expects one, and whether it
+    /// asked the daemon to stop
+    pub fn handle(&mut self, line: &str) -> (Option<Value>, bool) {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return (Some(error(Value::Null, PARSE_ERROR, &e.to_string())), false),
+        };
+        // Notifications carry no id and get no response
+        let id = request.get("id").cloned();
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            let id = id.unwrap_or(Value::Null);
+            return (Some(error(id, INVALID_REQUEST, "missing method")), false);
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "lint" => self.lint(&params),
+            "lens" => self.lens(&params),
+            "forget" => self.forget(&params),
+            "shutdown" => Ok(Value::Null),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method '{}'", method))),
+        };
+        let response = id.map(|id| match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error(id, code, &message),
+        });
+        (response, method == "shutdown")
+    }
+
+    fn lint(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let file = self.file_param(params)?;
+        let text = params
+            .get("text")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "missing 'text'".to_string()))?;
+
+        self.index.remove_file(&file);
+        let findings = match self.index.find_matches(
+            &file,
+            text,
+            self.threshold,
+            &self.options,
+            &ComparisonBudget::unlimited(),
+        ) {
+            Ok(matches) => matches.iter().map(match_json).collect(),
+            // Text being edited often does not parse; report nothing until it does
+            Err(e) => {
+                tracing::debug!(file = %file, error = %e, "lint text does not parse");
+                Vec::new()
+            }
+        };
+        if let Err(e) = self.index.add_source(&file, text) {
+            tracing::debug!(file = %file, error = %e, "not indexing lint text");
+        }
+        Ok(json!({ "findings": findings }))
+    }
+
+    fn lens(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let file = self.file_param(params)?;
+        let text = params
+            .get("text")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "missing 'text'".to_string()))?;
+
+        let lenses = match self.index.lens(
+            &file,
+            text,
+            self.threshold,
+            &self.options,
+            &ComparisonBudget::unlimited(),
+        ) {
+            Ok(lenses) => lenses,
+            Err(e) => {
+                tracing::debug!(file = %file, error = %e, "lens text does not parse");
+                Vec::new()
+            }
+        };
+        let result = lens_json(&file, &lenses);
+        self.index.remove_file(&file);
+        if let Err(e) = self.index.add_source(&file, text) {
+            tracing::debug!(file = %file, error = %e, "not indexing lens text");
+        }
+        Ok(result)
+    }
+
+    fn forget(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let file = self.file_param(params)?;
+        Ok(json!({ "removed": self.index.remove_file(&file) }))
+    }
+
+    /// The `file` parameter in the form the index uses, relative to the root
+    fn file_param(&self, params: &Value) -> Result<String, (i64, String)> {
+        let file = params
+            .get("file")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "missing 'file'".to_string()))?;
+        Ok(Path::new(file)
+            .strip_prefix(&self.root)
+            .unwrap_or(Path::new(file))
+            .to_string_lossy()
+            .to_string())
+    }
+}
+
+/// `{ "name", "startLine", "endLine", "similarity", "message", "similarTo" }`
+/// of a function and the indexed function it resembles most, `similarTo`
+/// giving that function's `file`, `name`, `startLine` and `endLine`
+pub fn match_json(found: &IndexMatch) -> Value {
+    json!({
+        "name": found.function.name.to_string(),
+        "startLine": found.function.start_line,
+        "endLine": found.function.end_line,
+        "similarity": found.similarity,
+        "message": format!(
+            "'{}' is {:.0}% similar to '{}' in {}:{}",
+            found.function.name,
+            found.similarity * 100.0,
+            found.indexed.name,
+            found.indexed.file,
+            found.indexed.start_line
+        ),
+        "similarTo": {
+            "file": &*found.indexed.file,
+            "name": &*found.indexed.name,
+            "startLine": found.indexed.start_line,
+            "endLine": found.indexed.end_line,
+        },
+    })
+}
+
+/// `{ "file", "lenses": [...] }` with per function its `name`, `startLine`,
+/// `endLine`, the `count` of similar functions, a `title` such as
+/// "3 similar implementations" and the most similar function as `best`
+/// (`null` without any)
+pub fn lens_json(file: &str, lenses: &[FunctionLens]) -> Value {
+    let lenses: Vec<Value> = lenses
+        .iter()
+        .map(|lens| {
+            let title = match lens.similar {
+                0 => "No similar implementations".to_string(),
+                1 => "1 similar implementation".to_string(),
+                count => format!("{} similar implementations", count),
+            };
+            let best = lens.best.as_ref().map(|(entry, similarity)| {
+                json!({
+                    "file": &*entry.file,
+                    "name": &*entry.name,
+                    "startLine": entry.start_line,
+                    "endLine": entry.end_line,
+                    "similarity": similarity,
+                })
+            });
+            json!({
+                "name": lens.function.name.to_string(),
+                "startLine": lens.function.start_line,
+                "endLine": lens.function.end_line,
+                "count": lens.similar,
+                "title": title,
+                "best": best,
+            })
+        })
+        .collect();
+    json!({ "file": file, "lenses": lenses })
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOTAL: &str = "export function sumPrices(items) {\n  let total = 0;\n  for (const item of items) {\n    total += item.price * item.quantity;\n  }\n  return total;\n}\n";
+
+    #[test]
+    fn test_lint_matches_other_files_and_keeps_the_index_current() {
+        let mut index = FunctionIndex::new();
+        index.add_source("src/prices.ts", TOTAL).unwrap();
+        index.add_source("src/cart.ts", "export const empty = () => [];\n").unwrap();
+        let options = TSEDOptions { size_penalty: false, ..TSEDOptions::default() };
+        let mut daemon = Daemon::new(index, 0.9, options, PathBuf::from("/repo"));
+
+        let text = TOTAL.replace("sumPrices", "cartTotal");
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "lint",
+            "params": { "file": "/repo/src/cart.ts", "text": text },
+        });
+        let input =
+            format!("{}\n{{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"shutdown\"}}\n", request);
+        let mut output = Vec::new();
+        daemon.serve(input.as_bytes(), &mut output).unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 2);
+        let findings = responses[0]["result"]["findings"].as_array().unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0]["name"], "cartTotal");
+        assert_eq!(findings[0]["similarTo"]["file"], "src/prices.ts");
+        assert_eq!(responses[1]["result"], Value::Null);
+
+        // The linted text replaced the file's previous functions
+        let names: Vec<&str> = daemon.index.functions().iter().map(|entry| &*entry.name).collect();
+        assert_eq!(names, ["sumPrices", "cartTotal"]);
+    }
+
+    #[test]
+    fn test_lens_counts_similar_functions() {
+        let mut index = FunctionIndex::new();
+        index.add_source("src/prices.ts", TOTAL).unwrap();
+        let options = TSEDOptions { size_penalty: false, ..TSEDOptions::default() };
+        let mut daemon = Daemon::new(index, 0.9, options, PathBuf::from("/repo"));
+
+        let text = format!(
+            "{}\nexport const empty = () => [];\n",
+            TOTAL.replace("sumPrices", "cartTotal")
+        );
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "lens",
+            "params": { "file": "/repo/src/cart.ts", "text": text },
+        });
+        let (response, _) = daemon.handle(&request.to_string());
+        let result = &response.unwrap()["result"];
+        assert_eq!(result["file"], "src/cart.ts");
+        let lenses = result["lenses"].as_array().unwrap();
+        assert_eq!(lenses.len(), 1);
+        assert_eq!(lenses[0]["name"], "cartTotal");
+        assert_eq!(lenses[0]["title"], "1 similar implementation");
+        assert_eq!(lenses[0]["best"]["file"], "src/prices.ts");
+        assert_eq!(daemon.index.len(), 3);
+    }
+
+    #[test]
+    fn test_malformed_requests_get_errors() {
+        let mut daemon =
+            Daemon::new(FunctionIndex::new(), 0.9, TSEDOptions::default(), PathBuf::from("/"));
+        let (response, _) = daemon.handle("{not json");
+        assert_eq!(response.unwrap()["error"]["code"], PARSE_ERROR);
+        let (response, _) = daemon.handle(r#"{"jsonrpc":"2.0","id":3,"method":"format"}"#);
+        assert_eq!(response.unwrap()["error"]["code"], METHOD_NOT_FOUND);
+        let (response, _) =
+            daemon.handle(r#"{"jsonrpc":"2.0","id":4,"method":"lint","params":{"file":"a.ts"}}"#);
+        assert_eq!(response.unwrap()["error"]["code"], INVALID_PARAMS);
+        let (response, shutdown) =
+            daemon.handle(r#"{"jsonrpc":"2.0","method":"forget","params":{"file":"a.ts"}}"#);
+        assert!(response.is_none() && !shutdown);
+    }
+}
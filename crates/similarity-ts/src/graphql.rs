@@ -0,0 +1,396 @@
+//! Duplicate GraphQL operations and fragments in `gql`/`graphql` tagged templates.
+//!
+//! Templates are found lexically, so files with syntax errors elsewhere still
+//! contribute their documents. `${...}` interpolations (usually fragments
+//! appended to a document) are blanked out before parsing. Each operation and
+//! fragment is converted to a tree whose selections are sorted by field name,
+//! so reordered selections compare as equal.
+
+use graphql_parser::query::{
+    parse_query, Definition, OperationDefinition, Selection, SelectionSet, TypeCondition, Value,
+};
+use similarity_core::cli_output::format_function_output;
+use similarity_core::injection::find_tagged_templates;
+use similarity_core::tree::TreeNode;
+use similarity_core::tsed::{calculate_tsed, TSEDOptions};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::rc::Rc;
+
+const TAGS: [&str; 2] = ["gql", "graphql"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphqlKind {
+    Query,
+    Mutation,
+    Subscription,
+    Fragment,
+}
+
+impl fmt::Display for GraphqlKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GraphqlKind::Query => "query",
+            GraphqlKind::Mutation => "mutation",
+            GraphqlKind::Subscription => "subscription",
+            GraphqlKind::Fragment => "fragment",
+        })
+    }
+}
+
+/// An operation or fragment of a tagged template
+#[derive(Debug, Clone)]
+pub struct GraphqlDefinition {
+    pub kind: GraphqlKind,
+    /// Operation or fragment name, `(anonymous)` for unnamed operations
+    pub name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub source: String,
+    /// Selected field paths, e.g. `user.profile.avatar`
+    pub fields: BTreeSet<String>,
+    pub tree: Rc<TreeNode>,
+}
+
+impl GraphqlDefinition {
+    pub fn line_count(&self) -> u32 {
+        self.end_line - self.start_line + 1
+    }
+}
+
+/// Extract the operations and fragments of the tagged templates in a file
+///
+/// Templates that are not valid GraphQL are skipped; an error is returned
+/// only when no template of the file parses.
+pub fn extract_graphql(file_path: &str, source: &str) -> Result<Vec<GraphqlDefinition>, String> {
+    let mut definitions = Vec::new();
+    let mut errors = Vec::new();
+    for template in find_tagged_templates(file_path, source, &TAGS) {
+        let body = template.body_without_interpolations(' ');
+        let document = match parse_query::<&str>(&body) {
+            Ok(document) => document,
+            Err(e) => {
+                errors.push(format!("line {}: {}", template.line, e.to_string().trim()));
+                continue;
+            }
+        };
+        let lines: Vec<&str> = body.lines().collect();
+        for definition in &document.definitions {
+            let (kind, name, position, selection_set) = match definition {
+                Definition::Fragment(fragment) => {
+                    let TypeCondition::On(on) = &fragment.type_condition;
+                    (
+                        GraphqlKind::Fragment,
+                        format!("{} on {}", fragment.name, on),
+                        fragment.position,
+                        &fragment.selection_set,
+                    )
+                }
+                Definition::Operation(operation) => match operation {
+                    OperationDefinition::SelectionSet(set) => {
+                        (GraphqlKind::Query, operation_name(None), set.span.0, set)
+                    }
+                    OperationDefinition::Query(query) => (
+                        GraphqlKind::Query,
+                        operation_name(query.name),
+                        query.position,
+                        &query.selection_set,
+                    ),
+                    OperationDefinition::Mutation(mutation) => (
+                        GraphqlKind::Mutation,
+                        operation_name(mutation.name),
+                        mutation.position,
+                        &mutation.selection_set,
+                    ),
+                    OperationDefinition::Subscription(subscription) => (
+                        GraphqlKind::Subscription,
+                        operation_name(subscription.name),
+                        subscription.position,
+                        &subscription.selection_set,
+                    ),
+                },
+            };
+
+            let (first, last) = (position.line, selection_set.span.1.line.max(position.line));
+            let mut fields = BTreeSet::new();
+            collect_fields(selection_set, "", &mut fields);
+            let mut next_id = 0;
+            let mut root = TreeNode::new(kind.to_string(), String::new(), next_id);
+            next_id += 1;
+            root.add_child(selection_tree(selection_set, &mut next_id));
+
+            definitions.push(GraphqlDefinition {
+                kind,
+                name,
+                file_path: file_path.to_string(),
+                start_line: template.line + first as u32 - 1,
+                end_line: template.line + last as u32 - 1,
+                source: lines.get(first - 1..last.min(lines.len())).unwrap_or_default().join("\n"),
+                fields,
+                tree: Rc::new(root),
+            });
+        }
+    }
+
+    if definitions.is_empty() && !errors.is_empty() {
+        return Err(format!("GraphQL parse errors: {}", errors.join("; ")));
+    }
+    Ok(definitions)
+}
+
+fn operation_name(name: Option<&str>) -> String {
+    name.unwrap_or("(anonymous)").to_string()
+}
+
+fn collect_fields<'a>(
+    selection_set: &SelectionSet<'a, &'a str>,
+    prefix: &str,
+    fields: &mut BTreeSet<String>,
+) {
+    for selection in &selection_set.items {
+        match selection {
+            Selection::Field(field) => {
+                let path = if prefix.is_empty() {
+                    field.name.to_string()
+                } else {
+                    format!("{prefix}.{}", field.name)
+                };
+                collect_fields(&field.selection_set, &path, fields);
+                fields.insert(path);
+            }
+            Selection::InlineFragment(fragment) => {
+                collect_fields(&fragment.selection_set, prefix, fields);
+            }
+            Selection::FragmentSpread(spread) => {
+                fields.insert(format!("{prefix}...{}", spread.fragment_name));
+            }
+        }
+    }
+}
+
+fn selection_tree<'a>(
+    selection_set: &SelectionSet<'a, &'a str>,
+    next_id: &mut usize,
+) -> Rc<TreeNode> {
+    let mut node = TreeNode::new("SelectionSet".to_string(), String::new(), *next_id);
+    *next_id += 1;
+
+    let mut items: Vec<&Selection<'a, &'a str>> = selection_set.items.iter().collect();
+    items.sort_by_key(|selection| match selection {
+        Selection::Field(field) => (0, field.name),
+        Selection::FragmentSpread(spread) => (1, spread.fragment_name),
+        Selection::InlineFragment(fragment) => {
+            (2, fragment.type_condition.as_ref().map_or("", |TypeCondition::On(on)| on))
+        }
+    });
+    for selection in items {
+        let child = match selection {
+            Selection::Field(field) => {
+                let mut child = leaf("Field", field.name, next_id);
+                for (argument, value) in &field.arguments {
+                    let mut argument_node = leaf("Argument", argument, next_id);
+                    argument_node.add_child(Rc::new(leaf(value_label(value), "", next_id)));
+                    child.add_child(Rc::new(argument_node));
+                }
+                if !field.selection_set.items.is_empty() {
+                    child.add_child(selection_tree(&field.selection_set, next_id));
+                }
+                child
+            }
+            Selection::FragmentSpread(spread) => {
+                leaf("FragmentSpread", spread.fragment_name, next_id)
+            }
+            Selection::InlineFragment(fragment) => {
+                let on = fragment.type_condition.as_ref().map_or("", |TypeCondition::On(on)| on);
+                let mut child = leaf("InlineFragment", on, next_id);
+                child.add_child(selection_tree(&fragment.selection_set, next_id));
+                child
+            }
+        };
+        node.add_child(Rc::new(child));
+    }
+    Rc::new(node)
+}
+
+fn leaf(label: &str, value: &str, next_id: &mut usize) -> TreeNode {
+    let node = TreeNode::new(label.to_string(), value.to_string(), *next_id);
+    *next_id += 1;
+    node
+}
+
+fn value_label<'a>(value: &Value<'a, &'a str>) -> &'static str {
+    match value {
+        Value::Variable(_) => "Variable",
+        Value::Int(_) | Value::Float(_) => "Number",
+        Value::String(_) => "String",
+        Value::Boolean(_) => "Boolean",
+        Value::Null => "Null",
+        Value::Enum(_) => "Enum",
+        Value::List(_) => "List",
+        Value::Object(_) => "Object",
+    }
+}
+
+/// A pair of similar operations or fragments
+#[derive(Debug, Clone)]
+pub struct GraphqlDuplicate<'a> {
+    pub first: &'a GraphqlDefinition,
+    pub second: &'a GraphqlDefinition,
+    pub similarity: f64,
+    /// Field paths selected by both
+    pub shared_fields: usize,
+}
+
+/// Find pairs of definitions of the same kind at least `threshold` similar,
+/// most similar first. Documents are short, so the size penalty of `options`
+/// is not applied.
+pub fn find_similar_graphql<'a>(
+    definitions: &'a [GraphqlDefinition],
+    threshold: f64,
+    options: &TSEDOptions,
+) -> Vec<GraphqlDuplicate<'a>> {
+    let options = TSEDOptions { size_penalty: false, ..options.clone() };
+    let candidates: Vec<&GraphqlDefinition> = definitions
+        .iter()
+        .filter(|definition| match options.min_tokens {
+            Some(min_tokens) => definition.tree.get_subtree_size() as u32 >= min_tokens,
+            None => definition.line_count() >= options.min_lines,
+        })
+        .collect();
+
+    let mut duplicates = Vec::new();
+    for (i, first) in candidates.iter().enumerate() {
+        for second in &candidates[i + 1..] {
+            if first.kind != second.kind {
+                continue;
+            }
+            let similarity = calculate_tsed(&first.tree, &second.tree, &options);
+            if similarity >= threshold {
+                let shared_fields = first.fields.intersection(&second.fields).count();
+                duplicates.push(GraphqlDuplicate { first, second, similarity, shared_fields });
+            }
+        }
+    }
+    duplicates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    duplicates
+}
+
+fn definition_label(definition: &GraphqlDefinition) -> String {
+    format_function_output(
+        &definition.file_path,
+        &format!("{} {}", definition.kind, definition.name),
+        definition.start_line,
+        definition.end_line,
+    )
+}
+
+/// Print similar operations and fragments; returns the number of pairs
+pub fn report_similar_graphql(duplicates: &[GraphqlDuplicate], print: bool) -> usize {
+    if duplicates.is_empty() {
+        println!("\nNo duplicate GraphQL documents found!");
+        return 0;
+    }
+
+    println!("\nDuplicate GraphQL operations and fragments:");
+    println!("{}", "-".repeat(60));
+    for duplicate in duplicates {
+        println!("\nSimilarity: {:.2}%", duplicate.similarity * 100.0);
+        println!("  {}", definition_label(duplicate.first));
+        println!("  {}", definition_label(duplicate.second));
+        println!(
+            "  Shared selections: {} of {}/{} fields",
+            duplicate.shared_fields,
+            duplicate.first.fields.len(),
+            duplicate.second.fields.len()
+        );
+
+        if print {
+            for definition in [duplicate.first, duplicate.second] {
+                println!(
+                    "\n\x1b[36m--- {}:{} ---\x1b[0m\n{}",
+                    definition.file_path, definition.name, definition.source
+                );
+            }
+        }
+    }
+    println!("\nTotal duplicate GraphQL pairs found: {}", duplicates.len());
+
+    duplicates.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"import { gql } from "@apollo/client";
+
+export const GET_USER = gql`
+  query GetUser($id: ID!) {
+    user(id: $id) {
+      id
+      name
+      avatar { url }
+    }
+  }
+`;
+
+const GET_PROFILE = graphql`
+  query GetProfile($id: ID!) {
+    user(id: $id) {
+      avatar { url }
+      name
+      id
+    }
+  }
+  ${USER_FIELDS}
+`;
+
+const label = `not ${graphql} a query`;
+
+export const USER_FIELDS = gql`
+  fragment UserFields on User {
+    id
+    ...Avatar
+  }
+`;
+"#;
+
+    #[test]
+    fn test_extract_graphql() {
+        let definitions = extract_graphql("queries.ts", SOURCE).unwrap();
+        let summary: Vec<(GraphqlKind, &str, u32, u32)> = definitions
+            .iter()
+            .map(|d| (d.kind, d.name.as_str(), d.start_line, d.end_line))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (GraphqlKind::Query, "GetUser", 4, 10),
+                (GraphqlKind::Query, "GetProfile", 14, 20),
+                (GraphqlKind::Fragment, "UserFields on User", 27, 30),
+            ]
+        );
+        assert_eq!(
+            definitions[0].fields.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["user", "user.avatar", "user.avatar.url", "user.id", "user.name"]
+        );
+        assert!(definitions[0].source.trim_start().starts_with("query GetUser"));
+
+        assert!(extract_graphql("broken.ts", "const q = gql`query {`;").is_err());
+    }
+
+    #[test]
+    fn test_find_similar_graphql() {
+        let definitions = extract_graphql("queries.ts", SOURCE).unwrap();
+        let duplicates = find_similar_graphql(&definitions, 0.9, &TSEDOptions::default());
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(
+            (duplicates[0].first.name.as_str(), duplicates[0].second.name.as_str()),
+            ("GetUser", "GetProfile")
+        );
+        // Selections in another order are the same selections
+        assert_eq!(duplicates[0].similarity, 1.0);
+        assert_eq!(duplicates[0].shared_fields, 5);
+    }
+}
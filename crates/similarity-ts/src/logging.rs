@@ -0,0 +1,35 @@
+//! Diagnostic logging to stderr.
+//!
+//! Reports stay on stdout; `--verbose` adds skipped files, parse failures and
+//! per-phase timings on stderr, and `--debug` adds every pruned comparison.
+
+use clap::ValueEnum;
+use std::io::IsTerminal;
+use tracing::Level;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per line, for capturing in CI
+    Json,
+}
+
+/// Install the global subscriber; warnings only unless `verbose` or `debug`
+pub fn init(verbose: bool, debug: bool, format: LogFormat) {
+    let level = if debug {
+        Level::DEBUG
+    } else if verbose {
+        Level::INFO
+    } else {
+        Level::WARN
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(level)
+        .with_target(false)
+        .with_ansi(std::io::stderr().is_terminal());
+    match format {
+        LogFormat::Text => builder.without_time().init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
@@ -1,27 +1,84 @@
 #![allow(clippy::uninlined_format_args)]
 
-use clap::Parser;
+use check::{FindingOutput, GroupBy, OutputFormat, PrintLayout, Scope};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use similarity_core::{
+    analyzer::{run_analyzer_with_budget, Analyzer},
+    builtin_injections, check_writable,
+    cli_file_utils::{collect_all_files, collect_files},
+    default_cache_dir, diff_reports, evaluate, fetch_repository, find_similar_directories,
+    find_similar_files, finding_json_schema, format_reference_timings, heatmap_json_schema,
+    load_findings, merge_reports, parse_duration, parse_findings, render_pr_comment, search_source,
+    BlobLinks, BoilerplateSet, ComparisonBudget, ConfigLoader, Dataset, DuplicationHeatmap,
+    FileFingerprint, FormatterRegistry, FunctionIndex, MatrixExporter, MemberComparisonStrategy,
+    NodeWeightConfig, ParserBackend, PenaltyCurve, Preset, PresetAnalyzer, PresetConfig,
+    RemoteSpec, ReportFinding, RunReport, Severity, SeverityConfig, SeverityPolicy, Shard,
+    SizePenalty, SizePenaltyConfig, SkipReason, StructuralPattern, Suppressions, TSEDOptions,
+    TriageState, TriageStore, DEFAULT_BOILERPLATE_PATH, DEFAULT_MATRIX_FLOOR,
+    DEFAULT_MAX_TREE_NODES, DEFAULT_MIN_FILE_TOKENS, DEFAULT_SESSION_DIR, DEFAULT_TRIAGE_PATH,
+    INJECTION_HOST_EXTENSIONS, PARSER_SAMPLES,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 mod check;
+mod daemon;
+mod graphql;
+mod logging;
+mod migration;
 pub mod parallel;
+mod serve;
+mod templates;
 
 #[derive(Parser)]
 #[command(name = "similarity-ts")]
 #[command(about = "TypeScript/JavaScript code similarity analyzer")]
 #[command(version)]
+#[command(disable_version_flag = true)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
-    /// Paths to analyze (files or directories)
-    #[arg(default_value = ".")]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Print version; with --verbose, also reference timings for estimating runtime
+    #[arg(short = 'V', long)]
+    version: bool,
+
+    /// Paths to analyze (files or directories). Defaults to "." unless --git is given
     paths: Vec<String>,
 
+    /// Shallow-clone a repository (URL[#BRANCH]) and analyze it together with the given paths (can be specified multiple times)
+    #[arg(long = "git", value_name = "URL[#BRANCH]")]
+    git: Vec<String>,
+
+    /// Keep --git clones in ~/.cache/similarity/repos and update them on the next run instead of using a temporary directory
+    #[arg(long, requires = "git")]
+    git_cache: bool,
+
     /// Print code in output
     #[arg(short, long)]
     print: bool,
 
+    /// How --print shows function pairs: auto (side-by-side on wide terminals, unified on
+    /// narrow ones, sequential when piped), sequential, side-by-side or unified
+    #[arg(long, value_enum, default_value = "auto")]
+    print_layout: PrintLayout,
+
+    /// Bundled threshold, minimum lines, excludes and analyzers for a project type: react,
+    /// node-api or design-system. Keys of similarity.toml and explicit flags override it
+    #[arg(long, value_name = "PRESET")]
+    preset: Option<Preset>,
+
     /// Similarity threshold (0.0-1.0)
     #[arg(short, long, default_value = "0.87")]
     threshold: f64,
 
+    /// Ignore the threshold and report the N most similar function pairs, ranked
+    #[arg(long, value_name = "N")]
+    top: Option<usize>,
+
     /// Disable function similarity checking
     #[arg(long = "no-functions")]
     no_functions: bool,
@@ -50,6 +107,14 @@ struct Cli {
     #[arg(long)]
     include_implements: bool,
 
+    /// Compare classes as if they had no decorators (@Component, @Input, ...)
+    #[arg(long)]
+    ignore_decorators: bool,
+
+    /// Only compare classes carrying the same class decorators, e.g. components with components
+    #[arg(long)]
+    same_decorators_only: bool,
+
     /// Show refactoring suggestions for excluded classes
     #[arg(long)]
     suggest: bool,
@@ -66,6 +131,10 @@ struct Cli {
     #[arg(long)]
     min_tokens: Option<u32>,
 
+    /// Only report function duplicates whose cyclomatic complexity is at least N
+    #[arg(long)]
+    min_complexity: Option<u32>,
+
     /// Rename cost for APTED algorithm
     #[arg(short, long, default_value = "0.3")]
     rename_cost: f64,
@@ -74,6 +143,30 @@ struct Cli {
     #[arg(long)]
     no_size_penalty: bool,
 
+    #[command(flatten)]
+    size_penalty: SizePenaltyArgs,
+
+    /// Keep comments in the compared function trees (stripped by default)
+    #[arg(long)]
+    include_comments: bool,
+
+    /// Compare trees above this many nodes by node label counts instead of tree edit distance (0: never)
+    #[arg(long, default_value_t = DEFAULT_MAX_TREE_NODES)]
+    max_tree_nodes: usize,
+
+    /// Put independent declarations into a canonical order before comparing, so reordered clones match
+    #[arg(long)]
+    canonicalize_order: bool,
+
+    /// Compare .then() chains and error-first callbacks as the equivalent async/await code
+    #[arg(long)]
+    normalize_async: bool,
+
+    /// Compare loops that build arrays or accumulate values as the equivalent map, filter or
+    /// reduce call, so the same transformation written in either style matches
+    #[arg(long)]
+    normalize_collections: bool,
+
     /// Filter functions by name (substring match)
     #[arg(long)]
     filter_function: Option<String>,
@@ -110,6 +203,10 @@ struct Cli {
     #[arg(long, default_value = "0.4")]
     naming_weight: f64,
 
+    /// Show an aligned property diff for each similar type pair
+    #[arg(long)]
+    diff: bool,
+
     /// Only check type literals (excludes type aliases and interfaces)
     #[arg(long)]
     type_literals_only: bool,
@@ -146,143 +243,2890 @@ struct Cli {
     #[arg(long, default_value = "0.25")]
     overlap_size_tolerance: f64,
 
+    /// Compare code cells of JavaScript/TypeScript Jupyter notebooks (.ipynb)
+    #[arg(long)]
+    notebooks: bool,
+
+    /// Compare custom React hooks (functions named useXxx) by state shape, dependency arrays
+    /// and effect bodies
+    #[arg(long)]
+    hooks: bool,
+
+    /// Compare test code: describe/it/test blocks and setup hooks, reported separately from
+    /// production code
+    #[arg(long)]
+    tests: bool,
+
+    /// Similarity threshold for --tests (0.0-1.0, defaults to --threshold)
+    #[arg(long)]
+    test_threshold: Option<f64>,
+
+    /// Compare GraphQL queries and fragments of gql`...`/graphql`...` tagged templates
+    #[arg(long)]
+    graphql: bool,
+
+    /// Compare functions in the <script> blocks of .ejs, .hbs, .erb and .html templates
+    #[arg(long)]
+    templates: bool,
+
+    /// Compare the markup of html`...`/svg`...` and the queries of sql`...` tagged templates
+    #[arg(long)]
+    injections: bool,
+
+    /// Report local types duplicating, or re-declaring part of, a type of these packages, read
+    /// from the .d.ts files in node_modules (or of their @types package), e.g. axios,zod
+    #[arg(long, value_delimiter = ',', value_name = "PACKAGE")]
+    against_deps: Vec<String>,
+
+    /// Only run a quick whole-file pass reporting near-duplicate files of any language
+    /// (all files unless --extensions is given)
+    #[arg(long)]
+    files_only: bool,
+
+    /// Write the fingerprints of every function to this index file and exit, so other
+    /// repositories can be checked against it with --against-index
+    #[arg(long, value_name = "PATH")]
+    export_index: Option<String>,
+
+    /// Only compare functions against those of an index written by --export-index
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["export_index", "files_only"])]
+    against_index: Option<String>,
+
+    /// Only match JavaScript functions with their TypeScript versions, comparing with types
+    /// stripped, and list the JavaScript files whose functions were all migrated
+    #[arg(long, conflicts_with_all = ["export_index", "files_only", "against_index"])]
+    migration: bool,
+
+    /// Index the functions of the paths, then answer JSON-RPC lint requests on stdin/stdout
+    /// until shut down, for editor and ESLint plugin integrations
+    #[arg(long, conflicts_with_all = ["export_index", "files_only", "against_index", "migration"])]
+    daemon: bool,
+
     /// Exit with code 1 if duplicates are found
     #[arg(long)]
     fail_on_duplicates: bool,
 
+    /// Exit with code 1 if a finding reaches this severity (info, warning, error). Severities
+    /// come from the [[severity]] rules of similarity.toml; without rules findings are warnings
+    #[arg(long, value_name = "LEVEL", conflicts_with = "fail_on_duplicates")]
+    fail_on: Option<Severity>,
+
+    /// Exit with an error if any file fails to parse
+    #[arg(long)]
+    strict: bool,
+
     /// Use new generalized structure comparison framework (experimental)
     #[arg(long)]
     use_structure_comparison: bool,
+
+    /// How the structure comparison compares member types: exact, normalized (default) or
+    /// semantic, which resolves aliases, containers, numeric families and the
+    /// [type_synonyms] of similarity.toml
+    #[arg(long, value_name = "STRATEGY", requires = "use_structure_comparison")]
+    member_comparison: Option<MemberComparisonStrategy>,
+
+    /// Output format: standard, pr-comment (Markdown summary for a pull-request
+    /// comment), or a registered formatter: text, vscode (one line per finding,
+    /// matched by the default VSCode problem matcher) or json (one object per
+    /// line, see `similarity-ts schema --document finding`)
+    #[arg(short, long, value_name = "FORMAT", default_value = "standard")]
+    output: String,
+
+    /// With --output pr-comment: `--output json` results of the base branch; only
+    /// findings missing there are listed as new
+    #[arg(long, value_name = "PATH")]
+    base_results: Option<PathBuf>,
+
+    /// With --output pr-comment: repository the finding links point into. Defaults to
+    /// the repository of the GitHub Actions or GitLab CI job
+    #[arg(long, value_name = "URL", requires = "head_ref")]
+    repo_url: Option<String>,
+
+    /// With --output pr-comment: commit, branch or tag the finding links point at
+    #[arg(long, value_name = "REF", requires = "repo_url")]
+    head_ref: Option<String>,
+
+    /// Export per-file duplication density (heatmap JSON, see `similarity-ts schema`) to the given path
+    #[arg(long, value_name = "PATH")]
+    export: Option<String>,
+
+    /// Stream every compared function and type pair scoring at least --matrix-floor to this
+    /// CSV edge list (kind, file, name and lines of both sides, similarity), for clustering
+    /// and visualization with other tools
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["top", "resume"])]
+    export_matrix: Option<PathBuf>,
+
+    /// Lowest similarity written by --export-matrix; pairs below the threshold are included
+    #[arg(long, value_name = "SIMILARITY", default_value_t = DEFAULT_MATRIX_FLOOR, requires = "export_matrix")]
+    matrix_floor: f64,
+
+    /// Show git blame provenance for duplicate functions (which copy is older, who added the newer one)
+    #[arg(long)]
+    blame: bool,
+
+    /// Print unified diffs extracting near-identical (>97%) function pairs into a shared module,
+    /// pairs with compatible parameter and return types first
+    #[arg(long)]
+    suggest_patch: bool,
+
+    /// Only report function duplicates whose files neither import each other nor share a directory
+    #[arg(long)]
+    only_unrelated_modules: bool,
+
+    /// Report duplicates within packages (per-package), across packages (cross-package) or all.
+    /// Packages are directories with a package.json or Cargo.toml
+    #[arg(long, value_enum, default_value = "all")]
+    scope: Scope,
+
+    /// Group function findings by CODEOWNERS owner
+    #[arg(long, value_enum)]
+    group_by: Option<GroupBy>,
+
+    /// Stop comparing functions after this long (e.g. 300s, 5m) and report partial results
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Compare at most this many candidate pairs across functions, types and classes. Cross-file
+    /// function pairs sharing a size and arity bucket are kept and the others are sampled
+    #[arg(long, value_name = "N")]
+    max_comparisons: Option<usize>,
+
+    /// Only run this shard of the function comparisons, e.g. 2/8 in the second of eight CI jobs.
+    /// Pairs are assigned by a stable hash, and the other analyzers run in shard 1; combine the
+    /// --output json results with `merge-results`
+    #[arg(
+        long,
+        value_name = "INDEX/COUNT",
+        conflicts_with_all = ["files_only", "against_index", "migration"]
+    )]
+    shard: Option<Shard>,
+
+    /// Checkpoint function analysis progress in this directory (default: .similarity/session),
+    /// so an interrupted run can be continued with --resume
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        default_missing_value = DEFAULT_SESSION_DIR,
+        conflicts_with_all = ["top", "max_comparisons"]
+    )]
+    session: Option<PathBuf>,
+
+    /// Continue the run checkpointed in the --session directory instead of starting over
+    #[arg(long, requires = "session")]
+    resume: bool,
+
+    /// Only report function findings in these triage states (new, wontfix, accepted, fixme)
+    #[arg(long, value_delimiter = ',', value_name = "STATE")]
+    triage_state: Vec<TriageState>,
+
+    /// Learn the functions in this directory as boilerplate (saved to .similarity/boilerplate.json);
+    /// duplicates matching learned boilerplate are not reported
+    #[arg(long, value_name = "DIR")]
+    boilerplate_dir: Option<String>,
+
+    /// Log skipped files, parse failures and phase timings to stderr
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Also log every pruned comparison (implies --verbose)
+    #[arg(long)]
+    debug: bool,
+
+    /// Log format (text, json)
+    #[arg(long, value_enum, default_value = "text")]
+    log_format: logging::LogFormat,
+}
+
+/// Curve of the penalty for code of different sizes; overrides `[size_penalty]` of similarity.toml
+#[derive(clap::Args)]
+struct SizePenaltyArgs {
+    /// Size ratio penalty curve: builtin, none, linear or quadratic
+    #[arg(long = "size-penalty-curve", value_name = "CURVE")]
+    curve: Option<PenaltyCurve>,
+
+    /// Lowest factor the size ratio penalty may yield (0.0-1.0)
+    #[arg(long = "size-penalty-floor", value_name = "FLOOR")]
+    floor: Option<f64>,
+}
+
+impl SizePenaltyArgs {
+    fn resolve(&self) -> SizePenalty {
+        let mut penalty = SizePenaltyConfig::find_and_load().size_penalty;
+        if let Some(curve) = self.curve {
+            penalty.curve = curve;
+        }
+        if let Some(floor) = self.floor {
+            penalty.floor = floor;
+        }
+        penalty
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Record triage decisions for findings in .similarity/triage.json
+    Triage {
+        #[command(subcommand)]
+        action: TriageAction,
+    },
+    /// Report precision and recall per clone type on a labeled dataset of clone pairs
+    Eval {
+        /// Directory holding pairs.toml, or the manifest itself
+        #[arg(long)]
+        dataset: std::path::PathBuf,
+
+        /// Similarity threshold (0.0-1.0)
+        #[arg(short, long, default_value = "0.87")]
+        threshold: f64,
+
+        /// Rename cost for APTED algorithm
+        #[arg(short, long, default_value = "0.3")]
+        rename_cost: f64,
+
+        /// Disable size penalty for very different sized functions
+        #[arg(long)]
+        no_size_penalty: bool,
+
+        #[command(flatten)]
+        size_penalty: SizePenaltyArgs,
+    },
+    /// Print the schema of the JSON written by --export or --output json
+    Schema {
+        #[arg(long, value_enum, default_value = "json-schema")]
+        format: SchemaFormat,
+
+        /// Which document to describe
+        #[arg(long, value_enum, default_value = "heatmap")]
+        document: SchemaDocument,
+    },
+    /// Check new code against existing functions before adding it; exits with 1
+    /// when a similar function already exists and 2 on errors
+    CheckSnippet(SnippetArgs),
+    /// Print every function of a file with the number of similar functions and the
+    /// most similar one, as JSON for editor code lenses; exits with 2 on errors
+    Lens(LensArgs),
+    /// Serve the warm function index over HTTP: `POST /analyze`, `GET /findings` and
+    /// `POST /query-snippet`, answered in JSON
+    Serve(ServeArgs),
+    /// Find functions structurally matching the functions of a pattern file, in
+    /// which `__ANY__` identifiers are holes; exits with 1 when none matches and
+    /// 2 on errors, like grep
+    Grep(GrepArgs),
+    /// Compare two `--output json` result files: new and resolved findings and
+    /// score changes; exits with 2 on errors
+    ReportDiff {
+        /// Results of the earlier run, e.g. of the base branch
+        old: std::path::PathBuf,
+
+        /// Results of the later run
+        new: std::path::PathBuf,
+
+        /// Print the difference as one JSON object
+        #[arg(long)]
+        json: bool,
+
+        /// Exit with 1 when the later run has findings the earlier one did not
+        #[arg(long)]
+        fail_on_new: bool,
+    },
+    /// Merge the `--output json` results of the shards of a `--shard` run into one report,
+    /// printed as JSON lines with each finding once; exits with 2 on errors
+    MergeResults {
+        /// Result files of the shards
+        #[arg(required = true)]
+        results: Vec<std::path::PathBuf>,
+    },
+    /// Check the parsers and directories of this build and print the effective settings
+    /// of a run with the given flags, e.g. `doctor --preset react src`; exits with 1 when
+    /// a check fails
+    Doctor {
+        /// Paths and flags of the run to describe
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(clap::Args)]
+struct GrepArgs {
+    /// File holding the pattern functions
+    #[arg(long)]
+    pattern: std::path::PathBuf,
+
+    /// Paths to search
+    #[arg(default_value = ".")]
+    paths: Vec<String>,
+
+    /// Lowest similarity to the pattern (0.0-1.0)
+    #[arg(short, long, default_value = "0.87")]
+    threshold: f64,
+
+    /// Cost of a name or literal differing from the pattern; lower it to match renamed code
+    #[arg(short, long, default_value = "1.0")]
+    rename_cost: f64,
+
+    /// File extensions to search
+    #[arg(short, long, value_delimiter = ',')]
+    extensions: Option<Vec<String>>,
+
+    /// Exclude directories matching the given patterns
+    #[arg(long)]
+    exclude: Vec<String>,
+}
+
+#[derive(clap::Args)]
+struct SnippetArgs {
+    /// File holding the snippet
+    #[arg(required_unless_present = "stdin", conflicts_with = "stdin")]
+    file: Option<std::path::PathBuf>,
+
+    /// Read the snippet from standard input
+    #[arg(long)]
+    stdin: bool,
+
+    /// File name the snippet read from standard input is parsed as
+    #[arg(long, default_value = "snippet.ts")]
+    stdin_filename: String,
+
+    /// Directory or file to search, or a function index written by --export-index
+    #[arg(long, default_value = ".")]
+    against: String,
+
+    /// Exclude directories matching the given patterns when searching a directory
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// Similarity threshold (0.0-1.0)
+    #[arg(short, long, default_value = "0.87")]
+    threshold: f64,
+
+    /// Rename cost for APTED algorithm
+    #[arg(short, long, default_value = "0.3")]
+    rename_cost: f64,
+
+    /// Minimum lines for snippet functions to be checked
+    #[arg(long, default_value = "3")]
+    min_lines: u32,
+
+    /// Disable size penalty for very different sized functions
+    #[arg(long)]
+    no_size_penalty: bool,
+
+    #[command(flatten)]
+    size_penalty: SizePenaltyArgs,
+}
+
+#[derive(clap::Args)]
+struct LensArgs {
+    /// File whose functions get lenses
+    file: std::path::PathBuf,
+
+    /// Directory or file to search, or a function index written by --export-index
+    #[arg(long, default_value = ".")]
+    against: String,
+
+    /// Exclude directories matching the given patterns when searching a directory
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// Similarity threshold (0.0-1.0)
+    #[arg(short, long, default_value = "0.87")]
+    threshold: f64,
+
+    /// Rename cost for APTED algorithm
+    #[arg(short, long, default_value = "0.3")]
+    rename_cost: f64,
+
+    /// Minimum lines for functions to get a lens
+    #[arg(long, default_value = "3")]
+    min_lines: u32,
+
+    /// Disable size penalty for very different sized functions
+    #[arg(long)]
+    no_size_penalty: bool,
+
+    #[command(flatten)]
+    size_penalty: SizePenaltyArgs,
+}
+
+#[derive(clap::Args)]
+struct ServeArgs {
+    /// Paths to index
+    #[arg(default_value = ".")]
+    paths: Vec<String>,
+
+    /// Port to listen on
+    #[arg(long, default_value = "8080")]
+    port: u16,
+
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Token every request must send as `Authorization: Bearer <token>`
+    /// (default: $SIMILARITY_SERVE_TOKEN)
+    #[arg(long)]
+    token: Option<String>,
+
+    /// Number of requests answered at the same time (default: number of CPUs)
+    #[arg(long)]
+    workers: Option<usize>,
+
+    /// File extensions to index
+    #[arg(short, long, value_delimiter = ',')]
+    extensions: Option<Vec<String>>,
+
+    /// Exclude directories matching the given patterns
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// Similarity threshold (0.0-1.0)
+    #[arg(short, long, default_value = "0.87")]
+    threshold: f64,
+
+    /// Rename cost for APTED algorithm
+    #[arg(short, long, default_value = "0.3")]
+    rename_cost: f64,
+
+    /// Minimum lines for functions to be compared
+    #[arg(long, default_value = "3")]
+    min_lines: u32,
+
+    /// Disable size penalty for very different sized functions
+    #[arg(long)]
+    no_size_penalty: bool,
+
+    #[command(flatten)]
+    size_penalty: SizePenaltyArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum SchemaFormat {
+    /// JSON Schema (draft 2020-12)
+    JsonSchema,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum SchemaDocument {
+    /// The duplication heatmap written by --export
+    Heatmap,
+    /// One finding line written by --output json
+    Finding,
+}
+
+#[derive(Subcommand)]
+enum TriageAction {
+    /// Set the state of a finding by its ID (`new` clears a previous decision)
+    Mark {
+        /// Finding ID shown in the report
+        id: String,
+
+        /// wontfix, accepted, fixme or new
+        state: TriageState,
+
+        /// Reason for the decision
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// List triaged findings
+    List,
+}
+
+fn run_triage(action: TriageAction) -> anyhow::Result<()> {
+    let mut store = TriageStore::load(DEFAULT_TRIAGE_PATH)?;
+    match action {
+        TriageAction::Mark { id, state, note } => {
+            store.mark(&id, state, note);
+            store.save()?;
+            println!("Marked {} as {}", id, state);
+        }
+        TriageAction::List => {
+            if store.entries().next().is_none() {
+                println!("No triaged findings.");
+            }
+            for (id, entry) in store.entries() {
+                match &entry.note {
+                    Some(note) => println!("{}  {:<8}  {}", id, entry.state, note),
+                    None => println!("{}  {}", id, entry.state),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_eval(
+    dataset: &std::path::Path,
+    threshold: f64,
+    rename_cost: f64,
+    no_size_penalty: bool,
+    size_ratio_penalty: SizePenalty,
+) -> anyhow::Result<()> {
+    let dataset = Dataset::load(dataset)?;
+    let mut options = TSEDOptions::default();
+    options.apted_options.rename_cost = rename_cost;
+    options.size_penalty = !no_size_penalty;
+    options.size_ratio_penalty = size_ratio_penalty;
+    options.node_weights = NodeWeightConfig::find_and_load().node_weights;
+
+    let report = evaluate(&dataset, threshold, &options)?;
+    println!("{}", report);
+
+    let misclassified: Vec<_> = report.misclassified().collect();
+    if !misclassified.is_empty() {
+        println!("\nMisclassified pairs:");
+        for result in misclassified {
+            println!(
+                "  {:<6} {:>6.2}%  {} <-> {}",
+                result.pair.clone,
+                result.similarity * 100.0,
+                result.pair.first.display(),
+                result.pair.second.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Print existing functions similar to the snippet's, one tab-separated line
+/// per snippet function: similarity, snippet function and lines, existing
+/// location and function. Returns whether any was found.
+fn run_check_snippet(args: &SnippetArgs) -> anyhow::Result<bool> {
+    let (snippet_name, snippet) = match &args.file {
+        Some(file) => (
+            file.to_string_lossy().to_string(),
+            std::fs::read_to_string(file)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file.display(), e))?,
+        ),
+        None => {
+            let mut snippet = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut snippet)?;
+            (args.stdin_filename.clone(), snippet)
+        }
+    };
+
+    // The snippet's own file must not match itself
+    let index = load_or_build_index(&args.against, &args.exclude, args.file.as_deref())?;
+    let options = index_query_options(
+        args.rename_cost,
+        args.no_size_penalty,
+        &args.size_penalty,
+        args.min_lines,
+    );
+
+    let mut matches = index
+        .find_matches(
+            &snippet_name,
+            &snippet,
+            args.threshold,
+            &options,
+            &ComparisonBudget::unlimited(),
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", snippet_name, e))?;
+    matches.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    for found in &matches {
+        println!(
+            "{:.4}\t{}\t{}-{}\t{}:{}-{}\t{}",
+            found.similarity,
+            found.function.name,
+            found.function.start_line,
+            found.function.end_line,
+            found.indexed.file,
+            found.indexed.start_line,
+            found.indexed.end_line,
+            found.indexed.name
+        );
+    }
+    Ok(!matches.is_empty())
+}
+
+/// The index file `against`, or an index of the sources under it leaving out `skip`
+fn load_or_build_index(
+    against: &str,
+    exclude: &[String],
+    skip: Option<&std::path::Path>,
+) -> anyhow::Result<FunctionIndex> {
+    let path = std::path::Path::new(against);
+    let is_source = |path: &std::path::Path| {
+        path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| {
+            ["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"].contains(&ext)
+        })
+    };
+    if path.is_file() && !is_source(path) {
+        return FunctionIndex::load(path);
+    }
+
+    let skip = skip.and_then(|file| file.canonicalize().ok());
+    let mut index = FunctionIndex::new();
+    for (file_path, content) in
+        collect_sources(std::slice::from_ref(&against.to_string()), None, exclude, None)?
+    {
+        let path = std::path::Path::new(&file_path).canonicalize().ok();
+        if path.is_some() && path == skip {
+            continue;
+        }
+        if let Err(e) = index.add_source(&file_path, &content) {
+            tracing::info!(file = %file_path, error = %e, "skipping file");
+        }
+    }
+    Ok(index)
+}
+
+/// Options of the queries against an index (check-snippet, lens)
+fn index_query_options(
+    rename_cost: f64,
+    no_size_penalty: bool,
+    size_penalty: &SizePenaltyArgs,
+    min_lines: u32,
+) -> TSEDOptions {
+    let mut options = TSEDOptions::default();
+    options.apted_options.rename_cost = rename_cost;
+    options.size_penalty = !no_size_penalty;
+    options.size_ratio_penalty = size_penalty.resolve();
+    options.node_weights = NodeWeightConfig::find_and_load().node_weights;
+    options.min_lines = min_lines;
+    options
+}
+
+/// Index the served paths and answer HTTP requests until the process is stopped
+fn run_serve(args: &ServeArgs) -> anyhow::Result<()> {
+    let options = index_query_options(
+        args.rename_cost,
+        args.no_size_penalty,
+        &args.size_penalty,
+        args.min_lines,
+    );
+    let token = args
+        .token
+        .clone()
+        .or_else(|| std::env::var(serve::SERVE_TOKEN_ENV).ok())
+        .filter(|token| !token.is_empty());
+    let (paths, extensions, exclude) =
+        (args.paths.clone(), args.extensions.clone(), args.exclude.clone());
+    let load: serve::SourceLoader =
+        Box::new(move || collect_sources(&paths, extensions.as_ref(), &exclude, None));
+    let server = serve::Server::new(load, args.threshold, options, token.clone())?;
+
+    let listener = std::net::TcpListener::bind((args.host.as_str(), args.port))
+        .map_err(|e| anyhow::anyhow!("Failed to listen on {}:{}: {}", args.host, args.port, e))?;
+    let address = listener.local_addr()?;
+    if token.is_none() && !address.ip().is_loopback() {
+        eprintln!(
+            "Warning: serving on {} without a token; set --token or {}",
+            address,
+            serve::SERVE_TOKEN_ENV
+        );
+    }
+    let (files, functions) = server.indexed();
+    eprintln!(
+        "Indexed {} function(s) from {} file(s), listening on http://{}",
+        functions, files, address
+    );
+    let workers = args
+        .workers
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(4, |count| count.get()));
+    std::sync::Arc::new(server).serve(listener, workers)?;
+    Ok(())
+}
+
+/// Print the lenses of a file's functions as one JSON object
+fn run_lens(args: &LensArgs) -> anyhow::Result<()> {
+    let file = args.file.to_string_lossy().to_string();
+    let source = std::fs::read_to_string(&args.file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file, e))?;
+    // The file's current functions stand in for its indexed ones
+    let index = load_or_build_index(&args.against, &args.exclude, Some(&args.file))?;
+    let options = index_query_options(
+        args.rename_cost,
+        args.no_size_penalty,
+        &args.size_penalty,
+        args.min_lines,
+    );
+
+    let lenses = index
+        .lens(&file, &source, args.threshold, &options, &ComparisonBudget::unlimited())
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", file, e))?;
+    println!("{}", daemon::lens_json(&file, &lenses));
+    Ok(())
+}
+
+/// Print the functions matching a pattern as `file:start-end name (similarity)`,
+/// most similar first; returns whether any matched
+fn run_grep(args: &GrepArgs) -> anyhow::Result<bool> {
+    let pattern_name = args.pattern.to_string_lossy().to_string();
+    let pattern_source = std::fs::read_to_string(&args.pattern)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", pattern_name, e))?;
+    let patterns = StructuralPattern::parse(&pattern_name, &pattern_source)
+        .map_err(|e| anyhow::anyhow!("Invalid pattern {}: {}", pattern_name, e))?;
+
+    let mut options = TSEDOptions::default().apted_options;
+    options.rename_cost = args.rename_cost;
+    let pattern_path = args.pattern.canonicalize().ok();
+
+    let mut matches = Vec::new();
+    for (file_path, content) in
+        collect_sources(&args.paths, args.extensions.as_ref(), &args.exclude, None)?
+    {
+        if std::path::Path::new(&file_path).canonicalize().ok() == pattern_path {
+            continue;
+        }
+        match search_source(&patterns, &file_path, &content, args.threshold, &options) {
+            Ok(found) => matches.extend(found.into_iter().map(|found| (file_path.clone(), found))),
+            Err(e) => tracing::info!(file = %file_path, error = %e, "skipping file"),
+        }
+    }
+    matches.sort_by(|(_, a), (_, b)| b.similarity.total_cmp(&a.similarity));
+
+    for (file_path, found) in &matches {
+        let pattern = if patterns.len() > 1 {
+            format!(", pattern {}", found.pattern.name)
+        } else {
+            String::new()
+        };
+        println!(
+            "{}:{}-{} {} ({:.2}%{})",
+            file_path,
+            found.function.start_line,
+            found.function.end_line,
+            found.function.name,
+            found.similarity * 100.0,
+            pattern
+        );
+    }
+    Ok(!matches.is_empty())
+}
+
+/// Print the findings of the result files of all shards, each once
+fn run_merge_results(results: &[PathBuf]) -> anyhow::Result<()> {
+    let reports = results.iter().map(|path| load_findings(path)).collect::<anyhow::Result<_>>()?;
+    let merged = merge_reports(reports);
+    for finding in &merged {
+        println!("{}", finding.value);
+    }
+    eprintln!("Merged {} findings from {} result files", merged.len(), results.len());
+    Ok(())
+}
+
+/// Print the findings introduced, resolved and rescored between two result
+/// files; returns whether any was introduced
+fn run_report_diff(old: &Path, new: &Path, json: bool) -> anyhow::Result<bool> {
+    let diff = diff_reports(load_findings(old)?, load_findings(new)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff.to_json())?);
+        return Ok(!diff.introduced.is_empty());
+    }
+
+    println!("{}", diff.summary());
+    let location = |finding: &ReportFinding| format!("{}:{}", finding.file, finding.line);
+    for (heading, findings) in [("New", &diff.introduced), ("Resolved", &diff.resolved)] {
+        if !findings.is_empty() {
+            println!("\n{}:", heading);
+        }
+        for finding in findings {
+            println!("  {} {}", location(finding), finding.message);
+        }
+    }
+    if !diff.changed.is_empty() {
+        println!("\nScore changes:");
+    }
+    let percent = |finding: &ReportFinding| finding.similarity.unwrap_or(0.0) * 100.0;
+    for change in &diff.changed {
+        println!(
+            "  {} {:.0}% -> {:.0}% {}",
+            location(&change.new),
+            percent(&change.old),
+            percent(&change.new),
+            change.new.message
+        );
+    }
+    Ok(!diff.introduced.is_empty())
 }
 
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+    if cli.version {
+        println!("similarity-ts {}", env!("CARGO_PKG_VERSION"));
+        if cli.verbose {
+            print!("\n{}", format_reference_timings());
+        }
+        return Ok(());
+    }
+    logging::init(cli.verbose, cli.debug, cli.log_format);
+    apply_project_settings(&mut cli, &matches);
 
-    let functions_enabled = !cli.no_functions && !cli.classes_only;
-    let types_enabled = (cli.types && !cli.no_types) && !cli.classes_only;
-    let classes_enabled = cli.classes || cli.classes_only;
-    let overlap_enabled = cli.overlap;
-    let unified_types_enabled = cli.unified_types && !cli.no_unified_types;
-    let include_type_literals = true; // Always include type literals
+    if let Some(Command::Doctor { args }) = &cli.command {
+        if !run_doctor(args)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Triage { action }) = cli.command {
+        return run_triage(action);
+    }
+    if let Some(Command::Schema { format: SchemaFormat::JsonSchema, document }) = cli.command {
+        let schema = match document {
+            SchemaDocument::Heatmap => heatmap_json_schema(),
+            SchemaDocument::Finding => finding_json_schema(),
+        };
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(());
+    }
+    if let Some(Command::Grep(args)) = &cli.command {
+        match run_grep(args) {
+            Ok(true) => return Ok(()),
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(Command::ReportDiff { old, new, json, fail_on_new }) = &cli.command {
+        match run_report_diff(old, new, *json) {
+            Ok(introduced) if introduced && *fail_on_new => std::process::exit(1),
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(Command::MergeResults { results }) = &cli.command {
+        if let Err(e) = run_merge_results(results) {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(2);
+        }
+        return Ok(());
+    }
+    if let Some(Command::Serve(args)) = &cli.command {
+        return run_serve(args);
+    }
+    if let Some(Command::Lens(args)) = &cli.command {
+        if let Err(e) = run_lens(args) {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(2);
+        }
+        return Ok(());
+    }
+    if let Some(Command::CheckSnippet(args)) = &cli.command {
+        match run_check_snippet(args) {
+            Ok(false) => return Ok(()),
+            Ok(true) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(Command::Eval { dataset, threshold, rename_cost, no_size_penalty, size_penalty }) =
+        &cli.command
+    {
+        return run_eval(
+            dataset,
+            *threshold,
+            *rename_cost,
+            *no_size_penalty,
+            size_penalty.resolve(),
+        );
+    }
+
+    // --files-only, --against-index and --migration replace the other analyzers
+    let files_only = cli.files_only;
+    let exclusive = files_only || cli.against_index.is_some() || cli.migration;
+    let EnabledAnalyzers {
+        functions: functions_enabled,
+        types: types_enabled,
+        classes: classes_enabled,
+        overlap: overlap_enabled,
+        notebooks: notebooks_enabled,
+        hooks: hooks_enabled,
+        tests: tests_enabled,
+        graphql: graphql_enabled,
+        templates: templates_enabled,
+        injections: injections_enabled,
+        deps: deps_enabled,
+    } = EnabledAnalyzers::from_cli(&cli);
+    let unified_types_enabled = cli.unified_types && !cli.no_unified_types;
+    let include_type_literals = true; // Always include type literals
+
+    // Validate that at least one analyzer is enabled; later shards may have nothing to do
+    if !exclusive
+        && cli.shard.is_none_or(|shard| shard.is_first())
+        && !functions_enabled
+        && !types_enabled
+        && !classes_enabled
+        && !overlap_enabled
+        && !notebooks_enabled
+        && !hooks_enabled
+        && !tests_enabled
+        && !graphql_enabled
+        && !templates_enabled
+        && !injections_enabled
+        && !deps_enabled
+    {
+        eprintln!("Error: At least one analyzer must be enabled. Remove --no-types to enable type checking, use --classes for class checking, use --overlap for overlap detection, or remove --no-functions.");
+        return Err(anyhow::anyhow!("No analyzer enabled"));
+    }
+
+    // Handle mutual exclusion of min_lines and min_tokens
+    let (min_lines, min_tokens) = match (cli.min_lines, cli.min_tokens) {
+        (Some(_), Some(tokens)) => {
+            eprintln!(
+                "Warning: Both --min-lines and --min-tokens specified. Using --min-tokens={}",
+                tokens
+            );
+            (None, Some(tokens))
+        }
+        (lines, tokens) => (lines, tokens),
+    };
+    let max_tree_nodes = (cli.max_tree_nodes > 0).then_some(cli.max_tree_nodes);
+
+    let cache_dir = if cli.git_cache {
+        Some(
+            default_cache_dir()
+                .ok_or_else(|| anyhow::anyhow!("Cannot determine cache directory"))?,
+        )
+    } else {
+        None
+    };
+    // Temporary clones are removed when `fetched` is dropped, so it must outlive the analysis
+    let mut fetched = Vec::new();
+    for spec in &cli.git {
+        let spec = RemoteSpec::parse(spec);
+        let repository = fetch_repository(&spec, cache_dir.as_deref())?;
+        eprintln!("Fetched {} into {}", spec.url, repository.path().display());
+        fetched.push(repository);
+    }
+
+    let triage = TriageStore::load(DEFAULT_TRIAGE_PATH)?;
+    let boilerplate = match &cli.boilerplate_dir {
+        Some(dir) => {
+            let extensions = ["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+            let files = collect_files(std::slice::from_ref(dir), &extensions)?;
+            let boilerplate = BoilerplateSet::learn(DEFAULT_BOILERPLATE_PATH, &files)?;
+            boilerplate.save()?;
+            eprintln!(
+                "Learned {} boilerplate function(s) from {} into {}",
+                boilerplate.len(),
+                dir,
+                boilerplate.path().display()
+            );
+            boilerplate
+        }
+        None => BoilerplateSet::load(DEFAULT_BOILERPLATE_PATH)?,
+    };
+
+    let mut paths = cli.paths.clone();
+    if paths.is_empty() && fetched.is_empty() {
+        paths.push(".".to_string());
+    }
+    paths.extend(fetched.iter().map(|repository| repository.path().to_string_lossy().to_string()));
+
+    if let Some(index_path) = &cli.export_index {
+        return export_index(&paths, index_path, cli.extensions.as_ref(), &cli.exclude);
+    }
+
+    if cli.daemon {
+        let mut options = TSEDOptions::default();
+        options.apted_options.rename_cost = cli.rename_cost;
+        options.size_penalty = !cli.no_size_penalty;
+        options.size_ratio_penalty = cli.size_penalty.resolve();
+        options.include_comments = cli.include_comments;
+        options.min_lines = min_lines.unwrap_or(3);
+        options.min_tokens = min_tokens;
+        options.max_tree_nodes = max_tree_nodes;
+        options.canonicalize_order = cli.canonicalize_order;
+        options.normalize_async = cli.normalize_async;
+        options.normalize_collections = cli.normalize_collections;
+        options.node_weights = NodeWeightConfig::find_and_load().node_weights;
+        return run_daemon(&paths, cli.threshold, options, cli.extensions.as_ref(), &cli.exclude);
+    }
+
+    let formatters = FormatterRegistry::with_builtins();
+    let mut output = FindingOutput::resolve(&cli.output, &formatters)?;
+    let standard_output = output.format == OutputFormat::Standard;
+    if standard_output {
+        println!("Analyzing code similarity...\n");
+    }
+
+    let mut budget =
+        cli.timeout.map_or_else(ComparisonBudget::unlimited, ComparisonBudget::with_timeout);
+    if let Some(max) = cli.max_comparisons {
+        budget = budget.with_max_comparisons(max);
+    }
+    if let Some(shard) = cli.shard {
+        budget = budget.with_shard(shard);
+    }
+    let size_ratio_penalty = cli.size_penalty.resolve();
+    let node_weights = NodeWeightConfig::find_and_load().node_weights;
+
+    let separator = "-".repeat(60);
+    let mut total_duplicates = 0;
+    let mut heatmap = DuplicationHeatmap::new();
+    let matrix = cli
+        .export_matrix
+        .as_deref()
+        .map(|path| MatrixExporter::create(path, cli.matrix_floor))
+        .transpose()?;
+    let report = RunReport::with_severity_policy(SeverityPolicy::new(
+        SeverityConfig::find_and_load().severity,
+    ))
+    .with_suppressions(Suppressions::discover());
+
+    if files_only {
+        if standard_output {
+            println!("=== File Similarity ===");
+        }
+        total_duplicates += check_files(
+            &paths,
+            cli.threshold,
+            cli.extensions.as_ref(),
+            &cli.exclude,
+            &mut output,
+            &mut heatmap,
+            &report,
+        )?;
+    }
+
+    if let Some(index_path) = &cli.against_index {
+        if standard_output {
+            println!("=== Index Matches ===");
+        }
+        let mut options = TSEDOptions::default();
+        options.apted_options.rename_cost = cli.rename_cost;
+        options.size_penalty = !cli.no_size_penalty;
+        options.size_ratio_penalty = size_ratio_penalty;
+        options.include_comments = cli.include_comments;
+        options.min_lines = min_lines.unwrap_or(3);
+        options.min_tokens = min_tokens;
+        options.max_tree_nodes = max_tree_nodes;
+        options.canonicalize_order = cli.canonicalize_order;
+        options.normalize_async = cli.normalize_async;
+        options.normalize_collections = cli.normalize_collections;
+        options.node_weights = node_weights.clone();
+        total_duplicates += check_against_index(
+            &paths,
+            index_path,
+            cli.threshold,
+            &options,
+            cli.extensions.as_ref(),
+            &cli.exclude,
+            &mut output,
+            &budget,
+            &report,
+        )?;
+    }
+
+    if cli.migration {
+        if standard_output {
+            println!("=== Migration ===");
+        }
+        let mut options = TSEDOptions::default();
+        options.apted_options.rename_cost = cli.rename_cost;
+        options.size_penalty = !cli.no_size_penalty;
+        options.size_ratio_penalty = size_ratio_penalty;
+        options.include_comments = cli.include_comments;
+        options.min_lines = min_lines.unwrap_or(3);
+        options.min_tokens = min_tokens;
+        options.max_tree_nodes = max_tree_nodes;
+        options.normalize_async = cli.normalize_async;
+        options.normalize_collections = cli.normalize_collections;
+        options.node_weights = node_weights.clone();
+        total_duplicates += check_migration(
+            &paths,
+            cli.threshold,
+            &options,
+            cli.extensions.as_ref(),
+            &cli.exclude,
+            &mut output,
+            &budget,
+            &report,
+        )?;
+    }
+
+    // Run functions analysis if enabled
+    if functions_enabled {
+        if standard_output {
+            println!("=== Function Similarity ===");
+        }
+        let duplicate_count = check::check_paths(
+            paths.clone(),
+            cli.threshold,
+            cli.top,
+            cli.rename_cost,
+            cli.extensions.as_ref(),
+            min_lines.unwrap_or(3),
+            min_tokens,
+            cli.min_complexity,
+            cli.no_size_penalty,
+            size_ratio_penalty,
+            cli.include_comments,
+            max_tree_nodes,
+            cli.canonicalize_order,
+            cli.normalize_async,
+            cli.normalize_collections,
+            node_weights.clone(),
+            cli.print,
+            cli.print_layout,
+            !cli.no_fast,
+            cli.filter_function.as_ref(),
+            cli.filter_function_body.as_ref(),
+            &cli.exclude,
+            cli.show_ignored,
+            &mut output,
+            cli.blame,
+            cli.suggest_patch,
+            cli.only_unrelated_modules,
+            cli.scope,
+            cli.group_by,
+            &budget,
+            cli.session.as_deref(),
+            cli.resume,
+            &triage,
+            &cli.triage_state,
+            &boilerplate,
+            &mut heatmap,
+            matrix.as_ref(),
+            &report,
+        )?;
+        total_duplicates += duplicate_count;
+    }
+
+    // Run types analysis if enabled
+    if standard_output && types_enabled && functions_enabled {
+        println!("\n{}\n", separator);
+    }
+
+    if types_enabled {
+        if standard_output {
+            println!("=== Type Similarity ===");
+        }
+        let type_duplicate_count = check_types(
+            paths.clone(),
+            cli.threshold,
+            cli.extensions.as_ref(),
+            cli.print,
+            cli.diff,
+            cli.types_only,
+            cli.interfaces_only,
+            cli.type_literals_only,
+            cli.allow_cross_kind,
+            cli.structural_weight,
+            cli.naming_weight,
+            include_type_literals,
+            unified_types_enabled,
+            &cli.exclude,
+            cli.use_structure_comparison,
+            cli.member_comparison.unwrap_or_default(),
+            size_ratio_penalty,
+            cli.show_ignored,
+            &mut output,
+            &mut heatmap,
+            matrix.as_ref(),
+            &report,
+            &budget,
+        )?;
+        total_duplicates += type_duplicate_count;
+    }
+
+    // Run class analysis if enabled
+    if standard_output && classes_enabled && (functions_enabled || types_enabled) {
+        println!("\n{}\n", separator);
+    }
+
+    if classes_enabled {
+        if standard_output {
+            println!("=== Class Similarity ===");
+        }
+        let class_duplicate_count = check_classes(
+            paths.clone(),
+            cli.threshold,
+            cli.extensions.as_ref(),
+            cli.print,
+            !cli.include_inheritance,
+            !cli.include_implements,
+            &similarity_core::ClassComparisonOptions {
+                ignore_decorators: cli.ignore_decorators,
+                same_decorators_only: cli.same_decorators_only,
+            },
+            cli.suggest,
+            &cli.exclude,
+            cli.show_ignored,
+            &mut output,
+            &mut heatmap,
+            &report,
+            &budget,
+        )?;
+        total_duplicates += class_duplicate_count;
+    }
+
+    // Run overlap analysis if enabled
+    if standard_output && overlap_enabled && (functions_enabled || types_enabled || classes_enabled)
+    {
+        println!("\n{}\n", separator);
+    }
+
+    if overlap_enabled {
+        if standard_output {
+            println!("=== Overlap Detection ===");
+        }
+        let overlap_duplicate_count = check_overlaps(
+            paths.clone(),
+            cli.threshold,
+            cli.extensions.as_ref(),
+            cli.print,
+            cli.overlap_min_window,
+            cli.overlap_max_window,
+            cli.overlap_size_tolerance,
+            &cli.exclude,
+            &mut output,
+            &mut heatmap,
+            &report,
+        )?;
+        total_duplicates += overlap_duplicate_count;
+    }
+
+    // Run notebook cell analysis if enabled
+    if standard_output
+        && notebooks_enabled
+        && (functions_enabled || types_enabled || classes_enabled || overlap_enabled)
+    {
+        println!("\n{}\n", separator);
+    }
+
+    if notebooks_enabled {
+        if standard_output {
+            println!("=== Notebook Cell Similarity ===");
+        }
+        let mut options = TSEDOptions::default();
+        options.apted_options.rename_cost = cli.rename_cost;
+        options.size_ratio_penalty = size_ratio_penalty;
+        options.min_lines = min_lines.unwrap_or(3);
+        options.min_tokens = min_tokens;
+        options.max_tree_nodes = max_tree_nodes;
+        options.canonicalize_order = cli.canonicalize_order;
+        options.normalize_async = cli.normalize_async;
+        options.normalize_collections = cli.normalize_collections;
+        options.node_weights = node_weights.clone();
+        options.size_penalty = !cli.no_size_penalty;
+        options.include_comments = cli.include_comments;
+        total_duplicates +=
+            check_notebooks(&paths, cli.threshold, &options, cli.print, &mut output, &report)?;
+    }
+
+    // Run React hook analysis if enabled
+    if standard_output
+        && hooks_enabled
+        && (functions_enabled
+            || types_enabled
+            || classes_enabled
+            || overlap_enabled
+            || notebooks_enabled)
+    {
+        println!("\n{}\n", separator);
+    }
+
+    if hooks_enabled {
+        if standard_output {
+            println!("=== React Hook Similarity ===");
+        }
+        let mut options = TSEDOptions::default();
+        options.apted_options.rename_cost = cli.rename_cost;
+        options.size_ratio_penalty = size_ratio_penalty;
+        options.min_lines = min_lines.unwrap_or(3);
+        options.min_tokens = min_tokens;
+        options.max_tree_nodes = max_tree_nodes;
+        options.canonicalize_order = cli.canonicalize_order;
+        options.normalize_async = cli.normalize_async;
+        options.normalize_collections = cli.normalize_collections;
+        options.node_weights = node_weights.clone();
+        options.size_penalty = !cli.no_size_penalty;
+        options.include_comments = cli.include_comments;
+        total_duplicates += check_hooks(
+            &paths,
+            cli.threshold,
+            &options,
+            cli.extensions.as_ref(),
+            &cli.exclude,
+            cli.print,
+            &mut output,
+            &mut heatmap,
+            &report,
+        )?;
+    }
+
+    // Run test block analysis if enabled
+    if standard_output
+        && tests_enabled
+        && (functions_enabled
+            || types_enabled
+            || classes_enabled
+            || overlap_enabled
+            || notebooks_enabled
+            || hooks_enabled)
+    {
+        println!("\n{}\n", separator);
+    }
+
+    if tests_enabled {
+        if standard_output {
+            println!("=== Test Similarity ===");
+        }
+        let mut options = TSEDOptions::default();
+        options.apted_options.rename_cost = cli.rename_cost;
+        options.size_ratio_penalty = size_ratio_penalty;
+        options.min_lines = min_lines.unwrap_or(3);
+        options.min_tokens = min_tokens;
+        options.max_tree_nodes = max_tree_nodes;
+        options.canonicalize_order = cli.canonicalize_order;
+        options.normalize_async = cli.normalize_async;
+        options.normalize_collections = cli.normalize_collections;
+        options.node_weights = node_weights.clone();
+        options.include_comments = cli.include_comments;
+        total_duplicates += check_tests(
+            &paths,
+            cli.test_threshold.unwrap_or(cli.threshold),
+            &options,
+            cli.extensions.as_ref(),
+            &cli.exclude,
+            cli.print,
+            &mut output,
+            &mut heatmap,
+            &report,
+        )?;
+    }
+
+    // Run GraphQL analysis if enabled
+    if standard_output
+        && graphql_enabled
+        && (functions_enabled
+            || types_enabled
+            || classes_enabled
+            || overlap_enabled
+            || notebooks_enabled
+            || hooks_enabled
+            || tests_enabled)
+    {
+        println!("\n{}\n", separator);
+    }
+
+    if graphql_enabled {
+        if standard_output {
+            println!("=== GraphQL Similarity ===");
+        }
+        let mut options = TSEDOptions::default();
+        options.apted_options.rename_cost = cli.rename_cost;
+        options.size_ratio_penalty = size_ratio_penalty;
+        options.min_lines = min_lines.unwrap_or(3);
+        options.min_tokens = min_tokens;
+        options.max_tree_nodes = max_tree_nodes;
+        options.canonicalize_order = cli.canonicalize_order;
+        options.normalize_async = cli.normalize_async;
+        options.normalize_collections = cli.normalize_collections;
+        options.node_weights = node_weights.clone();
+        total_duplicates += check_graphql(
+            &paths,
+            cli.threshold,
+            &options,
+            cli.extensions.as_ref(),
+            &cli.exclude,
+            cli.print,
+            &mut output,
+            &mut heatmap,
+            &report,
+        )?;
+    }
+
+    // Run template script analysis if enabled
+    if standard_output
+        && templates_enabled
+        && (functions_enabled
+            || types_enabled
+            || classes_enabled
+            || overlap_enabled
+            || notebooks_enabled
+            || hooks_enabled
+            || tests_enabled
+            || graphql_enabled)
+    {
+        println!("\n{}\n", separator);
+    }
+
+    if templates_enabled {
+        if standard_output {
+            println!("=== Template Function Similarity ===");
+        }
+        let mut options = TSEDOptions::default();
+        options.apted_options.rename_cost = cli.rename_cost;
+        options.size_penalty = !cli.no_size_penalty;
+        options.size_ratio_penalty = size_ratio_penalty;
+        options.include_comments = cli.include_comments;
+        options.min_lines = min_lines.unwrap_or(3);
+        options.min_tokens = min_tokens;
+        options.max_tree_nodes = max_tree_nodes;
+        options.canonicalize_order = cli.canonicalize_order;
+        options.normalize_async = cli.normalize_async;
+        options.normalize_collections = cli.normalize_collections;
+        options.node_weights = node_weights.clone();
+        total_duplicates += check_templates(
+            &paths,
+            cli.threshold,
+            &options,
+            &cli.exclude,
+            cli.print,
+            &mut output,
+            &mut heatmap,
+            &report,
+        )?;
+    }
+
+    // Run embedded HTML and SQL analysis if enabled
+    if standard_output
+        && injections_enabled
+        && (functions_enabled
+            || types_enabled
+            || classes_enabled
+            || overlap_enabled
+            || notebooks_enabled
+            || hooks_enabled
+            || tests_enabled
+            || graphql_enabled
+            || templates_enabled)
+    {
+        println!("\n{}\n", separator);
+    }
+
+    if injections_enabled {
+        if standard_output {
+            println!("=== Embedded HTML and SQL Similarity ===");
+        }
+        total_duplicates += check_injections(
+            &paths,
+            cli.threshold,
+            cli.extensions.as_ref(),
+            &cli.exclude,
+            cli.print,
+            &mut output,
+            &mut heatmap,
+            &report,
+            &budget,
+        )?;
+    }
+
+    // Run dependency type analysis if enabled
+    if standard_output
+        && deps_enabled
+        && (functions_enabled
+            || types_enabled
+            || classes_enabled
+            || overlap_enabled
+            || notebooks_enabled
+            || hooks_enabled
+            || tests_enabled
+            || graphql_enabled
+            || templates_enabled
+            || injections_enabled)
+    {
+        println!("\n{}\n", separator);
+    }
+
+    if deps_enabled {
+        if standard_output {
+            println!("=== Dependency Types ===");
+        }
+        total_duplicates += check_dependency_types(
+            &paths,
+            &cli.against_deps,
+            cli.threshold,
+            cli.extensions.as_ref(),
+            &cli.exclude,
+            cli.print,
+            &mut output,
+            &report,
+            &budget,
+        )?;
+    }
+
+    if let Some(path) = &cli.export {
+        heatmap.write_json(path)?;
+    }
+    if let (Some(matrix), Some(path)) = (matrix, &cli.export_matrix) {
+        matrix
+            .finish()
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+    }
+
+    report_coverage(&budget);
+    if output.format == OutputFormat::PrComment {
+        let base = cli.base_results.as_deref().map(load_findings).transpose()?;
+        let links = match (&cli.repo_url, &cli.head_ref) {
+            (Some(repo_url), Some(head_ref)) => Some(BlobLinks::new(repo_url, head_ref)),
+            _ => BlobLinks::from_env(),
+        };
+        let findings = parse_findings(&output.into_held())?;
+        print!("{}", render_pr_comment(findings, base, links.as_ref()));
+    }
+    if let Some(summary) = report.summary() {
+        if standard_output {
+            println!("\n{}", summary);
+        }
+    }
+    if let Some(summary) = report.suppression_summary() {
+        if standard_output {
+            println!("\n{}", summary);
+        }
+    }
+    if let Some(summary) = report.severity_summary() {
+        if standard_output {
+            println!("\n{}", summary);
+        }
+    }
+
+    // Fail before --fail-on-duplicates so CI tells unparsed code from duplicates
+    let parse_failures = report.parse_failures() + report.partially_parsed().len();
+    if cli.strict && parse_failures > 0 {
+        return Err(anyhow::anyhow!("{} file(s) failed to parse (--strict)", parse_failures));
+    }
+
+    // Exit with code 1 if duplicates found and --fail-on-duplicates is set, or a finding
+    // reaches the --fail-on severity
+    let failing = cli.fail_on.map_or(cli.fail_on_duplicates && total_duplicates > 0, |level| {
+        report.findings_at_least(level) > 0
+    });
+    if failing {
+        // `exit` skips destructors, so remove temporary clones first
+        drop(fetched);
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Tell how much of the search space a time-boxed or capped run covered
+fn report_coverage(budget: &ComparisonBudget) {
+    let coverage = budget.coverage();
+    if coverage.is_complete() {
+        return;
+    }
+    if let Some(max) = budget.max_comparisons().filter(|&max| coverage.compared >= max) {
+        eprintln!(
+            "Comparison cap of {} reached: compared {} of {} candidate pairs ({:.1}%); {} cross-file pairs were sampled out, keeping pairs in the same locality bucket. Results are partial.",
+            max,
+            coverage.compared,
+            coverage.candidates,
+            coverage.percent(),
+            budget.sampled_out()
+        );
+    } else if let Some(timeout) = budget.timeout() {
+        eprintln!(
+            "Timeout of {:?} reached: compared {} of {} candidate pairs ({:.1}%), densest buckets first. Results are partial.",
+            timeout,
+            coverage.compared,
+            coverage.candidates,
+            coverage.percent()
+        );
+    }
+}
+
+/// TypeScript/JavaScript files under `paths` with their relative paths, minus excluded ones
+fn collect_sources(
+    paths: &[String],
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+    report: Option<&RunReport>,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let default_extensions = vec!["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+    let exts: Vec<&str> =
+        extensions.map_or(default_extensions, |v| v.iter().map(String::as_str).collect());
+    let exclude_matcher = create_exclude_matcher(exclude_patterns);
+
+    let mut sources = Vec::new();
+    for file in collect_files(paths, &exts)? {
+        let file_path = get_relative_path(&file.to_string_lossy());
+        if exclude_matcher.as_ref().is_some_and(|matcher| matcher.is_match(&file_path)) {
+            if let Some(report) = report {
+                report.skip(&file_path, SkipReason::Excluded);
+            }
+            continue;
+        }
+        match std::fs::read_to_string(&file) {
+            Ok(content) => sources.push((file_path, content)),
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file_path, e);
+                if let Some(report) = report {
+                    report.skip(&file_path, SkipReason::Unreadable(e.to_string()));
+                }
+            }
+        }
+    }
+    Ok(sources)
+}
+
+fn export_index(
+    paths: &[String],
+    index_path: &str,
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+) -> anyhow::Result<()> {
+    let sources = collect_sources(paths, extensions, exclude_patterns, None)?;
+    let mut index = FunctionIndex::new();
+    for (file_path, content) in &sources {
+        if let Err(e) = index.add_source(file_path, content) {
+            tracing::info!(file = %file_path, error = %e, "skipping file");
+        }
+    }
+    index.save(index_path)?;
+    eprintln!(
+        "Indexed {} function(s) from {} file(s) into {}",
+        index.len(),
+        sources.len(),
+        index_path
+    );
+    Ok(())
+}
+
+fn run_daemon(
+    paths: &[String],
+    threshold: f64,
+    options: TSEDOptions,
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+) -> anyhow::Result<()> {
+    let sources = collect_sources(paths, extensions, exclude_patterns, None)?;
+    let mut index = FunctionIndex::new();
+    for (file_path, content) in &sources {
+        if let Err(e) = index.add_source(file_path, content) {
+            tracing::info!(file = %file_path, error = %e, "skipping file");
+        }
+    }
+    eprintln!(
+        "Indexed {} function(s) from {} file(s), waiting for requests",
+        index.len(),
+        sources.len()
+    );
+    let mut daemon = daemon::Daemon::new(index, threshold, options, std::env::current_dir()?);
+    daemon.serve(std::io::stdin().lock(), std::io::stdout().lock())?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_against_index(
+    paths: &[String],
+    index_path: &str,
+    threshold: f64,
+    options: &TSEDOptions,
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+    output: &mut FindingOutput,
+    budget: &ComparisonBudget,
+    report: &RunReport,
+) -> anyhow::Result<usize> {
+    let index = FunctionIndex::load(index_path)?;
+    let sources = collect_sources(paths, extensions, exclude_patterns, Some(report))?;
+
+    let mut matches = Vec::new();
+    for (file_path, content) in &sources {
+        match index.find_matches(file_path, content, threshold, options, budget) {
+            Ok(file_matches) => {
+                matches.extend(file_matches.into_iter().map(|found| (file_path, found)));
+            }
+            Err(e) => {
+                tracing::info!(file = %file_path, error = %e, "skipping file");
+                report.skip(file_path, SkipReason::ParseError(e));
+            }
+        }
+    }
+    matches.sort_by(|(_, a), (_, b)| b.similarity.total_cmp(&a.similarity));
+    let severities: Vec<Severity> =
+        matches.iter().map(|(_, found)| report.classify("index", found.similarity)).collect();
+
+    if output.format != OutputFormat::Standard {
+        for ((file_path, found), severity) in matches.iter().zip(&severities) {
+            let message = format!(
+                "Function '{}' is {:.0}% similar to '{}' in the index",
+                found.function.name,
+                found.similarity * 100.0,
+                found.indexed.name
+            );
+            output.print_finding(
+                file_path,
+                found.function.start_line as usize,
+                *severity,
+                &message,
+                &found.indexed.file,
+                found.indexed.start_line as usize,
+            );
+        }
+        return Ok(matches.len());
+    }
+
+    println!(
+        "Checking {} files against {} indexed function(s) from {}...",
+        sources.len(),
+        index.len(),
+        index_path
+    );
+    if matches.is_empty() {
+        println!("\nNo functions similar to the index found!");
+        return Ok(0);
+    }
+
+    println!("\nFunctions similar to the index:");
+    println!("{}", "-".repeat(60));
+    for (file_path, found) in &matches {
+        println!("\nSimilarity: {:.2}%", found.similarity * 100.0);
+        println!(
+            "  {}:{}-{} {}",
+            file_path, found.function.start_line, found.function.end_line, found.function.name
+        );
+        println!(
+            "  index: {}:{}-{} {}",
+            found.indexed.file,
+            found.indexed.start_line,
+            found.indexed.end_line,
+            found.indexed.name
+        );
+    }
+    println!("\nTotal functions similar to the index: {}", matches.len());
+
+    Ok(matches.len())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_migration(
+    paths: &[String],
+    threshold: f64,
+    options: &TSEDOptions,
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+    output: &mut FindingOutput,
+    budget: &ComparisonBudget,
+    report: &RunReport,
+) -> anyhow::Result<usize> {
+    let sources = collect_sources(paths, extensions, exclude_patterns, Some(report))?;
+    let migration = migration::find_migrated_functions(&sources, threshold, options, budget);
+    for (file_path, e) in migration.parse_errors {
+        tracing::info!(file = %file_path, error = %e, "skipping file");
+        report.skip(&file_path, SkipReason::ParseError(e));
+    }
+    let confidence = |found: &migration::MigratedFunction| {
+        if found.is_high_confidence() {
+            "high confidence"
+        } else {
+            "review"
+        }
+    };
+
+    if output.format != OutputFormat::Standard {
+        for found in &migration.functions {
+            let message = format!(
+                "Function '{}' was migrated to '{}' and not deleted ({:.0}% similar with types stripped, {})",
+                found.js.name,
+                found.ts.name,
+                found.similarity * 100.0,
+                confidence(found)
+            );
+            output.print_finding(
+                &found.js_file,
+                found.js.start_line as usize,
+                report.classify("migration", found.similarity),
+                &message,
+                &found.ts_file,
+                found.ts.start_line as usize,
+            );
+        }
+        for file_path in &migration.safe_to_delete {
+            let migrated_to = migration
+                .functions
+                .iter()
+                .find(|found| &found.js_file == file_path)
+                .map_or("", |found| found.ts_file.as_str());
+            output.print_finding(
+                file_path,
+                1,
+                report.classify("migration", 1.0),
+                "File is safe to delete: every function has a TypeScript version",
+                migrated_to,
+                1,
+            );
+        }
+        return Ok(migration.functions.len());
+    }
+
+    let count = |extensions: &[&str]| {
+        sources
+            .iter()
+            .filter(|(file_path, _)| {
+                Path::new(file_path)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| extensions.contains(&ext))
+            })
+            .count()
+    };
+    println!(
+        "Matching {} JavaScript file(s) with {} TypeScript file(s)...",
+        count(&migration::JS_EXTENSIONS),
+        count(&migration::TS_EXTENSIONS)
+    );
+    if migration.functions.is_empty() && migration.safe_to_delete.is_empty() {
+        println!("\nNo migrated JavaScript functions found!");
+        return Ok(0);
+    }
+
+    if !migration.functions.is_empty() {
+        println!("\nMigrated but not deleted:");
+        println!("{}", "-".repeat(60));
+        for found in &migration.functions {
+            println!("\nSimilarity: {:.2}% ({})", found.similarity * 100.0, confidence(found));
+            println!(
+                "  {}:{}-{} {}",
+                found.js_file, found.js.start_line, found.js.end_line, found.js.name
+            );
+            println!(
+                "  ts: {}:{}-{} {}",
+                found.ts_file, found.ts.start_line, found.ts.end_line, found.ts.name
+            );
+        }
+        println!("\nTotal migrated functions: {}", migration.functions.len());
+    }
+    if !migration.safe_to_delete.is_empty() {
+        println!("\nJavaScript files safe to delete:");
+        for file_path in &migration.safe_to_delete {
+            println!("  {}", file_path);
+        }
+    }
+
+    Ok(migration.functions.len())
+}
+
+fn check_files(
+    paths: &[String],
+    threshold: f64,
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+    output: &mut FindingOutput,
+    heatmap: &mut DuplicationHeatmap,
+    report: &RunReport,
+) -> anyhow::Result<usize> {
+    let files = match extensions {
+        Some(extensions) => {
+            let exts: Vec<&str> = extensions.iter().map(String::as_str).collect();
+            collect_files(paths, &exts)?
+        }
+        None => collect_all_files(paths)?,
+    };
+    let exclude_matcher = create_exclude_matcher(exclude_patterns);
+
+    let mut fingerprints = Vec::new();
+    let mut line_counts = HashMap::new();
+    for file in files {
+        let file_path = get_relative_path(&file.to_string_lossy());
+        if exclude_matcher.as_ref().is_some_and(|matcher| matcher.is_match(&file_path)) {
+            report.skip(&file_path, SkipReason::Excluded);
+            continue;
+        }
+
+        let content = match std::fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                tracing::debug!(file = %file_path, "skipping file: not text");
+                continue;
+            }
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file_path, e);
+                report.skip(&file_path, SkipReason::Unreadable(e.to_string()));
+                continue;
+            }
+        };
+        heatmap.add_file(&file_path);
+        line_counts.insert(file_path.clone(), content.lines().count().max(1));
+        if let Some(fingerprint) =
+            FileFingerprint::new(file_path, &content, DEFAULT_MIN_FILE_TOKENS)
+        {
+            fingerprints.push(fingerprint);
+        }
+    }
+
+    let duplicates = find_similar_files(&fingerprints, threshold);
+    let path_of = |fingerprint: &FileFingerprint| fingerprint.path.to_string_lossy().to_string();
+    for duplicate in &duplicates {
+        let (first, second) = (path_of(duplicate.first), path_of(duplicate.second));
+        heatmap.record_pair(
+            "files",
+            (&first, 1, line_counts[&first]),
+            (&second, 1, line_counts[&second]),
+        );
+    }
+
+    if output.format != OutputFormat::Standard {
+        for duplicate in &duplicates {
+            let severity = report.classify("files", duplicate.similarity);
+            let (first, second) = (path_of(duplicate.first), path_of(duplicate.second));
+            let message =
+                format!("File is {:.0}% similar to '{}'", duplicate.similarity * 100.0, second);
+            output.print_finding(&first, 1, severity, &message, &second, 1);
+        }
+        return Ok(duplicates.len());
+    }
+
+    println!("Checking {} files...", fingerprints.len());
+    let directories = find_similar_directories(&fingerprints, threshold);
+    // Files mapped by a directory clone are listed with it only
+    let duplicates: Vec<_> = duplicates
+        .into_iter()
+        .filter(|duplicate| {
+            !directories.iter().flat_map(|directory| &directory.files).any(|mapped| {
+                mapped.first.path == duplicate.first.path
+                    && mapped.second.path == duplicate.second.path
+                    || mapped.first.path == duplicate.second.path
+                        && mapped.second.path == duplicate.first.path
+            })
+        })
+        .collect();
+    if directories.is_empty() && duplicates.is_empty() {
+        println!("\nNo near-duplicate files found!");
+        return Ok(0);
+    }
+    for similarity in directories
+        .iter()
+        .map(|directory| directory.similarity)
+        .chain(duplicates.iter().map(|duplicate| duplicate.similarity))
+    {
+        report.classify("files", similarity);
+    }
+
+    if !directories.is_empty() {
+        println!("\nNear-duplicate directories:");
+        println!("{}", "-".repeat(60));
+    }
+    for directory in &directories {
+        let relative = |dir: &std::path::Path, fingerprint: &FileFingerprint| {
+            fingerprint.path.strip_prefix(dir).unwrap_or(&fingerprint.path).display().to_string()
+        };
+        println!("\nSimilarity: {:.2}%", directory.similarity * 100.0);
+        println!("  {}/", directory.first.display());
+        println!("  {}/", directory.second.display());
+        for file in &directory.files {
+            println!(
+                "    {} <-> {} ({:.2}%)",
+                relative(directory.first, file.first),
+                relative(directory.second, file.second),
+                file.similarity * 100.0
+            );
+        }
+        for (dir, files) in [
+            (directory.first, &directory.only_in_first),
+            (directory.second, &directory.only_in_second),
+        ] {
+            for file in files {
+                println!("    only in {}/: {}", dir.display(), relative(dir, file));
+            }
+        }
+    }
+
+    if !duplicates.is_empty() {
+        println!("\nNear-duplicate files:");
+        println!("{}", "-".repeat(60));
+    }
+    for duplicate in &duplicates {
+        println!("\nSimilarity: {:.2}%", duplicate.similarity * 100.0);
+        for fingerprint in [duplicate.first, duplicate.second] {
+            println!("  {} ({} tokens)", path_of(fingerprint), fingerprint.tokens);
+        }
+    }
+    if !directories.is_empty() {
+        println!("\nTotal near-duplicate directory pairs found: {}", directories.len());
+    }
+    println!("\nTotal near-duplicate file pairs found: {}", duplicates.len());
+
+    Ok(directories.len() + duplicates.len())
+}
+
+fn check_notebooks(
+    paths: &[String],
+    threshold: f64,
+    options: &TSEDOptions,
+    print: bool,
+    output: &mut FindingOutput,
+    report: &RunReport,
+) -> anyhow::Result<usize> {
+    use similarity_core::{
+        cli_file_utils::collect_files, find_duplicate_cells, parse_and_convert_to_tree,
+        parse_notebook, parser::parse_and_convert_to_tree_with_comments, report_duplicate_cells,
+        NotebookCell, ParsedCell,
+    };
+
+    let files = collect_files(paths, &["ipynb"])?;
+    let mut cells = Vec::new();
+    let mut notebook_count = 0;
+
+    for file in &files {
+        let notebook_path = get_relative_path(&file.to_string_lossy());
+        let notebook = match std::fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|content| parse_notebook(&content))
+        {
+            Ok(notebook) => notebook,
+            Err(e) => {
+                eprintln!("Error parsing {}: {}", notebook_path, e);
+                report.skip(&notebook_path, SkipReason::ParseError(e));
+                continue;
+            }
+        };
+
+        // Python and other kernels are handled by their own analyzers
+        let filename = if notebook.is_language(&["typescript"]) {
+            "cell.mts"
+        } else if notebook.is_language(&["javascript"]) {
+            "cell.mjs"
+        } else {
+            continue;
+        };
+        notebook_count += 1;
+
+        for cell in notebook.cells {
+            let NotebookCell { number, source } = cell;
+            // Cells are parsed as modules so that top-level await is accepted
+            let tree = if options.include_comments {
+                parse_and_convert_to_tree_with_comments(filename, &source)
+            } else {
+                parse_and_convert_to_tree(filename, &source)
+            };
+            match tree {
+                Ok(tree) => cells.push(ParsedCell {
+                    notebook: notebook_path.clone(),
+                    cell: NotebookCell { number, source },
+                    tree,
+                }),
+                Err(e) => eprintln!("Error parsing {} cell {}: {}", notebook_path, number, e),
+            }
+        }
+    }
+
+    if notebook_count == 0 {
+        if output.format == OutputFormat::Standard {
+            println!("No JavaScript/TypeScript notebooks found in specified paths");
+        }
+        return Ok(0);
+    }
+
+    let duplicates = find_duplicate_cells(&cells, threshold, options);
+    let severities: Vec<Severity> = duplicates
+        .iter()
+        .map(|duplicate| report.classify("notebooks", duplicate.similarity))
+        .collect();
+
+    if output.format != OutputFormat::Standard {
+        for (duplicate, severity) in duplicates.iter().zip(&severities) {
+            let (first, second) = (duplicate.first, duplicate.second);
+            let message = format!(
+                "Cell {} is {:.1}% similar to cell {}",
+                first.cell.number,
+                duplicate.similarity * 100.0,
+                second.cell.number
+            );
+            output.print_finding(&first.notebook, 1, *severity, &message, &second.notebook, 1);
+        }
+        return Ok(duplicates.len());
+    }
+
+    println!("Checking {} code cells in {} notebooks...", cells.len(), notebook_count);
+    Ok(report_duplicate_cells(&duplicates, print))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_hooks(
+    paths: &[String],
+    threshold: f64,
+    options: &TSEDOptions,
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+    print: bool,
+    output: &mut FindingOutput,
+    heatmap: &mut DuplicationHeatmap,
+    report: &RunReport,
+) -> anyhow::Result<usize> {
+    use similarity_core::{extract_hooks, find_similar_hooks, report_similar_hooks};
+
+    let default_extensions = vec!["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+    let exts: Vec<&str> =
+        extensions.map_or(default_extensions, |v| v.iter().map(String::as_str).collect());
+    let exclude_matcher = create_exclude_matcher(exclude_patterns);
+
+    let mut hooks = Vec::new();
+    for file in collect_files(paths, &exts)? {
+        let file_path = get_relative_path(&file.to_string_lossy());
+        if exclude_matcher.as_ref().is_some_and(|matcher| matcher.is_match(&file_path)) {
+            report.skip(&file_path, SkipReason::Excluded);
+            continue;
+        }
+        heatmap.add_file(&file_path);
+
+        let content = match std::fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file_path, e);
+                report.skip(&file_path, SkipReason::Unreadable(e.to_string()));
+                continue;
+            }
+        };
+        match extract_hooks(&file_path, &content) {
+            Ok(file_hooks) => hooks.extend(file_hooks),
+            Err(e) => {
+                tracing::info!(file = %file_path, error = %e, "skipping file");
+                report.skip(&file_path, SkipReason::ParseError(e));
+            }
+        }
+    }
+
+    let duplicates = find_similar_hooks(&hooks, threshold, options);
+    for duplicate in &duplicates {
+        let (first, second) = (duplicate.first, duplicate.second);
+        heatmap.record_pair(
+            "hooks",
+            (&first.file_path, first.start_line as usize, first.end_line as usize),
+            (&second.file_path, second.start_line as usize, second.end_line as usize),
+        );
+    }
+    let severities: Vec<Severity> = duplicates
+        .iter()
+        .map(|duplicate| report.classify("hooks", duplicate.similarity.similarity))
+        .collect();
+
+    if output.format != OutputFormat::Standard {
+        for (duplicate, severity) in duplicates.iter().zip(&severities) {
+            let (first, second) = (duplicate.first, duplicate.second);
+            let message = format!(
+                "Hook '{}' is {:.0}% similar to '{}'",
+                first.name,
+                duplicate.similarity.similarity * 100.0,
+                second.name
+            );
+            output.print_finding(
+                &first.file_path,
+                first.start_line as usize,
+                *severity,
+                &message,
+                &second.file_path,
+                second.start_line as usize,
+            );
+        }
+        return Ok(duplicates.len());
+    }
+
+    println!("Checking {} custom hooks...", hooks.len());
+    Ok(report_similar_hooks(&duplicates, print))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_tests(
+    paths: &[String],
+    threshold: f64,
+    options: &TSEDOptions,
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+    print: bool,
+    output: &mut FindingOutput,
+    heatmap: &mut DuplicationHeatmap,
+    report: &RunReport,
+) -> anyhow::Result<usize> {
+    use similarity_core::{extract_test_units, find_similar_tests, report_similar_tests};
+
+    let default_extensions = vec!["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+    let exts: Vec<&str> =
+        extensions.map_or(default_extensions, |v| v.iter().map(String::as_str).collect());
+    let exclude_matcher = create_exclude_matcher(exclude_patterns);
+
+    let mut units = Vec::new();
+    for file in collect_files(paths, &exts)? {
+        let file_path = get_relative_path(&file.to_string_lossy());
+        if exclude_matcher.as_ref().is_some_and(|matcher| matcher.is_match(&file_path)) {
+            report.skip(&file_path, SkipReason::Excluded);
+            continue;
+        }
+        heatmap.add_file(&file_path);
+
+        let content = match std::fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file_path, e);
+                report.skip(&file_path, SkipReason::Unreadable(e.to_string()));
+                continue;
+            }
+        };
+        match extract_test_units(&file_path, &content) {
+            Ok(file_units) => units.extend(file_units),
+            Err(e) => {
+                tracing::info!(file = %file_path, error = %e, "skipping file");
+                report.skip(&file_path, SkipReason::ParseError(e));
+            }
+        }
+    }
+
+    let duplicates = find_similar_tests(&units, threshold, options);
+    for duplicate in &duplicates {
+        let (first, second) = (duplicate.first, duplicate.second);
+        heatmap.record_pair(
+            "tests",
+            (&first.file_path, first.start_line as usize, first.end_line as usize),
+            (&second.file_path, second.start_line as usize, second.end_line as usize),
+        );
+    }
+    let severities: Vec<Severity> =
+        duplicates.iter().map(|duplicate| report.classify("tests", duplicate.similarity)).collect();
+
+    if output.format != OutputFormat::Standard {
+        for (duplicate, severity) in duplicates.iter().zip(&severities) {
+            let (first, second) = (duplicate.first, duplicate.second);
+            let message = format!(
+                "{} '{}' is {:.0}% similar to '{}'",
+                first.kind,
+                first.name,
+                duplicate.similarity * 100.0,
+                second.name
+            );
+            output.print_finding(
+                &first.file_path,
+                first.start_line as usize,
+                *severity,
+                &message,
+                &second.file_path,
+                second.start_line as usize,
+            );
+        }
+        return Ok(duplicates.len());
+    }
+
+    println!("Checking {} test blocks...", units.len());
+    Ok(report_similar_tests(&duplicates, print))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_graphql(
+    paths: &[String],
+    threshold: f64,
+    options: &TSEDOptions,
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+    print: bool,
+    output: &mut FindingOutput,
+    heatmap: &mut DuplicationHeatmap,
+    report: &RunReport,
+) -> anyhow::Result<usize> {
+    use graphql::{extract_graphql, find_similar_graphql, report_similar_graphql};
+
+    let default_extensions = vec!["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+    let exts: Vec<&str> =
+        extensions.map_or(default_extensions, |v| v.iter().map(String::as_str).collect());
+    let exclude_matcher = create_exclude_matcher(exclude_patterns);
+
+    let mut definitions = Vec::new();
+    for file in collect_files(paths, &exts)? {
+        let file_path = get_relative_path(&file.to_string_lossy());
+        if exclude_matcher.as_ref().is_some_and(|matcher| matcher.is_match(&file_path)) {
+            report.skip(&file_path, SkipReason::Excluded);
+            continue;
+        }
+        heatmap.add_file(&file_path);
+
+        let content = match std::fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file_path, e);
+                report.skip(&file_path, SkipReason::Unreadable(e.to_string()));
+                continue;
+            }
+        };
+        match extract_graphql(&file_path, &content) {
+            Ok(file_definitions) => definitions.extend(file_definitions),
+            Err(e) => {
+                tracing::info!(file = %file_path, error = %e, "skipping file");
+                report.skip(&file_path, SkipReason::ParseError(e));
+            }
+        }
+    }
+
+    let duplicates = find_similar_graphql(&definitions, threshold, options);
+    for duplicate in &duplicates {
+        let (first, second) = (duplicate.first, duplicate.second);
+        heatmap.record_pair(
+            "graphql",
+            (&first.file_path, first.start_line as usize, first.end_line as usize),
+            (&second.file_path, second.start_line as usize, second.end_line as usize),
+        );
+    }
+    let severities: Vec<Severity> = duplicates
+        .iter()
+        .map(|duplicate| report.classify("graphql", duplicate.similarity))
+        .collect();
+
+    if output.format != OutputFormat::Standard {
+        for (duplicate, severity) in duplicates.iter().zip(&severities) {
+            let (first, second) = (duplicate.first, duplicate.second);
+            let message = format!(
+                "GraphQL {} '{}' is {:.0}% similar to '{}'",
+                first.kind,
+                first.name,
+                duplicate.similarity * 100.0,
+                second.name
+            );
+            output.print_finding(
+                &first.file_path,
+                first.start_line as usize,
+                *severity,
+                &message,
+                &second.file_path,
+                second.start_line as usize,
+            );
+        }
+        return Ok(duplicates.len());
+    }
+
+    println!("Checking {} GraphQL operations and fragments...", definitions.len());
+    Ok(report_similar_graphql(&duplicates, print))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_templates(
+    paths: &[String],
+    threshold: f64,
+    options: &TSEDOptions,
+    exclude_patterns: &[String],
+    print: bool,
+    output: &mut FindingOutput,
+    heatmap: &mut DuplicationHeatmap,
+    report: &RunReport,
+) -> anyhow::Result<usize> {
+    use templates::{
+        find_similar_template_functions, report_similar_template_functions, TEMPLATE_EXTENSIONS,
+    };
+
+    let exclude_matcher = create_exclude_matcher(exclude_patterns);
+    let mut sources = Vec::new();
+    for file in collect_files(paths, &TEMPLATE_EXTENSIONS)? {
+        let file_path = get_relative_path(&file.to_string_lossy());
+        if exclude_matcher.as_ref().is_some_and(|matcher| matcher.is_match(&file_path)) {
+            report.skip(&file_path, SkipReason::Excluded);
+            continue;
+        }
+        heatmap.add_file(&file_path);
+
+        match std::fs::read_to_string(&file) {
+            Ok(content) => sources.push((file_path, content)),
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file_path, e);
+                report.skip(&file_path, SkipReason::Unreadable(e.to_string()));
+            }
+        }
+    }
+
+    let (duplicates, errors) = find_similar_template_functions(&sources, threshold, options);
+    for (file_path, e) in errors {
+        tracing::info!(file = %file_path, error = %e, "skipping file");
+        report.skip(&file_path, SkipReason::ParseError(e));
+    }
+    for duplicate in &duplicates {
+        let (func1, func2) = (&duplicate.result.func1, &duplicate.result.func2);
+        heatmap.record_pair(
+            "templates",
+            (&duplicate.first_file, func1.start_line as usize, func1.end_line as usize),
+            (&duplicate.second_file, func2.start_line as usize, func2.end_line as usize),
+        );
+    }
+
+    if output.format != OutputFormat::Standard {
+        for duplicate in &duplicates {
+            let (func1, func2) = (&duplicate.result.func1, &duplicate.result.func2);
+            let message = format!(
+                "Template function '{}' is {:.0}% similar to '{}'",
+                func1.name,
+                duplicate.result.similarity * 100.0,
+                func2.name
+            );
+            output.print_finding(
+                &duplicate.first_file,
+                func1.start_line as usize,
+                report.classify("templates", duplicate.result.similarity),
+                &message,
+                &duplicate.second_file,
+                func2.start_line as usize,
+            );
+        }
+        return Ok(duplicates.len());
+    }
+
+    println!("Checking scripts of {} template file(s)...", sources.len());
+    Ok(report_similar_template_functions(&duplicates, print))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_injections(
+    paths: &[String],
+    threshold: f64,
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+    print: bool,
+    output: &mut FindingOutput,
+    heatmap: &mut DuplicationHeatmap,
+    report: &RunReport,
+    budget: &ComparisonBudget,
+) -> anyhow::Result<usize> {
+    let exts: Vec<&str> = extensions
+        .map_or(INJECTION_HOST_EXTENSIONS.to_vec(), |v| v.iter().map(String::as_str).collect());
+    let exclude_matcher = create_exclude_matcher(exclude_patterns);
+    let mut sources = Vec::new();
+    for file in collect_files(paths, &exts)? {
+        let file_path = get_relative_path(&file.to_string_lossy());
+        if exclude_matcher.as_ref().is_some_and(|matcher| matcher.is_match(&file_path)) {
+            report.skip(&file_path, SkipReason::Excluded);
+            continue;
+        }
+        heatmap.add_file(&file_path);
+
+        match std::fs::read_to_string(&file) {
+            Ok(content) => sources.push((file_path, content)),
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file_path, e);
+                report.skip(&file_path, SkipReason::Unreadable(e.to_string()));
+            }
+        }
+    }
+
+    let mut total = 0;
+    for mut injection in builtin_injections().map_err(|e| anyhow::anyhow!(e))? {
+        let findings = run_analyzer_with_budget(&mut injection, &sources, threshold, budget)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        for finding in &findings {
+            let (first, second) = (&finding.first, &finding.second);
+            heatmap.record_pair(
+                "injections",
+                (&first.file, first.start_line as usize, first.end_line as usize),
+                (&second.file, second.start_line as usize, second.end_line as usize),
+            );
+        }
+        total += findings.len();
+
+        let language = injection.name().to_uppercase();
+        if output.format != OutputFormat::Standard {
+            for finding in &findings {
+                let (first, second) = (&finding.first, &finding.second);
+                let message = format!(
+                    "Embedded {} {} is {:.0}% similar to {}",
+                    language,
+                    first.name,
+                    finding.similarity * 100.0,
+                    second.name
+                );
+                output.print_finding(
+                    &first.file,
+                    first.start_line as usize,
+                    report.classify("injections", finding.similarity),
+                    &message,
+                    &second.file,
+                    second.start_line as usize,
+                );
+            }
+            continue;
+        }
+
+        let tags: Vec<String> =
+            injection.tags().iter().map(|tag| format!("{}`...`", tag)).collect();
+        println!("\nChecking {} of {} templates...", language, tags.join(", "));
+        injection.report(&findings, &mut std::io::stdout())?;
+        if print {
+            for finding in &findings {
+                for unit in [&finding.first, &finding.second] {
+                    similarity_core::cli_output::show_function_code(
+                        &unit.file,
+                        &unit.name,
+                        unit.start_line,
+                        unit.end_line,
+                    );
+                }
+            }
+        }
+    }
+    Ok(total)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_dependency_types(
+    paths: &[String],
+    packages: &[String],
+    threshold: f64,
+    extensions: Option<&Vec<String>>,
+    exclude_patterns: &[String],
+    print: bool,
+    output: &mut FindingOutput,
+    report: &RunReport,
+    budget: &ComparisonBudget,
+) -> anyhow::Result<usize> {
+    use similarity_core::{
+        declaration_files, extract_types_from_code, find_dependency_package,
+        find_dependency_type_matches, TypeComparisonOptions,
+    };
+
+    let mut local = Vec::new();
+    for (file_path, content) in collect_sources(paths, extensions, exclude_patterns, Some(report))?
+    {
+        match extract_types_from_code(&content, &file_path) {
+            Ok(types) => local.extend(types),
+            Err(e) => {
+                tracing::info!(file = %file_path, error = %e, "skipping file");
+                report.skip(&file_path, SkipReason::ParseError(e));
+            }
+        }
+    }
+
+    // node_modules is looked up from the analyzed paths, then the working directory
+    let starts: Vec<PathBuf> = paths
+        .iter()
+        .filter_map(|path| std::fs::canonicalize(path).ok())
+        .map(|path| if path.is_dir() { path } else { path.parent().unwrap_or(&path).to_path_buf() })
+        .chain(std::env::current_dir().ok())
+        .collect();
+    let mut dependencies = Vec::new();
+    let mut found = Vec::new();
+    for name in packages {
+        let Some(package) = starts.iter().find_map(|start| find_dependency_package(start, name))
+        else {
+            eprintln!("Warning: no declaration files of package '{}' found in node_modules", name);
+            continue;
+        };
+        for file in declaration_files(&package.dir) {
+            let file_path = get_relative_path(&file.to_string_lossy());
+            let Ok(content) = std::fs::read_to_string(&file) else {
+                continue;
+            };
+            match extract_types_from_code(&content, &file_path) {
+                Ok(types) => {
+                    dependencies.extend(types.into_iter().map(|t| (package.name.clone(), t)))
+                }
+                Err(e) => {
+                    tracing::info!(file = %file_path, error = %e, "skipping declaration file")
+                }
+            }
+        }
+        found.push(package.name);
+    }
+
+    let matches = find_dependency_type_matches(
+        &local,
+        &dependencies,
+        threshold,
+        &TypeComparisonOptions::default(),
+        budget,
+    );
+    let severities: Vec<Severity> =
+        matches.iter().map(|found| report.classify("types", found.similarity)).collect();
+
+    if output.format != OutputFormat::Standard {
+        for (found, severity) in matches.iter().zip(&severities) {
+            let message = format!(
+                "Type '{}' re-declares '{}' of {}; use {}",
+                found.local.name,
+                found.dependency.name,
+                found.package,
+                found.replacement()
+            );
+            output.print_finding(
+                &found.local.file_path,
+                found.local.start_line,
+                *severity,
+                &message,
+                &found.dependency.file_path,
+                found.dependency.start_line,
+            );
+        }
+        return Ok(matches.len());
+    }
+
+    println!(
+        "Checking {} local types against {} types of {}...",
+        local.len(),
+        dependencies.len(),
+        if found.is_empty() { "no packages".to_string() } else { found.join(", ") }
+    );
+    if matches.is_empty() {
+        println!("\nNo local types duplicating dependency types found!");
+        return Ok(0);
+    }
+
+    println!("\nLocal types duplicating dependency types:");
+    println!("{}", "-".repeat(60));
+    for found in &matches {
+        let (local, dependency) = (&found.local, &found.dependency);
+        match &found.picked {
+            Some(properties) => {
+                println!(
+                    "\nSubset: {} of {} properties",
+                    properties.len(),
+                    dependency.properties.len()
+                )
+            }
+            None => println!("\nSimilarity: {:.2}%", found.similarity * 100.0),
+        }
+        for (type_def, label) in [(local, "local"), (dependency, found.package.as_str())] {
+            println!(
+                "  {} ({})",
+                similarity_core::cli_output::format_function_output(
+                    &type_def.file_path,
+                    &type_def.name,
+                    type_def.start_line as u32,
+                    type_def.end_line as u32
+                ),
+                label
+            );
+        }
+        println!("  Use: {}", found.replacement());
+        println!("       {}", found.import());
+
+        if print {
+            for type_def in [local, dependency] {
+                similarity_core::cli_output::show_function_code(
+                    &type_def.file_path,
+                    &type_def.name,
+                    type_def.start_line as u32,
+                    type_def.end_line as u32,
+                );
+            }
+        }
+    }
+    println!("\n{}", "-".repeat(60));
+    println!("Total local types duplicating dependency types: {}", matches.len());
+    Ok(matches.len())
+}
+
+/// Analyzers a run executes, after presets are applied
+struct EnabledAnalyzers {
+    functions: bool,
+    types: bool,
+    classes: bool,
+    overlap: bool,
+    notebooks: bool,
+    hooks: bool,
+    tests: bool,
+    graphql: bool,
+    templates: bool,
+    injections: bool,
+    deps: bool,
+}
+
+impl EnabledAnalyzers {
+    fn from_cli(cli: &Cli) -> Self {
+        // --files-only, --against-index and --migration replace the other analyzers
+        let exclusive = cli.files_only || cli.against_index.is_some() || cli.migration;
+        // Only function comparisons are sharded; the first shard runs the rest
+        let unsharded = !exclusive && cli.shard.is_none_or(|shard| shard.is_first());
+        Self {
+            functions: !cli.no_functions && !cli.classes_only && !exclusive,
+            types: (cli.types && !cli.no_types) && !cli.classes_only && unsharded,
+            classes: (cli.classes || cli.classes_only) && unsharded,
+            overlap: cli.overlap && unsharded,
+            notebooks: cli.notebooks && unsharded,
+            hooks: cli.hooks && unsharded,
+            tests: cli.tests && unsharded,
+            graphql: cli.graphql && unsharded,
+            templates: cli.templates && unsharded,
+            injections: cli.injections && unsharded,
+            deps: !cli.against_deps.is_empty() && unsharded,
+        }
+    }
 
-    // Validate that at least one analyzer is enabled
-    if !functions_enabled && !types_enabled && !classes_enabled && !overlap_enabled {
-        eprintln!("Error: At least one analyzer must be enabled. Remove --no-types to enable type checking, use --classes for class checking, use --overlap for overlap detection, or remove --no-functions.");
-        return Err(anyhow::anyhow!("No analyzer enabled"));
+    fn names(&self) -> Vec<&'static str> {
+        [
+            (self.functions, "functions"),
+            (self.types, "types"),
+            (self.classes, "classes"),
+            (self.overlap, "overlap"),
+            (self.notebooks, "notebooks"),
+            (self.hooks, "hooks"),
+            (self.tests, "tests"),
+            (self.graphql, "graphql"),
+            (self.templates, "templates"),
+            (self.injections, "injections"),
+            (self.deps, "deps"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect()
     }
+}
 
-    // Handle mutual exclusion of min_lines and min_tokens
-    let (min_lines, min_tokens) = match (cli.min_lines, cli.min_tokens) {
-        (Some(_), Some(tokens)) => {
-            eprintln!(
-                "Warning: Both --min-lines and --min-tokens specified. Using --min-tokens={}",
-                tokens
-            );
-            (None, Some(tokens))
+/// Print the checks and effective settings of `similarity-ts doctor`; whether all checks passed
+fn run_doctor(args: &[String]) -> anyhow::Result<bool> {
+    let matches = Cli::command().try_get_matches_from(
+        std::iter::once("similarity-ts").chain(args.iter().map(String::as_str)),
+    )?;
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    apply_project_settings(&mut cli, &matches);
+    let mut healthy = true;
+    let mut report = |name: &str, result: Result<String, String>| match result {
+        Ok(detail) => println!("  ok    {:<12} {}", name, detail),
+        Err(error) => {
+            healthy = false;
+            println!("  FAIL  {:<12} {}", name, error);
         }
-        (lines, tokens) => (lines, tokens),
     };
 
-    println!("Analyzing code similarity...\n");
-
-    let separator = "-".repeat(60);
-    let mut total_duplicates = 0;
-
-    // Run functions analysis if enabled
-    if functions_enabled {
-        println!("=== Function Similarity ===");
-        let duplicate_count = check::check_paths(
-            cli.paths.clone(),
-            cli.threshold,
-            cli.rename_cost,
-            cli.extensions.as_ref(),
-            min_lines.unwrap_or(3),
-            min_tokens,
-            cli.no_size_penalty,
-            cli.print,
-            !cli.no_fast,
-            cli.filter_function.as_ref(),
-            cli.filter_function_body.as_ref(),
-            &cli.exclude,
-            cli.show_ignored,
-        )?;
-        total_duplicates += duplicate_count;
+    println!("similarity-ts {}\n", env!("CARGO_PKG_VERSION"));
+    println!("Parsers:");
+    for sample in PARSER_SAMPLES {
+        let extensions: Vec<String> =
+            sample.extensions.iter().map(|ext| format!(".{}", ext)).collect();
+        let detail = match sample.backend {
+            ParserBackend::Oxc => extensions.join(" "),
+            ParserBackend::TreeSitter(_) => {
+                format!("{} (tree-sitter; analyzed by similarity-generic)", extensions.join(" "))
+            }
+        };
+        report(sample.language, sample.check().map(|_| detail));
     }
-
-    // Run types analysis if enabled
-    if types_enabled && functions_enabled {
-        println!("\n{}\n", separator);
+    let graphql =
+        graphql::extract_graphql("sample.ts", "const q = gql`query User { user { id } }`;")
+            .and_then(|found| {
+                if found.is_empty() {
+                    Err("no operations found in the sample".to_string())
+                } else {
+                    Ok("gql`...`/graphql`...` templates (--graphql)".to_string())
+                }
+            });
+    report("GraphQL", graphql);
+    let notebook = similarity_core::parse_notebook(
+        r#"{"metadata":{"kernelspec":{"language":"typescript"}},"cells":[{"cell_type":"code","source":["const a = 1;"]}]}"#,
+    )
+    .map(|_| ".ipynb (--notebooks)".to_string());
+    report("Notebooks", notebook);
+    let templates = templates::extract_scripts("<script>function f() {}</script>");
+    report(
+        "Templates",
+        if templates.blocks == 1 {
+            Ok("<script> blocks of .ejs .hbs .erb .html (--templates)".to_string())
+        } else {
+            Err("no script found in the sample".to_string())
+        },
+    );
+    let injections = builtin_injections().map_err(|e| e.to_string()).map(|injections| {
+        let tags: Vec<String> = injections
+            .iter()
+            .flat_map(|injection| injection.tags())
+            .map(|tag| format!("{}`...`", tag))
+            .collect();
+        format!("{} templates (--injections)", tags.join(" "))
+    });
+    report("Injections", injections);
+
+    println!("\nDirectories:");
+    let state_dir = Path::new(DEFAULT_TRIAGE_PATH).parent().unwrap_or(Path::new("."));
+    report(
+        "State",
+        check_writable(state_dir)
+            .map(|()| format!("{} (triage, boilerplate, sessions)", state_dir.display())),
+    );
+    let cache = default_cache_dir()
+        .ok_or_else(|| "cannot determine the cache directory (no HOME)".to_string())
+        .and_then(|dir| check_writable(&dir).map(|()| format!("{} (--git-cache)", dir.display())));
+    report("Cache", cache);
+
+    let settings = [
+        (
+            "Config file",
+            PresetConfig::find_config_file().map_or_else(
+                || "none (no similarity.toml here or in a parent directory)".to_string(),
+                |path| path.display().to_string(),
+            ),
+        ),
+        ("Preset", cli.preset.map_or_else(|| "none".to_string(), |preset| preset.to_string())),
+        ("Paths", if cli.paths.is_empty() { ".".to_string() } else { cli.paths.join(", ") }),
+        (
+            "Extensions",
+            cli.extensions.as_ref().map_or_else(
+                || "ts, tsx, js, jsx, mjs, cjs, mts, cts".to_string(),
+                |extensions| extensions.join(", "),
+            ),
+        ),
+        ("Analyzers", EnabledAnalyzers::from_cli(&cli).names().join(", ")),
+        ("Threshold", cli.threshold.to_string()),
+        (
+            "Min size",
+            match (cli.min_tokens, cli.min_lines) {
+                (Some(tokens), _) => format!("{} tokens", tokens),
+                (None, Some(lines)) => format!("{} lines", lines),
+                (None, None) => "none".to_string(),
+            },
+        ),
+        ("Rename cost", cli.rename_cost.to_string()),
+        (
+            "Size penalty",
+            if cli.no_size_penalty {
+                "off".to_string()
+            } else {
+                let penalty = cli.size_penalty.resolve();
+                format!("{} curve, floor {}", penalty.curve, penalty.floor)
+            },
+        ),
+        (
+            "Exclude",
+            if cli.exclude.is_empty() { "none".to_string() } else { cli.exclude.join(", ") },
+        ),
+        ("Severity", format!("{} rule(s)", SeverityConfig::find_and_load().severity.len())),
+    ];
+    println!("\nEffective settings:");
+    for (name, value) in settings {
+        println!("  {:<13} {}", format!("{}:", name), value);
     }
 
-    if types_enabled {
-        println!("=== Type Similarity ===");
-        let type_duplicate_count = check_types(
-            cli.paths.clone(),
-            cli.threshold,
-            cli.extensions.as_ref(),
-            cli.print,
-            cli.types_only,
-            cli.interfaces_only,
-            cli.type_literals_only,
-            cli.allow_cross_kind,
-            cli.structural_weight,
-            cli.naming_weight,
-            include_type_literals,
-            unified_types_enabled,
-            &cli.exclude,
-            cli.use_structure_comparison,
-            cli.show_ignored,
-        )?;
-        total_duplicates += type_duplicate_count;
-    }
+    Ok(healthy)
+}
 
-    // Run class analysis if enabled
-    if classes_enabled && (functions_enabled || types_enabled) {
-        println!("\n{}\n", separator);
+/// Apply the preset and the top-level keys of similarity.toml to every setting
+/// not given on the command line
+fn apply_project_settings(cli: &mut Cli, matches: &clap::ArgMatches) {
+    let settings = PresetConfig::find_and_load().resolve(cli.preset);
+    let explicit = |ids: &[&str]| {
+        ids.iter().any(|id| matches.value_source(id) == Some(ValueSource::CommandLine))
+    };
+    cli.preset = settings.preset;
+    if let Some(preset) = settings.preset {
+        tracing::info!(%preset, "using preset");
     }
 
-    if classes_enabled {
-        println!("=== Class Similarity ===");
-        let class_duplicate_count = check_classes(
-            cli.paths.clone(),
-            cli.threshold,
-            cli.extensions.as_ref(),
-            cli.print,
-            !cli.include_inheritance,
-            !cli.include_implements,
-            cli.suggest,
-            &cli.exclude,
-            cli.show_ignored,
-        )?;
-        total_duplicates += class_duplicate_count;
+    if let (Some(threshold), false) = (settings.threshold, explicit(&["threshold"])) {
+        cli.threshold = threshold;
     }
-
-    // Run overlap analysis if enabled
-    if overlap_enabled && (functions_enabled || types_enabled || classes_enabled) {
-        println!("\n{}\n", separator);
+    if let (Some(min_lines), false) = (settings.min_lines, explicit(&["min_lines"])) {
+        cli.min_lines = Some(min_lines);
     }
+    cli.exclude.extend(settings.exclude.iter().cloned());
 
-    if overlap_enabled {
-        println!("=== Overlap Detection ===");
-        let overlap_duplicate_count = check_overlaps(
-            cli.paths,
-            cli.threshold,
-            cli.extensions.as_ref(),
-            cli.print,
-            cli.overlap_min_window,
-            cli.overlap_max_window,
-            cli.overlap_size_tolerance,
-            &cli.exclude,
-        )?;
-        total_duplicates += overlap_duplicate_count;
+    if let (Some(enabled), false) =
+        (settings.enables(PresetAnalyzer::Functions), explicit(&["no_functions"]))
+    {
+        cli.no_functions = !enabled;
     }
-
-    // Exit with code 1 if duplicates found and --fail-on-duplicates is set
-    if cli.fail_on_duplicates && total_duplicates > 0 {
-        std::process::exit(1);
+    if let (Some(enabled), false) =
+        (settings.enables(PresetAnalyzer::Types), explicit(&["types", "no_types"]))
+    {
+        cli.types = enabled;
+        cli.no_types = !enabled;
+    }
+    if let (Some(enabled), false) =
+        (settings.enables(PresetAnalyzer::Classes), explicit(&["classes", "classes_only"]))
+    {
+        cli.classes = enabled;
+    }
+    for (analyzer, flag, id) in [
+        (PresetAnalyzer::Hooks, &mut cli.hooks, "hooks"),
+        (PresetAnalyzer::Tests, &mut cli.tests, "tests"),
+        (PresetAnalyzer::Graphql, &mut cli.graphql, "graphql"),
+    ] {
+        if let (Some(enabled), false) = (settings.enables(analyzer), explicit(&[id])) {
+            *flag = enabled;
+        }
     }
-
-    Ok(())
 }
 
 fn create_exclude_matcher(exclude_patterns: &[String]) -> Option<globset::GlobSet> {
@@ -328,6 +3172,7 @@ fn check_types(
     threshold: f64,
     extensions: Option<&Vec<String>>,
     print: bool,
+    diff: bool,
     types_only: bool,
     interfaces_only: bool,
     type_literals_only: bool,
@@ -338,13 +3183,22 @@ fn check_types(
     unified_types: bool,
     exclude_patterns: &[String],
     use_structure_comparison: bool,
+    member_comparison: MemberComparisonStrategy,
+    size_ratio_penalty: SizePenalty,
     show_ignored: bool,
+    output: &mut FindingOutput,
+    heatmap: &mut DuplicationHeatmap,
+    matrix: Option<&MatrixExporter>,
+    report: &RunReport,
+    budget: &ComparisonBudget,
 ) -> anyhow::Result<usize> {
-    use ignore::WalkBuilder;
+    use similarity_core::cli_file_utils::walk_dir;
     use similarity_core::{
-        extract_type_literals_from_code, extract_types_from_code, find_similar_type_literals,
-        find_similar_types, find_similar_unified_types, find_similar_unified_types_structured,
-        ComparisonOptions, TypeComparisonOptions, TypeKind, UnifiedType,
+        extract_type_literals_from_code, extract_types_from_code,
+        find_similar_type_literals_with_budget, find_similar_types_with_budget,
+        find_similar_unified_types_structured_with_budget, find_similar_unified_types_with_budget,
+        ComparisonOptions, ImportGraph, SemanticTypeConfig, TypeComparisonOptions, TypeKind,
+        UnifiedType,
     };
     use std::collections::HashSet;
     use std::fs;
@@ -372,17 +3226,14 @@ fn check_types(
                                 files.push(path.to_path_buf());
                             }
                         }
+                    } else {
+                        report.skip(path_str, SkipReason::UnsupportedExtension);
                     }
                 }
             }
         } else if path.is_dir() {
-            // If it's a directory, walk it respecting .gitignore
-            let walker = WalkBuilder::new(path)
-                .follow_links(false)
-                .git_ignore(true) // Respect .gitignore files
-                .git_global(true) // Respect global gitignore
-                .git_exclude(true) // Respect .git/info/exclude
-                .build();
+            // If it's a directory, walk it respecting .gitignore and .similarityignore
+            let walker = walk_dir(path);
 
             for entry in walker {
                 let entry = entry?;
@@ -397,6 +3248,7 @@ fn check_types(
                 if let Some(ref matcher) = exclude_matcher {
                     // Check both the full path and relative path from the search root
                     if matcher.is_match(entry_path) {
+                        report.skip(&entry_path.display().to_string(), SkipReason::Excluded);
                         continue;
                     }
 
@@ -404,6 +3256,8 @@ fn check_types(
                     if let Ok(current_dir) = std::env::current_dir() {
                         if let Ok(relative) = entry_path.strip_prefix(&current_dir) {
                             if matcher.is_match(relative) {
+                                report
+                                    .skip(&entry_path.display().to_string(), SkipReason::Excluded);
                                 continue;
                             }
                         }
@@ -429,12 +3283,22 @@ fn check_types(
         }
     }
 
+    let standard_output = output.format == OutputFormat::Standard;
+
     if files.is_empty() {
-        println!("No TypeScript files found in specified paths");
+        if standard_output {
+            println!("No TypeScript files found in specified paths");
+        }
         return Ok(0);
     }
 
-    println!("Checking {} files for similar types...\n", files.len());
+    if standard_output {
+        println!("Checking {} files for similar types...\n", files.len());
+    }
+
+    for file in &files {
+        heatmap.add_file(&get_relative_path(&file.to_string_lossy()));
+    }
 
     // Extract types from all files
     let mut all_types = Vec::new();
@@ -450,10 +3314,13 @@ fn check_types(
                 if !type_literals_only {
                     match extract_types_from_code(&content, &file_str) {
                         Ok(mut types) => {
+                            if types.iter().any(|ty| ty.partial) {
+                                report.mark_partial(&file.display().to_string());
+                            }
                             if show_ignored {
                                 ignored_types.extend(
                                     types.iter().filter(|ty| ty.has_ignore_directive).map(|ty| {
-                                        (file.display().to_string(), ty.name.clone(), ty.start_line)
+                                        (file.display().to_string(), ty.name, ty.start_line)
                                     }),
                                 );
                             }
@@ -468,8 +3335,10 @@ fn check_types(
                             all_types.extend(types);
                         }
                         Err(e) => {
-                            // Skip files with parse errors silently
-                            if !e.contains("Parse errors:") {
+                            if e.contains("Parse errors:") {
+                                tracing::info!(file = %file.display(), error = %e, "skipping file");
+                                report.skip(&file.display().to_string(), SkipReason::ParseError(e));
+                            } else {
                                 eprintln!("Error in {}: {}", file.display(), e);
                             }
                         }
@@ -480,12 +3349,18 @@ fn check_types(
                 if include_type_literals {
                     match extract_type_literals_from_code(&content, &file_str) {
                         Ok(type_literals) => {
+                            if type_literals.iter().any(|literal| literal.partial) {
+                                report.mark_partial(&file.display().to_string());
+                            }
                             all_type_literals.extend(type_literals);
                         }
                         Err(e) => {
-                            // Skip files with parse errors silently
                             if !e.contains("Parse errors:") {
                                 eprintln!("Error in {}: {}", file.display(), e);
+                            } else if type_literals_only {
+                                // Otherwise already logged by the type extraction
+                                tracing::info!(file = %file.display(), error = %e, "skipping file");
+                                report.skip(&file.display().to_string(), SkipReason::ParseError(e));
                             }
                         }
                     }
@@ -493,20 +3368,25 @@ fn check_types(
             }
             Err(e) => {
                 eprintln!("Error reading {}: {}", file.display(), e);
+                report.skip(&file.display().to_string(), SkipReason::Unreadable(e.to_string()));
             }
         }
     }
 
     if all_types.is_empty() && all_type_literals.is_empty() {
-        println!("No type definitions or type literals found!");
+        if standard_output {
+            println!("No type definitions or type literals found!");
+        }
         return Ok(0);
     }
 
-    println!("Found {} type definitions", all_types.len());
-    if include_type_literals {
-        println!("Found {} type literals", all_type_literals.len());
+    if standard_output {
+        println!("Found {} type definitions", all_types.len());
+        if include_type_literals {
+            println!("Found {} type literals", all_type_literals.len());
+        }
     }
-    if show_ignored && !ignored_types.is_empty() {
+    if standard_output && show_ignored && !ignored_types.is_empty() {
         println!("Ignored {} type(s) via similarity-ignore directive:", ignored_types.len());
         for (file, name, line) in &ignored_types {
             println!("  {}:{} {}", file, line, name);
@@ -526,85 +3406,275 @@ fn check_types(
         eprintln!("Warning: structural_weight + naming_weight should equal 1.0");
     }
 
+    // Pairs down to the matrix floor are compared, then recorded and filtered
+    let comparison_threshold = matrix.map_or(threshold, |m| m.comparison_threshold(threshold));
+
     // Handle unified type comparison if enabled
-    let (similar_pairs, type_literal_pairs, type_literal_to_literal_pairs) = if unified_types {
-        // Use unified comparison that combines all types
-        let unified_pairs = if use_structure_comparison {
-            // Use new generalized structure comparison framework
-            let structure_options = ComparisonOptions {
-                name_weight: naming_weight,
-                structure_weight: structural_weight,
-                threshold,
-                ..Default::default()
+    let (mut similar_pairs, mut type_literal_pairs, mut type_literal_to_literal_pairs) =
+        if unified_types {
+            // Use unified comparison that combines all types
+            let unified_pairs = if use_structure_comparison {
+                // Use new generalized structure comparison framework
+                let structure_options = ComparisonOptions {
+                    name_weight: naming_weight,
+                    structure_weight: structural_weight,
+                    threshold: comparison_threshold,
+                    member_comparison,
+                    type_synonyms: SemanticTypeConfig::synonyms_for(member_comparison),
+                    size_ratio_penalty,
+                    ..Default::default()
+                };
+                find_similar_unified_types_structured_with_budget(
+                    &all_types,
+                    &all_type_literals,
+                    comparison_threshold,
+                    Some(structure_options),
+                    budget,
+                )
+            } else {
+                // Use existing comparison method
+                find_similar_unified_types_with_budget(
+                    &all_types,
+                    &all_type_literals,
+                    comparison_threshold,
+                    &options,
+                    budget,
+                )
             };
-            find_similar_unified_types_structured(
-                &all_types,
-                &all_type_literals,
-                threshold,
-                Some(structure_options),
-            )
-        } else {
-            // Use existing comparison method
-            find_similar_unified_types(&all_types, &all_type_literals, threshold, &options)
-        };
 
-        // Convert unified pairs to the existing format for display (for now)
-        let mut regular_pairs = Vec::new();
-        let mut literal_to_def_pairs = Vec::new();
-        let mut literal_to_literal_pairs = Vec::new();
-
-        for pair in unified_pairs {
-            match (&pair.type1, &pair.type2) {
-                (UnifiedType::TypeDef(def1), UnifiedType::TypeDef(def2)) => {
-                    regular_pairs.push(similarity_core::SimilarTypePair {
-                        type1: def1.clone(),
-                        type2: def2.clone(),
-                        result: pair.result,
-                    });
-                }
-                (UnifiedType::TypeLiteral(lit), UnifiedType::TypeDef(def))
-                | (UnifiedType::TypeDef(def), UnifiedType::TypeLiteral(lit)) => {
-                    literal_to_def_pairs.push(similarity_core::TypeLiteralComparisonPair {
-                        type_literal: lit.clone(),
-                        type_definition: def.clone(),
-                        result: pair.result,
-                    });
-                }
-                (UnifiedType::TypeLiteral(lit1), UnifiedType::TypeLiteral(lit2)) => {
-                    literal_to_literal_pairs.push((lit1.clone(), lit2.clone(), pair.result));
+            // Convert unified pairs to the existing format for display (for now)
+            let mut regular_pairs = Vec::new();
+            let mut literal_to_def_pairs = Vec::new();
+            let mut literal_to_literal_pairs = Vec::new();
+
+            for pair in unified_pairs {
+                match (&pair.type1, &pair.type2) {
+                    (UnifiedType::TypeDef(def1), UnifiedType::TypeDef(def2)) => {
+                        regular_pairs.push(similarity_core::SimilarTypePair {
+                            type1: def1.clone(),
+                            type2: def2.clone(),
+                            result: pair.result,
+                        });
+                    }
+                    (UnifiedType::TypeLiteral(lit), UnifiedType::TypeDef(def))
+                    | (UnifiedType::TypeDef(def), UnifiedType::TypeLiteral(lit)) => {
+                        literal_to_def_pairs.push(similarity_core::TypeLiteralComparisonPair {
+                            type_literal: lit.clone(),
+                            type_definition: def.clone(),
+                            result: pair.result,
+                        });
+                    }
+                    (UnifiedType::TypeLiteral(lit1), UnifiedType::TypeLiteral(lit2)) => {
+                        literal_to_literal_pairs.push((lit1.clone(), lit2.clone(), pair.result));
+                    }
                 }
             }
-        }
 
-        (regular_pairs, literal_to_def_pairs, literal_to_literal_pairs)
-    } else {
-        // Use existing separate comparison methods
-        let similar_pairs = if type_literals_only {
-            Vec::new()
+            (regular_pairs, literal_to_def_pairs, literal_to_literal_pairs)
         } else {
-            find_similar_types(&all_types, threshold, &options)
-        };
+            // Use existing separate comparison methods
+            let similar_pairs = if type_literals_only {
+                Vec::new()
+            } else {
+                find_similar_types_with_budget(&all_types, comparison_threshold, &options, budget)
+            };
 
-        let type_literal_pairs = if include_type_literals && !type_literals_only {
-            find_similar_type_literals(&all_type_literals, &all_types, threshold, &options)
-        } else {
-            Vec::new()
-        };
+            let type_literal_pairs = if include_type_literals && !type_literals_only {
+                find_similar_type_literals_with_budget(
+                    &all_type_literals,
+                    &all_types,
+                    comparison_threshold,
+                    &options,
+                    budget,
+                )
+            } else {
+                Vec::new()
+            };
 
-        let type_literal_to_literal_pairs = if include_type_literals {
-            similarity_core::find_similar_type_literals_pairs(
-                &all_type_literals,
-                threshold,
-                &options,
-            )
-        } else {
-            Vec::new()
+            let type_literal_to_literal_pairs = if include_type_literals {
+                similarity_core::find_similar_type_literals_pairs_with_budget(
+                    &all_type_literals,
+                    comparison_threshold,
+                    &options,
+                    budget,
+                )
+            } else {
+                Vec::new()
+            };
+
+            (similar_pairs, type_literal_pairs, type_literal_to_literal_pairs)
         };
 
-        (similar_pairs, type_literal_pairs, type_literal_to_literal_pairs)
-    };
+    if let Some(matrix) = matrix {
+        for pair in &similar_pairs {
+            matrix.record(
+                "type",
+                (
+                    &pair.type1.file_path,
+                    &pair.type1.name,
+                    pair.type1.start_line,
+                    pair.type1.end_line,
+                ),
+                (
+                    &pair.type2.file_path,
+                    &pair.type2.name,
+                    pair.type2.start_line,
+                    pair.type2.end_line,
+                ),
+                pair.result.similarity,
+            );
+        }
+        for pair in &type_literal_pairs {
+            let (literal, definition) = (&pair.type_literal, &pair.type_definition);
+            matrix.record(
+                "type",
+                (&literal.file_path, &literal.name, literal.start_line, literal.end_line),
+                (
+                    &definition.file_path,
+                    &definition.name,
+                    definition.start_line,
+                    definition.end_line,
+                ),
+                pair.result.similarity,
+            );
+        }
+        for (literal1, literal2, result) in &type_literal_to_literal_pairs {
+            matrix.record(
+                "type",
+                (&literal1.file_path, &literal1.name, literal1.start_line, literal1.end_line),
+                (&literal2.file_path, &literal2.name, literal2.start_line, literal2.end_line),
+                result.similarity,
+            );
+        }
+        similar_pairs.retain(|pair| pair.result.similarity >= threshold);
+        type_literal_pairs.retain(|pair| pair.result.similarity >= threshold);
+        type_literal_to_literal_pairs.retain(|(_, _, result)| result.similarity >= threshold);
+    }
+
+    similar_pairs.retain(|pair| {
+        !report.suppress(
+            "types",
+            (Path::new(&pair.type1.file_path), &pair.type1.name),
+            (Path::new(&pair.type2.file_path), &pair.type2.name),
+        )
+    });
+    type_literal_pairs.retain(|pair| {
+        !report.suppress(
+            "types",
+            (Path::new(&pair.type_literal.file_path), &pair.type_literal.name),
+            (Path::new(&pair.type_definition.file_path), &pair.type_definition.name),
+        )
+    });
+    type_literal_to_literal_pairs.retain(|(literal1, literal2, _)| {
+        !report.suppress(
+            "types",
+            (Path::new(&literal1.file_path), &literal1.name),
+            (Path::new(&literal2.file_path), &literal2.name),
+        )
+    });
+
+    for pair in &similar_pairs {
+        heatmap.record_pair(
+            "types",
+            (&get_relative_path(&pair.type1.file_path), pair.type1.start_line, pair.type1.end_line),
+            (&get_relative_path(&pair.type2.file_path), pair.type2.start_line, pair.type2.end_line),
+        );
+    }
+    for pair in &type_literal_pairs {
+        heatmap.record_pair(
+            "types",
+            (
+                &get_relative_path(&pair.type_literal.file_path),
+                pair.type_literal.start_line,
+                pair.type_literal.end_line,
+            ),
+            (
+                &get_relative_path(&pair.type_definition.file_path),
+                pair.type_definition.start_line,
+                pair.type_definition.end_line,
+            ),
+        );
+    }
+    for (literal1, literal2, _) in &type_literal_to_literal_pairs {
+        heatmap.record_pair(
+            "types",
+            (&get_relative_path(&literal1.file_path), literal1.start_line, literal1.end_line),
+            (&get_relative_path(&literal2.file_path), literal2.start_line, literal2.end_line),
+        );
+    }
+
+    let type_severities: Vec<Severity> =
+        similar_pairs.iter().map(|pair| report.classify("types", pair.result.similarity)).collect();
+    let literal_severities: Vec<Severity> = type_literal_pairs
+        .iter()
+        .map(|pair| report.classify("types", pair.result.similarity))
+        .collect();
+    let literal_pair_severities: Vec<Severity> = type_literal_to_literal_pairs
+        .iter()
+        .map(|(_, _, result)| report.classify("types", result.similarity))
+        .collect();
+
+    if output.format != OutputFormat::Standard {
+        for (pair, severity) in similar_pairs.iter().zip(&type_severities) {
+            let message = format!(
+                "Type '{}' is {:.0}% similar to '{}'",
+                pair.type1.name,
+                pair.result.similarity * 100.0,
+                pair.type2.name
+            );
+            print_type_finding(
+                output,
+                *severity,
+                &message,
+                (&get_relative_path(&pair.type1.file_path), pair.type1.start_line),
+                (&get_relative_path(&pair.type2.file_path), pair.type2.start_line),
+                diff.then_some((&pair.type1.properties, &pair.type2.properties, &pair.result)),
+            );
+        }
+
+        for (pair, severity) in type_literal_pairs.iter().zip(&literal_severities) {
+            let message = format!(
+                "Type literal '{}' is {:.0}% similar to '{}'",
+                pair.type_literal.name,
+                pair.result.similarity * 100.0,
+                pair.type_definition.name
+            );
+            print_type_finding(
+                output,
+                *severity,
+                &message,
+                (&get_relative_path(&pair.type_literal.file_path), pair.type_literal.start_line),
+                (
+                    &get_relative_path(&pair.type_definition.file_path),
+                    pair.type_definition.start_line,
+                ),
+                diff.then_some((
+                    &pair.type_literal.properties,
+                    &pair.type_definition.properties,
+                    &pair.result,
+                )),
+            );
+        }
 
-    if similar_pairs.is_empty()
+        for ((literal1, literal2, result), severity) in
+            type_literal_to_literal_pairs.iter().zip(&literal_pair_severities)
+        {
+            let message = format!(
+                "Type literal '{}' is {:.0}% similar to '{}'",
+                literal1.name,
+                result.similarity * 100.0,
+                literal2.name
+            );
+            print_type_finding(
+                output,
+                *severity,
+                &message,
+                (&get_relative_path(&literal1.file_path), literal1.start_line),
+                (&get_relative_path(&literal2.file_path), literal2.start_line),
+                diff.then_some((&literal1.properties, &literal2.properties, result)),
+            );
+        }
+    } else if similar_pairs.is_empty()
         && type_literal_pairs.is_empty()
         && type_literal_to_literal_pairs.is_empty()
     {
@@ -614,6 +3684,7 @@ fn check_types(
             println!("\nSimilar types found:");
             println!("{}", "-".repeat(60));
 
+            let import_graph = ImportGraph::build(&files);
             for pair in &similar_pairs {
                 // Get relative paths
                 let relative_path1 = get_relative_path(&pair.type1.file_path);
@@ -626,28 +3697,55 @@ fn check_types(
                     pair.result.naming_similarity * 100.0
                 );
                 println!(
-                    "  {}:{} | L{}-{} similar-type: {} ({})",
+                    "  {}:{} | L{}-{} similar-type: {} ({}){}",
                     relative_path1,
                     pair.type1.start_line,
                     pair.type1.start_line,
                     pair.type1.end_line,
                     pair.type1.name,
-                    format_type_kind(&pair.type1.kind)
+                    format_type_kind(&pair.type1.kind),
+                    partial_marker(pair.type1.partial)
                 );
                 println!(
-                    "  {}:{} | L{}-{} similar-type: {} ({})",
+                    "  {}:{} | L{}-{} similar-type: {} ({}){}",
                     relative_path2,
                     pair.type2.start_line,
                     pair.type2.start_line,
                     pair.type2.end_line,
                     pair.type2.name,
-                    format_type_kind(&pair.type2.kind)
+                    format_type_kind(&pair.type2.kind),
+                    partial_marker(pair.type2.partial)
                 );
+                if let Some(found) = import_graph.import_instead(
+                    (Path::new(&pair.type1.file_path), &pair.type1.name),
+                    (Path::new(&pair.type2.file_path), &pair.type2.name),
+                ) {
+                    let (copy_path, copy) = if found.original_is_first {
+                        (&relative_path2, &pair.type2)
+                    } else {
+                        (&relative_path1, &pair.type1)
+                    };
+                    println!(
+                        "  Should import instead of duplicate: '{}' in {} copies an exported type; use `{}`",
+                        copy.name,
+                        copy_path,
+                        found.statement(&copy.name, true)
+                    );
+                }
 
                 if print {
                     show_type_details(&pair.type1);
                     show_type_details(&pair.type2);
-                    show_comparison_details(&pair.result);
+                    if !diff {
+                        show_comparison_details(&pair.result);
+                    }
+                }
+                if diff {
+                    show_property_diff(
+                        &pair.type1.properties,
+                        &pair.type2.properties,
+                        &pair.result,
+                    );
                 }
             }
 
@@ -669,26 +3767,37 @@ fn check_types(
                     pair.result.naming_similarity * 100.0
                 );
                 println!(
-                    "  {}:{} | L{} similar-type-literal: {}",
+                    "  {}:{} | L{} similar-type-literal: {}{}",
                     literal_path,
                     pair.type_literal.start_line,
                     pair.type_literal.start_line,
-                    pair.type_literal.name
+                    pair.type_literal.name,
+                    partial_marker(pair.type_literal.partial)
                 );
                 println!(
-                    "  {}:{} | L{}-{} similar-type: {} ({})",
+                    "  {}:{} | L{}-{} similar-type: {} ({}){}",
                     def_path,
                     pair.type_definition.start_line,
                     pair.type_definition.start_line,
                     pair.type_definition.end_line,
                     pair.type_definition.name,
-                    format_type_kind(&pair.type_definition.kind)
+                    format_type_kind(&pair.type_definition.kind),
+                    partial_marker(pair.type_definition.partial)
                 );
 
                 if print {
                     show_type_literal_details(&pair.type_literal);
                     show_type_details(&pair.type_definition);
-                    show_comparison_details(&pair.result);
+                    if !diff {
+                        show_comparison_details(&pair.result);
+                    }
+                }
+                if diff {
+                    show_property_diff(
+                        &pair.type_literal.properties,
+                        &pair.type_definition.properties,
+                        &pair.result,
+                    );
                 }
             }
 
@@ -710,18 +3819,31 @@ fn check_types(
                     result.naming_similarity * 100.0
                 );
                 println!(
-                    "  {}:{} | L{} type-literal: {}",
-                    path1, literal1.start_line, literal1.start_line, literal1.name
+                    "  {}:{} | L{} type-literal: {}{}",
+                    path1,
+                    literal1.start_line,
+                    literal1.start_line,
+                    literal1.name,
+                    partial_marker(literal1.partial)
                 );
                 println!(
-                    "  {}:{} | L{} type-literal: {}",
-                    path2, literal2.start_line, literal2.start_line, literal2.name
+                    "  {}:{} | L{} type-literal: {}{}",
+                    path2,
+                    literal2.start_line,
+                    literal2.start_line,
+                    literal2.name,
+                    partial_marker(literal2.partial)
                 );
 
                 if print {
                     show_type_literal_details(literal1);
                     show_type_literal_details(literal2);
-                    show_comparison_details(result);
+                    if !diff {
+                        show_comparison_details(result);
+                    }
+                }
+                if diff {
+                    show_property_diff(&literal1.properties, &literal2.properties, result);
                 }
             }
 
@@ -747,6 +3869,15 @@ fn get_relative_path(file_path: &str) -> String {
     }
 }
 
+/// Marks definitions from files with syntax errors, where some code was not compared
+fn partial_marker(partial: bool) -> &'static str {
+    if partial {
+        " (partial)"
+    } else {
+        ""
+    }
+}
+
 fn format_type_kind(kind: &similarity_core::TypeKind) -> &'static str {
     match kind {
         similarity_core::TypeKind::Interface => "interface",
@@ -818,8 +3949,11 @@ fn check_overlaps(
     max_window_size: u32,
     size_tolerance: f64,
     exclude_patterns: &[String],
+    output: &mut FindingOutput,
+    heatmap: &mut DuplicationHeatmap,
+    report: &RunReport,
 ) -> anyhow::Result<usize> {
-    use ignore::WalkBuilder;
+    use similarity_core::cli_file_utils::walk_dir;
     use similarity_core::{find_overlaps_across_files, OverlapOptions};
     use std::collections::{HashMap, HashSet};
     use std::fs;
@@ -847,17 +3981,14 @@ fn check_overlaps(
                                 files.push(path.to_path_buf());
                             }
                         }
+                    } else {
+                        report.skip(path_str, SkipReason::UnsupportedExtension);
                     }
                 }
             }
         } else if path.is_dir() {
-            // If it's a directory, walk it respecting .gitignore
-            let walker = WalkBuilder::new(path)
-                .follow_links(false)
-                .git_ignore(true) // Respect .gitignore files
-                .git_global(true) // Respect global gitignore
-                .git_exclude(true) // Respect .git/info/exclude
-                .build();
+            // If it's a directory, walk it respecting .gitignore and .similarityignore
+            let walker = walk_dir(path);
 
             for entry in walker {
                 let entry = entry?;
@@ -872,6 +4003,7 @@ fn check_overlaps(
                 if let Some(ref matcher) = exclude_matcher {
                     // Check both the full path and relative path from the search root
                     if matcher.is_match(entry_path) {
+                        report.skip(&entry_path.display().to_string(), SkipReason::Excluded);
                         continue;
                     }
 
@@ -879,6 +4011,8 @@ fn check_overlaps(
                     if let Ok(current_dir) = std::env::current_dir() {
                         if let Ok(relative) = entry_path.strip_prefix(&current_dir) {
                             if matcher.is_match(relative) {
+                                report
+                                    .skip(&entry_path.display().to_string(), SkipReason::Excluded);
                                 continue;
                             }
                         }
@@ -905,11 +4039,19 @@ fn check_overlaps(
     }
 
     if files.is_empty() {
-        println!("No JavaScript/TypeScript files found in specified paths");
+        if output.format == OutputFormat::Standard {
+            println!("No JavaScript/TypeScript files found in specified paths");
+        }
         return Ok(0);
     }
 
-    println!("Checking {} files for overlapping code...\n", files.len());
+    if output.format == OutputFormat::Standard {
+        println!("Checking {} files for overlapping code...\n", files.len());
+    }
+
+    for file in &files {
+        heatmap.add_file(&get_relative_path(&file.to_string_lossy()));
+    }
 
     // Read all file contents
     let mut file_contents = HashMap::new();
@@ -917,10 +4059,19 @@ fn check_overlaps(
         match fs::read_to_string(file) {
             Ok(content) => {
                 let file_str = file.to_string_lossy().to_string();
+                // Files with parse errors have no overlaps, but should not go unnoticed
+                if let Err(e) = similarity_core::extract_functions(&file_str, &content) {
+                    if e.contains("Parse errors:") {
+                        tracing::info!(file = %file.display(), error = %e, "skipping file");
+                        report.skip(&file.display().to_string(), SkipReason::ParseError(e));
+                        continue;
+                    }
+                }
                 file_contents.insert(file_str, content);
             }
             Err(e) => {
                 eprintln!("Error reading {}: {}", file.display(), e);
+                report.skip(&file.display().to_string(), SkipReason::Unreadable(e.to_string()));
             }
         }
     }
@@ -931,7 +4082,49 @@ fn check_overlaps(
     // Find overlaps
     let overlaps = find_overlaps_across_files(&file_contents, &options)?;
 
-    if overlaps.is_empty() {
+    for overlap_with_files in &overlaps {
+        let overlap = &overlap_with_files.overlap;
+        heatmap.record_pair(
+            "overlaps",
+            (
+                &get_relative_path(&overlap_with_files.source_file),
+                overlap.source_lines.0 as usize,
+                overlap.source_lines.1 as usize,
+            ),
+            (
+                &get_relative_path(&overlap_with_files.target_file),
+                overlap.target_lines.0 as usize,
+                overlap.target_lines.1 as usize,
+            ),
+        );
+    }
+
+    let severities: Vec<Severity> = overlaps
+        .iter()
+        .map(|overlap_with_files| {
+            report.classify("overlaps", overlap_with_files.overlap.similarity)
+        })
+        .collect();
+
+    if output.format != OutputFormat::Standard {
+        for (overlap_with_files, severity) in overlaps.iter().zip(&severities) {
+            let overlap = &overlap_with_files.overlap;
+            let message = format!(
+                "Code in '{}' is {:.0}% similar to code in '{}'",
+                overlap.source_function,
+                overlap.similarity * 100.0,
+                overlap.target_function
+            );
+            output.print_finding(
+                &get_relative_path(&overlap_with_files.source_file),
+                overlap.source_lines.0 as usize,
+                *severity,
+                &message,
+                &get_relative_path(&overlap_with_files.target_file),
+                overlap.target_lines.0 as usize,
+            );
+        }
+    } else if overlaps.is_empty() {
         println!("\nNo code overlaps found!");
     } else {
         println!("\nCode overlaps found:");
@@ -1011,6 +4204,72 @@ fn extract_code_lines(code: &str, start_line: u32, end_line: u32) -> Result<Stri
     Ok(lines[start..end].join("\n"))
 }
 
+/// Print a type finding in a machine-readable format; JSON findings carry the
+/// property diff of the pair when asked for with `--diff`
+fn print_type_finding(
+    output: &mut FindingOutput,
+    severity: Severity,
+    message: &str,
+    (file, line): (&str, usize),
+    (other_file, other_line): (&str, usize),
+    property_diff: Option<(
+        &[similarity_core::PropertyDefinition],
+        &[similarity_core::PropertyDefinition],
+        &similarity_core::TypeComparisonResult,
+    )>,
+) {
+    let mut finding = similarity_core::cli_output::json_finding(
+        file,
+        line,
+        severity,
+        message,
+        other_file,
+        other_line,
+        &[],
+    );
+    finding.property_diff = property_diff.map(|(props1, props2, result)| {
+        similarity_core::diff_type_properties(props1, props2, result)
+    });
+    output.print_detailed(&finding);
+}
+
+/// Render an aligned, colored diff of the properties of two similar types
+fn show_property_diff(
+    props1: &[similarity_core::PropertyDefinition],
+    props2: &[similarity_core::PropertyDefinition],
+    result: &similarity_core::TypeComparisonResult,
+) {
+    use similarity_core::PropertyDiffKind;
+
+    let entries = similarity_core::diff_type_properties(props1, props2, result);
+    let width = entries
+        .iter()
+        .filter_map(|entry| entry.left.as_ref().map(|left| left.chars().count()))
+        .max()
+        .unwrap_or(0);
+
+    println!("\n\x1b[36m--- Property diff ---\x1b[0m");
+    for entry in &entries {
+        let (marker, color) = match entry.kind {
+            PropertyDiffKind::Matched => (' ', None),
+            PropertyDiffKind::TypeMismatch => ('~', Some("\x1b[33m")),
+            PropertyDiffKind::Missing => ('-', Some("\x1b[31m")),
+            PropertyDiffKind::Extra => ('+', Some("\x1b[32m")),
+        };
+        let line = format!(
+            "{} {:<width$} | {}",
+            marker,
+            entry.left.as_deref().unwrap_or(""),
+            entry.right.as_deref().unwrap_or(""),
+            width = width
+        );
+        match color {
+            Some(color) => println!("{}{}\x1b[0m", color, line.trim_end()),
+            None => println!("{}", line.trim_end()),
+        }
+    }
+}
+
 fn show_comparison_details(result: &similarity_core::TypeComparisonResult) {
     if !result.differences.missing_properties.is_empty() {
         println!("Missing properties: {}", result.differences.missing_properties.join(", "));
@@ -1043,12 +4302,17 @@ fn check_classes(
     print: bool,
     no_inheritance: bool,
     no_implements: bool,
+    comparison_options: &similarity_core::ClassComparisonOptions,
     suggest: bool,
     exclude_patterns: &[String],
     show_ignored: bool,
+    output: &mut FindingOutput,
+    heatmap: &mut DuplicationHeatmap,
+    report: &RunReport,
+    budget: &ComparisonBudget,
 ) -> anyhow::Result<usize> {
-    use ignore::WalkBuilder;
-    use similarity_core::{extract_classes_from_code, find_similar_classes};
+    use similarity_core::cli_file_utils::walk_dir;
+    use similarity_core::{extract_classes_from_code, find_similar_classes_with_budget};
     use std::collections::HashSet;
     use std::fs;
     use std::path::Path;
@@ -1075,17 +4339,14 @@ fn check_classes(
                                 files.push(path.to_path_buf());
                             }
                         }
+                    } else {
+                        report.skip(path_str, SkipReason::UnsupportedExtension);
                     }
                 }
             }
         } else if path.is_dir() {
-            // If it's a directory, walk it respecting .gitignore
-            let walker = WalkBuilder::new(path)
-                .follow_links(false)
-                .git_ignore(true) // Respect .gitignore files
-                .git_global(true) // Respect global gitignore
-                .git_exclude(true) // Respect .git/info/exclude
-                .build();
+            // If it's a directory, walk it respecting .gitignore and .similarityignore
+            let walker = walk_dir(path);
 
             for entry in walker {
                 let entry = entry?;
@@ -1100,6 +4361,7 @@ fn check_classes(
                 if let Some(ref matcher) = exclude_matcher {
                     // Check both the full path and relative path from the search root
                     if matcher.is_match(entry_path) {
+                        report.skip(&entry_path.display().to_string(), SkipReason::Excluded);
                         continue;
                     }
 
@@ -1107,6 +4369,8 @@ fn check_classes(
                     if let Ok(current_dir) = std::env::current_dir() {
                         if let Ok(relative) = entry_path.strip_prefix(&current_dir) {
                             if matcher.is_match(relative) {
+                                report
+                                    .skip(&entry_path.display().to_string(), SkipReason::Excluded);
                                 continue;
                             }
                         }
@@ -1132,12 +4396,22 @@ fn check_classes(
         }
     }
 
+    let standard_output = output.format == OutputFormat::Standard;
+
     if files.is_empty() {
-        println!("No TypeScript files found in specified paths");
+        if standard_output {
+            println!("No TypeScript files found in specified paths");
+        }
         return Ok(0);
     }
 
-    println!("Checking {} files for similar classes...\n", files.len());
+    if standard_output {
+        println!("Checking {} files for similar classes...\n", files.len());
+    }
+
+    for file in &files {
+        heatmap.add_file(&get_relative_path(&file.to_string_lossy()));
+    }
 
     // Extract classes from all files
     let mut all_classes = Vec::new();
@@ -1152,12 +4426,15 @@ fn check_classes(
                 // Extract classes
                 match extract_classes_from_code(&content, &file_str) {
                     Ok(classes) => {
+                        if classes.iter().any(|class| class.partial) {
+                            report.mark_partial(&file.display().to_string());
+                        }
                         for class in classes {
                             if class.has_ignore_directive {
                                 if show_ignored {
                                     ignored_classes.push((
                                         file.display().to_string(),
-                                        class.name.clone(),
+                                        class.name,
                                         class.start_line,
                                     ));
                                 }
@@ -1177,8 +4454,10 @@ fn check_classes(
                         }
                     }
                     Err(e) => {
-                        // Skip files with parse errors silently
-                        if !e.contains("Parse errors:") {
+                        if e.contains("Parse errors:") {
+                            tracing::info!(file = %file.display(), error = %e, "skipping file");
+                            report.skip(&file.display().to_string(), SkipReason::ParseError(e));
+                        } else {
                             eprintln!("Error in {}: {}", file.display(), e);
                         }
                     }
@@ -1186,15 +4465,70 @@ fn check_classes(
             }
             Err(e) => {
                 eprintln!("Error reading {}: {}", file.display(), e);
+                report.skip(&file.display().to_string(), SkipReason::Unreadable(e.to_string()));
             }
         }
     }
 
     if all_classes.is_empty() {
-        println!("No class definitions found!");
+        if standard_output {
+            println!("No class definitions found!");
+        }
         return Ok(0);
     }
 
+    // Find similar classes across all files
+    let mut similar_pairs =
+        find_similar_classes_with_budget(&all_classes, threshold, comparison_options, budget);
+    similar_pairs.retain(|pair| {
+        !report.suppress(
+            "classes",
+            (Path::new(&pair.class1.file_path), &pair.class1.name),
+            (Path::new(&pair.class2.file_path), &pair.class2.name),
+        )
+    });
+
+    for pair in &similar_pairs {
+        heatmap.record_pair(
+            "classes",
+            (
+                &get_relative_path(&pair.class1.file_path),
+                pair.class1.start_line,
+                pair.class1.end_line,
+            ),
+            (
+                &get_relative_path(&pair.class2.file_path),
+                pair.class2.start_line,
+                pair.class2.end_line,
+            ),
+        );
+    }
+
+    let severities: Vec<Severity> = similar_pairs
+        .iter()
+        .map(|pair| report.classify("classes", pair.result.similarity))
+        .collect();
+
+    if output.format != OutputFormat::Standard {
+        for (pair, severity) in similar_pairs.iter().zip(&severities) {
+            let message = format!(
+                "Class '{}' is {:.0}% similar to '{}'",
+                pair.class1.name,
+                pair.result.similarity * 100.0,
+                pair.class2.name
+            );
+            output.print_finding(
+                &get_relative_path(&pair.class1.file_path),
+                pair.class1.start_line,
+                *severity,
+                &message,
+                &get_relative_path(&pair.class2.file_path),
+                pair.class2.start_line,
+            );
+        }
+        return Ok(similar_pairs.len());
+    }
+
     println!("Found {} class definitions", all_classes.len());
     if show_ignored && !ignored_classes.is_empty() {
         println!("Ignored {} class(es) via similarity-ignore directive:", ignored_classes.len());
@@ -1219,9 +4553,6 @@ fn check_classes(
         println!();
     }
 
-    // Find similar classes across all files
-    let similar_pairs = find_similar_classes(&all_classes, threshold);
-
     if similar_pairs.is_empty() {
         println!("\nNo similar classes found!");
     } else {
@@ -1240,21 +4571,32 @@ fn check_classes(
                 pair.result.naming_similarity * 100.0
             );
             println!(
-                "  {}:{} | L{}-{} similar-class: {}",
+                "  {}:{} | L{}-{} similar-class: {}{}",
                 relative_path1,
                 pair.class1.start_line,
                 pair.class1.start_line,
                 pair.class1.end_line,
-                pair.class1.name
+                pair.class1.name,
+                partial_marker(pair.class1.partial)
             );
             println!(
-                "  {}:{} | L{}-{} similar-class: {}",
+                "  {}:{} | L{}-{} similar-class: {}{}",
                 relative_path2,
                 pair.class2.start_line,
                 pair.class2.start_line,
                 pair.class2.end_line,
-                pair.class2.name
+                pair.class2.name,
+                partial_marker(pair.class2.partial)
             );
+            if !pair.result.method_similarities.is_empty() {
+                let methods: Vec<String> = pair
+                    .result
+                    .method_similarities
+                    .iter()
+                    .map(|method| format!("{} {:.2}%", method.name, method.similarity * 100.0))
+                    .collect();
+                println!("  Method bodies: {}", methods.join(", "));
+            }
 
             if print {
                 show_class_details(&pair.class1);
@@ -1286,7 +4628,7 @@ fn check_classes(
         let mut extends_map = std::collections::HashMap::new();
         for class in &excluded_classes {
             if let Some(base) = &class.extends {
-                extends_map.entry(base.clone()).or_insert(Vec::new()).push(class.name.clone());
+                extends_map.entry(base.clone()).or_insert(Vec::new()).push(class.name.as_str());
             }
         }
 
@@ -1317,6 +4659,10 @@ fn check_classes(
 fn show_class_details(class: &similarity_core::ClassDefinition) {
     println!("\n\x1b[36m--- Class {} ---\x1b[0m", class.name);
 
+    if !class.decorators.is_empty() {
+        println!("Decorators: {}", decorator_prefix(&class.decorators).trim_end());
+    }
+
     if let Some(extends) = &class.extends {
         println!("Extends: {}", extends);
     }
@@ -1330,12 +4676,19 @@ fn show_class_details(class: &similarity_core::ClassDefinition) {
         for prop in &class.properties {
             let modifiers = format!(
                 "{}{}{}",
+                class_member_visibility(prop.is_private, prop.is_protected, &prop.name),
                 if prop.is_static { "static " } else { "" },
-                if prop.is_readonly { "readonly " } else { "" },
-                if prop.is_private { "private " } else { "" }
+                if prop.is_readonly { "readonly " } else { "" }
             );
             let optional = if prop.is_optional { "?" } else { "" };
-            println!("  {}{}{}: {}", modifiers, prop.name, optional, prop.type_annotation);
+            println!(
+                "  {}{}{}{}: {}",
+                decorator_prefix(&prop.decorators),
+                modifiers,
+                prop.name,
+                optional,
+                prop.type_annotation
+            );
         }
     }
 
@@ -1344,8 +4697,8 @@ fn show_class_details(class: &similarity_core::ClassDefinition) {
         for method in &class.methods {
             let modifiers = format!(
                 "{}{}{}{}",
+                class_member_visibility(method.is_private, method.is_protected, &method.name),
                 if method.is_static { "static " } else { "" },
-                if method.is_private { "private " } else { "" },
                 if method.is_async { "async " } else { "" },
                 if method.is_generator { "*" } else { "" }
             );
@@ -1355,7 +4708,8 @@ fn show_class_details(class: &similarity_core::ClassDefinition) {
                 _ => "",
             };
             println!(
-                "  {}{}{}({}): {}",
+                "  {}{}{}{}({}): {}",
+                decorator_prefix(&method.decorators),
                 modifiers,
                 kind_str,
                 method.name,
@@ -1366,7 +4720,38 @@ fn show_class_details(class: &similarity_core::ClassDefinition) {
     }
 }
 
+/// Accessibility modifier shown before a class member; `#private` names carry their own marker
+fn class_member_visibility(is_private: bool, is_protected: bool, name: &str) -> &'static str {
+    if is_private && !name.starts_with('#') {
+        "private "
+    } else if is_protected {
+        "protected "
+    } else {
+        ""
+    }
+}
+
+/// Decorators as written before a declaration, each followed by a space
+fn decorator_prefix(decorators: &[similarity_core::Decorator]) -> String {
+    decorators.iter().map(|decorator| format!("{} ", decorator)).collect()
+}
+
 fn show_class_comparison_details(result: &similarity_core::ClassComparisonResult) {
+    if !result.differences.missing_decorators.is_empty() {
+        println!("Missing decorators: {}", result.differences.missing_decorators.join(", "));
+    }
+
+    if !result.differences.extra_decorators.is_empty() {
+        println!("Extra decorators: {}", result.differences.extra_decorators.join(", "));
+    }
+
+    if !result.differences.decorator_mismatches.is_empty() {
+        println!("Decorator mismatches:");
+        for mismatch in &result.differences.decorator_mismatches {
+            println!("  {}: {} vs {}", mismatch.name, mismatch.decorator1, mismatch.decorator2);
+        }
+    }
+
     if !result.differences.missing_properties.is_empty() {
         println!("Missing properties: {}", result.differences.missing_properties.join(", "));
     }
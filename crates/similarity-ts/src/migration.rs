@@ -0,0 +1,174 @@
+//! Functions left behind by a JavaScript to TypeScript migration.
+//!
+//! While a codebase moves to TypeScript, a function often exists both in a
+//! `.js` file and in the `.ts` file that replaced it. Each JavaScript
+//! function is compared with the TypeScript functions after their type
+//! syntax is stripped, so a faithful port compares as identical. Functions
+//! keeping their name are compared without the short function penalty, the
+//! name being evidence enough; a pair is high confidence when the names match
+//! and the bodies are at least [`HIGH_CONFIDENCE_SIMILARITY`] similar.
+//!
+//! A JavaScript file is safe to delete when every function in it has a high
+//! confidence TypeScript version. Top-level code outside functions is not
+//! compared, so review the file before deleting it.
+
+use rayon::prelude::*;
+use similarity_core::tsed::TSEDOptions;
+use similarity_core::{compare_functions, extract_functions, ComparisonBudget, FunctionDefinition};
+use std::path::Path;
+
+/// Extensions of the files being migrated
+pub const JS_EXTENSIONS: [&str; 4] = ["js", "jsx", "mjs", "cjs"];
+/// Extensions of the files they are migrated to
+pub const TS_EXTENSIONS: [&str; 4] = ["ts", "tsx", "mts", "cts"];
+/// Similarity from which a pair of same-named functions is high confidence
+pub const HIGH_CONFIDENCE_SIMILARITY: f64 = 0.95;
+
+/// A JavaScript function and its TypeScript version
+#[derive(Debug, Clone)]
+pub struct MigratedFunction {
+    pub js_file: String,
+    pub js: FunctionDefinition,
+    pub ts_file: String,
+    pub ts: FunctionDefinition,
+    pub similarity: f64,
+}
+
+impl MigratedFunction {
+    pub fn is_high_confidence(&self) -> bool {
+        self.js.name == self.ts.name && self.similarity >= HIGH_CONFIDENCE_SIMILARITY
+    }
+}
+
+/// Migrated functions, most similar first, and the JavaScript files all of
+/// whose functions were migrated
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    pub functions: Vec<MigratedFunction>,
+    pub safe_to_delete: Vec<String>,
+    /// Files that could not be parsed, with the error
+    pub parse_errors: Vec<(String, String)>,
+}
+
+struct ParsedFile<'s> {
+    path: &'s str,
+    source: &'s str,
+    functions: Vec<FunctionDefinition>,
+}
+
+/// Match the functions of the JavaScript files of `sources` with those of
+/// the TypeScript files; other files are ignored
+pub fn find_migrated_functions(
+    sources: &[(String, String)],
+    threshold: f64,
+    options: &TSEDOptions,
+    budget: &ComparisonBudget,
+) -> MigrationReport {
+    let mut report = MigrationReport::default();
+    let mut js_files = Vec::new();
+    let mut ts_files = Vec::new();
+    for (path, source) in sources {
+        let extension = Path::new(path).extension().and_then(|ext| ext.to_str()).unwrap_or("");
+        let files = if JS_EXTENSIONS.contains(&extension) {
+            &mut js_files
+        } else if TS_EXTENSIONS.contains(&extension) {
+            &mut ts_files
+        } else {
+            continue;
+        };
+        match extract_functions(path, source) {
+            Ok(functions) => files.push(ParsedFile { path, source, functions }),
+            Err(e) => report.parse_errors.push((path.clone(), e)),
+        }
+    }
+
+    let stripped = TSEDOptions { strip_types: true, ..options.clone() };
+    let same_name = TSEDOptions { size_penalty: false, ..stripped.clone() };
+    let ts_functions: Vec<(&ParsedFile, &FunctionDefinition)> =
+        ts_files.iter().flat_map(|file| file.functions.iter().map(move |f| (file, f))).collect();
+
+    for js_file in &js_files {
+        let matches: Vec<Option<MigratedFunction>> = js_file
+            .functions
+            .par_iter()
+            .map(|js| {
+                let short = js.line_count() < options.min_lines;
+                ts_functions
+                    .iter()
+                    .filter(|(_, ts)| !short || ts.name == js.name)
+                    .filter_map(|(ts_file, ts)| {
+                        if !budget.try_compare() {
+                            return None;
+                        }
+                        let options = if ts.name == js.name { &same_name } else { &stripped };
+                        let similarity =
+                            compare_functions(js, ts, js_file.source, ts_file.source, options)
+                                .ok()?;
+                        Some(MigratedFunction {
+                            js_file: js_file.path.to_string(),
+                            js: js.clone(),
+                            ts_file: ts_file.path.to_string(),
+                            ts: (*ts).clone(),
+                            similarity,
+                        })
+                    })
+                    .filter(|found| found.similarity >= threshold)
+                    .max_by(|a, b| {
+                        (a.is_high_confidence(), a.similarity)
+                            .partial_cmp(&(b.is_high_confidence(), b.similarity))
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+            })
+            .collect();
+
+        let all_migrated = !matches.is_empty()
+            && matches.iter().all(|found| found.as_ref().is_some_and(|f| f.is_high_confidence()));
+        if all_migrated {
+            report.safe_to_delete.push(js_file.path.to_string());
+        }
+        // Short functions only decide whether the file can go
+        report.functions.extend(
+            matches
+                .into_iter()
+                .flatten()
+                .filter(|found| found.js.line_count() >= options.min_lines),
+        );
+    }
+
+    report.functions.sort_by(|a, b| {
+        b.similarity.total_cmp(&a.similarity).then_with(|| a.js_file.cmp(&b.js_file))
+    });
+    report.safe_to_delete.sort();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ported_functions_are_matched_across_extensions() {
+        let js = "export function total(items) {\n  let sum = 0;\n  for (const item of items) {\n    sum += item.price;\n  }\n  return sum;\n}\n\nexport const twice = (x) => x * 2;\n";
+        let ts = "export function total(items: Item[]): number {\n  let sum = 0;\n  for (const item of items) {\n    sum += item.price as number;\n  }\n  return sum;\n}\n\nexport const twice = (x: number) => x * 2;\n";
+        let other = "export function render(items) {\n  const out = [];\n  for (const item of items) {\n    out.push(item.name);\n  }\n  return out;\n}\n";
+        let sources = vec![
+            ("src/cart.js".to_string(), js.to_string()),
+            ("src/cart.ts".to_string(), ts.to_string()),
+            ("src/view.js".to_string(), other.to_string()),
+        ];
+        let options = TSEDOptions { min_lines: 3, ..TSEDOptions::default() };
+        let report =
+            find_migrated_functions(&sources, 0.8, &options, &ComparisonBudget::unlimited());
+
+        assert_eq!(report.functions.len(), 1);
+        let found = &report.functions[0];
+        assert_eq!(
+            (found.js_file.as_str(), found.ts_file.as_str()),
+            ("src/cart.js", "src/cart.ts")
+        );
+        assert_eq!(found.similarity, 1.0);
+        assert!(found.is_high_confidence());
+        // `render` has no TypeScript version
+        assert_eq!(report.safe_to_delete, ["src/cart.js"]);
+    }
+}
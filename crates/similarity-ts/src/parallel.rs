@@ -1,10 +1,21 @@
 use rayon::prelude::*;
+use similarity_core::analysis_session::{CrossFileFinding, CROSS_FILE_BATCH_SIZE};
 use similarity_core::{
-    extract_functions, find_similar_functions_fast, find_similar_functions_in_file,
-    FastSimilarityOptions, FunctionDefinition, SimilarityResult, TSEDOptions,
+    extract_functions, find_similar_functions_fast_with_budget,
+    find_similar_functions_in_file_with_budget, locality_bucket, pair_shard_key,
+    prioritize_by_bucket_density, shard_key, visit_similar_functions_in_file, AnalysisSession,
+    ComparisonBudget, FastSimilarityOptions, FunctionDefinition, MatrixExporter, RunReport,
+    SimilarityResult, SkipReason, TSEDOptions, TopPairs,
 };
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A ranked pair: first file, result, second file
+pub type RankedPair = (String, SimilarityResult, String);
+
+/// A function with its file name and file content
+type CandidateFunction = (String, String, FunctionDefinition);
 
 /// File with its content and extracted functions
 #[derive(Debug)]
@@ -14,25 +25,46 @@ pub struct FileData {
     pub functions: Vec<FunctionDefinition>,
 }
 
-/// Load and parse files in parallel
-pub fn load_files_parallel(files: &[PathBuf]) -> Vec<FileData> {
+/// Load and parse files in parallel, recording files that fail in `report`.
+///
+/// Files whose functions are recorded in `session` are not parsed again.
+pub fn load_files_parallel(
+    files: &[PathBuf],
+    report: &RunReport,
+    session: Option<&AnalysisSession>,
+) -> Vec<FileData> {
     files
         .par_iter()
         .filter_map(|file| {
             match fs::read_to_string(file) {
                 Ok(content) => {
                     let filename = file.to_string_lossy();
+                    let recorded = session.and_then(|session| session.parsed_functions(&filename));
                     // Extract functions, skip if parse error
-                    match extract_functions(&filename, &content) {
+                    match recorded.map_or_else(|| extract_functions(&filename, &content), Ok) {
                         Ok(mut functions) => {
+                            if let Some(session) = session {
+                                session.record_parsed(&filename, functions.clone());
+                                checkpoint(session);
+                            }
+                            if functions.iter().any(|function| function.partial) {
+                                tracing::info!(file = %file.display(), "partially parsed file");
+                                report.mark_partial(&file.display().to_string());
+                            }
                             functions.retain(|function| !function.has_ignore_directive);
                             Some(FileData { path: file.clone(), content, functions })
                         }
-                        Err(_) => None, // Skip files with parse errors
+                        Err(e) => {
+                            // Skip files with parse errors
+                            tracing::info!(file = %file.display(), error = %e, "skipping file");
+                            report.skip(&file.display().to_string(), SkipReason::ParseError(e));
+                            None
+                        }
                     }
                 }
                 Err(e) => {
                     eprintln!("Error reading {}: {}", file.display(), e);
+                    report.skip(&file.display().to_string(), SkipReason::Unreadable(e.to_string()));
                     None
                 }
             }
@@ -40,51 +72,239 @@ pub fn load_files_parallel(files: &[PathBuf]) -> Vec<FileData> {
         .collect()
 }
 
-/// Check for duplicates within files in parallel
+/// Save the session checkpoint when it is due; a failed save only loses progress
+fn checkpoint(session: &AnalysisSession) {
+    if let Err(e) = session.checkpoint() {
+        tracing::warn!(error = %e, "failed to save session checkpoint");
+    }
+}
+
+/// Check for duplicates within files in parallel.
+///
+/// Only the files owned by the budget's shard are compared. Files compared in `session` are not compared again, and files compared in
+/// full are recorded there. Pairs down to the floor of `matrix` are written to
+/// it before the threshold applies.
 pub fn check_within_file_duplicates_parallel(
     files: &[PathBuf],
     threshold: f64,
     options: &TSEDOptions,
     fast_mode: bool,
+    budget: &ComparisonBudget,
+    session: Option<&AnalysisSession>,
+    matrix: Option<&MatrixExporter>,
 ) -> Vec<(PathBuf, Vec<SimilarityResult>)> {
+    let comparison_threshold = matrix.map_or(threshold, |m| m.comparison_threshold(threshold));
     files
         .par_iter()
-        .filter_map(|file| match fs::read_to_string(file) {
-            Ok(code) => {
-                let file_str = file.to_string_lossy();
-
-                let similar_pairs = if fast_mode {
+        .filter(|file| budget.in_shard(shard_key(&file.to_string_lossy())))
+        .filter_map(|file| {
+            let file_str = file.to_string_lossy();
+            let recorded = session.and_then(|session| session.within_file_results(&file_str));
+            let similar_pairs = recorded.or_else(|| {
+                let code = fs::read_to_string(file).ok()?;
+                let mut pairs = if fast_mode {
                     let fast_options = FastSimilarityOptions {
                         fingerprint_threshold: 0.3,
-                        similarity_threshold: threshold,
+                        similarity_threshold: comparison_threshold,
                         tsed_options: options.clone(),
                         debug_stats: false,
                     };
-                    find_similar_functions_fast(&file_str, &code, &fast_options).ok()
+                    find_similar_functions_fast_with_budget(&file_str, &code, &fast_options, budget)
+                        .ok()
                 } else {
-                    find_similar_functions_in_file(&file_str, &code, threshold, options).ok()
-                };
-
-                similar_pairs.and_then(|pairs| {
-                    if pairs.is_empty() {
-                        None
-                    } else {
-                        Some((file.clone(), pairs))
+                    find_similar_functions_in_file_with_budget(
+                        &file_str,
+                        &code,
+                        comparison_threshold,
+                        options,
+                        budget,
+                    )
+                    .ok()
+                }?;
+                if let Some(matrix) = matrix {
+                    for pair in &pairs {
+                        record_pair(
+                            matrix,
+                            (&file_str, &pair.func1),
+                            (&file_str, &pair.func2),
+                            pair.similarity,
+                        );
                     }
-                })
+                    pairs.retain(|pair| pair.similarity >= threshold);
+                }
+                // A file cut short by the budget is compared again on resume
+                if let Some(session) = session.filter(|_| !budget.is_exhausted()) {
+                    session.record_within_file(&file_str, pairs.clone());
+                    checkpoint(session);
+                }
+                Some(pairs)
+            })?;
+
+            if similar_pairs.is_empty() {
+                None
+            } else {
+                Some((file.clone(), similar_pairs))
             }
-            Err(_) => None,
         })
         .collect()
 }
 
-/// Check for duplicates across files using parallel processing
+/// Collect the `top` most similar pairs within files, without a threshold
+pub fn rank_within_file_pairs_parallel(
+    files: &[PathBuf],
+    options: &TSEDOptions,
+    fast_mode: bool,
+    top: usize,
+    budget: &ComparisonBudget,
+) -> TopPairs<RankedPair> {
+    files
+        .par_iter()
+        .filter(|file| budget.in_shard(shard_key(&file.to_string_lossy())))
+        .fold(
+            || TopPairs::new(top),
+            |mut best, file| {
+                let Ok(code) = fs::read_to_string(file) else {
+                    return best;
+                };
+                let file_str = file.to_string_lossy();
+
+                if fast_mode {
+                    let fast_options = FastSimilarityOptions {
+                        fingerprint_threshold: 0.3,
+                        similarity_threshold: 0.0,
+                        tsed_options: options.clone(),
+                        debug_stats: false,
+                    };
+                    let pairs = find_similar_functions_fast_with_budget(
+                        &file_str,
+                        &code,
+                        &fast_options,
+                        budget,
+                    )
+                    .unwrap_or_default();
+                    for result in pairs {
+                        best.offer(result.similarity, || {
+                            (file_str.to_string(), result, file_str.to_string())
+                        });
+                    }
+                } else {
+                    let _ = visit_similar_functions_in_file(
+                        &file_str,
+                        &code,
+                        0.0,
+                        options,
+                        budget,
+                        |func1, func2, similarity, algorithm| {
+                            best.offer(similarity, || {
+                                (
+                                    file_str.to_string(),
+                                    SimilarityResult::new(func1.clone(), func2.clone(), similarity)
+                                        .with_algorithm(algorithm),
+                                    file_str.to_string(),
+                                )
+                            });
+                        },
+                    );
+                }
+                best
+            },
+        )
+        .reduce(|| TopPairs::new(top), TopPairs::merge)
+}
+
+/// Check for duplicates across files using parallel processing.
+///
+/// Pairs in dense locality buckets are compared first, so a budget that runs
+/// out early has still covered the most likely duplicates. Pairs are compared
+/// in batches of [`CROSS_FILE_BATCH_SIZE`]; batches recorded in `session` are
+/// skipped, and batches compared in full are recorded there. Every compared
+/// pair is offered to `matrix`.
 pub fn check_cross_file_duplicates_parallel(
     file_data: &[FileData],
     threshold: f64,
     options: &TSEDOptions,
     _fast_mode: bool,
-) -> Vec<(String, SimilarityResult, String)> {
+    budget: &ComparisonBudget,
+    session: Option<&AnalysisSession>,
+    matrix: Option<&MatrixExporter>,
+) -> Vec<CrossFileFinding> {
+    let (all_functions, pairs_to_check) = cross_file_candidates(file_data, budget);
+
+    let mut results = Vec::new();
+    for (batch, pairs) in pairs_to_check.chunks(CROSS_FILE_BATCH_SIZE).enumerate() {
+        if let Some(recorded) = session.and_then(|session| session.cross_file_batch(batch)) {
+            budget.mark_compared(pairs.len());
+            results.extend(recorded);
+            continue;
+        }
+
+        let cut_short = AtomicBool::new(false);
+        // Process pairs in parallel
+        let batch_results: Vec<CrossFileFinding> = pairs
+            .par_iter()
+            .filter_map(|&(i, j)| {
+                if !budget.start_comparison() {
+                    cut_short.store(true, Ordering::Relaxed);
+                    return None;
+                }
+                let (file1, content1, func1) = &all_functions[i];
+                let (file2, content2, func2) = &all_functions[j];
+
+                // Use core's compare_functions
+                match similarity_core::compare_functions_with_algorithm(
+                    func1, func2, content1, content2, options,
+                ) {
+                    Ok((similarity, algorithm)) => {
+                        if let Some(matrix) = matrix {
+                            record_pair(matrix, (file1, func1), (file2, func2), similarity);
+                        }
+                        if similarity >= threshold {
+                            Some((
+                                file1.clone(),
+                                SimilarityResult::new(func1.clone(), func2.clone(), similarity)
+                                    .with_algorithm(algorithm),
+                                file2.clone(),
+                            ))
+                        } else {
+                            None
+                        }
+                    }
+                    Err(_) => None,
+                }
+            })
+            .collect();
+
+        if let Some(session) = session.filter(|_| !cut_short.load(Ordering::Relaxed)) {
+            session.record_cross_file_batch(batch, batch_results.clone());
+            checkpoint(session);
+        }
+        results.extend(batch_results);
+    }
+    results
+}
+
+/// Write a compared function pair to the similarity matrix
+fn record_pair(
+    matrix: &MatrixExporter,
+    (file1, func1): (&str, &FunctionDefinition),
+    (file2, func2): (&str, &FunctionDefinition),
+    similarity: f64,
+) {
+    matrix.record(
+        "function",
+        (file1, func1.display_name(), func1.start_line as usize, func1.end_line as usize),
+        (file2, func2.display_name(), func2.start_line as usize, func2.end_line as usize),
+        similarity,
+    );
+}
+
+/// Functions of all files and the cross-file pairs among them that the
+/// budget's shard owns, densest locality buckets first, sampled down to the
+/// comparisons the budget allows
+fn cross_file_candidates(
+    file_data: &[FileData],
+    budget: &ComparisonBudget,
+) -> (Vec<CandidateFunction>, Vec<(usize, usize)>) {
     // Prepare all function pairs with file information
     let mut all_functions = Vec::new();
     for data in file_data {
@@ -94,6 +314,19 @@ pub fn check_cross_file_duplicates_parallel(
         }
     }
 
+    // A pair's shard follows from its functions' locations, not their order
+    let keys: Option<Vec<u64>> = budget.shard().map(|_| {
+        all_functions
+            .iter()
+            .map(|(file, _, func)| {
+                shard_key(&format!("{}:{}:{}", file, func.name, func.start_line))
+            })
+            .collect()
+    });
+    let owned = |i: usize, j: usize| {
+        keys.as_ref().is_none_or(|keys| budget.in_shard(pair_shard_key(keys[i], keys[j])))
+    };
+
     // Generate all cross-file pairs
     let mut pairs_to_check = Vec::new();
     for i in 0..all_functions.len() {
@@ -102,34 +335,59 @@ pub fn check_cross_file_duplicates_parallel(
             let (file2, _, _) = &all_functions[j];
 
             // Only check across different files
-            if file1 != file2 {
+            if file1 != file2 && owned(i, j) {
                 pairs_to_check.push((i, j));
             }
         }
     }
 
-    // Process pairs in parallel
+    let buckets: Vec<u64> =
+        all_functions.iter().map(|(_, _, func)| locality_bucket(func)).collect();
+    prioritize_by_bucket_density(&mut pairs_to_check, &buckets);
+    budget.sample_pairs(&mut pairs_to_check, &buckets);
+
+    (all_functions, pairs_to_check)
+}
+
+/// Collect the `top` most similar pairs across files, without a threshold.
+///
+/// Each thread keeps its own bounded collector and the collectors are merged,
+/// so only `top` pairs per thread are held at any time.
+pub fn rank_cross_file_pairs_parallel(
+    file_data: &[FileData],
+    options: &TSEDOptions,
+    top: usize,
+    budget: &ComparisonBudget,
+) -> TopPairs<RankedPair> {
+    let (all_functions, pairs_to_check) = cross_file_candidates(file_data, budget);
+
     pairs_to_check
         .into_par_iter()
-        .filter_map(|(i, j)| {
-            let (file1, content1, func1) = &all_functions[i];
-            let (file2, content2, func2) = &all_functions[j];
-
-            // Use core's compare_functions
-            match similarity_core::compare_functions(func1, func2, content1, content2, options) {
-                Ok(similarity) => {
-                    if similarity >= threshold {
-                        Some((
+        .fold(
+            || TopPairs::new(top),
+            |mut best, (i, j)| {
+                if !budget.start_comparison() {
+                    return best;
+                }
+                let (file1, content1, func1) = &all_functions[i];
+                let (file2, content2, func2) = &all_functions[j];
+
+                if let Ok((similarity, algorithm)) =
+                    similarity_core::compare_functions_with_algorithm(
+                        func1, func2, content1, content2, options,
+                    )
+                {
+                    best.offer(similarity, || {
+                        (
                             file1.clone(),
-                            SimilarityResult::new(func1.clone(), func2.clone(), similarity),
+                            SimilarityResult::new(func1.clone(), func2.clone(), similarity)
+                                .with_algorithm(algorithm),
                             file2.clone(),
-                        ))
-                    } else {
-                        None
-                    }
+                        )
+                    });
                 }
-                Err(_) => None,
-            }
-        })
-        .collect()
+                best
+            },
+        )
+        .reduce(|| TopPairs::new(top), TopPairs::merge)
 }
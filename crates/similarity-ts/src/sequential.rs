@@ -1,6 +1,6 @@
 use crate::parallel::FileData;
 use similarity_core::{
-    compare_functions, extract_functions, find_similar_functions_fast,
+    compare_functions_with_algorithm, extract_functions, find_similar_functions_fast,
     find_similar_functions_in_file, FastSimilarityOptions, SimilarityResult, TSEDOptions,
 };
 use std::fs;
@@ -92,12 +92,14 @@ pub fn check_cross_file_duplicates_sequential(
 
             // Only check across different files
             if file1 != file2 {
-                if let Ok(similarity) = compare_functions(func1, func2, content1, content2, options)
+                if let Ok((similarity, algorithm)) =
+                    compare_functions_with_algorithm(func1, func2, content1, content2, options)
                 {
                     if similarity >= threshold {
                         results.push((
                             file1.clone(),
-                            SimilarityResult::new(func1.clone(), func2.clone(), similarity),
+                            SimilarityResult::new(func1.clone(), func2.clone(), similarity)
+                                .with_algorithm(algorithm),
                             file2.clone(),
                         ));
                     }
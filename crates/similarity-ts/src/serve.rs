@@ -0,0 +1,644 @@
+//! HTTP API over the warm function index, for dashboards and bots.
+//!
+//! `serve` indexes the functions of the served paths once and keeps them in
+//! memory, so integrations can ask about the repository without running the
+//! CLI for every question. Requests and responses are JSON:
+//!
+//! - `POST /analyze`: read the served paths again, rebuild the index and
+//!   replace the findings; answers `{ "files", "functions", "findings" }`
+//!   with the number of each
+//! - `GET /findings`: `{ "findings": [...] }`, the functions resembling
+//!   another function of the repository as of the last analysis, each shaped
+//!   like a daemon `lint` finding (see [`match_json`]) with its `file`;
+//!   `?file=` keeps those of one file
+//! - `POST /query-snippet` with `{ "text", "file" }`: `{ "matches": [...] }`,
+//!   the functions of `text` resembling an indexed function, most similar
+//!   first; `file` (default `snippet.ts`) only selects the parser
+//!
+//! Errors are answered with an HTTP error status and `{ "error" }`. With a
+//! token, every request must carry `Authorization: Bearer <token>`.
+//!
+//! Connections are answered by a pool of worker threads. The index holds
+//! reference-counted trees that cannot cross threads, so the latest analysis
+//! is shared in the binary index format and each worker decodes its own copy
+//! when it falls behind.
+
+use crate::daemon::match_json;
+use anyhow::Result;
+use serde_json::{json, Value};
+use similarity_core::tsed::TSEDOptions;
+use similarity_core::{ComparisonBudget, FunctionIndex, IndexMatch};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Environment variable holding the token when `--token` is not given
+pub const SERVE_TOKEN_ENV: &str = "SIMILARITY_SERVE_TOKEN";
+
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Longest request or header line accepted
+const MAX_LINE_BYTES: u64 = 8 * 1024;
+
+const MAX_HEADERS: usize = 100;
+
+/// How long a client may take to send its whole request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reads the sources to index: file names with their text
+pub type SourceLoader = Box<dyn Fn() -> Result<Vec<(String, String)>> + Send + Sync>;
+
+/// One analysis of the served paths
+struct Snapshot {
+    generation: u64,
+    /// The index in the binary index format
+    index: Vec<u8>,
+    files: usize,
+    functions: usize,
+    findings: Vec<Value>,
+}
+
+impl Snapshot {
+    fn summary(&self) -> Value {
+        json!({
+            "files": self.files,
+            "functions": self.functions,
+            "findings": self.findings.len(),
+        })
+    }
+}
+
+/// A worker's decoded copy of the index of a snapshot
+#[derive(Default)]
+struct LocalIndex {
+    /// Generation of the decoded snapshot; 0 before the first
+    generation: u64,
+    index: FunctionIndex,
+}
+
+pub struct Server {
+    load: SourceLoader,
+    threshold: f64,
+    options: TSEDOptions,
+    token: Option<String>,
+    snapshot: RwLock<Arc<Snapshot>>,
+    /// Generation of the latest analysis, locked while one runs so that
+    /// concurrent `POST /analyze` requests take turns
+    analyzing: Mutex<u64>,
+}
+
+impl Server {
+    /// A server answering from an analysis of the sources `load` returns
+    pub fn new(
+        load: SourceLoader,
+        threshold: f64,
+        options: TSEDOptions,
+        token: Option<String>,
+    ) -> Result<Self> {
+        let (snapshot, _) = analyze(&load, threshold, &options, 1)?;
+        Ok(Self {
+            load,
+            threshold,
+            options,
+            token,
+            snapshot: RwLock::new(Arc::new(snapshot)),
+            analyzing: Mutex::new(1),
+        })
+    }
+
+    /// Number of indexed files and functions
+    pub fn indexed(&self) -> (usize, usize) {
+        let snapshot = self.current();
+        (snapshot.files, snapshot.functions)
+    }
+
+    /// Accept connections on `listener` and answer them with `workers`
+    /// threads; returns only when the listener fails
+    pub fn serve(self: Arc<Self>, listener: TcpListener, workers: usize) -> io::Result<()> {
+        let (sender, receiver) = mpsc::channel::<TcpStream>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..workers.max(1) {
+            let server = Arc::clone(&self);
+            let receiver = Arc::clone(&receiver);
+            std::thread::spawn(move || {
+                let mut local = LocalIndex::default();
+                loop {
+                    let Ok(stream) = receiver.lock().unwrap().recv() else {
+                        break;
+                    };
+                    if let Err(e) = server.answer(stream, &mut local) {
+                        tracing::debug!(error = %e, "connection failed");
+                    }
+                }
+            });
+        }
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let _ = sender.send(stream);
+                }
+                Err(e) => tracing::debug!(error = %e, "failed to accept a connection"),
+            }
+        }
+        Ok(())
+    }
+
+    fn current(&self) -> Arc<Snapshot> {
+        Arc::clone(&self.snapshot.read().unwrap())
+    }
+
+    /// Read one request from `stream` and write its response
+    fn answer(&self, stream: TcpStream, local: &mut LocalIndex) -> io::Result<()> {
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+        let mut reader = BufReader::new(DeadlineReader { stream: stream.try_clone()?, deadline });
+        let (status, body) = match self.receive(&mut reader) {
+            Ok(request) => {
+                let (status, body) = self.route(&request, local);
+                tracing::info!(method = %request.method, path = %request.path, status, "request");
+                (status, body)
+            }
+            Err((status, message)) => (status, error_body(&message)),
+        };
+        write_response(stream, status, &body)
+    }
+
+    /// Read a request, refusing it before its body is read when the token is wrong
+    fn receive(&self, reader: &mut impl BufRead) -> Result<Request, (u16, String)> {
+        let mut request = read_head(reader)?;
+        if !self.is_authorized(&request) {
+            return Err((401, "missing or invalid token".to_string()));
+        }
+        read_body(reader, &mut request)?;
+        Ok(request)
+    }
+
+    fn route(&self, request: &Request, local: &mut LocalIndex) -> (u16, Value) {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/analyze") => match self.reanalyze(local) {
+                Ok(summary) => (200, summary),
+                Err(e) => (500, error_body(&format!("{:#}", e))),
+            },
+            ("GET", "/findings") => (200, self.findings(request.query("file").as_deref())),
+            ("POST", "/query-snippet") => self.query_snippet(&request.body, local),
+            (_, "/analyze" | "/findings" | "/query-snippet") => {
+                (405, error_body(&format!("{} not allowed on {}", request.method, request.path)))
+            }
+            _ => (404, error_body(&format!("no endpoint {}", request.path))),
+        }
+    }
+
+    fn is_authorized(&self, request: &Request) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+        request
+            .header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|given| constant_time_eq(given.trim().as_bytes(), token.as_bytes()))
+    }
+
+    fn reanalyze(&self, local: &mut LocalIndex) -> Result<Value> {
+        let mut latest = self.analyzing.lock().unwrap();
+        let generation = *latest + 1;
+        let (snapshot, index) = analyze(&self.load, self.threshold, &self.options, generation)?;
+        let summary = snapshot.summary();
+        *self.snapshot.write().unwrap() = Arc::new(snapshot);
+        *latest = generation;
+        *local = LocalIndex { generation, index };
+        Ok(summary)
+    }
+
+    fn findings(&self, file: Option<&str>) -> Value {
+        let snapshot = self.current();
+        let file = file.map(|file| file.trim_start_matches("./"));
+        let findings: Vec<&Value> = snapshot
+            .findings
+            .iter()
+            .filter(|finding| {
+                file.is_none_or(|file| {
+                    finding["file"].as_str().map(|path| path.trim_start_matches("./")) == Some(file)
+                })
+            })
+            .collect();
+        json!({ "findings": findings })
+    }
+
+    fn query_snippet(&self, body: &[u8], local: &mut LocalIndex) -> (u16, Value) {
+        let params: Value = match serde_json::from_slice(body) {
+            Ok(params) => params,
+            Err(e) => return (400, error_body(&format!("invalid JSON: {}", e))),
+        };
+        let Some(text) = params.get("text").and_then(Value::as_str) else {
+            return (400, error_body("missing 'text'"));
+        };
+        let file = params.get("file").and_then(Value::as_str).unwrap_or("snippet.ts");
+
+        let snapshot = self.current();
+        if local.generation != snapshot.generation {
+            match FunctionIndex::read_from(snapshot.index.as_slice()) {
+                Ok(index) => *local = LocalIndex { generation: snapshot.generation, index },
+                Err(e) => return (500, error_body(&format!("failed to decode the index: {}", e))),
+            }
+        }
+        match local.index.find_matches(
+            file,
+            text,
+            self.threshold,
+            &self.options,
+            &ComparisonBudget::unlimited(),
+        ) {
+            Ok(mut matches) => {
+                matches.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+                let matches: Vec<Value> = matches.iter().map(match_json).collect();
+                (200, json!({ "matches": matches }))
+            }
+            Err(e) => (400, error_body(&format!("failed to parse {}: {}", file, e))),
+        }
+    }
+}
+
+/// Index the sources `load` returns and find, per file, the functions
+/// resembling another function of the repository
+fn analyze(
+    load: &SourceLoader,
+    threshold: f64,
+    options: &TSEDOptions,
+    generation: u64,
+) -> Result<(Snapshot, FunctionIndex)> {
+    let sources = load()?;
+    let mut index = FunctionIndex::new();
+    for (file, text) in &sources {
+        if let Err(e) = index.add_source(file, text) {
+            tracing::info!(file = %file, error = %e, "skipping file");
+        }
+    }
+
+    let mut findings = Vec::new();
+    for (file, text) in &sources {
+        let Ok(lenses) = index.lens(file, text, threshold, options, &ComparisonBudget::unlimited())
+        else {
+            continue;
+        };
+        for lens in lenses {
+            if let Some((indexed, similarity)) = &lens.best {
+                let found = IndexMatch {
+                    function: lens.function.clone(),
+                    indexed,
+                    similarity: *similarity,
+                };
+                let mut finding = match_json(&found);
+                finding["file"] = json!(file);
+                findings.push(finding);
+            }
+        }
+    }
+
+    let mut encoded = Vec::new();
+    index.write_to(&mut encoded)?;
+    let snapshot = Snapshot {
+        generation,
+        index: encoded,
+        files: sources.len(),
+        functions: index.len(),
+        findings,
+    };
+    Ok((snapshot, index))
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Decoded value of a query parameter
+    fn query(&self, name: &str) -> Option<String> {
+        self.query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| percent_decode(value))
+    }
+}
+
+/// The request line and headers of an HTTP/1.1 request, or the error status
+/// and message to answer
+fn read_head(reader: &mut impl BufRead) -> Result<Request, (u16, String)> {
+    let request_line = read_line(reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err((400, "malformed request line".to_string()));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err((400, "too many headers".to_string()));
+        }
+        let (name, value) =
+            line.split_once(':').ok_or_else(|| (400, format!("malformed header {:?}", line)))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body: Vec::new(),
+    })
+}
+
+/// Read the body announced by the headers, growing the buffer as bytes
+/// arrive rather than trusting `Content-Length` up front
+fn read_body(reader: &mut impl BufRead, request: &mut Request) -> Result<(), (u16, String)> {
+    let length = match request.header("content-length") {
+        Some(length) => {
+            length.parse::<usize>().map_err(|_| (400, "invalid Content-Length".to_string()))?
+        }
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err((413, format!("body larger than {} bytes", MAX_BODY_BYTES)));
+    }
+    reader.take(length as u64).read_to_end(&mut request.body).map_err(read_error)?;
+    if request.body.len() < length {
+        return Err((400, format!("incomplete body: {} of {} bytes", request.body.len(), length)));
+    }
+    Ok(())
+}
+
+/// Status and message for a failed read, 408 when the request took too long
+fn read_error(error: io::Error) -> (u16, String) {
+    match error.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+            (408, "request not received in time".to_string())
+        }
+        _ => (400, format!("failed to read the request: {}", error)),
+    }
+}
+
+/// A connection that fails reads once the deadline of its request has passed,
+/// however the client spreads the bytes over time
+struct DeadlineReader {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+/// One line without its line break, empty at the end of the input
+fn read_line(reader: &mut impl BufRead) -> Result<String, (u16, String)> {
+    let mut line = String::new();
+    reader.take(MAX_LINE_BYTES).read_line(&mut line).map_err(read_error)?;
+    if line.len() as u64 == MAX_LINE_BYTES && !line.ends_with('\n') {
+        return Err((400, "request line or header too long".to_string()));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn write_response(mut stream: impl Write, status: u16, body: &Value) -> io::Result<()> {
+    let body = body.to_string();
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        reason,
+        body.len()
+    );
+    if status == 401 {
+        head.push_str("WWW-Authenticate: Bearer\r\n");
+    }
+    write!(stream, "{}\r\n{}", head, body)?;
+    stream.flush()
+}
+
+fn error_body(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+/// `%XX` escapes and `+` decoded, as in query strings
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 2;
+            }
+            (None, b'+') => decoded.push(b' '),
+            (None, byte) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Comparison taking the same time wherever the inputs differ, so response
+/// times do not reveal how much of a token was guessed right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOTAL: &str = "export function sumPrices(items) {\n  let total = 0;\n  for (const item of items) {\n    total += item.price * item.quantity;\n  }\n  return total;\n}\n";
+
+    type Sources = Arc<Mutex<Vec<(String, String)>>>;
+
+    fn server(sources: &Sources, token: Option<&str>) -> Server {
+        let sources = Arc::clone(sources);
+        let load: SourceLoader = Box::new(move || Ok(sources.lock().unwrap().clone()));
+        let options = TSEDOptions { size_penalty: false, ..TSEDOptions::default() };
+        Server::new(load, 0.9, options, token.map(str::to_string)).unwrap()
+    }
+
+    fn raw_request(method: &str, target: &str, body: &str, token: Option<&str>) -> String {
+        let mut raw = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n",
+            method,
+            target,
+            body.len()
+        );
+        if let Some(token) = token {
+            raw.push_str(&format!("Authorization: Bearer {}\r\n", token));
+        }
+        raw.push_str("\r\n");
+        raw.push_str(body);
+        raw
+    }
+
+    fn call(server: &Server, method: &str, target: &str, body: &str) -> (u16, Value) {
+        let raw = raw_request(method, target, body, None);
+        let request = server.receive(&mut raw.as_bytes()).unwrap();
+        server.route(&request, &mut LocalIndex::default())
+    }
+
+    #[test]
+    fn test_findings_snippets_and_reanalysis() {
+        let sources: Sources = Arc::new(Mutex::new(vec![
+            ("src/prices.ts".to_string(), TOTAL.to_string()),
+            ("src/cart.ts".to_string(), TOTAL.replace("sumPrices", "cartTotal")),
+        ]));
+        let server = server(&sources, None);
+        assert_eq!(server.indexed(), (2, 2));
+
+        let (status, body) = call(&server, "GET", "/findings", "");
+        assert_eq!(status, 200);
+        assert_eq!(body["findings"].as_array().unwrap().len(), 2);
+        let (_, body) = call(&server, "GET", "/findings?file=src%2Fcart.ts", "");
+        let findings = body["findings"].as_array().unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0]["name"], "cartTotal");
+        assert_eq!(findings[0]["similarTo"]["file"], "src/prices.ts");
+
+        let snippet = json!({ "text": TOTAL.replace("sumPrices", "orderTotal") }).to_string();
+        let (status, body) = call(&server, "POST", "/query-snippet", &snippet);
+        assert_eq!(status, 200);
+        let matches = body["matches"].as_array().unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0]["name"], "orderTotal");
+
+        sources.lock().unwrap().pop();
+        let (status, body) = call(&server, "POST", "/analyze", "");
+        assert_eq!(status, 200);
+        assert_eq!(body, json!({ "files": 1, "functions": 1, "findings": 0 }));
+        let (_, body) = call(&server, "GET", "/findings", "");
+        assert_eq!(body["findings"], json!([]));
+        let (_, body) = call(&server, "POST", "/query-snippet", &snippet);
+        assert_eq!(body["matches"][0]["similarTo"]["name"], "sumPrices");
+    }
+
+    #[test]
+    fn test_tokens_and_bad_requests() {
+        let sources: Sources = Arc::new(Mutex::new(Vec::new()));
+        let server = server(&sources, Some("secret"));
+        let status = |token: Option<&str>| match server
+            .receive(&mut raw_request("GET", "/findings", "", token).as_bytes())
+        {
+            Ok(request) => server.route(&request, &mut LocalIndex::default()).0,
+            Err((status, _)) => status,
+        };
+        assert_eq!(status(None), 401);
+        assert_eq!(status(Some("guess")), 401);
+        assert_eq!(status(Some("secret")), 200);
+
+        // The token is checked before the announced body is read
+        let unauthorized =
+            format!("POST /analyze HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES);
+        assert_eq!(server.receive(&mut unauthorized.as_bytes()).err().unwrap().0, 401);
+        let truncated = raw_request("POST", "/analyze", "{}", Some("secret")).replace("{}", "{");
+        assert_eq!(server.receive(&mut truncated.as_bytes()).err().unwrap().0, 400);
+
+        let server = self::server(&sources, None);
+        assert_eq!(call(&server, "GET", "/metrics", "").0, 404);
+        assert_eq!(call(&server, "GET", "/analyze", "").0, 405);
+        assert_eq!(call(&server, "POST", "/query-snippet", "{").0, 400);
+        assert_eq!(call(&server, "POST", "/query-snippet", "{}").0, 400);
+
+        let oversized =
+            format!("POST /analyze HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES + 1);
+        assert_eq!(server.receive(&mut oversized.as_bytes()).err().unwrap().0, 413);
+        assert_eq!(server.receive(&mut "\r\n".as_bytes()).err().unwrap().0, 400);
+        assert_eq!(percent_decode("a%2Fb+c%zz"), "a/b c%zz");
+    }
+
+    #[test]
+    fn test_answers_concurrent_connections() {
+        let sources: Sources =
+            Arc::new(Mutex::new(vec![("src/prices.ts".to_string(), TOTAL.to_string())]));
+        let server = Arc::new(server(&sources, Some("secret")));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || server.serve(listener, 2));
+
+        let clients: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    let snippet = json!({ "text": TOTAL }).to_string();
+                    let mut stream = TcpStream::connect(address).unwrap();
+                    let request = raw_request("POST", "/query-snippet", &snippet, Some("secret"));
+                    stream.write_all(request.as_bytes()).unwrap();
+                    let mut response = String::new();
+                    stream.read_to_string(&mut response).unwrap();
+                    response
+                })
+            })
+            .collect();
+        for client in clients {
+            let response = client.join().unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+            let body: Value =
+                serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+            assert_eq!(body["matches"][0]["similarTo"]["name"], "sumPrices");
+        }
+    }
+
+    #[test]
+    fn test_deadline_covers_the_whole_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        // Each byte arrives well within any per-read timeout, the request never completes
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            for byte in b"GET /findings HTTP/1.1\r\nHost: localhost\r\n" {
+                if stream.write_all(&[*byte]).is_err() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        let started = Instant::now();
+        let deadline = started + Duration::from_millis(200);
+        let mut reader = BufReader::new(DeadlineReader { stream, deadline });
+        assert_eq!(read_head(&mut reader).err().unwrap().0, 408);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        drop(reader);
+        client.join().unwrap();
+    }
+}
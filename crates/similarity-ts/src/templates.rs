@@ -0,0 +1,326 @@
+//! Functions in the `<script>` blocks of EJS, Handlebars, ERB and HTML templates.
+//!
+//! Helper functions are often copied from one template to the next. The
+//! scanner keeps the scripts of a template at their original byte offsets
+//! and blanks everything else, so the regular function extraction runs on
+//! the result and its line numbers point into the template itself. Template
+//! tags inside a script (`<%= ... %>`, `{{ ... }}`) become placeholder
+//! identifiers of the same length, which keeps the surrounding code
+//! parseable. Scripts whose `type` is not JavaScript (JSON data, client-side
+//! templates) are skipped; `lang="ts"` or a TypeScript `type` parses the
+//! template as TypeScript.
+
+use similarity_core::cli_output::format_function_output;
+use similarity_core::tsed::TSEDOptions;
+use similarity_core::{
+    extract_functions, find_similar_functions_across_files, find_similar_functions_in_file,
+    SimilarityResult,
+};
+use std::collections::HashMap;
+
+/// Extensions of the template files scanned for scripts
+pub const TEMPLATE_EXTENSIONS: [&str; 6] = ["ejs", "hbs", "handlebars", "erb", "html", "htm"];
+
+const JS_TYPES: [&str; 5] =
+    ["text/javascript", "application/javascript", "module", "text/babel", "text/jsx"];
+const TS_TYPES: [&str; 2] = ["text/typescript", "application/typescript"];
+/// Template tag delimiters that may appear inside a script
+const TAGS: [(&str, &str); 3] = [("<%", "%>"), ("{{{", "}}}"), ("{{", "}}")];
+
+/// The scripts of a template laid out at their original offsets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateScripts {
+    /// The template with everything outside the scripts replaced by spaces
+    pub source: String,
+    /// Number of script blocks found
+    pub blocks: usize,
+    pub typescript: bool,
+}
+
+/// Scan a template for inline scripts
+pub fn extract_scripts(template: &str) -> TemplateScripts {
+    let lower = template.to_ascii_lowercase();
+    let mut source = String::with_capacity(template.len());
+    let mut blocks = 0;
+    let mut typescript = false;
+    let mut position = 0;
+
+    while let Some(offset) = lower[position..].find("<script") {
+        let tag_start = position + offset;
+        let Some(tag_length) = lower[tag_start..].find('>') else {
+            break;
+        };
+        let body_start = tag_start + tag_length + 1;
+        let body_end =
+            lower[body_start..].find("</script").map_or(lower.len(), |end| body_start + end);
+        blank(&template[position..body_start], &mut source);
+        position = body_end;
+
+        let script_type = match script_language(&lower[tag_start..body_start]) {
+            Some(script_type) => script_type,
+            None => {
+                blank(&template[body_start..body_end], &mut source);
+                continue;
+            }
+        };
+        typescript |= script_type;
+        blocks += 1;
+        mask_template_tags(&template[body_start..body_end], &mut source);
+    }
+    blank(&template[position..], &mut source);
+
+    TemplateScripts { source, blocks, typescript }
+}
+
+/// Whether an opening `<script ...>` tag holds TypeScript (`Some(true)`),
+/// JavaScript (`Some(false)`) or something else (`None`)
+fn script_language(tag: &str) -> Option<bool> {
+    if attribute(tag, "src").is_some() {
+        return None;
+    }
+    if attribute(tag, "lang").is_some_and(|lang| lang == "ts" || lang == "typescript") {
+        return Some(true);
+    }
+    match attribute(tag, "type") {
+        None => Some(false),
+        Some(script_type) if TS_TYPES.contains(&script_type) => Some(true),
+        Some(script_type) if JS_TYPES.contains(&script_type) => Some(false),
+        Some(_) => None,
+    }
+}
+
+/// Value of `name="..."` (or single-quoted, or bare) in a lowercased tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    loop {
+        let offset = rest.find(name)?;
+        let preceded_by_space = rest[..offset].chars().next_back().is_some_and(char::is_whitespace);
+        let after = rest[offset + name.len()..].trim_start();
+        rest = &rest[offset + name.len()..];
+        if !preceded_by_space {
+            continue;
+        }
+        // Bare attributes such as `defer` have no value
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        return Some(match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &value[1..];
+                &value[..value.find(quote).unwrap_or(value.len())]
+            }
+            _ => {
+                let end =
+                    value.find(|c: char| c.is_whitespace() || c == '>').unwrap_or(value.len());
+                &value[..end]
+            }
+        });
+    }
+}
+
+/// Copy `script`, replacing template tags with placeholder identifiers of
+/// the same byte length
+fn mask_template_tags(script: &str, out: &mut String) {
+    let mut rest = script;
+    while let Some((start, open, close)) = TAGS
+        .iter()
+        .filter_map(|&(open, close)| rest.find(open).map(|start| (start, open, close)))
+        .min_by_key(|&(start, open, _)| (start, std::cmp::Reverse(open.len())))
+    {
+        out.push_str(&rest[..start]);
+        let end = rest[start + open.len()..]
+            .find(close)
+            .map_or(rest.len(), |end| start + open.len() + end + close.len());
+        let tag = &rest[start..end];
+        // An identifier up to the first line break, whitespace after it
+        let (first_line, following) = tag.split_at(tag.find('\n').unwrap_or(tag.len()));
+        out.push_str(&"_".repeat(first_line.len()));
+        blank(following, out);
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+}
+
+/// Append `text` with every character but line breaks replaced by spaces,
+/// keeping byte offsets
+fn blank(text: &str, out: &mut String) {
+    for c in text.chars() {
+        if c == '\n' || c == '\r' {
+            out.push(c);
+        } else {
+            out.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    }
+}
+
+/// Two similar functions found in templates
+#[derive(Debug, Clone)]
+pub struct TemplateDuplicate {
+    pub first_file: String,
+    pub second_file: String,
+    pub result: SimilarityResult,
+}
+
+/// Similar functions within and across the scripts of `templates`, given as
+/// (path, content) pairs, most similar first. Templates whose scripts do not
+/// parse are returned with the error.
+pub fn find_similar_template_functions(
+    templates: &[(String, String)],
+    threshold: f64,
+    options: &TSEDOptions,
+) -> (Vec<TemplateDuplicate>, Vec<(String, String)>) {
+    let mut duplicates = Vec::new();
+    let mut errors = Vec::new();
+    // Scripts are parsed under a script file name, mapped back to the template afterwards
+    let mut templates_by_name = HashMap::new();
+    let mut sources = Vec::new();
+    for (path, content) in templates {
+        let scripts = extract_scripts(content);
+        if scripts.blocks == 0 {
+            continue;
+        }
+        let name = format!("{}.{}", path, if scripts.typescript { "ts" } else { "js" });
+        if let Err(e) = extract_functions(&name, &scripts.source) {
+            errors.push((path.clone(), e));
+            continue;
+        }
+        if let Ok(results) =
+            find_similar_functions_in_file(&name, &scripts.source, threshold, options)
+        {
+            duplicates.extend(results.into_iter().map(|result| TemplateDuplicate {
+                first_file: path.clone(),
+                second_file: path.clone(),
+                result,
+            }));
+        }
+        templates_by_name.insert(name.clone(), path.clone());
+        sources.push((name, scripts.source));
+    }
+
+    if let Ok(results) = find_similar_functions_across_files(&sources, threshold, options) {
+        duplicates.extend(results.into_iter().map(|(first, result, second)| TemplateDuplicate {
+            first_file: templates_by_name[&first].clone(),
+            second_file: templates_by_name[&second].clone(),
+            result,
+        }));
+    }
+    duplicates.sort_by(|a, b| b.result.similarity.total_cmp(&a.result.similarity));
+    (duplicates, errors)
+}
+
+/// Print similar template functions; returns the number of pairs
+pub fn report_similar_template_functions(duplicates: &[TemplateDuplicate], print: bool) -> usize {
+    if duplicates.is_empty() {
+        println!("\nNo duplicate template functions found!");
+        return 0;
+    }
+
+    println!("\nDuplicate template functions:");
+    println!("{}", "-".repeat(60));
+    for duplicate in duplicates {
+        let (func1, func2) = (&duplicate.result.func1, &duplicate.result.func2);
+        println!("\nSimilarity: {:.2}%", duplicate.result.similarity * 100.0);
+        println!(
+            "  {}",
+            format_function_output(
+                &duplicate.first_file,
+                &func1.name,
+                func1.start_line,
+                func1.end_line
+            )
+        );
+        println!(
+            "  {}",
+            format_function_output(
+                &duplicate.second_file,
+                &func2.name,
+                func2.start_line,
+                func2.end_line
+            )
+        );
+
+        if print {
+            for (file, function) in
+                [(&duplicate.first_file, func1), (&duplicate.second_file, func2)]
+            {
+                similarity_core::cli_output::show_function_code(
+                    file,
+                    &function.name,
+                    function.start_line,
+                    function.end_line,
+                );
+            }
+        }
+    }
+    println!("\nTotal duplicate template function pairs found: {}", duplicates.len());
+
+    duplicates.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html>
+<body>
+  <h1><%= title %></h1>
+  <script type="application/json">{"items": []}</script>
+  <script>
+    const endpoint = "<%= apiUrl %>";
+    function formatPrice(amount, currency) {
+      const rounded = Math.round(amount * 100) / 100;
+      const label = rounded.toFixed(2);
+      return currency + " " + label;
+    }
+  </script>
+</body>
+</html>
+"#;
+
+    #[test]
+    fn test_scripts_keep_their_template_lines() {
+        let scripts = extract_scripts(PAGE);
+        assert_eq!(scripts.blocks, 1);
+        assert!(!scripts.typescript);
+        assert_eq!(scripts.source.len(), PAGE.len());
+        assert!(!scripts.source.contains("title") && !scripts.source.contains("items"));
+        assert!(scripts.source.contains("const endpoint = \"_____________\";"));
+
+        let functions = extract_functions("page.ejs.js", &scripts.source).unwrap();
+        assert_eq!(functions.len(), 1);
+        assert_eq!((functions[0].start_line, functions[0].end_line), (7, 11));
+    }
+
+    #[test]
+    fn test_helpers_duplicated_across_templates() {
+        let other = PAGE
+            .replace("formatPrice(amount, currency)", "priceLabel(value, unit)")
+            .replace("amount", "value")
+            .replace("currency", "unit")
+            .replace("<%= apiUrl %>", "{{ apiUrl }}");
+        let templates = vec![
+            ("views/cart.ejs".to_string(), PAGE.to_string()),
+            ("views/checkout.hbs".to_string(), other),
+            ("views/broken.erb".to_string(), "<script>function (</script>".to_string()),
+        ];
+        let options = TSEDOptions { size_penalty: false, ..TSEDOptions::default() };
+        let (duplicates, errors) = find_similar_template_functions(&templates, 0.9, &options);
+
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].first_file, "views/cart.ejs");
+        assert_eq!(duplicates[0].second_file, "views/checkout.hbs");
+        assert_eq!(duplicates[0].result.func2.start_line, 7);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "views/broken.erb");
+    }
+
+    #[test]
+    fn test_script_language() {
+        assert_eq!(script_language("<script>"), Some(false));
+        assert_eq!(script_language("<script type=\"module\" defer>"), Some(false));
+        assert_eq!(script_language("<script lang='ts'>"), Some(true));
+        assert_eq!(script_language("<script type=\"text/x-handlebars-template\">"), None);
+        assert_eq!(script_language("<script src=\"/app.js\">"), None);
+    }
+}
@@ -78,7 +78,7 @@ impl LanguageParser for TypeScriptParser {
         Ok(types
             .into_iter()
             .map(|t| GenericTypeDef {
-                name: t.name,
+                name: t.name.to_string(),
                 kind: match t.kind {
                     TypeKind::Interface => "interface".to_string(),
                     TypeKind::TypeAlias => "type_alias".to_string(),
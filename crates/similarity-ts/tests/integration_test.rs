@@ -379,3 +379,2510 @@ export function found() {
         .success()
         .stdout(predicate::str::contains("No duplicate functions found"));
 }
+
+#[test]
+fn test_vscode_output_format() {
+    let dir = tempdir().unwrap();
+
+    fs::write(
+        dir.path().join("sample.ts"),
+        r#"
+export function calculateSum(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+
+export function computeTotal(values: number[]): number {
+    if (values.length === 0) return 0;
+
+    let sum = 0;
+    for (const val of values) {
+        sum += val;
+    }
+
+    return sum;
+}
+"#,
+    )
+    .unwrap();
+
+    // Each finding is a single `file:line:col: warning:` line that also
+    // carries the location of the other copy
+    let mut cmd = Command::cargo_bin("similarity-ts").unwrap();
+    cmd.arg(dir.path())
+        .arg("-t")
+        .arg("0.8")
+        .arg("--no-size-penalty")
+        .arg("--output")
+        .arg("vscode")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"sample\.ts:\d+:1: warning: Function '\w+' is \d+% similar to '\w+' at .*sample\.ts:\d+:1").unwrap())
+        .stdout(predicate::str::contains("Analyzing code similarity").not())
+        .stdout(predicate::str::contains("Similarity:").not());
+}
+
+#[test]
+fn test_pr_comment_collects_the_findings_of_every_analyzer() {
+    let dir = tempdir().unwrap();
+    let source = r#"export function sumPrices(items: { price: number; quantity: number }[]): number {
+  let total = 0;
+  for (const item of items) {
+    total += item.price * item.quantity;
+  }
+  return total;
+}
+export interface User { id: string; name: string; email: string; age: number }
+"#;
+    fs::write(dir.path().join("a.ts"), source).unwrap();
+    fs::write(
+        dir.path().join("b.ts"),
+        source.replace("sumPrices", "cartTotal").replace("User", "Person"),
+    )
+    .unwrap();
+
+    // Function and type findings end up in the one comment printed at the end
+    let mut cmd = Command::cargo_bin("similarity-ts").unwrap();
+    cmd.current_dir(dir.path())
+        .args([".", "--min-lines", "1", "--no-size-penalty", "--output", "pr-comment"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("<!-- similarity-report -->"))
+        .stdout(predicate::str::contains("**2 duplicates**"))
+        .stdout(predicate::str::contains("`sumPrices`"))
+        .stdout(predicate::str::contains("`b.ts:8`"))
+        .stdout(predicate::str::contains("{\"").not());
+}
+
+#[test]
+fn test_output_formats_resolve_through_the_formatter_registry() {
+    let dir = tempdir().unwrap();
+    let source = "export function sumPrices(items) {\n  let total = 0;\n  for (const item of items) {\n    total += item.price * item.quantity;\n  }\n  return total;\n}\n";
+    fs::write(dir.path().join("a.ts"), source).unwrap();
+    fs::write(dir.path().join("b.ts"), source.replace("sumPrices", "cartTotal")).unwrap();
+
+    let mut cmd = Command::cargo_bin("similarity-ts").unwrap();
+    cmd.current_dir(dir.path())
+        .args([".", "--min-lines", "1", "--no-size-penalty", "--output", "text"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("warning: Function 'sumPrices' is 97% similar to 'cartTotal'\n  --> ./a.ts:1\n  --> ./b.ts:1"))
+        .stdout(predicate::str::contains("Analyzing code similarity").not());
+
+    let mut cmd = Command::cargo_bin("similarity-ts").unwrap();
+    cmd.current_dir(dir.path()).args([".", "--output", "sarif"]).assert().failure().stderr(
+        predicate::str::contains(
+            "unknown output format 'sarif' (expected standard, pr-comment, text, vscode, json)",
+        ),
+    );
+}
+
+#[test]
+fn test_json_output_carries_fixes() {
+    let dir = tempdir().unwrap();
+    let source = r#"export function total(items: number[]): number {
+  let sum = 0;
+  for (const item of items) {
+    sum += item;
+  }
+  return sum;
+}
+"#;
+    fs::write(dir.path().join("a.ts"), source).unwrap();
+    fs::write(dir.path().join("b.ts"), source.replace("total", "grandTotal")).unwrap();
+
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args([".", "--no-size-penalty", "--no-types", "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let findings: Vec<serde_json::Value> =
+        stdout.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(findings.len(), 1);
+    let edits = &findings[0]["fixes"][0]["edits"];
+    assert_eq!(edits[0]["kind"], "insert_import");
+    assert_eq!(edits[0]["text"], "import { total as grandTotal } from \"./a\";");
+    assert_eq!(edits[1]["kind"], "replace_range");
+    assert_eq!(edits[1]["text"], "export { grandTotal };");
+    assert_eq!(findings[0]["algorithm"], "tsed");
+}
+
+#[test]
+fn test_json_findings_of_every_analyzer_carry_the_schema_version() {
+    let dir = tempdir().unwrap();
+    let user = "interface User {\n  id: string;\n  name: string;\n  email: string;\n}\n";
+    fs::write(dir.path().join("a.ts"), user).unwrap();
+    fs::write(dir.path().join("b.ts"), user.replace("User", "Account")).unwrap();
+
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args([".", "--no-functions", "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.trim().is_empty());
+    for line in stdout.lines() {
+        let finding: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(finding["schema_version"], similarity_core::SCHEMA_VERSION, "{line}");
+    }
+}
+
+#[test]
+fn test_large_functions_fall_back_above_max_tree_nodes() {
+    let dir = tempdir().unwrap();
+    let source = r#"export function total(items: number[]): number {
+  let sum = 0;
+  for (const item of items) {
+    sum += item;
+  }
+  return sum;
+}
+"#;
+    fs::write(dir.path().join("a.ts"), source).unwrap();
+    fs::write(dir.path().join("b.ts"), source.replace("total", "grandTotal")).unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args([".", "--no-size-penalty", "--no-types"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let json = run(&["--max-tree-nodes", "5", "--output", "json"]);
+    let finding: serde_json::Value = serde_json::from_str(json.lines().next().unwrap()).unwrap();
+    assert_eq!(finding["algorithm"], "fallback");
+    assert_eq!(finding["schema_version"], similarity_core::SCHEMA_VERSION);
+
+    let standard = run(&["--max-tree-nodes", "5"]);
+    assert!(standard.contains("Algorithm: fallback"), "{standard}");
+    assert!(!run(&[]).contains("Algorithm: fallback"));
+}
+
+#[test]
+fn test_copies_of_exported_functions_suggest_an_import() {
+    let dir = tempdir().unwrap();
+    let source = r#"export function total(items: number[]): number {
+  let sum = 0;
+  for (const item of items) {
+    sum += item;
+  }
+  return sum;
+}
+"#;
+    fs::create_dir_all(dir.path().join("src/utils")).unwrap();
+    fs::write(dir.path().join("src/utils/math.ts"), source).unwrap();
+    fs::write(
+        dir.path().join("src/report.ts"),
+        source.replace("export function total", "function grandTotal"),
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(["src", "--no-size-penalty", "--no-types"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let standard = run(&[]);
+    assert!(
+        standard.contains(
+            "Should import instead of duplicate: 'grandTotal' in src/report.ts copies an exported function; \
+             use `import { total as grandTotal } from \"./utils/math\";`"
+        ),
+        "{standard}"
+    );
+
+    let json = run(&["--output", "json"]);
+    let finding: serde_json::Value = serde_json::from_str(json.lines().next().unwrap()).unwrap();
+    assert_eq!(finding["import_instead"]["file"], "src/report.ts");
+    assert_eq!(finding["import_instead"]["module"], "./utils/math");
+}
+
+#[test]
+fn test_preset_settings_yield_to_config_and_flags() {
+    let dir = tempdir().unwrap();
+    let source = r#"export function total(items: number[]): number {
+  let sum = 0;
+  for (const item of items) {
+    sum += item;
+  }
+  return sum;
+}
+"#;
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::create_dir_all(dir.path().join("generated")).unwrap();
+    fs::write(dir.path().join("src/a.ts"), source).unwrap();
+    fs::write(dir.path().join("generated/b.ts"), source.replace("total", "grandTotal")).unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args([".", "--no-size-penalty"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // The preset excludes generated code
+    assert!(run(&[]).contains("generated/b.ts"));
+    assert!(!run(&["--preset", "react"]).contains("generated/b.ts"));
+
+    // similarity.toml replaces the preset's excludes and threshold
+    fs::write(
+        dir.path().join("similarity.toml"),
+        "preset = \"react\"\nexclude = []\nthreshold = 1.1\n",
+    )
+    .unwrap();
+    assert!(!run(&[]).contains("generated/b.ts"));
+    // ... and flags override similarity.toml
+    assert!(run(&["--threshold", "0.8"]).contains("generated/b.ts"));
+}
+
+#[test]
+fn test_doctor_checks_parsers_and_prints_effective_settings() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("similarity.toml"), "preset = \"node-api\"\nthreshold = 0.9\n")
+        .unwrap();
+
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["doctor", "src", "--min-tokens", "20"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("ok    TypeScript"), "{stdout}");
+    assert!(!stdout.contains("FAIL"), "{stdout}");
+    assert!(stdout.contains("similarity.toml"), "{stdout}");
+    assert!(stdout.contains("Preset:       node-api"), "{stdout}");
+    assert!(stdout.contains("Analyzers:    functions, types, classes"), "{stdout}");
+    assert!(stdout.contains("Threshold:    0.9"), "{stdout}");
+    assert!(stdout.contains("Min size:     20 tokens"), "{stdout}");
+    // Only the probe is written, and removed again
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_findings_show_scope_chain_and_export_status() {
+    let dir = tempdir().unwrap();
+    let body = r#"(payload: string[]): number {
+    let count = 0;
+    for (const item of payload) {
+      count += item.length;
+    }
+    return count;
+  }"#;
+    fs::write(
+        dir.path().join("service.ts"),
+        format!("export namespace Users {{\n  export function updateProfile{body}\n}}\n"),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("helpers.ts"),
+        format!("function outer() {{\n  function measure{body}\n  return measure;\n}}\n"),
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args([".", "--no-size-penalty", "--no-types"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let standard = run(&[]);
+    assert!(standard.contains("Users.updateProfile (exported)"), "{standard}");
+    assert!(standard.contains("outer > measure\n"), "{standard}");
+
+    let json = run(&["--output", "json"]);
+    let finding: serde_json::Value = serde_json::from_str(json.lines().next().unwrap()).unwrap();
+    let symbols = [&finding["symbol"], &finding["related"]["symbol"]];
+    assert!(symbols
+        .iter()
+        .any(|symbol| symbol["path"] == "outer > measure" && symbol["exported"] == false));
+    assert!(symbols
+        .iter()
+        .any(|symbol| symbol["path"] == "Users.updateProfile" && symbol["exported"] == true));
+}
+
+#[test]
+fn test_export_matrix_includes_pairs_below_the_threshold() {
+    let dir = tempdir().unwrap();
+    let matrix_path = dir.path().join("matrix.csv");
+    fs::write(
+        dir.path().join("a.ts"),
+        r#"
+export function calculateSum(numbers: number[]): number {
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+    return total;
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("b.ts"),
+        r#"
+export function countPositive(values: number[]): number {
+    let count = 0;
+    for (const value of values) {
+        if (value > 0) {
+            count++;
+        }
+    }
+    return count;
+}
+"#,
+    )
+    .unwrap();
+
+    let run = |floor: &str| {
+        Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .arg(dir.path())
+            .args(["--threshold", "0.99", "--no-size-penalty", "--matrix-floor", floor])
+            .arg("--export-matrix")
+            .arg(&matrix_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("No duplicate functions found"));
+        fs::read_to_string(&matrix_path).unwrap()
+    };
+
+    let matrix = run("0.3");
+    let rows: Vec<&str> = matrix.lines().collect();
+    assert_eq!(
+        rows[0],
+        "kind,file1,name1,start_line1,end_line1,file2,name2,start_line2,end_line2,similarity"
+    );
+    assert_eq!(rows.len(), 2, "{matrix}");
+    assert!(rows[1].starts_with("function,"), "{matrix}");
+    assert!(rows[1].contains("calculateSum,2,8,"), "{matrix}");
+    assert!(rows[1].contains("countPositive,2,10,"), "{matrix}");
+
+    assert_eq!(run("1.0").lines().count(), 1);
+}
+
+#[test]
+fn test_migration_reports_js_functions_ported_to_ts() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("cart.js"),
+        "export function cartTotal(items) {
+    let total = 0;
+    for (const item of items) {
+        total += item.price * item.quantity;
+    }
+    return total;
+}
+",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("cart.ts"),
+        "export function cartTotal(items: Item[]): number {
+    let total = 0;
+    for (const item of items) {
+        total += item.price! * (item.quantity as number);
+    }
+    return total;
+}
+",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("legacy.js"),
+        "export function ping(host) {\n    return fetch(host);\n}\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--migration", "--fail-on-duplicates"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("=== Migration ==="))
+        .stdout(predicate::str::contains("Matching 2 JavaScript file(s) with 1 TypeScript file(s)"))
+        .stdout(predicate::str::contains("Similarity: 100.00% (high confidence)"))
+        .stdout(predicate::str::contains("cart.ts:1-7 cartTotal"))
+        .stdout(predicate::str::is_match(r"safe to delete:\n  \S*cart\.js\n").unwrap())
+        .stdout(predicate::str::contains("legacy.js").not())
+        .stdout(predicate::str::contains("Function Similarity").not());
+}
+
+#[test]
+fn test_export_heatmap() {
+    let dir = tempdir().unwrap();
+    let sample_path = dir.path().join("sample.ts");
+    let export_path = dir.path().join("heatmap.json");
+
+    fs::write(
+        &sample_path,
+        r#"
+export function calculateSum(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+
+export function computeTotal(values: number[]): number {
+    if (values.length === 0) return 0;
+
+    let sum = 0;
+    for (const val of values) {
+        sum += val;
+    }
+
+    return sum;
+}
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("similarity-ts").unwrap();
+    cmd.arg(&sample_path)
+        .arg("-t")
+        .arg("0.8")
+        .arg("--no-size-penalty")
+        .arg("--export")
+        .arg(&export_path)
+        .assert()
+        .success();
+
+    let exported = fs::read_to_string(&export_path).unwrap();
+    assert!(exported.contains("\"schema_version\": 1"));
+    assert!(exported.contains("\"files\""));
+    assert!(exported.contains("sample.ts"));
+    assert!(exported.contains("\"duplicated_lines\": 20"));
+    assert!(exported.contains("\"functions\": 1"));
+}
+
+#[test]
+fn test_blame_provenance() {
+    let dir = tempdir().unwrap();
+    let git = |args: &[&str], author: &str, date: &str| {
+        let status = std::process::Command::new("git")
+            .args(["-c", &format!("user.name={author}"), "-c", "user.email=dev@example.com"])
+            .args(args)
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date)
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+
+    let original = r#"
+export function calculateSum(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+"#;
+    let copy = r#"
+export function computeTotal(values: number[]): number {
+    if (values.length === 0) return 0;
+
+    let sum = 0;
+    for (const val of values) {
+        sum += val;
+    }
+
+    return sum;
+}
+"#;
+
+    git(&["init", "-q"], "Alice", "2020-01-01T00:00:00Z");
+    fs::write(dir.path().join("original.ts"), original).unwrap();
+    git(&["add", "."], "Alice", "2020-01-01T00:00:00Z");
+    git(&["commit", "-q", "-m", "original"], "Alice", "2020-01-01T00:00:00Z");
+    fs::write(dir.path().join("copy.ts"), copy).unwrap();
+    git(&["add", "."], "Bob", "2023-06-01T00:00:00Z");
+    git(&["commit", "-q", "-m", "copy"], "Bob", "2023-06-01T00:00:00Z");
+
+    let mut cmd = Command::cargo_bin("similarity-ts").unwrap();
+    cmd.current_dir(dir.path())
+        .arg(".")
+        .arg("-t")
+        .arg("0.8")
+        .arg("--no-size-penalty")
+        .arg("--blame")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Provenance: 'calculateSum' is older (Alice in"))
+        .stdout(predicate::str::contains("2020-01-01"))
+        .stdout(predicate::str::contains("newer copy 'computeTotal' introduced by Bob in"));
+}
+
+#[test]
+fn test_git_remote_against_local_path() {
+    let remote = tempdir().unwrap();
+    let local = tempdir().unwrap();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=dev", "-c", "user.email=dev@example.com"])
+            .args(args)
+            .current_dir(remote.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+
+    git(&["init", "-q", "-b", "main"]);
+    git(&["checkout", "-q", "-b", "release"]);
+    fs::write(
+        remote.path().join("utils.ts"),
+        r#"
+export function calculateSum(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+"#,
+    )
+    .unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "utils"]);
+
+    fs::write(
+        local.path().join("copy.ts"),
+        r#"
+export function computeTotal(values: number[]): number {
+    if (values.length === 0) return 0;
+
+    let sum = 0;
+    for (const val of values) {
+        sum += val;
+    }
+
+    return sum;
+}
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("similarity-ts").unwrap();
+    cmd.arg(local.path())
+        .arg("--git")
+        .arg(format!("file://{}#release", remote.path().display()))
+        .arg("--no-types")
+        .arg("-t")
+        .arg("0.8")
+        .arg("--no-size-penalty")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Fetched file://"))
+        .stdout(predicate::str::contains("computeTotal"))
+        .stdout(predicate::str::contains("utils.ts"))
+        .stdout(predicate::str::contains("calculateSum"));
+}
+
+#[test]
+fn test_triage_mark_and_filter() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("sample.ts"),
+        r#"
+export function calculateSum(numbers: number[]): number {
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+    return total;
+}
+
+export function computeTotal(values: number[]): number {
+    let sum = 0;
+    for (const val of values) {
+        sum += val;
+    }
+    return sum;
+}
+"#,
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let report = run(&[".", "--no-types", "--no-size-penalty"]);
+    let id = report
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("ID: "))
+        .expect("findings should have an ID")
+        .to_string();
+
+    let marked = run(&["triage", "mark", &id, "wontfix", "--note", "kept for clarity"]);
+    assert!(marked.contains(&format!("Marked {id} as wontfix")));
+    assert!(dir.path().join(".similarity/triage.json").exists());
+    assert!(run(&["triage", "list"]).contains(&id));
+
+    let annotated = run(&[".", "--no-types", "--no-size-penalty"]);
+    assert!(annotated.contains(&format!("ID: {id} (triage: wontfix - kept for clarity)")));
+
+    let untriaged = run(&[".", "--no-types", "--no-size-penalty", "--triage-state", "new,fixme"]);
+    assert!(untriaged.contains("No duplicate functions found matching the triage states!"));
+    assert!(!untriaged.contains("calculateSum"));
+
+    run(&["triage", "mark", &id, "new"]);
+    assert!(run(&["triage", "list"]).contains("No triaged findings."));
+}
+
+#[test]
+fn test_triage_survives_renames_and_edits() {
+    let dir = tempdir().unwrap();
+    let source = r#"
+export function applyDiscount(items: Item[], rate: number): number {
+    let total = 0;
+    for (const item of items) {
+        total += item.price * item.quantity;
+    }
+    if (total > 100) {
+        total = total * (1 - rate);
+    }
+    return Math.round(total * 100) / 100;
+}
+"#;
+    fs::write(dir.path().join("cart.ts"), source).unwrap();
+    fs::write(dir.path().join("checkout.ts"), source.replace("applyDiscount", "discounted"))
+        .unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let find_id = |report: &str| {
+        report
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("ID: "))
+            .expect("findings should have an ID")
+            .to_string()
+    };
+
+    let id = find_id(&run(&[".", "--no-types", "--no-size-penalty"]));
+    run(&["triage", "mark", &id, "wontfix"]);
+
+    // Moving a file keeps the finding's ID
+    fs::create_dir(dir.path().join("billing")).unwrap();
+    fs::rename(dir.path().join("checkout.ts"), dir.path().join("billing/checkout.ts")).unwrap();
+    let moved = run(&[".", "--no-types", "--no-size-penalty"]);
+    assert!(moved.contains(&format!("ID: {id} (triage: wontfix)")), "{moved}");
+
+    // A small edit changes the ID, but the recorded state still applies
+    let edited = source.replace("    return Math", "    log(total);\n    return Math");
+    fs::write(dir.path().join("cart.ts"), edited).unwrap();
+    let drifted = run(&[".", "--no-types", "--no-size-penalty", "--triage-state", "wontfix"]);
+    assert_ne!(find_id(&drifted), id);
+    assert!(drifted.contains(&format!("(triage: wontfix, recorded as {id})")), "{drifted}");
+}
+
+#[test]
+fn test_notebook_cells() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("sales.ipynb"),
+        r##"{
+  "cells": [
+    { "cell_type": "markdown", "metadata": {}, "source": ["# Sales"] },
+    {
+      "cell_type": "code", "metadata": {}, "outputs": [],
+      "source": [
+        "const rows = await loadRows('sales.csv');\n",
+        "let total = 0;\n",
+        "for (const row of rows) {\n",
+        "  total += row.amount;\n",
+        "}\n",
+        "console.log(total);"
+      ]
+    },
+    { "cell_type": "code", "metadata": {}, "outputs": [], "source": "%time\nconsole.log(rows.length);" }
+  ],
+  "metadata": { "kernelspec": { "name": "deno", "language": "typescript" } },
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"##,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("costs.ipynb"),
+        r##"{
+  "cells": [
+    { "cell_type": "markdown", "metadata": {}, "source": ["# Costs"] },
+    {
+      "cell_type": "code", "metadata": {}, "outputs": [],
+      "source": "const items = await loadRows('costs.csv');\nlet sum = 0;\nfor (const item of items) {\n  sum += item.amount;\n}\nconsole.log(sum);"
+    }
+  ],
+  "metadata": { "kernelspec": { "name": "deno", "language": "typescript" } },
+  "nbformat": 4,
+  "nbformat_minor": 5
+}"##,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("similarity-ts").unwrap();
+    cmd.current_dir(dir.path())
+        .args([".", "--no-functions", "--no-types", "--notebooks", "--no-size-penalty"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("=== Notebook Cell Similarity ==="))
+        .stdout(predicate::str::contains("sales.ipynb:1-6 cell 2"))
+        .stdout(predicate::str::contains("costs.ipynb:1-6 cell 2"))
+        .stdout(predicate::str::contains("Total duplicate cell pairs found: 1"));
+}
+
+#[test]
+fn test_min_complexity_gates_results() {
+    let dir = tempdir().unwrap();
+    let sample_path = dir.path().join("sample.ts");
+
+    fs::write(
+        &sample_path,
+        r#"
+export function calculateSum(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+
+export function computeTotal(values: number[]): number {
+    if (values.length === 0) return 0;
+
+    let sum = 0;
+    for (const val of values) {
+        sum += val;
+    }
+
+    return sum;
+}
+"#,
+    )
+    .unwrap();
+
+    // Both functions have a cyclomatic complexity of 3 (if + for)
+    let mut cmd = Command::cargo_bin("similarity-ts").unwrap();
+    cmd.arg(&sample_path)
+        .arg("-t")
+        .arg("0.8")
+        .arg("--no-size-penalty")
+        .arg("--min-complexity")
+        .arg("3")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("calculateSum"));
+
+    let mut cmd = Command::cargo_bin("similarity-ts").unwrap();
+    cmd.arg(&sample_path)
+        .arg("-t")
+        .arg("0.8")
+        .arg("--no-size-penalty")
+        .arg("--min-complexity")
+        .arg("4")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No duplicate functions found"))
+        .stdout(predicate::str::contains("calculateSum").not());
+}
+
+#[test]
+fn test_boilerplate_dir_suppresses_template_instances() {
+    let dir = tempdir().unwrap();
+    let handler = |name: &str, entity: &str| {
+        format!(
+            r#"
+export async function {name}(req: Request, res: Response) {{
+    const item = await repository.find(req.params.id);
+    if (!item) {{
+        res.status(404).send("{entity} not found");
+        return;
+    }}
+    const payload = serialize(item);
+    res.status(200).json(payload);
+}}
+"#
+        )
+    };
+    fs::create_dir_all(dir.path().join("templates")).unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("templates/handler.ts"), handler("getEntity", "Entity")).unwrap();
+    fs::write(dir.path().join("src/users.ts"), handler("getUser", "User")).unwrap();
+    fs::write(dir.path().join("src/orders.ts"), handler("getOrder", "Order")).unwrap();
+    fs::write(
+        dir.path().join("src/math.ts"),
+        r#"
+export function calculateSum(numbers: number[]): number {
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+    return total;
+}
+
+export function computeTotal(values: number[]): number {
+    let sum = 0;
+    for (const val of values) {
+        sum += val;
+    }
+    return sum;
+}
+"#,
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let report = run(&["src", "--no-types", "--no-size-penalty"]);
+    assert!(report.contains("getUser"));
+    assert!(report.contains("calculateSum"));
+
+    let learned =
+        run(&["src", "--no-types", "--no-size-penalty", "--boilerplate-dir", "templates"]);
+    assert!(dir.path().join(".similarity/boilerplate.json").exists());
+    assert!(learned.contains("Suppressed 1 duplicate pair(s) matching known boilerplate"));
+    assert!(!learned.contains("getUser"));
+    assert!(learned.contains("calculateSum"));
+
+    // The learned set keeps applying without the flag
+    let later = run(&["src", "--no-types", "--no-size-penalty"]);
+    assert!(!later.contains("getUser"));
+    assert!(later.contains("calculateSum"));
+}
+
+#[test]
+fn test_suggest_patch_extracts_shared_function() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src/a")).unwrap();
+    fs::create_dir_all(dir.path().join("src/b")).unwrap();
+    let body = r#"(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+"#;
+    fs::write(dir.path().join("src/a/math.ts"), format!("export function calculateSum{body}"))
+        .unwrap();
+    fs::write(dir.path().join("src/b/report.ts"), format!("function computeSum{body}")).unwrap();
+
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["src", "--no-types", "--no-size-penalty", "--suggest-patch"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("=== Suggested Patches ==="));
+    assert!(stdout.contains("# Extract 'calculateSum' into src/shared/calculateSum.ts"));
+    assert!(stdout.contains("+++ b/src/shared/calculateSum.ts"));
+    assert!(stdout.contains("+export function calculateSum(numbers: number[]): number {"));
+    assert!(stdout.contains("+import { calculateSum } from \"../shared/calculateSum\";"));
+    assert!(stdout.contains("+export { calculateSum };"));
+    assert!(
+        stdout.contains("+import { calculateSum as computeSum } from \"../shared/calculateSum\";")
+    );
+}
+
+#[test]
+fn test_import_relation_annotation_and_filter() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src/lib")).unwrap();
+    let body = r#"(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+"#;
+    fs::write(dir.path().join("src/lib/math.ts"), format!("export function calculateSum{body}"))
+        .unwrap();
+    fs::write(
+        dir.path().join("src/app.ts"),
+        format!("import {{ calculateSum }} from \"./lib/math\";\n\nfunction computeSum{body}"),
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let report = run(&["src", "--no-types", "--no-size-penalty"]);
+    assert!(report.contains("Modules: src/app.ts imports src/lib/math.ts"));
+
+    let unrelated = run(&["src", "--no-types", "--no-size-penalty", "--only-unrelated-modules"]);
+    assert!(unrelated.contains("No duplicate functions found"));
+}
+
+#[test]
+fn test_scope_by_package_boundaries() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("packages/web/src")).unwrap();
+    fs::create_dir_all(root.join("packages/api/src")).unwrap();
+    fs::write(root.join("packages/web/package.json"), r#"{"name": "@acme/web"}"#).unwrap();
+    fs::write(root.join("packages/api/package.json"), r#"{"name": "@acme/api"}"#).unwrap();
+    let sum = r#"(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+"#;
+    fs::write(root.join("packages/web/src/math.ts"), format!("export function calculateSum{sum}"))
+        .unwrap();
+    fs::write(root.join("packages/api/src/math.ts"), format!("export function computeSum{sum}"))
+        .unwrap();
+    let label = r#"(user: { first: string; last: string; title?: string }): string {
+    const parts = [];
+    if (user.title) {
+        parts.push(user.title);
+    }
+    parts.push(user.first);
+    parts.push(user.last);
+    return parts.join(" ").trim();
+}
+"#;
+    fs::write(
+        root.join("packages/web/src/labels.ts"),
+        format!("export function formatUser{label}\nexport function displayUser{label}"),
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(root)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let all = run(&["packages", "--no-types", "--no-size-penalty"]);
+    assert!(all.contains("Packages: @acme/api, @acme/web"));
+    assert!(all.contains("Packages: @acme/web"));
+
+    let per_package =
+        run(&["packages", "--no-types", "--no-size-penalty", "--scope", "per-package"]);
+    assert!(per_package.contains("formatUser"));
+    assert!(!per_package.contains("calculateSum"));
+
+    let cross = run(&["packages", "--no-types", "--no-size-penalty", "--scope", "cross-package"]);
+    assert!(cross.contains("calculateSum"));
+    assert!(!cross.contains("formatUser"));
+}
+
+#[test]
+fn test_codeowners_annotation_and_grouping() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join(".github")).unwrap();
+    fs::create_dir_all(root.join("src/web")).unwrap();
+    fs::create_dir_all(root.join("src/api")).unwrap();
+    fs::write(root.join(".github/CODEOWNERS"), "/src/web/ @acme/web\n/src/api/ @acme/api\n")
+        .unwrap();
+    let sum = r#"(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+"#;
+    fs::write(root.join("src/web/math.ts"), format!("export function calculateSum{sum}")).unwrap();
+    fs::write(root.join("src/api/math.ts"), format!("export function computeSum{sum}")).unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(root)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let report = run(&["src", "--no-types", "--no-size-penalty"]);
+    assert!(report.contains("Owners: @acme/api, @acme/web"));
+
+    let grouped = run(&["src", "--no-types", "--no-size-penalty", "--group-by", "owner"]);
+    assert!(grouped.contains("=== Owner: @acme/api (1 finding) ==="));
+    assert!(grouped.contains("=== Owner: @acme/web (1 finding) ==="));
+}
+
+#[test]
+fn test_timeout_reports_partial_coverage() {
+    let dir = tempdir().unwrap();
+    let sum = r#"(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+"#;
+    fs::write(dir.path().join("a.ts"), format!("export function calculateSum{sum}")).unwrap();
+    fs::write(dir.path().join("b.ts"), format!("export function computeSum{sum}")).unwrap();
+    let run = |timeout: &str| {
+        Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .arg(dir.path())
+            .args(["--no-types", "--no-size-penalty", "--timeout", timeout])
+            .output()
+            .unwrap()
+    };
+
+    let expired = run("0s");
+    assert!(expired.status.success());
+    let stderr = String::from_utf8(expired.stderr).unwrap();
+    assert!(stderr.contains("Timeout of 0ns reached: compared 0 of 1 candidate pairs (0.0%)"));
+    assert!(!String::from_utf8(expired.stdout).unwrap().contains("calculateSum"));
+
+    let generous = run("5m");
+    assert!(String::from_utf8(generous.stdout).unwrap().contains("calculateSum"));
+    assert!(!String::from_utf8(generous.stderr).unwrap().contains("Timeout"));
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .args(["--timeout", "soon"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid duration"));
+}
+
+#[test]
+fn test_verbose_json_logs_parse_failures() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("broken.ts"), "function broken( {\n  return 1;\n}\n").unwrap();
+
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--verbose", "--log-format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let skipped = stderr
+        .lines()
+        .find(|line| line.contains("\"message\":\"skipping file\""))
+        .expect("parse failure logged as JSON");
+    assert!(skipped.starts_with('{') && skipped.contains("\"level\":\"INFO\""));
+    assert!(skipped.contains("broken.ts\""));
+    assert!(skipped.contains("at 2:10\""));
+    let quiet = Command::cargo_bin("similarity-ts").unwrap().arg(dir.path()).output().unwrap();
+    assert!(String::from_utf8(quiet.stderr).unwrap().is_empty());
+}
+
+#[test]
+fn test_skipped_file_summary_and_strict_mode() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("broken.ts"), "function broken( {\n  return 1;\n}\n").unwrap();
+    fs::write(dir.path().join("fine.ts"), "export const answer = 42;\n").unwrap();
+
+    let output = Command::cargo_bin("similarity-ts").unwrap().arg(dir.path()).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Skipped 1 file(s):\n  parse errors: 1\n"));
+    assert!(stdout.contains("broken.ts: Parse errors: "));
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .arg("--strict")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("1 file(s) failed to parse (--strict)"));
+
+    fs::remove_file(dir.path().join("broken.ts")).unwrap();
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .arg("--strict")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipped").not());
+}
+
+#[test]
+fn test_functions_outside_syntax_error_are_compared() {
+    let dir = tempdir().unwrap();
+    let sum = r#"(numbers: number[]): number {
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+    return total;
+}
+"#;
+    fs::write(
+        dir.path().join("mixed.ts"),
+        format!("export function calculateSum{sum}\nfunction broken( {{\n  return 1;\n}}\n"),
+    )
+    .unwrap();
+    fs::write(dir.path().join("clean.ts"), format!("export function computeSum{sum}")).unwrap();
+
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--no-types", "--no-size-penalty"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("calculateSum (exported, partial)"));
+    assert!(stdout.contains("computeSum (exported)\n"));
+    assert!(stdout.contains("Partially parsed 1 file(s)"));
+}
+
+#[test]
+fn test_class_comparison_with_decorators() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("cards.ts"),
+        r#"
+@Component({ selector: 'app-user', template: '<p>user</p>' })
+export class UserCard {
+    @Input() label: string;
+    load(id: string): void {}
+}
+
+@Component({ selector: 'app-team', template: '<p>team</p>' })
+export class UserCards {
+    @Output() label: string;
+    load(id: string): void {}
+}
+"#,
+    )
+    .unwrap();
+
+    let run = |extra: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .arg(dir.path())
+            .args(["--classes-only", "--print", "--threshold", "0.8"])
+            .args(extra)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(run(&[]).contains("No similar classes found!"));
+
+    let stdout = run(&["--ignore-decorators"]);
+    assert!(stdout.contains("similar-class: UserCards"));
+    assert!(stdout.contains("Method bodies: load 100.00%"));
+    assert!(stdout.contains("Decorators: @Component({ selector: 'app-user'"));
+    assert!(stdout.contains("  @Input() label: string"));
+}
+
+#[test]
+fn test_similar_react_hooks() {
+    let dir = tempdir().unwrap();
+    let hook = |name: &str, resource: &str| {
+        format!(
+            r#"export function {name}(id: string) {{
+    const [data, setData] = useState<Data | null>(null);
+    const [loading, setLoading] = useState(false);
+    useEffect(() => {{
+        setLoading(true);
+        fetch(`/{resource}/${{id}}`).then((res) => res.json()).then(setData).finally(() => setLoading(false));
+    }}, [id]);
+    return {{ data, loading }};
+}}
+"#
+        )
+    };
+    fs::write(dir.path().join("useUser.ts"), hook("useUser", "users")).unwrap();
+    fs::write(dir.path().join("useTeam.ts"), hook("useTeam", "teams")).unwrap();
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--no-functions", "--no-types", "--hooks"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("=== React Hook Similarity ==="))
+        .stdout(predicate::str::contains("Checking 2 custom hooks..."))
+        .stdout(predicate::str::contains("dependencies: 100.00%"))
+        .stdout(predicate::str::contains("useUser"))
+        .stdout(predicate::str::contains("useTeam"));
+}
+
+#[test]
+fn test_duplicate_test_blocks() {
+    let dir = tempdir().unwrap();
+    let spec = |subject: &str| {
+        format!(
+            r#"describe("{subject}", () => {{
+    it("serializes to JSON", () => {{
+        const value = create{subject}({{ id: 1, name: "a" }});
+        const json = JSON.parse(JSON.stringify(value));
+        expect(json.id).toBe(1);
+        expect(json.name).toBe("a");
+    }});
+}});
+"#
+        )
+    };
+    fs::write(dir.path().join("user.test.ts"), spec("User")).unwrap();
+    fs::write(dir.path().join("team.test.ts"), spec("Team")).unwrap();
+
+    // Test callbacks are not part of the function analysis
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .arg("--no-types")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("callback").not());
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--no-functions", "--no-types", "--tests", "--test-threshold", "0.9"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("=== Test Similarity ==="))
+        .stdout(predicate::str::contains("Checking 4 test blocks..."))
+        .stdout(predicate::str::contains("suite: User"))
+        .stdout(predicate::str::contains("suite: Team"))
+        .stdout(predicate::str::contains("test: User > serializes to JSON").not());
+}
+
+#[test]
+fn test_duplicate_graphql_queries() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("user.ts"),
+        r#"export const GET_USER = gql`
+  query GetUser($id: ID!) {
+    user(id: $id) {
+      id
+      name
+      email
+    }
+  }
+`;
+"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("account.tsx"),
+        r#"const query = graphql`
+  query GetAccount($id: ID!) {
+    user(id: $id) {
+      email
+      id
+      name
+    }
+  }
+`;
+export const Account = () => useQuery(query);
+"#,
+    )
+    .unwrap();
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--no-functions", "--no-types", "--graphql"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("=== GraphQL Similarity ==="))
+        .stdout(predicate::str::contains("Checking 2 GraphQL operations and fragments..."))
+        .stdout(predicate::str::contains("Similarity: 100.00%"))
+        .stdout(predicate::str::contains("query GetUser"))
+        .stdout(predicate::str::contains("Shared selections: 4 of 4/4 fields"));
+}
+
+#[test]
+fn test_eval_dataset() {
+    let dir = tempdir().unwrap();
+    let function = |name: &str, field: &str| {
+        format!(
+            "export function {name}(items: Item[]): number {{\n  let total = 0;\n  for (const item of items) {{\n    total += item.{field};\n  }}\n  return total;\n}}\n"
+        )
+    };
+    fs::write(dir.path().join("a.ts"), function("sumPrices", "price")).unwrap();
+    fs::write(dir.path().join("b.ts"), function("sumCosts", "cost")).unwrap();
+    fs::write(dir.path().join("c.ts"), "export const greet = (name: string) => `Hello ${name}`;\n")
+        .unwrap();
+    fs::write(
+        dir.path().join("pairs.toml"),
+        r#"
+[[pairs]]
+first = "a.ts"
+second = "b.ts"
+clone = "type2"
+
+[[pairs]]
+first = "a.ts"
+second = "c.ts"
+clone = "none"
+"#,
+    )
+    .unwrap();
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .args(["eval", "--dataset"])
+        .arg(dir.path())
+        .arg("--no-size-penalty")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("type2         1         1   100.0%     100.0%"))
+        .stdout(predicate::str::contains("False positives: 0 of 1 unrelated pairs"))
+        .stdout(predicate::str::contains("Misclassified pairs").not());
+}
+
+#[test]
+fn test_top_ranking_ignores_threshold() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("math.ts"),
+        r#"export function sumPrices(items: Item[]): number {
+    let total = 0;
+    for (const item of items) {
+        total += item.price;
+    }
+    return total;
+}
+
+export function sumWeights(items: Item[]): number {
+    let total = 0;
+    for (const item of items) {
+        total += item.weight;
+    }
+    return total;
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("format.ts"),
+        r#"export function formatName(user: User): string {
+    const first = user.firstName.trim();
+    const last = user.lastName.trim();
+    if (!first) {
+        return last;
+    }
+    return `${first} ${last}`;
+}
+"#,
+    )
+    .unwrap();
+
+    // Nothing passes the threshold
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--no-types", "--threshold", "0.999"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No duplicate functions found!"));
+
+    // Ranking mode still reports the best pair first
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--no-types", "--threshold", "0.999", "--top", "1"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Top 1 most similar pairs:"), "{stdout}");
+    assert!(stdout.contains("sumPrices") && stdout.contains("sumWeights"), "{stdout}");
+    assert!(!stdout.contains("formatName"), "{stdout}");
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--no-types", "--top", "3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Top 3 most similar pairs:"))
+        .stdout(predicate::str::contains("formatName"));
+}
+
+#[test]
+fn test_files_only_reports_near_duplicate_files() {
+    let dir = tempdir().unwrap();
+    let settings = r#"[server]
+host = "0.0.0.0"
+port = 8080
+workers = 4
+timeout_seconds = 30
+
+[database]
+url = "postgres://localhost/app"
+pool_size = 10
+"#;
+    fs::create_dir(dir.path().join("service-a")).unwrap();
+    fs::create_dir(dir.path().join("service-b")).unwrap();
+    fs::write(dir.path().join("service-a/settings.toml"), settings).unwrap();
+    fs::write(dir.path().join("service-b/settings.toml"), settings.replace("8080", "9090"))
+        .unwrap();
+    fs::write(
+        dir.path().join("main.ts"),
+        r#"export function main(argv: string[]): number {
+    const command = argv[0];
+    if (command === "serve") {
+        return serve(argv.slice(1));
+    }
+    console.error(`unknown command: ${command}`);
+    return 1;
+}
+"#,
+    )
+    .unwrap();
+    fs::write(dir.path().join("logo.png"), [0x89, b'P', b'N', b'G', 0xff, 0xfe, 0x00]).unwrap();
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .arg("--files-only")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("=== File Similarity ==="))
+        .stdout(predicate::str::contains("Checking 3 files..."))
+        .stdout(predicate::str::contains("service-a/settings.toml"))
+        .stdout(predicate::str::contains("service-b/settings.toml"))
+        .stdout(predicate::str::contains("main.ts").not())
+        .stdout(predicate::str::contains("=== Function Similarity ===").not());
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--files-only", "--extensions", "ts"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No near-duplicate files found!"));
+}
+
+#[test]
+fn test_files_only_reports_directory_clones() {
+    let dir = tempdir().unwrap();
+    let api = |entity: &str| {
+        format!(
+            r#"export async function fetch{entity}(id: string) {{
+  const response = await fetch(`/api/{entity}/${{id}}`);
+  if (!response.ok) {{
+    throw new Error(response.statusText);
+  }}
+  return response.json();
+}}
+"#
+        )
+    };
+    let view = |entity: &str| {
+        format!(
+            r#"export function render{entity}(item: Item): string {{
+  const title = escape(item.title);
+  const rows = item.fields.map((field) => `<td>${{field}}</td>`).join("");
+  return `<h1>${{title}}</h1><table>${{rows}}</table>`;
+}}
+"#
+        )
+    };
+    for entity in ["User", "Team"] {
+        let feature = dir.path().join("features").join(entity.to_lowercase());
+        fs::create_dir_all(&feature).unwrap();
+        fs::write(feature.join("api.ts"), api(entity)).unwrap();
+        fs::write(feature.join("view.ts"), view(entity)).unwrap();
+    }
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--files-only", "--threshold", "0.85"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Near-duplicate directories:"))
+        .stdout(predicate::str::contains("features/team/"))
+        .stdout(predicate::str::contains("features/user/"))
+        .stdout(predicate::str::contains("api.ts <-> api.ts"))
+        .stdout(predicate::str::contains("view.ts <-> view.ts"))
+        .stdout(predicate::str::contains("Near-duplicate files:").not())
+        .stdout(predicate::str::contains("Total near-duplicate directory pairs found: 1"));
+}
+
+#[test]
+fn test_print_layouts() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("sum.ts"),
+        r#"export function sumPrices(items: Item[]): number {
+    let total = 0;
+    for (const item of items) {
+        total += item.price;
+    }
+    return total;
+}
+
+export function sumWeights(items: Item[]): number {
+    let total = 0;
+    for (const item of items) {
+        if (item.weight > 0) {
+            total += item.weight;
+        }
+    }
+    return total;
+}
+"#,
+    )
+    .unwrap();
+    let run = |layout: &str| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .arg(dir.path())
+            .args(["--no-types", "--top", "1", "--print", "--print-layout", layout])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Output that is not a terminal keeps the sequential code blocks
+    let auto = run("auto");
+    assert!(auto.contains("sum.ts:sumPrices (lines 1-7) ---"), "{auto}");
+    assert_eq!(auto, run("sequential"));
+
+    let unified = run("unified");
+    assert!(unified.contains("-   4 │ "), "{unified}");
+    assert!(unified.contains("+  13 │ "), "{unified}");
+    assert!(unified.contains("    6 │ "), "{unified}");
+
+    let side_by_side = run("side-by-side");
+    assert!(side_by_side.contains("   4-"), "{side_by_side}");
+    assert!(side_by_side.contains("│   13+"), "{side_by_side}");
+}
+
+#[test]
+fn test_schema_subcommand() {
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .args(["schema", "--format", "json-schema"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"$schema\": \"https://json-schema.org/draft/2020-12/schema\"",
+        ))
+        .stdout(predicate::str::contains("\"schema_version\""))
+        .stdout(predicate::str::contains("\"duplicated_lines\""));
+}
+
+#[test]
+fn test_similarityignore_per_directory() {
+    let first = tempdir().unwrap();
+    let second = tempdir().unwrap();
+    let source = "export function add(a: number, b: number): number {\n    return a + b;\n}\n";
+    fs::create_dir_all(first.path().join("src/generated")).unwrap();
+    fs::write(first.path().join("src/app.ts"), source).unwrap();
+    fs::write(first.path().join("src/generated/client.ts"), source).unwrap();
+    fs::write(first.path().join("src/.similarityignore"), "generated/\n").unwrap();
+    fs::write(second.path().join("lib.ts"), source).unwrap();
+    fs::write(second.path().join("lib.gen.ts"), source).unwrap();
+    fs::write(second.path().join(".similarityignore"), "*.gen.ts\n").unwrap();
+
+    // Each root applies its own ignore files
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(first.path())
+        .arg(second.path())
+        .arg("--no-types")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Checking 2 files for duplicates..."));
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(first.path().join("src"))
+        .args(["--files-only", "--extensions", "ts"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Checking 1 files..."));
+}
+
+#[test]
+fn test_export_and_against_index() {
+    let library = tempdir().unwrap();
+    let app = tempdir().unwrap();
+    let index = library.path().join("index.bin");
+    let body = "(items: Item[]): number {
+    let total = 0;
+    for (const item of items) {
+        if (item.price > 0) {
+            total += item.price * item.quantity;
+        }
+    }
+    return total;
+}
+";
+    fs::write(library.path().join("prices.ts"), format!("export function sumPrices{body}"))
+        .unwrap();
+    fs::write(app.path().join("cart.ts"), format!("function computeTotal{body}")).unwrap();
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(library.path())
+        .args(["--export-index", "index.bin"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Indexed 1 function(s) from 1 file(s)"));
+
+    // The library source is not needed once the index exists
+    fs::remove_file(library.path().join("prices.ts")).unwrap();
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(app.path())
+        .arg("--against-index")
+        .arg(&index)
+        .args(["--no-size-penalty", "--fail-on-duplicates"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("=== Index Matches ==="))
+        .stdout(predicate::str::contains("prices.ts:1-9 sumPrices"))
+        .stdout(predicate::str::contains("computeTotal"))
+        .stdout(predicate::str::contains("Type Similarity").not());
+
+    fs::write(&index, "not an index").unwrap();
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(app.path())
+        .arg("--against-index")
+        .arg(&index)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a similarity function index"));
+}
+
+#[test]
+fn test_severity_rules_and_fail_on() {
+    let dir = tempdir().unwrap();
+    let source = r#"
+export function applyDiscount(items: Item[], rate: number): number {
+    let total = 0;
+    for (const item of items) {
+        total += item.price * item.quantity;
+    }
+    if (total > 100) {
+        total = total * (1 - rate);
+    }
+    return Math.round(total * 100) / 100;
+}
+"#;
+    fs::write(dir.path().join("cart.ts"), source).unwrap();
+    fs::write(dir.path().join("checkout.ts"), source).unwrap();
+    let run = |args: &[&str]| {
+        Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args([".", "--no-types", "--no-size-penalty"])
+            .args(args)
+            .assert()
+    };
+
+    // Without rules every finding is a warning
+    run(&["--fail-on", "error"]).success();
+    run(&["--fail-on", "warning"]).code(1);
+
+    fs::write(
+        dir.path().join("similarity.toml"),
+        "[[severity]]\nmin_similarity = 1.0\nlevel = \"error\"\n\n\
+         [[severity]]\nmin_similarity = 0.9\nlevel = \"warning\"\n",
+    )
+    .unwrap();
+    run(&["--fail-on", "error"])
+        .code(1)
+        .stdout(predicate::str::contains("  Severity: error"))
+        .stdout(predicate::str::contains("Findings by severity:\n  error: 1 (functions: 1)"));
+    run(&["--output", "vscode"])
+        .success()
+        .stdout(predicate::str::contains("cart.ts:2:1: error: Function 'applyDiscount'"));
+
+    fs::write(dir.path().join("checkout.ts"), source.replace("100)", "200)")).unwrap();
+    run(&["--fail-on", "error"]).success().stdout(predicate::str::contains("  Severity: warning"));
+}
+
+#[test]
+fn test_allow_rules_suppress_findings() {
+    let dir = tempdir().unwrap();
+    let source = r#"
+export function applyDiscount(items: Item[], rate: number): number {
+    let total = 0;
+    for (const item of items) {
+        total += item.price * item.quantity;
+    }
+    if (total > 100) {
+        total = total * (1 - rate);
+    }
+    return Math.round(total * 100) / 100;
+}
+"#;
+    for sub in ["legacy", "v2", "shop"] {
+        fs::create_dir(dir.path().join(sub)).unwrap();
+    }
+    fs::write(dir.path().join("legacy/cart.ts"), source).unwrap();
+    fs::write(dir.path().join("v2/cart.ts"), source).unwrap();
+    let run = || {
+        Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args([".", "--no-types", "--no-size-penalty", "--fail-on-duplicates"])
+            .assert()
+    };
+
+    run().code(1);
+
+    fs::write(
+        dir.path().join("similarity.toml"),
+        "[[allow]]\nfrom = \"legacy/**\"\nto = \"v2/**\"\n",
+    )
+    .unwrap();
+    run().success().stdout(predicate::str::contains(
+        "Suppressed 1 finding(s) by allow rules in similarity.toml (functions: 1)",
+    ));
+
+    // A copy outside the allowed pair is still reported
+    fs::write(dir.path().join("shop/cart.ts"), source).unwrap();
+    run().code(1).stdout(predicate::str::contains("shop/cart.ts"));
+
+    fs::write(dir.path().join("similarity.toml"), "allow_symbols = [\"applyDiscount\"]\n").unwrap();
+    run().success().stdout(predicate::str::contains("Suppressed 3 finding(s)"));
+}
+
+#[test]
+fn test_check_snippet_against_directory_and_index() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    let existing = r#"export function sumPrices(items: Item[]): number {
+    let total = 0;
+    for (const item of items) {
+        total += item.price * item.quantity;
+    }
+    if (total > 100) {
+        total = total * 0.9;
+    }
+    return total;
+}
+"#;
+    fs::write(dir.path().join("src/prices.ts"), existing).unwrap();
+    let snippet = existing.replace("sumPrices", "totalOf").replace("items", "list");
+    let check = |args: &[&str], stdin: &str| {
+        Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(["check-snippet", "--stdin", "--no-size-penalty"])
+            .args(args)
+            .write_stdin(stdin.to_string())
+            .assert()
+    };
+
+    check(&["--against", "src/"], &snippet).code(1).stdout(
+        predicate::str::is_match(r"^0\.9\d{3}\ttotalOf\t1-10\tsrc/prices\.ts:1-10\tsumPrices\n$")
+            .unwrap(),
+    );
+    check(&["--against", "src/"], "export const answer = 42;\n").success().stdout("");
+    check(&["--against", "src/"], "function (").code(2);
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["src", "--export-index", "index.bin"])
+        .assert()
+        .success();
+    check(&["--against", "index.bin"], &snippet)
+        .code(1)
+        .stdout(predicate::str::contains("\tsrc/prices.ts:1-10\tsumPrices"));
+
+    // A file in the searched directory does not match itself
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["check-snippet", "src/prices.ts", "--against", "src", "--no-size-penalty"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_lens_of_a_file() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    let existing = r#"export function sumPrices(items: Item[]): number {
+    let total = 0;
+    for (const item of items) {
+        total += item.price * item.quantity;
+    }
+    return total;
+}
+"#;
+    fs::write(dir.path().join("src/prices.ts"), existing).unwrap();
+    let cart = format!(
+        "{}\nexport function greet(name: string) {{\n    const message = `Hi ${{name}}`;\n    console.log(message);\n    return message;\n}}\n",
+        existing.replace("sumPrices", "cartTotal")
+    );
+    fs::write(dir.path().join("src/cart.ts"), cart).unwrap();
+
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["lens", "src/cart.ts", "--against", "src", "--no-size-penalty"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let lenses = result["lenses"].as_array().unwrap();
+    assert_eq!(lenses.len(), 2);
+    assert_eq!(lenses[0]["name"], "cartTotal");
+    assert_eq!(lenses[0]["count"], 1);
+    assert_eq!(lenses[0]["title"], "1 similar implementation");
+    assert_eq!(lenses[0]["best"]["name"], "sumPrices");
+    assert_eq!(lenses[1]["count"], 0);
+    assert_eq!(lenses[1]["best"], serde_json::Value::Null);
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["lens", "src/missing.ts"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_merged_shards_match_an_unsharded_run() {
+    let dir = tempdir().unwrap();
+    for (file, name) in [("a.ts", "sumA"), ("b.ts", "sumB"), ("c.ts", "sumC"), ("d.ts", "sumD")] {
+        let content = format!(
+            r#"export function {name}(items: Item[]): number {{
+    let total = 0;
+    for (const item of items) {{
+        total += item.price * item.quantity;
+    }}
+    return total;
+}}
+
+export function {name}Twice(items: Item[]): number {{
+    let total = 0;
+    for (const item of items) {{
+        total += item.price * item.quantity * 2;
+    }}
+    return total;
+}}
+"#
+        );
+        fs::write(dir.path().join(file), content).unwrap();
+    }
+
+    let run = |extra: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args([".", "--output", "json", "--no-size-penalty"])
+            .args(extra)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let locations = |results: &str| {
+        let mut locations: Vec<String> = results
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .map(|finding| {
+                format!("{} {} {}", finding["file"], finding["line"], finding["related"])
+            })
+            .collect();
+        locations.sort();
+        locations
+    };
+
+    let full = run(&[]);
+    let mut shard_sizes = Vec::new();
+    for index in 1..=3 {
+        let results = run(&["--shard", &format!("{index}/3")]);
+        shard_sizes.push(results.lines().count());
+        fs::write(dir.path().join(format!("shard{index}.json")), results).unwrap();
+    }
+    assert_eq!(shard_sizes.iter().sum::<usize>(), full.lines().count());
+
+    let merged = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["merge-results", "shard1.json", "shard2.json", "shard3.json", "shard1.json"])
+        .output()
+        .unwrap();
+    assert!(merged.status.success());
+    let merged = String::from_utf8(merged.stdout).unwrap();
+    assert!(!locations(&full).is_empty());
+    assert_eq!(locations(&merged), locations(&full));
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args([".", "--shard", "4/3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("shard index must be between 1 and 3"));
+}
+
+#[test]
+fn test_json_type_findings_carry_the_property_diff() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("a.ts"),
+        "interface User {\n  id: string;\n  name: string;\n  age: number;\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("b.ts"),
+        "interface Person {\n  id: string;\n  name: string;\n  age: string;\n  extra: boolean;\n}\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args([".", "--no-functions", "--threshold", "0.5", "--output", "json"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        serde_json::from_str::<serde_json::Value>(stdout.lines().next().unwrap()).unwrap()
+    };
+
+    let finding = run(&["--diff"]);
+    let kinds: Vec<&str> = finding["property_diff"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["matched", "matched", "type_mismatch", "missing"]);
+    assert_eq!(finding["property_diff"][2]["left"], "age: string");
+    assert_eq!(finding["property_diff"][2]["right"], "age: number");
+    assert_eq!(finding["property_diff"][3]["left"], "extra: boolean");
+
+    assert!(run(&[]).get("property_diff").is_none());
+}
+
+#[test]
+fn test_semantic_member_comparison_is_opt_in() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("a.ts"),
+        "interface Order {\n  id: OrderId;\n  owner: UserId;\n  placedAt: Timestamp;\n  total: Cents;\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("b.ts"),
+        "interface OrderRecord {\n  id: string;\n  owner: string;\n  placedAt: number;\n  total: number;\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("similarity.toml"),
+        "[type_synonyms]\nOrderId = \"string\"\nUserId = \"string\"\nTimestamp = \"number\"\nCents = \"number\"\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args([".", "--no-functions", "--unified-types", "--use-structure-comparison"])
+            .args(["--threshold", "0.7"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(run(&[]).contains("No similar types found!"));
+    assert!(run(&["--member-comparison", "normalized"]).contains("No similar types found!"));
+    let semantic = run(&["--member-comparison", "semantic"]);
+    assert!(semantic.contains("OrderRecord"), "{semantic}");
+}
+
+#[test]
+fn test_local_types_duplicating_dependency_types() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("node_modules/axios")).unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("node_modules/axios/index.d.ts"),
+        r#"export interface AxiosRequestConfig<D = any> {
+  url?: string;
+  method?: string;
+  baseURL?: string;
+  headers?: Record<string, string>;
+  data?: D;
+  timeout?: number;
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("src/http.ts"),
+        r#"export type RequestOptions = {
+  url?: string;
+  method?: string;
+  timeout?: number;
+};
+"#,
+    )
+    .unwrap();
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["src", "--no-functions", "--no-types", "--against-deps", "axios,zod"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("=== Dependency Types ==="))
+        .stdout(predicate::str::contains("node_modules/axios/index.d.ts:1-8 AxiosRequestConfig"))
+        .stdout(predicate::str::contains(
+            r#"Use: Pick<AxiosRequestConfig, "url" | "method" | "timeout">"#,
+        ))
+        .stdout(predicate::str::contains(r#"import type { AxiosRequestConfig } from "axios";"#))
+        .stderr(predicate::str::contains("package 'zod'"));
+
+    // Without the flag dependencies are not read
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["src", "--no-functions"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dependency Types").not());
+}
+
+#[test]
+fn test_grep_by_structural_pattern() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("pattern.ts"),
+        "function __ANY__(__ANY__) {\n    const result = [];\n    __ANY__;\n    return result;\n}\n",
+    )
+    .unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/users.ts"),
+        r#"export function collectNames(users: User[], prefix: string) {
+    const result = [];
+    for (const user of users) {
+        result.push(prefix + user.name);
+    }
+    return result;
+}
+
+export function sum(values: number[]) {
+    let total = 0;
+    values.forEach((value) => {
+        total += value;
+    });
+    return total;
+}
+"#,
+    )
+    .unwrap();
+    let grep = |args: &[&str]| {
+        Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(["grep", "--pattern", "pattern.ts"])
+            .args(args)
+            .assert()
+    };
+
+    grep(&["src"]).success().stdout("src/users.ts:1-7 collectNames (100.00%)\n");
+    // Names differing from the pattern cost less with a lower rename cost
+    grep(&["src", "--rename-cost", "0.3", "--threshold", "0.8"])
+        .success()
+        .stdout(predicate::str::contains("src/users.ts:9-15 sum ("));
+
+    fs::write(dir.path().join("src/users.ts"), "export const answer = 42;\n").unwrap();
+    grep(&["src"]).code(1).stdout("");
+    fs::write(dir.path().join("pattern.ts"), "const x = 1;\n").unwrap();
+    grep(&["src"]).code(2);
+}
+
+#[test]
+fn test_suggest_patch_ranks_type_compatible_pairs_first() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src/a")).unwrap();
+    fs::create_dir_all(dir.path().join("src/b")).unwrap();
+    let sum_body = r#" {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+"#;
+    // Longer, so it would be listed first by score alone
+    let label_body = r#" {
+    const parts = [];
+    if (value === null) {
+        return "";
+    }
+    parts.push(prefix);
+    parts.push(String(value));
+    parts.push(suffix);
+    const label = parts.join(" ");
+    console.log(label);
+    return label;
+}
+"#;
+    fs::write(
+        dir.path().join("src/a/math.ts"),
+        format!(
+            "export function calculateSum(numbers: number[]): number{sum_body}\n\
+             export function formatLabel(value: number, prefix: string, suffix: string): string{label_body}"
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("src/b/report.ts"),
+        format!(
+            "export function computeSum(numbers: number[]): number{sum_body}\n\
+             export function describe(value: Date, prefix: Locale, suffix: boolean){label_body}"
+        ),
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["src", "--no-types", "--no-size-penalty", "--suggest-patch"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("Type compatibility: "), "{stdout}");
+    let sum = stdout.find("# Extract 'calculateSum'").expect(&stdout);
+    let label = stdout.find("# Extract 'formatLabel'").expect(&stdout);
+    assert!(sum < label, "{stdout}");
+    assert!(
+        stdout.contains("calculateSum.ts (similarity 98%, type compatibility 100%)"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_max_comparisons_samples_cross_bucket_pairs() {
+    let dir = tempdir().unwrap();
+    let sum = r#"(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+"#;
+    fs::write(dir.path().join("a.ts"), format!("export function calculateSum{sum}")).unwrap();
+    fs::write(dir.path().join("b.ts"), format!("export function computeSum{sum}")).unwrap();
+    // A different size and arity puts this function in another locality bucket
+    fs::write(
+        dir.path().join("c.ts"),
+        r#"export function render(items: Item[], title: string, footer: string): string {
+    const lines = [title];
+    for (const item of items) {
+        if (item.hidden) {
+            continue;
+        }
+        const label = item.label.trim();
+        const price = item.price.toFixed(2);
+        lines.push(label + ": " + price);
+        if (item.note) {
+            lines.push("  " + item.note);
+        }
+    }
+    lines.push(footer);
+    return lines.join("\n");
+}
+"#,
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .args(["--no-types", "--no-size-penalty", "--max-comparisons", "1"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(
+            "Comparison cap of 1 reached: compared 1 of 3 candidate pairs (33.3%); 2 cross-file pairs were sampled out"
+        ),
+        "{stderr}"
+    );
+    assert!(String::from_utf8(output.stdout).unwrap().contains("calculateSum"));
+}
+
+#[test]
+fn test_timeout_cuts_short_type_and_class_comparison() {
+    let dir = tempdir().unwrap();
+    let body = "{\n  id: string;\n  name: string;\n  email: string;\n  age: number;\n}\n";
+    fs::write(dir.path().join("a.ts"), format!("interface User {body}")).unwrap();
+    fs::write(dir.path().join("b.ts"), format!("interface Person {body}")).unwrap();
+    fs::write(
+        dir.path().join("c.ts"),
+        "export function save(user: { id: string; name: string; email: string; age: number }) {\n  return user;\n}\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .arg(dir.path())
+            .args(["--no-functions", "--classes", "--threshold", "0.8"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+    };
+
+    let (stdout, stderr) = run(&[]);
+    assert!(stdout.contains("Person"), "{stdout}");
+    assert!(!stderr.contains("Results are partial"), "{stderr}");
+
+    for extra in [&[][..], &["--unified-types"], &["--unified-types", "--use-structure-comparison"]]
+    {
+        let (stdout, stderr) = run(&[&["--timeout", "0s"], extra].concat());
+        assert!(!stdout.contains("Person"), "{stdout}");
+        assert!(stderr.contains("Timeout of 0ns reached: compared 0 of"), "{stderr}");
+    }
+}
+
+#[test]
+fn test_session_resumes_interrupted_run() {
+    let dir = tempdir().unwrap();
+    let sum = r#"(numbers: number[]): number {
+    if (numbers.length === 0) return 0;
+
+    let total = 0;
+    for (const num of numbers) {
+        total += num;
+    }
+
+    return total;
+}
+"#;
+    fs::write(dir.path().join("a.ts"), format!("export function calculateSum{sum}")).unwrap();
+    fs::write(dir.path().join("b.ts"), format!("export function computeSum{sum}")).unwrap();
+    let session = dir.path().join("session");
+
+    let run = |extra: &[&str]| {
+        let output = Command::cargo_bin("similarity-ts")
+            .unwrap()
+            .arg(dir.path())
+            .args(["--no-types", "--no-size-penalty", "--session"])
+            .arg(&session)
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+    };
+
+    // Interrupted before any comparison: only the parsed files are recorded
+    let (stdout, stderr) = run(&["--timeout", "0s"]);
+    assert!(!stdout.contains("calculateSum"), "{stdout}");
+    assert!(stderr.contains("rerun with --resume to continue"), "{stderr}");
+    assert!(session.join("checkpoint.json").exists());
+
+    let (stdout, stderr) = run(&["--resume"]);
+    assert!(
+        stderr.contains("2 files parsed, 0 files and 0 cross-file batches compared"),
+        "{stderr}"
+    );
+    assert!(stdout.contains("calculateSum"), "{stdout}");
+
+    // A completed session replays its findings once each
+    let (replayed, stderr) = run(&["--resume"]);
+    assert!(
+        stderr.contains("2 files parsed, 2 files and 1 cross-file batches compared"),
+        "{stderr}"
+    );
+    assert_eq!(replayed.matches("calculateSum").count(), stdout.matches("calculateSum").count());
+
+    // Changing a file invalidates the session
+    fs::write(dir.path().join("b.ts"), format!("export function addAll{sum}")).unwrap();
+    let (_, stderr) = run(&["--resume"]);
+    assert!(stderr.contains("was recorded for other files or options; starting over"), "{stderr}");
+}
+
+#[test]
+fn test_version_verbose_prints_reference_timings() {
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("similarity-ts "))
+        .stdout(predicate::str::contains("Reference timings").not());
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .args(["--version", "--verbose"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reference timings (synthetic repos, single core):"))
+        .stdout(predicate::str::contains("fingerprint"));
+}
+
+#[test]
+fn test_injections_report_near_identical_embedded_sql_across_files() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("orders.ts"),
+        r#"import { sql } from "./db";
+
+// A `sql` backtick in a comment is not a template
+export function recentOrders(userId: string) {
+  return sql`
+    SELECT id, total, created_at
+    FROM orders
+    WHERE user_id = ${userId} AND status = 'paid'
+    ORDER BY created_at DESC
+  `;
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("invoices.ts"),
+        r#"import { db } from "./db";
+
+export class Invoices {
+  paid = (customerId: string) =>
+    db.sql`SELECT id, total, created_at FROM orders WHERE user_id = ${customerId} AND status = 'open' ORDER BY created_at DESC`;
+}
+"#,
+    )
+    .unwrap();
+
+    let output = Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .current_dir(dir.path())
+        .args([".", "--no-functions", "--no-types", "--injections", "--threshold", "0.9"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("=== Embedded HTML and SQL Similarity ==="), "{stdout}");
+    assert!(stdout.contains("orders.ts:6-9"), "{stdout}");
+    assert!(stdout.contains("invoices.ts:5-5"), "{stdout}");
+}
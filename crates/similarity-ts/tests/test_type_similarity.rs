@@ -306,3 +306,46 @@ interface Type{} {{
         .assert()
         .success();
 }
+
+#[test]
+fn test_type_property_diff() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("types.ts");
+
+    let content = r#"
+interface User {
+    id: number;
+    name: string;
+    email: string;
+    role: string;
+    createdAt: Date;
+}
+
+interface Account {
+    id: number;
+    name: string;
+    email?: string;
+    role: string;
+    updatedAt: Date;
+}
+"#;
+
+    fs::write(&file, content).unwrap();
+
+    Command::cargo_bin("similarity-ts")
+        .unwrap()
+        .arg(dir.path())
+        .arg("--no-functions")
+        .arg("--threshold")
+        .arg("0.6")
+        .arg("--diff")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--- Property diff ---"))
+        .stdout(predicate::str::contains("  id: number"))
+        .stdout(predicate::str::contains("~ email: string"))
+        .stdout(predicate::str::contains("| email?: string"))
+        .stdout(predicate::str::contains("- createdAt: Date"))
+        .stdout(predicate::str::contains("+ ").and(predicate::str::contains("| updatedAt: Date")))
+        .stdout(predicate::str::contains("Missing properties:").not());
+}